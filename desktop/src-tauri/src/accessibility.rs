@@ -0,0 +1,297 @@
+//! Accessibility audit of the native surfaces the Rust shell creates: the
+//! tray menu, its webview windows and the bundled settings page.
+//!
+//! The checks are deliberately simple — a missing accessible name is the
+//! failure screen-reader users (NVDA, VoiceOver, Orca) actually hit — and the
+//! report is meant to be run from the settings UI or a dev console whenever
+//! one of those surfaces changes.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::tray::{MENU_ENTRIES, TRAY_TOOLTIP};
+
+/// Pages bundled into the app that native windows load.
+const BUNDLED_PAGES: &[&str] = &[
+    "index.html",
+    "setup.html",
+    "control.html",
+    "logs.html",
+    "report.html",
+];
+
+/// One accessibility problem found by the audit.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AccessibilityIssue {
+    /// Where the problem is: "tray", "window:<label>" or "page:<file>".
+    pub surface: String,
+    /// The offending element (menu id, window label, element id...).
+    pub element: String,
+    pub problem: String,
+}
+
+fn issue(surface: &str, element: &str, problem: &str) -> AccessibilityIssue {
+    AccessibilityIssue {
+        surface: surface.to_string(),
+        element: element.to_string(),
+        problem: problem.to_string(),
+    }
+}
+
+/// Run every check and return the problems found (empty means clean).
+pub fn audit<R: Runtime>(app: &AppHandle<R>) -> Vec<AccessibilityIssue> {
+    let mut issues = audit_menu(TRAY_TOOLTIP, MENU_ENTRIES);
+
+    for (label, window) in app.webview_windows() {
+        let title = window.title().unwrap_or_default();
+        if title.trim().is_empty() {
+            issues.push(issue(
+                &format!("window:{}", label),
+                &label,
+                "window has no title (its accessible name)",
+            ));
+        }
+    }
+
+    for page in BUNDLED_PAGES {
+        match app.asset_resolver().get(page.to_string()) {
+            Some(asset) => {
+                let html = String::from_utf8_lossy(&asset.bytes);
+                issues.extend(audit_html(page, &html));
+            }
+            None => issues.push(issue(
+                &format!("page:{}", page),
+                page,
+                "bundled page not found",
+            )),
+        }
+    }
+
+    issues
+}
+
+/// Check the tray's accessible name and its menu labels: every entry needs a
+/// label and a mnemonic, and mnemonics must not collide.
+fn audit_menu(tooltip: &str, entries: &[(&str, &str)]) -> Vec<AccessibilityIssue> {
+    let mut issues = Vec::new();
+    if tooltip.trim().is_empty() {
+        issues.push(issue("tray", "tray-icon", "tray icon has no tooltip"));
+    }
+
+    let mut mnemonics: HashMap<char, &str> = HashMap::new();
+    for (id, label) in entries {
+        if label.replace('&', "").trim().is_empty() {
+            issues.push(issue("tray", id, "menu item has no label"));
+            continue;
+        }
+        let Some(mnemonic) = mnemonic(label) else {
+            issues.push(issue("tray", id, "menu item has no keyboard mnemonic"));
+            continue;
+        };
+        if let Some(other) = mnemonics.insert(mnemonic, id) {
+            issues.push(issue(
+                "tray",
+                id,
+                &format!("mnemonic '{}' is also used by '{}'", mnemonic, other),
+            ));
+        }
+    }
+    issues
+}
+
+/// The character following the first single `&` in a menu label.
+fn mnemonic(label: &str) -> Option<char> {
+    let mut chars = label.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            match chars.next() {
+                Some('&') => continue,
+                Some(m) => return Some(m.to_ascii_lowercase()),
+                None => return None,
+            }
+        }
+    }
+    None
+}
+
+/// Check that every form control in `html` has an accessible name: a
+/// `<label for>`, an enclosing `<label>`, `aria-label`/`aria-labelledby`, or
+/// (for buttons) visible text.
+fn audit_html(page: &str, html: &str) -> Vec<AccessibilityIssue> {
+    let surface = format!("page:{}", page);
+    let mut issues = Vec::new();
+
+    let labelled_ids: Vec<String> = tags(html, "label")
+        .into_iter()
+        .filter_map(|(_, tag)| attribute(tag, "for"))
+        .collect();
+
+    for control in ["input", "select", "textarea", "button"] {
+        for (pos, tag) in tags(html, control) {
+            let kind = attribute(tag, "type").unwrap_or_default();
+            if kind == "hidden" {
+                continue;
+            }
+            let id = attribute(tag, "id");
+            let element = id
+                .clone()
+                .unwrap_or_else(|| format!("<{}> at byte {}", control, pos));
+
+            let named = attribute(tag, "aria-label").is_some_and(|v| !v.trim().is_empty())
+                || attribute(tag, "aria-labelledby").is_some()
+                || id.as_ref().is_some_and(|id| labelled_ids.contains(id))
+                || inside_label(html, pos)
+                || (control == "button" && has_text_content(html, pos, "button"));
+            if !named {
+                issues.push(issue(
+                    &surface,
+                    &element,
+                    "form control has no accessible name",
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// `(byte offset, tag source)` for every opening `<name ...>` tag.
+fn tags<'a>(html: &'a str, name: &str) -> Vec<(usize, &'a str)> {
+    let open = format!("<{}", name);
+    html.match_indices(&open)
+        .map(|(pos, _)| pos)
+        .filter(|pos| {
+            // Reject prefixes such as `<inputs` or `<labelled`.
+            html[pos + open.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_whitespace() || c == '>' || c == '/')
+        })
+        .filter_map(|pos| {
+            html[pos..]
+                .find('>')
+                .map(|end| (pos, &html[pos..=pos + end]))
+        })
+        .collect()
+}
+
+/// Value of a double-quoted attribute on a single tag; valueless attributes
+/// yield an empty string.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let bytes = tag.as_bytes();
+    let mut search = 0;
+    while let Some(found) = tag[search..].find(name) {
+        let start = search + found;
+        let end = start + name.len();
+        search = end;
+        let before_ok = start > 0 && bytes[start - 1].is_ascii_whitespace();
+        let after = tag[end..].chars().next();
+        if !before_ok {
+            continue;
+        }
+        match after {
+            Some('=') => {
+                let rest = &tag[end + 1..];
+                let rest = rest.strip_prefix('"')?;
+                return rest.find('"').map(|close| rest[..close].to_string());
+            }
+            Some(c) if c.is_whitespace() || c == '>' || c == '/' => return Some(String::new()),
+            _ => continue,
+        }
+    }
+    None
+}
+
+/// Whether byte offset `pos` sits between an opening `<label` and its close.
+fn inside_label(html: &str, pos: usize) -> bool {
+    let before = &html[..pos];
+    match (before.rfind("<label"), before.rfind("</label>")) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Whether the element opened at `pos` has non-whitespace text before its
+/// closing tag, ignoring nested markup.
+fn has_text_content(html: &str, pos: usize, name: &str) -> bool {
+    let Some(open_end) = html[pos..].find('>') else {
+        return false;
+    };
+    let body_start = pos + open_end + 1;
+    let Some(close) = html[body_start..].find(&format!("</{}>", name)) else {
+        return false;
+    };
+    let mut in_tag = false;
+    html[body_start..body_start + close]
+        .chars()
+        .any(|c| match c {
+            '<' => {
+                in_tag = true;
+                false
+            }
+            '>' => {
+                in_tag = false;
+                false
+            }
+            c => !in_tag && !c.is_whitespace(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tray_menu_is_clean() {
+        assert_eq!(audit_menu(TRAY_TOOLTIP, MENU_ENTRIES), vec![]);
+    }
+
+    #[test]
+    fn test_menu_audit_flags_missing_and_duplicate_mnemonics() {
+        let issues = audit_menu(
+            "",
+            &[
+                ("a", "&Alpha"),
+                ("b", "&Another"),
+                ("c", "Plain"),
+                ("d", ""),
+            ],
+        );
+        let problems: Vec<(&str, &str)> = issues
+            .iter()
+            .map(|i| (i.element.as_str(), i.problem.as_str()))
+            .collect();
+        assert_eq!(problems.len(), 4);
+        assert_eq!(problems[0], ("tray-icon", "tray icon has no tooltip"));
+        assert!(problems[1].1.contains("also used by 'a'"));
+        assert_eq!(problems[2], ("c", "menu item has no keyboard mnemonic"));
+        assert_eq!(problems[3], ("d", "menu item has no label"));
+    }
+
+    #[test]
+    fn test_html_audit() {
+        let html = r#"
+            <label for="port">Port</label><input type="number" id="port">
+            <label class="checkbox-label"><input type="checkbox" id="wrapped"><span>On</span></label>
+            <input type="text" id="orphan">
+            <input type="text" aria-label="Search">
+            <input type="hidden" id="csrf">
+            <button type="submit">Save</button>
+            <button type="button" id="icon"><svg></svg></button>
+        "#;
+        let issues = audit_html("test.html", html);
+        let elements: Vec<&str> = issues.iter().map(|i| i.element.as_str()).collect();
+        assert_eq!(elements, vec!["orphan", "icon"]);
+    }
+
+    #[test]
+    fn test_bundled_pages_are_clean() {
+        let html = include_str!("../../src/index.html");
+        assert_eq!(audit_html("index.html", html), vec![]);
+        let html = include_str!("../../src/control.html");
+        assert_eq!(audit_html("control.html", html), vec![]);
+        let html = include_str!("../../src/logs.html");
+        assert_eq!(audit_html("logs.html", html), vec![]);
+    }
+}
@@ -0,0 +1,335 @@
+//! A printable summary of one day on the mesh, for clubs that keep a paper
+//! log of their nets: messages per channel, direct messages, nodes first
+//! heard that day, and how long the backend ran and the mesh link was up.
+//! It's rendered as a self-contained HTML page and saved to the `reports`
+//! folder as `activity-YYYY-MM-DD.html`. With `Config::daily_activity_report`
+//! on, the previous day's is saved after midnight; the Activity Report window
+//! shows any day's and prints it, which also offers saving as PDF.
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::api::{ApiMessage, BackendApi};
+use crate::config::{self, Config};
+use crate::fallback::escape;
+use crate::{log, message_export, uptime};
+
+/// How often the scheduler checks whether yesterday's report is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Messages fetched per request.
+const PAGE_SIZE: usize = 500;
+
+/// Guards against spawning a second scheduler thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelActivity {
+    pub name: String,
+    pub messages: usize,
+}
+
+/// One day's activity.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    pub channels: Vec<ChannelActivity>,
+    pub direct_messages: usize,
+    /// Display names of the nodes first heard that day.
+    pub new_nodes: Vec<String>,
+    pub uptime: Option<uptime::DayReport>,
+}
+
+/// Spawn the scheduler saving yesterday's report. It idles while
+/// `daily_activity_report` is off or no API token is configured. Safe to
+/// call more than once.
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        let Ok(config) = Config::load() else {
+            continue;
+        };
+        if !config.daily_activity_report {
+            continue;
+        }
+        let yesterday = Local::now().date_naive() - ChronoDuration::days(1);
+        if report_path(yesterday).is_ok_and(|path| path.exists()) {
+            continue;
+        }
+        if BackendApi::from_config(&config).is_none() {
+            continue;
+        }
+        match save(&config, yesterday) {
+            Ok(path) => log(&format!("Saved the activity report {}", path.display())),
+            Err(e) => log(&format!("Activity report failed: {}", e)),
+        }
+    });
+}
+
+/// The day `date` (`YYYY-MM-DD`), or today without one.
+pub fn parse_date(date: Option<&str>) -> Result<NaiveDate, String> {
+    match date {
+        Some(date) => {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Not a date: {}", date))
+        }
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+/// Collect the report for `date` from the backend.
+pub fn build(config: &Config, date: NaiveDate) -> Result<Report, String> {
+    let api = BackendApi::from_config(config)
+        .ok_or("Set an API token in Settings for activity reports")?;
+    let (start, end) = day_bounds(date)?;
+
+    let mut messages = Vec::new();
+    let mut cursor = start - 1;
+    loop {
+        let page = api.messages_after(cursor, PAGE_SIZE)?;
+        let (complete, next) = message_export::complete_prefix(&page, PAGE_SIZE);
+        let Some(next) = next else {
+            break;
+        };
+        messages.extend(
+            page[..complete]
+                .iter()
+                .filter(|message| message.timestamp < end)
+                .cloned(),
+        );
+        cursor = next;
+        if page.len() < PAGE_SIZE || cursor >= end {
+            break;
+        }
+    }
+
+    let channel_names: BTreeMap<i64, String> = api
+        .channels()
+        .map(|channels| {
+            channels
+                .into_iter()
+                .filter_map(|channel| Some((channel.id, channel.display_name?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut new_nodes: Vec<String> = api
+        .nodes()?
+        .iter()
+        .filter(|node| node.created_at.is_some_and(|at| at >= start && at < end))
+        .map(|node| node.display_name())
+        .collect();
+    new_nodes.sort();
+
+    let days_back = (Local::now().date_naive() - date).num_days();
+    let uptime = u32::try_from(days_back + 1).ok().and_then(|days| {
+        uptime::daily_report(&uptime::load(), days, Local::now())
+            .into_iter()
+            .find(|day| day.date == date.to_string())
+    });
+
+    Ok(tally(date, &messages, &channel_names, new_nodes, uptime))
+}
+
+fn tally(
+    date: NaiveDate,
+    messages: &[ApiMessage],
+    channel_names: &BTreeMap<i64, String>,
+    new_nodes: Vec<String>,
+    uptime: Option<uptime::DayReport>,
+) -> Report {
+    let mut per_channel: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut direct_messages = 0;
+    for message in messages {
+        if message.is_direct() {
+            direct_messages += 1;
+        } else {
+            *per_channel.entry(message.channel).or_default() += 1;
+        }
+    }
+    Report {
+        date: date.to_string(),
+        channels: per_channel
+            .into_iter()
+            .map(|(channel, messages)| ChannelActivity {
+                name: channel_names
+                    .get(&channel)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Channel {}", channel)),
+                messages,
+            })
+            .collect(),
+        direct_messages,
+        new_nodes,
+        uptime,
+    }
+}
+
+/// Unix milliseconds of the start of `date` and of the next day.
+fn day_bounds(date: NaiveDate) -> Result<(i64, i64), String> {
+    let start = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+            .map(|at| at.timestamp_millis())
+            .ok_or_else(|| format!("No midnight on {}", date))
+    };
+    Ok((start(date)?, start(date + ChronoDuration::days(1))?))
+}
+
+/// Build the report for `date` and save it to the reports folder.
+pub fn save(config: &Config, date: NaiveDate) -> Result<PathBuf, String> {
+    let html = render(&build(config, date)?);
+    let path = report_path(date)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create the reports folder: {}", e))?;
+    }
+    std::fs::write(&path, html)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn report_path(date: NaiveDate) -> Result<PathBuf, String> {
+    Ok(config::get_data_path()?
+        .join("reports")
+        .join(format!("activity-{}.html", date.format("%Y-%m-%d"))))
+}
+
+/// The report as a page of its own, laid out for A4 or Letter paper.
+pub fn render(report: &Report) -> String {
+    let date = NaiveDate::parse_from_str(&report.date, "%Y-%m-%d")
+        .map(|date| date.format("%A %-d %B %Y").to_string())
+        .unwrap_or_else(|_| report.date.clone());
+    let total: usize =
+        report.channels.iter().map(|c| c.messages).sum::<usize>() + report.direct_messages;
+
+    let mut rows = String::new();
+    for channel in &report.channels {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"count\">{}</td></tr>\n",
+            escape(&channel.name),
+            channel.messages
+        ));
+    }
+    rows.push_str(&format!(
+        "<tr><td>Direct messages</td><td class=\"count\">{}</td></tr>\n\
+         <tr class=\"total\"><td>Total</td><td class=\"count\">{}</td></tr>\n",
+        report.direct_messages, total
+    ));
+
+    let nodes = if report.new_nodes.is_empty() {
+        "<p>None.</p>".to_string()
+    } else {
+        let items: String = report
+            .new_nodes
+            .iter()
+            .map(|name| format!("<li>{}</li>", escape(name)))
+            .collect();
+        format!("<ul>{}</ul>", items)
+    };
+
+    let uptime = match &report.uptime {
+        Some(day) => format!(
+            "<table>\n<tr><td>Backend running</td><td class=\"count\">{:.1}%</td></tr>\n\
+             <tr><td>Mesh link up</td><td class=\"count\">{:.1}%</td></tr>\n\
+             <tr><td>Backend crashes</td><td class=\"count\">{}</td></tr>\n\
+             <tr><td>Backend hangs</td><td class=\"count\">{}</td></tr>\n</table>",
+            day.backend_percent, day.link_percent, day.crashes, day.hangs
+        ),
+        None => "<p>No uptime history for this day.</p>".to_string(),
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<title>MeshMonitor Activity Report {iso}</title>
+<style>
+@page {{ margin: 18mm; }}
+body {{ font-family: Georgia, 'Times New Roman', serif; color: #000; background: #fff; max-width: 720px; margin: 0 auto; padding: 16px; }}
+h1 {{ font-size: 22px; margin-bottom: 2px; }}
+.date {{ font-size: 15px; margin-top: 0; }}
+h2 {{ font-size: 16px; border-bottom: 1px solid #000; padding-bottom: 2px; margin-top: 24px; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td {{ padding: 4px 0; border-bottom: 1px solid #ccc; }}
+.count {{ text-align: right; font-variant-numeric: tabular-nums; }}
+.total td {{ font-weight: bold; border-bottom: none; }}
+ul {{ columns: 2; padding-left: 18px; }}
+.generated {{ margin-top: 32px; font-size: 11px; color: #555; }}
+</style>
+</head>
+<body>
+<h1>MeshMonitor Activity Report</h1>
+<p class="date">{date}</p>
+<h2>Messages</h2>
+<table>
+{rows}</table>
+<h2>New Nodes ({node_count})</h2>
+{nodes}
+<h2>Uptime</h2>
+{uptime}
+<p class="generated">Generated {generated} by MeshMonitor Desktop {version}</p>
+</body>
+</html>
+"#,
+        iso = report.date,
+        date = escape(&date),
+        rows = rows,
+        node_count = report.new_nodes.len(),
+        nodes = nodes,
+        uptime = uptime,
+        generated = Local::now().format("%Y-%m-%d %H:%M"),
+        version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(channel: i64) -> ApiMessage {
+        ApiMessage {
+            id: String::new(),
+            from_node_id: "!a".to_string(),
+            to_node_id: "!b".to_string(),
+            text: String::new(),
+            channel,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_tally_and_render() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 13).unwrap();
+        let names = BTreeMap::from([(0, "LongFast".to_string())]);
+        let messages = [message(0), message(0), message(2), message(-1)];
+        let report = tally(date, &messages, &names, vec!["<Base>".to_string()], None);
+        assert_eq!(
+            report.channels,
+            [
+                ChannelActivity {
+                    name: "LongFast".to_string(),
+                    messages: 2
+                },
+                ChannelActivity {
+                    name: "Channel 2".to_string(),
+                    messages: 1
+                },
+            ]
+        );
+        assert_eq!(report.direct_messages, 1);
+
+        let html = render(&report);
+        assert!(html.contains("Tuesday 13 October 2026"));
+        assert!(html.contains("<td>LongFast</td><td class=\"count\">2</td>"));
+        assert!(html.contains("<td>Total</td><td class=\"count\">4</td>"));
+        assert!(html.contains("<li>&lt;Base&gt;</li>"));
+    }
+}
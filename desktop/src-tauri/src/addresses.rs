@@ -0,0 +1,197 @@
+//! Every address the local web UI can be reached on — loopback, LAN, VPN and
+//! hostname — each with a QR code so a phone can be pointed at it without
+//! typing an IP.
+
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr};
+
+/// How an address reaches this machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressKind {
+    /// Loopback; only works from this machine.
+    Localhost,
+    /// A private LAN address.
+    Lan,
+    /// An overlay/VPN address (Tailscale, WireGuard, ZeroTier, OpenVPN...).
+    Vpn,
+    /// The machine's hostname, for networks with working local DNS/mDNS.
+    Hostname,
+}
+
+/// One way to reach the web UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebAddress {
+    pub kind: AddressKind,
+    pub url: String,
+    /// Interface or host the address came from, e.g. "en0" or "studio.local".
+    pub label: String,
+    /// The single address best suited for other devices; exactly one entry
+    /// in a list is preferred.
+    pub preferred: bool,
+    /// The URL encoded as an SVG QR code, ready to drop into the page.
+    pub qr_svg: String,
+}
+
+/// All addresses the web UI on `port` is reachable at, localhost first. The
+/// first LAN address is preferred, then VPN, then localhost.
+pub fn web_addresses(port: u16) -> Vec<WebAddress> {
+    let mut found: Vec<(AddressKind, String, String)> = vec![(
+        AddressKind::Localhost,
+        format!("http://localhost:{}", port),
+        "localhost".to_string(),
+    )];
+
+    let mut interfaces = if_addrs::get_if_addrs().unwrap_or_default();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    for interface in interfaces {
+        let ip = interface.ip();
+        if let Some(kind) = classify(&interface.name, ip) {
+            found.push((kind, url_for(ip, port), interface.name));
+        }
+    }
+
+    if let Some(host) = hostname() {
+        found.push((
+            AddressKind::Hostname,
+            format!("http://{}:{}", host, port),
+            host,
+        ));
+    }
+
+    let preferred = [AddressKind::Lan, AddressKind::Vpn, AddressKind::Localhost]
+        .iter()
+        .find_map(|kind| found.iter().position(|(k, _, _)| k == kind));
+
+    found
+        .into_iter()
+        .enumerate()
+        .map(|(i, (kind, url, label))| WebAddress {
+            kind,
+            qr_svg: qr_svg(&url),
+            url,
+            label,
+            preferred: Some(i) == preferred,
+        })
+        .collect()
+}
+
+/// Classify an interface address, or `None` for ones that can't usefully
+/// reach the web UI (loopback is listed separately, link-local needs a zone).
+fn classify(interface: &str, ip: IpAddr) -> Option<AddressKind> {
+    if ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() {
+        return None;
+    }
+    let name = interface.to_ascii_lowercase();
+    let vpn_interface = ["tailscale", "wg", "tun", "utun", "zt", "ppp", "ipsec"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix));
+
+    match ip {
+        IpAddr::V4(v4) => {
+            if v4.is_link_local() {
+                None
+            } else if vpn_interface || is_cgnat(v4) {
+                // Tailscale hands out 100.64.0.0/10 addresses
+                Some(AddressKind::Vpn)
+            } else {
+                // Private ranges, or (unusual on a desktop) a public address
+                // directly on the interface
+                Some(AddressKind::Lan)
+            }
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            if segments[0] & 0xffc0 == 0xfe80 {
+                None
+            } else if vpn_interface || segments[0] == 0xfd7a {
+                // fd7a:115c:a1e0::/48 is Tailscale's ULA range
+                Some(AddressKind::Vpn)
+            } else {
+                Some(AddressKind::Lan)
+            }
+        }
+    }
+}
+
+/// Carrier-grade NAT range 100.64.0.0/10.
+fn is_cgnat(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    a == 100 && (64..128).contains(&b)
+}
+
+fn url_for(ip: IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(v4) => format!("http://{}:{}", v4, port),
+        IpAddr::V6(v6) => format!("http://[{}]:{}", v6, port),
+    }
+}
+
+/// This machine's hostname, if it is set to something resolvable-looking.
+fn hostname() -> Option<String> {
+    let host = gethostname::gethostname()
+        .to_string_lossy()
+        .trim()
+        .to_string();
+    let valid = !host.is_empty()
+        && host != "localhost"
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    valid.then_some(host)
+}
+
+pub(crate) fn qr_svg(payload: &str) -> String {
+    use qrcode::render::svg;
+    match qrcode::QrCode::new(payload.as_bytes()) {
+        Ok(code) => code
+            .render::<svg::Color>()
+            .min_dimensions(200, 200)
+            .dark_color(svg::Color("#000000"))
+            .light_color(svg::Color("#ffffff"))
+            .build(),
+        Err(_) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_interfaces() {
+        let v4 = |s: &str| IpAddr::V4(s.parse().unwrap());
+        assert_eq!(classify("eth0", v4("192.168.1.20")), Some(AddressKind::Lan));
+        assert_eq!(classify("en0", v4("10.0.0.5")), Some(AddressKind::Lan));
+        assert_eq!(
+            classify("tailscale0", v4("100.101.102.103")),
+            Some(AddressKind::Vpn)
+        );
+        assert_eq!(classify("utun3", v4("100.70.1.1")), Some(AddressKind::Vpn));
+        assert_eq!(classify("wg0", v4("10.8.0.2")), Some(AddressKind::Vpn));
+        assert_eq!(classify("lo", v4("127.0.0.1")), None);
+        assert_eq!(classify("eth0", v4("169.254.3.4")), None);
+        assert_eq!(
+            classify("eth0", IpAddr::V6("fe80::1".parse().unwrap())),
+            None
+        );
+        assert_eq!(
+            classify("eth0", IpAddr::V6("fd7a:115c:a1e0::1".parse().unwrap())),
+            Some(AddressKind::Vpn)
+        );
+    }
+
+    #[test]
+    fn test_ipv6_urls_are_bracketed() {
+        let ip = IpAddr::V6("2001:db8::1".parse().unwrap());
+        assert_eq!(url_for(ip, 8080), "http://[2001:db8::1]:8080");
+    }
+
+    #[test]
+    fn test_exactly_one_preferred_address() {
+        let addresses = web_addresses(8080);
+        assert_eq!(addresses[0].kind, AddressKind::Localhost);
+        assert_eq!(addresses.iter().filter(|a| a.preferred).count(), 1);
+        assert!(addresses[0].qr_svg.starts_with("<?xml"));
+    }
+}
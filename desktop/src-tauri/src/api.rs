@@ -0,0 +1,559 @@
+//! Minimal client for the backend's token-authenticated `/api/v1` REST API.
+//!
+//! The desktop shell only ever talks to the backend it spawned, over
+//! loopback, so this is a thin blocking wrapper around `ureq` rather than a
+//! general-purpose HTTP layer. Every request carries the API token the user
+//! created in the web UI (`Config::api_token`); without one the v1 API
+//! answers 401 and the desktop features that depend on it stay dormant.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// Per-request timeout. The backend is on loopback, so anything slower than
+/// this means it is busy or wedged and the caller should simply try again on
+/// its next tick.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Health checks answer from memory, so a short timeout tells a hung backend
+/// apart from a slow one.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+/// `/api/desktop/shutdown` answers before it starts closing anything, so a
+/// slow reply means the backend is wedged and should be signalled instead.
+const SHUTDOWN_REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A restore sends one admin message per section and waits for the node to
+/// save them to flash.
+const DEVICE_RESTORE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Standard v1 response envelope: `{ "success": true, "data": ... }`.
+#[derive(Debug, Deserialize)]
+struct Envelope<T> {
+    data: T,
+}
+
+/// A mesh node as returned by `GET /api/v1/nodes`. Only the fields the
+/// desktop shell uses are modelled; the rest are ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiNode {
+    pub node_num: u64,
+    pub node_id: String,
+    #[serde(default)]
+    pub long_name: Option<String>,
+    #[serde(default)]
+    pub short_name: Option<String>,
+    /// Last known position in degrees; hidden when the token may not see it
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    /// When the backend first heard of it, Unix milliseconds
+    #[serde(default)]
+    pub created_at: Option<i64>,
+}
+
+impl ApiNode {
+    /// Human-friendly label: "Long Name (!a1b2c3d4)", falling back to the
+    /// short name or the bare node ID when names haven't been heard yet.
+    pub fn display_name(&self) -> String {
+        let name = self
+            .long_name
+            .as_deref()
+            .filter(|n| !n.trim().is_empty())
+            .or_else(|| self.short_name.as_deref().filter(|n| !n.trim().is_empty()));
+        match name {
+            Some(name) => format!("{} ({})", name, self.node_id),
+            None => self.node_id.clone(),
+        }
+    }
+}
+
+/// A text message as returned by `GET /api/v1/messages`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiMessage {
+    pub id: String,
+    pub from_node_id: String,
+    pub to_node_id: String,
+    #[serde(default)]
+    pub text: String,
+    /// Channel index; direct messages are stored with channel -1.
+    pub channel: i64,
+    /// Server receive time, Unix milliseconds.
+    pub timestamp: i64,
+}
+
+impl ApiMessage {
+    pub fn is_direct(&self) -> bool {
+        self.channel == -1
+    }
+
+    /// Whether this is a direct message sent to the local node by someone
+    /// else. With the local node unknown, any DM not from it counts.
+    pub fn is_incoming_dm(&self, local_node_id: Option<&str>) -> bool {
+        self.is_direct()
+            && local_node_id != Some(self.from_node_id.as_str())
+            && local_node_id.is_none_or(|id| id == self.to_node_id)
+    }
+}
+
+/// A channel as returned by `GET /api/v1/channels`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChannel {
+    pub id: i64,
+    /// The name, or the modem preset's for an unnamed primary channel.
+    #[serde(default)]
+    pub display_name: Option<String>,
+}
+
+/// Local node identity and link state from `GET /api/v1/status`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiStatus {
+    #[serde(default)]
+    pub local_node_id: Option<String>,
+    #[serde(default)]
+    pub connected: bool,
+}
+
+/// Result of `POST /api/v1/sources/:id/actions/add-channel`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddedChannels {
+    pub added: Vec<AddedChannel>,
+    /// Channels in the URL the node already had.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddedChannel {
+    /// Channel slot (1–7).
+    pub index: u8,
+    pub name: String,
+}
+
+/// Firmware check for the local node from `GET /api/v1/sources/:id/firmware`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirmwareInfo {
+    #[serde(default)]
+    pub current_version: Option<String>,
+    /// Display name of the hardware model, e.g. "Heltec V3".
+    #[serde(default)]
+    pub hardware: Option<String>,
+    /// Board name used in release artifacts, e.g. `heltec-v3`.
+    #[serde(default)]
+    pub board: Option<String>,
+    /// Chip family, e.g. `esp32s3` or `nrf52840`.
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub latest: Option<FirmwareRelease>,
+    #[serde(default)]
+    pub update_available: bool,
+}
+
+/// Newest release carrying an artifact for the node's platform.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirmwareRelease {
+    pub version: String,
+    pub asset: FirmwareAsset,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirmwareAsset {
+    pub name: String,
+    pub size: u64,
+    pub download_url: String,
+}
+
+/// Liveness report from the unauthenticated `GET /api/health`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiHealth {
+    pub status: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Milliseconds since the backend process started.
+    #[serde(default)]
+    pub uptime: i64,
+}
+
+/// Query `/api/health` on the local backend. Needs no API token, so it works
+/// before the user has created one.
+pub fn health(web_port: u16) -> Result<ApiHealth, String> {
+    ureq::AgentBuilder::new()
+        .timeout(HEALTH_TIMEOUT)
+        .build()
+        .get(&format!("http://127.0.0.1:{}/api/health", web_port))
+        .call()
+        .map_err(|e| format!("Health check failed: {}", e))?
+        .into_json()
+        .map_err(|e| format!("Health check returned invalid JSON: {}", e))
+}
+
+/// The watchdog's health check, as configured (`Config::health_check_*`):
+/// a 2xx answer from the health path that passes `health_verdict`. A path
+/// that 404s falls back to checking that the port accepts connections.
+pub fn check_health(web_port: u16, config: &Config) -> Result<(), String> {
+    let timeout = Duration::from_secs(u64::from(config.health_check_timeout_secs.max(1)));
+    let path = config.health_check_path.trim();
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let answer = ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .build()
+        .get(&format!("http://127.0.0.1:{}{}", web_port, path))
+        .call();
+    match answer {
+        Ok(response) => {
+            let body = response
+                .into_string()
+                .map_err(|e| format!("Health check answer couldn't be read: {}", e))?;
+            health_verdict(&body, config.health_check_expect.as_deref())
+        }
+        Err(ureq::Error::Status(404, _)) => {
+            let address = std::net::SocketAddr::from(([127, 0, 0, 1], web_port));
+            std::net::TcpStream::connect_timeout(&address, timeout)
+                .map(|_| ())
+                .map_err(|e| {
+                    format!(
+                        "Health check failed: {} not found, port closed: {}",
+                        path, e
+                    )
+                })
+        }
+        Err(e) => Err(format!("Health check failed: {}", e)),
+    }
+}
+
+/// Whether a 2xx health answer means healthy: it contains `expect` when one
+/// is configured, and otherwise isn't a health report with a bad status.
+fn health_verdict(body: &str, expect: Option<&str>) -> Result<(), String> {
+    if let Some(expect) = expect.filter(|expect| !expect.is_empty()) {
+        return if body.contains(expect) {
+            Ok(())
+        } else {
+            Err(format!("Health check answer doesn't contain {:?}", expect))
+        };
+    }
+    match serde_json::from_str::<ApiHealth>(body) {
+        Ok(health) if health.status != "ok" => {
+            Err(format!("Backend reports status {:?}", health.status))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Ask the local backend to shut down gracefully via `POST
+/// /api/desktop/shutdown`, authenticated with the per-spawn control token.
+/// Node then closes its Meshtastic connections and the database itself.
+pub fn request_shutdown(web_port: u16, control_token: &str) -> Result<(), String> {
+    ureq::AgentBuilder::new()
+        .timeout(SHUTDOWN_REQUEST_TIMEOUT)
+        .build()
+        .post(&format!(
+            "http://127.0.0.1:{}/api/desktop/shutdown",
+            web_port
+        ))
+        .set("Authorization", &format!("Bearer {}", control_token))
+        .call()
+        .map(|_| ())
+        .map_err(|e| format!("Shutdown request failed: {}", e))
+}
+
+/// A share link from `POST /api/desktop/share-links`, or a login token from
+/// `POST /api/desktop/login-tokens`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareToken {
+    pub token: String,
+    /// Unix milliseconds.
+    pub expires_at: i64,
+}
+
+/// Ask the local backend for a read-only share link valid for `minutes`,
+/// authenticated with the per-spawn control token.
+pub fn create_share_link(
+    web_port: u16,
+    control_token: &str,
+    minutes: u32,
+) -> Result<ShareToken, String> {
+    ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .post(&format!(
+            "http://127.0.0.1:{}/api/desktop/share-links",
+            web_port
+        ))
+        .set("Authorization", &format!("Bearer {}", control_token))
+        .send_json(serde_json::json!({ "minutes": minutes }))
+        .map_err(|e| format!("Share link request failed: {}", e))?
+        .into_json()
+        .map_err(|e| format!("Unexpected share link response: {}", e))
+}
+
+/// Ask the local backend for a one-time token that logs the web UI in as
+/// its first admin, authenticated with the per-spawn control token.
+pub fn create_login_token(web_port: u16, control_token: &str) -> Result<ShareToken, String> {
+    ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .post(&format!(
+            "http://127.0.0.1:{}/api/desktop/login-tokens",
+            web_port
+        ))
+        .set("Authorization", &format!("Bearer {}", control_token))
+        .call()
+        .map_err(|e| format!("Login token request failed: {}", e))?
+        .into_json()
+        .map_err(|e| format!("Unexpected login token response: {}", e))
+}
+
+/// One setting a device restore changes, from `POST
+/// /api/desktop/device-restore`; `None` is the firmware default. Keys come
+/// back as `(hidden)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub path: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Answer of `POST /api/desktop/device-restore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceRestore {
+    /// The node the backend is connected to.
+    pub node_id: Option<String>,
+    pub changes: Vec<ConfigChange>,
+    pub applied: bool,
+}
+
+/// Compare a device backup with the connected node and, with `apply`, write
+/// it to the node, authenticated with the per-spawn control token.
+pub fn device_restore(
+    web_port: u16,
+    control_token: &str,
+    yaml: &str,
+    apply: bool,
+) -> Result<DeviceRestore, String> {
+    ureq::AgentBuilder::new()
+        .timeout(DEVICE_RESTORE_TIMEOUT)
+        .build()
+        .post(&format!(
+            "http://127.0.0.1:{}/api/desktop/device-restore",
+            web_port
+        ))
+        .set("Authorization", &format!("Bearer {}", control_token))
+        .send_json(serde_json::json!({ "yaml": yaml, "apply": apply }))
+        .map_err(|e| match e {
+            ureq::Error::Status(_, response) => response
+                .into_json::<serde_json::Value>()
+                .ok()
+                .and_then(|body| body["error"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "The backend refused the restore".to_string()),
+            e => format!("Restore request failed: {}", e),
+        })?
+        .into_json()
+        .map_err(|e| format!("Unexpected restore response: {}", e))
+}
+
+/// Client bound to the local backend's web port and the configured token.
+pub struct BackendApi {
+    origin: String,
+    base_url: String,
+    token: String,
+    agent: ureq::Agent,
+}
+
+impl BackendApi {
+    /// Build a client from the current configuration. Returns `None` when no
+    /// API token is configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let token = config
+            .api_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())?
+            .to_string();
+        let origin = format!("http://127.0.0.1:{}", config.web_port);
+        Some(Self {
+            base_url: format!("{}/api/v1", origin),
+            origin,
+            token,
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        })
+    }
+
+    /// `GET` a v1 endpoint and unwrap the `data` field of the envelope.
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let envelope: Envelope<T> = self.get_raw(path)?;
+        Ok(envelope.data)
+    }
+
+    /// `GET` a v1 endpoint and deserialize the whole response body.
+    fn get_raw<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .agent
+            .get(&url)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(|e| format!("GET {} failed: {}", path, e))?;
+        response
+            .into_json()
+            .map_err(|e| format!("GET {} returned invalid JSON: {}", path, e))
+    }
+
+    /// `POST` a JSON body to a v1 endpoint and unwrap the `data` field. When
+    /// the backend refuses, its `error` message is returned.
+    fn post<T: DeserializeOwned>(&self, path: &str, body: serde_json::Value) -> Result<T, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .agent
+            .post(&url)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_json(body)
+            .map_err(|e| match e {
+                ureq::Error::Status(_, response) => response
+                    .into_json::<serde_json::Value>()
+                    .ok()
+                    .and_then(|body| body["error"].as_str().map(str::to_string))
+                    .unwrap_or_else(|| format!("POST {} was refused", path)),
+                e => format!("POST {} failed: {}", path, e),
+            })?;
+        let envelope: Envelope<T> = response
+            .into_json()
+            .map_err(|e| format!("POST {} returned invalid JSON: {}", path, e))?;
+        Ok(envelope.data)
+    }
+
+    /// All nodes known to the backend, across every source.
+    pub fn nodes(&self) -> Result<Vec<ApiNode>, String> {
+        self.get("/nodes")
+    }
+
+    /// Channels the token may read.
+    pub fn channels(&self) -> Result<Vec<ApiChannel>, String> {
+        self.get("/channels")
+    }
+
+    /// Messages received after `since_ms` (Unix milliseconds), oldest first.
+    pub fn messages_since(&self, since_ms: i64) -> Result<Vec<ApiMessage>, String> {
+        self.messages_after(since_ms, 100)
+    }
+
+    /// Up to `limit` messages received after `since_ms`, oldest first.
+    pub fn messages_after(&self, since_ms: i64, limit: usize) -> Result<Vec<ApiMessage>, String> {
+        self.get(&format!("/messages?since={}&limit={}", since_ms, limit))
+    }
+
+    /// Local node identity and whether the backend is connected to it.
+    pub fn status(&self) -> Result<ApiStatus, String> {
+        self.get("/status")
+    }
+
+    /// The local node's configuration (channels, LoRa and module settings)
+    /// as YAML in the `meshtastic --export-config` format, from `GET
+    /// /api/device/backup`, which takes the API token like the v1 API.
+    pub fn device_backup(&self) -> Result<String, String> {
+        self.agent
+            .get(&format!("{}/api/device/backup", self.origin))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(|e| format!("GET /api/device/backup failed: {}", e))?
+            .into_string()
+            .map_err(|e| format!("GET /api/device/backup couldn't be read: {}", e))
+    }
+
+    /// Number of packets logged since `since_ms` (Unix milliseconds). Uses the
+    /// `total` field of a one-row page so the backend only has to count.
+    pub fn packet_count_since(&self, since_ms: i64) -> Result<u64, String> {
+        #[derive(Deserialize)]
+        struct PacketPage {
+            total: u64,
+        }
+        let page: PacketPage = self.get_raw(&format!("/packets?since={}&limit=1", since_ms))?;
+        Ok(page.total)
+    }
+
+    /// Time (Unix milliseconds) of the most recently logged packet, if any.
+    pub fn last_packet_ms(&self) -> Result<Option<i64>, String> {
+        #[derive(Deserialize)]
+        struct Packet {
+            timestamp: i64,
+        }
+        let packets: Vec<Packet> = self.get("/packets?limit=1")?;
+        // Older rows store seconds rather than milliseconds
+        Ok(packets.first().map(|packet| {
+            if packet.timestamp < 10_000_000_000 {
+                packet.timestamp * 1000
+            } else {
+                packet.timestamp
+            }
+        }))
+    }
+
+    /// Add the channels in a Meshtastic channel URL to free slots on the
+    /// default source's node. With `dry_run`, only reports what would be
+    /// added.
+    pub fn add_channel(&self, url: &str, dry_run: bool) -> Result<AddedChannels, String> {
+        self.post(
+            "/sources/default/actions/add-channel",
+            serde_json::json!({ "url": url, "dryRun": dry_run }),
+        )
+    }
+
+    /// The default source's node firmware compared against the newest
+    /// Meshtastic release on the backend's update channel.
+    pub fn firmware(&self) -> Result<FirmwareInfo, String> {
+        self.get("/sources/default/firmware")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_requires_token() {
+        let mut config = Config::default();
+        assert!(BackendApi::from_config(&config).is_none());
+
+        config.api_token = Some("   ".to_string());
+        assert!(BackendApi::from_config(&config).is_none());
+
+        config.api_token = Some("mm_v1_abc".to_string());
+        let api = BackendApi::from_config(&config).expect("token configured");
+        assert_eq!(api.base_url, "http://127.0.0.1:8080/api/v1");
+    }
+
+    #[test]
+    fn test_node_display_name_fallbacks() {
+        let json = r#"{ "success": true, "count": 2, "data": [
+            { "nodeNum": 1, "nodeId": "!00000001", "longName": "Base Camp", "shortName": "BC" },
+            { "nodeNum": 2, "nodeId": "!00000002", "longName": "", "shortName": null }
+        ] }"#;
+        let envelope: Envelope<Vec<ApiNode>> = serde_json::from_str(json).unwrap();
+        assert_eq!(envelope.data[0].display_name(), "Base Camp (!00000001)");
+        assert_eq!(envelope.data[1].display_name(), "!00000002");
+    }
+
+    #[test]
+    fn test_health_verdict() {
+        let report = r#"{"status":"ok","version":"3.1.0","uptime":1200}"#;
+        assert!(health_verdict(report, None).is_ok());
+        assert!(health_verdict(r#"{"status":"degraded"}"#, None).is_err());
+        assert!(health_verdict("OK", None).is_ok());
+        assert!(health_verdict("pong", Some("pong")).is_ok());
+        assert!(health_verdict(report, Some("pong")).is_err());
+    }
+}
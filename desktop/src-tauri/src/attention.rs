@@ -0,0 +1,126 @@
+//! Tray attention state for unread direct messages: the icon gains a small
+//! badge dot and the tooltip gives the count until the web UI is opened.
+//!
+//! The badge is a steady switch rather than a blink, so it's noticeable at a
+//! glance without flashing in the corner of an office screen. It's drawn
+//! over the tray's status dot (`tray::Health`), in the opposite corner.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri::image::Image;
+use tauri::{AppHandle, Runtime};
+
+use crate::locale;
+use crate::tray::{self, TRAY_ID, TRAY_TOOLTIP};
+
+/// Badge colour (RGB): the amber used for highlights in the web UI.
+const BADGE_COLOR: [u8; 3] = [0xf5, 0x9e, 0x0b];
+
+/// Badge diameter as a fraction of the icon size.
+const BADGE_FRACTION: f32 = 0.45;
+
+/// Direct messages received since the web UI was last opened from the tray.
+static UNREAD_DMS: AtomicUsize = AtomicUsize::new(0);
+
+/// Record `count` newly received direct messages and badge the tray icon.
+pub fn add_unread_dms<R: Runtime>(app: &AppHandle<R>, count: usize) {
+    let unread = UNREAD_DMS.fetch_add(count, Ordering::SeqCst) + count;
+    update_tray(app, unread);
+}
+
+/// Clear the unread count and restore the normal tray icon.
+pub fn clear<R: Runtime>(app: &AppHandle<R>) {
+    if UNREAD_DMS.swap(0, Ordering::SeqCst) > 0 {
+        update_tray(app, 0);
+    }
+}
+
+/// Put back the tray icon and tooltip for the current unread count, after
+/// something else showed its own status there.
+pub fn refresh<R: Runtime>(app: &AppHandle<R>) {
+    update_tray(app, UNREAD_DMS.load(Ordering::SeqCst));
+}
+
+/// Redraw the tray icon after the status dot changed, leaving the tooltip.
+pub fn refresh_icon<R: Runtime>(app: &AppHandle<R>) {
+    set_icon(app, UNREAD_DMS.load(Ordering::SeqCst));
+}
+
+fn update_tray<R: Runtime>(app: &AppHandle<R>, unread: usize) {
+    set_icon(app, unread);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip(unread)));
+    }
+}
+
+fn set_icon<R: Runtime>(app: &AppHandle<R>, unread: usize) {
+    let (Some(tray), Some(icon)) = (app.tray_by_id(TRAY_ID), app.default_window_icon()) else {
+        return;
+    };
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+    if let Some(health) = tray::health() {
+        rgba = tray::with_health_dot(&rgba, width, height, health);
+    }
+    if unread > 0 {
+        rgba = with_badge(&rgba, width, height);
+    }
+    let _ = tray.set_icon(Some(Image::new_owned(rgba, width, height)));
+}
+
+/// Tooltip (the tray's accessible name) for `unread` direct messages.
+fn tooltip(unread: usize) -> String {
+    match unread {
+        0 => TRAY_TOOLTIP.to_string(),
+        1 => format!("{} — 1 unread direct message", TRAY_TOOLTIP),
+        n => format!(
+            "{} — {} unread direct messages",
+            TRAY_TOOLTIP,
+            locale::current().count(n as u64)
+        ),
+    }
+}
+
+/// Copy of an RGBA image with a filled badge circle in the top-right corner.
+fn with_badge(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut out = rgba.to_vec();
+    let size = width.min(height) as f32;
+    let radius = size * BADGE_FRACTION / 2.0;
+    let (cx, cy) = (width as f32 - radius, radius);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                out[i..i + 3].copy_from_slice(&BADGE_COLOR);
+                out[i + 3] = 0xff;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_covers_top_right_only() {
+        let (w, h) = (16, 16);
+        let badged = with_badge(&vec![0; (w * h * 4) as usize], w, h);
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * w + x) * 4) as usize;
+            &badged[i..i + 4]
+        };
+        assert_eq!(pixel(w - 2, 1), &[0xf5, 0x9e, 0x0b, 0xff]);
+        assert_eq!(pixel(0, h - 1), &[0, 0, 0, 0]);
+        assert_eq!(pixel(1, 1), &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_tooltip_counts() {
+        assert_eq!(tooltip(0), "MeshMonitor");
+        assert_eq!(tooltip(1), "MeshMonitor — 1 unread direct message");
+        assert_eq!(tooltip(3), "MeshMonitor — 3 unread direct messages");
+    }
+}
@@ -0,0 +1,108 @@
+//! "Geiger counter" packet-activity ticker: a soft click for every packet the
+//! backend receives, so mesh activity can be sensed without looking at it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::api::BackendApi;
+use crate::config::Config;
+use crate::log;
+
+/// How often the backend is asked for new packets while the ticker is on.
+/// Clicks for one interval are spread evenly across the next one, so this is
+/// also the latency between a packet arriving and its click.
+const TICK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Upper bound on clicks per tick (5 per second). A busy mesh or a backfill
+/// of stored packets would otherwise turn into a continuous buzz.
+const MAX_CLICKS_PER_TICK: u64 = 10;
+
+/// Click tone: a short, quiet high-pitched blip.
+const CLICK_FREQUENCY_HZ: f32 = 1800.0;
+const CLICK_DURATION: Duration = Duration::from_millis(6);
+const CLICK_VOLUME: f32 = 0.12;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TICKER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the ticker is currently audible.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Turn the ticker on or off, starting its thread on first enable. Does not
+/// persist the choice; callers update `Config::packet_audio_enabled`.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::SeqCst);
+    if enabled && !TICKER_STARTED.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(run_ticker);
+    }
+}
+
+/// Number of clicks to play for `packets` new packets in one tick.
+fn clicks_for(packets: u64) -> u64 {
+    packets.min(MAX_CLICKS_PER_TICK)
+}
+
+fn run_ticker() {
+    use rodio::Source;
+
+    // The output stream isn't Send, so it lives on this thread for good.
+    let (_stream, handle) = match rodio::OutputStream::try_default() {
+        Ok(output) => output,
+        Err(e) => {
+            log(&format!("Packet audio disabled: no audio output ({})", e));
+            ENABLED.store(false, Ordering::SeqCst);
+            TICKER_STARTED.store(false, Ordering::SeqCst);
+            return;
+        }
+    };
+
+    let mut since_ms = chrono::Utc::now().timestamp_millis();
+    loop {
+        if !is_enabled() {
+            // Skip whatever arrived while muted.
+            std::thread::sleep(TICK_INTERVAL);
+            since_ms = chrono::Utc::now().timestamp_millis();
+            continue;
+        }
+
+        let polled_at = chrono::Utc::now().timestamp_millis();
+        let packets = Config::load()
+            .ok()
+            .and_then(|config| BackendApi::from_config(&config))
+            .map(|api| api.packet_count_since(since_ms))
+            .unwrap_or(Ok(0))
+            .unwrap_or(0);
+        since_ms = polled_at;
+
+        let clicks = clicks_for(packets);
+        if clicks == 0 {
+            std::thread::sleep(TICK_INTERVAL);
+            continue;
+        }
+
+        let spacing = TICK_INTERVAL / clicks as u32;
+        for _ in 0..clicks {
+            if is_enabled() {
+                let click = rodio::source::SineWave::new(CLICK_FREQUENCY_HZ)
+                    .take_duration(CLICK_DURATION)
+                    .amplify(CLICK_VOLUME);
+                let _ = handle.play_raw(click);
+            }
+            std::thread::sleep(spacing);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clicks_are_rate_limited() {
+        assert_eq!(clicks_for(0), 0);
+        assert_eq!(clicks_for(3), 3);
+        assert_eq!(clicks_for(500), MAX_CLICKS_PER_TICK);
+    }
+}
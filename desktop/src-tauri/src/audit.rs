@@ -0,0 +1,160 @@
+//! Append-only audit trail of administrative actions (config changes,
+//! backend restarts, config restores, exposure changes) in `audit.log` in
+//! the logs folder, one JSON object per line, so it sits next to the other
+//! logs opened from the tray.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config::{self, Config};
+
+/// What initiated an audited action.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// A tray menu entry (or its control-window stand-in).
+    Tray,
+    /// A command invoked from an app window, such as the settings page.
+    Command,
+    /// A background task acting on its own, such as the watchdog.
+    Scheduler,
+    /// A prompt answered by the user (config reload or recovery).
+    Dialog,
+    /// A launch argument, such as `--provision-kiosk`.
+    CommandLine,
+    /// A Unix signal sent by an administrator, such as SIGHUP.
+    Signal,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Local time, RFC 3339.
+    pub timestamp: String,
+    pub source: Source,
+    /// What happened, e.g. `config_changed` or `backend_restarted`.
+    pub action: String,
+    #[serde(default)]
+    pub detail: String,
+}
+
+/// Settings that change who can reach the web UI; edits to them are also
+/// recorded as `exposure_changed`.
+const EXPOSURE_FIELDS: &[&str] = &["web_port", "allowed_origins"];
+
+/// Settings whose values are never written to the audit log.
+const SECRET_FIELDS: &[&str] = &["session_secret", "api_token"];
+
+fn audit_path() -> Result<PathBuf, String> {
+    Ok(config::get_logs_path()?.join("audit.log"))
+}
+
+/// Append an entry. Failures are reported on stderr only: auditing must
+/// never stop the action itself.
+pub fn record(source: Source, action: &str, detail: &str) {
+    let entry = AuditEntry {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        source,
+        action: action.to_string(),
+        detail: detail.to_string(),
+    };
+    let result = audit_path().and_then(|path| {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create logs directory: {}", e))?;
+        }
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize audit entry: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open audit log: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write audit log: {}", e))
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+/// Record a settings save: which fields changed and, for non-secret ones,
+/// their new values. Nothing is recorded when nothing changed.
+pub fn record_config_change(source: Source, old: &Config, new: &Config) {
+    let changes = changed_fields(old, new);
+    if changes.is_empty() {
+        return;
+    }
+    let describe = |fields: &[(String, String)]| {
+        fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    record(source, "config_changed", &describe(&changes));
+
+    let exposure: Vec<_> = changes
+        .into_iter()
+        .filter(|(name, _)| EXPOSURE_FIELDS.contains(&name.as_str()))
+        .collect();
+    if !exposure.is_empty() {
+        record(source, "exposure_changed", &describe(&exposure));
+    }
+}
+
+/// Fields that differ between two configs, with the new value (or
+/// `<redacted>` for secrets), sorted by name.
+fn changed_fields(old: &Config, new: &Config) -> Vec<(String, String)> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    new.into_iter()
+        .filter(|(name, value)| old.get(name) != Some(value))
+        .map(|(name, value)| {
+            let shown = if SECRET_FIELDS.contains(&name.as_str()) {
+                "<redacted>".to_string()
+            } else {
+                value.to_string()
+            };
+            (name, shown)
+        })
+        .collect()
+}
+
+/// The last `limit` entries, oldest first. Unparseable lines are skipped.
+pub fn recent(limit: usize) -> Vec<AuditEntry> {
+    let Ok(content) = audit_path().and_then(|path| {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read audit log: {}", e))
+    }) else {
+        return Vec::new();
+    };
+    let entries: Vec<AuditEntry> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(limit);
+    entries.into_iter().skip(skip).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_fields_redacts_secrets() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert!(changed_fields(&old, &new).is_empty());
+
+        new.web_port = 8081;
+        new.api_token = Some("mm_v1_secret".to_string());
+        let changes = changed_fields(&old, &new);
+        assert!(changes.contains(&("web_port".to_string(), "8081".to_string())));
+        assert!(changes.contains(&("api_token".to_string(), "<redacted>".to_string())));
+        assert_eq!(changes.len(), 2);
+    }
+}
@@ -0,0 +1,193 @@
+//! Launching MeshMonitor when the user logs in (`Config::auto_start`): an
+//! XDG autostart entry on Linux, a LaunchAgent on macOS and a `Run` value
+//! in the user's registry hive on Windows. All of them are per user, so a
+//! dedicated kiosk account gets its own without touching anyone else's.
+
+/// Argument the login item launches MeshMonitor with, so it knows the
+/// network may still be coming up.
+pub const AUTOSTART_FLAG: &str = "--autostart";
+
+/// Whether this run was launched by the login item.
+pub fn launched_at_login() -> bool {
+    std::env::args().skip(1).any(|arg| arg == AUTOSTART_FLAG)
+}
+
+/// Add or remove the login item for the running executable.
+pub fn set(enabled: bool) -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the MeshMonitor executable: {}", e))?;
+    platform::set(enabled, &exe.to_string_lossy())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{remove, AUTOSTART_FLAG};
+    use std::path::PathBuf;
+
+    pub fn set(enabled: bool, exe: &str) -> Result<(), String> {
+        let path = entry_path().ok_or("Couldn't find the autostart folder")?;
+        if !enabled {
+            return remove(&path);
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, desktop_entry(exe))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn entry_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("autostart").join("meshmonitor.desktop"))
+    }
+
+    pub(super) fn desktop_entry(exe: &str) -> String {
+        // Exec arguments containing spaces or quotes must be quoted
+        let exec = if exe.contains([' ', '"', '\\']) {
+            format!("\"{}\"", exe.replace('\\', "\\\\").replace('"', "\\\""))
+        } else {
+            exe.to_string()
+        };
+        format!(
+            "[Desktop Entry]\nType=Application\nName=MeshMonitor\nExec={} {}\n\
+             X-GNOME-Autostart-enabled=true\n",
+            exec, AUTOSTART_FLAG
+        )
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{remove, AUTOSTART_FLAG};
+    use std::path::PathBuf;
+
+    const LABEL: &str = "org.meshmonitor.desktop";
+
+    pub fn set(enabled: bool, exe: &str) -> Result<(), String> {
+        let home = std::env::var_os("HOME").ok_or("HOME is not set")?;
+        let path = PathBuf::from(home)
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("{}.plist", LABEL));
+        if !enabled {
+            return remove(&path);
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, launch_agent(exe))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn launch_agent(exe: &str) -> String {
+        let exe = exe
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+            LABEL, exe, AUTOSTART_FLAG
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::AUTOSTART_FLAG;
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const VALUE: &str = "MeshMonitor";
+    /// CREATE_NO_WINDOW, so no console flashes up
+    const NO_WINDOW: u32 = 0x0800_0000;
+
+    pub fn set(enabled: bool, exe: &str) -> Result<(), String> {
+        let mut command = Command::new("reg");
+        if enabled {
+            command.args([
+                "add",
+                RUN_KEY,
+                "/v",
+                VALUE,
+                "/t",
+                "REG_SZ",
+                "/d",
+                &format!("\"{}\" {}", exe, AUTOSTART_FLAG),
+                "/f",
+            ]);
+        } else {
+            // Nothing to delete is fine
+            let exists = Command::new("reg")
+                .args(["query", RUN_KEY, "/v", VALUE])
+                .creation_flags(NO_WINDOW)
+                .output()
+                .is_ok_and(|out| out.status.success());
+            if !exists {
+                return Ok(());
+            }
+            command.args(["delete", RUN_KEY, "/v", VALUE, "/f"]);
+        }
+        let output = command
+            .creation_flags(NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Failed to update the login items: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    pub fn set(_enabled: bool, _exe: &str) -> Result<(), String> {
+        Err("Autostart isn't supported on this platform".to_string())
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn remove(path: &std::path::Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::platform::desktop_entry;
+
+    #[test]
+    fn test_desktop_entry_quotes_paths_with_spaces() {
+        assert!(desktop_entry("/usr/bin/meshmonitor-desktop")
+            .contains("\nExec=/usr/bin/meshmonitor-desktop --autostart\n"));
+        assert!(desktop_entry("/opt/Mesh Monitor/meshmonitor")
+            .contains("\nExec=\"/opt/Mesh Monitor/meshmonitor\" --autostart\n"));
+    }
+}
@@ -0,0 +1,111 @@
+//! The environment the backend starts with. Rather than everything this app
+//! inherited from the user's shell or login profile, where a stray
+//! `NODE_OPTIONS`, `HTTP_PROXY` or `DEBUG` would change what the backend
+//! does, it gets only the variables the OS and Node.js need plus those in
+//! `Config::backend_env_allowlist`. Everything MeshMonitor configures is set
+//! explicitly afterwards. `Config::inherit_backend_env` turns this off.
+
+use std::process::Command;
+
+use crate::config::Config;
+
+/// Variables always passed on. A trailing `*` matches a prefix.
+pub const BASE_ALLOWLIST: &[&str] = &[
+    // Unix
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "LANG",
+    "LANGUAGE",
+    "LC_*",
+    "TZ",
+    "TMPDIR",
+    "XDG_RUNTIME_DIR",
+    // Windows
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+    "USERNAME",
+    "USERPROFILE",
+    "HOMEDRIVE",
+    "HOMEPATH",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "COMPUTERNAME",
+    "NUMBER_OF_PROCESSORS",
+    "PROCESSOR_ARCHITECTURE",
+];
+
+/// Clear `cmd`'s environment down to the allowed variables, unless the
+/// config says to inherit it. Call before setting any variables on `cmd`.
+/// Returns the names that were left out, sorted.
+pub fn restrict(cmd: &mut Command, config: &Config) -> Vec<String> {
+    if config.inherit_backend_env {
+        return Vec::new();
+    }
+    let mut dropped = Vec::new();
+    cmd.env_clear();
+    for (name, value) in std::env::vars_os() {
+        let Some(key) = name.to_str() else {
+            continue;
+        };
+        if allowed(key, &config.backend_env_allowlist) {
+            cmd.env(&name, value);
+        } else {
+            dropped.push(key.to_string());
+        }
+    }
+    dropped.sort();
+    dropped
+}
+
+/// Whether `name` is on the base allowlist or `extra`.
+fn allowed(name: &str, extra: &[String]) -> bool {
+    BASE_ALLOWLIST
+        .iter()
+        .copied()
+        .chain(extra.iter().map(|pattern| pattern.trim()))
+        .any(|pattern| matches(pattern, name))
+}
+
+/// Names match case-insensitively on Windows, like the variables themselves.
+fn matches(pattern: &str, name: &str) -> bool {
+    let eq = |a: &str, b: &str| {
+        if cfg!(windows) {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    };
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name
+            .get(..prefix.len())
+            .is_some_and(|start| eq(start, prefix)),
+        None => !pattern.is_empty() && eq(pattern, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_allowlisted_variables_are_kept() {
+        let extra = vec!["NODE_EXTRA_CA_CERTS".to_string(), " MY_* ".to_string()];
+        assert!(allowed("PATH", &extra));
+        assert!(allowed("LC_ALL", &extra));
+        assert!(allowed("NODE_EXTRA_CA_CERTS", &extra));
+        assert!(allowed("MY_SETTING", &extra));
+        assert!(!allowed("NODE_OPTIONS", &extra));
+        assert!(!allowed("HTTPS_PROXY", &extra));
+        assert!(!allowed("PATHS", &extra));
+        assert!(!allowed("ANYTHING", &["".to_string()]));
+    }
+}
@@ -0,0 +1,171 @@
+//! On-disk record of the running backend (PID, port, start time), so a
+//! backend left running by "Quit, Keep Backend Running" — or orphaned by a
+//! desktop crash — is adopted on the next launch instead of being joined by
+//! a duplicate fighting it for the port and database.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::api;
+use crate::config;
+
+/// How far the backend's reported start time may drift from the recorded
+/// spawn time. Node takes a few seconds to load server.js; a much larger gap
+/// means the PID and port now belong to some other process.
+const START_TIME_TOLERANCE_MS: i64 = 120_000;
+
+/// A backend process spawned by some run of the desktop app.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackendRecord {
+    pub pid: u32,
+    pub web_port: u16,
+    /// Spawn time, Unix milliseconds.
+    pub started_at: i64,
+    /// The Apprise sidecar the backend was pointed at, if any.
+    #[serde(default)]
+    pub apprise_pid: Option<u32>,
+    #[serde(default)]
+    pub apprise_url: Option<String>,
+    /// Bearer token for the backend's `/api/desktop/shutdown` endpoint,
+    /// generated for each spawn and passed as DESKTOP_CONTROL_TOKEN.
+    #[serde(default)]
+    pub control_token: Option<String>,
+    /// Loopback port of the desktop's node bridge the backend was pointed
+    /// at, so an adopting run can serve it again.
+    #[serde(default)]
+    pub bridge_port: Option<u16>,
+}
+
+fn record_path() -> Result<PathBuf, String> {
+    Ok(config::get_data_path()?.join("backend.json"))
+}
+
+impl BackendRecord {
+    /// Persist this record, replacing any previous one.
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize backend record: {}", e))?;
+        fs::write(record_path()?, content)
+            .map_err(|e| format!("Failed to write backend record: {}", e))
+    }
+
+    /// The record left by the last backend spawned, if any.
+    pub fn load() -> Option<Self> {
+        let content = fs::read_to_string(record_path().ok()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Forget the recorded backend (it was stopped, or is not ours anymore).
+    pub fn clear() {
+        if let Ok(path) = record_path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Whether a backend's reported `uptime_ms` at `now_ms` places its start
+    /// close enough to this record's spawn time to be the same process.
+    fn matches_uptime(&self, now_ms: i64, uptime_ms: i64) -> bool {
+        (now_ms - uptime_ms - self.started_at).abs() <= START_TIME_TOLERANCE_MS
+    }
+}
+
+/// Find a backend from a previous run that is still alive, answering health
+/// checks on the recorded port, and demonstrably the process we spawned. A
+/// stale record is cleared.
+pub fn find_adoptable() -> Option<BackendRecord> {
+    let record = BackendRecord::load()?;
+    let adoptable = is_alive(record.pid)
+        && api::health(record.web_port).is_ok_and(|health| {
+            health.status == "ok"
+                && record.matches_uptime(chrono::Utc::now().timestamp_millis(), health.uptime)
+        });
+    if adoptable {
+        Some(record)
+    } else {
+        BackendRecord::clear();
+        None
+    }
+}
+
+/// Whether a process with `pid` exists.
+pub fn is_alive(pid: u32) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .is_ok_and(|out| String::from_utf8_lossy(&out.stdout).contains(&pid.to_string()))
+    }
+    #[cfg(not(windows))]
+    {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .is_ok_and(|out| out.status.success())
+    }
+}
+
+/// Ask a process to exit cleanly (SIGTERM). Returns `false` where no such
+/// signal exists: Windows can only close console or windowed programs
+/// politely, and the backend is neither.
+pub fn terminate(pid: u32) -> bool {
+    #[cfg(windows)]
+    {
+        let _ = pid;
+        false
+    }
+    #[cfg(not(windows))]
+    {
+        Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+/// Forcefully kill a process we don't hold a `Child` handle for.
+pub fn kill(pid: u32) {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status();
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = Command::new("kill")
+            .args(["-KILL", &pid.to_string()])
+            .status();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uptime_must_match_spawn_time() {
+        let record = BackendRecord {
+            pid: 1234,
+            web_port: 8080,
+            started_at: 1_000_000,
+            apprise_pid: None,
+            apprise_url: None,
+            control_token: None,
+            bridge_port: None,
+        };
+        // Started 5s after spawn, checked an hour later
+        let now = 1_000_000 + 3_600_000;
+        assert!(record.matches_uptime(now, 3_600_000 - 5_000));
+        // Uptime far shorter than expected: PID/port reused by another process
+        assert!(!record.matches_uptime(now, 60_000));
+    }
+}
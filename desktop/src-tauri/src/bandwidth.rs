@@ -0,0 +1,463 @@
+//! Bandwidth metering for the backend's outbound requests (map tile server
+//! tests, elevation lookups, version checks and the like), for users on
+//! metered satellite or LTE links (`Config::track_bandwidth`). The backend's
+//! HTTP(S) requests go through a forward proxy on loopback, which counts the
+//! bytes each way; daily totals are kept in `bandwidth.json` in the data
+//! folder, with an optional alert when the month's total passes
+//! `Config::bandwidth_monthly_cap_mb`.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::config::{self, Config};
+use crate::{locale, log, notifications, proxy};
+
+/// How often the counters are added to the daily totals.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a client gets to send its request head.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long connecting to a remote host may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Days of totals kept, enough for this month and last year's same month.
+const KEPT_DAYS: usize = 400;
+
+/// User name in the proxy URL; the password is `Usage::token`.
+const PROXY_USER: &str = "meshmonitor";
+
+/// Bytes not yet added to the daily totals.
+static SENT: AtomicU64 = AtomicU64::new(0);
+static RECEIVED: AtomicU64 = AtomicU64::new(0);
+
+/// Guards against starting the meter twice.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// URL of the running meter, with its credentials, for `HTTP(S)_PROXY`.
+static PROXY_URL: OnceLock<String> = OnceLock::new();
+
+/// Serializes reading and writing `bandwidth.json`.
+static USAGE_FILE: Mutex<()> = Mutex::new(());
+
+/// Bytes moved on one day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayUsage {
+    pub sent: u64,
+    pub received: u64,
+}
+
+impl DayUsage {
+    pub fn total(&self) -> u64 {
+        self.sent + self.received
+    }
+}
+
+/// Contents of `bandwidth.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Usage {
+    /// Totals by local date, `YYYY-MM-DD`.
+    #[serde(default)]
+    days: BTreeMap<String, DayUsage>,
+    /// Month (`YYYY-MM`) the cap alert was last shown for.
+    #[serde(default)]
+    alerted_month: Option<String>,
+    /// Port and password of the meter, reused on the next run so a backend
+    /// that outlived the app keeps reaching the internet once it's adopted.
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    token: Option<String>,
+}
+
+/// One day of the report shown in Settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyTotal {
+    pub date: String,
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// Usage report for Settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub tracking: bool,
+    /// Most recent day first.
+    pub days: Vec<DailyTotal>,
+    pub month_total: u64,
+    pub monthly_cap_mb: Option<u64>,
+}
+
+fn usage_path() -> Option<PathBuf> {
+    config::get_data_path()
+        .ok()
+        .map(|path| path.join("bandwidth.json"))
+}
+
+fn load() -> Usage {
+    usage_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(usage: &Usage) {
+    let Some(path) = usage_path() else {
+        return;
+    };
+    match serde_json::to_string_pretty(usage) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                log(&format!("Failed to save bandwidth totals: {}", e));
+            }
+        }
+        Err(e) => log(&format!("Failed to save bandwidth totals: {}", e)),
+    }
+}
+
+/// Start the meter when tracking is on; it runs until the app exits. Run
+/// before the backend spawns so `proxy_url` is set. Safe to call more than
+/// once.
+pub fn start<R: Runtime>(app: &AppHandle<R>, config: &Config) {
+    if !config.track_bandwidth || STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let (port, token) = {
+        let _file = USAGE_FILE.lock().unwrap();
+        let mut usage = load();
+        let token = usage.token.clone().unwrap_or_else(config::generate_secret);
+        let listener = usage
+            .port
+            .and_then(|port| TcpListener::bind(("127.0.0.1", port)).ok())
+            .or_else(|| TcpListener::bind(("127.0.0.1", 0)).ok());
+        let Some((listener, port)) = listener.and_then(|listener| {
+            let port = listener.local_addr().ok()?.port();
+            Some((listener, port))
+        }) else {
+            log("Bandwidth meter couldn't listen on loopback; not tracking");
+            return;
+        };
+        usage.port = Some(port);
+        usage.token = Some(token.clone());
+        save(&usage);
+        spawn_listener(listener, proxy_authorization(&token));
+        (port, token)
+    };
+    let _ = PROXY_URL.set(format!(
+        "http://{}:{}@127.0.0.1:{}",
+        PROXY_USER, token, port
+    ));
+    log(&format!("Bandwidth meter listening on 127.0.0.1:{}", port));
+
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        flush(&app);
+    });
+}
+
+/// Proxy URL the backend's requests go through, while metering is on.
+pub fn proxy_url() -> Option<&'static str> {
+    PROXY_URL.get().map(String::as_str)
+}
+
+/// Add the counters to today's total and alert once a month when the cap
+/// is passed.
+fn flush<R: Runtime>(app: &AppHandle<R>) {
+    let sent = SENT.swap(0, Ordering::SeqCst);
+    let received = RECEIVED.swap(0, Ordering::SeqCst);
+    if sent == 0 && received == 0 {
+        return;
+    }
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let month = today[..7].to_string();
+    let cap_mb = Config::load()
+        .ok()
+        .and_then(|config| config.bandwidth_monthly_cap_mb);
+
+    let _file = USAGE_FILE.lock().unwrap();
+    let mut usage = load();
+    let day = usage.days.entry(today).or_default();
+    day.sent += sent;
+    day.received += received;
+    while usage.days.len() > KEPT_DAYS {
+        usage.days.pop_first();
+    }
+    let total = month_total(&usage.days, &month);
+    let alert = cap_mb.is_some_and(|cap| {
+        total >= cap * 1_000_000 && usage.alerted_month.as_deref() != Some(month.as_str())
+    });
+    if alert {
+        usage.alerted_month = Some(month);
+    }
+    save(&usage);
+    if alert {
+        notifications::notify(
+            app,
+            "MeshMonitor passed its monthly data cap",
+            &format!(
+                "The backend used {} of internet traffic this month, over the {} MB cap set \
+                 in Settings.",
+                locale::current().bytes(total),
+                locale::current().count(cap_mb.unwrap_or_default())
+            ),
+            None,
+        );
+    }
+}
+
+/// Bytes used in `month` (`YYYY-MM`).
+fn month_total(days: &BTreeMap<String, DayUsage>, month: &str) -> u64 {
+    days.iter()
+        .filter(|(date, _)| date.starts_with(month))
+        .map(|(_, day)| day.total())
+        .sum()
+}
+
+/// Totals for the last `days` days that had traffic, today's including what
+/// hasn't been flushed yet.
+pub fn report(days: usize) -> Report {
+    let config = Config::load().unwrap_or_default();
+    let mut usage = {
+        let _file = USAGE_FILE.lock().unwrap();
+        load()
+    };
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let pending = usage.days.entry(today.clone()).or_default();
+    pending.sent += SENT.load(Ordering::SeqCst);
+    pending.received += RECEIVED.load(Ordering::SeqCst);
+    Report {
+        tracking: config.track_bandwidth,
+        days: usage
+            .days
+            .iter()
+            .rev()
+            .filter(|(_, day)| day.total() > 0)
+            .take(days)
+            .map(|(date, day)| DailyTotal {
+                date: date.clone(),
+                sent: day.sent,
+                received: day.received,
+            })
+            .collect(),
+        month_total: month_total(&usage.days, &today[..7]),
+        monthly_cap_mb: config.bandwidth_monthly_cap_mb,
+    }
+}
+
+/// `Proxy-Authorization` value the backend sends for the proxy URL.
+fn proxy_authorization(token: &str) -> String {
+    let credentials = format!("{}:{}", PROXY_USER, token);
+    format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD.encode(credentials)
+    )
+}
+
+fn spawn_listener(listener: TcpListener, authorization: String) {
+    std::thread::spawn(move || {
+        for client in listener.incoming().flatten() {
+            let authorization = authorization.clone();
+            std::thread::spawn(move || handle(client, &authorization));
+        }
+    });
+}
+
+/// Where a proxy request goes and what to send there first.
+#[derive(Debug, PartialEq)]
+enum Forward {
+    /// `CONNECT host:port`, an HTTPS tunnel.
+    Tunnel(String),
+    /// A plain HTTP request, with its head rewritten for the origin server.
+    Request(String, String),
+}
+
+/// Serve one proxy connection.
+fn handle(mut client: TcpStream, authorization: &str) {
+    let _ = client.set_read_timeout(Some(HEAD_TIMEOUT));
+    let Some((head, body)) = proxy::read_head(&mut client) else {
+        return proxy::respond(client, "400 Bad Request", "Bad request");
+    };
+    let authorized =
+        header(&head, "proxy-authorization").is_some_and(|value| value == authorization);
+    if !authorized {
+        return proxy::respond(
+            client,
+            "407 Proxy Authentication Required",
+            "Proxy authentication required",
+        );
+    }
+    let Some(forward) = forward(&head) else {
+        return proxy::respond(client, "400 Bad Request", "Bad request");
+    };
+    let target = match &forward {
+        Forward::Tunnel(target) | Forward::Request(target, _) => target,
+    };
+    let server = target
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .and_then(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok());
+    let Some(mut server) = server else {
+        return proxy::respond(client, "502 Bad Gateway", "Couldn't reach the server");
+    };
+    let first = match &forward {
+        Forward::Tunnel(_) => {
+            if client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .is_err()
+            {
+                return;
+            }
+            body
+        }
+        Forward::Request(_, head) => [head.as_bytes(), &body].concat(),
+    };
+    if server.write_all(&first).is_err() {
+        return;
+    }
+    SENT.fetch_add(first.len() as u64, Ordering::SeqCst);
+    let _ = client.set_read_timeout(None);
+    splice_counted(client, server);
+}
+
+/// The value of header `name` in a request head.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.split("\r\n")
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(header, _)| header.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// How to forward a proxy request head, or `None` if it isn't one.
+fn forward(head: &str) -> Option<Forward> {
+    let mut lines = head.trim_end().split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let (method, target, version) = (
+        request_line.next()?,
+        request_line.next()?,
+        request_line.next()?,
+    );
+    if method.eq_ignore_ascii_case("CONNECT") {
+        return target
+            .rsplit_once(':')
+            .filter(|(_, port)| port.parse::<u16>().is_ok())
+            .map(|_| Forward::Tunnel(target.to_string()));
+    }
+    let rest = target.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return None;
+    }
+    let address = if authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+    {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    let mut out = format!("{} {} {}\r\n", method, path, version);
+    for line in lines {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        let dropped = [
+            "proxy-authorization",
+            "proxy-connection",
+            "connection",
+            "keep-alive",
+        ]
+        .iter()
+        .any(|dropped| name.eq_ignore_ascii_case(dropped));
+        if !dropped {
+            out.push_str(line);
+            out.push_str("\r\n");
+        }
+    }
+    out.push_str("Connection: close\r\n\r\n");
+    Some(Forward::Request(address, out))
+}
+
+/// Copy both ways until either side closes, counting the bytes.
+fn splice_counted(mut client: TcpStream, mut server: TcpStream) {
+    let (Ok(mut client_reader), Ok(mut server_writer)) = (client.try_clone(), server.try_clone())
+    else {
+        return;
+    };
+    let upload = std::thread::spawn(move || {
+        let _ = copy_counted(&mut client_reader, &mut server_writer, &SENT);
+        let _ = server_writer.shutdown(Shutdown::Write);
+    });
+    let _ = copy_counted(&mut server, &mut client, &RECEIVED);
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = server.shutdown(Shutdown::Both);
+    let _ = upload.join();
+}
+
+fn copy_counted(from: &mut TcpStream, to: &mut TcpStream, counter: &AtomicU64) -> io::Result<()> {
+    let mut buf = [0u8; 16 * 1024];
+    loop {
+        let read = from.read(&mut buf)?;
+        if read == 0 {
+            return Ok(());
+        }
+        to.write_all(&buf[..read])?;
+        counter.fetch_add(read as u64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forwards_tunnels_and_plain_requests() {
+        assert_eq!(
+            forward("CONNECT tile.openstreetmap.org:443 HTTP/1.1\r\nHost: tile.openstreetmap.org:443\r\n\r\n"),
+            Some(Forward::Tunnel("tile.openstreetmap.org:443".to_string()))
+        );
+        assert_eq!(
+            forward(
+                "GET http://example.com/a?b=1 HTTP/1.1\r\nHost: example.com\r\n\
+                 Proxy-Authorization: Basic eA==\r\nProxy-Connection: keep-alive\r\n\r\n"
+            ),
+            Some(Forward::Request(
+                "example.com:80".to_string(),
+                "GET /a?b=1 HTTP/1.1\r\nHost: example.com\r\nConnection: close\r\n\r\n".to_string()
+            ))
+        );
+        assert_eq!(forward("GET /api/health HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(forward("CONNECT example.com HTTP/1.1\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn test_month_total_counts_only_that_month() {
+        let day = |sent, received| DayUsage { sent, received };
+        let days = BTreeMap::from([
+            ("2026-09-30".to_string(), day(5, 5)),
+            ("2026-10-01".to_string(), day(100, 900)),
+            ("2026-10-14".to_string(), day(1, 2)),
+        ]);
+        assert_eq!(month_total(&days, "2026-10"), 1003);
+        assert_eq!(month_total(&days, "2026-11"), 0);
+    }
+
+    #[test]
+    fn test_proxy_authorization_is_basic_auth() {
+        assert_eq!(
+            proxy_authorization("secret"),
+            "Basic bWVzaG1vbml0b3I6c2VjcmV0"
+        );
+    }
+}
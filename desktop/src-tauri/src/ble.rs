@@ -0,0 +1,489 @@
+//! Bluetooth LE link to a Meshtastic node, served to the backend through a
+//! [`bridge`](crate::bridge). Packets go out by writing the `ToRadio`
+//! characteristic; the node announces new packets on `FromNum`, and
+//! `FromRadio` is read until it comes back empty.
+//!
+//! The link goes through btleplug, which uses BlueZ on Linux, CoreBluetooth
+//! on macOS and WinRT on Windows. The node has to be paired first: `scan`
+//! lists the Meshtastic nodes in range for Settings, and `pair` pairs one.
+//! btleplug can't pair, so on Linux the device it found is paired through
+//! BlueZ's D-Bus API, whose agent (the desktop's Bluetooth applet) asks for
+//! the PIN the node shows. macOS and Windows pair by themselves, asking for
+//! the PIN, when the node is first read from.
+
+use btleplug::api::{
+    Central, Characteristic, Manager as _, Peripheral as _, ScanFilter, WriteType,
+};
+use btleplug::platform::{Adapter, Manager, Peripheral};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use uuid::{uuid, Uuid};
+
+use crate::bridge::Link;
+
+/// How long to scan for a node the system doesn't know about yet.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long `scan` listens for nodes.
+const LIST_DURATION: Duration = Duration::from_secs(8);
+
+/// How long a node gets to connect and list its services.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest a read or write may take before the link counts as lost.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What to do when pairing fails.
+const PIN_HINT: &str =
+    "Enter the PIN the node shows when asked, or 123456 for a node without a screen.";
+
+/// A Meshtastic node found by `scan`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Device {
+    pub address: String,
+    pub name: Option<String>,
+    /// Only known on Linux; elsewhere `pair` is harmless for a paired node.
+    pub paired: bool,
+}
+
+/// Meshtastic's GATT service and characteristics.
+mod uuids {
+    use super::{uuid, Uuid};
+
+    pub const SERVICE: Uuid = uuid!("6ba1b218-15a8-461f-9fa8-5dcae273eafd");
+    pub const TO_RADIO: Uuid = uuid!("f75c76d2-129e-4dad-a1dd-7866124401e7");
+    pub const FROM_RADIO: Uuid = uuid!("2c55e69e-4993-11ed-b878-0242ac120002");
+    pub const FROM_NUM: Uuid = uuid!("ed9da18c-a800-4f66-a670-aa7547e34453");
+}
+
+struct BleLink {
+    peripheral: Peripheral,
+    to_radio: Characteristic,
+    from_radio: Characteristic,
+    /// One message per `FromNum` notification.
+    notified: Receiver<()>,
+    /// Read `FromRadio` until it is empty before waiting again.
+    draining: bool,
+    /// Passes the node's notifications on to `notified`.
+    forwarding: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl Link for BleLink {
+    fn send(&mut self, packet: &[u8]) -> Result<(), String> {
+        run(
+            "write",
+            self.peripheral
+                .write(&self.to_radio, packet, WriteType::WithResponse),
+        )?;
+        // Replies come without a notification while the node sends its
+        // configuration
+        self.draining = true;
+        Ok(())
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        if !self.draining {
+            match self.notified.recv_timeout(timeout) {
+                Ok(()) => self.draining = true,
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err("Bluetooth notifications stopped".to_string())
+                }
+            }
+        }
+        let packet = run("read", self.peripheral.read(&self.from_radio))?;
+        if packet.is_empty() {
+            self.draining = false;
+            return Ok(None);
+        }
+        Ok(Some(packet))
+    }
+}
+
+impl Drop for BleLink {
+    fn drop(&mut self) {
+        self.forwarding.abort();
+        let peripheral = self.peripheral.clone();
+        tauri::async_runtime::spawn(async move {
+            let _ = peripheral.disconnect().await;
+        });
+    }
+}
+
+/// Run a read or write from the bridge's thread, giving up after
+/// `IO_TIMEOUT`.
+fn run<T>(operation: &str, io: impl Future<Output = btleplug::Result<T>>) -> Result<T, String> {
+    tauri::async_runtime::block_on(tokio::time::timeout(IO_TIMEOUT, io))
+        .map_err(|_| format!("Bluetooth {} timed out", operation))?
+        .map_err(|e| format!("Bluetooth {} failed: {}", operation, e))
+}
+
+/// Whether a device is the one configured, by address or by advertised
+/// name, ignoring case.
+fn matches_device(wanted: &str, address: &str, name: Option<&str>) -> bool {
+    let wanted = wanted.trim();
+    wanted.eq_ignore_ascii_case(address) || name.is_some_and(|n| wanted.eq_ignore_ascii_case(n))
+}
+
+/// Connect to the node `device` (address or name). Returns the link and a
+/// description of the device for status messages.
+pub fn open(device: &str) -> Result<(Box<dyn Link>, String), String> {
+    if device.trim().is_empty() {
+        return Err("No Bluetooth node is configured".to_string());
+    }
+    tauri::async_runtime::block_on(connect(device))
+}
+
+/// The configured node, connected, with its Meshtastic characteristics.
+struct Node {
+    peripheral: Peripheral,
+    description: String,
+    to_radio: Characteristic,
+    from_radio: Characteristic,
+    from_num: Characteristic,
+}
+
+/// Connect to `peripheral`, found by `discover`.
+async fn reach(peripheral: Peripheral, description: String) -> Result<Node, String> {
+    let connecting = async {
+        if !peripheral.is_connected().await? {
+            peripheral.connect().await?;
+        }
+        peripheral.discover_services().await
+    };
+    match tokio::time::timeout(CONNECT_TIMEOUT, connecting).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(format!("Couldn't connect to {}: {}", description, e)),
+        Err(_) => {
+            return Err(format!(
+                "{} didn't list its Bluetooth services",
+                description
+            ))
+        }
+    }
+
+    let characteristics = peripheral.characteristics();
+    let characteristic = |uuid: Uuid| {
+        characteristics
+            .iter()
+            .find(|characteristic| characteristic.uuid == uuid)
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "{} doesn't offer the Meshtastic service ({})",
+                    description,
+                    uuids::SERVICE
+                )
+            })
+    };
+    Ok(Node {
+        to_radio: characteristic(uuids::TO_RADIO)?,
+        from_radio: characteristic(uuids::FROM_RADIO)?,
+        from_num: characteristic(uuids::FROM_NUM)?,
+        peripheral,
+        description,
+    })
+}
+
+/// Connect to `wanted` and turn on its `FromNum` notifications.
+async fn connect(wanted: &str) -> Result<(Box<dyn Link>, String), String> {
+    let Node {
+        peripheral,
+        description,
+        to_radio,
+        from_radio,
+        from_num,
+    } = {
+        let adapter = adapter().await?;
+        let (peripheral, description) = discover(&adapter, wanted).await?;
+        reach(peripheral, description).await?
+    };
+
+    // Listen for FromNum changes before turning notifications on
+    let notifications_failed =
+        |e: btleplug::Error| format!("Bluetooth notifications failed: {}", e);
+    let mut notifications = peripheral
+        .notifications()
+        .await
+        .map_err(notifications_failed)?;
+    let (notify, notified) = mpsc::channel();
+    let forwarding = tauri::async_runtime::spawn(async move {
+        while let Some(notification) = notifications.next().await {
+            if notification.uuid == uuids::FROM_NUM && notify.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    if let Err(e) = peripheral.subscribe(&from_num).await {
+        forwarding.abort();
+        return Err(notifications_failed(e));
+    }
+
+    let link = BleLink {
+        peripheral,
+        to_radio,
+        from_radio,
+        notified,
+        draining: true,
+        forwarding,
+    };
+    Ok((Box::new(link), description))
+}
+
+async fn adapter() -> Result<Adapter, String> {
+    let manager = Manager::new()
+        .await
+        .map_err(|e| format!("Bluetooth isn't available: {}", e))?;
+    manager
+        .adapters()
+        .await
+        .map_err(|e| format!("Bluetooth isn't available: {}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No Bluetooth adapter found".to_string())
+}
+
+/// The address a node is configured by. macOS doesn't reveal Bluetooth
+/// addresses, so there it's the identifier macOS gives the node.
+fn address(peripheral: &Peripheral) -> String {
+    if cfg!(target_os = "macos") {
+        peripheral.id().to_string()
+    } else {
+        peripheral.address().to_string()
+    }
+}
+
+/// The configured node among the devices the system knows, with its
+/// description.
+async fn find_device(adapter: &Adapter, wanted: &str) -> Option<(Peripheral, String)> {
+    for peripheral in adapter.peripherals().await.ok()? {
+        let address = address(&peripheral);
+        let name = peripheral
+            .properties()
+            .await
+            .ok()
+            .flatten()
+            .and_then(|props| props.local_name);
+        if matches_device(wanted, &address, name.as_deref()) {
+            let description = match name {
+                Some(name) => format!("{} ({})", name, address),
+                None => address,
+            };
+            return Some((peripheral, description));
+        }
+    }
+    None
+}
+
+/// Scan until the configured node shows up or `SCAN_TIMEOUT` passes.
+async fn discover(adapter: &Adapter, wanted: &str) -> Result<(Peripheral, String), String> {
+    if let Some(found) = find_device(adapter, wanted).await {
+        return Ok(found);
+    }
+    adapter
+        .start_scan(ScanFilter::default())
+        .await
+        .map_err(|e| format!("Bluetooth scan failed: {}", e))?;
+    let deadline = Instant::now() + SCAN_TIMEOUT;
+    let found = loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        if let Some(found) = find_device(adapter, wanted).await {
+            break Some(found);
+        }
+        if Instant::now() >= deadline {
+            break None;
+        }
+    };
+    let _ = adapter.stop_scan().await;
+    found.ok_or_else(|| {
+        format!(
+            "{} wasn't found. Make sure the node is on, in range and paired.",
+            wanted
+        )
+    })
+}
+
+/// Scan for Meshtastic nodes in range, for a few seconds.
+pub async fn scan() -> Result<Vec<Device>, String> {
+    let adapter = adapter().await?;
+    adapter
+        .start_scan(ScanFilter::default())
+        .await
+        .map_err(|e| format!("Bluetooth scan failed: {}", e))?;
+    tokio::time::sleep(LIST_DURATION).await;
+    let peripherals = adapter.peripherals().await;
+    let _ = adapter.stop_scan().await;
+    let mut devices = Vec::new();
+    for peripheral in peripherals.map_err(|e| format!("Bluetooth scan failed: {}", e))? {
+        let Some(props) = peripheral.properties().await.ok().flatten() else {
+            continue;
+        };
+        if !is_meshtastic(&props.services, props.local_name.as_deref()) {
+            continue;
+        }
+        devices.push(Device {
+            address: address(&peripheral),
+            name: props.local_name,
+            paired: imp::paired(&peripheral).await,
+        });
+    }
+    devices.sort_by(|a, b| a.name.cmp(&b.name).then(a.address.cmp(&b.address)));
+    Ok(devices)
+}
+
+/// Pair the node at `address`, waiting for its PIN to be entered.
+pub async fn pair(address: &str) -> Result<(), String> {
+    let adapter = adapter().await?;
+    let (peripheral, description) = discover(&adapter, address).await?;
+    imp::pair(peripheral, description).await
+}
+
+/// Whether a device is a Meshtastic node: it advertises the service, or,
+/// before its services have been read, by the firmware's default name.
+fn is_meshtastic(service_uuids: &[Uuid], name: Option<&str>) -> bool {
+    service_uuids.contains(&uuids::SERVICE)
+        || name.is_some_and(|name| name.starts_with("Meshtastic_"))
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use btleplug::api::Peripheral as _;
+    use btleplug::platform::Peripheral;
+    use zbus::blocking::Connection;
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::PIN_HINT;
+
+    const DEVICE: &str = "org.bluez.Device1";
+    const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+    /// BlueZ's object path for a device btleplug found, whose ID is that
+    /// path without `/org/bluez/`.
+    fn device_path(peripheral: &Peripheral) -> String {
+        format!("/org/bluez/{}", peripheral.id())
+    }
+
+    fn system_bus() -> Result<Connection, String> {
+        Connection::system().map_err(|e| format!("Couldn't reach the system D-Bus: {}", e))
+    }
+
+    fn is_paired(conn: &Connection, device: &str) -> Result<bool, String> {
+        let value: OwnedValue = conn
+            .call_method(
+                Some("org.bluez"),
+                device,
+                Some(PROPERTIES),
+                "Get",
+                &(DEVICE, "Paired"),
+            )
+            .and_then(|reply| reply.body().deserialize())
+            .map_err(|e| format!("Couldn't read the pairing state: {}", e))?;
+        Ok(value.downcast_ref::<bool>().unwrap_or(false))
+    }
+
+    /// Whether BlueZ has paired `peripheral`.
+    pub async fn paired(peripheral: &Peripheral) -> bool {
+        let device = device_path(peripheral);
+        tauri::async_runtime::spawn_blocking(move || {
+            system_bus().and_then(|conn| is_paired(&conn, &device))
+        })
+        .await
+        .is_ok_and(|paired| paired.unwrap_or(false))
+    }
+
+    /// Pair and trust the node through BlueZ, which btleplug can't.
+    pub async fn pair(peripheral: Peripheral, description: String) -> Result<(), String> {
+        let device = device_path(&peripheral);
+        tauri::async_runtime::spawn_blocking(move || pair_and_trust(&device, &description))
+            .await
+            .map_err(|e| format!("Pairing failed: {}", e))?
+    }
+
+    fn pair_and_trust(device: &str, description: &str) -> Result<(), String> {
+        let conn = system_bus()?;
+        if !is_paired(&conn, device)? {
+            conn.call_method(Some("org.bluez"), device, Some(DEVICE), "Pair", &())
+                .map_err(|e| format!("{} wasn't paired: {}. {}", description, e, PIN_HINT))?;
+        }
+        // Trusted, so the node reconnects without asking again
+        conn.call_method(
+            Some("org.bluez"),
+            device,
+            Some(PROPERTIES),
+            "Set",
+            &(DEVICE, "Trusted", Value::from(true)),
+        )
+        .map_err(|e| format!("Couldn't trust {}: {}", description, e))?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use btleplug::api::Peripheral as _;
+    use btleplug::platform::Peripheral;
+    use std::time::Duration;
+
+    use super::{reach, PIN_HINT};
+
+    /// How long the user gets to enter the PIN.
+    const PAIR_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Which devices are paired isn't known here.
+    pub async fn paired(_peripheral: &Peripheral) -> bool {
+        false
+    }
+
+    /// Read from the node, which has the system pair it first, asking for
+    /// the PIN, when the node requires that.
+    pub async fn pair(peripheral: Peripheral, description: String) -> Result<(), String> {
+        let node = reach(peripheral, description).await?;
+        let read = tokio::time::timeout(PAIR_TIMEOUT, node.peripheral.read(&node.from_radio)).await;
+        let _ = node.peripheral.disconnect().await;
+        match read {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(format!(
+                "{} wasn't paired: {}. {} Pairing in the system's Bluetooth settings works too.",
+                node.description, e, PIN_HINT
+            )),
+            Err(_) => Err(format!(
+                "{} wasn't paired in time. {}",
+                node.description, PIN_HINT
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_device_by_address_or_name() {
+        assert!(matches_device(
+            "aa:bb:cc:dd:ee:ff",
+            "AA:BB:CC:DD:EE:FF",
+            None
+        ));
+        assert!(matches_device(
+            " Meshtastic_1a2b ",
+            "AA:BB:CC:DD:EE:FF",
+            Some("meshtastic_1a2b")
+        ));
+        assert!(!matches_device(
+            "Meshtastic_1a2b",
+            "AA:BB:CC:DD:EE:FF",
+            Some("Meshtastic_9999")
+        ));
+
+        assert!(is_meshtastic(&[uuids::SERVICE], None));
+        assert!(is_meshtastic(&[], Some("Meshtastic_1a2b")));
+        assert!(!is_meshtastic(
+            &[uuid!("0000180f-0000-1000-8000-00805f9b34fb")],
+            Some("Headphones")
+        ));
+    }
+}
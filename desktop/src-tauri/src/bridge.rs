@@ -0,0 +1,285 @@
+//! Loopback TCP bridge for nodes the Node backend can't reach itself. The
+//! backend only speaks Meshtastic's TCP stream protocol, so for other links
+//! the desktop listens on a loopback port, frames each packet from the node
+//! the way a TCP node would, and hands packets from the backend to the link.
+//! The link is reopened whenever it drops; the backend sees a closed socket
+//! and reconnects on its own.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{log, polling};
+
+/// First two bytes of every frame in the stream protocol.
+const FRAME_START: [u8; 2] = [0x94, 0xC3];
+
+/// Largest packet the firmware sends or accepts.
+const MAX_PACKET: usize = 512;
+
+/// How long each side is waited on before checking the other.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The node side of a bridge: whole `ToRadio` / `FromRadio` protobufs.
+pub trait Link: Send {
+    /// Send one `ToRadio` packet to the node.
+    fn send(&mut self, packet: &[u8]) -> Result<(), String>;
+    /// The next `FromRadio` packet, waiting up to `timeout`. `Ok(None)` when
+    /// nothing arrived in time; `Err` when the link is gone.
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, String>;
+}
+
+/// State of a bridge's link to the node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BridgeStatus {
+    Connecting,
+    /// Open to the named device.
+    Connected(String),
+    /// The last attempt failed or the link dropped; retrying.
+    Disconnected(String),
+}
+
+/// A running bridge. The backend may be restarted any number of times
+/// against the same port; dropping the bridge stops it.
+pub struct Bridge {
+    pub port: u16,
+    /// The kind of link and device the bridge was started for, as configured.
+    pub target: String,
+    status: Arc<Mutex<BridgeStatus>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Bridge {
+    /// Listen on a loopback port (`port`, e.g. the one an adopted backend
+    /// already uses, or else any free one) and serve it from links made by
+    /// `open`, which is called again each time the link drops.
+    pub fn start<F>(
+        name: &'static str,
+        target: &str,
+        port: Option<u16>,
+        mut open: F,
+    ) -> Result<Self, String>
+    where
+        F: FnMut() -> Result<(Box<dyn Link>, String), String> + Send + 'static,
+    {
+        let listener = port
+            .and_then(|port| TcpListener::bind(("127.0.0.1", port)).ok())
+            .map_or_else(|| TcpListener::bind("127.0.0.1:0"), Ok)
+            .map_err(|e| format!("Failed to open the {} bridge port: {}", name, e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read the {} bridge port: {}", name, e))?
+            .port();
+        let status = Arc::new(Mutex::new(BridgeStatus::Connecting));
+        log(&format!("{} bridge listening on 127.0.0.1:{}", name, port));
+
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let shared = status.clone();
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                let (link, device) = match open() {
+                    Ok(opened) => opened,
+                    Err(e) => {
+                        set_status(&shared, name, BridgeStatus::Disconnected(e));
+                        std::thread::sleep(polling::current().reconnect_delay());
+                        continue;
+                    }
+                };
+                set_status(&shared, name, BridgeStatus::Connected(device));
+                let Some(reason) = serve(&listener, link, &stopped) else {
+                    break;
+                };
+                set_status(&shared, name, BridgeStatus::Disconnected(reason));
+                std::thread::sleep(polling::current().reconnect_delay());
+            }
+            log(&format!("{} bridge stopped", name));
+        });
+
+        Ok(Self {
+            port,
+            target: target.to_string(),
+            status,
+            stop,
+        })
+    }
+
+    pub fn status(&self) -> BridgeStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+impl BridgeStatus {
+    /// One line for the tray menu.
+    pub fn summary(&self) -> String {
+        match self {
+            BridgeStatus::Connecting => "Node: connecting...".to_string(),
+            BridgeStatus::Connected(device) => format!("Node: connected to {}", device),
+            BridgeStatus::Disconnected(reason) => format!("Node: disconnected ({})", reason),
+        }
+    }
+}
+
+impl Drop for Bridge {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn set_status(status: &Mutex<BridgeStatus>, name: &str, new: BridgeStatus) {
+    let mut current = status.lock().unwrap();
+    if *current != new {
+        match &new {
+            BridgeStatus::Connecting => {}
+            BridgeStatus::Connected(device) => {
+                log(&format!("{} bridge connected to {}", name, device))
+            }
+            BridgeStatus::Disconnected(reason) => {
+                log(&format!("{} bridge disconnected: {}", name, reason))
+            }
+        }
+        *current = new;
+    }
+}
+
+/// Pass packets between the link and one backend connection at a time until
+/// the link fails, returning why, or until the bridge is stopped (`None`).
+fn serve(listener: &TcpListener, mut link: Box<dyn Link>, stop: &AtomicBool) -> Option<String> {
+    let _ = listener.set_nonblocking(true);
+    let mut client: Option<(TcpStream, FrameDecoder)> = None;
+    let mut buf = [0u8; 1024];
+    while !stop.load(Ordering::SeqCst) {
+        // A new backend connection replaces the old one (after a restart)
+        if let Ok((stream, _)) = listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(POLL_INTERVAL));
+            let _ = stream.set_nodelay(true);
+            client = Some((stream, FrameDecoder::default()));
+        }
+
+        if let Some((stream, decoder)) = client.as_mut() {
+            match stream.read(&mut buf) {
+                Ok(0) => client = None,
+                Ok(read) => {
+                    decoder.push(&buf[..read]);
+                    while let Some(packet) = decoder.next_packet() {
+                        if let Err(e) = link.send(&packet) {
+                            return Some(e);
+                        }
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(_) => client = None,
+            }
+        } else {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        match link.recv(POLL_INTERVAL) {
+            Ok(Some(packet)) => {
+                if let Some((stream, _)) = client.as_mut() {
+                    if stream.write_all(&encode_frame(&packet)).is_err() {
+                        client = None;
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => return Some(e),
+        }
+    }
+    None
+}
+
+/// Frame a packet for the stream protocol: start bytes, big-endian length,
+/// payload.
+pub fn encode_frame(packet: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(packet.len() + 4);
+    frame.extend_from_slice(&FRAME_START);
+    frame.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+    frame.extend_from_slice(packet);
+    frame
+}
+
+/// Splits a byte stream into packets. Bytes outside frames (such as debug
+/// text a node prints on its serial port) and frames with an impossible
+/// length are skipped.
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+}
+
+impl FrameDecoder {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn next_packet(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let start = self.buf.windows(2).position(|w| w == FRAME_START);
+            match start {
+                Some(start) => {
+                    self.buf.drain(..start);
+                }
+                None => {
+                    // Keep a trailing first start byte for the next push
+                    let keep = usize::from(self.buf.last() == Some(&FRAME_START[0]));
+                    self.buf.drain(..self.buf.len() - keep);
+                    return None;
+                }
+            }
+            if self.buf.len() < 4 {
+                return None;
+            }
+            let len = u16::from_be_bytes([self.buf[2], self.buf[3]]) as usize;
+            if len > MAX_PACKET {
+                // Not a real frame; look for the next start after this one
+                self.buf.drain(..1);
+                continue;
+            }
+            if self.buf.len() < 4 + len {
+                return None;
+            }
+            let packet = self.buf[4..4 + len].to_vec();
+            self.buf.drain(..4 + len);
+            return Some(packet);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_round_trip_across_pushes() {
+        let mut stream = encode_frame(b"first");
+        stream.extend(encode_frame(b"second"));
+        let mut decoder = FrameDecoder::default();
+        decoder.push(&stream[..7]);
+        assert_eq!(decoder.next_packet(), None);
+        decoder.push(&stream[7..]);
+        assert_eq!(decoder.next_packet(), Some(b"first".to_vec()));
+        assert_eq!(decoder.next_packet(), Some(b"second".to_vec()));
+        assert_eq!(decoder.next_packet(), None);
+    }
+
+    #[test]
+    fn test_decoder_skips_noise_and_bad_lengths() {
+        let mut decoder = FrameDecoder::default();
+        decoder.push(b"INFO | booting\r\n");
+        decoder.push(&[0x94, 0xC3, 0xFF, 0xFF]);
+        decoder.push(&encode_frame(&[1, 2, 3]));
+        assert_eq!(decoder.next_packet(), Some(vec![1, 2, 3]));
+
+        // A start byte split across pushes still starts a frame
+        let frame = encode_frame(&[9]);
+        decoder.push(b"noise");
+        decoder.push(&frame[..1]);
+        assert_eq!(decoder.next_packet(), None);
+        decoder.push(&frame[1..]);
+        assert_eq!(decoder.next_packet(), Some(vec![9]));
+    }
+}
@@ -0,0 +1,195 @@
+//! Meshtastic channel share links (`https://meshtastic.org/e/#…`). Links
+//! reach the app as a launch argument (the `meshtastic:` URL scheme it
+//! registers, or "Open with"), forwarded from a second launch, or pasted
+//! into Settings. Opening one previews the channels it carries, asks the
+//! user, then adds them to free slots on the connected node through the
+//! backend's v1 API.
+
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::api::{self, AddedChannels, BackendApi};
+use crate::config::Config;
+use crate::{audit, log};
+
+/// How long a link opened at launch waits for the backend to come up.
+const BACKEND_WAIT: Duration = Duration::from_secs(60);
+
+/// Normalize a channel link to `https://meshtastic.org/e/#<data>`. Accepts
+/// http(s) links with or without `www.` and the `meshtastic://` form the URL
+/// scheme delivers, including the `?add=true` variant. Anything else is
+/// `None`.
+pub fn parse(link: &str) -> Option<String> {
+    let link = link.trim();
+    let (scheme, rest) = link.split_once("://")?;
+    let path = match scheme.to_ascii_lowercase().as_str() {
+        "https" | "http" => rest
+            .strip_prefix("www.")
+            .unwrap_or(rest)
+            .strip_prefix("meshtastic.org/")?,
+        "meshtastic" => rest.strip_prefix("meshtastic.org/").unwrap_or(rest),
+        _ => return None,
+    };
+    let (page, data) = path.split_once('#')?;
+    let page_ok = page == "e" || page.starts_with("e/") || page.starts_with("e?");
+    let data_ok = !data.is_empty()
+        && data
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '/' | '='));
+    (page_ok && data_ok).then(|| format!("https://meshtastic.org/e/#{}", data))
+}
+
+/// The first channel link among command-line arguments.
+pub fn from_args(args: &[String]) -> Option<String> {
+    args.iter().skip(1).find_map(|arg| parse(arg))
+}
+
+/// Preview the link's channels and, once the user agrees, add them. Runs in
+/// the background; every outcome is reported with a dialog.
+pub fn open<R: Runtime>(app: &AppHandle<R>, link: String) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let config = Config::load().unwrap_or_default();
+        let Some(backend) = BackendApi::from_config(&config) else {
+            notify(
+                &app,
+                MessageDialogKind::Warning,
+                "Adding channels from links needs an API token. Create one in the web UI \
+                 and enter it in MeshMonitor Desktop settings, then open the link again.",
+            );
+            return;
+        };
+
+        // A link that launched the app beats the backend to it
+        let deadline = Instant::now() + BACKEND_WAIT;
+        while api::health(config.web_port).is_err() {
+            if Instant::now() >= deadline {
+                notify(
+                    &app,
+                    MessageDialogKind::Error,
+                    "MeshMonitor isn't running, so the channel can't be added.",
+                );
+                return;
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+
+        match backend.add_channel(&link, true) {
+            Ok(preview) if preview.added.is_empty() => notify(
+                &app,
+                MessageDialogKind::Info,
+                "Your node already has the channels in this link.",
+            ),
+            Ok(preview) => confirm(&app, backend, link, &preview),
+            Err(e) => notify(
+                &app,
+                MessageDialogKind::Error,
+                &format!("Couldn't read the channel link: {}", e),
+            ),
+        }
+    });
+}
+
+fn confirm<R: Runtime>(
+    app: &AppHandle<R>,
+    backend: BackendApi,
+    link: String,
+    preview: &AddedChannels,
+) {
+    let mut message = format!("Add to your node:\n\n{}", describe(preview));
+    if preview.skipped > 0 {
+        message.push_str(&format!(
+            "\n\n{} channel(s) in the link are already on the node.",
+            preview.skipped
+        ));
+    }
+    message.push_str("\n\nOnly add channels from people you trust.");
+
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title("Add Meshtastic Channel")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Add Channel".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |add| {
+            if !add {
+                return;
+            }
+            std::thread::spawn(move || match backend.add_channel(&link, false) {
+                Ok(result) => {
+                    let added = describe(&result);
+                    audit::record(audit::Source::Dialog, "channel_added", &added);
+                    log(&format!("Added channels from link: {}", added));
+                    notify(
+                        &handle,
+                        MessageDialogKind::Info,
+                        &format!("Added to your node:\n\n{}", added),
+                    );
+                }
+                Err(e) => notify(
+                    &handle,
+                    MessageDialogKind::Error,
+                    &format!("Failed to add the channel: {}", e),
+                ),
+            });
+        });
+}
+
+/// One line per channel, e.g. "• Friends (slot 1)".
+fn describe(channels: &AddedChannels) -> String {
+    channels
+        .added
+        .iter()
+        .map(|channel| {
+            let name = if channel.name.is_empty() {
+                "(unnamed)"
+            } else {
+                &channel.name
+            };
+            format!("• {} (slot {})", name, channel.index)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn notify<R: Runtime>(app: &AppHandle<R>, kind: MessageDialogKind, message: &str) {
+    app.dialog()
+        .message(message)
+        .title("Add Meshtastic Channel")
+        .kind(kind)
+        .show(|_| {});
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channel_links() {
+        let canonical = Some("https://meshtastic.org/e/#CgcSAQE6AggN".to_string());
+        assert_eq!(parse("https://meshtastic.org/e/#CgcSAQE6AggN"), canonical);
+        assert_eq!(
+            parse(" http://www.meshtastic.org/e/#CgcSAQE6AggN\n"),
+            canonical
+        );
+        assert_eq!(
+            parse("https://meshtastic.org/e/?add=true#CgcSAQE6AggN"),
+            canonical
+        );
+        assert_eq!(
+            parse("meshtastic://meshtastic.org/e/#CgcSAQE6AggN"),
+            canonical
+        );
+        assert_eq!(parse("meshtastic://e/#CgcSAQE6AggN"), canonical);
+
+        assert_eq!(parse("https://meshtastic.org/e/#"), None);
+        assert_eq!(parse("https://meshtastic.org/docs/#CgcSAQE6AggN"), None);
+        assert_eq!(parse("https://example.com/e/#CgcSAQE6AggN"), None);
+        assert_eq!(parse("https://meshtastic.org/e/#<script>"), None);
+        assert_eq!(parse("--minimized"), None);
+    }
+}
@@ -0,0 +1,197 @@
+//! Channel QR codes received as pictures (a screenshot from another
+//! messenger, a saved image): the QR code is read from a PNG file or the
+//! clipboard and the link inside it goes through the same confirm-and-add
+//! flow as a clicked channel link.
+
+use std::path::Path;
+use std::process::Command;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::channel_link;
+use crate::qr_decode::{self, GrayImage};
+
+/// Ask for an image file and import the channel QR code in it.
+pub fn import_from_file<R: Runtime>(app: &AppHandle<R>) {
+    let handle = app.clone();
+    app.dialog()
+        .file()
+        .set_title("Choose a Channel QR Code Image")
+        .add_filter("PNG images", &["png"])
+        .pick_file(move |picked| {
+            let Some(path) = picked.and_then(|p| p.into_path().ok()) else {
+                return;
+            };
+            let result = std::fs::read(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+            import(&handle, result);
+        });
+}
+
+/// Import the channel QR code in the image on the clipboard.
+pub fn import_from_clipboard<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || import(&app, clipboard_png()));
+}
+
+fn import<R: Runtime>(app: &AppHandle<R>, png: Result<Vec<u8>, String>) {
+    let text = png
+        .and_then(|bytes| decode_png(&bytes))
+        .and_then(|image| qr_decode::decode(&image));
+    let result = text.and_then(|text| {
+        channel_link::parse(&text)
+            .ok_or_else(|| format!("The QR code isn't a Meshtastic channel link:\n\n{}", text))
+    });
+    match result {
+        Ok(link) => channel_link::open(app, link),
+        Err(e) => {
+            app.dialog()
+                .message(e)
+                .title("Add Meshtastic Channel")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+        }
+    }
+}
+
+/// Decode a PNG to grayscale, compositing any transparency onto white.
+fn decode_png(bytes: &[u8]) -> Result<GrayImage, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("Not a readable PNG image: {}", e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut buf)
+        .map_err(|e| format!("Not a readable PNG image: {}", e))?;
+    let channels = frame.color_type.samples();
+    let luma = |px: &[u8]| -> u8 {
+        let (value, alpha) = match px {
+            [g] => (*g as u32, 255),
+            [g, a] => (*g as u32, *a as u32),
+            [r, g, b] => (
+                (*r as u32 * 299 + *g as u32 * 587 + *b as u32 * 114) / 1000,
+                255,
+            ),
+            [r, g, b, a, ..] => (
+                (*r as u32 * 299 + *g as u32 * 587 + *b as u32 * 114) / 1000,
+                *a as u32,
+            ),
+            _ => (255, 255),
+        };
+        ((value * alpha + 255 * (255 - alpha)) / 255) as u8
+    };
+    let pixels = buf[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(luma)
+        .collect();
+    Ok(GrayImage {
+        width: frame.width as usize,
+        height: frame.height as usize,
+        pixels,
+    })
+}
+
+/// The clipboard image as PNG bytes, via the platform's own tools.
+fn clipboard_png() -> Result<Vec<u8>, String> {
+    let missing = || "There is no image on the clipboard.".to_string();
+    let temp = crate::config::get_data_path()?.join("clipboard-qr.png");
+    let _ = std::fs::remove_file(&temp);
+
+    let output = clipboard_command(&temp)
+        .output()
+        .map_err(|e| format!("Couldn't read the clipboard: {}", e))?;
+    if !output.status.success() {
+        return Err(missing());
+    }
+    // Windows and macOS write the image to `temp`; Linux tools print it
+    let bytes = if output.stdout.is_empty() {
+        let bytes = std::fs::read(&temp).map_err(|_| missing())?;
+        let _ = std::fs::remove_file(&temp);
+        bytes
+    } else {
+        output.stdout
+    };
+    if bytes.is_empty() {
+        Err(missing())
+    } else {
+        Ok(bytes)
+    }
+}
+
+#[cfg(windows)]
+fn clipboard_command(temp: &Path) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; \
+         $image = [System.Windows.Forms.Clipboard]::GetImage(); \
+         if ($image -eq $null) {{ exit 1 }}; \
+         $image.Save('{}', [System.Drawing.Imaging.ImageFormat]::Png)",
+        temp.display().to_string().replace('\'', "''")
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-STA", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn clipboard_command(temp: &Path) -> Command {
+    let path = temp
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    let mut cmd = Command::new("osascript");
+    cmd.args([
+        "-e",
+        &format!(
+            "set f to open for access POSIX file \"{}\" with write permission",
+            path
+        ),
+        "-e",
+        "write (the clipboard as «class PNGf») to f",
+        "-e",
+        "close access f",
+    ]);
+    cmd
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn clipboard_command(_temp: &Path) -> Command {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut cmd = Command::new("wl-paste");
+        cmd.args(["--no-newline", "--type", "image/png"]);
+        cmd
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard", "-target", "image/png", "-out"]);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_png_composites_alpha() {
+        // 2x1 RGBA: opaque black, fully transparent black
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[0, 0, 0, 255, 0, 0, 0, 0])
+                .unwrap();
+        }
+        let image = decode_png(&bytes).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![0, 255]);
+    }
+}
@@ -0,0 +1,217 @@
+//! Offers to open what's copied, for hopping between radio logs and the web
+//! UI. With `Config::clipboard_watcher` on, the clipboard's text is read
+//! every `POLL_INTERVAL` through the platform's own tools; when it changes
+//! to a node ID (`!a1b2c3d4`) or a latitude/longitude pair
+//! (`51.5072, -0.1276`), a notification offers to open that node's messages
+//! or center the map there. What was on the clipboard when the watcher
+//! started is never offered, and the text itself is never logged.
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::config::Config;
+use crate::{log, notifications};
+
+/// How often the clipboard is read.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Longer text is a document, not an ID or a position.
+const MAX_TEXT: usize = 200;
+
+/// Guards against spawning a second watcher thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Something worth offering to open.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Found {
+    Node(String),
+    Position { latitude: f64, longitude: f64 },
+}
+
+/// Spawn the watcher. It idles while `clipboard_watcher` is off. Safe to
+/// call more than once.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        // Clipboard text at the last read; None until read since turned on
+        let mut last: Option<String> = None;
+        let mut warned = false;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let config = Config::load().unwrap_or_default();
+            if !config.clipboard_watcher {
+                last = None;
+                continue;
+            }
+            let text = match clipboard_text() {
+                Ok(text) => text,
+                Err(e) => {
+                    if !warned {
+                        log(&format!(
+                            "Clipboard watcher can't read the clipboard: {}",
+                            e
+                        ));
+                        warned = true;
+                    }
+                    continue;
+                }
+            };
+            let changed = last.as_ref().is_some_and(|last| *last != text);
+            last = Some(text);
+            if !changed {
+                continue;
+            }
+            match last.as_deref().and_then(recognize) {
+                Some(Found::Node(node_id)) => notifications::notify(
+                    &app,
+                    "Node ID copied",
+                    &format!("Click to open {} in MeshMonitor", node_id),
+                    Some(notifications::dm_url(config.web_port, &node_id)),
+                ),
+                Some(Found::Position {
+                    latitude,
+                    longitude,
+                }) => notifications::notify(
+                    &app,
+                    "Coordinates copied",
+                    &format!(
+                        "Click to center the MeshMonitor map on {:.5}, {:.5}",
+                        latitude, longitude
+                    ),
+                    Some(notifications::map_url(config.web_port, latitude, longitude)),
+                ),
+                None => {}
+            }
+        }
+    });
+}
+
+/// The node ID or position `text` is, if any. A short text mentioning a
+/// node ID, such as a log line, counts too.
+pub fn recognize(text: &str) -> Option<Found> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > MAX_TEXT {
+        return None;
+    }
+    if let Some(position) = position(text) {
+        return Some(position);
+    }
+    text.split(|c: char| c.is_whitespace() || ",;:()[]<>\"'".contains(c))
+        .find_map(node_id)
+        .map(Found::Node)
+}
+
+/// `!` and eight hex digits, as Meshtastic writes node IDs.
+fn node_id(word: &str) -> Option<String> {
+    let hex = word.strip_prefix('!')?;
+    (hex.len() == 8 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| format!("!{}", hex.to_ascii_lowercase()))
+}
+
+/// Two decimal degrees, latitude first, separated by a comma or spaces and
+/// optionally in brackets. Whole numbers are too likely to be something
+/// else, so both need a decimal point.
+fn position(text: &str) -> Option<Found> {
+    let inner = text.trim_matches(|c| matches!(c, '(' | ')' | '[' | ']'));
+    let parts: Vec<&str> = inner
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+    let [latitude, longitude] = parts[..] else {
+        return None;
+    };
+    if !latitude.contains('.') || !longitude.contains('.') {
+        return None;
+    }
+    let (latitude, longitude) = (latitude.parse().ok()?, longitude.parse().ok()?);
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)).then_some(
+        Found::Position {
+            latitude,
+            longitude,
+        },
+    )
+}
+
+/// The clipboard's text, via the platform's own tools.
+fn clipboard_text() -> Result<String, String> {
+    let output = clipboard_command().output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        // Empty, or holding something other than text
+        return Ok(String::new());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(windows)]
+fn clipboard_command() -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let mut cmd = Command::new("powershell");
+    cmd.args([
+        "-NoProfile",
+        "-NonInteractive",
+        "-Command",
+        "Get-Clipboard -Raw",
+    ])
+    .creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn clipboard_command() -> Command {
+    Command::new("pbpaste")
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn clipboard_command() -> Command {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut cmd = Command::new("wl-paste");
+        cmd.args(["--no-newline", "--type", "text/plain"]);
+        cmd
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard", "-out"]);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognize() {
+        assert_eq!(
+            recognize(" !A1B2C3D4\n"),
+            Some(Found::Node("!a1b2c3d4".to_string()))
+        );
+        assert_eq!(
+            recognize("12:04:51 RX from !0badcafe: snr=6.25"),
+            Some(Found::Node("!0badcafe".to_string()))
+        );
+        assert_eq!(
+            recognize("(51.5072, -0.1276)"),
+            Some(Found::Position {
+                latitude: 51.5072,
+                longitude: -0.1276
+            })
+        );
+        assert_eq!(
+            recognize("-33.8688 151.2093"),
+            Some(Found::Position {
+                latitude: -33.8688,
+                longitude: 151.2093
+            })
+        );
+        assert_eq!(recognize("12, 34"), None);
+        assert_eq!(recognize("95.0, 10.0"), None);
+        assert_eq!(recognize("!a1b2c3"), None);
+        assert_eq!(recognize(&"!a1b2c3d4 ".repeat(30)), None);
+    }
+}
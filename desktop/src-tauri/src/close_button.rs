@@ -0,0 +1,129 @@
+//! The web UI window's close button (`Config::close_action`). By default it
+//! hides the window and MeshMonitor keeps running in the tray, which not
+//! everyone expects; it can quit and stop the backend instead, or ask each
+//! time. When asked, the answer can be remembered, which saves it as the
+//! close action.
+
+use tauri::{AppHandle, Manager, Runtime, Window};
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
+};
+
+use crate::config::{CloseAction, Config};
+use crate::{audit, log};
+
+const HIDE: &str = "Hide to Tray";
+const QUIT: &str = "Quit";
+const CANCEL: &str = "Cancel";
+
+/// Handle a close request for `window`, whose closing has been prevented.
+pub fn close_requested<R: Runtime>(window: &Window<R>) {
+    match Config::load().unwrap_or_default().close_action {
+        CloseAction::Hide => {
+            let _ = window.hide();
+        }
+        CloseAction::Quit => quit(window.app_handle(), audit::Source::Command),
+        CloseAction::Ask => ask(window),
+    }
+}
+
+fn ask<R: Runtime>(window: &Window<R>) {
+    let handle = window.clone();
+    window
+        .dialog()
+        .message(
+            "Hide the window and keep MeshMonitor running in the tray, or quit and stop the \
+             MeshMonitor server? Monitoring and logging stop until MeshMonitor is started again.",
+        )
+        .title("Close MeshMonitor?")
+        .kind(MessageDialogKind::Info)
+        .parent(window)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            HIDE.to_string(),
+            QUIT.to_string(),
+            CANCEL.to_string(),
+        ))
+        .show_with_result(move |result| {
+            let Some(action) = chosen(&result) else {
+                return;
+            };
+            if action == CloseAction::Hide {
+                let _ = handle.hide();
+            }
+            offer_to_remember(handle, action);
+        });
+}
+
+/// The action picked in the dialog; `None` when it was cancelled.
+fn chosen(result: &MessageDialogResult) -> Option<CloseAction> {
+    match result {
+        MessageDialogResult::Yes => Some(CloseAction::Hide),
+        MessageDialogResult::No => Some(CloseAction::Quit),
+        MessageDialogResult::Custom(label) if label == HIDE => Some(CloseAction::Hide),
+        MessageDialogResult::Custom(label) if label == QUIT => Some(CloseAction::Quit),
+        _ => None,
+    }
+}
+
+/// Ask whether `action` should be the close button's from now on, then
+/// carry out a quit.
+fn offer_to_remember<R: Runtime>(window: Window<R>, action: CloseAction) {
+    let question = match action {
+        CloseAction::Quit => "Always quit MeshMonitor when its window is closed?",
+        _ => "Always keep MeshMonitor running in the tray when its window is closed?",
+    };
+    let app = window.app_handle().clone();
+    window
+        .dialog()
+        .message(format!(
+            "{} You can change this in Settings under Close Button.",
+            question
+        ))
+        .title("Remember This Choice?")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Always".to_string(),
+            "Ask Next Time".to_string(),
+        ))
+        .show(move |remember| {
+            if remember {
+                save(action);
+            }
+            if action == CloseAction::Quit {
+                quit(&app, audit::Source::Dialog);
+            }
+        });
+}
+
+fn save(action: CloseAction) {
+    let previous = Config::load().unwrap_or_default();
+    let mut config = previous.clone();
+    config.close_action = action;
+    match config.save() {
+        Ok(()) => audit::record_config_change(audit::Source::Dialog, &previous, &config),
+        Err(e) => log(&format!("Failed to save the close button choice: {}", e)),
+    }
+}
+
+fn quit<R: Runtime>(app: &AppHandle<R>, source: audit::Source) {
+    audit::record(source, "quit", "backend stopped");
+    crate::quit(app, false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialog_buttons_map_to_actions() {
+        let custom = |label: &str| MessageDialogResult::Custom(label.to_string());
+        assert_eq!(chosen(&custom(HIDE)), Some(CloseAction::Hide));
+        assert_eq!(chosen(&custom(QUIT)), Some(CloseAction::Quit));
+        assert_eq!(chosen(&custom(CANCEL)), None);
+        assert_eq!(chosen(&MessageDialogResult::Cancel), None);
+        assert_eq!(
+            serde_json::from_str::<CloseAction>("\"ask\"").unwrap(),
+            CloseAction::Ask
+        );
+    }
+}
@@ -1,6 +1,186 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::polling::PollingPreset;
+use crate::{keyring, log};
+
+/// Fingerprint of the content this process last wrote to config.json, so the
+/// config watcher can tell our own saves apart from external edits.
+static LAST_WRITTEN: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Keyring account the session secret is kept under.
+const SESSION_SECRET_ACCOUNT: &str = "session_secret";
+
+/// How often the keyring is asked for the session secret before the
+/// backend's start fails, and how long apart.
+const KEYRING_ATTEMPTS: u32 = 10;
+const KEYRING_RETRY: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// How the backend reaches the Meshtastic node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionType {
+    #[default]
+    Tcp,
+    /// A Bluetooth LE node, bridged to a loopback TCP port.
+    Ble,
+    /// A node on a USB serial port, bridged to a loopback TCP port.
+    Serial,
+    /// Mesh traffic multicast on the LAN by nodes with UDP enabled, received
+    /// only and bridged to a loopback TCP port.
+    Udp,
+}
+
+/// File format of exported messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// JSON Lines: one message object per line.
+    Json,
+}
+
+/// What closing the web UI window does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseAction {
+    /// Hide the window; MeshMonitor keeps running in the tray.
+    #[default]
+    Hide,
+    /// Quit the app and stop the backend.
+    Quit,
+    /// Ask each time, offering to remember the answer.
+    Ask,
+}
+
+/// Another MeshMonitor instance served by the reverse proxy under
+/// `/node/<name>/`. It must run with `BASE_URL=/node/<name>` so the links in
+/// its pages point back through the proxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyInstance {
+    pub name: String,
+    /// Where the instance listens, e.g. `http://192.168.1.20:8080`
+    pub url: String,
+}
+
+/// Another MeshMonitor server whose state the tray shows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteInstance {
+    pub name: String,
+    /// Its web UI, including any `BASE_URL` path, e.g. `https://club.example.org/meshmonitor`
+    pub url: String,
+}
+
+/// An address mesh events are posted to as JSON. `templates` gives the
+/// payload for each event (`new_node`, `direct_message`, `channel_message`),
+/// with placeholders such as `{node_name}` filled in; only events with a
+/// template are sent. Without templates, every event is sent in the default
+/// format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub templates: BTreeMap<String, serde_json::Value>,
+}
+
+/// A phone or mailbox alerts are pushed to. Its token or password, if it
+/// needs one, is kept in the OS keyring rather than here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushTarget {
+    pub id: String,
+    #[serde(flatten)]
+    pub service: PushService,
+    /// Events pushed: `new_node`, `direct_message`, `channel_message` and
+    /// `emergency`
+    #[serde(default = "default_push_events")]
+    pub events: Vec<String>,
+}
+
+/// Where a push target's alerts go.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "service", rename_all = "snake_case")]
+pub enum PushService {
+    /// An ntfy topic; the keyring holds its access token, if it's protected
+    Ntfy {
+        #[serde(default = "default_ntfy_server")]
+        server: String,
+        topic: String,
+    },
+    /// A Pushover user or group; the keyring holds the application token
+    Pushover { user_key: String },
+    /// Mail through an SMTP server; the keyring holds the password
+    Email {
+        smtp_host: String,
+        #[serde(default = "default_smtp_port")]
+        smtp_port: u16,
+        #[serde(default)]
+        username: Option<String>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+/// A club's own tile server or a paid provider, served through the tile
+/// cache at `/<id>/{z}/{x}/{y}.png`. Its API key, if it needs one, is kept in
+/// the OS keyring rather than here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileServer {
+    pub id: String,
+    pub name: String,
+    /// Upstream URL with `{z}`, `{x}` and `{y}`, and `{apikey}` where the key goes
+    pub url: String,
+    #[serde(default = "default_tile_server_max_zoom")]
+    pub max_zoom: u8,
+    #[serde(default)]
+    pub attribution: String,
+}
+
+/// A helper program started and stopped along with the backend, such as an
+/// MQTT broker or a tile server. Its output goes to `logs/sidecar-<name>.log`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sidecar {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+}
+
+/// When a sidecar that exited on its own is started again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Never,
+    /// Only after it exited with an error
+    #[default]
+    OnFailure,
+    Always,
+}
+
+/// A named set of node and web UI settings, such as one per mesh, that
+/// `Config::switch_profile` swaps in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub meshtastic_ip: String,
+    #[serde(default = "default_meshtastic_port")]
+    pub meshtastic_port: u16,
+    #[serde(default)]
+    pub meshtastic_alt_ips: Vec<String>,
+    pub web_port: u16,
+    /// Database the backend uses; meshmonitor.db in the data directory when unset
+    #[serde(default)]
+    pub database_path: Option<String>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -13,12 +193,23 @@ pub struct Config {
     /// Legacy: Meshtastic TCP port (default: 4403). See `meshtastic_ip` note.
     #[serde(default = "default_meshtastic_port")]
     pub meshtastic_port: u16,
+    /// Other addresses of the same node (e.g. its Wi-Fi address), tried in
+    /// order after `meshtastic_ip`; the first that answers is used
+    #[serde(default)]
+    pub meshtastic_alt_ips: Vec<String>,
     /// Web UI port (default: 8080)
     pub web_port: u16,
-    /// Autostart on user login. Persisted from the settings UI but not yet
-    /// wired to a platform implementation (no autostart plugin is registered).
+    /// Ports the web UI may move to when another program holds `web_port`;
+    /// the one picked is saved as `web_port`. No fallback unless both are set
+    #[serde(default)]
+    pub web_port_range_start: Option<u16>,
+    #[serde(default)]
+    pub web_port_range_end: Option<u16>,
+    /// Autostart on user login
     pub auto_start: bool,
-    /// Session secret for authentication
+    /// Session secret for authentication. Empty when it is kept in the OS
+    /// keyring, see `store_session_secret`
+    #[serde(default)]
     pub session_secret: String,
     /// First run completed
     pub setup_completed: bool,
@@ -32,6 +223,254 @@ pub struct Config {
     /// Localhost is always included automatically
     #[serde(default)]
     pub allowed_origins: Option<String>,
+    /// API token (created in the web UI) the desktop shell uses to read mesh
+    /// data from the backend's `/api/v1` endpoints. Features that need mesh
+    /// data, such as native notifications, stay idle while this is unset.
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// Show a desktop notification when a node never seen before appears
+    #[serde(default)]
+    pub notify_new_nodes: bool,
+    /// Show a desktop notification for incoming direct and channel messages,
+    /// one per conversation
+    #[serde(default)]
+    pub notify_messages: bool,
+    /// Channel indexes whose messages don't raise notifications, muted from
+    /// the tray
+    #[serde(default)]
+    pub muted_channels: Vec<i64>,
+    /// Save a map snapshot centred on the sender of an emergency message
+    #[serde(default)]
+    pub incident_snapshots: bool,
+    /// Words or phrases that make a message an emergency, matched as whole
+    /// words regardless of case
+    #[serde(default = "default_incident_keywords")]
+    pub incident_keywords: Vec<String>,
+    /// Offer to open copied node IDs and coordinates in the web UI
+    #[serde(default)]
+    pub clipboard_watcher: bool,
+    /// Play a soft click for each received packet ("geiger counter" mode)
+    #[serde(default)]
+    pub packet_audio_enabled: bool,
+    /// How often the backend is polled and dropped connections retried,
+    /// picked from the tray
+    #[serde(default)]
+    pub polling_preset: PollingPreset,
+    /// Read incoming direct messages aloud with the OS text-to-speech engine
+    #[serde(default)]
+    pub tts_enabled: bool,
+    /// Channel indexes whose messages are also read aloud when TTS is on
+    #[serde(default)]
+    pub tts_channels: Vec<i64>,
+    /// Start of quiet hours, local time "HH:MM". Spoken and audible alerts
+    /// are suppressed between start and end; unset means no quiet hours.
+    #[serde(default)]
+    pub quiet_hours_start: Option<String>,
+    /// End of quiet hours, local time "HH:MM" (may be earlier than the start
+    /// to span midnight)
+    #[serde(default)]
+    pub quiet_hours_end: Option<String>,
+    /// Leave the backend running when the app quits without an explicit
+    /// choice (e.g. Cmd+Q or logging out), so logging continues
+    #[serde(default)]
+    pub keep_backend_on_quit: bool,
+    /// Ask for confirmation before a quit that stops the backend
+    #[serde(default)]
+    pub confirm_quit: bool,
+    /// What the web UI window's close button does
+    #[serde(default)]
+    pub close_action: CloseAction,
+    /// Run as a regular windowed app with no tray icon: the control window
+    /// is the main window and closing it quits and stops the backend
+    #[serde(default)]
+    pub no_tray: bool,
+    /// Path to a file holding the session secret, read each time the backend
+    /// starts (like Docker's `_FILE` variables). Overrides `session_secret`, so
+    /// a secrets manager can own the value instead of config.json.
+    #[serde(default)]
+    pub session_secret_file: Option<String>,
+    /// Require OS authentication (Windows Hello, Touch ID or polkit) before
+    /// opening Settings or replacing the configuration
+    #[serde(default)]
+    pub require_os_auth: bool,
+    /// Open the web UI in a native MeshMonitor window (with a status strip)
+    /// instead of the default browser
+    #[serde(default)]
+    pub open_in_window: bool,
+    /// Open the web UI at its login page, instead of logged in as its admin
+    #[serde(default)]
+    pub no_auto_login: bool,
+    /// How the backend reaches the Meshtastic node: `tcp` (it connects itself,
+    /// to `meshtastic_ip` or the sources set up in the web UI), or through a
+    /// local bridge to the Bluetooth node `ble_device` (`ble`), the USB node on
+    /// `serial_port` (`serial`) or the traffic nodes multicast on the LAN
+    /// (`udp`)
+    #[serde(default)]
+    pub connection_type: ConnectionType,
+    /// Bluetooth address (`AA:BB:CC:DD:EE:FF`) or advertised name of the node
+    /// used with `connection_type: ble`
+    #[serde(default)]
+    pub ble_device: String,
+    /// Serial port of the node used with `connection_type: serial` (`/dev/ttyUSB0`,
+    /// `COM3`...); empty uses the first USB serial device found
+    #[serde(default)]
+    pub serial_port: String,
+    /// Baud rate of `serial_port`, as set in the node's serial module
+    #[serde(default = "default_serial_baud")]
+    pub serial_baud: u32,
+    /// MAC address of the computer a network node hangs off, for Wake-on-LAN
+    #[serde(default)]
+    pub wake_on_lan_mac: Option<String>,
+    /// Wake that computer when the node can't be reached at login or the
+    /// backend loses its link
+    #[serde(default)]
+    pub wake_on_lan_auto: bool,
+    /// ID of the node the backend should be connected to (`!1a2b3c4d`), pinned
+    /// at the first connection; another node raises an alert
+    #[serde(default)]
+    pub expected_node_id: Option<String>,
+    /// Locale for dates and numbers in the tray, notifications and dialogs
+    /// (`de-DE`); `None` follows the OS
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Public port of the reverse proxy that serves this backend at `/` and each
+    /// of `proxy_instances` at `/node/<name>/`; `None` turns it off
+    #[serde(default)]
+    pub proxy_port: Option<u16>,
+    /// MeshMonitor instances the reverse proxy routes to besides this backend
+    #[serde(default)]
+    pub proxy_instances: Vec<ProxyInstance>,
+    /// Other MeshMonitor servers listed with their state in the tray
+    #[serde(default)]
+    pub remote_instances: Vec<RemoteInstance>,
+    /// Folder new messages are exported to on a schedule; `None` turns exporting off
+    #[serde(default)]
+    pub export_dir: Option<String>,
+    /// File format of exported messages
+    #[serde(default)]
+    pub export_format: ExportFormat,
+    /// Minutes between message exports
+    #[serde(default = "default_export_interval_minutes")]
+    pub export_interval_minutes: u32,
+    /// Save each day's activity report to the reports folder after midnight
+    #[serde(default)]
+    pub daily_activity_report: bool,
+    /// Hours between backups of the node's configuration into the data
+    /// folder; 0 turns them off
+    #[serde(default = "default_device_backup_interval_hours")]
+    pub device_backup_interval_hours: u32,
+    /// Minutes without keyboard or mouse input, on AC power, that scheduled
+    /// backups and exports wait for; 0 runs them when due
+    #[serde(default)]
+    pub maintenance_idle_minutes: u32,
+    /// Kiosk display: the web UI fills the screen from launch and is kept there,
+    /// with no tray or control window
+    #[serde(default)]
+    pub kiosk: bool,
+    /// Start the backend with local logins turned off, so its web UI only shows
+    /// what anonymous users may see
+    #[serde(default)]
+    pub read_only: bool,
+    /// Privacy mode: nothing is sent off this computer (firmware and installer
+    /// downloads, map tiles, webhooks and the backend's version check)
+    #[serde(default)]
+    pub privacy_mode: bool,
+    /// Settings can't be opened or saved from the app, even with OS
+    /// authentication; only editing config.json changes them
+    #[serde(default)]
+    pub settings_locked: bool,
+    /// Keep restarting a backend that keeps exiting instead of giving up
+    /// after five attempts
+    #[serde(default)]
+    pub crash_recovery: bool,
+    /// Don't run a backend; monitor the one already answering on `web_port`,
+    /// such as the Docker image
+    #[serde(default)]
+    pub external_backend: bool,
+    /// Path the watchdog checks the backend's health at, for deployments that
+    /// moved the route
+    #[serde(default = "default_health_check_path")]
+    pub health_check_path: String,
+    /// Seconds the watchdog waits for a health check answer
+    #[serde(default = "default_health_check_timeout_secs")]
+    pub health_check_timeout_secs: u32,
+    /// Text a healthy answer must contain; `None` accepts any 2xx answer,
+    /// and a standard health report only when its status is "ok"
+    #[serde(default)]
+    pub health_check_expect: Option<String>,
+    /// Seconds the backend gets to shut down after being asked over the
+    /// control channel or HTTP, before it is sent SIGTERM
+    #[serde(default = "default_stop_quiesce_secs")]
+    pub stop_quiesce_secs: u32,
+    /// Seconds the backend gets after SIGTERM before it is killed
+    #[serde(default = "default_stop_sigterm_secs")]
+    pub stop_sigterm_secs: u32,
+    /// Meter the backend's outbound HTTP(S) traffic through a loopback proxy
+    #[serde(default)]
+    pub track_bandwidth: bool,
+    /// Monthly data cap in MB; passing it raises a notification, once a month
+    #[serde(default)]
+    pub bandwidth_monthly_cap_mb: Option<u64>,
+    /// Seconds a launch at login waits for the network and node before starting
+    /// the backend anyway; 0 starts it straight away
+    #[serde(default = "default_network_wait_secs")]
+    pub network_wait_secs: u32,
+    /// Command that must succeed before the backend first starts, e.g. one that
+    /// checks a VM or container hosting the node
+    #[serde(default)]
+    pub pre_start_command: Option<String>,
+    /// `http(s)://` or `tcp://` address that must answer before the backend first starts
+    #[serde(default)]
+    pub pre_start_url: Option<String>,
+    /// Times the pre-start hook is tried before the backend starts anyway
+    #[serde(default = "default_pre_start_attempts")]
+    pub pre_start_attempts: u32,
+    /// Seconds between pre-start hook attempts
+    #[serde(default = "default_pre_start_interval_secs")]
+    pub pre_start_interval_secs: u32,
+    /// Pass this app's whole environment to the backend instead of only the
+    /// variables it needs (`backend_env::BASE_ALLOWLIST` and `backend_env_allowlist`)
+    #[serde(default)]
+    pub inherit_backend_env: bool,
+    /// Further variables passed on to the backend; a trailing `*` matches a prefix
+    #[serde(default)]
+    pub backend_env_allowlist: Vec<String>,
+    /// Database file the backend uses instead of meshmonitor.db in the data
+    /// directory, e.g. on a larger drive
+    #[serde(default)]
+    pub database_path: Option<String>,
+    /// Milliseconds the backend waits on a locked database before failing
+    /// with SQLITE_BUSY; raise on slow storage such as an SD card
+    #[serde(default = "default_sqlite_busy_timeout_ms")]
+    pub sqlite_busy_timeout_ms: u32,
+    /// WAL pages written before the backend checkpoints them into the
+    /// database; higher means fewer, longer pauses
+    #[serde(default = "default_sqlite_wal_autocheckpoint")]
+    pub sqlite_wal_autocheckpoint: u32,
+    /// Loopback port of the map tile cache; `None` turns it off
+    #[serde(default)]
+    pub tile_cache_port: Option<u16>,
+    /// Largest size of the map tile cache in MB; the oldest tiles go first
+    #[serde(default = "default_tile_cache_max_mb")]
+    pub tile_cache_max_mb: u32,
+    /// Tile servers the tile cache serves besides the built-in ones
+    #[serde(default)]
+    pub tile_servers: Vec<TileServer>,
+    /// Addresses mesh events are posted to
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// Phones and mailboxes alerts are pushed to
+    #[serde(default)]
+    pub push_targets: Vec<PushTarget>,
+    /// Helper programs supervised alongside the backend
+    #[serde(default)]
+    pub sidecars: Vec<Sidecar>,
+    /// Node and web UI settings to switch between, e.g. one per mesh
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// Profile the node and web UI settings above belong to, if any
+    #[serde(default)]
+    pub active_profile: Option<String>,
 }
 
 impl Default for Config {
@@ -46,56 +485,122 @@ impl Default for Config {
             // never configured. See discussion #2604.
             meshtastic_ip: String::new(),
             meshtastic_port: 4403,
+            meshtastic_alt_ips: Vec::new(),
             web_port: 8080,
+            web_port_range_start: None,
+            web_port_range_end: None,
             auto_start: false,
-            session_secret: generate_secret(),
+            // Created in the keyring by `store_session_secret`, so a default
+            // saved over a config that failed to load can't replace it
+            session_secret: String::new(),
             setup_completed: false,
             enable_virtual_node: false,
             virtual_node_allow_admin: false,
             allowed_origins: None,
+            api_token: None,
+            notify_new_nodes: false,
+            notify_messages: false,
+            muted_channels: Vec::new(),
+            incident_snapshots: false,
+            incident_keywords: default_incident_keywords(),
+            clipboard_watcher: false,
+            packet_audio_enabled: false,
+            polling_preset: PollingPreset::Balanced,
+            tts_enabled: false,
+            tts_channels: Vec::new(),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            keep_backend_on_quit: false,
+            confirm_quit: false,
+            close_action: CloseAction::Hide,
+            no_tray: false,
+            session_secret_file: None,
+            require_os_auth: false,
+            open_in_window: false,
+            no_auto_login: false,
+            connection_type: ConnectionType::Tcp,
+            ble_device: String::new(),
+            serial_port: String::new(),
+            serial_baud: default_serial_baud(),
+            wake_on_lan_mac: None,
+            wake_on_lan_auto: false,
+            expected_node_id: None,
+            locale: None,
+            proxy_port: None,
+            proxy_instances: Vec::new(),
+            remote_instances: Vec::new(),
+            export_dir: None,
+            export_format: ExportFormat::Csv,
+            export_interval_minutes: 60,
+            daily_activity_report: false,
+            device_backup_interval_hours: 24,
+            maintenance_idle_minutes: 0,
+            kiosk: false,
+            read_only: false,
+            privacy_mode: false,
+            settings_locked: false,
+            crash_recovery: false,
+            external_backend: false,
+            health_check_path: default_health_check_path(),
+            health_check_timeout_secs: 3,
+            health_check_expect: None,
+            stop_quiesce_secs: default_stop_quiesce_secs(),
+            stop_sigterm_secs: default_stop_sigterm_secs(),
+            track_bandwidth: false,
+            bandwidth_monthly_cap_mb: None,
+            network_wait_secs: 60,
+            pre_start_command: None,
+            pre_start_url: None,
+            pre_start_attempts: 30,
+            pre_start_interval_secs: 5,
+            inherit_backend_env: false,
+            backend_env_allowlist: Vec::new(),
+            database_path: None,
+            sqlite_busy_timeout_ms: 5000,
+            sqlite_wal_autocheckpoint: 1000,
+            tile_cache_port: None,
+            tile_cache_max_mb: 1024,
+            tile_servers: Vec::new(),
+            webhooks: Vec::new(),
+            push_targets: Vec::new(),
+            sidecars: Vec::new(),
+            profiles: Vec::new(),
+            active_profile: None,
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file, creating default if not exists
+    /// Load configuration from file, creating default if not exists.
+    ///
+    /// A corrupted file is never overwritten here: defaults are returned in
+    /// memory and the file is left for `offer_config_recovery` to restore from
+    /// `config.json.bak` (or reset) at startup.
     pub fn load() -> Result<Self, String> {
         let config_path = get_config_path()?;
 
-        if config_path.exists() {
-            let content = fs::read_to_string(&config_path)
-                .map_err(|e| format!("Failed to read config: {}", e))?;
-
-            // Handle empty or whitespace-only config files
-            let trimmed = content.trim();
-            if trimmed.is_empty() {
-                eprintln!("Config file is empty, creating default configuration");
+        match read_config_file(&config_path)? {
+            ConfigFile::Valid(config) => Ok(*config),
+            ConfigFile::Missing => {
                 let config = Config::default();
                 config.save()?;
-                return Ok(config);
+                Ok(config)
             }
-
-            // Try to parse, fall back to default if corrupted
-            match serde_json::from_str(&content) {
-                Ok(config) => Ok(config),
-                Err(e) => {
-                    eprintln!(
-                        "Config file is corrupted ({}), creating default configuration",
-                        e
-                    );
-                    let config = Config::default();
-                    config.save()?;
-                    Ok(config)
-                }
+            ConfigFile::Corrupted(e) => {
+                eprintln!(
+                    "Config file is corrupted ({}), using default configuration until it is restored",
+                    e
+                );
+                Ok(Config::default())
             }
-        } else {
-            let config = Config::default();
-            config.save()?;
-            Ok(config)
         }
     }
 
-    /// Save configuration to file
+    /// Save configuration to file.
+    ///
+    /// Writes to a temporary file and renames it over config.json so a crash
+    /// mid-write can never leave a truncated file behind. The previous
+    /// version is kept as config.json.bak when it was valid.
     pub fn save(&self) -> Result<(), String> {
         let config_path = get_config_path()?;
 
@@ -108,7 +613,7 @@ impl Config {
         let content = serde_json::to_string_pretty(self)
             .map_err(|e| format!("Failed to serialize config: {}", e))?;
 
-        fs::write(&config_path, content).map_err(|e| format!("Failed to write config: {}", e))
+        write_config_file(&config_path, &content)
     }
 
     /// Check if first-run setup is needed
@@ -116,11 +621,360 @@ impl Config {
         !self.setup_completed
     }
 
+    /// Whether `now` falls inside the configured quiet hours. Returns false
+    /// when either bound is unset or unparseable.
+    pub fn in_quiet_hours(&self, now: chrono::NaiveTime) -> bool {
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|v| chrono::NaiveTime::parse_from_str(v.trim(), "%H:%M").ok())
+        };
+        let (Some(start), Some(end)) =
+            (parse(&self.quiet_hours_start), parse(&self.quiet_hours_end))
+        else {
+            return false;
+        };
+        if start <= end {
+            now >= start && now < end
+        } else {
+            // Spans midnight, e.g. 22:00–07:00
+            now >= start || now < end
+        }
+    }
+
     /// Mark setup as completed
     pub fn complete_setup(&mut self) -> Result<(), String> {
         self.setup_completed = true;
         self.save()
     }
+
+    /// Switch to the profile `name`. The current node and web UI settings are
+    /// kept in the active profile, if any, and replaced by those of `name`.
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), String> {
+        let profile = self
+            .profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+            .ok_or_else(|| format!("There is no profile named \"{}\"", name))?;
+        if let Some(active) = self.active_profile.clone() {
+            let current = Profile {
+                name: active,
+                meshtastic_ip: self.meshtastic_ip.clone(),
+                meshtastic_port: self.meshtastic_port,
+                meshtastic_alt_ips: self.meshtastic_alt_ips.clone(),
+                web_port: self.web_port,
+                database_path: self.database_path.clone(),
+            };
+            if let Some(kept) = self.profiles.iter_mut().find(|p| p.name == current.name) {
+                *kept = current;
+            }
+        }
+        self.meshtastic_ip = profile.meshtastic_ip;
+        self.meshtastic_port = profile.meshtastic_port;
+        self.meshtastic_alt_ips = profile.meshtastic_alt_ips;
+        self.web_port = profile.web_port;
+        self.database_path = profile.database_path;
+        self.active_profile = Some(profile.name);
+        Ok(())
+    }
+
+    /// The session secret to hand the backend: the contents of
+    /// `session_secret_file` when set, otherwise `session_secret`, or the one
+    /// in the OS keyring once it was moved there.
+    pub fn resolve_session_secret(&self) -> Result<String, String> {
+        self.resolve_session_secret_with(&Keyring)
+    }
+
+    fn resolve_session_secret_with(&self, store: &impl SecretStore) -> Result<String, String> {
+        let secret = resolve_secret(&self.session_secret, self.session_secret_file.as_deref())?;
+        if !secret.is_empty() {
+            return Ok(secret);
+        }
+        // Right after login the Secret Service may still be unlocking
+        let mut attempt = 1;
+        loop {
+            match store.get() {
+                Ok(Some(secret)) => return Ok(secret),
+                Ok(None) => {
+                    return Err(
+                        "The session secret is in neither config.json nor the system \
+                                keyring. Restart MeshMonitor to create a new one."
+                            .to_string(),
+                    )
+                }
+                Err(e) if attempt < KEYRING_ATTEMPTS => {
+                    log(&format!("Session secret not readable yet: {}", e));
+                    std::thread::sleep(KEYRING_RETRY);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "Couldn't read the session secret from the system keyring ({}). \
+                         Unlock the keyring, then restart MeshMonitor.",
+                        e
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Where the session secret is kept instead of config.json: the OS keyring,
+/// or a stand-in in tests.
+trait SecretStore {
+    fn get(&self) -> Result<Option<String>, String>;
+    fn set(&self, secret: &str) -> Result<(), String>;
+}
+
+struct Keyring;
+
+impl SecretStore for Keyring {
+    fn get(&self) -> Result<Option<String>, String> {
+        keyring::get(SESSION_SECRET_ACCOUNT)
+    }
+
+    fn set(&self, secret: &str) -> Result<(), String> {
+        keyring::set(SESSION_SECRET_ACCOUNT, secret)
+    }
+}
+
+/// What `store_session_secret` did.
+#[derive(Debug, PartialEq)]
+enum Stored {
+    /// Moved from config.json to the keyring.
+    Moved,
+    /// Created in the keyring, for a new install.
+    Created,
+    /// Nothing to do: the keyring has it already, or config.json is
+    /// corrupted and waits to be recovered.
+    Unchanged,
+}
+
+/// Keep the session secret in the OS keyring rather than config.json:
+/// move one found in config.json, replacing any stored before, or create
+/// one. Where the keyring can't be used, such as Linux without a running
+/// Secret Service, the secret stays in config.json, which only this user
+/// may read, and the reason is logged.
+pub fn store_session_secret() {
+    let stored = get_config_path().and_then(|path| store_session_secret_in(&path, &Keyring));
+    match stored {
+        Ok(Stored::Moved) => log("Moved the session secret to the system keyring"),
+        Ok(Stored::Created) => log("Created a session secret in the system keyring"),
+        Ok(Stored::Unchanged) => {}
+        Err(e) => log(&format!("Keeping the session secret in config.json: {}", e)),
+    }
+}
+
+fn store_session_secret_in(path: &Path, store: &impl SecretStore) -> Result<Stored, String> {
+    // Files written before config.json was kept private
+    restrict_permissions(path);
+    restrict_permissions(&backup_path(path));
+
+    let (mut config, missing) = match read_config_file(path)? {
+        ConfigFile::Valid(config) => (*config, false),
+        ConfigFile::Missing => (Config::default(), true),
+        ConfigFile::Corrupted(_) => return Ok(Stored::Unchanged),
+    };
+    let save = |config: &Config| {
+        let content = serde_json::to_string_pretty(config)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        write_config_file(path, &content)
+    };
+
+    if config.session_secret.is_empty() {
+        // An unreadable keyring may well hold a secret, which mustn't be replaced
+        if store.get()?.is_some() {
+            return Ok(Stored::Unchanged);
+        }
+        let secret = generate_secret();
+        if let Err(e) = keep(store, &secret) {
+            config.session_secret = secret;
+            if let Some(parent) = path.parent().filter(|_| missing) {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create config directory: {}", e))?;
+            }
+            save(&config)?;
+            return Err(e);
+        }
+        return Ok(Stored::Created);
+    }
+
+    keep(store, &config.session_secret)?;
+    config.session_secret.clear();
+    save(&config)?;
+    // Again, so config.json.bak doesn't keep the secret either
+    save(&config)?;
+    Ok(Stored::Moved)
+}
+
+/// Store `secret`, and read it back: a keyring that didn't keep it mustn't
+/// cost the secret.
+fn keep(store: &impl SecretStore, secret: &str) -> Result<(), String> {
+    store.set(secret)?;
+    if store.get()?.as_deref() != Some(secret) {
+        return Err("The system keyring didn't keep the session secret".to_string());
+    }
+    Ok(())
+}
+
+/// Resolve a credential that may be given inline or, Docker `_FILE` style, as
+/// a path to a file holding it. The file wins when set; it is read fresh on
+/// every call and surrounding whitespace (such as a trailing newline) is
+/// dropped. An unreadable or empty file is an error rather than a silent
+/// fallback to the inline value.
+pub fn resolve_secret(inline: &str, file: Option<&str>) -> Result<String, String> {
+    let Some(path) = file.map(str::trim).filter(|path| !path.is_empty()) else {
+        return Ok(inline.to_string());
+    };
+    let secret = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read secret file {}: {}", path, e))?;
+    let secret = secret.trim();
+    if secret.is_empty() {
+        return Err(format!("Secret file {} is empty", path));
+    }
+    Ok(secret.to_string())
+}
+
+/// What was found at the config path.
+enum ConfigFile {
+    Missing,
+    // Boxed: Config is large compared to the other variants.
+    Valid(Box<Config>),
+    /// Present but empty or unparseable; carries the parse error.
+    Corrupted(String),
+}
+
+/// Read and parse a config file without side effects. An empty file counts
+/// as corrupted: that is what an interrupted in-place write leaves behind.
+fn read_config_file(path: &Path) -> Result<ConfigFile, String> {
+    if !path.exists() {
+        return Ok(ConfigFile::Missing);
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read config: {}", e))?;
+    if content.trim().is_empty() {
+        return Ok(ConfigFile::Corrupted("file is empty".to_string()));
+    }
+    match serde_json::from_str(&content) {
+        Ok(config) => Ok(ConfigFile::Valid(Box::new(config))),
+        Err(e) => Ok(ConfigFile::Corrupted(e.to_string())),
+    }
+}
+
+/// Atomically replace `path` with `content`, keeping the current file as
+/// `<path>.bak` first if it parses. A corrupt current file is not rotated
+/// into the backup, so the last good version survives repeated saves.
+fn write_config_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Ok(ConfigFile::Valid(_)) = read_config_file(path) {
+        fs::copy(path, backup_path(path))
+            .map_err(|e| format!("Failed to back up config: {}", e))?;
+        restrict_permissions(&backup_path(path));
+    }
+
+    let tmp_path = path.with_extension("json.tmp");
+    {
+        let mut file =
+            fs::File::create(&tmp_path).map_err(|e| format!("Failed to write config: {}", e))?;
+        // Still empty, so nothing is readable before it's private
+        restrict_permissions(&tmp_path);
+        file.write_all(content.as_bytes())
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Failed to write config: {}", e))?;
+    }
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write config: {}", e))?;
+    *LAST_WRITTEN.lock().unwrap() = Some(fingerprint(content.as_bytes()));
+    Ok(())
+}
+
+/// Cheap content fingerprint used to detect changes to config.json.
+pub fn fingerprint(content: &[u8]) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Fingerprint of config.json as it is on disk now, `None` if unreadable.
+pub fn disk_fingerprint() -> Option<u64> {
+    let path = get_config_path().ok()?;
+    fs::read(path).ok().map(|content| fingerprint(&content))
+}
+
+/// Fingerprint of the last config.json content this process wrote.
+pub fn last_written_fingerprint() -> Option<u64> {
+    *LAST_WRITTEN.lock().unwrap()
+}
+
+/// Make `path` readable by this user only, as config.json may hold secrets.
+/// There needn't be a file; Windows keeps the profile folder private.
+fn restrict_permissions(path: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if path.exists() {
+            let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+fn backup_path(config_path: &Path) -> PathBuf {
+    config_path.with_extension("json.bak")
+}
+
+/// The parse error if config.json exists but is corrupted, `None` if it is
+/// valid or missing.
+pub fn config_corruption() -> Option<String> {
+    let path = get_config_path().ok()?;
+    match read_config_file(&path) {
+        Ok(ConfigFile::Corrupted(e)) => Some(e),
+        _ => None,
+    }
+}
+
+/// Whether config.json.bak exists and parses.
+pub fn has_valid_backup() -> bool {
+    load_backup().is_some()
+}
+
+/// The configuration saved in config.json.bak, if it exists and parses.
+pub fn load_backup() -> Option<Config> {
+    let path = get_config_path().ok()?;
+    match read_config_file(&backup_path(&path)).ok()? {
+        ConfigFile::Valid(config) => Some(*config),
+        _ => None,
+    }
+}
+
+/// Replace a corrupted config.json with the contents of config.json.bak.
+pub fn restore_backup() -> Result<Config, String> {
+    let path = get_config_path()?;
+    let config = match read_config_file(&backup_path(&path))? {
+        ConfigFile::Valid(config) => *config,
+        _ => return Err("No valid config backup to restore".to_string()),
+    };
+    quarantine_corrupted(&path);
+    config.save()?;
+    Ok(config)
+}
+
+/// Give up on a corrupted config.json: keep it aside for inspection and
+/// start over from defaults.
+pub fn reset_corrupted() -> Result<Config, String> {
+    let path = get_config_path()?;
+    quarantine_corrupted(&path);
+    let config = Config::default();
+    config.save()?;
+    Ok(config)
+}
+
+/// Move a corrupted config file to `config.json.corrupt` so it's neither
+/// lost nor picked up again.
+fn quarantine_corrupted(path: &Path) {
+    if path.exists() {
+        let _ = fs::rename(path, path.with_extension("json.corrupt"));
+    }
 }
 
 /// Get the configuration file path
@@ -144,7 +998,16 @@ pub fn get_data_path() -> Result<PathBuf, String> {
 
 /// Get the database path
 pub fn get_database_path() -> Result<PathBuf, String> {
-    Ok(get_data_path()?.join("meshmonitor.db"))
+    database_path(&Config::load().unwrap_or_default())
+}
+
+/// The database `config` points the backend at: `database_path`, or
+/// meshmonitor.db in the data directory
+pub fn database_path(config: &Config) -> Result<PathBuf, String> {
+    match config.database_path.as_deref().map(str::trim) {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Ok(get_data_path()?.join("meshmonitor.db")),
+    }
 }
 
 /// Get the logs directory path
@@ -154,13 +1017,95 @@ pub fn get_logs_path() -> Result<PathBuf, String> {
     Ok(logs_dir)
 }
 
+/// Get the map tile cache directory path
+pub fn get_tile_cache_path() -> Result<PathBuf, String> {
+    let cache_dir =
+        dirs::cache_dir().ok_or_else(|| "Could not find cache directory".to_string())?;
+    let tiles_dir = cache_dir.join("MeshMonitor").join("tiles");
+    fs::create_dir_all(&tiles_dir)
+        .map_err(|e| format!("Failed to create tile cache directory: {}", e))?;
+    Ok(tiles_dir)
+}
+
 /// Default Meshtastic TCP port for legacy `meshtastic_port` field.
 fn default_meshtastic_port() -> u16 {
     4403
 }
 
+fn default_device_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_export_interval_minutes() -> u32 {
+    60
+}
+
+fn default_health_check_path() -> String {
+    "/api/health".to_string()
+}
+
+fn default_health_check_timeout_secs() -> u32 {
+    3
+}
+
+fn default_serial_baud() -> u32 {
+    115_200
+}
+
+fn default_incident_keywords() -> Vec<String> {
+    ["emergency", "sos", "mayday"].map(String::from).to_vec()
+}
+
+fn default_stop_quiesce_secs() -> u32 {
+    10
+}
+
+fn default_stop_sigterm_secs() -> u32 {
+    5
+}
+
+fn default_network_wait_secs() -> u32 {
+    60
+}
+
+fn default_pre_start_attempts() -> u32 {
+    30
+}
+
+fn default_pre_start_interval_secs() -> u32 {
+    5
+}
+
+fn default_sqlite_busy_timeout_ms() -> u32 {
+    5000
+}
+
+fn default_sqlite_wal_autocheckpoint() -> u32 {
+    1000
+}
+
+fn default_tile_cache_max_mb() -> u32 {
+    1024
+}
+
+fn default_tile_server_max_zoom() -> u8 {
+    19
+}
+
+fn default_push_events() -> Vec<String> {
+    vec!["direct_message".to_string(), "emergency".to_string()]
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
 /// Generate a random session secret
-fn generate_secret() -> String {
+pub(crate) fn generate_secret() -> String {
     uuid::Uuid::new_v4().to_string().replace("-", "")
         + &uuid::Uuid::new_v4().to_string().replace("-", "")
 }
@@ -183,6 +1128,135 @@ mod tests {
         assert_eq!(secret.len(), 64); // Two UUIDs without dashes
     }
 
+    #[test]
+    fn test_quiet_hours() {
+        let at = |h, m| chrono::NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let mut config = Config::default();
+        assert!(!config.in_quiet_hours(at(3, 0)));
+
+        config.quiet_hours_start = Some("22:00".to_string());
+        config.quiet_hours_end = Some("07:00".to_string());
+        assert!(config.in_quiet_hours(at(23, 30)));
+        assert!(config.in_quiet_hours(at(6, 59)));
+        assert!(!config.in_quiet_hours(at(7, 0)));
+        assert!(!config.in_quiet_hours(at(12, 0)));
+
+        config.quiet_hours_start = Some("13:00".to_string());
+        config.quiet_hours_end = Some("14:00".to_string());
+        assert!(config.in_quiet_hours(at(13, 15)));
+        assert!(!config.in_quiet_hours(at(22, 0)));
+
+        config.quiet_hours_end = Some("bogus".to_string());
+        assert!(!config.in_quiet_hours(at(13, 15)));
+    }
+
+    #[test]
+    fn test_switch_profile() {
+        let profile = |name: &str, ip: &str, web_port| Profile {
+            name: name.to_string(),
+            meshtastic_ip: ip.to_string(),
+            meshtastic_port: 4403,
+            meshtastic_alt_ips: Vec::new(),
+            web_port,
+            database_path: Some(format!("/meshes/{}.db", name)),
+        };
+        let mut config = Config {
+            profiles: vec![
+                profile("home", "192.168.1.10", 8080),
+                profile("club", "10.0.0.5", 8081),
+            ],
+            ..Config::default()
+        };
+        config.switch_profile("club").unwrap();
+        assert_eq!(config.meshtastic_ip, "10.0.0.5");
+        assert_eq!(config.web_port, 8081);
+        assert_eq!(config.database_path.as_deref(), Some("/meshes/club.db"));
+        assert_eq!(config.active_profile.as_deref(), Some("club"));
+
+        // Changes made while a profile is active are kept in it
+        config.meshtastic_ip = "10.0.0.6".to_string();
+        config.switch_profile("home").unwrap();
+        assert_eq!(config.meshtastic_ip, "192.168.1.10");
+        assert_eq!(config.profiles[1].meshtastic_ip, "10.0.0.6");
+        assert!(config.switch_profile("work").is_err());
+        assert_eq!(config.active_profile.as_deref(), Some("home"));
+    }
+
+    /// Unique scratch directory for file-handling tests.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "meshmonitor-config-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_secret_file_overrides_inline_value() {
+        let dir = scratch_dir("secret-file");
+        let path = dir.join("session_secret");
+        assert_eq!(resolve_secret("inline", None).unwrap(), "inline");
+        assert_eq!(resolve_secret("inline", Some("  ")).unwrap(), "inline");
+
+        fs::write(&path, "from-file\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+        assert_eq!(resolve_secret("inline", Some(&path)).unwrap(), "from-file");
+
+        fs::write(&path, "\n").unwrap();
+        assert!(resolve_secret("inline", Some(&path)).is_err());
+        assert!(resolve_secret("inline", Some(&format!("{}.missing", path))).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_keeps_last_valid_version_as_backup() {
+        let dir = scratch_dir("backup");
+        let path = dir.join("config.json");
+
+        write_config_file(&path, r#"{"web_port": 1, "auto_start": false, "session_secret": "a", "setup_completed": true}"#).unwrap();
+        assert!(!backup_path(&path).exists());
+        assert!(!path.with_extension("json.tmp").exists());
+
+        write_config_file(&path, r#"{"web_port": 2, "auto_start": false, "session_secret": "a", "setup_completed": true}"#).unwrap();
+        let ConfigFile::Valid(backup) = read_config_file(&backup_path(&path)).unwrap() else {
+            panic!("backup should be valid");
+        };
+        assert_eq!(backup.web_port, 1);
+
+        // A corrupt current file must not replace the good backup.
+        fs::write(&path, "{ truncated").unwrap();
+        assert!(matches!(
+            read_config_file(&path).unwrap(),
+            ConfigFile::Corrupted(_)
+        ));
+        write_config_file(&path, r#"{"web_port": 3, "auto_start": false, "session_secret": "a", "setup_completed": true}"#).unwrap();
+        let ConfigFile::Valid(backup) = read_config_file(&backup_path(&path)).unwrap() else {
+            panic!("backup should be valid");
+        };
+        assert_eq!(backup.web_port, 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_empty_config_file_is_corrupted() {
+        let dir = scratch_dir("empty");
+        let path = dir.join("config.json");
+        assert!(matches!(
+            read_config_file(&path).unwrap(),
+            ConfigFile::Missing
+        ));
+        fs::write(&path, "  \n").unwrap();
+        assert!(matches!(
+            read_config_file(&path).unwrap(),
+            ConfigFile::Corrupted(_)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     /// A config.json written by a future build that no longer emits the legacy
     /// Meshtastic fields must deserialize cleanly, with sensible defaults.
     /// Locks in the `#[serde(default)]` markers added when the setup UI
@@ -219,4 +1293,117 @@ mod tests {
         assert_eq!(config.meshtastic_ip, "10.0.0.42");
         assert_eq!(config.meshtastic_port, 4403);
     }
+
+    /// A keyring in memory; `None` stands for one that can't be used.
+    struct Memory(std::cell::RefCell<Option<Option<String>>>);
+
+    impl Memory {
+        fn holding(secret: Option<&str>) -> Self {
+            Memory(std::cell::RefCell::new(Some(secret.map(str::to_string))))
+        }
+
+        fn unavailable() -> Self {
+            Memory(std::cell::RefCell::new(None))
+        }
+
+        fn secret(&self) -> Option<String> {
+            self.0.borrow().clone().flatten()
+        }
+    }
+
+    impl SecretStore for Memory {
+        fn get(&self) -> Result<Option<String>, String> {
+            self.0
+                .borrow()
+                .clone()
+                .ok_or_else(|| "no keyring".to_string())
+        }
+
+        fn set(&self, secret: &str) -> Result<(), String> {
+            let mut stored = self.0.borrow_mut();
+            let slot = stored.as_mut().ok_or_else(|| "no keyring".to_string())?;
+            *slot = Some(secret.to_string());
+            Ok(())
+        }
+    }
+
+    fn config_with_secret(secret: &str) -> String {
+        serde_json::to_string(&Config {
+            session_secret: secret.to_string(),
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_store_session_secret() {
+        let dir = scratch_dir("store-secret");
+        let path = dir.join("config.json");
+
+        // Valid: moved, and gone from the file and its backup
+        write_config_file(&path, &config_with_secret("old")).unwrap();
+        write_config_file(&path, &config_with_secret("inline")).unwrap();
+        let store = Memory::holding(Some("old"));
+        assert_eq!(store_session_secret_in(&path, &store), Ok(Stored::Moved));
+        assert_eq!(store.secret().as_deref(), Some("inline"));
+        for file in [&path, &backup_path(&path)] {
+            assert!(!fs::read_to_string(file).unwrap().contains("inline"));
+        }
+        assert_eq!(
+            store_session_secret_in(&path, &store),
+            Ok(Stored::Unchanged)
+        );
+        assert_eq!(store.secret().as_deref(), Some("inline"));
+
+        // Missing: created in the keyring, not the file
+        fs::remove_file(&path).unwrap();
+        let store = Memory::holding(None);
+        assert_eq!(store_session_secret_in(&path, &store), Ok(Stored::Created));
+        assert_eq!(store.secret().map(|secret| secret.len()), Some(64));
+        assert!(!path.exists());
+
+        // Corrupted: left for recovery
+        fs::write(&path, "{").unwrap();
+        let store = Memory::holding(None);
+        assert_eq!(
+            store_session_secret_in(&path, &store),
+            Ok(Stored::Unchanged)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{");
+        assert_eq!(store.secret(), None);
+
+        // No keyring: kept in config.json, which only this user may read
+        write_config_file(&path, &config_with_secret("inline")).unwrap();
+        assert!(store_session_secret_in(&path, &Memory::unavailable()).is_err());
+        let config: Config = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config.session_secret, "inline");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resolve_session_secret_prefers_inline_then_keyring() {
+        let store = Memory::holding(Some("from-keyring"));
+        let config = Config::default();
+        assert_eq!(
+            config.resolve_session_secret_with(&store),
+            Ok("from-keyring".to_string())
+        );
+        let config = Config {
+            session_secret: "inline".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.resolve_session_secret_with(&store),
+            Ok("inline".to_string())
+        );
+        assert!(Config::default()
+            .resolve_session_secret_with(&Memory::holding(None))
+            .is_err());
+    }
 }
@@ -0,0 +1,161 @@
+//! Watches config.json for edits made outside the app (a text editor, a
+//! config-management tool) so they are neither silently ignored by the
+//! running backend nor clobbered by the next save from the settings window.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::config;
+use crate::{
+    audit, log, port_release, show_start_failure, start_backend, stop_backend, BackendState,
+};
+
+/// How often config.json is checked. Edits are made by hand, so a couple of
+/// seconds of latency is unnoticeable.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Event emitted to the settings window after the user chooses to reload.
+pub const CONFIG_RELOADED_EVENT: &str = "config-reloaded";
+
+static WATCHER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Set while the reload prompt is showing, so a second edit doesn't stack
+/// another dialog and saves from the settings window are refused.
+static PROMPT_OPEN: AtomicBool = AtomicBool::new(false);
+
+/// Fingerprint of config.json as this app last knew it: loaded at startup,
+/// written by us, or acknowledged by the user after an external edit.
+static KNOWN: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Fingerprint of config.json when the backend was last started.
+static LAUNCHED: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Spawn the watcher thread. Safe to call more than once.
+pub fn start_watcher<R: Runtime>(app: &AppHandle<R>) {
+    if WATCHER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    *KNOWN.lock().unwrap() = config::disk_fingerprint();
+
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        if is_external_edit() {
+            *KNOWN.lock().unwrap() = config::disk_fingerprint();
+            on_external_edit(&app);
+        }
+    });
+}
+
+/// Whether config.json has changed on disk in a way the user hasn't yet
+/// accepted or dismissed. `save_config` refuses to write while this holds.
+pub fn has_unacknowledged_edit() -> bool {
+    PROMPT_OPEN.load(Ordering::SeqCst) || is_external_edit()
+}
+
+/// Note the config.json the backend is being started with.
+pub fn backend_starting() {
+    *LAUNCHED.lock().unwrap() = config::disk_fingerprint();
+}
+
+/// Whether config.json changed since the backend was started.
+pub fn changed_since_backend_start() -> bool {
+    *LAUNCHED.lock().unwrap() != config::disk_fingerprint()
+}
+
+/// Take config.json as it is on disk as known, so an edit already acted
+/// on raises no prompt.
+pub fn acknowledge_edit() {
+    *KNOWN.lock().unwrap() = config::disk_fingerprint();
+}
+
+/// Tell the settings window to reload and, when `restart`, restart the
+/// backend with the configuration on disk.
+pub fn reload<R: Runtime>(app: &AppHandle<R>, restart: bool) {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.emit(CONFIG_RELOADED_EVENT, ());
+    }
+    if !restart {
+        return;
+    }
+    let state: tauri::State<BackendState> = app.state();
+    stop_backend(&state);
+    let web_port = config::Config::load().unwrap_or_default().web_port;
+    match port_release::wait(web_port).and_then(|()| start_backend(app)) {
+        Ok(child) => *state.process.lock().unwrap() = Some(child),
+        Err(e) => {
+            eprintln!("Failed to restart backend: {}", e);
+            show_start_failure(app, &config::Config::load().unwrap_or_default(), &e);
+        }
+    }
+}
+
+/// Whether the file on disk differs from both what we last knew and what we
+/// last wrote ourselves. Our own writes are folded into `KNOWN` here.
+fn is_external_edit() -> bool {
+    let Some(current) = config::disk_fingerprint() else {
+        return false;
+    };
+    let mut known = KNOWN.lock().unwrap();
+    if *known == Some(current) {
+        return false;
+    }
+    if config::last_written_fingerprint() == Some(current) {
+        *known = Some(current);
+        return false;
+    }
+    true
+}
+
+fn on_external_edit<R: Runtime>(app: &AppHandle<R>) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    if let Some(error) = config::config_corruption() {
+        log(&format!(
+            "config.json was edited externally but is not valid JSON ({}); ignoring",
+            error
+        ));
+        return;
+    }
+
+    let settings_open = app
+        .get_webview_window("settings")
+        .is_some_and(|window| window.is_visible().unwrap_or(false));
+    let backend_running = app.state::<BackendState>().is_running();
+    if !settings_open && !backend_running {
+        // Nothing holds the old values; the next load picks up the edit.
+        log("config.json was edited externally");
+        return;
+    }
+    if PROMPT_OPEN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    log("config.json was edited externally; asking whether to reload");
+
+    let handle = app.clone();
+    app.dialog()
+        .message(
+            "MeshMonitor's configuration file was changed outside the app.\n\n\
+             Reload it and restart the backend so the changes take effect?",
+        )
+        .title("Config changed on disk")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Reload and Restart".to_string(),
+            "Keep Current".to_string(),
+        ))
+        .show(move |confirmed| {
+            PROMPT_OPEN.store(false, Ordering::SeqCst);
+            if !confirmed {
+                return;
+            }
+            audit::record(
+                audit::Source::Dialog,
+                "config_reloaded",
+                "config.json was edited outside the app",
+            );
+            reload(&handle, backend_running);
+        });
+}
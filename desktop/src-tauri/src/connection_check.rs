@@ -0,0 +1,183 @@
+//! Step-by-step checks of the path to a network node, for when the backend
+//! can't reach it: does the name resolve, does the host answer ping, does
+//! the Meshtastic API port accept a connection, and does the node's own web
+//! UI answer. Settings shows the results like the installation checklist,
+//! so the step that fails points at the cause (a typo, a sleeping host, a
+//! firewall, the API turned off on the node).
+
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::preflight::Check;
+
+/// How long each network step may take.
+const STEP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Run every check against `host`, with the Meshtastic API on `port`. The
+/// remaining steps are skipped when the name doesn't resolve.
+pub fn run(host: &str, port: u16) -> Vec<Check> {
+    let host = host.trim();
+    let addresses = match resolve(host, port) {
+        Ok(addresses) => addresses,
+        Err(e) => {
+            return vec![Check {
+                label: "Name resolution",
+                ok: false,
+                detail: e,
+            }]
+        }
+    };
+    let list = addresses
+        .iter()
+        .map(|address| address.ip().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let resolved = Check {
+        label: "Name resolution",
+        ok: true,
+        detail: if host.parse::<IpAddr>().is_ok() {
+            format!("{} is an IP address", host)
+        } else {
+            format!("{} is {}", host, list)
+        },
+    };
+    vec![
+        resolved,
+        ping(addresses[0].ip()),
+        tcp(&addresses, port),
+        web_ui(host),
+    ]
+}
+
+fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    if host.is_empty() {
+        return Err("No node address is set".to_string());
+    }
+    let addresses: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("{} couldn't be resolved: {}", host, e))?
+        .collect();
+    if addresses.is_empty() {
+        return Err(format!("{} has no addresses", host));
+    }
+    Ok(addresses)
+}
+
+/// One ping with the system's `ping`, which unlike raw ICMP sockets needs no
+/// special rights.
+fn ping(ip: IpAddr) -> Check {
+    let mut command = Command::new("ping");
+    if cfg!(windows) {
+        command.args(["-n", "1", "-w", "3000"]);
+    } else if cfg!(target_os = "macos") {
+        command.args(["-c", "1", "-t", "3"]);
+    } else {
+        command.args(["-c", "1", "-W", "3"]);
+    }
+    if ip.is_ipv6() && !cfg!(windows) && !cfg!(target_os = "macos") {
+        command.arg("-6");
+    }
+    let started = Instant::now();
+    let (ok, detail) = match command.arg(ip.to_string()).output() {
+        Ok(output) if output.status.success() => (
+            true,
+            format!("{} answered in {} ms", ip, started.elapsed().as_millis()),
+        ),
+        Ok(_) => (
+            false,
+            format!(
+                "{} didn't answer. Some hosts and firewalls never answer ping, so \
+                 this alone doesn't mean the node is down.",
+                ip
+            ),
+        ),
+        Err(e) => (false, format!("ping couldn't be run: {}", e)),
+    };
+    Check {
+        label: "Ping",
+        ok,
+        detail,
+    }
+}
+
+/// A connection to the Meshtastic API port on any of `addresses`.
+fn tcp(addresses: &[SocketAddr], port: u16) -> Check {
+    let mut last_error = String::new();
+    for address in addresses {
+        let started = Instant::now();
+        match TcpStream::connect_timeout(address, STEP_TIMEOUT) {
+            Ok(_) => {
+                return Check {
+                    label: "Meshtastic API port",
+                    ok: true,
+                    detail: format!(
+                        "{} accepted a connection in {} ms",
+                        address,
+                        started.elapsed().as_millis()
+                    ),
+                }
+            }
+            Err(e) => last_error = format!("{}: {}", address, e),
+        }
+    }
+    Check {
+        label: "Meshtastic API port",
+        ok: false,
+        detail: format!(
+            "Port {} refused or didn't answer ({}). Check that the node's network API \
+             is on and no firewall blocks the port.",
+            port, last_error
+        ),
+    }
+}
+
+/// The node's own web UI on port 80. Any HTTP answer counts; nodes without
+/// Wi-Fi or Ethernet web support have none, so this is informational.
+fn web_ui(host: &str) -> Check {
+    let host = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{}]", ip),
+        _ => host.to_string(),
+    };
+    let agent = ureq::AgentBuilder::new()
+        .timeout(STEP_TIMEOUT)
+        .redirects(0)
+        .build();
+    let (ok, detail) = match agent.get(&format!("http://{}/", host)).call() {
+        Ok(response) => (true, format!("Answered HTTP {}", response.status())),
+        Err(ureq::Error::Status(status, _)) => (true, format!("Answered HTTP {}", status)),
+        Err(e) => (
+            false,
+            format!(
+                "No answer ({}). Only nodes that serve their own web page answer here.",
+                e
+            ),
+        ),
+    };
+    Check {
+        label: "Node web UI",
+        ok,
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_resolution_and_api_port() {
+        assert!(resolve("  ", 4403).is_err());
+        let addresses = resolve("127.0.0.1", 4403).unwrap();
+        assert_eq!(addresses, vec![SocketAddr::from(([127, 0, 0, 1], 4403))]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap();
+        assert!(tcp(&[open], open.port()).ok);
+        drop(listener);
+        let closed = tcp(&[open], open.port());
+        assert!(!closed.ok);
+        assert!(closed.detail.starts_with(&format!("Port {} ", open.port())));
+    }
+}
@@ -0,0 +1,147 @@
+//! Diagnostics snapshot taken whenever the backend exits on its own, so an
+//! intermittent overnight crash can be investigated after the fact. Each
+//! snapshot is a timestamped folder under `logs/crashes/` with the tail of
+//! every log (the backend's are truncated when it is restarted) and a
+//! `status.json` with the exit reason, the backend's last status report and
+//! a hash of config.json that shows whether settings changed between
+//! crashes without revealing them.
+
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::{config, ipc, log_to_file};
+
+/// How much of the end of each log is kept.
+const TAIL_BYTES: u64 = 64 * 1024;
+
+/// Snapshots kept; older ones are deleted.
+const KEPT_SNAPSHOTS: usize = 20;
+
+/// Logs copied into a snapshot.
+const LOGS: &[&str] = &[
+    "server-stderr.log",
+    "server-stdout.log",
+    "desktop.log",
+    "apprise.log",
+];
+
+/// Take a snapshot for backend `pid`, which exited for `reason`. Returns
+/// the snapshot folder.
+pub fn capture(pid: u32, reason: &str) -> Result<PathBuf, String> {
+    let logs_path = config::get_logs_path()?;
+    let crashes = logs_path.join("crashes");
+    let now = chrono::Local::now();
+    let dir = crashes.join(now.format("%Y-%m-%dT%H-%M-%S").to_string());
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    for name in LOGS {
+        if let Some(tail) = tail(&logs_path.join(name), TAIL_BYTES) {
+            let _ = fs::write(dir.join(name), tail);
+        }
+    }
+
+    let last_status = ipc::last_status(pid);
+    let status = serde_json::json!({
+        "time": now.to_rfc3339(),
+        "pid": pid,
+        "reason": reason,
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "lastStatus": last_status.map(|status| serde_json::json!({
+            "uptimeMs": status.uptime,
+            "rssBytes": status.rss,
+        })),
+        "configSha256": config::get_config_path()
+            .ok()
+            .and_then(|path| fs::read(path).ok())
+            .map(|content| hex(&Sha256::digest(content))),
+    });
+    let json = serde_json::to_string_pretty(&status)
+        .map_err(|e| format!("Failed to serialize crash status: {}", e))?;
+    fs::write(dir.join("status.json"), json)
+        .map_err(|e| format!("Failed to write crash status: {}", e))?;
+
+    prune(&crashes, KEPT_SNAPSHOTS);
+    log_to_file(
+        &logs_path,
+        &format!("Crash diagnostics saved to {}", dir.display()),
+    );
+    Ok(dir)
+}
+
+/// The last `max` bytes of `path`, from the first full line.
+fn tail(path: &Path, max: u64) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(max);
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    if start > 0 {
+        if let Some(newline) = bytes.iter().position(|&b| b == b'\n') {
+            bytes.drain(..=newline);
+        }
+    }
+    Some(bytes)
+}
+
+/// Delete all but the newest `keep` snapshots. Their names sort by time.
+fn prune(crashes: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(crashes) else {
+        return;
+    };
+    let mut snapshots: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(keep);
+    for old in &snapshots[..excess] {
+        let _ = fs::remove_dir_all(old);
+    }
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_starts_at_a_full_line() {
+        let dir = std::env::temp_dir().join(format!("mm-crash-tail-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("server-stderr.log");
+        fs::write(&path, "first line\nsecond line\nthird\n").unwrap();
+        assert_eq!(
+            tail(&path, 1024).unwrap(),
+            b"first line\nsecond line\nthird\n"
+        );
+        assert_eq!(tail(&path, 15).unwrap(), b"third\n");
+        assert!(tail(&dir.join("missing.log"), 1024).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_keeps_the_newest_snapshots() {
+        let dir = std::env::temp_dir().join(format!("mm-crash-prune-{}", std::process::id()));
+        for name in [
+            "2026-10-01T01-00-00",
+            "2026-10-02T01-00-00",
+            "2026-10-03T01-00-00",
+        ] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        prune(&dir, 2);
+        assert!(!dir.join("2026-10-01T01-00-00").exists());
+        assert!(dir.join("2026-10-02T01-00-00").exists());
+        assert!(dir.join("2026-10-03T01-00-00").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+//! Keeping the mesh history when the database moves. The database file the
+//! backend last used is remembered in `database-path.txt` in the data
+//! folder. If `Config::database_path` is changed to a location with no
+//! database yet while the old file has data, the backend is not started on a
+//! fresh, empty database. A dialog offers to copy the old one over first,
+//! and the copy is only used once it matches the original byte for byte
+//! (SHA-256). The original is never touched. Switching to a profile with
+//! its own database is a deliberate move and doesn't ask.
+
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
+};
+
+use crate::config::{self, Config};
+use crate::{audit, log, show_start_failure, start_backend, BackendState};
+
+const COPY: &str = "Copy Database";
+const START_EMPTY: &str = "Start Empty";
+const NOT_NOW: &str = "Not Now";
+
+/// Why the backend isn't started while the dialog is up.
+const WAITING: &str =
+    "The database location changed. Choose in the dialog whether to copy the existing database there.";
+
+/// Guards against a second dialog when the backend is restarted meanwhile.
+static ASKING: AtomicBool = AtomicBool::new(false);
+
+/// Called before the backend starts with the database at `db_path`. Fails,
+/// after asking what to do, when that would leave the old database behind.
+pub fn check<R: Runtime>(app: &AppHandle<R>, db_path: &Path) -> Result<(), String> {
+    if ASKING.load(Ordering::SeqCst) {
+        return Err(WAITING.to_string());
+    }
+    let last = last_path();
+    if let Some(old) = left_behind(last.as_deref(), db_path) {
+        ASKING.store(true, Ordering::SeqCst);
+        log(&format!(
+            "Database moved from {} to {}, where there's none yet; asking before starting",
+            old.display(),
+            db_path.display()
+        ));
+        ask(app, old, db_path.to_path_buf());
+        return Err(WAITING.to_string());
+    }
+    if last.as_deref() != Some(db_path) {
+        remember(db_path);
+    }
+    Ok(())
+}
+
+/// The database `last` used, when `current` is elsewhere and empty while
+/// `last` holds data.
+fn left_behind(last: Option<&Path>, current: &Path) -> Option<PathBuf> {
+    let last = last?;
+    (last != current && has_data(last) && !has_data(current)).then(|| last.to_path_buf())
+}
+
+fn has_data(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0)
+}
+
+fn ask<R: Runtime>(app: &AppHandle<R>, old: PathBuf, new: PathBuf) {
+    let handle = app.clone();
+    app.dialog()
+        .message(format!(
+            "MeshMonitor's database is now set to be\n{}\nbut there's no database there yet. \
+             The one with your mesh history is at\n{}\n\n\
+             Copy it to the new location? Starting empty leaves the old database where it is, \
+             unused.",
+            new.display(),
+            old.display()
+        ))
+        .title("Database location changed")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            COPY.to_string(),
+            START_EMPTY.to_string(),
+            NOT_NOW.to_string(),
+        ))
+        .show_with_result(move |result| {
+            let choice = chosen(&result);
+            // Copying can take a while for a large database
+            std::thread::spawn(move || decided(&handle, choice, &old, &new));
+        });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    Copy,
+    StartEmpty,
+}
+
+/// The choice made in the dialog; `None` when it was put off or closed.
+fn chosen(result: &MessageDialogResult) -> Option<Choice> {
+    match result {
+        MessageDialogResult::Yes => Some(Choice::Copy),
+        MessageDialogResult::No => Some(Choice::StartEmpty),
+        MessageDialogResult::Custom(label) if label == COPY => Some(Choice::Copy),
+        MessageDialogResult::Custom(label) if label == START_EMPTY => Some(Choice::StartEmpty),
+        _ => None,
+    }
+}
+
+/// Carry out `choice`, then start the backend. When the dialog was put off
+/// or the copy failed the backend stays stopped, and starting it asks again.
+fn decided<R: Runtime>(app: &AppHandle<R>, choice: Option<Choice>, old: &Path, new: &Path) {
+    let config = Config::load().unwrap_or_default();
+    let detail = format!("{} -> {}", old.display(), new.display());
+    let result = match choice {
+        Some(Choice::Copy) => copy(old, new).map(|()| {
+            log(&format!("Copied the database: {}", detail));
+            audit::record(audit::Source::Dialog, "database_copied", &detail);
+        }),
+        Some(Choice::StartEmpty) => {
+            log(&format!("Starting with an empty database: {}", detail));
+            audit::record(audit::Source::Dialog, "database_started_empty", &detail);
+            Ok(())
+        }
+        None => Err(format!(
+            "The database location changed and the old database wasn't copied yet. \
+             Start MeshMonitor's backend again to choose. ({})",
+            detail
+        )),
+    };
+    ASKING.store(false, Ordering::SeqCst);
+    if let Err(e) = result {
+        log(&e);
+        show_start_failure(app, &config, &e);
+        return;
+    }
+    remember(new);
+    let state = app.state::<BackendState>();
+    // Started from Settings or the tray in the meantime
+    if state.is_running() {
+        return;
+    }
+    match start_backend(app) {
+        Ok(child) => *state.process.lock().unwrap() = Some(child),
+        Err(e) => {
+            eprintln!("Failed to start backend: {}", e);
+            show_start_failure(app, &config, &e);
+        }
+    }
+}
+
+/// Copy the database at `old`, and its write-ahead log if one was left
+/// behind, to `new`. Each file is copied under a temporary name and only
+/// renamed into place once it reads back the same as the original.
+fn copy(old: &Path, new: &Path) -> Result<(), String> {
+    if let Some(parent) = new.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Can't create {}: {}", parent.display(), e))?;
+    }
+    let mut files = vec![(old.to_path_buf(), new.to_path_buf())];
+    let old_wal = with_suffix(old, "-wal");
+    if has_data(&old_wal) {
+        files.push((old_wal, with_suffix(new, "-wal")));
+    }
+    let mut copied = Vec::new();
+    for (from, to) in &files {
+        let partial = with_suffix(to, ".copying");
+        let verified = fs::copy(from, &partial)
+            .map_err(|e| format!("Can't copy {}: {}", from.display(), e))
+            .and_then(|_| {
+                if digest(from)? == digest(&partial)? {
+                    Ok(())
+                } else {
+                    Err(format!("The copy of {} doesn't match it", from.display()))
+                }
+            });
+        if let Err(e) = verified {
+            let _ = fs::remove_file(&partial);
+            for partial in copied {
+                let _ = fs::remove_file(partial);
+            }
+            return Err(e);
+        }
+        copied.push(partial);
+    }
+    for ((_, to), partial) in files.iter().zip(copied) {
+        fs::rename(&partial, to).map_err(|e| format!("Can't move {}: {}", to.display(), e))?;
+    }
+    Ok(())
+}
+
+fn digest(path: &Path) -> Result<Vec<u8>, String> {
+    let mut file = File::open(path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+        if read == 0 {
+            return Ok(hasher.finalize().to_vec());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn record_path() -> Result<PathBuf, String> {
+    Ok(config::get_data_path()?.join("database-path.txt"))
+}
+
+fn last_path() -> Option<PathBuf> {
+    let content = fs::read_to_string(record_path().ok()?).ok()?;
+    let path = content.trim();
+    (!path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// Record `db_path` as the database in use, so the next start doesn't offer
+/// to copy the previous one there.
+pub fn remember(db_path: &Path) {
+    let saved = record_path().and_then(|path| {
+        fs::write(path, db_path.to_string_lossy().as_bytes()).map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        log(&format!("Failed to remember the database location: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_left_behind_and_copy() {
+        let dir = std::env::temp_dir().join(format!(
+            "meshmonitor-database-move-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let old = dir.join("meshmonitor.db");
+        let new = dir.join("elsewhere").join("mesh.db");
+        fs::write(&old, b"SQLite format 3\0history").unwrap();
+        fs::write(with_suffix(&old, "-wal"), b"recent pages").unwrap();
+
+        assert_eq!(left_behind(None, &new), None);
+        assert_eq!(left_behind(Some(&old), &old), None);
+        assert_eq!(left_behind(Some(&old), &new), Some(old.clone()));
+        assert_eq!(left_behind(Some(&new), &old), None);
+
+        copy(&old, &new).unwrap();
+        assert_eq!(fs::read(&new).unwrap(), b"SQLite format 3\0history");
+        assert_eq!(
+            fs::read(with_suffix(&new, "-wal")).unwrap(),
+            b"recent pages"
+        );
+        assert!(!with_suffix(&new, ".copying").exists());
+        assert!(old.exists());
+        assert_eq!(left_behind(Some(&old), &new), None);
+
+        assert_eq!(
+            chosen(&MessageDialogResult::Custom(COPY.to_string())),
+            Some(Choice::Copy)
+        );
+        assert_eq!(chosen(&MessageDialogResult::Cancel), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
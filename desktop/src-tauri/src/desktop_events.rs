@@ -0,0 +1,75 @@
+//! Supervisor events shown in the web UI's own notifications in
+//! native-window mode, so the window tells one story instead of the web UI
+//! saying one thing and a desktop notification another: the backend
+//! stopping unexpectedly, the watchdog restarting a hung one, and the
+//! backend running again afterwards. Like the status strip, events are
+//! handed to the page with `eval`, as the web UI's (remote) origin has no
+//! IPC access. The bridge keeps the latest in the window's session storage,
+//! so one raised while the status page stands in for a restarting backend
+//! is still shown once the web UI is back.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::webview::WEB_UI_WINDOW;
+
+/// Creates `window.__meshmonitorDesktop`, whose `push(event)` stores the
+/// event and announces it with a `meshmonitor:desktop-event` event, and
+/// whose `events()` returns the ones stored.
+pub const INIT_SCRIPT: &str = r#"
+(function () {
+  if (window.__meshmonitorDesktop) return;
+  var KEY = 'meshmonitor.desktopEvents', LIMIT = 20;
+  function load() {
+    try { return JSON.parse(sessionStorage.getItem(KEY)) || []; } catch (e) { return []; }
+  }
+  window.__meshmonitorDesktop = {
+    events: load,
+    push: function (event) {
+      var events = load().concat([event]).slice(-LIMIT);
+      try { sessionStorage.setItem(KEY, JSON.stringify(events)); } catch (e) {}
+      window.dispatchEvent(new CustomEvent('meshmonitor:desktop-event', { detail: event }));
+    }
+  };
+})();
+"#;
+
+/// Numbers events so the page shows each once, also after a reload.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+struct Event<'a> {
+    id: u64,
+    /// Milliseconds since the epoch, so the page can skip stale events.
+    at: i64,
+    level: Level,
+    message: &'a str,
+}
+
+/// Show `message` in the web UI window, if it's open.
+pub fn publish<R: Runtime>(app: &AppHandle<R>, level: Level, message: &str) {
+    let Some(window) = app.get_webview_window(WEB_UI_WINDOW) else {
+        return;
+    };
+    let event = Event {
+        id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+        at: chrono::Utc::now().timestamp_millis(),
+        level,
+        message,
+    };
+    if let Ok(json) = serde_json::to_string(&event) {
+        let _ = window.eval(format!(
+            "window.__meshmonitorDesktop && window.__meshmonitorDesktop.push({})",
+            json
+        ));
+    }
+}
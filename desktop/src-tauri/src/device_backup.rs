@@ -0,0 +1,262 @@
+//! Scheduled backups of the connected node's configuration (channels, LoRa
+//! and module settings) into the data folder, every
+//! `Config::device_backup_interval_hours`, so a radio that was reset or
+//! reflashed can be set up again quickly. Backups are the backend's
+//! `meshtastic --export-config` YAML, named `<node>-<date>-<time>.yaml` in
+//! `device_backups`. A backup identical to the node's newest is skipped, and
+//! only the newest `KEEP` per node are kept. Settings restores a backup to
+//! the connected node through the backend, after showing what would change.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::api::{self, BackendApi, DeviceRestore};
+use crate::backend_record::BackendRecord;
+use crate::config::{self, Config};
+use crate::{audit, idle, log};
+
+/// How often the scheduler checks whether a backup is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Backups kept per node.
+const KEEP: usize = 30;
+
+/// Guards against spawning a second scheduler thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// A saved backup, as listed for restoring.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SavedBackup {
+    pub name: String,
+    pub node_id: String,
+    /// Local time it was taken, `2026-03-01 12:00:00`.
+    pub taken: String,
+}
+
+/// Spawn the backup scheduler. It idles while backups are off, no API token
+/// is configured or the backend isn't connected to its node. Safe to call
+/// more than once.
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        let mut last_run: Option<Instant> = None;
+        let mut gate = idle::Gate::new("node configuration backup");
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let Ok(config) = Config::load() else {
+                continue;
+            };
+            if config.device_backup_interval_hours == 0 {
+                continue;
+            }
+            let interval =
+                Duration::from_secs(u64::from(config.device_backup_interval_hours) * 3600);
+            if last_run.is_some_and(|run| run.elapsed() < interval) {
+                continue;
+            }
+            let Some(api) = BackendApi::from_config(&config) else {
+                continue;
+            };
+            // Before the node is connected the backend has no config to export
+            let Some(node_id) = api
+                .status()
+                .ok()
+                .filter(|status| status.connected)
+                .and_then(|status| status.local_node_id)
+            else {
+                continue;
+            };
+            if !gate.ready(&config) {
+                continue;
+            }
+            last_run = Some(Instant::now());
+            match backup(&api, &node_id) {
+                Ok(Some(path)) => log(&format!("Backed up the node's configuration to {:?}", path)),
+                Ok(None) => {}
+                Err(e) => log(&format!("Node configuration backup failed: {}", e)),
+            }
+        }
+    });
+}
+
+/// Folder the backups are kept in.
+pub fn backups_dir() -> Result<PathBuf, String> {
+    config::get_data_path().map(|path| path.join("device_backups"))
+}
+
+/// Back up the configuration of node `node_id`; `None` when it hasn't
+/// changed since the last backup.
+fn backup(api: &BackendApi, node_id: &str) -> Result<Option<PathBuf>, String> {
+    let yaml = api.device_backup()?;
+    if !yaml.lines().any(|line| line.starts_with("config:")) {
+        return Err("The backend returned no device configuration".to_string());
+    }
+    let dir = backups_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+    save(&dir, node_id, &yaml, chrono::Local::now())
+}
+
+fn save(
+    dir: &Path,
+    node_id: &str,
+    yaml: &str,
+    now: chrono::DateTime<chrono::Local>,
+) -> Result<Option<PathBuf>, String> {
+    let existing = backups(dir, node_id);
+    if existing
+        .first()
+        .is_some_and(|newest| fs::read_to_string(newest).is_ok_and(|saved| saved == yaml))
+    {
+        return Ok(None);
+    }
+    let path = dir.join(format!(
+        "{}-{}.yaml",
+        file_prefix(node_id),
+        now.format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, yaml).map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+    for old in existing.iter().skip(KEEP - 1) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(Some(path))
+}
+
+/// Node `!1a2b3c4d`'s backups start with `1a2b3c4d-`.
+fn file_prefix(node_id: &str) -> String {
+    node_id.trim_start_matches('!').to_ascii_lowercase()
+}
+
+/// Backups of node `node_id` in `dir`, newest first.
+pub fn backups(dir: &Path, node_id: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}-", file_prefix(node_id));
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension().is_some_and(|ext| ext == "yaml")
+                        && path
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(|name| name.starts_with(&prefix))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // The timestamp in the name sorts chronologically
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+/// The node and time in a backup's file name.
+fn parse_name(name: &str) -> Option<SavedBackup> {
+    let mut parts = name.strip_suffix(".yaml")?.rsplitn(3, '-');
+    let (time, date, node) = (parts.next()?, parts.next()?, parts.next()?);
+    let taken =
+        chrono::NaiveDateTime::parse_from_str(&format!("{}{}", date, time), "%Y%m%d%H%M%S").ok()?;
+    Some(SavedBackup {
+        name: name.to_string(),
+        node_id: format!("!{}", node),
+        taken: taken.format("%Y-%m-%d %H:%M:%S").to_string(),
+    })
+}
+
+/// Every saved backup, newest first.
+pub fn list() -> Vec<SavedBackup> {
+    let Ok(entries) = backups_dir().and_then(|dir| fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+    let mut saved: Vec<SavedBackup> = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str().and_then(parse_name))
+        .collect();
+    saved.sort_by(|a, b| b.taken.cmp(&a.taken).then_with(|| a.name.cmp(&b.name)));
+    saved
+}
+
+/// Compare the backup `name` with the connected node and, with `apply`,
+/// write it to the node.
+pub fn restore(config: &Config, name: &str, apply: bool) -> Result<DeviceRestore, String> {
+    // Only listed names, so `name` can't point outside the folder
+    let backup = list()
+        .into_iter()
+        .find(|backup| backup.name == name)
+        .ok_or_else(|| format!("The backup {} no longer exists", name))?;
+    let path = backups_dir()?.join(&backup.name);
+    let yaml =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let control_token = BackendRecord::load()
+        .and_then(|record| record.control_token)
+        .ok_or("Start MeshMonitor's backend before restoring a backup")?;
+    let result = api::device_restore(config.web_port, &control_token, &yaml, apply)?;
+    if result.applied {
+        let detail = format!("{} ({} changes)", name, result.changes.len());
+        log(&format!(
+            "Restored the node's configuration from {}",
+            detail
+        ));
+        audit::record(audit::Source::Command, "device_config_restored", &detail);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_save_skips_unchanged_and_keeps_newest() {
+        let dir = std::env::temp_dir().join(format!("mm-device-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let at = |minute| {
+            chrono::Local
+                .with_ymd_and_hms(2026, 3, 1, 12, minute, 0)
+                .unwrap()
+        };
+
+        let first = save(&dir, "!1A2B3C4D", "config: 1\n", at(0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            first.file_name().unwrap().to_str(),
+            Some("1a2b3c4d-20260301-120000.yaml")
+        );
+        assert_eq!(save(&dir, "!1a2b3c4d", "config: 1\n", at(1)).unwrap(), None);
+        for minute in 2..(KEEP as u32 + 5) {
+            let yaml = format!("config: {}\n", minute);
+            assert!(save(&dir, "!1a2b3c4d", &yaml, at(minute))
+                .unwrap()
+                .is_some());
+        }
+        save(&dir, "!deadbeef", "config: 1\n", at(0)).unwrap();
+
+        let kept = backups(&dir, "!1a2b3c4d");
+        assert_eq!(kept.len(), KEEP);
+        assert!(kept[0].ends_with(format!("1a2b3c4d-20260301-12{}00.yaml", KEEP + 4)));
+        assert!(!kept.contains(&first));
+        assert_eq!(backups(&dir, "!deadbeef").len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_name() {
+        assert_eq!(
+            parse_name("1a2b3c4d-20260301-120005.yaml"),
+            Some(SavedBackup {
+                name: "1a2b3c4d-20260301-120005.yaml".to_string(),
+                node_id: "!1a2b3c4d".to_string(),
+                taken: "2026-03-01 12:00:05".to_string(),
+            })
+        );
+        assert_eq!(parse_name("1a2b3c4d-20260301-120005.txt"), None);
+        assert_eq!(parse_name("notes.yaml"), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! Recognizes common fatal backend errors in `server-stderr.log` and tells
+//! the user how to fix them, with a button that goes straight to the
+//! setting, file or page involved. Each kind of problem is explained once
+//! per run of the app, however often the backend hits it.
+
+use std::collections::HashSet;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{config, log_to_file, repair, tray};
+
+/// How much of the log is read at most per scan.
+const MAX_SCAN: u64 = 256 * 1024;
+
+/// A recognized backend failure.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnosis {
+    /// Another program listens on the web port.
+    PortInUse(Option<u16>),
+    /// A node's host name doesn't resolve.
+    HostNotFound(String),
+    /// SQLite can't open the database file.
+    DatabaseUnavailable,
+    /// A bundled native module is missing or built for another Node.js.
+    NativeModuleBroken(Option<String>),
+}
+
+/// What the dialog's button does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fix {
+    /// Open Settings with this field focused.
+    Setting(&'static str),
+    OpenWebUi,
+    RevealDatabase,
+    Repair,
+}
+
+impl Diagnosis {
+    fn kind(&self) -> &'static str {
+        match self {
+            Diagnosis::PortInUse(_) => "port_in_use",
+            Diagnosis::HostNotFound(_) => "host_not_found",
+            Diagnosis::DatabaseUnavailable => "database_unavailable",
+            Diagnosis::NativeModuleBroken(_) => "native_module_broken",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Diagnosis::PortInUse(_) => "Web Port in Use",
+            Diagnosis::HostNotFound(_) => "Node Not Found",
+            Diagnosis::DatabaseUnavailable => "Database Unavailable",
+            Diagnosis::NativeModuleBroken(_) => "Installation Damaged",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Diagnosis::PortInUse(port) => format!(
+                "MeshMonitor couldn't start because another program is already using {}.\n\n\
+                 Close that program (it may be another copy of MeshMonitor), or choose a \
+                 different web port in Settings.",
+                port.map_or("the web port".to_string(), |port| format!("port {}", port))
+            ),
+            Diagnosis::HostNotFound(host) => format!(
+                "The node address \"{}\" couldn't be found on the network.\n\n\
+                 Check the spelling, or use the node's IP address instead. Node addresses \
+                 are set in the web UI under the source's settings.",
+                host
+            ),
+            Diagnosis::DatabaseUnavailable => "MeshMonitor couldn't open its database file.\n\n\
+                 Make sure the disk isn't full, the data folder is writable by your user, and \
+                 no backup or antivirus program is locking meshmonitor.db."
+                .to_string(),
+            Diagnosis::NativeModuleBroken(module) => format!(
+                "Part of MeshMonitor{} is missing or doesn't match the bundled Node.js. \
+                 The installation is incomplete or was modified.\n\n\
+                 Repair or reinstall MeshMonitor Desktop; your data and settings are kept.",
+                module
+                    .as_ref()
+                    .map(|module| format!(" ({})", module))
+                    .unwrap_or_default()
+            ),
+        }
+    }
+
+    fn fix(&self) -> Fix {
+        match self {
+            Diagnosis::PortInUse(_) => Fix::Setting("webPort"),
+            Diagnosis::HostNotFound(_) => Fix::OpenWebUi,
+            Diagnosis::DatabaseUnavailable => Fix::RevealDatabase,
+            Diagnosis::NativeModuleBroken(_) => Fix::Repair,
+        }
+    }
+}
+
+impl Fix {
+    fn label(self) -> &'static str {
+        match self {
+            Fix::Setting(_) => "Open Settings",
+            Fix::OpenWebUi => "Open MeshMonitor",
+            Fix::RevealDatabase => "Show Database File",
+            Fix::Repair => "Repair MeshMonitor",
+        }
+    }
+
+    fn apply<R: Runtime>(self, app: &AppHandle<R>) {
+        match self {
+            Fix::Setting(field) => tray::show_setting(app, field),
+            Fix::OpenWebUi => {
+                let _ = tray::run_action(app, "open");
+            }
+            Fix::RevealDatabase => {
+                let _ = tray::run_action(app, "reveal_db");
+            }
+            Fix::Repair => repair::start(app),
+        }
+    }
+}
+
+/// The first known failure in `text`.
+pub fn classify(text: &str) -> Option<Diagnosis> {
+    text.lines().find_map(classify_line)
+}
+
+fn classify_line(line: &str) -> Option<Diagnosis> {
+    if line.contains("EADDRINUSE") {
+        // "listen EADDRINUSE: address already in use :::8080"
+        let port = line
+            .rsplit(':')
+            .next()
+            .and_then(|port| port.trim().parse().ok());
+        return Some(Diagnosis::PortInUse(port));
+    }
+    if let Some((_, rest)) = line.split_once("ENOTFOUND ") {
+        // "getaddrinfo ENOTFOUND meshtastic.local"
+        let host = rest.split_whitespace().next().unwrap_or_default();
+        return Some(Diagnosis::HostNotFound(host.to_string()));
+    }
+    if line.contains("SQLITE_CANTOPEN") {
+        return Some(Diagnosis::DatabaseUnavailable);
+    }
+    if line.contains("NODE_MODULE_VERSION") || line.contains("ERR_DLOPEN_FAILED") {
+        return Some(Diagnosis::NativeModuleBroken(None));
+    }
+    if let Some((_, rest)) = line.split_once("Cannot find module '") {
+        // Only bare package names; a missing relative file is a bug, not
+        // a damaged install
+        let module = rest.split('\'').next().unwrap_or_default();
+        if !module.starts_with('.') && !module.starts_with('/') {
+            return Some(Diagnosis::NativeModuleBroken(Some(module.to_string())));
+        }
+    }
+    None
+}
+
+/// How far `server-stderr.log` has been read. It is recreated each time
+/// the backend starts, which shows as the file being shorter than this.
+static SCANNED: Mutex<u64> = Mutex::new(0);
+
+/// Problems already explained this run.
+static EXPLAINED: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+/// Read what the backend wrote to stderr since the last scan and explain
+/// the first known problem in it, unless it was explained before.
+pub fn scan<R: Runtime>(app: &AppHandle<R>) {
+    let Ok(logs_path) = config::get_logs_path() else {
+        return;
+    };
+    let Ok(mut file) = std::fs::File::open(logs_path.join("server-stderr.log")) else {
+        return;
+    };
+    let len = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let text = {
+        let mut scanned = SCANNED.lock().unwrap();
+        if len < *scanned {
+            *scanned = 0;
+        }
+        let start = (*scanned).max(len.saturating_sub(MAX_SCAN));
+        let mut bytes = Vec::new();
+        if file.seek(SeekFrom::Start(start)).is_err()
+            || file.take(len - start).read_to_end(&mut bytes).is_err()
+        {
+            return;
+        }
+        *scanned = len;
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    let Some(diagnosis) = classify(&text) else {
+        return;
+    };
+    if !EXPLAINED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashSet::new)
+        .insert(diagnosis.kind())
+    {
+        return;
+    }
+    log_to_file(
+        &logs_path,
+        &format!("Recognized backend error: {:?}", diagnosis),
+    );
+    explain(app, &diagnosis);
+}
+
+fn explain<R: Runtime>(app: &AppHandle<R>, diagnosis: &Diagnosis) {
+    let fix = diagnosis.fix();
+    let handle = app.clone();
+    app.dialog()
+        .message(diagnosis.message())
+        .title(diagnosis.title())
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            fix.label().to_string(),
+            "Close".to_string(),
+        ))
+        .show(move |apply| {
+            if apply {
+                fix.apply(&handle);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classifies_known_failures() {
+        assert_eq!(
+            classify("Error: listen EADDRINUSE: address already in use :::8080\n    at Server"),
+            Some(Diagnosis::PortInUse(Some(8080)))
+        );
+        assert_eq!(
+            classify("[ERROR] Connection failed: Error: getaddrinfo ENOTFOUND meshtastic.local"),
+            Some(Diagnosis::HostNotFound("meshtastic.local".to_string()))
+        );
+        assert_eq!(
+            classify("SqliteError: unable to open database file\n  code: 'SQLITE_CANTOPEN'"),
+            Some(Diagnosis::DatabaseUnavailable)
+        );
+        assert_eq!(
+            classify("Error: Cannot find module 'better-sqlite3'\nRequire stack:"),
+            Some(Diagnosis::NativeModuleBroken(Some(
+                "better-sqlite3".to_string()
+            )))
+        );
+        assert_eq!(
+            classify(
+                "was compiled against a different Node.js version using\nNODE_MODULE_VERSION 115."
+            ),
+            Some(Diagnosis::NativeModuleBroken(None))
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_output() {
+        assert_eq!(classify("[WARN] Slow query (1200 ms)\n"), None);
+        assert_eq!(
+            classify("Error: Cannot find module './routes/missing.js'"),
+            None
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! External backend mode (`Config::external_backend`), for a MeshMonitor
+//! the desktop doesn't run itself, such as the official Docker image
+//! publishing its port on this computer. No process is spawned, stopped or
+//! restarted; instead the health check on `web_port` is polled, the tray
+//! tooltip says when the backend is down, and a notification is raised when
+//! it stops answering and when it's back. The rest of the app (web UI,
+//! notifications, status strip) talks to it like to its own backend.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::config::Config;
+use crate::tray::{TRAY_ID, TRAY_TOOLTIP};
+use crate::{api, attention, log, notifications, uptime};
+
+/// How often the backend's health is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Failed checks in a row before the backend counts as down (30s), so a
+/// container restart or a busy moment doesn't raise an alert.
+const MISSED_CHECK_LIMIT: u32 = 3;
+
+/// Guards against a second monitor thread.
+static MONITORING: AtomicBool = AtomicBool::new(false);
+
+/// A change in the backend's availability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Change {
+    /// Answering, for the first time since monitoring began.
+    Up,
+    /// Answering again after being down.
+    Back,
+    Down,
+}
+
+/// What the checks so far say about the backend.
+#[derive(Debug, Default)]
+struct Availability {
+    /// `None` until the first verdict.
+    up: Option<bool>,
+    missed: u32,
+}
+
+impl Availability {
+    /// Record one health check; returns the change it caused, if any.
+    fn observe(&mut self, healthy: bool) -> Option<Change> {
+        if healthy {
+            self.missed = 0;
+            return match self.up.replace(true) {
+                None => Some(Change::Up),
+                Some(false) => Some(Change::Back),
+                Some(true) => None,
+            };
+        }
+        self.missed += 1;
+        (self.missed >= MISSED_CHECK_LIMIT && self.up != Some(false)).then(|| {
+            self.up = Some(false);
+            Change::Down
+        })
+    }
+}
+
+/// Start monitoring the external backend. Safe to call more than once;
+/// monitoring ends by itself when the mode is turned off.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if MONITORING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut availability = Availability::default();
+        log("Monitoring the external backend");
+        loop {
+            let config = Config::load().unwrap_or_default();
+            if !config.external_backend {
+                break;
+            }
+            let healthy = api::check_health(config.web_port, &config);
+            match availability.observe(healthy.is_ok()) {
+                Some(Change::Up) => backend_up(&app, &config, false),
+                Some(Change::Back) => backend_up(&app, &config, true),
+                Some(Change::Down) => {
+                    backend_down(&app, &config, &healthy.err().unwrap_or_default())
+                }
+                None => {}
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+        log("Stopped monitoring the external backend");
+        attention::refresh(&app);
+        MONITORING.store(false, Ordering::SeqCst);
+    });
+}
+
+fn backend_up<R: Runtime>(app: &AppHandle<R>, config: &Config, was_down: bool) {
+    let message = format!("External backend answering on port {}", config.web_port);
+    log(&message);
+    uptime::record(uptime::EventKind::BackendStarted, &message);
+    attention::refresh(app);
+    if was_down {
+        notifications::notify(
+            app,
+            "MeshMonitor backend is back",
+            &format!(
+                "The backend on port {} is answering again.",
+                config.web_port
+            ),
+            None,
+        );
+    }
+}
+
+fn backend_down<R: Runtime>(app: &AppHandle<R>, config: &Config, error: &str) {
+    let message = format!(
+        "External backend on port {} stopped answering: {}",
+        config.web_port, error
+    );
+    log(&message);
+    uptime::record(uptime::EventKind::BackendCrashed, &message);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("{} — Backend down", TRAY_TOOLTIP)));
+    }
+    notifications::notify(
+        app,
+        "MeshMonitor backend is down",
+        &format!(
+            "Nothing answers on port {}. Check that the MeshMonitor container is running.",
+            config.web_port
+        ),
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_down_after_missed_checks_and_up_at_once() {
+        let mut availability = Availability::default();
+        assert_eq!(availability.observe(true), Some(Change::Up));
+        assert_eq!(availability.observe(true), None);
+        assert_eq!(availability.observe(false), None);
+        assert_eq!(availability.observe(false), None);
+        assert_eq!(availability.observe(false), Some(Change::Down));
+        assert_eq!(availability.observe(false), None);
+        assert_eq!(availability.observe(true), Some(Change::Back));
+
+        // A backend that was never up is reported down too
+        let mut availability = Availability::default();
+        availability.observe(false);
+        availability.observe(false);
+        assert_eq!(availability.observe(false), Some(Change::Down));
+    }
+}
@@ -0,0 +1,225 @@
+//! Status page served on the web port while the backend is down, so a
+//! browser pointed at MeshMonitor explains what is going on instead of
+//! failing to connect. It lets go of the port just before the backend is
+//! spawned and refreshes itself, so the web UI comes back on its own.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::{config, log, BackendState};
+
+/// How often the page reloads itself, in seconds.
+const REFRESH_SECONDS: u32 = 5;
+
+/// How often the listener checks whether it was stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a client gets to send its request head.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What the page says.
+#[derive(Debug, Clone, PartialEq)]
+struct Notice {
+    headline: String,
+    detail: Option<String>,
+}
+
+/// A running status page; dropping it frees the port.
+pub struct Fallback {
+    port: u16,
+    notice: Arc<Mutex<Notice>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Fallback {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Serve the status page on `port`, or update the one already served. Does
+/// nothing when the port is taken, e.g. by a backend that is still running,
+/// or belongs to an external backend.
+pub fn show(state: &BackendState, port: u16, headline: &str, detail: Option<&str>) {
+    if config::Config::load().is_ok_and(|config| config.external_backend) {
+        return;
+    }
+    let notice = Notice {
+        headline: headline.to_string(),
+        detail: detail.map(str::to_string),
+    };
+    let mut fallback = state.fallback.lock().unwrap();
+    if let Some(running) = fallback.as_ref().filter(|f| f.port == port) {
+        *running.notice.lock().unwrap() = notice;
+        return;
+    }
+    *fallback = None;
+    let listener = match TcpListener::bind(("0.0.0.0", port))
+        .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+    {
+        Ok(listener) => listener,
+        Err(e) => {
+            log(&format!("Status page not served on port {}: {}", port, e));
+            return;
+        }
+    };
+    log(&format!("Serving the status page on port {}", port));
+
+    let notice = Arc::new(Mutex::new(notice));
+    let stop = Arc::new(AtomicBool::new(false));
+    let shared = notice.clone();
+    let stopped = stop.clone();
+    let thread = std::thread::spawn(move || {
+        while !stopped.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((client, _)) => {
+                    let notice = shared.lock().unwrap().clone();
+                    std::thread::spawn(move || answer(client, &notice));
+                }
+                Err(_) => std::thread::sleep(POLL_INTERVAL),
+            }
+        }
+    });
+    *fallback = Some(Fallback {
+        port,
+        notice,
+        stop,
+        thread: Some(thread),
+    });
+}
+
+/// Port the status page is served on, if it is.
+pub fn serving_on(state: &BackendState) -> Option<u16> {
+    state.fallback.lock().unwrap().as_ref().map(|f| f.port)
+}
+
+/// Stop serving the status page. The port is free once this returns.
+pub fn hide(state: &BackendState) {
+    if state.fallback.lock().unwrap().take().is_some() {
+        log("Stopped serving the status page");
+    }
+}
+
+fn answer(mut client: TcpStream, notice: &Notice) {
+    let _ = client.set_nonblocking(false);
+    let _ = client.set_read_timeout(Some(READ_TIMEOUT));
+    let mut head = [0u8; 2048];
+    let read = client.read(&mut head).unwrap_or(0);
+    let request = String::from_utf8_lossy(&head[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let _ = client.write_all(response(path, notice).as_bytes());
+}
+
+/// A 503 with the notice: JSON in the v1 API's error shape for API paths,
+/// a page for anything else.
+fn response(path: &str, notice: &Notice) -> String {
+    let (content_type, body) = if path.starts_with("/api/") {
+        let message = match &notice.detail {
+            Some(detail) => format!("{} ({})", notice.headline, detail),
+            None => notice.headline.clone(),
+        };
+        let body = serde_json::json!({
+            "success": false,
+            "error": "Service Unavailable",
+            "message": message,
+        });
+        ("application/json", body.to_string())
+    } else {
+        ("text/html; charset=utf-8", page(notice))
+    };
+    format!(
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Retry-After: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        content_type,
+        body.len(),
+        REFRESH_SECONDS,
+        body
+    )
+}
+
+fn page(notice: &Notice) -> String {
+    let detail = notice
+        .detail
+        .as_deref()
+        .map(|detail| format!("<p class=\"detail\">{}</p>", escape(detail)))
+        .unwrap_or_default();
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="{refresh}">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>MeshMonitor</title>
+<style>
+body {{ font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif; background: linear-gradient(135deg, #1a1a2e 0%, #16213e 100%); color: #e4e4e7; display: flex; align-items: center; justify-content: center; min-height: 100vh; margin: 0; }}
+main {{ max-width: 36rem; padding: 2rem; text-align: center; }}
+h1 {{ font-size: 1.4rem; color: #67e8f9; }}
+.detail {{ color: #fca5a5; word-break: break-word; }}
+.hint {{ color: #a1a1aa; font-size: 0.9rem; }}
+</style>
+</head>
+<body>
+<main role="status">
+<h1>{headline}</h1>
+{detail}
+<p class="hint">This page reloads every {refresh} seconds and shows MeshMonitor again once the backend is back.</p>
+</main>
+</body>
+</html>
+"#,
+        refresh = REFRESH_SECONDS,
+        headline = escape(&notice.headline),
+        detail = detail,
+    )
+}
+
+/// `text` made safe to put in HTML, including attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notice() -> Notice {
+        Notice {
+            headline: "MeshMonitor backend is restarting (attempt 3/5)".to_string(),
+            detail: Some("Last error: exit status: 1 <node>".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_page_escapes_the_notice() {
+        let response = response("/nodes", &notice());
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(response.contains("Content-Type: text/html"));
+        assert!(response.contains("<h1>MeshMonitor backend is restarting (attempt 3/5)</h1>"));
+        assert!(response.contains("exit status: 1 &lt;node&gt;"));
+    }
+
+    #[test]
+    fn test_api_paths_get_json() {
+        let response = response("/api/v1/nodes", &notice());
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"], "Service Unavailable");
+        assert_eq!(
+            json["message"],
+            "MeshMonitor backend is restarting (attempt 3/5) (Last error: exit status: 1 <node>)"
+        );
+    }
+}
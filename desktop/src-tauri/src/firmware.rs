@@ -0,0 +1,721 @@
+//! Guided firmware update for the node attached to this computer. The
+//! backend compares the node's firmware with Meshtastic releases; for ESP32
+//! boards on USB the desktop then downloads the release, stops the backend so
+//! nothing holds the node while it is written, flashes it with esptool and
+//! starts the backend again. Other boards are pointed at the Meshtastic web
+//! flasher.
+
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::api::{BackendApi, FirmwareInfo};
+use crate::config::{self, Config, ConnectionType};
+use crate::progress::Progress;
+use crate::{
+    audit, fallback, log, outbound, show_start_failure, start_backend, stop_backend, BackendState,
+};
+
+const TITLE: &str = "Update Node Firmware";
+
+const WEB_FLASHER_URL: &str = "https://flasher.meshtastic.org";
+
+/// Update images go to the app partition, leaving the bootloader, partition
+/// table and the node's settings alone.
+const APP_OFFSET: &str = "0x10000";
+
+/// The baud rate Meshtastic's own update script uses; every USB-serial chip
+/// on supported boards manages it.
+const FLASH_BAUD: &str = "115200";
+
+/// How long a stalled release download may sit without receiving data.
+const DOWNLOAD_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the firmware check found.
+#[derive(Debug, Clone, PartialEq)]
+enum Plan {
+    /// Nothing to do; the message says why.
+    Nothing(String),
+    /// A newer release exists but this board can't be flashed from here.
+    WebFlasher(String),
+    Flash(Update),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Update {
+    hardware: String,
+    board: String,
+    from: String,
+    to: String,
+    download_url: String,
+    size: u64,
+}
+
+/// Check the node's firmware and walk the user through updating it. Runs in
+/// the background; every outcome is reported with a dialog.
+pub fn check<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let config = Config::load().unwrap_or_default();
+        let Some(backend) = BackendApi::from_config(&config) else {
+            notify(
+                &app,
+                MessageDialogKind::Warning,
+                "Checking the node's firmware needs an API token. Create one in the web UI \
+                 and enter it in MeshMonitor Desktop settings.",
+            );
+            return;
+        };
+        match backend.firmware().map(|info| plan(&info)) {
+            Ok(Plan::Nothing(message)) => notify(&app, MessageDialogKind::Info, &message),
+            Ok(Plan::WebFlasher(message)) => offer_web_flasher(&app, message),
+            Ok(Plan::Flash(update)) => confirm(&app, update),
+            Err(e) => notify(
+                &app,
+                MessageDialogKind::Error,
+                &format!("Couldn't check the node's firmware: {}", e),
+            ),
+        }
+    });
+}
+
+fn plan(info: &FirmwareInfo) -> Plan {
+    let hardware = info
+        .hardware
+        .clone()
+        .unwrap_or_else(|| "this hardware".to_string());
+    let Some(current) = info.current_version.clone() else {
+        return Plan::Nothing(
+            "The node hasn't reported its firmware version yet. Try again once MeshMonitor \
+             is connected to it."
+                .to_string(),
+        );
+    };
+    let (Some(board), Some(latest)) = (info.board.clone(), info.latest.as_ref()) else {
+        return Plan::Nothing(format!(
+            "No Meshtastic release has firmware for {}. The node runs {}.",
+            hardware, current
+        ));
+    };
+    if !info.update_available {
+        return Plan::Nothing(format!(
+            "Your node is up to date: it runs firmware {}, the newest release for {}.",
+            current, hardware
+        ));
+    }
+    let esp32 = info
+        .platform
+        .as_deref()
+        .is_some_and(|platform| platform.starts_with("esp32"));
+    if !esp32 {
+        return Plan::WebFlasher(format!(
+            "Firmware {} is available for your {} (it runs {}).\n\nMeshMonitor can only \
+             update ESP32 boards itself. Use the Meshtastic Web Flasher for this one.",
+            latest.version, hardware, current
+        ));
+    }
+    Plan::Flash(Update {
+        hardware,
+        board,
+        from: current,
+        to: latest.version.clone(),
+        download_url: latest.asset.download_url.clone(),
+        size: latest.asset.size,
+    })
+}
+
+fn offer_web_flasher<R: Runtime>(app: &AppHandle<R>, message: String) {
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title(TITLE)
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Open Web Flasher".to_string(),
+            "Close".to_string(),
+        ))
+        .show(move |open| {
+            if open {
+                let _ = handle.opener().open_url(WEB_FLASHER_URL, None::<&str>);
+            }
+        });
+}
+
+fn confirm<R: Runtime>(app: &AppHandle<R>, update: Update) {
+    let message = format!(
+        "Update your {} from firmware {} to {}?\n\n\
+         Connect the node to this computer by USB and make sure it's the only ESP32 \
+         device plugged in. MeshMonitor stops while the firmware is written and starts \
+         again afterwards. Don't unplug the node until the update has finished.",
+        update.hardware, update.from, update.to
+    );
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title(TITLE)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Update Firmware".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |go| {
+            if go {
+                std::thread::spawn(move || run(&handle, update));
+            }
+        });
+}
+
+fn run<R: Runtime>(app: &AppHandle<R>, update: Update) {
+    let progress = Progress::start(app, "Firmware update");
+    match install(app, &update, &progress) {
+        Ok(()) => {
+            let detail = format!("{} → {}", update.from, update.to);
+            audit::record(audit::Source::Dialog, "firmware_updated", &detail);
+            notify(
+                app,
+                MessageDialogKind::Info,
+                &format!(
+                    "Your node now runs firmware {}. It restarts by itself; MeshMonitor \
+                     reconnects once it's back.",
+                    update.to
+                ),
+            );
+        }
+        Err(e) => {
+            progress.fail(&e);
+            notify(
+                app,
+                MessageDialogKind::Error,
+                &format!("The firmware update failed: {}", e),
+            );
+        }
+    }
+}
+
+/// Download, extract and flash. The backend is only stopped once the
+/// firmware image is ready, and is started again whether or not flashing
+/// worked.
+fn install<R: Runtime>(
+    app: &AppHandle<R>,
+    update: &Update,
+    progress: &Progress<R>,
+) -> Result<(), String> {
+    let esptool = Esptool::find().ok_or_else(|| {
+        "esptool wasn't found. Install it with `pip install esptool` and try again.".to_string()
+    })?;
+
+    let zip = download(&update.download_url, update.size, progress)?;
+    let names = zip_names(&zip)?;
+    let name = firmware_binary(&names, &update.board).ok_or_else(|| {
+        format!(
+            "The release doesn't contain update firmware for the {} board.",
+            update.board
+        )
+    })?;
+    let image = zip_entry(&zip, name)?;
+    let dir = config::get_data_path()?.join("firmware");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create firmware directory: {}", e))?;
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let image_path = dir.join(file_name);
+    std::fs::write(&image_path, image)
+        .map_err(|e| format!("Failed to save {}: {}", file_name, e))?;
+
+    log(&format!(
+        "Stopping backend to flash {} with {}",
+        file_name, esptool
+    ));
+    let state = app.state::<BackendState>();
+    stop_backend(&state);
+    // A serial bridge holds the node's port; it is started again with the
+    // backend
+    if state.bridge.lock().unwrap().take().is_some() {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    let config = Config::load().unwrap_or_default();
+    let port = (config.connection_type == ConnectionType::Serial)
+        .then(|| config.serial_port.trim())
+        .filter(|port| !port.is_empty());
+    fallback::show(
+        &state,
+        config.web_port,
+        "MeshMonitor is paused while the node's firmware is updated",
+        None,
+    );
+    let flashed = esptool.write_flash(&image_path, port, progress);
+    let restarted = start_backend(app)
+        .map(|child| {
+            *state.process.lock().unwrap() = Some(child);
+        })
+        .inspect_err(|e| show_start_failure(app, &config, e));
+
+    flashed?;
+    restarted.map_err(|e| {
+        format!(
+            "the firmware was written, but MeshMonitor failed to start again: {}",
+            e
+        )
+    })
+}
+
+/// Fetch a release asset into memory. Download is the first half of the
+/// progress bar, flashing the second.
+fn download<R: Runtime>(url: &str, size: u64, progress: &Progress<R>) -> Result<Vec<u8>, String> {
+    let client =
+        outbound::Client::new(ureq::AgentBuilder::new().timeout_read(DOWNLOAD_READ_TIMEOUT));
+    let response = client
+        .get(url)?
+        .call()
+        .map_err(|e| format!("Failed to download the firmware: {}", e))?;
+    let mut reader = response.into_reader();
+    let mut bytes = Vec::with_capacity(size as usize);
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let read = reader
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to download the firmware: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+        progress.set(bytes.len() as u64, size * 2);
+    }
+    if size > 0 && bytes.len() as u64 != size {
+        return Err(format!(
+            "The firmware download is incomplete ({} of {} bytes).",
+            bytes.len(),
+            size
+        ));
+    }
+    Ok(bytes)
+}
+
+/// The update image for `board` among the files of a release archive:
+/// `firmware-<board>-<x.y.z.hash>.bin`. Factory images (which also erase the
+/// node's settings) and other boards' images don't match.
+fn firmware_binary<'a>(names: &'a [String], board: &str) -> Option<&'a str> {
+    let prefix = format!("firmware-{}-", board);
+    names.iter().map(String::as_str).find(|name| {
+        let file = name.rsplit('/').next().unwrap_or(name);
+        file.strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".bin"))
+            .is_some_and(is_release_version)
+    })
+}
+
+/// `x.y.z.hash`, as in `2.7.1.a1b2c3d`.
+fn is_release_version(version: &str) -> bool {
+    let parts: Vec<&str> = version.split('.').collect();
+    let [major, minor, patch, hash] = parts[..] else {
+        return false;
+    };
+    let number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    number(major)
+        && number(minor)
+        && number(patch)
+        && !hash.is_empty()
+        && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Percentage from an esptool progress line: `Writing at 0x00010000... (3 %)`
+/// (esptool 4) or `Writing [=====>    ]  34.5% 1024/2048 bytes` (esptool 5).
+fn flash_percent(line: &str) -> Option<u64> {
+    if !line.contains("Writing") {
+        return None;
+    }
+    let before = line[..line.rfind('%')?].trim_end();
+    let start = before
+        .rfind(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or(0, |i| i + 1);
+    let percent: f64 = before[start..].parse().ok()?;
+    Some((percent as u64).min(100))
+}
+
+/// How esptool is run on this machine: its own executable, or the Python
+/// module.
+struct Esptool {
+    program: &'static str,
+    args: &'static [&'static str],
+}
+
+impl std::fmt::Display for Esptool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        self.args.iter().try_for_each(|arg| write!(f, " {}", arg))
+    }
+}
+
+impl Esptool {
+    const CANDIDATES: &'static [Esptool] = &[
+        Esptool {
+            program: "esptool",
+            args: &[],
+        },
+        Esptool {
+            program: "esptool.py",
+            args: &[],
+        },
+        Esptool {
+            program: "python3",
+            args: &["-m", "esptool"],
+        },
+        Esptool {
+            program: "python",
+            args: &["-m", "esptool"],
+        },
+        Esptool {
+            program: "py",
+            args: &["-m", "esptool"],
+        },
+    ];
+
+    /// The first way of running esptool that answers `version`.
+    fn find() -> Option<&'static Esptool> {
+        Self::CANDIDATES.iter().find(|esptool| {
+            esptool
+                .command()
+                .arg("version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        })
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(self.program);
+        cmd.args(self.args);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+        cmd
+    }
+
+    /// Write `image` to the app partition of the ESP32 on `port`, or the one
+    /// esptool finds on USB, reporting its progress.
+    fn write_flash<R: Runtime>(
+        &self,
+        image: &Path,
+        port: Option<&str>,
+        progress: &Progress<R>,
+    ) -> Result<(), String> {
+        let mut cmd = self.command();
+        if let Some(port) = port {
+            cmd.args(["--port", port]);
+        }
+        let mut child = cmd
+            .args([
+                "--chip",
+                "auto",
+                "--baud",
+                FLASH_BAUD,
+                "write_flash",
+                APP_OFFSET,
+            ])
+            .arg(image)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run esptool: {}", e))?;
+
+        // esptool 5 logs errors to stderr; drain it so it can't block
+        let mut stderr = child.stderr.take();
+        let errors = std::thread::spawn(move || {
+            let mut text = String::new();
+            if let Some(stderr) = stderr.as_mut() {
+                let _ = stderr.read_to_string(&mut text);
+            }
+            text
+        });
+
+        // Progress lines are redrawn with carriage returns
+        let mut output = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            let mut line = Vec::new();
+            for byte in BufReader::new(stdout).bytes().map_while(Result::ok) {
+                if byte == b'\r' || byte == b'\n' {
+                    let text = String::from_utf8_lossy(&line).to_string();
+                    if let Some(percent) = flash_percent(&text) {
+                        progress.set(100 + percent, 200);
+                    }
+                    if !text.trim().is_empty() {
+                        output.push(text);
+                    }
+                    line.clear();
+                } else {
+                    line.push(byte);
+                }
+            }
+        }
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to run esptool: {}", e))?;
+        let errors = errors.join().unwrap_or_default();
+
+        log(&format!(
+            "esptool exited with {}: {}",
+            status,
+            output.last().map(String::as_str).unwrap_or("")
+        ));
+        if status.success() {
+            return Ok(());
+        }
+        let reason = output
+            .iter()
+            .map(String::as_str)
+            .chain(errors.lines())
+            .find(|line| line.contains("fatal error"))
+            .or_else(|| errors.lines().rev().find(|line| !line.trim().is_empty()))
+            .map(|line| line.trim().to_string())
+            .unwrap_or_else(|| format!("esptool exited with {}", status));
+        Err(reason)
+    }
+}
+
+/// One file in a zip archive's central directory.
+struct ZipEntry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    size: usize,
+    header_offset: usize,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Read the central directory of a (non-Zip64) archive.
+fn zip_directory(zip: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let invalid = || "The firmware download isn't a valid zip archive.".to_string();
+    // The end-of-directory record sits at the end, before a comment of up
+    // to 64 KiB
+    let end = (zip.len().saturating_sub(22 + 0xFFFF)..=zip.len().saturating_sub(22))
+        .rev()
+        .find(|&at| u32_at(zip, at) == Some(0x0605_4b50))
+        .ok_or_else(invalid)?;
+    let count = u16_at(zip, end + 10).ok_or_else(invalid)? as usize;
+    let mut at = u32_at(zip, end + 16).ok_or_else(invalid)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if u32_at(zip, at) != Some(0x0201_4b50) {
+            return Err(invalid());
+        }
+        let field16 = |offset| u16_at(zip, at + offset).ok_or_else(invalid);
+        let field32 = |offset| u32_at(zip, at + offset).ok_or_else(invalid);
+        let name_len = field16(28)? as usize;
+        let extra_len = field16(30)? as usize;
+        let comment_len = field16(32)? as usize;
+        let name = zip.get(at + 46..at + 46 + name_len).ok_or_else(invalid)?;
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(name).to_string(),
+            method: field16(10)?,
+            compressed_size: field32(20)? as usize,
+            size: field32(24)? as usize,
+            header_offset: field32(42)? as usize,
+        });
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Names of the files in a zip archive.
+fn zip_names(zip: &[u8]) -> Result<Vec<String>, String> {
+    Ok(zip_directory(zip)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect())
+}
+
+/// The contents of one file in a zip archive (stored or deflated).
+fn zip_entry(zip: &[u8], name: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("{} is damaged in the firmware download.", name);
+    let entry = zip_directory(zip)?
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| format!("{} isn't in the firmware download.", name))?;
+    let at = entry.header_offset;
+    if u32_at(zip, at) != Some(0x0403_4b50) {
+        return Err(invalid());
+    }
+    let name_len = u16_at(zip, at + 26).ok_or_else(invalid)? as usize;
+    let extra_len = u16_at(zip, at + 28).ok_or_else(invalid)? as usize;
+    let start = at + 30 + name_len + extra_len;
+    let data = zip
+        .get(start..start + entry.compressed_size)
+        .ok_or_else(invalid)?;
+
+    let contents = match entry.method {
+        0 => data.to_vec(),
+        8 => {
+            let mut contents = Vec::with_capacity(entry.size);
+            flate2::read::DeflateDecoder::new(data)
+                .read_to_end(&mut contents)
+                .map_err(|_| invalid())?;
+            contents
+        }
+        method => {
+            return Err(format!(
+                "{} uses an unsupported compression method ({}).",
+                name, method
+            ))
+        }
+    };
+    if contents.len() != entry.size {
+        return Err(invalid());
+    }
+    Ok(contents)
+}
+
+fn notify<R: Runtime>(app: &AppHandle<R>, kind: MessageDialogKind, message: &str) {
+    app.dialog()
+        .message(message)
+        .title(TITLE)
+        .kind(kind)
+        .show(|_| {});
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{FirmwareAsset, FirmwareRelease};
+    use std::io::Write;
+
+    fn info(platform: &str, update_available: bool) -> FirmwareInfo {
+        FirmwareInfo {
+            current_version: Some("2.6.0.ccccccc".to_string()),
+            hardware: Some("Heltec V3".to_string()),
+            board: Some("heltec-v3".to_string()),
+            platform: Some(platform.to_string()),
+            latest: Some(FirmwareRelease {
+                version: "2.7.1.bbbbbbb".to_string(),
+                asset: FirmwareAsset {
+                    name: format!("firmware-{}-2.7.1.bbbbbbb.zip", platform),
+                    size: 1000,
+                    download_url: "https://example.invalid/firmware.zip".to_string(),
+                },
+            }),
+            update_available,
+        }
+    }
+
+    /// A zip archive with one stored and one deflated file.
+    fn sample_zip() -> Vec<u8> {
+        let files: [(&str, &[u8], bool); 2] = [
+            ("stored.txt", b"hello zip", false),
+            ("deflated.bin", &[7u8; 4096], true),
+        ];
+        let mut zip = Vec::new();
+        let mut directory = Vec::new();
+        for (name, contents, deflate) in files {
+            let data = if deflate {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(contents).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                contents.to_vec()
+            };
+            let method: u16 = if deflate { 8 } else { 0 };
+            let offset = zip.len() as u32;
+
+            zip.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            zip.extend_from_slice(&[20, 0, 0, 0]);
+            zip.extend_from_slice(&method.to_le_bytes());
+            zip.extend_from_slice(&[0; 8]); // time, date, crc
+            zip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            zip.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            zip.extend_from_slice(&[0, 0]);
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(&data);
+
+            directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            directory.extend_from_slice(&method.to_le_bytes());
+            directory.extend_from_slice(&[0; 8]);
+            directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = zip.len() as u32;
+        zip.extend_from_slice(&directory);
+        zip.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        zip.extend_from_slice(&[0; 4]);
+        zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&directory_offset.to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+        zip
+    }
+
+    #[test]
+    fn test_plan() {
+        let Plan::Flash(update) = plan(&info("esp32s3", true)) else {
+            panic!("expected a flash plan");
+        };
+        assert_eq!(update.board, "heltec-v3");
+        assert_eq!(update.to, "2.7.1.bbbbbbb");
+
+        assert!(matches!(plan(&info("esp32s3", false)), Plan::Nothing(_)));
+        assert!(matches!(plan(&info("nrf52840", true)), Plan::WebFlasher(_)));
+
+        let mut unknown = info("esp32s3", true);
+        unknown.current_version = None;
+        assert!(matches!(plan(&unknown), Plan::Nothing(_)));
+    }
+
+    #[test]
+    fn test_firmware_binary_skips_factory_and_other_boards() {
+        let names: Vec<String> = [
+            "firmware-heltec-v3-2.7.1.bbbbbbb.factory.bin",
+            "firmware-heltec-v3-extra-2.7.1.bbbbbbb.bin",
+            "bleota-s3.bin",
+            "firmware-heltec-v3-2.7.1.bbbbbbb.bin",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            firmware_binary(&names, "heltec-v3"),
+            Some("firmware-heltec-v3-2.7.1.bbbbbbb.bin")
+        );
+        assert_eq!(firmware_binary(&names, "tbeam"), None);
+    }
+
+    #[test]
+    fn test_flash_percent() {
+        assert_eq!(flash_percent("Writing at 0x00010000... (3 %)"), Some(3));
+        assert_eq!(
+            flash_percent("Writing [=====>      ]  34.5% 1024/2048 bytes..."),
+            Some(34)
+        );
+        assert_eq!(flash_percent("Hash of data verified."), None);
+        assert_eq!(flash_percent("Writing at 0x00010000..."), None);
+    }
+
+    #[test]
+    fn test_zip_entries() {
+        let zip = sample_zip();
+        assert_eq!(zip_names(&zip).unwrap(), vec!["stored.txt", "deflated.bin"]);
+        assert_eq!(zip_entry(&zip, "stored.txt").unwrap(), b"hello zip");
+        assert_eq!(zip_entry(&zip, "deflated.bin").unwrap(), vec![7u8; 4096]);
+        assert!(zip_entry(&zip, "missing.bin").is_err());
+        assert!(zip_names(b"not a zip").is_err());
+    }
+}
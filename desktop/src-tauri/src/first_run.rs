@@ -0,0 +1,137 @@
+//! The first-run wizard shown in the setup window: welcome, discovery of
+//! how the node is attached, a connection test, security, and finish. Which
+//! step the wizard is on is kept here rather than in the page and saved to
+//! `first_run.json` each time it moves, so a setup cut short by a crash or
+//! a closed window resumes at the step it reached. The page saves each
+//! step's settings to config.json as it's left, with `setup_completed`
+//! still false, and the file is removed once setup completes.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    #[default]
+    Welcome,
+    Discovery,
+    ConnectionTest,
+    Security,
+    Finish,
+}
+
+impl Step {
+    pub const ALL: [Step; 5] = [
+        Step::Welcome,
+        Step::Discovery,
+        Step::ConnectionTest,
+        Step::Security,
+        Step::Finish,
+    ];
+
+    fn index(self) -> usize {
+        Step::ALL.iter().position(|&step| step == self).unwrap()
+    }
+
+    pub fn next(self) -> Option<Step> {
+        Step::ALL.get(self.index() + 1).copied()
+    }
+
+    pub fn back(self) -> Option<Step> {
+        self.index().checked_sub(1).map(|index| Step::ALL[index])
+    }
+}
+
+/// Where the wizard is, as the setup page shows it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct State {
+    pub step: Step,
+    pub steps: [Step; 5],
+    /// Whether an earlier setup got this far before it was cut short.
+    pub resumed: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Progress {
+    step: Step,
+}
+
+fn progress_path() -> Option<PathBuf> {
+    let config_path = config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("first_run.json"))
+}
+
+fn load() -> Option<Progress> {
+    let content = std::fs::read_to_string(progress_path()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save(step: Step) -> Result<(), String> {
+    let path = progress_path().ok_or("No config folder for the setup progress")?;
+    let content = serde_json::to_string(&Progress { step }).map_err(|e| e.to_string())?;
+    std::fs::write(&path, content).map_err(|e| format!("Failed to save the setup progress: {}", e))
+}
+
+/// The step to show, resuming an interrupted setup.
+pub fn state() -> State {
+    let step = load().map(|progress| progress.step).unwrap_or_default();
+    State {
+        step,
+        steps: Step::ALL,
+        resumed: step != Step::Welcome,
+    }
+}
+
+/// Leave `from`, which must be the current step, for the next one. A page
+/// left open from before a resume can't skip steps this way.
+pub fn advance(from: Step) -> Result<State, String> {
+    move_from(from, Step::next)
+}
+
+/// Go back from `from` to the step before it.
+pub fn back(from: Step) -> Result<State, String> {
+    move_from(from, Step::back)
+}
+
+fn move_from(from: Step, to: impl Fn(Step) -> Option<Step>) -> Result<State, String> {
+    let current = state();
+    if current.step != from {
+        return Ok(current);
+    }
+    let Some(step) = to(from) else {
+        return Ok(current);
+    };
+    save(step)?;
+    Ok(State {
+        step,
+        resumed: false,
+        ..current
+    })
+}
+
+/// Forget the progress once setup has completed.
+pub fn clear() {
+    if let Some(path) = progress_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_run_in_order() {
+        assert_eq!(Step::Welcome.back(), None);
+        assert_eq!(Step::Welcome.next(), Some(Step::Discovery));
+        assert_eq!(Step::Discovery.next(), Some(Step::ConnectionTest));
+        assert_eq!(Step::Security.back(), Some(Step::ConnectionTest));
+        assert_eq!(Step::Finish.next(), None);
+        assert_eq!(
+            serde_json::to_string(&Step::ConnectionTest).unwrap(),
+            "\"connection_test\""
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Recent supervisor events for the tray's History submenu: crashes and
+//! hangs from the uptime history, and restarts, config reloads and node
+//! firmware updates from the audit log, newest first.
+
+use chrono::{DateTime, Local, TimeZone};
+
+use crate::locale::Locale;
+use crate::{audit, uptime};
+
+/// Audit entries read back when looking for recent events.
+const AUDIT_LOOKBACK: usize = 200;
+
+/// One event, as shown in the menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub at: DateTime<Local>,
+    pub what: String,
+}
+
+impl Entry {
+    /// Menu label: the time (with the date unless it's `today`) and what
+    /// happened, written for `locale`.
+    pub fn label(&self, locale: &Locale, today: chrono::NaiveDate) -> String {
+        let time = if self.at.date_naive() == today {
+            locale.time(&self.at)
+        } else {
+            format!(
+                "{} {}",
+                locale.day_month(self.at.date_naive()),
+                locale.time(&self.at)
+            )
+        };
+        format!("{}  {}", time, self.what)
+    }
+}
+
+/// The last `limit` events, newest first.
+pub fn recent(limit: usize) -> Vec<Entry> {
+    merge(&uptime::load(), &audit::recent(AUDIT_LOOKBACK), limit)
+}
+
+fn merge(events: &[uptime::Event], audit: &[audit::AuditEntry], limit: usize) -> Vec<Entry> {
+    let from_uptime = events.iter().filter_map(|event| {
+        let what = match event.kind {
+            uptime::EventKind::BackendCrashed => crash_summary(&event.detail),
+            uptime::EventKind::BackendHung => "Watchdog restart: stopped responding".to_string(),
+            _ => return None,
+        };
+        Some(Entry {
+            at: Local.timestamp_millis_opt(event.at).single()?,
+            what,
+        })
+    });
+    let from_audit = audit.iter().filter_map(|entry| {
+        let what = match (entry.action.as_str(), entry.source) {
+            // Crash and hang restarts are shown as the crash or hang
+            ("backend_restarted", audit::Source::Scheduler) => return None,
+            ("backend_restarted", _) => "Manual restart".to_string(),
+            ("config_reloaded", _) => "Restart: config.json reloaded".to_string(),
+            ("firmware_updated", _) => "Node firmware update".to_string(),
+            _ => return None,
+        };
+        Some(Entry {
+            at: DateTime::parse_from_rfc3339(&entry.timestamp)
+                .ok()?
+                .with_timezone(&Local),
+            what,
+        })
+    });
+    let mut entries: Vec<Entry> = from_uptime.chain(from_audit).collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.at));
+    entries.truncate(limit);
+    entries
+}
+
+/// "Crash: exit status: 1" from the watchdog's "... exited unexpectedly:
+/// exit status: 1".
+fn crash_summary(detail: &str) -> String {
+    match detail.rsplit_once("unexpectedly: ") {
+        Some((_, status)) => format!("Crash: {}", status),
+        None => "Crash".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audit_entry(at: DateTime<Local>, source: audit::Source, action: &str) -> audit::AuditEntry {
+        audit::AuditEntry {
+            timestamp: at.to_rfc3339(),
+            source,
+            action: action.to_string(),
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_merges_newest_first() {
+        let at = |hour| Local.with_ymd_and_hms(2026, 10, 14, hour, 0, 0).unwrap();
+        let events = [
+            uptime::Event {
+                at: at(1).timestamp_millis(),
+                kind: uptime::EventKind::BackendCrashed,
+                detail: "Backend (PID 42) exited unexpectedly: exit status: 1".to_string(),
+            },
+            uptime::Event {
+                at: at(2).timestamp_millis(),
+                kind: uptime::EventKind::BackendStarted,
+                detail: "PID 43".to_string(),
+            },
+            uptime::Event {
+                at: at(4).timestamp_millis(),
+                kind: uptime::EventKind::BackendHung,
+                detail: "PID 43".to_string(),
+            },
+        ];
+        let audit = [
+            audit_entry(at(1), audit::Source::Scheduler, "backend_restarted"),
+            audit_entry(at(3), audit::Source::Command, "backend_restarted"),
+            audit_entry(at(5), audit::Source::Command, "config_changed"),
+        ];
+        let whats: Vec<String> = merge(&events, &audit, 5)
+            .into_iter()
+            .map(|entry| entry.what)
+            .collect();
+        assert_eq!(
+            whats,
+            [
+                "Watchdog restart: stopped responding",
+                "Manual restart",
+                "Crash: exit status: 1",
+            ]
+        );
+        assert_eq!(merge(&events, &audit, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_label_shows_the_date_before_today() {
+        let entry = Entry {
+            at: Local.with_ymd_and_hms(2026, 10, 13, 3, 12, 0).unwrap(),
+            what: "Manual restart".to_string(),
+        };
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let us = Locale::parse("en-US");
+        assert_eq!(entry.label(&us, today), "Oct 13 3:12 AM  Manual restart");
+        assert_eq!(
+            entry.label(&Locale::parse("de-DE"), entry.at.date_naive()),
+            "03:12  Manual restart"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Deferring heavy scheduled work while the computer is in use. With
+//! `Config::maintenance_idle_minutes` set, the node configuration backup and
+//! message export schedulers wait until there has been no keyboard or mouse
+//! input for that long and the computer is on AC power, so they don't slow
+//! the UI down or drain the battery. Work deferred for `MAX_DEFERRAL` runs
+//! anyway.
+//!
+//! Idle time comes from GetLastInputInfo on Windows, the HID system's idle
+//! time on macOS and the GNOME or freedesktop screensaver idle monitor on
+//! Linux. Where it or the power source can't be read, it doesn't hold work
+//! back.
+
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::log;
+
+/// Longest a due job waits for the computer to become idle.
+const MAX_DEFERRAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Tracks how long one scheduled job has been waiting.
+pub struct Gate {
+    job: &'static str,
+    deferred_since: Option<Instant>,
+}
+
+impl Gate {
+    /// `job` names the work in the log, `node configuration backup`.
+    pub fn new(job: &'static str) -> Gate {
+        Gate {
+            job,
+            deferred_since: None,
+        }
+    }
+
+    /// Whether the due job may run now.
+    pub fn ready(&mut self, config: &Config) -> bool {
+        if config.maintenance_idle_minutes == 0 {
+            self.deferred_since = None;
+            return true;
+        }
+        let required = Duration::from_secs(u64::from(config.maintenance_idle_minutes) * 60);
+        let Some(reason) = reason_to_wait(required, idle_time(), on_ac_power()) else {
+            self.deferred_since = None;
+            return true;
+        };
+        let job = self.job;
+        let since = *self.deferred_since.get_or_insert_with(|| {
+            log(&format!("Deferring the {} because {}", job, reason));
+            Instant::now()
+        });
+        if since.elapsed() < MAX_DEFERRAL {
+            return false;
+        }
+        log(&format!(
+            "Running the {} after waiting a day for the computer to be idle",
+            job
+        ));
+        self.deferred_since = None;
+        true
+    }
+}
+
+/// Why heavy work should wait, or `None` when it may run. Unknown idle time
+/// or power source doesn't hold it back.
+fn reason_to_wait(
+    required: Duration,
+    idle: Option<Duration>,
+    on_ac: Option<bool>,
+) -> Option<&'static str> {
+    if on_ac == Some(false) {
+        Some("the computer is on battery")
+    } else if idle.is_some_and(|idle| idle < required) {
+        Some("the computer is in use")
+    } else {
+        None
+    }
+}
+
+/// Time since the last keyboard or mouse input.
+#[cfg(windows)]
+pub fn idle_time() -> Option<Duration> {
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both are milliseconds since boot and wrap after 49 days
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(u64::from(
+        now.wrapping_sub(info.dwTime),
+    )))
+}
+
+#[cfg(target_os = "macos")]
+pub fn idle_time() -> Option<Duration> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    // `"HIDIdleTime" = 1234567890`, in nanoseconds
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            key.contains("\"HIDIdleTime\"")
+                .then(|| value.trim().parse().ok())
+                .flatten()
+        })
+        .map(Duration::from_nanos)
+}
+
+#[cfg(target_os = "linux")]
+pub fn idle_time() -> Option<Duration> {
+    use zbus::blocking::Connection;
+
+    let conn = Connection::session().ok()?;
+    // GNOME, in milliseconds
+    let mutter: Option<u64> = conn
+        .call_method(
+            Some("org.gnome.Mutter.IdleMonitor"),
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            Some("org.gnome.Mutter.IdleMonitor"),
+            "GetIdletime",
+            &(),
+        )
+        .ok()
+        .and_then(|reply| reply.body().deserialize().ok());
+    if let Some(ms) = mutter {
+        return Some(Duration::from_millis(ms));
+    }
+    // KDE and others implementing the freedesktop screensaver, in milliseconds
+    let ms: u32 = conn
+        .call_method(
+            Some("org.freedesktop.ScreenSaver"),
+            "/org/freedesktop/ScreenSaver",
+            Some("org.freedesktop.ScreenSaver"),
+            "GetSessionIdleTime",
+            &(),
+        )
+        .ok()?
+        .body()
+        .deserialize()
+        .ok()?;
+    Some(Duration::from_millis(u64::from(ms)))
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+pub fn idle_time() -> Option<Duration> {
+    None
+}
+
+/// Whether the computer runs on AC power rather than its battery.
+#[cfg(windows)]
+pub fn on_ac_power() -> Option<bool> {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return None;
+    }
+    // 255 is unknown
+    match status.ACLineStatus {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn on_ac_power() -> Option<bool> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    // `Now drawing from 'AC Power'`
+    let text = String::from_utf8_lossy(&output.stdout);
+    if text.contains("'AC Power'") {
+        Some(true)
+    } else if text.contains("'Battery Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn on_ac_power() -> Option<bool> {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .map(|text| text.trim().to_string())
+            .unwrap_or_default()
+    };
+    let (mut mains, mut online, mut discharging) = (false, false, false);
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        match read(path.join("type")).as_str() {
+            "Mains" => {
+                mains = true;
+                online |= read(path.join("online")) == "1";
+            }
+            // USB-C ports are listed on desktops too, so only count one charging
+            "USB" => online |= read(path.join("online")) == "1",
+            "Battery" => discharging |= read(path.join("status")) == "Discharging",
+            _ => {}
+        }
+    }
+    if online {
+        Some(true)
+    } else if mains || discharging {
+        Some(false)
+    } else {
+        // A desktop without a battery, or nothing reported
+        None
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+pub fn on_ac_power() -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_to_wait() {
+        let required = Duration::from_secs(600);
+        let idle = |minutes: u64| Some(Duration::from_secs(minutes * 60));
+        assert_eq!(reason_to_wait(required, idle(15), Some(true)), None);
+        assert_eq!(
+            reason_to_wait(required, idle(2), Some(true)),
+            Some("the computer is in use")
+        );
+        assert_eq!(
+            reason_to_wait(required, idle(15), Some(false)),
+            Some("the computer is on battery")
+        );
+        assert_eq!(reason_to_wait(required, None, None), None);
+    }
+}
@@ -0,0 +1,246 @@
+//! Map snapshots of emergencies, for a visual record responders don't have
+//! to remember to take. When `Config::incident_snapshots` is on and a
+//! message contains one of `Config::incident_keywords` (or the alert bell
+//! the Meshtastic apps send), a PNG of the map centred on the sender's last
+//! known position is saved to the `incidents` folder, named for the time it
+//! arrived. The map is drawn from the tile cache's OpenStreetMap tiles, so it
+//! works offline where the area was downloaded; missing tiles are left grey.
+//! The sender, message and position are kept in the PNG's text fields.
+
+use std::path::PathBuf;
+
+use crate::config::{self, Config};
+use crate::tile_cache::{self, Tile, TILESETS};
+use crate::{log, tile_download};
+
+/// Zoom level of the snapshot, about street level.
+const ZOOM: u8 = 15;
+
+/// Width and height of the snapshot in pixels.
+const SIZE: u32 = 768;
+
+const TILE_SIZE: u32 = 256;
+
+/// Colour of a tile that couldn't be had.
+const MISSING: [u8; 4] = [0xdd, 0xdd, 0xdd, 0xff];
+
+const MARKER: [u8; 4] = [0xd3, 0x2f, 0x2f, 0xff];
+
+/// The character the Meshtastic apps' "alert bell" adds to a message.
+const BELL: char = '\u{7}';
+
+/// A message that counts as an emergency.
+#[derive(Debug, Clone)]
+pub struct Incident {
+    pub node_id: String,
+    pub node_name: String,
+    pub text: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Unix milliseconds.
+    pub timestamp: i64,
+}
+
+/// Whether `text` is an emergency under `config`.
+pub fn is_emergency(config: &Config, text: &str) -> bool {
+    if text.contains(BELL) {
+        return true;
+    }
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .collect();
+    config.incident_keywords.iter().any(|keyword| {
+        let keyword: Vec<String> = keyword.split_whitespace().map(str::to_lowercase).collect();
+        !keyword.is_empty() && words.windows(keyword.len()).any(|window| window == keyword)
+    })
+}
+
+/// Save the snapshot of `incident` in the background.
+pub fn capture(incident: Incident) {
+    std::thread::spawn(move || match save(&incident) {
+        Ok(path) => log(&format!(
+            "Emergency message from {}; map snapshot saved to {}",
+            incident.node_name,
+            path.display()
+        )),
+        Err(e) => log(&format!("Failed to save the map snapshot: {}", e)),
+    });
+}
+
+fn save(incident: &Incident) -> Result<PathBuf, String> {
+    let cache_dir = config::get_tile_cache_path()?;
+    let agent = tile_cache::agent();
+    let image = render(incident.longitude, incident.latitude, |tile| {
+        tile_cache::get(&agent, tile, &cache_dir).ok()
+    });
+
+    let dir = config::get_data_path()?.join("incidents");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create the incidents folder: {}", e))?;
+    let at = chrono::DateTime::from_timestamp_millis(incident.timestamp)
+        .unwrap_or_default()
+        .with_timezone(&chrono::Local);
+    let node: String = incident
+        .node_id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    let path = dir.join(format!("{}_{}.png", at.format("%Y-%m-%d_%H-%M-%S"), node));
+
+    let file = std::fs::File::create(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), SIZE, SIZE);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let text = [
+        (
+            "Title",
+            format!("Emergency message from {}", incident.node_name),
+        ),
+        ("Description", incident.text.replace(BELL, "")),
+        ("Creation Time", at.to_rfc3339()),
+        (
+            "Comment",
+            format!("{:.5}, {:.5}", incident.latitude, incident.longitude),
+        ),
+    ];
+    for (keyword, value) in text {
+        encoder
+            .add_itxt_chunk(keyword.to_string(), value)
+            .map_err(|e| e.to_string())?;
+    }
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// RGBA pixels of the `SIZE` square map centred on `lon`, `lat`, with a
+/// marker there.
+fn render(lon: f64, lat: f64, fetch: impl Fn(&Tile) -> Option<Vec<u8>>) -> Vec<u8> {
+    // OpenStreetMap, the web UI's default map
+    let osm = &TILESETS[0];
+    let (x, y) = tile_download::tile_position(lon, lat, ZOOM);
+    let (centre_x, centre_y) = (x * TILE_SIZE as f64, y * TILE_SIZE as f64);
+    let left = centre_x as i64 - (SIZE / 2) as i64;
+    let top = centre_y as i64 - (SIZE / 2) as i64;
+
+    let mut image = MISSING.repeat((SIZE * SIZE) as usize);
+    let tiles = |from: i64| {
+        from.div_euclid(TILE_SIZE as i64)..=(from + SIZE as i64 - 1).div_euclid(TILE_SIZE as i64)
+    };
+    for tile_y in tiles(top) {
+        for tile_x in tiles(left) {
+            let Some(tile) = u32::try_from(tile_x)
+                .ok()
+                .zip(u32::try_from(tile_y).ok())
+                .and_then(|(x, y)| Tile::new(osm, ZOOM, x, y))
+            else {
+                continue;
+            };
+            let Some(pixels) = fetch(&tile).and_then(|bytes| decode(&bytes)) else {
+                continue;
+            };
+            for row in 0..TILE_SIZE as i64 {
+                let image_y = tile_y * TILE_SIZE as i64 + row - top;
+                if !(0..SIZE as i64).contains(&image_y) {
+                    continue;
+                }
+                for column in 0..TILE_SIZE as i64 {
+                    let image_x = tile_x * TILE_SIZE as i64 + column - left;
+                    if !(0..SIZE as i64).contains(&image_x) {
+                        continue;
+                    }
+                    let from = ((row * TILE_SIZE as i64 + column) * 4) as usize;
+                    let to = ((image_y * SIZE as i64 + image_x) * 4) as usize;
+                    image[to..to + 4].copy_from_slice(&pixels[from..from + 4]);
+                }
+            }
+        }
+    }
+
+    // A ring around the position, so the map under it stays visible
+    let centre = (SIZE / 2) as i64;
+    for dy in -12i64..=12 {
+        for dx in -12i64..=12 {
+            let distance = ((dx * dx + dy * dy) as f64).sqrt();
+            if (7.0..=11.0).contains(&distance) || distance <= 2.0 {
+                let to = (((centre + dy) * SIZE as i64 + centre + dx) * 4) as usize;
+                image[to..to + 4].copy_from_slice(&MARKER);
+            }
+        }
+    }
+    image
+}
+
+/// A tile's RGBA pixels, when it's a `TILE_SIZE` square PNG.
+fn decode(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buf).ok()?;
+    if frame.width != TILE_SIZE || frame.height != TILE_SIZE {
+        return None;
+    }
+    let rgba = buf[..frame.buffer_size()]
+        .chunks_exact(frame.color_type.samples())
+        .flat_map(|px| match *px {
+            [g] => [g, g, g, 0xff],
+            [g, a] => [g, g, g, a],
+            [r, g, b] => [r, g, b, 0xff],
+            [r, g, b, a, ..] => [r, g, b, a],
+            _ => MISSING,
+        })
+        .collect();
+    Some(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_emergency() {
+        let config = Config::default();
+        assert!(is_emergency(&config, "SOS at the trailhead"));
+        assert!(is_emergency(&config, "Medical EMERGENCY, need help"));
+        assert!(is_emergency(&config, "\u{7}"));
+        assert!(!is_emergency(&config, "Heading to the sosban fach"));
+        let config = Config {
+            incident_keywords: vec!["man down".to_string()],
+            ..Config::default()
+        };
+        assert!(is_emergency(&config, "Man down near checkpoint 3"));
+        assert!(!is_emergency(&config, "SOS"));
+    }
+
+    #[test]
+    fn test_render_marks_the_position() {
+        // A uniformly blue tile
+        let mut tile = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut tile, TILE_SIZE, TILE_SIZE);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&[0, 0, 0xff].repeat((TILE_SIZE * TILE_SIZE) as usize))
+                .unwrap();
+        }
+        let image = render(-0.1276, 51.5072, |_| Some(tile.clone()));
+        let pixel = |x: u32, y: u32| {
+            let at = ((y * SIZE + x) * 4) as usize;
+            image[at..at + 4].to_vec()
+        };
+        assert_eq!(image.len(), (SIZE * SIZE * 4) as usize);
+        assert_eq!(pixel(SIZE / 2, SIZE / 2), MARKER);
+        assert_eq!(pixel(SIZE / 2 + 5, SIZE / 2), [0, 0, 0xff, 0xff]);
+        assert_eq!(pixel(0, 0), [0, 0, 0xff, 0xff]);
+
+        let image = render(-0.1276, 51.5072, |_| None);
+        assert_eq!(image[..4], MISSING);
+    }
+}
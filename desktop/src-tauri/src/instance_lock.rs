@@ -0,0 +1,33 @@
+//! `desktop.lock` in the data directory: present while the desktop app is
+//! running and removed as the last step of an orderly shutdown, so a lock
+//! left behind at launch means the previous run didn't exit cleanly.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config;
+
+fn lock_path() -> Result<PathBuf, String> {
+    Ok(config::get_data_path()?.join("desktop.lock"))
+}
+
+/// Take the lock for this run, recording our PID. Returns `true` if a lock
+/// from an earlier run was still present (it crashed or was killed).
+pub fn acquire() -> Result<bool, String> {
+    let path = lock_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let stale = path.exists();
+    fs::write(&path, std::process::id().to_string())
+        .map_err(|e| format!("Failed to write lock file: {}", e))?;
+    Ok(stale)
+}
+
+/// Release the lock at the end of an orderly shutdown.
+pub fn release() {
+    if let Ok(path) = lock_path() {
+        let _ = fs::remove_file(path);
+    }
+}
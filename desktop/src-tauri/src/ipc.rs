@@ -0,0 +1,237 @@
+//! Control channel between the desktop app and the Node backend. We listen
+//! on a local socket — a unix socket in the data directory, or a loopback
+//! TCP port on Windows — and pass its address to the backend as
+//! DESKTOP_IPC_ADDRESS. The backend connects back, proves it is the process
+//! we spawned with its DESKTOP_CONTROL_TOKEN, then pushes status updates and
+//! accepts commands (log level, config reload, quiesce) without a restart.
+//!
+//! Messages are newline-delimited JSON; see
+//! `src/server/services/desktopIpcService.ts` for the backend half.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::backend_record::BackendRecord;
+use crate::{config, log};
+
+#[cfg(unix)]
+type Stream = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+type Stream = std::net::TcpStream;
+
+/// A runtime command for the backend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "command", rename_all = "camelCase")]
+pub enum Command {
+    /// Change the backend's log level (trace, debug, info, warn, error).
+    SetLogLevel { level: String },
+    /// Apply new values for environment-derived settings.
+    ReloadConfig { env: HashMap<String, String> },
+    /// Shut down gracefully, closing Meshtastic connections and the database.
+    Quiesce,
+}
+
+/// Status pushed by the backend every few seconds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendStatus {
+    /// Milliseconds since the backend process started.
+    pub uptime: i64,
+    /// Resident memory, bytes.
+    #[serde(default)]
+    pub rss: u64,
+    /// CPU time used since the process started, milliseconds.
+    #[serde(default)]
+    pub cpu: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum BackendMessage {
+    Hello {
+        token: String,
+        pid: u32,
+    },
+    Status(BackendStatus),
+    Reply {
+        command: String,
+        ok: bool,
+        #[serde(default)]
+        error: Option<String>,
+    },
+}
+
+/// The authenticated backend connection and its PID.
+static CONNECTION: Mutex<Option<(u32, Stream)>> = Mutex::new(None);
+
+/// The latest status and when it arrived, by backend PID.
+static LAST_STATUS: Mutex<Option<(u32, Instant, BackendStatus)>> = Mutex::new(None);
+
+/// Address the listener is bound to, once started.
+static ADDRESS: Mutex<Option<String>> = Mutex::new(None);
+
+/// The listener's address in the form the backend expects: a socket path,
+/// or `tcp://127.0.0.1:<port>`. `None` if the listener isn't running.
+pub fn address() -> Option<String> {
+    ADDRESS.lock().unwrap().clone()
+}
+
+#[cfg(unix)]
+fn socket_path() -> Result<std::path::PathBuf, String> {
+    Ok(config::get_data_path()?.join("desktop.sock"))
+}
+
+/// Start listening for the backend. Safe to call more than once.
+pub fn start_listener() -> Result<String, String> {
+    if let Some(address) = address() {
+        return Ok(address);
+    }
+
+    #[cfg(unix)]
+    let (listener, address) = {
+        let path = socket_path()?;
+        // A socket file left by an earlier run blocks the bind
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path)
+            .map_err(|e| format!("Failed to create IPC socket: {}", e))?;
+        (listener, path.to_string_lossy().to_string())
+    };
+    #[cfg(windows)]
+    let (listener, address) = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| format!("Failed to create IPC listener: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read IPC listener port: {}", e))?
+            .port();
+        (listener, format!("tcp://127.0.0.1:{}", port))
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || handle_connection(stream));
+        }
+    });
+
+    *ADDRESS.lock().unwrap() = Some(address.clone());
+    Ok(address)
+}
+
+/// Remove the unix socket file at shutdown.
+pub fn stop_listener() {
+    #[cfg(unix)]
+    if ADDRESS.lock().unwrap().take().is_some() {
+        if let Ok(path) = socket_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn handle_connection(stream: Stream) {
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+    let mut lines = BufReader::new(stream).lines();
+
+    // The first message must carry the token of the backend we last spawned
+    let expected = BackendRecord::load().and_then(|record| record.control_token);
+    let pid = match lines
+        .next()
+        .and_then(Result::ok)
+        .map(|l| serde_json::from_str(&l))
+    {
+        Some(Ok(BackendMessage::Hello { token, pid })) if Some(&token) == expected.as_ref() => pid,
+        _ => return,
+    };
+    *CONNECTION.lock().unwrap() = Some((pid, writer));
+    log(&format!(
+        "Backend (PID {}) connected to the control channel",
+        pid
+    ));
+
+    for line in lines.map_while(Result::ok) {
+        match serde_json::from_str(&line) {
+            Ok(BackendMessage::Status(status)) => {
+                *LAST_STATUS.lock().unwrap() = Some((pid, Instant::now(), status));
+            }
+            Ok(BackendMessage::Reply {
+                command,
+                ok: false,
+                error,
+            }) => log(&format!(
+                "Backend rejected {}: {}",
+                command,
+                error.unwrap_or_default()
+            )),
+            _ => {}
+        }
+    }
+
+    let mut connection = CONNECTION.lock().unwrap();
+    if connection
+        .as_ref()
+        .is_some_and(|(current, _)| *current == pid)
+    {
+        *connection = None;
+    }
+}
+
+/// Send a command to the connected backend.
+pub fn send(command: &Command) -> Result<(), String> {
+    let mut connection = CONNECTION.lock().unwrap();
+    let (_, stream) = connection
+        .as_mut()
+        .ok_or_else(|| "Backend is not connected to the control channel".to_string())?;
+    let line = serde_json::to_string(command)
+        .map_err(|e| format!("Failed to serialize IPC command: {}", e))?;
+    writeln!(stream, "{}", line).map_err(|e| format!("Failed to send IPC command: {}", e))
+}
+
+/// Whether backend `pid` has pushed a status update within `window`.
+pub fn heard_from_within(pid: u32, window: Duration) -> bool {
+    LAST_STATUS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|(from, at, _)| *from == pid && at.elapsed() <= window)
+}
+
+/// The latest status pushed by backend `pid`.
+pub fn last_status(pid: u32) -> Option<BackendStatus> {
+    LAST_STATUS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|(from, _, _)| *from == pid)
+        .map(|(_, _, status)| status.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_format_matches_backend() {
+        let json = serde_json::to_string(&Command::SetLogLevel {
+            level: "debug".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"command":"setLogLevel","level":"debug"}"#);
+        assert_eq!(
+            serde_json::to_string(&Command::Quiesce).unwrap(),
+            r#"{"command":"quiesce"}"#
+        );
+
+        let status: BackendMessage =
+            serde_json::from_str(r#"{"type":"status","uptime":5000,"rss":1024}"#).unwrap();
+        assert!(matches!(
+            status,
+            BackendMessage::Status(BackendStatus { uptime: 5000, .. })
+        ));
+        let hello: BackendMessage =
+            serde_json::from_str(r#"{"type":"hello","token":"abc","pid":42}"#).unwrap();
+        assert!(matches!(hello, BackendMessage::Hello { pid: 42, .. }));
+    }
+}
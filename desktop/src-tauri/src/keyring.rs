@@ -0,0 +1,343 @@
+//! Secrets kept in the OS credential store rather than `config.json`, such
+//! as tile server API keys: Windows Credential Manager, the macOS login
+//! keychain, or the Secret Service (GNOME Keyring, KWallet) over D-Bus on
+//! Linux. Each secret is stored under the service `MeshMonitor` and an
+//! account name chosen by the caller.
+
+/// Service name the secrets are filed under.
+const SERVICE: &str = "MeshMonitor";
+
+/// Store `secret` for `account`, replacing any stored before.
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    platform::set(account, secret)
+}
+
+/// The secret stored for `account`, if there is one.
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    platform::get(account)
+}
+
+/// Forget the secret stored for `account`; there needn't be one.
+pub fn delete(account: &str) -> Result<(), String> {
+    platform::delete(account)
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::SERVICE;
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_FOUND};
+    use windows_sys::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE,
+        CRED_TYPE_GENERIC,
+    };
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(Some(0)).collect()
+    }
+
+    fn target(account: &str) -> Vec<u16> {
+        wide(&format!("{}:{}", SERVICE, account))
+    }
+
+    pub fn set(account: &str, secret: &str) -> Result<(), String> {
+        let mut target = target(account);
+        let mut user = wide(account);
+        let mut blob = secret.as_bytes().to_vec();
+        let credential = CREDENTIALW {
+            Type: CRED_TYPE_GENERIC,
+            TargetName: target.as_mut_ptr(),
+            UserName: user.as_mut_ptr(),
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            ..Default::default()
+        };
+        // SAFETY: the buffers `credential` points into outlive the call
+        if unsafe { CredWriteW(&credential, 0) } == 0 {
+            return Err(format!(
+                "Failed to save to Credential Manager: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, String> {
+        let target = target(account);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        // SAFETY: on success `credential` points to a CREDENTIALW owned by
+        // the system until CredFree, and its blob is `CredentialBlobSize`
+        // bytes long
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                if GetLastError() == ERROR_NOT_FOUND {
+                    return Ok(None);
+                }
+                return Err(format!(
+                    "Failed to read from Credential Manager: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            let size = (*credential).CredentialBlobSize as usize;
+            let secret = if size == 0 {
+                String::new()
+            } else {
+                let blob = std::slice::from_raw_parts((*credential).CredentialBlob, size);
+                String::from_utf8_lossy(blob).into_owned()
+            };
+            CredFree(credential as *const _);
+            Ok(Some(secret))
+        }
+    }
+
+    pub fn delete(account: &str) -> Result<(), String> {
+        let target = target(account);
+        // SAFETY: `target` is a NUL-terminated UTF-16 string
+        unsafe {
+            if CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) == 0
+                && GetLastError() != ERROR_NOT_FOUND
+            {
+                return Err(format!(
+                    "Failed to remove from Credential Manager: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::SERVICE;
+    use security_framework::passwords;
+
+    /// errSecItemNotFound: there's no such item.
+    const ITEM_NOT_FOUND: i32 = -25300;
+
+    // Security.framework directly rather than the `security` tool, whose
+    // `-w` would put the secret in the process list
+    pub fn set(account: &str, secret: &str) -> Result<(), String> {
+        passwords::set_generic_password(SERVICE, account, secret.as_bytes())
+            .map_err(|e| format!("Failed to save to the keychain: {}", e))
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, String> {
+        match passwords::get_generic_password(SERVICE, account) {
+            Ok(secret) => Ok(Some(String::from_utf8_lossy(&secret).into_owned())),
+            Err(e) if e.code() == ITEM_NOT_FOUND => Ok(None),
+            Err(e) => Err(format!("Failed to read from the keychain: {}", e)),
+        }
+    }
+
+    pub fn delete(account: &str) -> Result<(), String> {
+        match passwords::delete_generic_password(SERVICE, account) {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == ITEM_NOT_FOUND => Ok(()),
+            Err(e) => Err(format!("Failed to remove from the keychain: {}", e)),
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    // The Secret Service D-Bus API directly rather than `secret-tool`, which
+    // many desktops don't have installed. The attributes are the ones
+    // `secret-tool` used, so secrets stored by older versions are found.
+    use super::SERVICE;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use zbus::blocking::{Connection, MessageIterator};
+    use zbus::message::{Message, Type as MessageType};
+    use zbus::zvariant::{DynamicType, OwnedObjectPath, OwnedValue, Type, Value};
+    use zbus::MatchRule;
+
+    const DESTINATION: &str = "org.freedesktop.secrets";
+    const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+    const SECRET_SERVICE: &str = "org.freedesktop.Secret.Service";
+    const COLLECTION: &str = "org.freedesktop.Secret.Collection";
+    const ITEM: &str = "org.freedesktop.Secret.Item";
+    const PROMPT: &str = "org.freedesktop.Secret.Prompt";
+
+    /// How long the keyring's unlock prompt may wait for its password.
+    const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// A secret as the Secret Service passes it: the session, its
+    /// parameters, the value and the value's content type.
+    type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+    fn call(
+        conn: &Connection,
+        path: &str,
+        interface: &str,
+        method: &str,
+        body: &(impl serde::Serialize + DynamicType),
+    ) -> Result<Message, String> {
+        conn.call_method(Some(DESTINATION), path, Some(interface), method, body)
+            .map_err(|e| e.to_string())
+    }
+
+    fn reply<R: serde::de::DeserializeOwned + Type>(message: Message) -> Result<R, String> {
+        message.body().deserialize().map_err(|e| e.to_string())
+    }
+
+    fn connect() -> Result<Connection, String> {
+        Connection::session().map_err(|e| format!("there's no session bus: {}", e))
+    }
+
+    /// A session to pass secrets in. They aren't encrypted, as they only
+    /// cross this user's own session bus.
+    fn open_session(conn: &Connection) -> Result<OwnedObjectPath, String> {
+        let (_, session): (OwnedValue, OwnedObjectPath) = reply(call(
+            conn,
+            SERVICE_PATH,
+            SECRET_SERVICE,
+            "OpenSession",
+            &("plain", Value::from("")),
+        )?)?;
+        Ok(session)
+    }
+
+    fn attributes(account: &str) -> HashMap<&str, &str> {
+        HashMap::from([("service", SERVICE), ("account", account)])
+    }
+
+    /// The items stored for `account`, unlocked.
+    fn items(conn: &Connection, account: &str) -> Result<Vec<OwnedObjectPath>, String> {
+        let (mut unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) = reply(call(
+            conn,
+            SERVICE_PATH,
+            SECRET_SERVICE,
+            "SearchItems",
+            &(attributes(account),),
+        )?)?;
+        if !locked.is_empty() {
+            unlock(conn, &locked)?;
+            unlocked.extend(locked);
+        }
+        Ok(unlocked)
+    }
+
+    fn unlock(conn: &Connection, objects: &[OwnedObjectPath]) -> Result<(), String> {
+        let (_, prompt): (Vec<OwnedObjectPath>, OwnedObjectPath) = reply(call(
+            conn,
+            SERVICE_PATH,
+            SECRET_SERVICE,
+            "Unlock",
+            &(objects,),
+        )?)?;
+        complete(conn, &prompt)
+    }
+
+    /// Show `prompt`, such as for the keyring's password, and wait for the
+    /// user to answer it. "/" means the Secret Service needs no prompt.
+    fn complete(conn: &Connection, prompt: &OwnedObjectPath) -> Result<(), String> {
+        if prompt.as_str() == "/" {
+            return Ok(());
+        }
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface(PROMPT)
+            .and_then(|rule| rule.member("Completed"))
+            .and_then(|rule| rule.path(prompt.as_str()))
+            .map_err(|e| e.to_string())?
+            .build();
+        let signals =
+            MessageIterator::for_match_rule(rule, conn, Some(1)).map_err(|e| e.to_string())?;
+        call(conn, prompt.as_str(), PROMPT, "Prompt", &("",))?;
+        let (answered, answer) = mpsc::channel();
+        std::thread::spawn(move || {
+            if let Some(message) = signals.flatten().next() {
+                let dismissed = message
+                    .body()
+                    .deserialize::<(bool, OwnedValue)>()
+                    .map_or(true, |(dismissed, _)| dismissed);
+                let _ = answered.send(dismissed);
+            }
+        });
+        match answer.recv_timeout(PROMPT_TIMEOUT) {
+            Ok(false) => Ok(()),
+            Ok(true) => Err("the keyring wasn't unlocked".to_string()),
+            Err(_) => Err("the keyring wasn't unlocked in time".to_string()),
+        }
+    }
+
+    pub fn set(account: &str, secret: &str) -> Result<(), String> {
+        store(account, secret).map_err(|e| format!("Failed to save to the keyring: {}", e))
+    }
+
+    fn store(account: &str, secret: &str) -> Result<(), String> {
+        let conn = connect()?;
+        let session = open_session(&conn)?;
+        let collection: OwnedObjectPath = reply(call(
+            &conn,
+            SERVICE_PATH,
+            SECRET_SERVICE,
+            "ReadAlias",
+            &("default",),
+        )?)?;
+        if collection.as_str() == "/" {
+            return Err("there's no default keyring".to_string());
+        }
+        unlock(&conn, std::slice::from_ref(&collection))?;
+        let label = format!("{} {}", SERVICE, account);
+        let properties = HashMap::from([
+            ("org.freedesktop.Secret.Item.Label", Value::from(label)),
+            (
+                "org.freedesktop.Secret.Item.Attributes",
+                Value::from(attributes(account)),
+            ),
+        ]);
+        let secret: Secret = (
+            session,
+            Vec::new(),
+            secret.as_bytes().to_vec(),
+            "text/plain".to_string(),
+        );
+        // `true` replaces an item with the same attributes
+        let (_, prompt): (OwnedObjectPath, OwnedObjectPath) = reply(call(
+            &conn,
+            collection.as_str(),
+            COLLECTION,
+            "CreateItem",
+            &(properties, secret, true),
+        )?)?;
+        complete(&conn, &prompt)
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>, String> {
+        look_up(account).map_err(|e| format!("Failed to read from the keyring: {}", e))
+    }
+
+    fn look_up(account: &str) -> Result<Option<String>, String> {
+        let conn = connect()?;
+        let Some(item) = items(&conn, account)?.into_iter().next() else {
+            return Ok(None);
+        };
+        let session = open_session(&conn)?;
+        let mut secrets: HashMap<OwnedObjectPath, Secret> = reply(call(
+            &conn,
+            SERVICE_PATH,
+            SECRET_SERVICE,
+            "GetSecrets",
+            &(std::slice::from_ref(&item), &session),
+        )?)?;
+        Ok(secrets
+            .remove(&item)
+            .map(|(_, _, value, _)| String::from_utf8_lossy(&value).into_owned()))
+    }
+
+    pub fn delete(account: &str) -> Result<(), String> {
+        remove(account).map_err(|e| format!("Failed to remove from the keyring: {}", e))
+    }
+
+    fn remove(account: &str) -> Result<(), String> {
+        let conn = connect()?;
+        for item in items(&conn, account)? {
+            let prompt: OwnedObjectPath = reply(call(&conn, item.as_str(), ITEM, "Delete", &())?)?;
+            complete(&conn, &prompt)?;
+        }
+        Ok(())
+    }
+}
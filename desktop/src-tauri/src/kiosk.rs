@@ -0,0 +1,187 @@
+//! Kiosk mode for a display account nobody should reconfigure. Launching
+//! once with `--provision-kiosk` under that account turns on everything it
+//! needs in one go: starting at login, the web UI full screen without a
+//! tray, a backend without local logins, locked settings and unlimited
+//! crash restarts. `--unprovision-kiosk` turns the display parts back off.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::config::Config;
+use crate::{api, audit, autostart, log, shutdown, webview, BackendState};
+
+/// Launch argument that provisions kiosk mode.
+pub const PROVISION_FLAG: &str = "--provision-kiosk";
+
+/// Launch argument that ends kiosk mode.
+pub const UNPROVISION_FLAG: &str = "--unprovision-kiosk";
+
+/// How often the kiosk window is checked on.
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Guards against spawning a second kiosk watcher.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// What a launch argument asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provisioning {
+    Provision,
+    Unprovision,
+}
+
+/// The provisioning requested by the app's arguments, if any.
+pub fn from_args(args: &[String]) -> Option<Provisioning> {
+    args.iter().skip(1).find_map(|arg| match arg.as_str() {
+        PROVISION_FLAG => Some(Provisioning::Provision),
+        UNPROVISION_FLAG => Some(Provisioning::Unprovision),
+        _ => None,
+    })
+}
+
+/// Change `config` to (or back from) kiosk mode. Ending it keeps autostart
+/// and crash recovery, which are as useful on an ordinary install.
+fn apply(config: &mut Config, provisioning: Provisioning) {
+    let on = provisioning == Provisioning::Provision;
+    config.kiosk = on;
+    config.read_only = on;
+    config.settings_locked = on;
+    if on {
+        config.auto_start = true;
+        config.open_in_window = true;
+        config.crash_recovery = true;
+        config.virtual_node_allow_admin = false;
+    }
+}
+
+/// Write kiosk mode to config.json and the login items. Run at launch,
+/// before the config is read for startup.
+pub fn provision(provisioning: Provisioning) -> Result<(), String> {
+    let previous = Config::load()?;
+    if provisioning == Provisioning::Provision && previous.needs_setup() {
+        return Err("Finish MeshMonitor setup before provisioning kiosk mode".to_string());
+    }
+    let mut config = previous.clone();
+    apply(&mut config, provisioning);
+    config.save()?;
+    audit::record_config_change(audit::Source::CommandLine, &previous, &config);
+    if config.auto_start {
+        autostart::set(true)?;
+    }
+    log(match provisioning {
+        Provisioning::Provision => "Kiosk mode provisioned",
+        Provisioning::Unprovision => "Kiosk mode ended",
+    });
+    Ok(())
+}
+
+/// Provisioning handed over by a second launch: apply it and restart the
+/// app, backend included, so it comes up the way a fresh login would.
+pub fn provision_running<R: Runtime>(app: &AppHandle<R>, provisioning: Provisioning) {
+    if let Err(e) = provision(provisioning) {
+        log(&format!("Kiosk provisioning failed: {}", e));
+        app.dialog()
+            .message(e)
+            .title("Kiosk Mode")
+            .kind(MessageDialogKind::Error)
+            .show(|_| {});
+        return;
+    }
+    let state: tauri::State<BackendState> = app.state();
+    *state.keep_backend_on_exit.lock().unwrap() = Some(false);
+    shutdown(&state);
+    app.restart();
+}
+
+/// Show the web UI full screen once the backend answers, and bring it back
+/// whenever it is minimized, left full screen or closed by a crash of its
+/// web process. Does nothing outside kiosk mode.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    let config = Config::load().unwrap_or_default();
+    if !config.kiosk || STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let url = format!("http://localhost:{}", config.web_port);
+    let web_port = config.web_port;
+    let init_script = webview::web_ui_init_script(&config);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        while api::health(web_port).is_err() {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+        loop {
+            match app.get_webview_window(webview::WEB_UI_WINDOW) {
+                Some(window) => {
+                    let displaced = window.is_minimized().unwrap_or(false)
+                        || !window.is_fullscreen().unwrap_or(true)
+                        || !window.is_visible().unwrap_or(true);
+                    if displaced {
+                        webview::show_web_ui_fullscreen(&app, &url, &init_script);
+                    }
+                }
+                None => {
+                    if !webview::show_web_ui_fullscreen(&app, &url, &init_script) {
+                        log("Kiosk window unavailable; native windows don't work here");
+                        return;
+                    }
+                }
+            }
+            std::thread::sleep(WATCH_INTERVAL);
+        }
+    });
+}
+
+/// Whether Settings are locked, telling the user so when they are.
+pub fn refuse_settings<R: Runtime>(app: &AppHandle<R>) -> bool {
+    if !Config::load().unwrap_or_default().settings_locked {
+        return false;
+    }
+    app.dialog()
+        .message(
+            "Settings are locked on this computer. Launch MeshMonitor with \
+             --unprovision-kiosk, or edit config.json, to change them.",
+        )
+        .title("Settings Locked")
+        .kind(MessageDialogKind::Info)
+        .show(|_| {});
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reads_the_flags_after_the_program_name() {
+        assert_eq!(
+            from_args(&args(&["meshmonitor", "--provision-kiosk"])),
+            Some(Provisioning::Provision)
+        );
+        assert_eq!(
+            from_args(&args(&["meshmonitor", "--unprovision-kiosk"])),
+            Some(Provisioning::Unprovision)
+        );
+        assert_eq!(from_args(&args(&["--provision-kiosk"])), None);
+    }
+
+    #[test]
+    fn test_unprovisioning_keeps_autostart_and_crash_recovery() {
+        let mut config = Config {
+            virtual_node_allow_admin: true,
+            ..Config::default()
+        };
+        apply(&mut config, Provisioning::Provision);
+        assert!(config.kiosk && config.read_only && config.settings_locked);
+        assert!(config.auto_start && config.open_in_window && config.crash_recovery);
+        assert!(!config.virtual_node_allow_admin);
+
+        apply(&mut config, Provisioning::Unprovision);
+        assert!(!config.kiosk && !config.read_only && !config.settings_locked);
+        assert!(config.auto_start && config.crash_recovery);
+    }
+}
@@ -1,11 +1,96 @@
+pub mod accessibility;
+pub mod activity_report;
+pub mod addresses;
+pub mod api;
+pub mod attention;
+pub mod audio;
+pub mod audit;
+pub mod autostart;
+pub mod backend_env;
+pub mod backend_record;
+pub mod bandwidth;
+pub mod ble;
+pub mod bridge;
+pub mod channel_link;
+pub mod channel_qr;
+pub mod clipboard_watcher;
+pub mod close_button;
 pub mod config;
+pub mod config_watcher;
+pub mod connection_check;
+pub mod crash_report;
+pub mod database_move;
+pub mod desktop_events;
+pub mod device_backup;
+pub mod diagnose;
+pub mod dry_run;
+pub mod external;
+pub mod fallback;
+pub mod firmware;
+pub mod first_run;
+pub mod history;
+pub mod idle;
+pub mod incidents;
+pub mod instance_lock;
+pub mod ipc;
+pub mod keyring;
+pub mod kiosk;
+pub mod locale;
+pub mod log_search;
+pub mod login_handoff;
+pub mod mdns;
+pub mod message_export;
+pub mod network_wait;
+pub mod node_identity;
+pub mod node_select;
+pub mod notifications;
+pub mod os_auth;
+pub mod outbound;
+pub mod platform_support;
+pub mod poller;
+pub mod polling;
+pub mod port_release;
+pub mod preflight;
+pub mod prestart;
+pub mod progress;
+pub mod proxy;
+pub mod push;
+pub mod qr_decode;
+pub mod remote_instances;
+pub mod repair;
+pub mod resource_history;
+pub mod safe_mode;
+pub mod serial;
+pub mod share_link;
+pub mod sidecar_arch;
+pub mod sidecars;
+pub mod signals;
+pub mod smtp;
+pub mod sqlite_wal;
+pub mod startup_profile;
+pub mod status_bar;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+pub mod theme;
+pub mod tile_cache;
+pub mod tile_download;
 pub mod tray;
+pub mod tts;
+pub mod udp;
+pub mod uptime;
+pub mod wake_on_lan;
+pub mod watchdog;
+pub mod web_port;
+pub mod webhooks;
+pub mod webview;
+pub mod window_size;
 
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Child, Stdio};
-use std::sync::Mutex;
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager, Runtime};
 
 /// Strip the Windows extended-length path prefix (\\?\) if present.
@@ -40,10 +125,37 @@ pub struct BackendState {
     /// Injected into the Node backend as APPRISE_URL so the notification
     /// service targets the bundled sidecar. `None` when no sidecar is running.
     pub apprise_url: Mutex<Option<String>>,
+    /// Whether to leave the backend (and Apprise) running when the app
+    /// exits, as chosen from the tray. `None` falls back to
+    /// `Config::keep_backend_on_quit`.
+    pub keep_backend_on_exit: Mutex<Option<bool>>,
+    /// PID of a backend left running by a previous run of the app and
+    /// adopted at launch. We hold no `Child` for it, so it is stopped by PID.
+    pub adopted_pid: Mutex<Option<u32>>,
+    /// PID of the Apprise sidecar adopted along with the backend.
+    pub adopted_apprise_pid: Mutex<Option<u32>>,
+    /// Loopback bridge to a node the backend can't reach itself (see
+    /// `Config::connection_type`). Kept across backend restarts.
+    pub bridge: Mutex<Option<bridge::Bridge>>,
+    /// Reverse proxy in front of this backend and other instances (see
+    /// `Config::proxy_port`). Kept across backend restarts.
+    pub proxy: Mutex<Option<proxy::Proxy>>,
+    /// Caching proxy for map tiles (see `Config::tile_cache_port`). Kept
+    /// across backend restarts.
+    pub tile_cache: Mutex<Option<tile_cache::TileCache>>,
+    /// Status page on the web port while the backend is down.
+    pub fallback: Mutex<Option<fallback::Fallback>>,
+}
+
+impl BackendState {
+    /// Whether a backend is running, spawned by us or adopted.
+    pub fn is_running(&self) -> bool {
+        self.process.lock().unwrap().is_some() || self.adopted_pid.lock().unwrap().is_some()
+    }
 }
 
 /// Write a log message to the MeshMonitor log file
-fn log_to_file(logs_path: &std::path::Path, message: &str) {
+pub(crate) fn log_to_file(logs_path: &std::path::Path, message: &str) {
     let log_file_path = logs_path.join("desktop.log");
     if let Ok(mut file) = OpenOptions::new()
         .create(true)
@@ -55,6 +167,13 @@ fn log_to_file(logs_path: &std::path::Path, message: &str) {
     }
 }
 
+/// Write a log message to desktop.log in the MeshMonitor logs folder
+pub(crate) fn log(message: &str) {
+    if let Ok(logs_path) = config::get_logs_path() {
+        log_to_file(&logs_path, message);
+    }
+}
+
 /// Ask the OS for a free TCP port on loopback by binding to port 0 and reading
 /// back the assigned port. There is an inherent (small) race between releasing
 /// the listener here and the sidecar binding it, but on a single-user desktop
@@ -82,11 +201,7 @@ pub fn start_apprise<R: Runtime>(app: &AppHandle<R>) -> Result<Option<(Child, St
     std::fs::create_dir_all(&logs_path)
         .map_err(|e| format!("Failed to create logs directory: {}", e))?;
 
-    let resource_path = strip_extended_length_prefix(
-        app.path()
-            .resource_dir()
-            .map_err(|e| format!("Failed to get resource dir: {}", e))?,
-    );
+    let resource_path = resource_path(app)?;
 
     let apprise_path = resource_path.join("binaries").join(if cfg!(windows) {
         "apprise-api.exe"
@@ -160,6 +275,9 @@ pub fn start_apprise<R: Runtime>(app: &AppHandle<R>) -> Result<Option<(Child, St
 
 /// Stop the Apprise sidecar process if running.
 pub fn stop_apprise(state: &BackendState) {
+    if let Some(pid) = state.adopted_apprise_pid.lock().unwrap().take() {
+        backend_record::kill(pid);
+    }
     let mut process = state.apprise.lock().unwrap();
     if let Some(mut child) = process.take() {
         println!("Stopping Apprise sidecar...");
@@ -169,120 +287,147 @@ pub fn stop_apprise(state: &BackendState) {
     *state.apprise_url.lock().unwrap() = None;
 }
 
-/// Start the MeshMonitor backend server
-pub fn start_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
-    let config = Config::load()?;
+/// The resource directory where the server files are bundled, without the
+/// \\?\ prefix on Windows as Node.js doesn't handle it correctly.
+pub fn resource_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    Ok(strip_extended_length_prefix(
+        app.path()
+            .resource_dir()
+            .map_err(|e| format!("Failed to get resource dir: {}", e))?,
+    ))
+}
+
+/// Placeholder for the bridge and bandwidth meter ports in a dry run, which
+/// are only known once those are started.
+pub const ASSIGNED_AT_START: &str = "<assigned at start>";
 
+/// The backend's command line and environment, ready to spawn, and the
+/// session secret it's handed on stdin.
+pub struct BackendLaunch {
+    pub command: std::process::Command,
+    pub session_secret: String,
+    pub bridge_port: Option<u16>,
+}
+
+/// Resolve the paths, run the preflight checks and build the command for
+/// the backend. A dry run does the same without logging or starting the
+/// bridge, reverse proxy, tile cache or bandwidth meter.
+pub fn prepare_backend<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &Config,
+    dry_run: bool,
+) -> Result<BackendLaunch, String> {
     // Get paths
     let data_path = config::get_data_path()?;
-    let db_path = config::get_database_path()?;
+    let db_path = config::database_path(config)?;
     let logs_path = config::get_logs_path()?;
+    let log = |msg: &str| {
+        if !dry_run {
+            log_to_file(&logs_path, msg);
+        }
+    };
 
     // Ensure logs directory exists
     std::fs::create_dir_all(&logs_path)
         .map_err(|e| format!("Failed to create logs directory: {}", e))?;
+    // and the folder of a database kept outside the data directory
+    if let Some(db_dir) = db_path.parent() {
+        std::fs::create_dir_all(db_dir)
+            .map_err(|e| format!("Failed to create database directory: {}", e))?;
+    }
 
-    log_to_file(&logs_path, "=== Starting MeshMonitor backend ===");
+    log("=== Starting MeshMonitor backend ===");
+    let path_checks = startup_profile::span("path checks");
 
     // Get the resource directory where the server files are bundled
-    // Strip the \\?\ prefix on Windows as Node.js doesn't handle it correctly
-    let resource_path = strip_extended_length_prefix(
-        app.path()
-            .resource_dir()
-            .map_err(|e| format!("Failed to get resource dir: {}", e))?,
-    );
+    let resource_path = resource_path(app)?;
 
     let server_path = resource_path.join("dist").join("server").join("server.js");
 
-    // Get the sidecar binary path for Node.js
-    let node_path =
-        resource_path
-            .join("binaries")
-            .join(if cfg!(windows) { "node.exe" } else { "node" });
-
     // Get the dist directory for current working directory (server.js imports ../services/, ../utils/, etc.)
     let server_dir = resource_path.join("dist");
 
-    // Log all paths for debugging
-    log_to_file(&logs_path, &format!("Node path: {:?}", node_path));
-    log_to_file(&logs_path, &format!("Server path: {:?}", server_path));
-    log_to_file(&logs_path, &format!("Server dir: {:?}", server_dir));
-    log_to_file(&logs_path, &format!("Database: {:?}", db_path));
-    log_to_file(&logs_path, &format!("Data dir: {:?}", data_path));
-    log_to_file(&logs_path, &format!("Logs: {:?}", logs_path));
-
-    // Check if required files exist
-    if !node_path.exists() {
-        let msg = format!("ERROR: Node.js binary not found at {:?}", node_path);
-        log_to_file(&logs_path, &msg);
-        return Err(msg);
+    // The checks read unrelated files, so on a slow disk they're waited on
+    // together rather than one after another
+    let (node, wal_findings, bundle) = std::thread::scope(|scope| {
+        // Get the sidecar binary path for Node.js, matching the host architecture
+        let node = scope.spawn(|| {
+            sidecar_arch::select_node(&resource_path.join("binaries"), sidecar_arch::host_arch())
+        });
+        let wal_findings = scope.spawn(|| sqlite_wal::check(&db_path));
+        let bundle = preflight::bundle(&server_dir);
+        (node.join().unwrap(), wal_findings.join().unwrap(), bundle)
+    });
+    let (node_path, arch_warning) = node.inspect_err(|msg| log(msg))?;
+    if let Some(warning) = &arch_warning {
+        log(warning);
     }
-    log_to_file(&logs_path, "Node.js binary exists: OK");
 
-    if !server_path.exists() {
-        let msg = format!("ERROR: Server.js not found at {:?}", server_path);
-        log_to_file(&logs_path, &msg);
-        return Err(msg);
-    }
-    log_to_file(&logs_path, "Server.js exists: OK");
+    // Log all paths for debugging
+    log(&format!("Node path: {:?}", node_path));
+    log(&format!("Server path: {:?}", server_path));
+    log(&format!("Server dir: {:?}", server_dir));
+    log(&format!("Database: {:?}", db_path));
+    log(&format!("Data dir: {:?}", data_path));
+    log(&format!("Logs: {:?}", logs_path));
 
-    // Check for package.json (in dist/ directory)
-    let package_json_path = server_dir.join("package.json");
-    if !package_json_path.exists() {
-        let msg = format!("ERROR: package.json not found at {:?}", package_json_path);
-        log_to_file(&logs_path, &msg);
-        return Err(msg);
+    for finding in wal_findings {
+        log(&format!("Database WAL: {}", finding));
     }
-    log_to_file(&logs_path, "package.json exists: OK");
 
-    // Check for node_modules (in dist/ directory)
-    let node_modules_path = server_dir.join("node_modules");
-    if !node_modules_path.exists() {
-        let msg = format!("ERROR: node_modules not found at {:?}", node_modules_path);
-        log_to_file(&logs_path, &msg);
-        return Err(msg);
-    }
-    log_to_file(&logs_path, "node_modules exists: OK");
+    // Check if required files exist (the Node.js binary was verified above)
+    log("Node.js binary exists: OK");
 
-    // Check for services directory (sibling to server/)
-    let services_path = server_dir.join("services");
-    if !services_path.exists() {
-        let msg = format!("ERROR: services not found at {:?}", services_path);
-        log_to_file(&logs_path, &msg);
-        return Err(msg);
+    for check in bundle {
+        if !check.ok {
+            let msg = format!("ERROR: {}", check.detail);
+            log(&msg);
+            if !dry_run {
+                repair::offer(app, &check.detail);
+            }
+            return Err(msg);
+        }
+        log(&format!("{} exists: OK", check.label));
     }
-    log_to_file(&logs_path, "services directory exists: OK");
+    drop(path_checks);
 
-    println!("Starting MeshMonitor backend...");
-    println!("  Node path: {:?}", node_path);
-    println!("  Server path: {:?}", server_path);
-    println!("  Server dir: {:?}", server_dir);
-    println!("  Database: {:?}", db_path);
-    println!("  Logs: {:?}", logs_path);
-
-    // Create stdout/stderr log files
-    let stdout_log_path = logs_path.join("server-stdout.log");
-    let stderr_log_path = logs_path.join("server-stderr.log");
-
-    let stdout_file = File::create(&stdout_log_path)
-        .map_err(|e| format!("Failed to create stdout log: {}", e))?;
-    let stderr_file = File::create(&stderr_log_path)
-        .map_err(|e| format!("Failed to create stderr log: {}", e))?;
+    if !dry_run {
+        println!("Starting MeshMonitor backend...");
+        println!("  Node path: {:?}", node_path);
+        println!("  Server path: {:?}", server_path);
+        println!("  Server dir: {:?}", server_dir);
+        println!("  Database: {:?}", db_path);
+        println!("  Logs: {:?}", logs_path);
+    }
 
-    log_to_file(&logs_path, &format!("Stdout log: {:?}", stdout_log_path));
-    log_to_file(&logs_path, &format!("Stderr log: {:?}", stderr_log_path));
+    let session_secret = config
+        .resolve_session_secret()
+        .inspect_err(|msg| log(msg))?;
 
     // Build environment variables
     let mut cmd = std::process::Command::new(&node_path);
+    let dropped = backend_env::restrict(&mut cmd, config);
+    if !dropped.is_empty() {
+        log(&format!(
+            "Not passed to the backend: {}",
+            dropped.join(", ")
+        ));
+    }
     cmd.arg(&server_path)
         .current_dir(&server_dir)
-        .stdout(Stdio::from(stdout_file))
-        .stderr(Stdio::from(stderr_file))
+        .stdin(Stdio::piped())
         .env("NODE_ENV", "production")
         .env("PORT", config.web_port.to_string())
         .env("DATABASE_PATH", db_path.to_string_lossy().to_string())
         .env("DATA_DIR", data_path.to_string_lossy().to_string())
-        .env("SESSION_SECRET", &config.session_secret)
+        .env(
+            "SQLITE_BUSY_TIMEOUT_MS",
+            config.sqlite_busy_timeout_ms.to_string(),
+        )
+        .env(
+            "SQLITE_WAL_AUTOCHECKPOINT",
+            config.sqlite_wal_autocheckpoint.to_string(),
+        )
         .env("ALLOWED_ORIGINS", {
             // Always include localhost
             let mut origins = format!("http://localhost:{}", config.web_port);
@@ -306,14 +451,78 @@ pub fn start_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
         )
         .env(
             "VIRTUAL_NODE_ALLOW_ADMIN_COMMANDS",
-            if config.virtual_node_allow_admin {
+            if config.virtual_node_allow_admin && !config.read_only {
                 "true"
             } else {
                 "false"
             },
         )
         .env("IS_DESKTOP", "true")
-        .env("FIRMWARE_CHECK_ENABLED", "false");
+        // Secrets go over stdin (below), never in the environment, where
+        // other processes could read them
+        .env_remove("SESSION_SECRET")
+        .env_remove("DESKTOP_CONTROL_TOKEN")
+        .env("DESKTOP_SECRETS_STDIN", "true")
+        .env("FIRMWARE_CHECK_ENABLED", "false")
+        .env(
+            "VERSION_CHECK_DISABLED",
+            if config.privacy_mode { "true" } else { "false" },
+        )
+        // The web UI's map entries for `tile_servers`, through the tile cache
+        .env(
+            "DESKTOP_TILESETS",
+            tile_cache::backend_tilesets(config).to_string(),
+        );
+
+    // Clients of the reverse proxy are told apart by X-Forwarded-For
+    if let Some(trust_proxy) = proxy::backend_trust_proxy(config) {
+        cmd.env("TRUST_PROXY", trust_proxy);
+    }
+
+    // Read-only: no local logins, so the web UI only offers what anonymous
+    // users may see
+    if config.read_only {
+        cmd.env("DISABLE_LOCAL_AUTH", "true")
+            .env("DISABLE_ANONYMOUS", "false");
+        log("Read-only: local logins disabled");
+    }
+
+    // Safe mode: the backend connects to no source, so a node or MQTT
+    // setting that crashes it at startup can't stop the web UI coming up
+    let safe_mode = !dry_run && safe_mode::active();
+    if safe_mode {
+        cmd.env("SAFE_MODE", "true");
+        log("Safe mode: not connecting to the node or MQTT");
+    }
+
+    // Send the backend's outbound requests through the bandwidth meter.
+    // Loopback and the node stay direct.
+    if !dry_run {
+        bandwidth::start(app, config);
+    }
+    let proxy_url = if dry_run {
+        Some(ASSIGNED_AT_START)
+    } else {
+        bandwidth::proxy_url()
+    };
+    if let Some(proxy_url) = proxy_url.filter(|_| config.track_bandwidth) {
+        let mut no_proxy = "localhost,127.0.0.1,::1".to_string();
+        for ip in node_select::candidates(config) {
+            no_proxy.push(',');
+            no_proxy.push_str(&ip);
+        }
+        cmd.env("NODE_USE_ENV_PROXY", "1")
+            .env("HTTP_PROXY", proxy_url)
+            .env("HTTPS_PROXY", proxy_url)
+            .env("NO_PROXY", no_proxy);
+        log("Outbound requests metered for bandwidth tracking");
+    }
+
+    // Have the backend connect back to our control channel, if it's up
+    if let Some(address) = ipc::address() {
+        cmd.env("DESKTOP_IPC_ADDRESS", &address);
+        log(&format!("DESKTOP_IPC_ADDRESS: {}", address));
+    }
 
     // Point the backend's notification service at the bundled Apprise sidecar
     // if one is running. Resolved from BackendState so it survives Node backend
@@ -327,7 +536,7 @@ pub fn start_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
         .clone()
     {
         cmd.env("APPRISE_URL", &apprise_url);
-        log_to_file(&logs_path, &format!("APPRISE_URL: {}", apprise_url));
+        log(&format!("APPRISE_URL: {}", apprise_url));
     }
 
     // Only pass MESHTASTIC_NODE_IP / TCP_PORT if the user has actually
@@ -335,48 +544,60 @@ pub fn start_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
     // backend's auto-created Meshtastic TCP source, which would otherwise
     // pin every MeshCore-only desktop install into a forever ENETUNREACH
     // reconnect loop against a placeholder address. See discussion #2604.
-    let meshtastic_ip_configured = !config.meshtastic_ip.trim().is_empty();
-    if meshtastic_ip_configured {
-        cmd.env("MESHTASTIC_NODE_IP", &config.meshtastic_ip)
+    // A bridged node stands in for the TCP node at the bridge's port.
+    // A dry run starts none of them, so it leaves the device and ports to a
+    // running instance. Safe mode leaves the device alone as well
+    let bridge_port = if dry_run || safe_mode {
+        None
+    } else {
+        let state = app.state::<BackendState>();
+        ensure_proxy(&state, config);
+        ensure_tile_cache(&state, config);
+        ensure_bridge(&state, config, None)
+    };
+    // A node with several addresses gets the first that answers
+    let node_ip = if safe_mode {
+        None
+    } else {
+        node_select::choose(config)
+    };
+    if let Some(port) = bridge_port {
+        cmd.env("MESHTASTIC_NODE_IP", "127.0.0.1")
+            .env("MESHTASTIC_TCP_PORT", port.to_string());
+    } else if dry_run && config.connection_type != config::ConnectionType::Tcp {
+        cmd.env("MESHTASTIC_NODE_IP", "127.0.0.1")
+            .env("MESHTASTIC_TCP_PORT", ASSIGNED_AT_START);
+    } else if let Some(ip) = &node_ip {
+        cmd.env("MESHTASTIC_NODE_IP", ip)
             .env("MESHTASTIC_TCP_PORT", config.meshtastic_port.to_string());
     }
 
-    log_to_file(&logs_path, "Environment variables set");
-    log_to_file(&logs_path, &format!("PORT: {}", config.web_port));
-    if meshtastic_ip_configured {
-        log_to_file(
-            &logs_path,
-            &format!("MESHTASTIC_NODE_IP: {}", config.meshtastic_ip),
-        );
+    log("Environment variables set");
+    log(&format!("PORT: {}", config.web_port));
+    if let Some(port) = bridge_port {
+        log(&format!("MESHTASTIC_NODE_IP: 127.0.0.1:{} (bridge)", port));
+    } else if let Some(ip) = &node_ip {
+        log(&format!("MESHTASTIC_NODE_IP: {}", ip));
     } else {
-        log_to_file(
-            &logs_path,
-            "MESHTASTIC_NODE_IP: <unset> (no Meshtastic node configured)",
-        );
+        log("MESHTASTIC_NODE_IP: <unset> (no Meshtastic node configured)");
     }
-    log_to_file(
-        &logs_path,
-        &format!(
-            "ALLOWED_ORIGINS: http://localhost:{}{}",
-            config.web_port,
-            config
-                .allowed_origins
-                .as_ref()
-                .map(|o| format!(",{}", o))
-                .unwrap_or_default()
-        ),
-    );
-    log_to_file(
-        &logs_path,
-        &format!("ENABLE_VIRTUAL_NODE: {}", config.enable_virtual_node),
-    );
-    log_to_file(
-        &logs_path,
-        &format!(
-            "VIRTUAL_NODE_ALLOW_ADMIN_COMMANDS: {}",
-            config.virtual_node_allow_admin
-        ),
-    );
+    log(&format!(
+        "ALLOWED_ORIGINS: http://localhost:{}{}",
+        config.web_port,
+        config
+            .allowed_origins
+            .as_ref()
+            .map(|o| format!(",{}", o))
+            .unwrap_or_default()
+    ));
+    log(&format!(
+        "ENABLE_VIRTUAL_NODE: {}",
+        config.enable_virtual_node
+    ));
+    log(&format!(
+        "VIRTUAL_NODE_ALLOW_ADMIN_COMMANDS: {}",
+        config.virtual_node_allow_admin
+    ));
 
     // On Windows, hide the console window
     #[cfg(windows)]
@@ -384,33 +605,490 @@ pub fn start_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
         use std::os::windows::process::CommandExt;
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         cmd.creation_flags(CREATE_NO_WINDOW);
-        log_to_file(&logs_path, "Windows: CREATE_NO_WINDOW flag set");
+        log("Windows: CREATE_NO_WINDOW flag set");
+    }
+
+    Ok(BackendLaunch {
+        command: cmd,
+        session_secret,
+        bridge_port,
+    })
+}
+
+/// Start the MeshMonitor backend server
+pub fn start_backend<R: Runtime>(app: &AppHandle<R>) -> Result<Child, String> {
+    let config = {
+        let _span = startup_profile::span("config load");
+        Config::load()?
+    };
+    if config.external_backend {
+        return Err("MeshMonitor is set to monitor an external backend".to_string());
+    }
+    let config = web_port::settle(app, config);
+    // Don't start on an empty database while the old one is left behind
+    database_move::check(app, &config::database_path(&config)?)?;
+    // Helpers such as an MQTT broker come up before the backend needs them
+    if safe_mode::check(app) {
+        sidecars::stop_all();
+    } else {
+        sidecars::start_all(&config.sidecars);
     }
+    config_watcher::backend_starting();
+    let BackendLaunch {
+        command: mut cmd,
+        session_secret,
+        bridge_port,
+    } = prepare_backend(app, &config, false)?;
+    let logs_path = config::get_logs_path()?;
+
+    // Create stdout/stderr log files
+    let stdout_log_path = logs_path.join("server-stdout.log");
+    let stderr_log_path = logs_path.join("server-stderr.log");
+
+    let stdout_file = File::create(&stdout_log_path)
+        .map_err(|e| format!("Failed to create stdout log: {}", e))?;
+    let stderr_file = File::create(&stderr_log_path)
+        .map_err(|e| format!("Failed to create stderr log: {}", e))?;
+
+    log_to_file(&logs_path, &format!("Stdout log: {:?}", stdout_log_path));
+    log_to_file(&logs_path, &format!("Stderr log: {:?}", stderr_log_path));
+
+    cmd.stdout(Stdio::from(stdout_file))
+        .stderr(Stdio::from(stderr_file));
+
+    // Fresh token for the desktop-only shutdown endpoint on every spawn
+    let control_token = config::generate_secret();
 
     log_to_file(&logs_path, "Spawning Node.js process...");
 
-    let child = cmd.spawn().map_err(|e| {
+    // Hand the web port back from the status page
+    fallback::hide(&app.state::<BackendState>());
+    let spawn = startup_profile::span("spawn");
+    let mut child = cmd.spawn().map_err(|e| {
         let msg = format!("Failed to start backend: {}", e);
         log_to_file(&logs_path, &msg);
         msg
     })?;
+    drop(spawn);
+    startup_profile::await_healthy(config.web_port);
+
+    // Hand over the secrets; the backend reads stdin to EOF before it
+    // loads its configuration, so the pipe is closed straight away
+    let secrets = serde_json::json!({
+        "SESSION_SECRET": session_secret,
+        "DESKTOP_CONTROL_TOKEN": control_token,
+    });
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Err(e) = writeln!(stdin, "{}", secrets) {
+            log_to_file(
+                &logs_path,
+                &format!("Failed to send secrets to backend: {}", e),
+            );
+        }
+    }
 
     let pid = child.id();
     log_to_file(&logs_path, &format!("Backend started with PID: {}", pid));
     println!("Backend started with PID: {}", pid);
+    uptime::record(uptime::EventKind::BackendStarted, &format!("PID {}", pid));
+    // The web UI window is only open this early after a restart
+    desktop_events::publish(
+        app,
+        desktop_events::Level::Info,
+        "The MeshMonitor backend is running again.",
+    );
+
+    // Record the backend so a later launch can adopt it if it outlives us
+    let state = app.state::<BackendState>();
+    let apprise_pid = state
+        .apprise
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(Child::id)
+        .or(*state.adopted_apprise_pid.lock().unwrap());
+    let record = backend_record::BackendRecord {
+        pid,
+        web_port: config.web_port,
+        started_at: chrono::Utc::now().timestamp_millis(),
+        apprise_pid,
+        apprise_url: state.apprise_url.lock().unwrap().clone(),
+        control_token: Some(control_token),
+        bridge_port,
+    };
+    if let Err(e) = record.save() {
+        log_to_file(&logs_path, &e);
+    }
 
     Ok(child)
 }
 
-/// Stop the backend server
+/// Adopt a backend left running by a previous run of the app. Its Apprise
+/// sidecar, if it had one, is adopted with it so restarts keep using it.
+pub fn adopt_backend(state: &BackendState, record: backend_record::BackendRecord) {
+    log(&format!(
+        "Adopted running backend (PID {}, port {})",
+        record.pid, record.web_port
+    ));
+    *state.apprise_url.lock().unwrap() = record.apprise_url;
+    *state.adopted_apprise_pid.lock().unwrap() = record.apprise_pid;
+    uptime::record(
+        uptime::EventKind::BackendAdopted,
+        &format!("PID {}", record.pid),
+    );
+    *state.adopted_pid.lock().unwrap() = Some(record.pid);
+
+    // The adopted backend keeps using the bridge port it was started with
+    let config = Config::load().unwrap_or_default();
+    if record.bridge_port.is_some() {
+        ensure_bridge(state, &config, record.bridge_port);
+    }
+    ensure_proxy(state, &config);
+    ensure_tile_cache(state, &config);
+    sidecars::start_all(&config.sidecars);
+}
+
+/// Hand `tile_servers` to the tile cache, with their API keys from the
+/// keyring.
+pub fn load_tile_servers(config: &Config) {
+    tile_cache::set_servers(&config.tile_servers, |id| {
+        keyring::get(&tile_cache::key_account(id))
+    });
+}
+
+/// Start, update or stop the reverse proxy as `config` asks.
+fn ensure_proxy(state: &BackendState, config: &Config) {
+    let mut proxy = state.proxy.lock().unwrap();
+    let Some(port) = config.proxy_port else {
+        *proxy = None;
+        return;
+    };
+    let routes = proxy::Routes::from_config(config);
+    if let Some(running) = proxy.as_ref().filter(|p| p.port == port) {
+        running.set_routes(routes);
+        return;
+    }
+    // Let go of the old port before binding the new one
+    *proxy = None;
+    match proxy::Proxy::start(port, routes) {
+        Ok(started) => *proxy = Some(started),
+        Err(e) => {
+            log(&e);
+        }
+    }
+}
+
+/// Start, restart or stop the map tile cache as `config` asks.
+fn ensure_tile_cache(state: &BackendState, config: &Config) {
+    let mut cache = state.tile_cache.lock().unwrap();
+    let Some(port) = config.tile_cache_port else {
+        *cache = None;
+        return;
+    };
+    load_tile_servers(config);
+    if cache
+        .as_ref()
+        .is_some_and(|c| c.port == port && c.max_mb == config.tile_cache_max_mb)
+    {
+        return;
+    }
+    *cache = None;
+    match tile_cache::TileCache::start(port, config.tile_cache_max_mb) {
+        Ok(started) => *cache = Some(started),
+        Err(e) => {
+            log(&e);
+        }
+    }
+}
+
+/// Start, keep or replace the bridge `config` asks for, and return its
+/// port. `port` is tried first when a new bridge is started. With
+/// `connection_type: tcp` any running bridge is stopped and `None` returned.
+fn ensure_bridge(state: &BackendState, config: &Config, port: Option<u16>) -> Option<u16> {
+    use config::ConnectionType;
+    let mut bridge = state.bridge.lock().unwrap();
+    let (name, device) = match config.connection_type {
+        ConnectionType::Tcp => {
+            *bridge = None;
+            return None;
+        }
+        ConnectionType::Ble => ("Bluetooth", config.ble_device.clone()),
+        ConnectionType::Serial => ("Serial", config.serial_port.clone()),
+        ConnectionType::Udp => ("LAN", String::new()),
+    };
+    let baud = config.serial_baud;
+    let target = match config.connection_type {
+        // Reopened at the new rate when it changes
+        ConnectionType::Serial => format!("{}:{}@{}", name, device, baud),
+        _ => format!("{}:{}", name, device),
+    };
+    if let Some(running) = bridge.as_ref().filter(|b| b.target == target) {
+        return Some(running.port);
+    }
+    // Stop the old link first so it lets go of the device
+    *bridge = None;
+    let kind = config.connection_type;
+    let started = bridge::Bridge::start(name, &target, port, move || match kind {
+        ConnectionType::Serial => serial::open(&device, baud),
+        ConnectionType::Udp => udp::open(),
+        _ => ble::open(&device),
+    });
+    match started {
+        Ok(started) => {
+            let port = started.port;
+            *bridge = Some(started);
+            Some(port)
+        }
+        Err(e) => {
+            log(&e);
+            *bridge = None;
+            None
+        }
+    }
+}
+
+/// Stop the backend server. It is first asked to shut itself down — over
+/// the control channel, or failing that over HTTP — so Node closes the
+/// Meshtastic socket and checkpoints the SQLite WAL, and gets
+/// `Config::stop_quiesce_secs` to do so. Still running, or when neither
+/// works, it is sent SIGTERM and gets `Config::stop_sigterm_secs`, then is
+/// killed. Slow machines with a large WAL need both raised.
 pub fn stop_backend(state: &BackendState) {
+    let config = Config::load().unwrap_or_default();
+    let requested = ipc::send(&ipc::Command::Quiesce).is_ok()
+        || backend_record::BackendRecord::load().is_some_and(|record| {
+            let Some(token) = record.control_token else {
+                return false;
+            };
+            api::request_shutdown(record.web_port, &token)
+                .inspect_err(|e| {
+                    log(&format!("{}; signalling backend instead", e));
+                })
+                .is_ok()
+        });
+
+    if let Some(pid) = state.adopted_pid.lock().unwrap().take() {
+        uptime::record(uptime::EventKind::BackendStopped, &format!("PID {}", pid));
+        println!("Stopping adopted backend (PID {})...", pid);
+        if !escalate_stop(pid, requested, &config, || !backend_record::is_alive(pid)) {
+            backend_record::kill(pid);
+        }
+    }
+    backend_record::BackendRecord::clear();
+
     let mut process = state.process.lock().unwrap();
     if let Some(mut child) = process.take() {
+        uptime::record(
+            uptime::EventKind::BackendStopped,
+            &format!("PID {}", child.id()),
+        );
         println!("Stopping backend...");
-        let _ = child.kill();
+        let exited = escalate_stop(child.id(), requested, &config, || {
+            matches!(child.try_wait(), Ok(Some(_)))
+        });
+        if !exited {
+            let _ = child.kill();
+        }
         let _ = child.wait();
         println!("Backend stopped");
     }
+    sidecars::stop_all();
+}
+
+/// Wait for the backend `pid` to exit, escalating from the shutdown request
+/// (when `requested`) to SIGTERM, and log each step. Returns `false` when
+/// it's still running and has to be killed.
+fn escalate_stop(
+    pid: u32,
+    requested: bool,
+    config: &Config,
+    mut exited: impl FnMut() -> bool,
+) -> bool {
+    let began = Instant::now();
+    if requested {
+        log(&format!(
+            "Stop: asked backend PID {} to shut down; waiting up to {} s",
+            pid, config.stop_quiesce_secs
+        ));
+        if wait_until(secs(config.stop_quiesce_secs), &mut exited) {
+            log(&format!(
+                "Stop: backend exited after {:.1} s",
+                began.elapsed().as_secs_f64()
+            ));
+            return true;
+        }
+    }
+    if backend_record::terminate(pid) {
+        log(&format!(
+            "Stop: sent SIGTERM to backend PID {}; waiting up to {} s",
+            pid, config.stop_sigterm_secs
+        ));
+        if wait_until(secs(config.stop_sigterm_secs), &mut exited) {
+            log(&format!(
+                "Stop: backend exited after {:.1} s",
+                began.elapsed().as_secs_f64()
+            ));
+            return true;
+        }
+    }
+    log(&format!(
+        "Stop: backend PID {} did not exit after {:.1} s; killing it",
+        pid,
+        began.elapsed().as_secs_f64()
+    ));
+    false
+}
+
+fn secs(secs: u32) -> Duration {
+    Duration::from_secs(secs.into())
+}
+
+/// Poll `done` until it returns true or `timeout` passes.
+fn wait_until(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if done() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Quit the app, either stopping the backend or leaving it running so
+/// logging and the web UI continue without the desktop shell.
+pub fn quit<R: Runtime>(app: &AppHandle<R>, keep_backend: bool) {
+    let state: tauri::State<BackendState> = app.state();
+    *state.keep_backend_on_exit.lock().unwrap() = Some(keep_backend);
+    app.exit(0);
+}
+
+/// Guards `shutdown` so the ExitRequested and Exit paths run it only once.
+static SHUTDOWN: Once = Once::new();
+
+/// Orderly shutdown, run before any window is closed: stop the backend and
+/// wait for it to exit (unless the user asked for it to keep running), stop
+/// Apprise, flush logs, and finally release the lock file, which marks the
+/// run as having ended cleanly.
+pub fn shutdown(state: &BackendState) {
+    SHUTDOWN.call_once(|| {
+        let keep_backend = state
+            .keep_backend_on_exit
+            .lock()
+            .unwrap()
+            .unwrap_or_else(|| {
+                Config::load()
+                    .map(|config| config.keep_backend_on_quit)
+                    .unwrap_or(false)
+            });
+
+        if keep_backend && state.is_running() {
+            // Nothing would supervise the sidecars, and the next launch
+            // starts its own along with adopting the backend
+            sidecars::stop_all();
+            println!("Leaving backend running");
+            log("Desktop app exited; backend left running");
+        } else {
+            stop_backend(state);
+            stop_apprise(state);
+            log("Desktop app exited; backend stopped");
+        }
+
+        let _ = std::io::stdout().flush();
+        let _ = std::io::stderr().flush();
+        ipc::stop_listener();
+        instance_lock::release();
+    });
+}
+
+/// Ask the user how to recover from a corrupted config.json: restore the
+/// last good `config.json.bak` or start over with defaults. The backend is
+/// started once a choice has been made, since it needs the real session
+/// secret and ports from the recovered config.
+pub fn offer_config_recovery<R: Runtime>(app: &AppHandle<R>, error: &str) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    log(&format!("Config file is corrupted: {}", error));
+
+    let handle = app.clone();
+    app.dialog()
+        .message(format!(
+            "MeshMonitor's configuration file could not be read ({}).\n\n\
+             A backup from the last successful save is available. Restore it?",
+            error
+        ))
+        .title("MeshMonitor configuration corrupted")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Restore Backup".to_string(),
+            "Use Defaults".to_string(),
+        ))
+        .show(move |restore| {
+            // Either choice replaces the configuration
+            let reason = if restore {
+                "restore the MeshMonitor configuration backup"
+            } else {
+                "reset the MeshMonitor configuration"
+            };
+            let app = handle.clone();
+            os_auth::gate(&handle, reason, move || recover_config(&app, restore));
+        });
+}
+
+/// Carry out the choice made in `offer_config_recovery`, then start the
+/// backend unless setup is still needed.
+fn recover_config<R: Runtime>(app: &AppHandle<R>, restore: bool) {
+    let recovered = if restore {
+        config::restore_backup()
+    } else {
+        config::reset_corrupted()
+    };
+    if recovered.is_ok() {
+        let action = if restore {
+            "config_restored"
+        } else {
+            "config_reset"
+        };
+        audit::record(audit::Source::Dialog, action, "config.json was corrupted");
+    }
+    let config = match recovered {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to recover configuration: {}", e);
+            return;
+        }
+    };
+    if config.needs_setup() {
+        return;
+    }
+    if config.external_backend {
+        external::start(app);
+        return;
+    }
+    match start_backend(app) {
+        Ok(child) => {
+            let state: tauri::State<BackendState> = app.state();
+            *state.process.lock().unwrap() = Some(child);
+            println!("Backend started successfully");
+        }
+        Err(e) => {
+            eprintln!("Failed to start backend: {}", e);
+            show_start_failure(app, &config, &e);
+        }
+    }
+}
+
+/// Put the reason the backend didn't start on the web port.
+pub fn show_start_failure<R: Runtime>(app: &AppHandle<R>, config: &Config, error: &str) {
+    watchdog::failed(error);
+    fallback::show(
+        &app.state::<BackendState>(),
+        config.web_port,
+        "MeshMonitor backend couldn't start",
+        Some(error),
+    );
 }
 
 // Note: Tauri commands are defined in main.rs to avoid E0255 duplicate symbol errors
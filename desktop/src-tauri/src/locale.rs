@@ -0,0 +1,228 @@
+//! Locale-aware formatting for what the desktop app shows outside the web
+//! UI: tray tooltips and menus, notifications and native dialogs. Dates,
+//! times and numbers follow the OS locale, or `Config::locale` when set
+//! (a BCP 47 tag such as `de-DE`), read once at launch. Log lines and file
+//! names keep their fixed formats.
+//!
+//! Only the conventions for the common languages are known; any other
+//! locale gets ISO dates, a 24-hour clock and `1,234.5` numbers.
+
+use chrono::{DateTime, Local, NaiveDate};
+use std::sync::OnceLock;
+
+use crate::config::Config;
+
+/// Narrow no-break space, the group separator French uses.
+const NARROW_NBSP: &str = "\u{202f}";
+
+/// No-break space, the group separator of most of northern and eastern Europe.
+const NBSP: &str = "\u{a0}";
+
+static CURRENT: OnceLock<Locale> = OnceLock::new();
+
+/// How one locale writes dates, times and numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    /// The tag the conventions were picked for, `de-DE`.
+    pub tag: String,
+    decimal: char,
+    group: &'static str,
+    /// chrono formats of a full date, a date without the year and a time.
+    date: &'static str,
+    day_month: &'static str,
+    time: &'static str,
+}
+
+impl Locale {
+    /// Conventions for `tag`, which may also be in the POSIX form
+    /// (`de_DE.UTF-8`).
+    pub fn parse(tag: &str) -> Locale {
+        let tag = normalize(tag);
+        let mut parts = tag.split('-');
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts
+            .find(|part| part.len() == 2)
+            .map(str::to_ascii_uppercase);
+        let (decimal, group) = match language.as_str() {
+            "de" | "da" | "es" | "it" | "nl" | "pt" | "tr" | "id" => (',', "."),
+            "fr" => (',', NARROW_NBSP),
+            "sv" | "nb" | "no" | "nn" | "fi" | "pl" | "cs" | "sk" | "ru" | "uk" | "hu" => {
+                (',', NBSP)
+            }
+            _ => ('.', ","),
+        };
+        let twelve_hour = "%-I:%M %p";
+        let (date, day_month, time) = match (language.as_str(), region.as_deref()) {
+            ("en", None | Some("US" | "PH")) => ("%b %-d, %Y", "%b %-d", twelve_hour),
+            ("en", Some("CA")) => ("%Y-%m-%d", "%b %-d", twelve_hour),
+            ("en", Some("AU" | "NZ" | "IN")) => ("%-d %b %Y", "%-d %b", twelve_hour),
+            ("en", _) => ("%-d %b %Y", "%-d %b", "%H:%M"),
+            (
+                "de" | "da" | "fi" | "nb" | "no" | "nn" | "pl" | "cs" | "sk" | "ru" | "uk" | "tr",
+                _,
+            ) => ("%d.%m.%Y", "%d.%m.", "%H:%M"),
+            ("fr" | "es" | "it" | "pt" | "id", _) => ("%d/%m/%Y", "%d/%m", "%H:%M"),
+            ("nl", _) => ("%d-%m-%Y", "%d-%m", "%H:%M"),
+            ("hu", _) => ("%Y. %m. %d.", "%m. %d.", "%H:%M"),
+            ("ja" | "zh", _) => ("%Y/%m/%d", "%m/%d", "%H:%M"),
+            ("ko", _) => ("%Y. %m. %d.", "%m. %d.", "%H:%M"),
+            _ => ("%Y-%m-%d", "%m-%d", "%H:%M"),
+        };
+        Locale {
+            tag,
+            decimal,
+            group,
+            date,
+            day_month,
+            time,
+        }
+    }
+
+    /// `1234567` as `1,234,567` (`1.234.567` in German).
+    pub fn count(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3 * self.group.len());
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push_str(self.group);
+            }
+            out.push(digit);
+        }
+        out
+    }
+
+    /// `value` with `places` decimals, grouped like `count`.
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let fixed = format!("{:.*}", places, value.abs());
+        let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+        let mut out = if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            "-".to_string()
+        } else {
+            String::new()
+        };
+        out.push_str(&self.count(whole.parse().unwrap_or_default()));
+        if !fraction.is_empty() {
+            out.push(self.decimal);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// A data size in MB, or in GB from 1 GB: `12.5 MB`, `1,23 GB`.
+    pub fn bytes(&self, bytes: u64) -> String {
+        if bytes >= 1_000_000_000 {
+            format!("{} GB", self.decimal(bytes as f64 / 1e9, 2))
+        } else {
+            format!("{} MB", self.decimal(bytes as f64 / 1e6, 1))
+        }
+    }
+
+    /// Time of day: `3:12 PM`, `15:12`.
+    pub fn time(&self, at: &DateTime<Local>) -> String {
+        at.format(self.time).to_string()
+    }
+
+    /// A full date: `Oct 13, 2026`, `13.10.2026`.
+    pub fn date(&self, date: NaiveDate) -> String {
+        date.format(self.date).to_string()
+    }
+
+    /// A date in the current year, without it: `Oct 13`, `13.10.`.
+    pub fn day_month(&self, date: NaiveDate) -> String {
+        date.format(self.day_month).to_string()
+    }
+}
+
+/// `de_DE.UTF-8@euro` → `de-DE`.
+fn normalize(tag: &str) -> String {
+    let tag = tag.trim();
+    let tag = tag.split(['.', '@']).next().unwrap_or_default();
+    match tag {
+        "" | "C" | "POSIX" => "en-US".to_string(),
+        tag => tag.replace('_', "-"),
+    }
+}
+
+/// The locale everything is formatted for: `Config::locale`, else the OS's.
+pub fn current() -> &'static Locale {
+    CURRENT.get_or_init(|| {
+        let configured = Config::load()
+            .ok()
+            .and_then(|config| config.locale)
+            .filter(|tag| !tag.trim().is_empty());
+        Locale::parse(&configured.or_else(system_locale).unwrap_or_default())
+    })
+}
+
+#[cfg(not(windows))]
+fn system_locale() -> Option<String> {
+    let from_env = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty());
+    #[cfg(target_os = "macos")]
+    {
+        // Apps started from Finder get no LANG
+        if from_env.is_none() {
+            return std::process::Command::new("defaults")
+                .args(["read", "-g", "AppleLocale"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+    }
+    from_env
+}
+
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+
+    // LOCALE_NAME_MAX_LENGTH
+    let mut name = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(name.as_mut_ptr(), name.len() as i32) };
+    // The length includes the terminating NUL
+    (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_numbers_follow_the_locale() {
+        let us = Locale::parse("en_US.UTF-8");
+        let de = Locale::parse("de-DE");
+        let fr = Locale::parse("fr_FR");
+        assert_eq!(us.tag, "en-US");
+        assert_eq!(us.count(1_234_567), "1,234,567");
+        assert_eq!(de.count(1_234_567), "1.234.567");
+        assert_eq!(fr.count(999), "999");
+        assert_eq!(us.bytes(12_345_678), "12.3 MB");
+        assert_eq!(de.bytes(1_234_567_890), "1,23 GB");
+        assert_eq!(fr.decimal(-1234.5, 1), "-1\u{202f}234,5");
+        assert_eq!(Locale::parse("C"), us);
+    }
+
+    #[test]
+    fn test_dates_follow_the_locale() {
+        let at = Local.with_ymd_and_hms(2026, 10, 13, 15, 12, 0).unwrap();
+        let date = at.date_naive();
+        let us = Locale::parse("en-US");
+        let de = Locale::parse("de_DE.UTF-8");
+        let ja = Locale::parse("ja-JP");
+        assert_eq!(
+            (us.date(date), us.time(&at)),
+            ("Oct 13, 2026".into(), "3:12 PM".into())
+        );
+        assert_eq!(
+            (de.day_month(date), de.time(&at)),
+            ("13.10.".into(), "15:12".into())
+        );
+        assert_eq!(ja.date(date), "2026/10/13");
+        assert_eq!(Locale::parse("en-GB").day_month(date), "13 Oct");
+        assert_eq!(Locale::parse("xx").date(date), "2026-10-13");
+    }
+}
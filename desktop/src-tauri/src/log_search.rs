@@ -0,0 +1,232 @@
+//! Searching the desktop, backend and Apprise logs, including rotated or
+//! renamed copies (`desktop.log.1`, `server-stderr.log.old`...), for the Log
+//! Search window. Files are streamed line by line here rather than loaded
+//! into the webview, so multi-megabyte logs stay quick to search. Matching
+//! is a case-insensitive substring match; a line without a timestamp of its
+//! own counts as written at the last timestamp above it.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Lines shown before and after each match.
+const CONTEXT_LINES: usize = 2;
+
+/// Matches returned at most; the rest are only counted.
+const MAX_MATCHES: usize = 500;
+
+/// Formats a log line's timestamp may have.
+const LINE_TIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"];
+
+/// One matching line with the lines around it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct LogMatch {
+    pub file: String,
+    /// 1-based.
+    pub line_number: usize,
+    pub line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// What a search found.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub matches: Vec<LogMatch>,
+    /// Matches found, including those past `MAX_MATCHES`.
+    pub total: usize,
+}
+
+/// Search every log in `logs_path` for `pattern`, skipping lines older
+/// than `since`. Files are searched newest first.
+pub fn search(
+    logs_path: &Path,
+    pattern: &str,
+    since: Option<&str>,
+) -> Result<SearchResult, String> {
+    let pattern = pattern.trim().to_lowercase();
+    if pattern.is_empty() {
+        return Err("Enter text to search for".to_string());
+    }
+    let since = since
+        .map(str::trim)
+        .filter(|since| !since.is_empty())
+        .map(parse_since)
+        .transpose()?;
+
+    let mut result = SearchResult {
+        matches: Vec::new(),
+        total: 0,
+    };
+    for path in log_files(logs_path, since)? {
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        search_file(BufReader::new(file), &name, &pattern, since, &mut result);
+    }
+    Ok(result)
+}
+
+/// `since` as entered: a date, or a date and time with or without seconds.
+fn parse_since(since: &str) -> Result<NaiveDateTime, String> {
+    [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(since, format).ok())
+    .or_else(|| {
+        chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })
+    .ok_or_else(|| format!("\"{}\" isn't a date and time", since))
+}
+
+/// The log files in `logs_path`, newest first, leaving out those last
+/// written before `since`.
+fn log_files(logs_path: &Path, since: Option<NaiveDateTime>) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(logs_path)
+        .map_err(|e| format!("Failed to read {}: {}", logs_path.display(), e))?;
+    let mut files: Vec<(PathBuf, std::time::SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().contains(".log"))
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            let modified = meta.modified().ok()?;
+            meta.is_file().then(|| (entry.path(), modified))
+        })
+        .filter(|(_, modified)| {
+            since.is_none_or(|since| {
+                chrono::DateTime::<chrono::Local>::from(*modified).naive_local() >= since
+            })
+        })
+        .collect();
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+fn search_file(
+    mut reader: impl BufRead,
+    name: &str,
+    pattern: &str,
+    since: Option<NaiveDateTime>,
+    result: &mut SearchResult,
+) {
+    let mut before: VecDeque<String> = VecDeque::with_capacity(CONTEXT_LINES);
+    // Matches still collecting lines after them, as indexes into `result`
+    let mut open: Vec<usize> = Vec::new();
+    let mut last_time: Option<NaiveDateTime> = None;
+    let mut buf = Vec::new();
+    let mut line_number = 0;
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        line_number += 1;
+        let line = String::from_utf8_lossy(&buf)
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+
+        open.retain(|&index| {
+            let after = &mut result.matches[index].after;
+            after.push(line.clone());
+            after.len() < CONTEXT_LINES
+        });
+
+        if let Some(time) = line_time(&line) {
+            last_time = Some(time);
+        }
+        let recent = since.is_none_or(|since| last_time.is_some_and(|time| time >= since));
+        if recent && line.to_lowercase().contains(pattern) {
+            result.total += 1;
+            if result.matches.len() < MAX_MATCHES {
+                result.matches.push(LogMatch {
+                    file: name.to_string(),
+                    line_number,
+                    line: line.clone(),
+                    before: before.iter().cloned().collect(),
+                    after: Vec::new(),
+                });
+                open.push(result.matches.len() - 1);
+            }
+        }
+
+        if before.len() == CONTEXT_LINES {
+            before.pop_front();
+        }
+        before.push_back(line);
+    }
+}
+
+/// Timestamp at the start of `line`, as `[2026-10-14 09:30:00]` in
+/// `desktop.log` or `2026-10-14T09:30:00.000Z` from the backend.
+fn line_time(line: &str) -> Option<NaiveDateTime> {
+    let start = line.trim_start_matches('[');
+    let stamp = start.get(..19)?;
+    LINE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(stamp, format).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_file_with_context_and_since() {
+        let log = "[2026-10-13 23:59:00] Backend started\n\
+                   [2026-10-14 08:00:00] Connecting to node\n\
+                   retrying connection\n\
+                   [2026-10-14 08:00:05] Connected\n\
+                   [2026-10-14 08:01:00] Backend started\n";
+        let mut result = SearchResult {
+            matches: Vec::new(),
+            total: 0,
+        };
+        search_file(log.as_bytes(), "desktop.log", "connect", None, &mut result);
+        assert_eq!(result.total, 3);
+        assert_eq!(result.matches[0].line_number, 2);
+        assert_eq!(
+            result.matches[0].before,
+            ["[2026-10-13 23:59:00] Backend started"]
+        );
+        assert_eq!(
+            result.matches[2].after,
+            ["[2026-10-14 08:01:00] Backend started"]
+        );
+
+        let mut result = SearchResult {
+            matches: Vec::new(),
+            total: 0,
+        };
+        let since = parse_since("2026-10-14T00:00").unwrap();
+        search_file(
+            log.as_bytes(),
+            "desktop.log",
+            "started",
+            Some(since),
+            &mut result,
+        );
+        let lines: Vec<usize> = result.matches.iter().map(|m| m.line_number).collect();
+        assert_eq!(lines, [5]);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert!(parse_since("2026-10-14").is_ok());
+        assert!(parse_since("2026-10-14 08:30").is_ok());
+        assert!(parse_since("yesterday").is_err());
+    }
+}
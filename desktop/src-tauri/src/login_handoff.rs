@@ -0,0 +1,65 @@
+//! Opening the web UI already logged in. Before the browser or the native
+//! window is opened, the backend is asked for a one-time login token, which
+//! is added to the address as `?login=<token>`. The backend logs that page
+//! load in as its first admin and drops the token from the address; it is
+//! only good for a minute. Without a token (the backend isn't up yet, or
+//! `Config::no_auto_login`, read-only or kiosk mode) the plain address is
+//! opened and the web UI asks for a password as usual.
+
+use crate::backend_record::BackendRecord;
+use crate::config::Config;
+use crate::{api, log};
+
+/// `url` with a login token, when one can be had.
+pub fn url(config: &Config, url: &str) -> String {
+    if config.no_auto_login || config.read_only || config.kiosk {
+        return url.to_string();
+    }
+    let Some(control_token) = BackendRecord::load().and_then(|record| record.control_token) else {
+        return url.to_string();
+    };
+    match api::create_login_token(config.web_port, &control_token) {
+        Ok(login) => with_token(url, &login.token),
+        Err(e) => {
+            log(&format!("Opening the web UI without logging in: {}", e));
+            url.to_string()
+        }
+    }
+}
+
+/// `url` with `login=<token>` added to its query, ahead of any fragment.
+fn with_token(url: &str, token: &str) -> String {
+    let (address, fragment) = match url.find('#') {
+        Some(at) => url.split_at(at),
+        None => (url, ""),
+    };
+    let separator = if address.contains('?') {
+        "&"
+    } else if address.ends_with('/') || address.matches('/').count() > 2 {
+        "?"
+    } else {
+        "/?"
+    };
+    format!("{}{}login={}{}", address, separator, token, fragment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_token() {
+        assert_eq!(
+            with_token("http://localhost:8080", "abc"),
+            "http://localhost:8080/?login=abc"
+        );
+        assert_eq!(
+            with_token("http://localhost:8080/#notificationNav=%7B%7D", "abc"),
+            "http://localhost:8080/?login=abc#notificationNav=%7B%7D"
+        );
+        assert_eq!(
+            with_token("http://localhost:8080/meshmonitor?tab=map", "abc"),
+            "http://localhost:8080/meshmonitor?tab=map&login=abc"
+        );
+    }
+}
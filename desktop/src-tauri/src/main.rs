@@ -2,9 +2,18 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use meshmonitor_desktop_lib::{
-    start_apprise, start_backend, stop_apprise, stop_backend, tray, BackendState, Config,
+    accessibility, activity_report, addresses, adopt_backend, api, audio, audit, autostart,
+    backend_record, bandwidth, ble, channel_link, channel_qr, clipboard_watcher, close_button,
+    config, config_watcher, connection_check, database_move, device_backup, dry_run, external,
+    firmware, first_run, instance_lock, ipc, keyring, kiosk, load_tile_servers, log_search, mdns,
+    message_export, network_wait, node_select, notifications, offer_config_recovery,
+    platform_support, poller, port_release, preflight, push, quit, remote_instances,
+    resource_history, resource_path, safe_mode, serial, share_link, show_start_failure, shutdown,
+    signals, start_apprise, start_backend, startup_profile, status_bar, stop_backend, theme,
+    tile_cache, tile_download, tray, uptime, watchdog, webview, window_size, BackendState, Config,
 };
 use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
 
 // Tauri commands must be defined in the binary crate to avoid E0255 duplicate symbol errors
 
@@ -13,24 +22,305 @@ fn get_config() -> Result<Config, String> {
     Config::load()
 }
 
+/// Save the settings `update` makes to the current config, unless config.json
+/// was edited by hand since it was loaded or settings are locked. The login
+/// item follows `auto_start`; if it can't be changed, the old config is put
+/// back so config.json and the login item can't disagree. Returns the old
+/// and the new config.
+fn save_settings(update: impl FnOnce(&Config) -> Config) -> Result<(Config, Config), String> {
+    if config_watcher::has_unacknowledged_edit() {
+        return Err(
+            "config.json was changed outside MeshMonitor. Reload it before saving.".to_string(),
+        );
+    }
+    let previous = Config::load()?;
+    if previous.settings_locked {
+        return Err("Settings are locked on this computer.".to_string());
+    }
+    let config = update(&previous);
+    config.save()?;
+    if config.auto_start != previous.auto_start {
+        if let Err(e) = autostart::set(config.auto_start) {
+            let _ = previous.save();
+            return Err(e);
+        }
+    }
+    audit::record_config_change(audit::Source::Command, &previous, &config);
+    Ok((previous, config))
+}
+
 #[tauri::command]
 fn save_config(config: Config) -> Result<(), String> {
-    config.save()
+    let (previous, config) = save_settings(|_| config)?;
+    if previous.needs_setup() && !config.needs_setup() {
+        first_run::clear();
+    }
+    Ok(())
+}
+
+/// Add or remove the login item right away, rather than when Settings are
+/// next saved.
+#[tauri::command]
+fn set_auto_start(enabled: bool) -> Result<(), String> {
+    save_settings(|previous| Config {
+        auto_start: enabled,
+        ..previous.clone()
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_setup_state() -> first_run::State {
+    first_run::state()
 }
 
 #[tauri::command]
-fn get_web_url() -> Result<String, String> {
+fn setup_next(step: first_run::Step) -> Result<first_run::State, String> {
+    first_run::advance(step)
+}
+
+#[tauri::command]
+fn setup_back(step: first_run::Step) -> Result<first_run::State, String> {
+    first_run::back(step)
+}
+
+#[tauri::command]
+fn get_web_url() -> Result<Vec<addresses::WebAddress>, String> {
     let config = Config::load()?;
-    Ok(format!("http://localhost:{}", config.web_port))
+    Ok(addresses::web_addresses(config.web_port))
+}
+
+#[tauri::command]
+fn audit_accessibility(app: AppHandle) -> Vec<accessibility::AccessibilityIssue> {
+    accessibility::audit(&app)
+}
+
+#[tauri::command]
+fn get_audit_log(limit: Option<usize>) -> Vec<audit::AuditEntry> {
+    audit::recent(limit.unwrap_or(200))
+}
+
+#[tauri::command]
+fn export_uptime_report(format: String, days: Option<u32>) -> Result<String, String> {
+    uptime::export_report(&format, days.unwrap_or(30))
+}
+
+/// The activity report for `date` (`YYYY-MM-DD`, today by default) as a page.
+#[tauri::command]
+async fn get_activity_report(date: Option<String>) -> Result<String, String> {
+    let date = activity_report::parse_date(date.as_deref())?;
+    activity_report::build(&Config::load()?, date).map(|report| activity_report::render(&report))
+}
+
+/// Save the activity report for `date` to the reports folder.
+#[tauri::command]
+async fn save_activity_report(date: Option<String>) -> Result<String, String> {
+    let date = activity_report::parse_date(date.as_deref())?;
+    activity_report::save(&Config::load()?, date).map(|path| path.display().to_string())
+}
+
+#[tauri::command]
+fn get_backend_status(state: tauri::State<'_, BackendState>) -> watchdog::Status {
+    watchdog::status(&state)
+}
+
+#[tauri::command]
+fn get_resource_history() -> resource_history::History {
+    resource_history::history()
+}
+
+#[tauri::command]
+fn get_bandwidth_usage(days: Option<usize>) -> bandwidth::Report {
+    bandwidth::report(days.unwrap_or(7))
+}
+
+#[tauri::command]
+async fn create_share_link(minutes: u32) -> Result<share_link::ShareLink, String> {
+    share_link::create(&Config::load()?, minutes)
+}
+
+#[tauri::command]
+async fn search_logs(
+    pattern: String,
+    since: Option<String>,
+) -> Result<log_search::SearchResult, String> {
+    log_search::search(&config::get_logs_path()?, &pattern, since.as_deref())
+}
+
+#[tauri::command]
+fn download_map_region(app: AppHandle, region: tile_download::Region) -> Result<u64, String> {
+    tile_download::start(&app, region)
+}
+
+/// Save the API key of tile server `id`, or forget it when `key` is blank.
+#[tauri::command]
+fn set_tile_server_key(id: String, key: String) -> Result<(), String> {
+    let config = Config::load()?;
+    if !config.tile_servers.iter().any(|server| server.id == id) {
+        return Err(format!("There's no tile server \"{}\" in config.json", id));
+    }
+    let account = tile_cache::key_account(&id);
+    match key.trim() {
+        "" => keyring::delete(&account)?,
+        key => keyring::set(&account, key)?,
+    }
+    audit::record(audit::Source::Command, "tile_server_key_changed", &id);
+    load_tile_servers(&config);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_push_secret(id: String, secret: String) -> Result<(), String> {
+    let config = Config::load()?;
+    if !config.push_targets.iter().any(|target| target.id == id) {
+        return Err(format!("There's no push target \"{}\" in config.json", id));
+    }
+    let account = push::secret_account(&id);
+    match secret.trim() {
+        "" => keyring::delete(&account)?,
+        secret => keyring::set(&account, secret)?,
+    }
+    audit::record(audit::Source::Command, "push_secret_changed", &id);
+    Ok(())
+}
+
+/// Async since it waits on the push service or mail server.
+#[tauri::command]
+async fn test_push_target(id: String) -> Result<(), String> {
+    push::test(&Config::load()?, &id)
+}
+
+#[tauri::command]
+fn cancel_map_download() {
+    tile_download::cancel();
+}
+
+#[tauri::command]
+fn preflight_check(
+    app: AppHandle,
+    state: tauri::State<'_, BackendState>,
+) -> Result<Vec<preflight::Check>, String> {
+    Ok(preflight::run(
+        &resource_path(&app)?,
+        &Config::load()?,
+        state.is_running(),
+    ))
+}
+
+// Async so the network checks run off the main thread
+#[tauri::command]
+async fn diagnose_connection(host: Option<String>) -> Result<Vec<preflight::Check>, String> {
+    let config = Config::load()?;
+    let host = host
+        .filter(|host| !host.trim().is_empty())
+        .unwrap_or(config.meshtastic_ip);
+    Ok(connection_check::run(&host, config.meshtastic_port))
+}
+
+#[tauri::command]
+fn list_device_backups() -> Vec<device_backup::SavedBackup> {
+    device_backup::list()
+}
+
+/// Shows what a restore would change unless `apply` is set. Async since the
+/// backend talks to the node meanwhile.
+#[tauri::command]
+async fn restore_device_backup(name: String, apply: bool) -> Result<api::DeviceRestore, String> {
+    device_backup::restore(&Config::load()?, &name, apply)
+}
+
+#[tauri::command]
+fn get_theme() -> &'static str {
+    theme::name(theme::current())
+}
+
+#[tauri::command]
+fn add_channel_link(app: AppHandle, link: String) -> Result<(), String> {
+    let link = channel_link::parse(&link).ok_or_else(|| {
+        "That isn't a Meshtastic channel link (https://meshtastic.org/e/#…)".to_string()
+    })?;
+    channel_link::open(&app, link);
+    Ok(())
+}
+
+#[tauri::command]
+fn import_channel_qr(app: AppHandle, source: String) -> Result<(), String> {
+    match source.as_str() {
+        "file" => channel_qr::import_from_file(&app),
+        "clipboard" => channel_qr::import_from_clipboard(&app),
+        other => return Err(format!("Unknown QR code source: {}", other)),
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn update_firmware(app: AppHandle) {
+    firmware::check(&app);
+}
+
+#[tauri::command]
+fn list_serial_ports() -> Vec<String> {
+    serial::available_ports()
+}
+
+#[tauri::command]
+async fn discover_nodes() -> Result<Vec<mdns::Node>, String> {
+    mdns::discover()
+}
+
+#[tauri::command]
+async fn scan_ble_devices() -> Result<Vec<ble::Device>, String> {
+    ble::scan().await
+}
+
+#[tauri::command]
+async fn pair_ble_device(address: String) -> Result<(), String> {
+    ble::pair(&address).await?;
+    audit::record(audit::Source::Command, "ble_node_paired", &address);
+    Ok(())
+}
+
+// Async so the blocking folder picker runs off the main thread
+#[tauri::command]
+async fn pick_export_folder(app: AppHandle) -> Option<String> {
+    app.dialog()
+        .file()
+        .set_title("Choose a Folder for Exported Messages")
+        .blocking_pick_folder()
+        .and_then(|folder| folder.into_path().ok())
+        .map(|path| path.display().to_string())
+}
+
+#[tauri::command]
+fn tray_action(app: AppHandle, id: String) -> Result<(), String> {
+    tray::run_action(&app, &id)
+}
+
+#[tauri::command]
+fn set_backend_log_level(level: String) -> Result<(), String> {
+    ipc::send(&ipc::Command::SetLogLevel { level })
 }
 
 #[tauri::command]
 fn restart_backend(app: AppHandle, state: tauri::State<'_, BackendState>) -> Result<(), String> {
+    audit::record(audit::Source::Command, "backend_restarted", "");
+    safe_mode::leave();
+
     // Stop existing backend
     stop_backend(&state);
 
-    // Start new backend
-    let child = start_backend(&app)?;
+    // Nothing to start when a backend outside the app is monitored instead
+    if Config::load().unwrap_or_default().external_backend {
+        external::start(&app);
+        return Ok(());
+    }
+
+    // Start new backend once the old one has let go of the port and database
+    let child = port_release::wait(Config::load().unwrap_or_default().web_port)
+        .and_then(|()| start_backend(&app))
+        .inspect_err(|e| {
+            show_start_failure(&app, &Config::load().unwrap_or_default(), e);
+        })?;
 
     // Store in state
     let mut process = state.process.lock().unwrap();
@@ -39,90 +329,364 @@ fn restart_backend(app: AppHandle, state: tauri::State<'_, BackendState>) -> Res
     Ok(())
 }
 
+#[tauri::command]
+fn list_profiles() -> Result<Vec<config::Profile>, String> {
+    Ok(Config::load()?.profiles)
+}
+
+#[tauri::command]
+fn switch_profile(
+    app: AppHandle,
+    state: tauri::State<'_, BackendState>,
+    name: String,
+) -> Result<(), String> {
+    if config_watcher::has_unacknowledged_edit() {
+        return Err(
+            "config.json was changed outside MeshMonitor. Reload it before saving.".to_string(),
+        );
+    }
+    let previous = Config::load()?;
+    if previous.settings_locked {
+        return Err("Settings are locked on this computer.".to_string());
+    }
+    let mut config = previous.clone();
+    config.switch_profile(&name)?;
+    let db_path = config::database_path(&config)?;
+    config.save()?;
+    // Each profile has its own database; there's nothing to copy over
+    database_move::remember(&db_path);
+    audit::record_config_change(audit::Source::Command, &previous, &config);
+
+    // The web UI window would stay on the old profile's port
+    if config.web_port != previous.web_port {
+        if let Some(window) = app.get_webview_window(webview::WEB_UI_WINDOW) {
+            let _ = window.close();
+        }
+    }
+    restart_backend(app, state)
+}
+
 fn main() {
-    tauri::Builder::default()
+    startup_profile::begin();
+    let dry_run = dry_run::requested(&std::env::args().collect::<Vec<_>>());
+    let mut builder = tauri::Builder::default();
+    // A dry run may be made while MeshMonitor is running
+    if !dry_run {
+        // A second launch (e.g. a channel link clicked while running) hands
+        // its arguments to this instance and exits
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            if let Some(provisioning) = kiosk::from_args(&args) {
+                kiosk::provision_running(app, provisioning);
+            }
+            if let Some(link) = channel_link::from_args(&args) {
+                channel_link::open(app, link);
+            }
+        }));
+    }
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_dialog::init())
         .manage(BackendState::default())
-        .setup(|app| {
+        .setup(move |app| {
             let handle = app.handle().clone();
+            if dry_run {
+                std::process::exit(dry_run::run(&handle));
+            }
+
+            // Mark this run as in progress; the lock is released by `shutdown`
+            match instance_lock::acquire() {
+                Ok(true) => eprintln!("Previous run did not shut down cleanly"),
+                Ok(false) => {}
+                Err(e) => eprintln!("{}", e),
+            }
+            uptime::record(uptime::EventKind::DesktopStarted, "");
+
+            // Listen for the backend's control connection before it spawns
+            if let Err(e) = ipc::start_listener() {
+                eprintln!("{}", e);
+            }
+
+            // Find out whether native windows can work before creating any
+            webview::check();
+            theme::start(&handle);
+
+            // A backend left running by "Quit, Keep Backend Running" (or
+            // orphaned by a crash) is adopted rather than duplicated, along
+            // with the Apprise sidecar it already points at.
+            let adopted = backend_record::find_adoptable().is_some_and(|record| {
+                adopt_backend(&handle.state(), record);
+                println!("Adopted running backend");
+                true
+            });
 
             // Start the bundled Apprise notification sidecar (if present) before
             // the backend, so its loopback URL is available to inject as
             // APPRISE_URL when the Node backend spawns. A missing sidecar is
             // non-fatal — the app runs without bundled Apprise.
-            match start_apprise(&handle) {
-                Ok(Some((child, url))) => {
-                    let state: tauri::State<BackendState> = handle.state();
-                    *state.apprise.lock().unwrap() = Some(child);
-                    *state.apprise_url.lock().unwrap() = Some(url);
-                    println!("Apprise sidecar started");
+            if !adopted {
+                match start_apprise(&handle) {
+                    Ok(Some((child, url))) => {
+                        let state: tauri::State<BackendState> = handle.state();
+                        *state.apprise.lock().unwrap() = Some(child);
+                        *state.apprise_url.lock().unwrap() = Some(url);
+                        println!("Apprise sidecar started");
+                    }
+                    Ok(None) => println!("No Apprise sidecar bundled; skipping"),
+                    Err(e) => eprintln!("Failed to start Apprise sidecar: {}", e),
                 }
-                Ok(None) => println!("No Apprise sidecar bundled; skipping"),
-                Err(e) => eprintln!("Failed to start Apprise sidecar: {}", e),
+            }
+
+            // A corrupted config with a good backup is the user's call; without
+            // a backup there is nothing to restore, so set it aside and start over.
+            let corruption = config::config_corruption();
+            let awaiting_recovery = match &corruption {
+                Some(error) if config::has_valid_backup() => {
+                    offer_config_recovery(&handle, error);
+                    true
+                }
+                Some(error) => {
+                    eprintln!(
+                        "Config file is corrupted ({}), resetting to defaults",
+                        error
+                    );
+                    if let Err(e) = config::reset_corrupted() {
+                        eprintln!("Failed to reset configuration: {}", e);
+                    }
+                    false
+                }
+                None => false,
+            };
+
+            // Kiosk provisioning from the command line, before the config
+            // is read for startup
+            let args: Vec<String> = std::env::args().collect();
+            if let Some(provisioning) = kiosk::from_args(&args) {
+                if let Err(e) = kiosk::provision(provisioning) {
+                    eprintln!("Kiosk provisioning failed: {}", e);
+                }
+            }
+
+            // The session secret goes to the OS keyring instead of
+            // config.json, which other users may be able to read
+            if !awaiting_recovery {
+                config::store_session_secret();
             }
 
             // Load or create configuration
             let config = Config::load().unwrap_or_default();
 
             // Check if first-run setup is needed
-            if config.needs_setup() {
-                // Show setup window
-                let window = tauri::WebviewWindowBuilder::new(
-                    &handle,
-                    "setup",
-                    tauri::WebviewUrl::App("index.html".into()),
-                )
-                .title("MeshMonitor Setup")
-                .inner_size(450.0, 400.0)
-                .resizable(false)
-                .center()
-                .build()?;
-
-                window.show()?;
+            if awaiting_recovery {
+                // The recovery dialog starts the backend once the user chooses
+            } else if adopted {
+                // Already running
+            } else if config.needs_setup() && webview::show_setup_window(&handle) {
+                // The setup window starts the backend once it's configured
             } else {
-                // Start the backend server
-                match start_backend(&handle) {
-                    Ok(child) => {
-                        let state: tauri::State<BackendState> = handle.state();
-                        let mut process = state.process.lock().unwrap();
-                        *process = Some(child);
-                        println!("Backend started successfully");
+                if config.needs_setup() {
+                    // No native windows: start with defaults and let the web
+                    // UI take over; the user can adjust config.json later
+                    let mut config = config.clone();
+                    config.setup_completed = true;
+                    if let Err(e) = config.save() {
+                        eprintln!("Failed to save default configuration: {}", e);
                     }
-                    Err(e) => {
-                        eprintln!("Failed to start backend: {}", e);
-                        // Show error dialog or settings window
+                    webview::open_browser_when_ready(&handle, config.web_port);
+                }
+
+                // Explain a computer the backend can't run on, rather than
+                // leaving it to fail with an illegal instruction
+                if !config.external_backend {
+                    if let Ok(resource_path) = resource_path(&handle) {
+                        platform_support::check(&handle, &resource_path);
+                    }
+                }
+
+                // Start the backend server. At login the network may still be
+                // coming up, and a pre-start hook may be configured, so wait
+                // for those first.
+                if config.external_backend {
+                    external::start(&handle);
+                } else if network_wait::gated(&config) {
+                    network_wait::start_backend_when_ready(&handle, &config);
+                } else {
+                    match start_backend(&handle) {
+                        Ok(child) => {
+                            let state: tauri::State<BackendState> = handle.state();
+                            let mut process = state.process.lock().unwrap();
+                            *process = Some(child);
+                            println!("Backend started successfully");
+                        }
+                        Err(e) => {
+                            eprintln!("Failed to start backend: {}", e);
+                            show_start_failure(&handle, &config, &e);
+                        }
                     }
                 }
             }
 
-            // Setup system tray
-            tray::setup_tray(&handle)?;
+            // Keep the login item pointing at this executable, which moves
+            // when the app is updated or reinstalled elsewhere
+            if config.auto_start {
+                if let Err(e) = autostart::set(true) {
+                    eprintln!("Failed to update the login item: {}", e);
+                }
+            }
+
+            // Restore the packet-activity ticker before the tray reads its state
+            audio::set_enabled(config.packet_audio_enabled);
+
+            // Setup system tray, or the control window when the tray is turned
+            // off or the desktop has none. A kiosk has neither: the web UI
+            // is all it shows.
+            let tray_wanted = !config.no_tray || !webview::native_windows_available();
+            if config.kiosk && webview::native_windows_available() {
+                kiosk::start(&handle);
+            } else if tray_wanted && tray::tray_host_available() {
+                tray::setup_tray(&handle)?;
+            } else if !webview::show_control_window(&handle) {
+                eprintln!("Neither a tray nor native windows are available");
+            }
+
+            // Watch the backend for mesh events that raise native notifications
+            notifications::init();
+            poller::start_poller(&handle);
+
+            // Export new messages to the chosen folder on a schedule
+            message_export::start();
+
+            // Save each day's activity report after midnight, when enabled
+            activity_report::start();
+
+            // Back up the node's configuration into the data folder
+            device_backup::start();
+
+            // Check the other MeshMonitor servers listed in the tray
+            remote_instances::start(&handle);
+
+            // Offer to open copied node IDs and coordinates, when enabled
+            clipboard_watcher::start(&handle);
+
+            // Sample the backend's memory and CPU for the Settings chart
+            resource_history::start(&handle);
+
+            // SIGHUP reloads the configuration, SIGUSR1 logs the status
+            signals::start(&handle);
+
+            // Restart the backend if it stops answering heartbeats
+            watchdog::start_watchdog(&handle);
+
+            // Keep the status strip in the native web UI window current
+            status_bar::start(&handle);
+
+            // Watch config.json for hand edits
+            config_watcher::start_watcher(&handle);
+
+            // Move a node with several addresses to the one that answers
+            // after network changes
+            node_select::start_watcher(&handle);
+
+            // A channel link the app was launched with
+            if let Some(link) = channel_link::from_args(&args) {
+                channel_link::open(&handle, link);
+            }
 
             Ok(())
         })
         .on_window_event(|window, event| {
-            // Hide window instead of closing on close request (minimize to tray)
+            // Hide window instead of closing on close request (minimize to tray),
+            // unless `close_action` says otherwise for the web UI window.
+            // The control window stands in for the tray, so it only minimizes.
+            if let tauri::WindowEvent::ScaleFactorChanged { .. } = event {
+                if let Some(sizing) = window_size::sizing_for(window.label()) {
+                    window_size::apply(window, sizing);
+                }
+            }
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                window.hide().unwrap();
+                if window_size::sizing_for(window.label()).is_some() {
+                    window_size::remember(window);
+                }
+                if window.label() == webview::CONTROL_WINDOW {
+                    // In no-tray mode it's the app's main window: closing quits
+                    if Config::load().unwrap_or_default().no_tray {
+                        quit(window.app_handle(), false);
+                    } else {
+                        let _ = window.minimize();
+                    }
+                } else if window.label() == webview::WEB_UI_WINDOW {
+                    // A kiosk's display stays up
+                    if !Config::load().unwrap_or_default().kiosk {
+                        close_button::close_requested(window);
+                    }
+                } else {
+                    window.hide().unwrap();
+                }
                 api.prevent_close();
             }
         })
         .invoke_handler(tauri::generate_handler![
             get_config,
             save_config,
+            set_auto_start,
+            get_activity_report,
+            save_activity_report,
+            get_setup_state,
+            setup_next,
+            setup_back,
             get_web_url,
+            preflight_check,
+            diagnose_connection,
+            list_device_backups,
+            restore_device_backup,
+            search_logs,
+            download_map_region,
+            cancel_map_download,
+            set_tile_server_key,
+            set_push_secret,
+            test_push_target,
             restart_backend,
+            list_profiles,
+            switch_profile,
+            set_backend_log_level,
+            audit_accessibility,
+            get_audit_log,
+            export_uptime_report,
+            get_bandwidth_usage,
+            get_backend_status,
+            get_resource_history,
+            get_theme,
+            create_share_link,
+            add_channel_link,
+            import_channel_qr,
+            update_firmware,
+            list_serial_ports,
+            discover_nodes,
+            scan_ble_devices,
+            pair_ble_device,
+            pick_export_folder,
+            tray_action,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app, event| {
-            if let tauri::RunEvent::Exit = event {
-                // Stop the backend and Apprise sidecar when the app exits
+            // Shut down on ExitRequested, before the windows are closed, so a
+            // fast quit can't tear the app down while the backend is still
+            // writing. Exit repeats it for exits that skip ExitRequested;
+            // `shutdown` only runs once.
+            if let tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit = event {
                 let state: tauri::State<BackendState> = app.state();
-                stop_backend(&state);
-                stop_apprise(&state);
+                shutdown(&state);
+            }
+            // macOS delivers URL-scheme launches as an event, not arguments
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &event {
+                for url in urls {
+                    if let Some(link) = channel_link::parse(url.as_str()) {
+                        channel_link::open(app, link);
+                    }
+                }
             }
         });
 }
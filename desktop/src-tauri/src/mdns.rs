@@ -0,0 +1,264 @@
+//! Finding Meshtastic nodes on the LAN with mDNS, for the setup wizard's
+//! pick-list. Network nodes advertise `_meshtastic._tcp`; meshtasticd on a
+//! Linux box may only announce its web server as `_http._tcp`, so those are
+//! kept when their instance name mentions Meshtastic.
+//!
+//! Just enough DNS for a one-shot browse: the query goes to the mDNS group
+//! from an ephemeral port and asks for unicast answers, so nothing has to
+//! listen on port 5353 beside the system's own responder.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+const PORT: u16 = 5353;
+
+const MESHTASTIC_SERVICE: &str = "_meshtastic._tcp.local";
+const HTTP_SERVICE: &str = "_http._tcp.local";
+
+/// How long answers are collected.
+const BROWSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// When the query is sent again, for a packet lost on Wi-Fi.
+const RESEND_AFTER: Duration = Duration::from_secs(1);
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_SRV: u16 = 33;
+
+/// Class IN, with the top bit asking for a unicast answer.
+const CLASS_IN_UNICAST: u16 = 0x8001;
+
+/// A node found on the LAN.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Node {
+    /// Instance name, such as `Meshtastic_1a2b`.
+    pub name: String,
+    /// Host name without the trailing dot, such as `meshtastic-1a2b.local`.
+    pub host: String,
+    /// IPv4 address, or the answer's source when it wasn't included.
+    pub address: String,
+    pub port: u16,
+    /// The service it advertised.
+    pub service: String,
+}
+
+/// The records of one mDNS answer that matter here.
+#[derive(Debug, Default)]
+struct Answer {
+    /// Service → instance names.
+    ptr: Vec<(String, String)>,
+    /// Instance → port and target host.
+    srv: HashMap<String, (u16, String)>,
+    /// Host → address.
+    a: HashMap<String, Ipv4Addr>,
+}
+
+/// Browse for nodes for a few seconds.
+pub fn discover() -> Result<Vec<Node>, String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("Couldn't open a socket for mDNS: {}", e))?;
+    let query = query(&[MESHTASTIC_SERVICE, HTTP_SERVICE]);
+    let group = SocketAddr::from((GROUP, PORT));
+    socket
+        .send_to(&query, group)
+        .map_err(|e| format!("Couldn't send the mDNS query: {}", e))?;
+
+    let started = Instant::now();
+    let mut resent = false;
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut buf = [0u8; 9000];
+    while started.elapsed() < BROWSE_TIMEOUT {
+        if !resent && started.elapsed() >= RESEND_AFTER {
+            let _ = socket.send_to(&query, group);
+            resent = true;
+        }
+        let _ = socket.set_read_timeout(Some(Duration::from_millis(200)));
+        let (read, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(format!("Receiving mDNS answers failed: {}", e)),
+        };
+        let Some(answer) = parse(&buf[..read]) else {
+            continue;
+        };
+        for node in nodes_in(&answer, from) {
+            if !nodes.iter().any(|known| known.address == node.address) {
+                nodes.push(node);
+            }
+        }
+    }
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(nodes)
+}
+
+/// The nodes an answer from `from` describes.
+fn nodes_in(answer: &Answer, from: SocketAddr) -> Vec<Node> {
+    answer
+        .ptr
+        .iter()
+        .filter(|(service, instance)| {
+            service.eq_ignore_ascii_case(MESHTASTIC_SERVICE)
+                || (service.eq_ignore_ascii_case(HTTP_SERVICE)
+                    && instance.to_ascii_lowercase().contains("meshtastic"))
+        })
+        .map(|(service, instance)| {
+            let (port, host) = answer
+                .srv
+                .get(instance)
+                .cloned()
+                .unwrap_or_else(|| (0, String::new()));
+            let address = answer
+                .a
+                .get(&host)
+                .map(Ipv4Addr::to_string)
+                .unwrap_or_else(|| from.ip().to_string());
+            let name = instance
+                .strip_suffix(&format!(".{}", service))
+                .unwrap_or(instance);
+            Node {
+                name: name.to_string(),
+                host,
+                address,
+                port,
+                service: service.clone(),
+            }
+        })
+        .collect()
+}
+
+/// A PTR query for each of `services`.
+fn query(services: &[&str]) -> Vec<u8> {
+    // ID 0 and no flags, as mDNS queries have
+    let mut packet = vec![0, 0, 0, 0];
+    packet.extend_from_slice(&(services.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    for service in services {
+        for label in service.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN_UNICAST.to_be_bytes());
+    }
+    packet
+}
+
+/// The PTR, SRV and A records of a DNS response; `None` when it isn't one.
+fn parse(packet: &[u8]) -> Option<Answer> {
+    let word = |at: usize| -> Option<u16> {
+        Some(u16::from_be_bytes([*packet.get(at)?, *packet.get(at + 1)?]))
+    };
+    // Only responses
+    if word(2)? & 0x8000 == 0 {
+        return None;
+    }
+    let questions = word(4)?;
+    let records = u32::from(word(6)?) + u32::from(word(8)?) + u32::from(word(10)?);
+    let mut at = 12;
+    for _ in 0..questions {
+        at = name(packet, at)?.1 + 4;
+    }
+    let mut answer = Answer::default();
+    for _ in 0..records {
+        let (owner, next) = name(packet, at)?;
+        let kind = word(next)?;
+        let length = usize::from(word(next + 8)?);
+        let data = next + 10;
+        packet.get(data..data + length)?;
+        match kind {
+            TYPE_PTR => answer.ptr.push((owner, name(packet, data)?.0)),
+            TYPE_SRV => {
+                let port = word(data + 4)?;
+                answer.srv.insert(owner, (port, name(packet, data + 6)?.0));
+            }
+            TYPE_A if length == 4 => {
+                let octets = &packet[data..data + 4];
+                answer.a.insert(
+                    owner,
+                    Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]),
+                );
+            }
+            _ => {}
+        }
+        at = data + length;
+    }
+    Some(answer)
+}
+
+/// The name at `at`, following compression pointers, without the trailing
+/// dot; and where the record continues after it.
+fn name(packet: &[u8], mut at: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // A pointer loop would otherwise never end
+    for _ in 0..128 {
+        let length = *packet.get(at)?;
+        match length {
+            0 => {
+                return Some((labels.join("."), end.unwrap_or(at + 1)));
+            }
+            length if length & 0xC0 == 0xC0 => {
+                let pointer =
+                    usize::from(u16::from_be_bytes([length & 0x3F, *packet.get(at + 1)?]));
+                end.get_or_insert(at + 2);
+                at = pointer;
+            }
+            length => {
+                let label = packet.get(at + 1..at + 1 + usize::from(length))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                at += 1 + usize::from(length);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answer() {
+        // Response: PTR _meshtastic._tcp.local -> Meshtastic_1a2b._meshtastic._tcp.local,
+        // SRV 4403 meshtastic-1a2b.local, A 192.168.1.50, with compression
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 3, 0, 0, 0, 0];
+        let service_at = packet.len();
+        for label in ["_meshtastic", "_tcp", "local"] {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&[0, 12, 0, 1, 0, 0, 0, 120, 0, 18]);
+        let instance_at = packet.len();
+        packet.push(15);
+        packet.extend_from_slice(b"Meshtastic_1a2b");
+        packet.extend_from_slice(&[0xC0, service_at as u8]);
+        packet.extend_from_slice(&[0xC0, instance_at as u8]);
+        packet.extend_from_slice(&[0, 33, 0x80, 1, 0, 0, 0, 120, 0, 24, 0, 0, 0, 0, 0x11, 0x33]);
+        let host_at = packet.len();
+        packet.push(15);
+        packet.extend_from_slice(b"meshtastic-1a2b");
+        packet.extend_from_slice(&[0xC0, (service_at + 17) as u8]);
+        packet.extend_from_slice(&[0xC0, host_at as u8]);
+        packet.extend_from_slice(&[0, 1, 0x80, 1, 0, 0, 0, 120, 0, 4, 192, 168, 1, 50]);
+
+        let answer = parse(&packet).unwrap();
+        let from = SocketAddr::from(([192, 168, 1, 50], 5353));
+        assert_eq!(
+            nodes_in(&answer, from),
+            [Node {
+                name: "Meshtastic_1a2b".to_string(),
+                host: "meshtastic-1a2b.local".to_string(),
+                address: "192.168.1.50".to_string(),
+                port: 4403,
+                service: MESHTASTIC_SERVICE.to_string(),
+            }]
+        );
+        assert!(parse(&query(&[MESHTASTIC_SERVICE])).is_none());
+    }
+}
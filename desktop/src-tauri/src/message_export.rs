@@ -0,0 +1,256 @@
+//! Scheduled export of new messages to dated files in a folder
+//! (`Config::export_dir`), for spreadsheets, SIEMs and net logs that
+//! shouldn't read the database. Each run appends the messages received since
+//! the last one to `messages-YYYY-MM-DD.csv` or `.jsonl`, by the local date
+//! they were received.
+
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::api::{ApiMessage, BackendApi};
+use crate::config::{self, Config, ExportFormat};
+use crate::{idle, log};
+
+/// How often the scheduler checks whether an export is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Messages fetched per request.
+const PAGE_SIZE: usize = 500;
+
+const CSV_HEADER: &str = "received,id,from_node_id,from_name,to_node_id,channel,text\n";
+
+/// Guards against spawning a second scheduler thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Spawn the export scheduler. It idles while no export folder or API token
+/// is configured. Safe to call more than once.
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        let mut last_run: Option<Instant> = None;
+        let mut gate = idle::Gate::new("message export");
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let Ok(config) = Config::load() else {
+                continue;
+            };
+            let Some(dir) = config.export_dir.as_deref().filter(|dir| !dir.is_empty()) else {
+                continue;
+            };
+            let interval =
+                Duration::from_secs(u64::from(config.export_interval_minutes.max(1)) * 60);
+            if last_run.is_some_and(|run| run.elapsed() < interval) {
+                continue;
+            }
+            let Some(api) = BackendApi::from_config(&config) else {
+                continue;
+            };
+            if !gate.ready(&config) {
+                continue;
+            }
+            last_run = Some(Instant::now());
+            match export(&api, Path::new(dir), config.export_format) {
+                Ok(0) => {}
+                Ok(count) => log(&format!("Exported {} new messages to {}", count, dir)),
+                Err(e) => log(&format!("Message export failed: {}", e)),
+            }
+        }
+    });
+}
+
+/// Where the receive time of the last exported message is kept.
+fn cursor_path() -> Option<PathBuf> {
+    config::get_data_path()
+        .ok()
+        .map(|path| path.join("export_cursor.json"))
+}
+
+/// Append every message newer than the cursor and move the cursor past them.
+/// The first run only sets the cursor, so the history isn't dumped.
+fn export(api: &BackendApi, dir: &Path, format: ExportFormat) -> Result<usize, String> {
+    let path = cursor_path().ok_or("No data folder")?;
+    let Some(mut cursor) = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<i64>(&content).ok())
+    else {
+        let now = chrono::Utc::now().timestamp_millis();
+        return save_cursor(&path, now).map(|()| 0);
+    };
+
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let names: HashMap<String, String> = api
+        .nodes()
+        .map(|nodes| {
+            nodes
+                .iter()
+                .map(|node| (node.node_id.clone(), node.display_name()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut exported = 0;
+    loop {
+        let page = api.messages_after(cursor, PAGE_SIZE)?;
+        let (complete, next) = complete_prefix(&page, PAGE_SIZE);
+        let Some(next) = next else {
+            break;
+        };
+        for message in &page[..complete] {
+            append(dir, format, message, &names)?;
+        }
+        exported += complete;
+        cursor = next;
+        save_cursor(&path, cursor)?;
+        if page.len() < PAGE_SIZE {
+            break;
+        }
+    }
+    Ok(exported)
+}
+
+fn save_cursor(path: &Path, cursor: i64) -> Result<(), String> {
+    fs::write(path, cursor.to_string()).map_err(|e| format!("Failed to save export cursor: {}", e))
+}
+
+/// How many messages of a page can be written, and the cursor after them.
+/// The backend returns messages strictly after the cursor, so on a full page
+/// the messages sharing the last timestamp are held back for the next page
+/// unless the whole page shares it.
+pub(crate) fn complete_prefix(page: &[ApiMessage], page_size: usize) -> (usize, Option<i64>) {
+    let Some(last) = page.last() else {
+        return (0, None);
+    };
+    if page.len() < page_size {
+        return (page.len(), Some(last.timestamp));
+    }
+    let held = page
+        .iter()
+        .rev()
+        .take_while(|message| message.timestamp == last.timestamp)
+        .count();
+    if held == page.len() {
+        return (page.len(), Some(last.timestamp));
+    }
+    let complete = page.len() - held;
+    (complete, Some(page[complete - 1].timestamp))
+}
+
+fn append(
+    dir: &Path,
+    format: ExportFormat,
+    message: &ApiMessage,
+    names: &HashMap<String, String>,
+) -> Result<(), String> {
+    let received = chrono::DateTime::from_timestamp_millis(message.timestamp)
+        .unwrap_or_default()
+        .with_timezone(&chrono::Local);
+    let from_name = names.get(&message.from_node_id).map_or("", String::as_str);
+    let (extension, line) = match format {
+        ExportFormat::Csv => ("csv", csv_row(&received.to_rfc3339(), message, from_name)),
+        ExportFormat::Json => {
+            let object = serde_json::json!({
+                "received": received.to_rfc3339(),
+                "id": message.id,
+                "fromNodeId": message.from_node_id,
+                "fromName": from_name,
+                "toNodeId": message.to_node_id,
+                "channel": message.channel,
+                "text": message.text,
+            });
+            ("jsonl", format!("{}\n", object))
+        }
+    };
+    let path = dir.join(format!(
+        "messages-{}.{}",
+        received.format("%Y-%m-%d"),
+        extension
+    ));
+    let new_file = !path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    if new_file && format == ExportFormat::Csv {
+        file.write_all(CSV_HEADER.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    file.write_all(line.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn csv_row(received: &str, message: &ApiMessage, from_name: &str) -> String {
+    let fields = [
+        csv_field(received),
+        csv_field(&message.id),
+        csv_field(&message.from_node_id),
+        csv_field(&defuse(from_name)),
+        csv_field(&message.to_node_id),
+        message.channel.to_string(),
+        csv_field(&defuse(&message.text)),
+    ];
+    format!("{}\n", fields.join(","))
+}
+
+/// Text a spreadsheet would run as a formula gets a leading apostrophe,
+/// since anyone on the mesh can send it.
+fn defuse(text: &str) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, timestamp: i64) -> ApiMessage {
+        ApiMessage {
+            id: id.to_string(),
+            from_node_id: "!a1b2c3d4".to_string(),
+            to_node_id: "!ffffffff".to_string(),
+            text: "hello".to_string(),
+            channel: 0,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_full_pages_hold_back_the_last_timestamp() {
+        let page = [message("1", 10), message("2", 20), message("3", 20)];
+        assert_eq!(complete_prefix(&page, 3), (1, Some(10)));
+        assert_eq!(complete_prefix(&page, 4), (3, Some(20)));
+        assert_eq!(complete_prefix(&page[1..], 2), (2, Some(20)));
+        assert_eq!(complete_prefix(&[], 3), (0, None));
+    }
+
+    #[test]
+    fn test_csv_rows_quote_and_defuse_text() {
+        let mut message = message("42", 0);
+        message.text = "=HYPERLINK(\"x\"), hi".to_string();
+        assert_eq!(
+            csv_row("2026-01-01T00:00:00+00:00", &message, "Base, North"),
+            "2026-01-01T00:00:00+00:00,42,!a1b2c3d4,\"Base, North\",!ffffffff,0,\
+             \"'=HYPERLINK(\"\"x\"\"), hi\"\n"
+        );
+        message.channel = -1;
+        message.text = "-5 dBm".to_string();
+        assert!(csv_row("", &message, "").ends_with(",-1,'-5 dBm\n"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Startup gate for launches at login (`autostart::launched_at_login`):
+//! Wi-Fi is often still connecting then, and a backend started before the
+//! node is reachable comes up in a failed state. The backend is started
+//! once the network is up and a TCP node answers, or once
+//! `Config::network_wait_secs` have passed, whichever comes first. Any
+//! launch then waits for the [`prestart`](crate::prestart) hook. The web
+//! port shows a status page in the meantime.
+
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::config::{Config, ConnectionType};
+use crate::{
+    autostart, fallback, log, node_select, prestart, show_start_failure, start_backend,
+    wake_on_lan, BackendState,
+};
+
+/// How often the network and node are checked while waiting.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long one connection attempt to the node may take.
+const NODE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Whether starting the backend waits on anything this launch.
+pub fn gated(config: &Config) -> bool {
+    at_login(config) || prestart::configured(config)
+}
+
+fn at_login(config: &Config) -> bool {
+    autostart::launched_at_login() && config.network_wait_secs > 0
+}
+
+/// Start the backend in the background once the network is ready and the
+/// pre-start hook has passed.
+pub fn start_backend_when_ready<R: Runtime>(app: &AppHandle<R>, config: &Config) {
+    let app = app.clone();
+    let config = config.clone();
+    std::thread::spawn(move || {
+        if at_login(&config) {
+            wait(&app, &config);
+        }
+        prestart::wait(&app, &config);
+        let state = app.state::<BackendState>();
+        // Started from Settings or the tray in the meantime
+        if state.is_running() {
+            return;
+        }
+        match start_backend(&app) {
+            Ok(child) => *state.process.lock().unwrap() = Some(child),
+            Err(e) => {
+                eprintln!("Failed to start backend: {}", e);
+                show_start_failure(&app, &config, &e);
+            }
+        }
+    });
+}
+
+fn wait<R: Runtime>(app: &AppHandle<R>, config: &Config) {
+    // Bluetooth and serial nodes are reached without a network
+    if matches!(
+        config.connection_type,
+        ConnectionType::Ble | ConnectionType::Serial
+    ) {
+        return;
+    }
+    let timeout = Duration::from_secs(u64::from(config.network_wait_secs));
+    let node = node_address(config);
+    let started = Instant::now();
+    let mut shown = false;
+    let mut woken = false;
+    loop {
+        let network = has_network(
+            &if_addrs::get_if_addrs()
+                .unwrap_or_default()
+                .iter()
+                .map(|interface| interface.ip())
+                .collect::<Vec<_>>(),
+        );
+        let node_ready = node.is_none() || node_select::first_reachable(config).is_some();
+        if network && node_ready {
+            if shown {
+                log(&format!(
+                    "Network ready after {} s",
+                    started.elapsed().as_secs()
+                ));
+            }
+            return;
+        }
+        if started.elapsed() >= timeout {
+            log("Network still not ready; starting the backend anyway");
+            return;
+        }
+        if !shown {
+            log("Waiting for the network before starting the backend");
+            shown = true;
+        }
+        if network && !woken {
+            wake_on_lan::wake_automatically(config, "The node isn't reachable at login");
+            woken = true;
+        }
+        let detail = if network {
+            format!(
+                "The node at {} isn't reachable yet.",
+                node.as_deref().unwrap_or_default()
+            )
+        } else {
+            "This computer isn't connected to a network yet.".to_string()
+        };
+        fallback::show(
+            &app.state::<BackendState>(),
+            config.web_port,
+            "Waiting for the network before starting MeshMonitor",
+            Some(&detail),
+        );
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// `host:port` of the TCP node the backend will connect to, if any.
+pub(crate) fn node_address(config: &Config) -> Option<String> {
+    let ip = config.meshtastic_ip.trim();
+    (config.connection_type == ConnectionType::Tcp && !ip.is_empty())
+        .then(|| format!("{}:{}", ip, config.meshtastic_port))
+}
+
+/// Whether any interface has an address other than loopback and IPv6
+/// link-local, which exist before a network is joined.
+fn has_network(addresses: &[IpAddr]) -> bool {
+    addresses.iter().any(|ip| match ip {
+        IpAddr::V4(ip) => !ip.is_loopback() && !ip.is_link_local(),
+        IpAddr::V6(ip) => !ip.is_loopback() && (ip.segments()[0] & 0xffc0) != 0xfe80,
+    })
+}
+
+pub(crate) fn reachable(address: &str) -> bool {
+    address
+        .to_socket_addrs()
+        .ok()
+        .into_iter()
+        .flatten()
+        .any(|addr: SocketAddr| TcpStream::connect_timeout(&addr, NODE_TIMEOUT).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_local_and_loopback_are_not_a_network() {
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+        let link_local: IpAddr = "fe80::1c2d:3eff:fe4f:5a6b".parse().unwrap();
+        let autoconf: IpAddr = "169.254.10.20".parse().unwrap();
+        let lan: IpAddr = "192.168.1.50".parse().unwrap();
+        assert!(!has_network(&[loopback, link_local, autoconf]));
+        assert!(has_network(&[loopback, lan]));
+    }
+
+    #[test]
+    fn test_only_tcp_nodes_are_waited_for() {
+        let mut config = Config {
+            meshtastic_ip: "192.168.1.100".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(node_address(&config).as_deref(), Some("192.168.1.100:4403"));
+        config.connection_type = ConnectionType::Serial;
+        assert_eq!(node_address(&config), None);
+        config.connection_type = ConnectionType::Tcp;
+        config.meshtastic_ip = " ".to_string();
+        assert_eq!(node_address(&config), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Pinning the identity of the configured node (`Config::expected_node_id`),
+//! so a different radio answering at the same address or port, such as a
+//! swapped USB device or a neighbour's node after a DHCP change, doesn't go
+//! unnoticed. Meshtastic derives the node ID from the radio's MAC address,
+//! so the ID identifies the hardware. After each (re)connect the ID the
+//! backend reports is compared with the pinned one; the first node seen is
+//! pinned when none is, and a mismatch raises a warning dialog and a
+//! notification once per unexpected node.
+
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::config::Config;
+use crate::{audit, log, notifications};
+
+/// The unexpected node the alert was last raised for, so reconnects to the
+/// same one don't repeat it.
+static ALERTED: Mutex<Option<String>> = Mutex::new(None);
+
+/// How the connected node compares with the pinned one.
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    /// Nothing pinned yet; pin this one.
+    Pin(String),
+    Matches,
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+/// A node ID in the `!1a2b3c4d` form the backend reports, whatever case
+/// and with or without the `!`.
+fn normalize(id: &str) -> String {
+    format!(
+        "!{}",
+        id.trim().trim_start_matches('!').to_ascii_lowercase()
+    )
+}
+
+fn compare(expected: Option<&str>, actual: &str) -> Verdict {
+    let actual = normalize(actual);
+    match expected.map(str::trim).filter(|id| !id.is_empty()) {
+        None => Verdict::Pin(actual),
+        Some(expected) if normalize(expected) == actual => Verdict::Matches,
+        Some(expected) => Verdict::Mismatch {
+            expected: normalize(expected),
+            actual,
+        },
+    }
+}
+
+/// Check the node the backend just connected to, `actual` being the ID it
+/// reports for its local node.
+pub fn verify<R: Runtime>(app: &AppHandle<R>, config: &Config, actual: &str) {
+    match compare(config.expected_node_id.as_deref(), actual) {
+        Verdict::Matches => *ALERTED.lock().unwrap() = None,
+        Verdict::Pin(id) => {
+            if let Err(e) = pin(&id) {
+                log(&format!("Failed to pin node {}: {}", id, e));
+                return;
+            }
+            log(&format!("Pinned node {} as the expected node", id));
+            audit::record(audit::Source::Scheduler, "node_pinned", &id);
+        }
+        Verdict::Mismatch { expected, actual } => {
+            if ALERTED.lock().unwrap().replace(actual.clone()).as_deref() == Some(&actual) {
+                return;
+            }
+            alert(app, &expected, &actual);
+        }
+    }
+}
+
+/// Store `id` as the expected node, keeping everything else on disk.
+fn pin(id: &str) -> Result<(), String> {
+    let mut config = Config::load()?;
+    config.expected_node_id = Some(id.to_string());
+    config.save()
+}
+
+fn alert<R: Runtime>(app: &AppHandle<R>, expected: &str, actual: &str) {
+    let detail = format!("Expected node {}, connected to {}", expected, actual);
+    log(&detail);
+    audit::record(audit::Source::Scheduler, "node_identity_mismatch", &detail);
+    notifications::notify(
+        app,
+        "Connected to a different node",
+        &format!(
+            "MeshMonitor expected node {} but is connected to {}.",
+            expected, actual
+        ),
+        None,
+    );
+    let actual = actual.to_string();
+    app.dialog()
+        .message(format!(
+            "MeshMonitor is connected to node {}, not to node {} it was connected to \
+             before.\n\nIf you replaced your radio, trust the new node. Otherwise check \
+             the connection settings: another device may be answering at the node's \
+             address or port.",
+            actual, expected
+        ))
+        .title("Different Node Connected")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Trust New Node".to_string(),
+            "Dismiss".to_string(),
+        ))
+        .show(move |trust| {
+            if !trust {
+                return;
+            }
+            match pin(&actual) {
+                Ok(()) => {
+                    log(&format!("Trusted node {} as the expected node", actual));
+                    audit::record(audit::Source::Dialog, "node_pinned", &actual);
+                    *ALERTED.lock().unwrap() = None;
+                }
+                Err(e) => log(&format!("Failed to pin node {}: {}", actual, e)),
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_pins_first_node_and_flags_another() {
+        assert_eq!(compare(None, "!1A2B3C4D"), Verdict::Pin("!1a2b3c4d".into()));
+        assert_eq!(
+            compare(Some("  "), "!1a2b3c4d"),
+            Verdict::Pin("!1a2b3c4d".into())
+        );
+        assert_eq!(compare(Some("1A2B3C4D"), "!1a2b3c4d"), Verdict::Matches);
+        assert_eq!(
+            compare(Some("!1a2b3c4d"), "!deadbeef"),
+            Verdict::Mismatch {
+                expected: "!1a2b3c4d".into(),
+                actual: "!deadbeef".into()
+            }
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Choosing between the addresses one network node has on different
+//! networks, such as its Ethernet address at home and its Wi-Fi address on
+//! a field hotspot (`Config::meshtastic_ip`, then
+//! `Config::meshtastic_alt_ips`). The first that accepts a connection is
+//! passed to the backend when it starts. While it runs, the computer's own
+//! addresses are watched; after a network change the addresses are probed
+//! again and the backend is restarted if another one should be used.
+
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::config::{Config, ConnectionType};
+use crate::{
+    audit, log, network_wait, port_release, show_start_failure, start_backend, stop_backend,
+    BackendState,
+};
+
+/// How often the computer's addresses are compared.
+const WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Pause after a network change before probing, while DHCP and routes
+/// settle.
+const SETTLE_DELAY: Duration = Duration::from_secs(5);
+
+/// The node address the running backend was given.
+static CURRENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Guards against a second watcher thread.
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// The node's addresses in the order they're tried; empty unless the node
+/// is reached over the network.
+pub fn candidates(config: &Config) -> Vec<String> {
+    if config.connection_type != ConnectionType::Tcp {
+        return Vec::new();
+    }
+    let mut candidates: Vec<String> = Vec::new();
+    for ip in std::iter::once(&config.meshtastic_ip).chain(&config.meshtastic_alt_ips) {
+        let ip = ip.trim();
+        if !ip.is_empty() && !candidates.iter().any(|known| known == ip) {
+            candidates.push(ip.to_string());
+        }
+    }
+    candidates
+}
+
+/// The first of `candidates` that `reachable` accepts.
+fn select(candidates: &[String], reachable: impl Fn(&str) -> bool) -> Option<&str> {
+    candidates
+        .iter()
+        .map(String::as_str)
+        .find(|ip| reachable(ip))
+}
+
+fn probe(config: &Config) -> impl Fn(&str) -> bool + '_ {
+    move |ip: &str| {
+        let address = match ip.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, config.meshtastic_port),
+            _ => format!("{}:{}", ip, config.meshtastic_port),
+        };
+        network_wait::reachable(&address)
+    }
+}
+
+/// The first of the node's addresses that answers, if any does.
+pub fn first_reachable(config: &Config) -> Option<String> {
+    select(&candidates(config), probe(config)).map(str::to_string)
+}
+
+/// The address to give a backend that is starting, remembered for the
+/// watcher. With a single address there's nothing to probe; when none
+/// answers, the first is used and the backend keeps retrying it.
+pub fn choose(config: &Config) -> Option<String> {
+    let candidates = candidates(config);
+    let chosen = if candidates.len() > 1 {
+        let reachable = select(&candidates, probe(config)).map(str::to_string);
+        match &reachable {
+            Some(ip) => log(&format!("Node answers at {}", ip)),
+            None => log(&format!(
+                "Node doesn't answer at {}; using {}",
+                candidates.join(", "),
+                candidates[0]
+            )),
+        }
+        reachable.or_else(|| candidates.first().cloned())
+    } else {
+        candidates.first().cloned()
+    };
+    *CURRENT.lock().unwrap() = chosen.clone();
+    chosen
+}
+
+/// Watch for network changes while a node has more than one address.
+/// Safe to call more than once.
+pub fn start_watcher<R: Runtime>(app: &AppHandle<R>) {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut known = local_addresses();
+        loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let now = local_addresses();
+            if now == known {
+                continue;
+            }
+            known = now;
+            std::thread::sleep(SETTLE_DELAY);
+            reevaluate(&app);
+        }
+    });
+}
+
+fn local_addresses() -> BTreeSet<IpAddr> {
+    if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .iter()
+        .map(|interface| interface.ip())
+        .filter(|ip| !ip.is_loopback())
+        .collect()
+}
+
+/// Restart the backend if, on the network just joined, the node answers at
+/// a different address than the one it was given.
+fn reevaluate<R: Runtime>(app: &AppHandle<R>) {
+    let config = Config::load().unwrap_or_default();
+    let state = app.state::<BackendState>();
+    if config.external_backend || !state.is_running() || candidates(&config).len() < 2 {
+        return;
+    }
+    let Some(best) = first_reachable(&config) else {
+        log("Network changed; the node doesn't answer at any of its addresses yet");
+        return;
+    };
+    let current = CURRENT.lock().unwrap().clone();
+    if current.as_deref() == Some(best.as_str()) {
+        return;
+    }
+    let detail = format!(
+        "Network changed; the node now answers at {} instead of {}",
+        best,
+        current.as_deref().unwrap_or("none")
+    );
+    log(&format!("{}; restarting the backend", detail));
+    audit::record(audit::Source::Scheduler, "backend_restarted", &detail);
+    stop_backend(&state);
+    match port_release::wait(config.web_port).and_then(|()| start_backend(app)) {
+        Ok(child) => *state.process.lock().unwrap() = Some(child),
+        Err(e) => {
+            log(&format!("Failed to restart backend: {}", e));
+            show_start_failure(app, &config, &e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_in_order_and_first_reachable_wins() {
+        let mut config = Config {
+            meshtastic_ip: "192.168.1.20".to_string(),
+            meshtastic_alt_ips: vec![
+                " 10.0.0.20 ".to_string(),
+                "".to_string(),
+                "192.168.1.20".to_string(),
+                "meshtastic.local".to_string(),
+            ],
+            ..Config::default()
+        };
+        let candidates = candidates(&config);
+        assert_eq!(
+            candidates,
+            ["192.168.1.20", "10.0.0.20", "meshtastic.local"]
+        );
+        assert_eq!(
+            select(&candidates, |ip| ip != "192.168.1.20"),
+            Some("10.0.0.20")
+        );
+        assert_eq!(select(&candidates, |_| false), None);
+
+        config.connection_type = ConnectionType::Serial;
+        assert!(super::candidates(&config).is_empty());
+    }
+}
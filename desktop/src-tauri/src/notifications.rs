@@ -0,0 +1,256 @@
+//! OS-native desktop notifications raised by the Rust shell.
+//!
+//! Message notifications are grouped per conversation: further messages
+//! from the same node or on the same channel update one notification
+//! ("KD7XYZ: 3 new messages") until it's opened or dismissed. On Linux the
+//! notification is replaced in place through the XDG `replaces_id`; the
+//! Windows and macOS notification centers, which the notification library
+//! can't address by tag or thread, show each update as a new notification
+//! carrying the running count, grouped under the app.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::config::Config;
+use crate::login_handoff;
+
+/// Bundle identifier from tauri.conf.json. Windows uses it as the toast
+/// AppUserModelID and macOS to attribute notifications to the app bundle.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+const APP_ID: &str = "org.meshmonitor.desktop";
+
+/// One-time platform setup; call before the first notification is shown.
+pub fn init() {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = notify_rust::set_application(APP_ID);
+    }
+}
+
+/// A direct-message thread or a channel, whose message notifications are
+/// grouped.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Conversation {
+    /// Direct messages from this node.
+    Direct(String),
+    /// Messages on this channel index.
+    Channel(i64),
+}
+
+/// A conversation's notification, while it's shown.
+#[derive(Default)]
+struct Group {
+    unread: usize,
+    /// Bumped for every update, so the waiter of a replaced notification
+    /// can tell it's stale.
+    generation: u64,
+    /// Notification ID to replace (Linux).
+    id: Option<u32>,
+}
+
+static GROUPS: Mutex<BTreeMap<Conversation, Group>> = Mutex::new(BTreeMap::new());
+
+/// Show a notification. When `click_url` is set, activating the notification
+/// opens that URL in the default browser.
+pub fn notify<R: Runtime>(app: &AppHandle<R>, title: &str, body: &str, click_url: Option<String>) {
+    let Some(handle) = show(title, body, None) else {
+        return;
+    };
+
+    // Waiting for the action blocks until the notification is clicked or
+    // dismissed, so each one gets its own short-lived thread.
+    let app = app.clone();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|action| {
+            if action == "__closed" {
+                return;
+            }
+            if let Some(url) = click_url {
+                open(&app, &url);
+            }
+        });
+    });
+}
+
+/// Notify `new` messages in `conversation`, named `name`, the newest saying
+/// `latest`. Activating the notification opens `click_url`.
+pub fn message<R: Runtime>(
+    app: &AppHandle<R>,
+    conversation: Conversation,
+    name: &str,
+    new: usize,
+    latest: &str,
+    click_url: String,
+) {
+    let (unread, generation, replaces) = {
+        let mut groups = GROUPS.lock().unwrap();
+        let group = groups.entry(conversation.clone()).or_default();
+        group.unread += new;
+        group.generation += 1;
+        (group.unread, group.generation, group.id)
+    };
+    let Some(handle) = show(&group_title(name, unread), latest, replaces) else {
+        return;
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Some(group) = GROUPS.lock().unwrap().get_mut(&conversation) {
+        group.id = Some(handle.id());
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|action| {
+            // A replaced notification's waiter may see the new one's action
+            {
+                let mut groups = GROUPS.lock().unwrap();
+                if groups.get(&conversation).map(|group| group.generation) != Some(generation) {
+                    return;
+                }
+                groups.remove(&conversation);
+            }
+            if action != "__closed" {
+                open(&app, &click_url);
+            }
+        });
+    });
+}
+
+/// Start every conversation's count afresh, once the messages are read in
+/// the web UI.
+pub fn clear_groups() {
+    GROUPS.lock().unwrap().clear();
+}
+
+/// "KD7XYZ" for one message, "KD7XYZ: 3 new messages" for more.
+fn group_title(name: &str, unread: usize) -> String {
+    if unread > 1 {
+        format!(
+            "{}: {} new messages",
+            name,
+            crate::locale::current().count(unread as u64)
+        )
+    } else {
+        name.to_string()
+    }
+}
+
+fn show(title: &str, body: &str, replaces: Option<u32>) -> Option<notify_rust::NotificationHandle> {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .summary(title)
+        .body(body)
+        .appname("MeshMonitor")
+        .action("default", "Open");
+    #[cfg(target_os = "windows")]
+    notification.app_id(APP_ID);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Some(id) = replaces {
+        notification.id(id);
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let _ = replaces;
+
+    notification
+        .show()
+        .inspect_err(|e| eprintln!("Failed to show notification: {}", e))
+        .ok()
+}
+
+fn open<R: Runtime>(app: &AppHandle<R>, url: &str) {
+    let url = login_handoff::url(&Config::load().unwrap_or_default(), url);
+    if let Err(e) = app.opener().open_url(&url, None::<&str>) {
+        eprintln!("Failed to open notification target: {}", e);
+    }
+}
+
+/// Deep link into the web UI that selects a node's direct-message view,
+/// using the same `#notificationNav=` hash the web push notifications use
+/// (see `usePushNotificationNavigation` in the web UI).
+pub fn dm_url(web_port: u16, node_id: &str) -> String {
+    nav_url(
+        web_port,
+        serde_json::json!({ "type": "dm", "senderNodeId": node_id }),
+    )
+}
+
+/// Deep link into the web UI that selects a node on the Nodes page, with
+/// its details.
+pub fn node_url(web_port: u16, node_id: &str) -> String {
+    nav_url(
+        web_port,
+        serde_json::json!({ "type": "node", "nodeId": node_id }),
+    )
+}
+
+/// Deep link into the web UI that selects channel `channel`.
+pub fn channel_url(web_port: u16, channel: i64) -> String {
+    nav_url(
+        web_port,
+        serde_json::json!({ "type": "channel", "channelId": channel }),
+    )
+}
+
+/// Deep link into the web UI that centers the map on `latitude`, `longitude`.
+pub fn map_url(web_port: u16, latitude: f64, longitude: f64) -> String {
+    nav_url(
+        web_port,
+        serde_json::json!({ "type": "map", "latitude": latitude, "longitude": longitude }),
+    )
+}
+
+fn nav_url(web_port: u16, nav: serde_json::Value) -> String {
+    let nav = nav.to_string();
+    format!(
+        "http://localhost:{}/#notificationNav={}",
+        web_port,
+        percent_encode(&nav)
+    )
+}
+
+/// Percent-encode everything outside the RFC 3986 unreserved set.
+fn percent_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() * 3);
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dm_url_encodes_navigation_payload() {
+        let url = dm_url(8080, "!a1b2c3d4");
+        assert!(url.starts_with("http://localhost:8080/#notificationNav=%7B"));
+        assert!(url.contains("%22senderNodeId%22%3A%22%21a1b2c3d4%22"));
+        assert!(url.contains("%22type%22%3A%22dm%22"));
+        assert!(!url[url.find('=').unwrap() + 1..].contains('"'));
+    }
+
+    #[test]
+    fn test_node_url_opens_node_details() {
+        let url = node_url(8080, "!a1b2c3d4");
+        let nav = url
+            .strip_prefix("http://localhost:8080/#notificationNav=")
+            .unwrap();
+        assert_eq!(
+            nav,
+            percent_encode(r#"{"nodeId":"!a1b2c3d4","type":"node"}"#)
+        );
+    }
+
+    #[test]
+    fn test_group_title_counts_unread_messages() {
+        assert_eq!(group_title("KD7XYZ", 1), "KD7XYZ");
+        assert_eq!(group_title("KD7XYZ", 3), "KD7XYZ: 3 new messages");
+    }
+}
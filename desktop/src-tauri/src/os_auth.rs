@@ -0,0 +1,136 @@
+//! Optional OS authentication gate (`Config::require_os_auth`) in front of
+//! Settings and destructive actions, so a shared shack computer can display
+//! MeshMonitor without letting anyone reconfigure it. Uses Windows Hello,
+//! the macOS administrator prompt (which accepts Touch ID), or polkit.
+
+use std::process::Command;
+use tauri::{AppHandle, Runtime};
+
+use crate::config::{self, Config};
+use crate::log;
+
+/// Run `action` once the user passes OS authentication, or straight away
+/// when the gate is off. Authentication blocks on a system prompt, so it
+/// runs on its own thread; when it fails, `action` is dropped and the user
+/// is told why.
+pub fn gate<R: Runtime>(app: &AppHandle<R>, reason: &str, action: impl FnOnce() + Send + 'static) {
+    if !required() {
+        action();
+        return;
+    }
+
+    let app = app.clone();
+    let reason = reason.to_string();
+    std::thread::spawn(move || match verify(&reason) {
+        Ok(()) => action(),
+        Err(e) => {
+            log(&format!("Authentication refused: {}", e));
+            use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+            app.dialog()
+                .message(e)
+                .title("Authentication required")
+                .kind(MessageDialogKind::Warning)
+                .show(|_| {});
+        }
+    });
+}
+
+/// Whether the gate is on. While config.json is corrupted, `Config::load`
+/// returns defaults, so the last good backup decides instead; otherwise
+/// corrupting the file would be a way around the gate.
+fn required() -> bool {
+    let config = if config::config_corruption().is_some() {
+        config::load_backup()
+    } else {
+        Config::load().ok()
+    };
+    config.is_some_and(|config| config.require_os_auth)
+}
+
+/// Ask the OS to confirm the user's identity, blocking until they answer.
+/// `reason` completes "Authenticate to …" in the system prompt.
+pub fn verify(reason: &str) -> Result<(), String> {
+    let status = auth_command(&format!("Authenticate to {}.", reason))
+        .status()
+        .map_err(|e| format!("System authentication is unavailable: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Authentication was cancelled or failed.".to_string())
+    }
+}
+
+#[cfg(windows)]
+fn auth_command(prompt: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    // Windows Hello via UserConsentVerifier, awaited through AsTask since
+    // PowerShell has no native await for WinRT operations
+    let script = format!(
+        "Add-Type -AssemblyName System.Runtime.WindowsRuntime; \
+         $asTask = ([System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {{ \
+           $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and \
+           $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' }})[0]; \
+         $verifier = [Windows.Security.Credentials.UI.UserConsentVerifier,Windows.Security.Credentials.UI,ContentType=WindowsRuntime]; \
+         $op = $verifier::RequestVerificationAsync({}); \
+         $task = $asTask.MakeGenericMethod([Windows.Security.Credentials.UI.UserConsentVerificationResult]).Invoke($null, @($op)); \
+         $task.Wait(); \
+         if ($task.Result -eq 'Verified') {{ exit 0 }} else {{ exit 1 }}",
+        powershell_string(prompt)
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(target_os = "macos")]
+fn auth_command(prompt: &str) -> Command {
+    let script = format!(
+        "do shell script \"/usr/bin/true\" with prompt \"{}\" with administrator privileges",
+        applescript_escape(prompt)
+    );
+    let mut cmd = Command::new("osascript");
+    cmd.args(["-e", &script]);
+    cmd
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn auth_command(_prompt: &str) -> Command {
+    // polkit shows its own prompt; the action only asks "are you an admin"
+    let mut cmd = Command::new("pkcheck");
+    cmd.args([
+        "--action-id",
+        "org.freedesktop.policykit.exec",
+        "--process",
+        &std::process::id().to_string(),
+        "--allow-user-interaction",
+    ]);
+    cmd
+}
+
+/// Quote `value` as a PowerShell single-quoted string.
+#[cfg(windows)]
+fn powershell_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Escape `value` for use inside an AppleScript double-quoted string.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn applescript_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applescript_escape() {
+        assert_eq!(
+            applescript_escape(r#"Open "Settings" \ now"#),
+            r#"Open \"Settings\" \\ now"#
+        );
+    }
+}
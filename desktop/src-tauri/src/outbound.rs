@@ -0,0 +1,80 @@
+//! HTTP requests that leave this computer: firmware and installer
+//! downloads, map tiles from upstream tile servers and webhooks all go
+//! through `Client`, which refuses every request while privacy mode
+//! (`Config::privacy_mode`) is on. Connections that aren't HTTP, such as
+//! mail to an SMTP server, ask `ensure_allowed` first. New features that
+//! talk to the internet use one of the two, so the switch covers them
+//! without further checks. Requests to the backend on loopback and to the
+//! node and probes on the local network don't go through it.
+
+use crate::config::Config;
+
+/// An HTTP client that only sends while privacy mode is off.
+#[derive(Clone)]
+pub struct Client {
+    agent: ureq::Agent,
+}
+
+impl Client {
+    pub fn new(builder: ureq::AgentBuilder) -> Client {
+        Client {
+            agent: builder.build(),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Result<ureq::Request, String> {
+        check(private(), url)?;
+        Ok(self.agent.get(url))
+    }
+
+    pub fn post(&self, url: &str) -> Result<ureq::Request, String> {
+        check(private(), url)?;
+        Ok(self.agent.post(url))
+    }
+}
+
+/// Fails while privacy mode is on, for a connection to `host` made without
+/// `Client`.
+pub fn ensure_allowed(host: &str) -> Result<(), String> {
+    check(private(), host)
+}
+
+/// Whether privacy mode is on. Read from disk each time, so turning it on
+/// in Settings takes effect without a restart.
+pub fn private() -> bool {
+    Config::load().is_ok_and(|config| config.privacy_mode)
+}
+
+fn check(private: bool, url: &str) -> Result<(), String> {
+    if !private {
+        return Ok(());
+    }
+    let host = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    Err(format!(
+        "Privacy mode is on, so MeshMonitor doesn't connect to {}",
+        host
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_refuses_only_in_privacy_mode() {
+        assert_eq!(check(false, "https://example.com/hook"), Ok(()));
+        assert_eq!(
+            check(true, "https://tile.example.com/1/2/3.png?key=secret"),
+            Err("Privacy mode is on, so MeshMonitor doesn't connect to tile.example.com".into())
+        );
+        assert_eq!(
+            check(true, "smtp.example.com"),
+            Err("Privacy mode is on, so MeshMonitor doesn't connect to smtp.example.com".into())
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Computers the backend can't run on, explained in a dialog instead of
+//! left to a backend that dies with `STATUS_ILLEGAL_INSTRUCTION` or "bad CPU
+//! type" in server-stderr.log. At launch the host is checked for a 32-bit
+//! system, a processor other than x64 or ARM64, and a bundle with no Node.js
+//! for its processor, such as an x64-only build on Windows 10 on ARM, which
+//! can't emulate x64. A backend that still exits on an illegal instruction
+//! gets the same kind of dialog. Each links to the release page, which has
+//! the build for every platform; the dialog is shown once per run.
+
+use std::path::Path;
+use std::process::ExitStatus;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::sidecar_arch::{self, Arch, WINDOWS_11_BUILD};
+use crate::{log, repair};
+
+/// Whether a dialog was shown this run.
+static EXPLAINED: AtomicBool = AtomicBool::new(false);
+
+/// Why the backend can't run here.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// A processor no Node.js is bundled for, by Rust's name for it.
+    UnsupportedProcessor(String),
+    /// A 32-bit operating system.
+    ThirtyTwoBit,
+    /// Windows 10 on ARM with no ARM64 build of Node.js bundled.
+    WindowsTenOnArm,
+    /// Node.js is bundled, but not for this processor.
+    WrongBuild(Arch),
+    /// The backend was stopped for an instruction the processor lacks.
+    IllegalInstruction(Option<Arch>),
+}
+
+impl Problem {
+    fn message(&self) -> String {
+        let advice = "Download the MeshMonitor Desktop build for this computer from the release \
+                      page; your data and settings are kept.";
+        match self {
+            Problem::UnsupportedProcessor(arch) => format!(
+                "MeshMonitor Desktop can't run on this computer's processor ({}). It runs on \
+                 64-bit x64 (Intel and AMD) and ARM64 processors.",
+                arch
+            ),
+            Problem::ThirtyTwoBit => "MeshMonitor Desktop needs a 64-bit operating system, and \
+                                      this is a 32-bit one. Its server can't run here."
+                .to_string(),
+            Problem::WindowsTenOnArm => format!(
+                "This installation of MeshMonitor only includes an x64 server, and Windows 10 on \
+                 ARM can't run x64 programs. Install the ARM64 build, or update to Windows 11, \
+                 which can. {}",
+                advice
+            ),
+            Problem::WrongBuild(host) => format!(
+                "This installation of MeshMonitor was made for a different processor than this \
+                 computer's ({}), so its server can't run here. {}",
+                arch_name(*host),
+                advice
+            ),
+            Problem::IllegalInstruction(host) => format!(
+                "MeshMonitor's server was stopped by the processor for using an instruction it \
+                 doesn't have. This happens when the build doesn't match the computer, such as an \
+                 x64 build emulated on an ARM processor{}. {}",
+                host.map(|host| format!(" (this computer's processor is {})", arch_name(host)))
+                    .unwrap_or_default(),
+                advice
+            ),
+        }
+    }
+}
+
+fn arch_name(arch: Arch) -> &'static str {
+    match arch {
+        Arch::X64 => "x64",
+        Arch::Arm64 => "ARM64",
+        Arch::X86 => "32-bit x86",
+    }
+}
+
+/// What keeps the backend from running on `host`, given whether none of the
+/// bundled Node.js binaries can run there and the Windows build, if any.
+fn problem(host: Option<Arch>, none_runnable: bool, windows_build: Option<u32>) -> Option<Problem> {
+    match host {
+        None => Some(Problem::UnsupportedProcessor(
+            std::env::consts::ARCH.to_string(),
+        )),
+        Some(Arch::X86) => Some(Problem::ThirtyTwoBit),
+        Some(Arch::Arm64)
+            if none_runnable && windows_build.is_some_and(|b| b < WINDOWS_11_BUILD) =>
+        {
+            Some(Problem::WindowsTenOnArm)
+        }
+        Some(host) if none_runnable => Some(Problem::WrongBuild(host)),
+        Some(_) => None,
+    }
+}
+
+/// Check this computer at launch, with the app's resources in
+/// `resource_path`, and explain any problem.
+pub fn check<R: Runtime>(app: &AppHandle<R>, resource_path: &Path) {
+    let host = sidecar_arch::host_arch();
+    let none_runnable = sidecar_arch::none_runnable(&resource_path.join("binaries"), host);
+    if let Some(problem) = problem(host, none_runnable, sidecar_arch::windows_build()) {
+        explain(app, &problem);
+    }
+}
+
+/// Explain a backend that exited with `status`, if it was an illegal
+/// instruction.
+pub fn backend_exited<R: Runtime>(app: &AppHandle<R>, status: &ExitStatus) {
+    if illegal_instruction(status) {
+        explain(app, &Problem::IllegalInstruction(sidecar_arch::host_arch()));
+    }
+}
+
+#[cfg(windows)]
+fn illegal_instruction(status: &ExitStatus) -> bool {
+    const STATUS_ILLEGAL_INSTRUCTION: u32 = 0xC000_001D;
+    status
+        .code()
+        .is_some_and(|code| code as u32 == STATUS_ILLEGAL_INSTRUCTION)
+}
+
+#[cfg(unix)]
+fn illegal_instruction(status: &ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal() == Some(libc::SIGILL)
+}
+
+fn explain<R: Runtime>(app: &AppHandle<R>, problem: &Problem) {
+    if EXPLAINED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let message = problem.message();
+    log(&format!("Unsupported platform: {}", message));
+    let url = repair::release_url(&app.package_info().version.to_string());
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title("Unsupported Computer")
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Open Download Page".to_string(),
+            "Close".to_string(),
+        ))
+        .show(move |open| {
+            if open {
+                let _ = handle.opener().open_url(url, None::<&str>);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problem() {
+        assert_eq!(problem(Some(Arch::X64), false, None), None);
+        assert_eq!(problem(Some(Arch::Arm64), false, Some(19045)), None);
+        assert_eq!(
+            problem(Some(Arch::X86), false, None),
+            Some(Problem::ThirtyTwoBit)
+        );
+        assert_eq!(
+            problem(Some(Arch::Arm64), true, Some(19045)),
+            Some(Problem::WindowsTenOnArm)
+        );
+        assert_eq!(
+            problem(Some(Arch::Arm64), true, Some(22631)),
+            Some(Problem::WrongBuild(Arch::Arm64))
+        );
+        assert!(matches!(
+            problem(None, false, None),
+            Some(Problem::UnsupportedProcessor(_))
+        ));
+        assert!(Problem::IllegalInstruction(Some(Arch::Arm64))
+            .message()
+            .contains("this computer's processor is ARM64"));
+    }
+}
@@ -0,0 +1,432 @@
+//! Background poller that watches the backend's v1 API for mesh events the
+//! desktop shell reacts to natively (new-node and message notifications,
+//! spoken messages, the unread-DM tray indicator, webhooks, the node
+//! identity check).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Runtime};
+
+use crate::api::{ApiMessage, ApiNode, BackendApi};
+use crate::backend_record::BackendRecord;
+use crate::config::{self, Config};
+use crate::notifications::Conversation;
+use crate::polling::PollingPreset;
+use crate::webhooks::{self, EventKind};
+use crate::{
+    attention, incidents, log, node_identity, notifications, push, tts, uptime, wake_on_lan,
+};
+
+/// Mutable state carried between polls.
+struct PollerState {
+    known_nodes: KnownNodes,
+    /// Node ID → display name, refreshed with every node poll, used to name
+    /// message senders.
+    node_names: HashMap<String, String>,
+    /// Node ID → last known latitude and longitude, refreshed with the node
+    /// list, for incident snapshots.
+    node_positions: HashMap<String, (f64, f64)>,
+    /// Channel index → display name, refreshed with the node list.
+    channel_names: HashMap<i64, String>,
+    /// Timestamp (Unix ms) of the newest message already handled. Starts at
+    /// launch time so history is never replayed.
+    last_message_ms: i64,
+    local_node_id: Option<String>,
+    /// Backend PID and whether it last reported its mesh link as connected,
+    /// so only changes are recorded in the uptime history.
+    link: Option<(u32, bool)>,
+}
+
+/// Guards against spawning a second poller thread if setup runs twice.
+static POLLER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Backend PID and whether its mesh link was up when last polled.
+static LINK: Mutex<Option<(u32, bool)>> = Mutex::new(None);
+
+/// The node's channels as last polled, as (index, name) by index.
+static CHANNELS: Mutex<Vec<(i64, String)>> = Mutex::new(Vec::new());
+
+/// The node's channels as last polled, as (index, name) by index, for the
+/// tray's Mute Channels submenu. Empty until the first node poll.
+pub fn channels() -> Vec<(i64, String)> {
+    CHANNELS.lock().unwrap().clone()
+}
+
+/// Backend PID and whether it reported its link to the node as up, for the
+/// tray's status dot. `None` until the first poll, which needs an API token.
+pub fn link() -> Option<(u32, bool)> {
+    *LINK.lock().unwrap()
+}
+
+/// Spawn the poller thread. Safe to call more than once; only the first call
+/// starts a thread. The poller idles until an API token is configured.
+pub fn start_poller<R: Runtime>(app: &AppHandle<R>) {
+    if POLLER_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut state = PollerState {
+            known_nodes: KnownNodes::load(),
+            node_names: HashMap::new(),
+            node_positions: HashMap::new(),
+            channel_names: HashMap::new(),
+            last_message_ms: chrono::Utc::now().timestamp_millis(),
+            local_node_id: None,
+            link: None,
+        };
+        // New messages are checked on every wake since announcing them is
+        // latency-sensitive. Node discovery isn't and every poll is a full
+        // node-list query, so nodes are fetched less often.
+        let mut preset = PollingPreset::default();
+        let mut nodes_polled: Option<Instant> = None;
+        loop {
+            std::thread::sleep(preset.message_interval());
+
+            let Ok(config) = Config::load() else {
+                continue;
+            };
+            preset = config.polling_preset;
+            let Some(api) = BackendApi::from_config(&config) else {
+                continue;
+            };
+
+            if nodes_polled.is_none_or(|polled| polled.elapsed() >= preset.node_interval()) {
+                poll_nodes(&app, &api, &config, &mut state);
+                nodes_polled = Some(Instant::now());
+            }
+            poll_messages(&app, &api, &config, &mut state);
+        }
+    });
+}
+
+fn log_poll_error(error: &str) {
+    log(&format!("Poller: {}", error));
+}
+
+/// Fetch the node list, record any nodes not seen before, forward them to
+/// the webhooks and, if enabled, raise a notification for each of them.
+fn poll_nodes<R: Runtime>(
+    app: &AppHandle<R>,
+    api: &BackendApi,
+    config: &Config,
+    state: &mut PollerState,
+) {
+    let nodes = match api.nodes() {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            log_poll_error(&e);
+            return;
+        }
+    };
+
+    // The local node can change when sources are reconfigured, so refresh
+    // it alongside the node list.
+    if let Ok(status) = api.status() {
+        let previous_node_id = std::mem::replace(&mut state.local_node_id, status.local_node_id);
+        // Keyed by backend PID: a restarted backend starts with the link down
+        let link = BackendRecord::load().map(|record| (record.pid, status.connected));
+        // The node is checked after each (re)connect and when it changes
+        if let Some(node_id) = state.local_node_id.as_deref().filter(|_| status.connected) {
+            if state.link != link || previous_node_id.as_deref() != Some(node_id) {
+                node_identity::verify(app, config, node_id);
+            }
+        }
+        if link.is_some() && state.link != link {
+            let kind = if status.connected {
+                uptime::EventKind::LinkUp
+            } else {
+                // Not on the first look at a new backend, which is still connecting
+                if state
+                    .link
+                    .is_some_and(|(pid, _)| Some(pid) == link.map(|(pid, _)| pid))
+                {
+                    wake_on_lan::wake_automatically(
+                        config,
+                        "The backend lost its link to the node",
+                    );
+                }
+                uptime::EventKind::LinkDown
+            };
+            uptime::record(kind, "");
+            state.link = link;
+            *LINK.lock().unwrap() = link;
+        }
+    }
+    state.node_names = nodes
+        .iter()
+        .map(|node| (node.node_id.clone(), node.display_name()))
+        .collect();
+    state.node_positions = nodes
+        .iter()
+        .filter_map(|node| Some((node.node_id.clone(), (node.latitude?, node.longitude?))))
+        .collect();
+    if let Ok(channels) = api.channels() {
+        let mut listed: Vec<(i64, String)> = channels
+            .iter()
+            .map(|channel| {
+                let name = channel
+                    .display_name
+                    .clone()
+                    .unwrap_or_else(|| format!("Channel {}", channel.id));
+                (channel.id, name)
+            })
+            .collect();
+        listed.sort();
+        *CHANNELS.lock().unwrap() = listed;
+        state.channel_names = channels
+            .into_iter()
+            .filter_map(|channel| Some((channel.id, channel.display_name?)))
+            .collect();
+    }
+
+    let known_nodes = &mut state.known_nodes;
+    let known_before = known_nodes.ids.len();
+    let new_nodes = known_nodes.record(&nodes);
+    if known_nodes.ids.len() != known_before {
+        known_nodes.save();
+    }
+
+    for node in new_nodes {
+        let event = webhooks::Event {
+            kind: EventKind::NewNode,
+            node_id: node.node_id.clone(),
+            node_name: node.display_name(),
+            text: String::new(),
+            channel: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        };
+        push::send(config, &event);
+        webhooks::send(config, event);
+        if config.notify_new_nodes {
+            notifications::notify(
+                app,
+                "New node joined the mesh",
+                &node.display_name(),
+                Some(notifications::node_url(config.web_port, &node.node_id)),
+            );
+        }
+    }
+}
+
+/// Fetch messages newer than the last one handled and hand each to the
+/// consumers that want it: the unread-DM tray indicator, the webhooks and
+/// push targets and,
+/// if enabled, notifications and text-to-speech.
+fn poll_messages<R: Runtime>(
+    app: &AppHandle<R>,
+    api: &BackendApi,
+    config: &Config,
+    state: &mut PollerState,
+) {
+    let messages = match api.messages_since(state.last_message_ms) {
+        Ok(messages) => messages,
+        Err(e) => {
+            log_poll_error(&e);
+            return;
+        }
+    };
+
+    let now = chrono::Local::now().time();
+    let local_node_id = state.local_node_id.as_deref();
+    let mut incoming_dms = 0;
+    // Conversation → message count and the newest message's event
+    let mut to_notify: BTreeMap<Conversation, (usize, webhooks::Event)> = BTreeMap::new();
+    for message in &messages {
+        state.last_message_ms = state.last_message_ms.max(message.timestamp);
+        if message.is_incoming_dm(local_node_id) {
+            incoming_dms += 1;
+        }
+        if let Some(event) = webhook_event(state, message, local_node_id) {
+            if config.incident_snapshots && incidents::is_emergency(config, &message.text) {
+                capture_incident(state, &event);
+            }
+            let muted = event
+                .channel
+                .is_some_and(|channel| config.muted_channels.contains(&channel));
+            if config.notify_messages && !muted {
+                let conversation = match event.channel {
+                    Some(channel) => Conversation::Channel(channel),
+                    None => Conversation::Direct(event.node_id.clone()),
+                };
+                to_notify
+                    .entry(conversation)
+                    .and_modify(|(count, newest)| {
+                        *count += 1;
+                        *newest = event.clone();
+                    })
+                    .or_insert((1, event.clone()));
+            }
+            push::send(config, &event);
+            webhooks::send(config, event);
+        }
+        if tts::should_announce(config, message, local_node_id, now) {
+            tts::speak(tts::announcement(&sender_name(state, message), message));
+        }
+    }
+    if incoming_dms > 0 {
+        attention::add_unread_dms(app, incoming_dms);
+    }
+    // One notification per conversation, however many arrived in this poll
+    for (conversation, (count, event)) in to_notify {
+        let (name, latest, url) = match &conversation {
+            Conversation::Direct(node_id) => (
+                event.node_name.clone(),
+                event.text.clone(),
+                notifications::dm_url(config.web_port, node_id),
+            ),
+            Conversation::Channel(channel) => (
+                state
+                    .channel_names
+                    .get(channel)
+                    .cloned()
+                    .unwrap_or_else(|| format!("Channel {}", channel)),
+                format!("{}: {}", event.node_name, event.text),
+                notifications::channel_url(config.web_port, *channel),
+            ),
+        };
+        notifications::message(app, conversation, &name, count, &latest, url);
+    }
+}
+
+/// The webhook event for a message received from someone else, if any:
+/// direct messages to another node aren't forwarded.
+fn webhook_event(
+    state: &PollerState,
+    message: &ApiMessage,
+    local_node_id: Option<&str>,
+) -> Option<webhooks::Event> {
+    let kind = if message.is_incoming_dm(local_node_id) {
+        EventKind::DirectMessage
+    } else if !message.is_direct() && local_node_id != Some(message.from_node_id.as_str()) {
+        EventKind::ChannelMessage
+    } else {
+        return None;
+    };
+    Some(webhooks::Event {
+        kind,
+        node_id: message.from_node_id.clone(),
+        node_name: sender_name(state, message),
+        text: message.text.clone(),
+        channel: (!message.is_direct()).then_some(message.channel),
+        timestamp: message.timestamp,
+    })
+}
+
+/// Snapshot the map at the sender of an emergency message, if its position
+/// is known.
+fn capture_incident(state: &PollerState, event: &webhooks::Event) {
+    let Some(&(latitude, longitude)) = state.node_positions.get(&event.node_id) else {
+        log(&format!(
+            "Emergency message from {}, whose position isn't known; no map snapshot",
+            event.node_name
+        ));
+        return;
+    };
+    incidents::capture(incidents::Incident {
+        node_id: event.node_id.clone(),
+        node_name: event.node_name.clone(),
+        text: event.text.clone(),
+        latitude,
+        longitude,
+        timestamp: event.timestamp,
+    });
+}
+
+/// Best available name for a message's sender.
+fn sender_name(state: &PollerState, message: &ApiMessage) -> String {
+    state
+        .node_names
+        .get(&message.from_node_id)
+        .cloned()
+        .unwrap_or_else(|| message.from_node_id.clone())
+}
+
+/// Set of node IDs the desktop has already seen, persisted across launches
+/// so "never seen before" survives restarts.
+struct KnownNodes {
+    ids: HashSet<String>,
+    /// False until the first successful poll. The first poll only seeds the
+    /// set — otherwise a fresh install would announce every node in the DB.
+    seeded: bool,
+}
+
+impl KnownNodes {
+    fn path() -> Option<PathBuf> {
+        config::get_data_path()
+            .ok()
+            .map(|p| p.join("known_nodes.json"))
+    }
+
+    fn load() -> Self {
+        let ids: Option<HashSet<String>> = Self::path()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok());
+        match ids {
+            Some(ids) => Self { ids, seeded: true },
+            None => Self {
+                ids: HashSet::new(),
+                seeded: false,
+            },
+        }
+    }
+
+    fn save(&self) {
+        if let Some(path) = Self::path() {
+            if let Ok(content) = serde_json::to_string(&self.ids) {
+                let _ = fs::write(path, content);
+            }
+        }
+    }
+
+    /// Add `nodes` to the set and return the ones not previously known.
+    /// Returns nothing on the seeding pass.
+    fn record(&mut self, nodes: &[ApiNode]) -> Vec<ApiNode> {
+        let mut new_nodes = Vec::new();
+        for node in nodes {
+            if self.ids.insert(node.node_id.clone()) && self.seeded {
+                new_nodes.push(node.clone());
+            }
+        }
+        self.seeded = true;
+        new_nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str) -> ApiNode {
+        ApiNode {
+            node_num: 0,
+            node_id: id.to_string(),
+            long_name: None,
+            short_name: None,
+            latitude: None,
+            longitude: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_known_nodes_seeds_silently_then_reports_new() {
+        let mut known = KnownNodes {
+            ids: HashSet::new(),
+            seeded: false,
+        };
+
+        assert!(known.record(&[node("!a")]).is_empty());
+        assert!(known.ids.contains("!a"));
+
+        let new_nodes = known.record(&[node("!a"), node("!b")]);
+        assert_eq!(new_nodes.len(), 1);
+        assert_eq!(new_nodes[0].node_id, "!b");
+        assert!(known.record(&[node("!b")]).is_empty());
+    }
+}
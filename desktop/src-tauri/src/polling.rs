@@ -0,0 +1,168 @@
+//! Polling presets, picked from the tray's Polling submenu: how often the
+//! poller asks the backend for new messages and nodes, how soon a dropped
+//! serial or Bluetooth link is reopened, and how eagerly the web UI window
+//! reconnects its socket to the backend. Battery saver trades freshness for
+//! fewer wakeups on a laptop. The choice is saved as
+//! `Config::polling_preset` and applies without a restart.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::config::Config;
+use crate::webview::WEB_UI_WINDOW;
+use crate::{audit, log};
+
+/// Key the web UI window keeps its socket options under, so a reload
+/// keeps a preset picked after the window was opened.
+const STORAGE_KEY: &str = "meshmonitor.socketOptions";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PollingPreset {
+    Realtime,
+    #[default]
+    Balanced,
+    BatterySaver,
+}
+
+impl PollingPreset {
+    pub const ALL: [PollingPreset; 3] = [
+        PollingPreset::Realtime,
+        PollingPreset::Balanced,
+        PollingPreset::BatterySaver,
+    ];
+
+    /// Id of the preset's tray menu entry.
+    pub fn menu_id(self) -> &'static str {
+        match self {
+            PollingPreset::Realtime => "polling_realtime",
+            PollingPreset::Balanced => "polling_balanced",
+            PollingPreset::BatterySaver => "polling_battery_saver",
+        }
+    }
+
+    pub fn from_menu_id(id: &str) -> Option<PollingPreset> {
+        PollingPreset::ALL
+            .into_iter()
+            .find(|preset| preset.menu_id() == id)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PollingPreset::Realtime => "&Realtime",
+            PollingPreset::Balanced => "&Balanced",
+            PollingPreset::BatterySaver => "Battery &Saver",
+        }
+    }
+
+    /// How often new messages are fetched.
+    pub fn message_interval(self) -> Duration {
+        Duration::from_secs(match self {
+            PollingPreset::Realtime => 2,
+            PollingPreset::Balanced => 5,
+            PollingPreset::BatterySaver => 30,
+        })
+    }
+
+    /// How often the node list is fetched.
+    pub fn node_interval(self) -> Duration {
+        Duration::from_secs(match self {
+            PollingPreset::Realtime => 10,
+            PollingPreset::Balanced => 30,
+            PollingPreset::BatterySaver => 300,
+        })
+    }
+
+    /// Pause before a dropped bridge link is reopened.
+    pub fn reconnect_delay(self) -> Duration {
+        Duration::from_secs(match self {
+            PollingPreset::Realtime => 2,
+            PollingPreset::Balanced => 5,
+            PollingPreset::BatterySaver => 30,
+        })
+    }
+
+    /// The web UI socket's first and longest pause between reconnection
+    /// attempts, in milliseconds.
+    fn socket_reconnect_ms(self) -> (u64, u64) {
+        match self {
+            PollingPreset::Realtime => (500, 2_000),
+            PollingPreset::Balanced => (1_000, 5_000),
+            PollingPreset::BatterySaver => (5_000, 60_000),
+        }
+    }
+}
+
+/// The configured preset, read from disk so a change applies at once.
+pub fn current() -> PollingPreset {
+    Config::load()
+        .map(|config| config.polling_preset)
+        .unwrap_or_default()
+}
+
+/// Switch to `preset`, save it and tell the web UI window.
+pub fn set<R: Runtime>(app: &AppHandle<R>, preset: PollingPreset) {
+    let previous = Config::load().unwrap_or_default();
+    let mut config = previous.clone();
+    config.polling_preset = preset;
+    if let Err(e) = config.save() {
+        log(&format!("Failed to save the polling preset: {}", e));
+        return;
+    }
+    audit::record_config_change(audit::Source::Tray, &previous, &config);
+    if let Some(window) = app.get_webview_window(WEB_UI_WINDOW) {
+        let _ = window.eval(format!(
+            "(function () {{ {} window.dispatchEvent(new Event('meshmonitor:socket-options')); }})();",
+            assign_options(preset)
+        ));
+    }
+}
+
+/// Added to the web UI window's initialization script: the socket options
+/// of `preset`, unless the page was given newer ones before a reload.
+pub fn init_script(preset: PollingPreset) -> String {
+    format!(
+        "(function () {{ try {{ var saved = sessionStorage.getItem('{key}'); \
+         if (saved) {{ window.__meshmonitorSocketOptions = JSON.parse(saved); return; }} }} catch (e) {{}} \
+         {assign} }})();",
+        key = STORAGE_KEY,
+        assign = assign_options(preset)
+    )
+}
+
+/// Statements setting `window.__meshmonitorSocketOptions` to `preset`'s.
+fn assign_options(preset: PollingPreset) -> String {
+    let (delay, max) = preset.socket_reconnect_ms();
+    let json = format!(
+        "{{\"reconnectionDelay\":{},\"reconnectionDelayMax\":{}}}",
+        delay, max
+    );
+    format!(
+        "window.__meshmonitorSocketOptions = {json}; \
+         try {{ sessionStorage.setItem('{key}', '{json}'); }} catch (e) {{}}",
+        json = json,
+        key = STORAGE_KEY
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_round_trip_through_menu_ids_and_config() {
+        for preset in PollingPreset::ALL {
+            assert_eq!(PollingPreset::from_menu_id(preset.menu_id()), Some(preset));
+        }
+        assert_eq!(PollingPreset::from_menu_id("polling"), None);
+        assert_eq!(
+            serde_json::to_string(&PollingPreset::BatterySaver).unwrap(),
+            "\"battery_saver\""
+        );
+        assert!(
+            PollingPreset::BatterySaver.message_interval()
+                > PollingPreset::Realtime.message_interval()
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Waiting for a stopped backend to let go of the web port and the database
+//! before another is spawned. `stop_backend` returns once the old process
+//! has exited, but an adopted backend that had to be killed, or a Node child
+//! it left behind, can keep either for a moment longer; starting too soon
+//! makes the new backend fail with EADDRINUSE or a locked database. If the
+//! port is still taken when the wait is over, some other program grabbed it
+//! in between, and the restart is abandoned with that reason.
+
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::{config, log};
+
+/// How long a restart waits for the port and database.
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait until `web_port` can be listened on and nothing holds a lock on
+/// the database.
+pub fn wait(web_port: u16) -> Result<(), String> {
+    let db_path = config::get_database_path()?;
+    let started = Instant::now();
+    let result = wait_with(web_port, &db_path, RELEASE_TIMEOUT);
+    match &result {
+        Ok(()) if started.elapsed() >= Duration::from_secs(1) => log(&format!(
+            "Port {} and the database were released after {:.1}s",
+            web_port,
+            started.elapsed().as_secs_f32()
+        )),
+        Ok(()) => {}
+        Err(e) => log(e),
+    }
+    result
+}
+
+/// `wait` for the database at `db_path`, giving up after `timeout`.
+pub fn wait_with(web_port: u16, db_path: &Path, timeout: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let port_free = port_free(web_port);
+        let db_free = db_free(db_path);
+        if port_free && db_free {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(if !port_free {
+                format!(
+                    "Port {} is in use by another program, which took it after the backend \
+                     stopped. Close that program or choose a different web port in Settings.",
+                    web_port
+                )
+            } else {
+                format!(
+                    "{} is still locked by another process. Make sure no backup or \
+                     database program has it open.",
+                    db_path.display()
+                )
+            });
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// Whether `port` can be listened on, as the backend does, on every
+/// interface.
+fn port_free(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+/// Whether no process has the database, or its WAL index, locked.
+fn db_free(db_path: &Path) -> bool {
+    let mut shm = db_path.as_os_str().to_owned();
+    shm.push("-shm");
+    [db_path.to_path_buf(), PathBuf::from(shm)]
+        .iter()
+        .filter(|path| path.exists())
+        .all(|path| !platform::locked(path))
+}
+
+/// Whether another process holds a lock on `path`.
+pub(crate) fn file_locked(path: &Path) -> bool {
+    platform::locked(path)
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    /// SQLite takes POSIX record locks; ask whether any other process holds
+    /// one anywhere in the file.
+    pub fn locked(path: &Path) -> bool {
+        let Ok(file) = std::fs::File::open(path) else {
+            return false;
+        };
+        // SAFETY: flock is plain data, and fcntl only writes into it
+        let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+        lock.l_type = libc::F_WRLCK as libc::c_short;
+        lock.l_whence = libc::SEEK_SET as libc::c_short;
+        let ok = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) } == 0;
+        ok && lock.l_type != libc::F_UNLCK as libc::c_short
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::path::Path;
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    /// Opening the file without sharing fails while anyone else has it open.
+    pub fn locked(path: &Path) -> bool {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .share_mode(0)
+            .open(path)
+            .is_err_and(|e| e.raw_os_error() == Some(ERROR_SHARING_VIOLATION))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn locked(_path: &std::path::Path) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_a_port_someone_else_holds() {
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let db_path = std::env::temp_dir().join("meshmonitor-port-release-test.db");
+
+        let err = wait_with(port, &db_path, Duration::ZERO).unwrap_err();
+        assert!(err.contains(&format!("Port {} is in use", port)), "{}", err);
+
+        drop(listener);
+        assert!(wait_with(port, &db_path, Duration::ZERO).is_ok());
+    }
+}
@@ -0,0 +1,173 @@
+//! The checks made before the backend is spawned, as a checklist: the
+//! bundled Node.js binary and server files, whether the database can be
+//! written, and whether the web port is free. `start_backend` stops at the
+//! first failed file check; Settings shows every item with its result, so a
+//! broken install or a port clash can be found without starting anything.
+
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::net::TcpListener;
+use std::path::Path;
+
+use crate::config::{self, Config};
+use crate::sidecar_arch;
+
+/// One checklist item.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub label: &'static str,
+    pub ok: bool,
+    /// The path or port checked, or what's wrong with it.
+    pub detail: String,
+}
+
+/// Every check, for Settings. The port counts as available while
+/// `backend_running`, since it's then the backend's own.
+pub fn run(resource_path: &Path, config: &Config, backend_running: bool) -> Vec<Check> {
+    let binaries = resource_path.join("binaries");
+    let node = match sidecar_arch::select_node(&binaries, sidecar_arch::host_arch()) {
+        Ok((path, warning)) => Check {
+            label: "Node.js binary",
+            ok: true,
+            detail: warning.unwrap_or_else(|| path.display().to_string()),
+        },
+        Err(e) => Check {
+            label: "Node.js binary",
+            ok: false,
+            detail: e,
+        },
+    };
+    let mut checks = vec![node];
+    checks.extend(bundle(&resource_path.join("dist")));
+    checks.push(database());
+    checks.push(port(config.web_port, backend_running));
+    checks
+}
+
+/// The server files under `server_dir` (`dist/`).
+pub fn bundle(server_dir: &Path) -> Vec<Check> {
+    [
+        (
+            "Server script",
+            "server.js",
+            server_dir.join("server").join("server.js"),
+        ),
+        (
+            "package.json",
+            "package.json",
+            server_dir.join("package.json"),
+        ),
+        (
+            "Node modules",
+            "node_modules",
+            server_dir.join("node_modules"),
+        ),
+        (
+            "Services directory",
+            "services",
+            server_dir.join("services"),
+        ),
+    ]
+    .into_iter()
+    .map(|(label, name, path)| {
+        let ok = path.exists();
+        Check {
+            label,
+            ok,
+            detail: if ok {
+                path.display().to_string()
+            } else {
+                format!("{} not found at {:?}", name, path)
+            },
+        }
+    })
+    .collect()
+}
+
+/// Whether the database, or the folder it will be created in, is writable.
+fn database() -> Check {
+    let result = config::get_database_path().and_then(|db_path| {
+        if db_path.exists() {
+            OpenOptions::new()
+                .append(true)
+                .open(&db_path)
+                .map_err(|e| format!("Can't write {}: {}", db_path.display(), e))?;
+        } else {
+            let dir = db_path.parent().unwrap_or(Path::new("."));
+            let probe = dir.join(".meshmonitor-write-test");
+            std::fs::write(&probe, b"")
+                .map_err(|e| format!("Can't create files in {}: {}", dir.display(), e))?;
+            let _ = std::fs::remove_file(&probe);
+        }
+        Ok(db_path)
+    });
+    match result {
+        Ok(db_path) => Check {
+            label: "Database writable",
+            ok: true,
+            detail: db_path.display().to_string(),
+        },
+        Err(e) => Check {
+            label: "Database writable",
+            ok: false,
+            detail: e,
+        },
+    }
+}
+
+fn port(web_port: u16, backend_running: bool) -> Check {
+    let (ok, detail) = if backend_running {
+        (
+            true,
+            format!("Port {} is used by the running backend", web_port),
+        )
+    } else if TcpListener::bind(("0.0.0.0", web_port)).is_ok() {
+        (true, format!("Port {} is free", web_port))
+    } else {
+        (
+            false,
+            format!(
+                "Port {} is in use by another program; close it or choose a different web port",
+                web_port
+            ),
+        )
+    };
+    Check {
+        label: "Web port available",
+        ok,
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_reports_missing_files() {
+        let dir =
+            std::env::temp_dir().join(format!("meshmonitor-preflight-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("server")).unwrap();
+        std::fs::write(dir.join("server").join("server.js"), "").unwrap();
+        std::fs::create_dir_all(dir.join("node_modules")).unwrap();
+
+        let checks = bundle(&dir);
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|check| !check.ok)
+            .map(|check| check.label)
+            .collect();
+        assert_eq!(failed, ["package.json", "Services directory"]);
+        assert!(checks[1].detail.starts_with("package.json not found at"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_port_held_by_another_program() {
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let web_port = listener.local_addr().unwrap().port();
+        assert!(!port(web_port, false).ok);
+        assert!(port(web_port, true).ok);
+    }
+}
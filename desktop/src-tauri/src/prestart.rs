@@ -0,0 +1,208 @@
+//! Optional pre-start hook for a node that runs in a VM or container on the
+//! same machine and comes up after MeshMonitor: a command that must succeed
+//! (`Config::pre_start_command`) and/or an `http(s)://` or `tcp://` address
+//! that must answer (`Config::pre_start_url`). Both are retried up to
+//! `Config::pre_start_attempts` times before the backend first starts; if
+//! they never pass, it starts anyway.
+
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::config::Config;
+use crate::{fallback, log, BackendState};
+
+/// How long one run of the command may take before it counts as failed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long one probe of the address may take.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether a hook is configured.
+pub fn configured(config: &Config) -> bool {
+    command(config).is_some() || url(config).is_some()
+}
+
+fn command(config: &Config) -> Option<&str> {
+    config
+        .pre_start_command
+        .as_deref()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+}
+
+fn url(config: &Config) -> Option<&str> {
+    config
+        .pre_start_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+}
+
+/// Retry the hook until it passes or runs out of attempts, showing what is
+/// awaited on the web port. Blocks.
+pub fn wait<R: Runtime>(app: &AppHandle<R>, config: &Config) {
+    if !configured(config) {
+        return;
+    }
+    let attempts = config.pre_start_attempts.max(1);
+    let interval = Duration::from_secs(u64::from(config.pre_start_interval_secs));
+    for attempt in 1..=attempts {
+        let result = check(config);
+        match &result {
+            Ok(()) => {
+                log(&format!("Pre-start hook passed (attempt {})", attempt));
+                return;
+            }
+            Err(e) => log(&format!(
+                "Pre-start hook failed (attempt {}/{}): {}",
+                attempt, attempts, e
+            )),
+        }
+        if attempt == attempts {
+            break;
+        }
+        fallback::show(
+            &app.state::<BackendState>(),
+            config.web_port,
+            &format!(
+                "Waiting for the node to come up (attempt {}/{})",
+                attempt, attempts
+            ),
+            result.err().as_deref(),
+        );
+        std::thread::sleep(interval);
+    }
+    log("Pre-start hook never passed; starting the backend anyway");
+}
+
+fn check(config: &Config) -> Result<(), String> {
+    if let Some(command) = command(config) {
+        run(command)?;
+    }
+    if let Some(url) = url(config) {
+        probe(url)?;
+    }
+    Ok(())
+}
+
+/// Run `command` through the shell; it passes when it exits with 0.
+fn run(command: &str) -> Result<(), String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    };
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        shell.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = shell
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("couldn't run the command: {}", e))?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("the command exited with {}", status)),
+            Ok(None) if started.elapsed() >= COMMAND_TIMEOUT => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err("the command timed out".to_string());
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(200)),
+            Err(e) => return Err(format!("couldn't wait for the command: {}", e)),
+        }
+    }
+}
+
+/// How an address is probed.
+#[derive(Debug, PartialEq)]
+enum Probe<'a> {
+    /// `GET`, passing on any 2xx answer.
+    Http(&'a str),
+    /// A TCP connection to `host:port`.
+    Tcp(&'a str),
+}
+
+fn probe_for(url: &str) -> Result<Probe<'_>, String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(Probe::Http(url))
+    } else if let Some(address) = url.strip_prefix("tcp://") {
+        Ok(Probe::Tcp(address.trim_end_matches('/')))
+    } else {
+        Err(format!(
+            "{} isn't an http://, https:// or tcp:// address",
+            url
+        ))
+    }
+}
+
+fn probe(url: &str) -> Result<(), String> {
+    match probe_for(url)? {
+        Probe::Http(url) => ureq::AgentBuilder::new()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .get(url)
+            .call()
+            .map(|_| ())
+            .map_err(|e| format!("{} didn't answer: {}", url, e)),
+        Probe::Tcp(address) => {
+            let connected = address
+                .to_socket_addrs()
+                .map_err(|e| format!("{} couldn't be resolved: {}", address, e))?
+                .any(|addr| TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok());
+            if connected {
+                Ok(())
+            } else {
+                Err(format!("{} isn't accepting connections", address))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_kinds() {
+        assert_eq!(
+            probe_for("http://192.168.122.10/api/v1/fromradio"),
+            Ok(Probe::Http("http://192.168.122.10/api/v1/fromradio"))
+        );
+        assert_eq!(
+            probe_for("tcp://192.168.122.10:4403/"),
+            Ok(Probe::Tcp("192.168.122.10:4403"))
+        );
+        assert!(probe_for("192.168.122.10:4403").is_err());
+    }
+
+    #[test]
+    fn test_blank_hooks_are_not_configured() {
+        let mut config = Config {
+            pre_start_command: Some("  ".to_string()),
+            ..Config::default()
+        };
+        assert!(!configured(&config));
+        config.pre_start_url = Some("tcp://127.0.0.1:4403".to_string());
+        assert!(configured(&config));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_passes_on_exit_status_zero() {
+        assert!(run("true").is_ok());
+        assert!(run("exit 3").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! Taskbar (Windows) and dock (macOS, and Unity-style Linux launchers)
+//! progress for long-running operations such as backups, restores, database
+//! vacuums and bundle downloads, so a busy app isn't mistaken for a hung one.
+//!
+//! Operations hold a [`Progress`] guard for as long as they run and report
+//! through it; the bar is cleared when the last guard is dropped.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::log;
+
+/// Operations currently holding a `Progress` guard.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+/// Progress of one long-running operation. Starts indeterminate.
+pub struct Progress<R: Runtime> {
+    app: AppHandle<R>,
+    operation: String,
+}
+
+impl<R: Runtime> Progress<R> {
+    /// Begin reporting progress for `operation` (e.g. "Database backup").
+    pub fn start(app: &AppHandle<R>, operation: &str) -> Self {
+        ACTIVE.fetch_add(1, Ordering::SeqCst);
+        log(&format!("{} started", operation));
+        let progress = Self {
+            app: app.clone(),
+            operation: operation.to_string(),
+        };
+        progress.apply(ProgressBarStatus::Indeterminate, None);
+        progress
+    }
+
+    /// Report `done` out of `total` units (bytes, rows, steps...).
+    pub fn set(&self, done: u64, total: u64) {
+        match percent(done, total) {
+            Some(percent) => self.apply(ProgressBarStatus::Normal, Some(percent)),
+            None => self.apply(ProgressBarStatus::Indeterminate, None),
+        }
+    }
+
+    /// Show the bar in its error state; it is still cleared on drop.
+    pub fn fail(&self, error: &str) {
+        log(&format!("{} failed: {}", self.operation, error));
+        self.apply(ProgressBarStatus::Error, Some(100));
+    }
+
+    fn apply(&self, status: ProgressBarStatus, progress: Option<u64>) {
+        // Windows shows the bar on each window's taskbar button; elsewhere it
+        // is app-wide and any window will do. With no window open (tray only)
+        // there is nothing to attach it to.
+        for window in self.app.webview_windows().values() {
+            let _ = window.set_progress_bar(ProgressBarState {
+                status: Some(status),
+                progress,
+            });
+        }
+    }
+}
+
+impl<R: Runtime> Drop for Progress<R> {
+    fn drop(&mut self) {
+        if ACTIVE.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.apply(ProgressBarStatus::None, None);
+        }
+    }
+}
+
+/// `done`/`total` as a whole percentage, `None` when the total is unknown.
+fn percent(done: u64, total: u64) -> Option<u64> {
+    if total == 0 {
+        return None;
+    }
+    Some((done.min(total) as u128 * 100 / total as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(0, 0), None);
+        assert_eq!(percent(0, 200), Some(0));
+        assert_eq!(percent(50, 200), Some(25));
+        assert_eq!(percent(300, 200), Some(100));
+        assert_eq!(percent(u64::MAX - 1, u64::MAX), Some(99));
+    }
+}
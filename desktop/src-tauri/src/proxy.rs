@@ -0,0 +1,374 @@
+//! Reverse proxy on one public port in front of this backend and other
+//! MeshMonitor instances, so remote viewers need one address and one
+//! firewall rule. Requests for `/node/<name>/...` go to the instance of that
+//! name, which must run with `BASE_URL=/node/<name>`; everything else goes to
+//! this backend.
+//!
+//! Each connection carries one request, so a browser's next request can go
+//! to a different instance; WebSocket upgrades stay connected to theirs.
+
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::log;
+
+/// Prefix of the paths routed to other instances.
+const NODE_PREFIX: &str = "/node/";
+
+/// Largest request head accepted.
+const MAX_HEAD: usize = 16 * 1024;
+
+/// How long a client gets to send its request head.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long an instance gets to accept the connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the listener checks whether the proxy was stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The backend's `TRUST_PROXY` for `config`. Behind the proxy every client
+/// connects from loopback, so the backend has to take the address the proxy
+/// appends to `X-Forwarded-For` for its rate limits; trusting only loopback
+/// hops keeps an address a client put there itself from being used.
+pub fn backend_trust_proxy(config: &Config) -> Option<&'static str> {
+    config.proxy_port.map(|_| "loopback")
+}
+
+/// Where requests go: this backend, and other instances by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Routes {
+    local: String,
+    instances: Vec<(String, String)>,
+}
+
+impl Routes {
+    /// Routes for `config`. Instances with an unusable name or URL are left
+    /// out and logged.
+    pub fn from_config(config: &Config) -> Self {
+        let instances = config
+            .proxy_instances
+            .iter()
+            .filter_map(|instance| {
+                let checked = check_name(&instance.name)
+                    .and_then(|()| upstream_addr(&instance.url))
+                    .map_err(|e| {
+                        log(&format!(
+                            "Proxy instance {:?} skipped: {}",
+                            instance.name, e
+                        ))
+                    });
+                checked.ok().map(|addr| (instance.name.clone(), addr))
+            })
+            .collect();
+        Self {
+            local: format!("127.0.0.1:{}", config.web_port),
+            instances,
+        }
+    }
+
+    /// The address serving `path`, or `None` for an unknown instance.
+    fn upstream(&self, path: &str) -> Option<&str> {
+        let Some(rest) = path.strip_prefix(NODE_PREFIX) else {
+            return Some(&self.local);
+        };
+        let name = rest.split(['/', '?']).next().unwrap_or_default();
+        self.instances
+            .iter()
+            .find(|(instance, _)| instance == name)
+            .map(|(_, addr)| addr.as_str())
+    }
+}
+
+/// A running proxy; dropping it stops listening.
+pub struct Proxy {
+    pub port: u16,
+    routes: Arc<Mutex<Routes>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Proxy {
+    /// Listen on `port` on all interfaces.
+    pub fn start(port: u16, routes: Routes) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .map_err(|e| format!("Failed to open the proxy port {}: {}", port, e))?;
+        log(&format!("Reverse proxy listening on port {}", port));
+
+        let routes = Arc::new(Mutex::new(routes));
+        let stop = Arc::new(AtomicBool::new(false));
+        let shared = routes.clone();
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((client, _)) => {
+                        let routes = shared.lock().unwrap().clone();
+                        std::thread::spawn(move || handle(client, &routes));
+                    }
+                    Err(_) => std::thread::sleep(POLL_INTERVAL),
+                }
+            }
+            log("Reverse proxy stopped");
+        });
+
+        Ok(Self { port, routes, stop })
+    }
+
+    /// Route new connections by `routes` from now on.
+    pub fn set_routes(&self, routes: Routes) {
+        *self.routes.lock().unwrap() = routes;
+    }
+}
+
+impl Drop for Proxy {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Serve one client connection.
+fn handle(mut client: TcpStream, routes: &Routes) {
+    let _ = client.set_nonblocking(false);
+    let _ = client.set_read_timeout(Some(HEAD_TIMEOUT));
+    let Some((head, body)) = read_head(&mut client) else {
+        return respond(client, "400 Bad Request", "Bad request");
+    };
+    let Some(path) = head.split_whitespace().nth(1) else {
+        return respond(client, "400 Bad Request", "Bad request");
+    };
+    let Some(addr) = routes.upstream(path) else {
+        return respond(
+            client,
+            "404 Not Found",
+            "There is no MeshMonitor instance by that name",
+        );
+    };
+    let server = addr
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .and_then(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok());
+    let Some(mut server) = server else {
+        return respond(
+            client,
+            "502 Bad Gateway",
+            "That MeshMonitor instance isn't reachable right now",
+        );
+    };
+    let peer = client.peer_addr().map(|addr| addr.ip());
+    let head = rewrite_head(&head, peer.ok());
+    if server.write_all(head.as_bytes()).is_err() || server.write_all(&body).is_err() {
+        return respond(
+            client,
+            "502 Bad Gateway",
+            "That MeshMonitor instance isn't reachable right now",
+        );
+    }
+    let _ = client.set_read_timeout(None);
+    splice(client, server);
+}
+
+/// Read up to the end of the request head. Returns the head and whatever
+/// of the body came with it.
+pub(crate) fn read_head(client: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = client.read(&mut chunk).ok().filter(|&read| read > 0)?;
+        buf.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let body = buf.split_off(end + 4);
+            return Some((String::from_utf8(buf).ok()?, body));
+        }
+        if buf.len() > MAX_HEAD {
+            return None;
+        }
+    }
+}
+
+/// The request head as sent on to the instance: one request per connection
+/// unless it is an upgrade, and the client's address in `X-Forwarded-For`.
+fn rewrite_head(head: &str, peer: Option<IpAddr>) -> String {
+    let mut lines = head.trim_end().split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let value = |wanted: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .map(|(_, value)| *value)
+    };
+    let upgrade = value("upgrade").is_some()
+        && value("connection").is_some_and(|v| v.to_ascii_lowercase().contains("upgrade"));
+
+    let mut out = format!("{}\r\n", request_line);
+    for (name, value) in &headers {
+        let dropped = if upgrade {
+            name.eq_ignore_ascii_case("x-forwarded-for")
+        } else {
+            [
+                "connection",
+                "keep-alive",
+                "proxy-connection",
+                "x-forwarded-for",
+            ]
+            .iter()
+            .any(|dropped| name.eq_ignore_ascii_case(dropped))
+        };
+        if !dropped {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    if let Some(peer) = peer {
+        match value("x-forwarded-for") {
+            Some(earlier) => out.push_str(&format!("X-Forwarded-For: {}, {}\r\n", earlier, peer)),
+            None => out.push_str(&format!("X-Forwarded-For: {}\r\n", peer)),
+        }
+    }
+    if !upgrade {
+        out.push_str("Connection: close\r\n");
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// Copy both ways until either side closes.
+fn splice(mut client: TcpStream, mut server: TcpStream) {
+    let (Ok(mut client_reader), Ok(mut server_writer)) = (client.try_clone(), server.try_clone())
+    else {
+        return;
+    };
+    let upload = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut server_writer);
+        let _ = server_writer.shutdown(Shutdown::Write);
+    });
+    let _ = io::copy(&mut server, &mut client);
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = server.shutdown(Shutdown::Both);
+    let _ = upload.join();
+}
+
+pub(crate) fn respond(mut client: TcpStream, status: &str, message: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+    let _ = client.write_all(response.as_bytes());
+}
+
+/// Instance names become a path segment.
+fn check_name(name: &str) -> Result<(), String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err("names may only use letters, digits, `-` and `_`".to_string())
+    }
+}
+
+/// `host:port` of an instance URL such as `http://192.168.1.20:8080`.
+fn upstream_addr(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    if url.starts_with("https://") {
+        return Err("HTTPS instances aren't supported; use the instance's HTTP port".to_string());
+    }
+    let host = url.strip_prefix("http://").unwrap_or(url);
+    let host = host.split('/').next().unwrap_or_default();
+    if host.is_empty() {
+        return Err("the URL has no host".to_string());
+    }
+    if host
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+    {
+        Ok(host.to_string())
+    } else {
+        Ok(format!("{}:80", host))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes() -> Routes {
+        Routes {
+            local: "127.0.0.1:8080".to_string(),
+            instances: vec![("attic".to_string(), "192.168.1.20:8080".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_backend_trusts_the_proxy_only_when_it_runs() {
+        assert_eq!(backend_trust_proxy(&Config::default()), None);
+        let config = Config {
+            proxy_port: Some(9000),
+            ..Config::default()
+        };
+        assert_eq!(backend_trust_proxy(&config), Some("loopback"));
+    }
+
+    #[test]
+    fn test_routes_by_node_prefix() {
+        let routes = routes();
+        assert_eq!(routes.upstream("/"), Some("127.0.0.1:8080"));
+        assert_eq!(routes.upstream("/api/v1/nodes"), Some("127.0.0.1:8080"));
+        assert_eq!(routes.upstream("/node/attic/"), Some("192.168.1.20:8080"));
+        assert_eq!(
+            routes.upstream("/node/attic?x=1"),
+            Some("192.168.1.20:8080")
+        );
+        assert_eq!(routes.upstream("/node/attic-2/"), None);
+        assert_eq!(routes.upstream("/node/"), None);
+    }
+
+    #[test]
+    fn test_rewrite_head_closes_plain_requests() {
+        let head = "GET / HTTP/1.1\r\nHost: mesh:9000\r\nConnection: keep-alive\r\n\r\n";
+        assert_eq!(
+            rewrite_head(head, Some([10, 0, 0, 5].into())),
+            "GET / HTTP/1.1\r\nHost: mesh:9000\r\nX-Forwarded-For: 10.0.0.5\r\nConnection: close\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_head_keeps_upgrades_open() {
+        let head = "GET /socket.io/ HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+                    X-Forwarded-For: 203.0.113.9\r\n\r\n";
+        assert_eq!(
+            rewrite_head(head, Some([10, 0, 0, 5].into())),
+            "GET /socket.io/ HTTP/1.1\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             X-Forwarded-For: 203.0.113.9, 10.0.0.5\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_instance_validation() {
+        assert!(check_name("attic_2").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("a/b").is_err());
+        assert_eq!(
+            upstream_addr("http://192.168.1.20:8080/").unwrap(),
+            "192.168.1.20:8080"
+        );
+        assert_eq!(
+            upstream_addr("meshmonitor.lan").unwrap(),
+            "meshmonitor.lan:80"
+        );
+        assert!(upstream_addr("https://meshmonitor.lan").is_err());
+        assert!(upstream_addr("http://").is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Alerts pushed to phones and mailboxes (`Config::push_targets`), for a
+//! desktop left running at a remote site: an ntfy topic, Pushover, or mail
+//! through an SMTP server. They're fed the same mesh events as the webhooks,
+//! plus `emergency` for messages matching `Config::incident_keywords`, and
+//! each target only gets the events it lists (by default direct messages and
+//! emergencies). Emergencies go out at the services' high priority.
+//!
+//! Tokens and passwords are kept in the OS keyring under `push:<id>`. Like
+//! webhooks, nothing is sent in privacy mode.
+
+use std::time::Duration;
+
+use crate::config::{Config, PushService, PushTarget};
+use crate::webhooks::{Event, EventKind};
+use crate::{incidents, keyring, log, outbound, smtp};
+
+/// How long ntfy and Pushover get to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+/// What a target is sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub title: String,
+    pub body: String,
+    pub urgent: bool,
+}
+
+/// Keyring account holding push target `id`'s token or password.
+pub fn secret_account(id: &str) -> String {
+    format!("push:{}", id)
+}
+
+/// Push `event` to every target that wants it, in the background.
+pub fn send(config: &Config, event: &Event) {
+    if config.push_targets.is_empty() {
+        return;
+    }
+    let emergency =
+        event.kind != EventKind::NewNode && incidents::is_emergency(config, &event.text);
+    let deliveries: Vec<PushTarget> = config
+        .push_targets
+        .iter()
+        .filter(|target| wants(target, event.kind, emergency))
+        .cloned()
+        .collect();
+    if deliveries.is_empty() {
+        return;
+    }
+    let alert = alert(event, emergency);
+    std::thread::spawn(move || {
+        for target in deliveries {
+            if let Err(e) = deliver(&target, &alert) {
+                log(&format!("Push to {} failed: {}", target.id, e));
+            }
+        }
+    });
+}
+
+/// Send a test alert to target `id`, waiting for it to go out.
+pub fn test(config: &Config, id: &str) -> Result<(), String> {
+    let target = config
+        .push_targets
+        .iter()
+        .find(|target| target.id == id)
+        .ok_or_else(|| format!("There's no push target \"{}\" in config.json", id))?;
+    deliver(
+        target,
+        &Alert {
+            title: "MeshMonitor test alert".to_string(),
+            body: "Alerts from MeshMonitor will arrive like this.".to_string(),
+            urgent: false,
+        },
+    )
+}
+
+/// Whether `target` is sent events of `kind`; an emergency is also sent to
+/// targets listing its message kind.
+fn wants(target: &PushTarget, kind: EventKind, emergency: bool) -> bool {
+    target
+        .events
+        .iter()
+        .any(|name| name == kind.name() || (emergency && name == "emergency"))
+}
+
+fn alert(event: &Event, emergency: bool) -> Alert {
+    let title = match (event.kind, emergency) {
+        (_, true) => format!("Emergency from {}", event.node_name),
+        (EventKind::NewNode, _) => "New node joined the mesh".to_string(),
+        (EventKind::DirectMessage, _) => event.node_name.clone(),
+        (EventKind::ChannelMessage, _) => format!(
+            "{} on channel {}",
+            event.node_name,
+            event.channel.unwrap_or_default()
+        ),
+    };
+    let body = match event.kind {
+        EventKind::NewNode => format!("{} ({})", event.node_name, event.node_id),
+        _ => event.text.replace('\u{7}', ""),
+    };
+    Alert {
+        title,
+        body,
+        urgent: emergency,
+    }
+}
+
+fn deliver(target: &PushTarget, alert: &Alert) -> Result<(), String> {
+    let secret = keyring::get(&secret_account(&target.id)).unwrap_or_default();
+    let client = outbound::Client::new(ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT));
+    match &target.service {
+        PushService::Ntfy { server, topic } => {
+            let url = format!("{}/{}", server.trim_end_matches('/'), topic);
+            let mut request = client
+                .post(&url)?
+                .set("Title", &header_value(&alert.title))
+                .set("Priority", if alert.urgent { "urgent" } else { "default" });
+            if alert.urgent {
+                request = request.set("Tags", "rotating_light");
+            }
+            if let Some(token) = secret {
+                request = request.set("Authorization", &format!("Bearer {}", token));
+            }
+            request
+                .send_string(&alert.body)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        PushService::Pushover { user_key } => {
+            let token = secret.ok_or("Save the Pushover application token in Settings")?;
+            client
+                .post(PUSHOVER_URL)?
+                .send_form(&[
+                    ("token", token.as_str()),
+                    ("user", user_key.as_str()),
+                    ("title", alert.title.as_str()),
+                    ("message", alert.body.as_str()),
+                    ("priority", if alert.urgent { "1" } else { "0" }),
+                ])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        PushService::Email {
+            smtp_host,
+            smtp_port,
+            username,
+            from,
+            to,
+        } => {
+            let login = match (username, &secret) {
+                (Some(username), Some(password)) => Some((username.as_str(), password.as_str())),
+                (Some(_), None) => return Err("Save the SMTP password in Settings".to_string()),
+                (None, _) => None,
+            };
+            let subject = if alert.urgent {
+                format!("[URGENT] {}", alert.title)
+            } else {
+                alert.title.clone()
+            };
+            smtp::send(
+                &smtp::Server {
+                    host: smtp_host,
+                    port: *smtp_port,
+                    login,
+                },
+                &smtp::Mail {
+                    from,
+                    to,
+                    subject: &subject,
+                    body: &alert.body,
+                },
+            )
+        }
+    }
+}
+
+/// `text` fit for an HTTP header: ntfy takes UTF-8 there, but line breaks
+/// would end it.
+fn header_value(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_and_alerts() {
+        let target: PushTarget = serde_json::from_str(
+            r#"{"id": "phone", "service": "ntfy", "topic": "club-mesh-alerts"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            target.service,
+            PushService::Ntfy {
+                server: "https://ntfy.sh".to_string(),
+                topic: "club-mesh-alerts".to_string()
+            }
+        );
+        assert!(wants(&target, EventKind::DirectMessage, false));
+        assert!(!wants(&target, EventKind::ChannelMessage, false));
+        assert!(wants(&target, EventKind::ChannelMessage, true));
+        assert!(!wants(&target, EventKind::NewNode, false));
+
+        let event = Event {
+            kind: EventKind::ChannelMessage,
+            node_id: "!a1b2c3d4".to_string(),
+            node_name: "Base".to_string(),
+            text: "\u{7}Mayday at the ridge".to_string(),
+            channel: Some(0),
+            timestamp: 0,
+        };
+        assert_eq!(
+            alert(&event, true),
+            Alert {
+                title: "Emergency from Base".to_string(),
+                body: "Mayday at the ridge".to_string(),
+                urgent: true
+            }
+        );
+        assert_eq!(alert(&event, false).title, "Base on channel 0");
+    }
+}
@@ -0,0 +1,943 @@
+//! QR code reader for channel QR codes in screenshots and saved images.
+//!
+//! Locates the three finder patterns, samples the module grid with an affine
+//! transform between them, then reads format and version information,
+//! unmasks, de-interleaves and Reed–Solomon-corrects the codewords and
+//! decodes numeric, alphanumeric and byte segments. An affine grid copes
+//! with scaling and rotation but not perspective, which is fine for images
+//! that came off a screen and not meant for camera photos.
+
+/// An 8-bit grayscale image, row-major.
+pub struct GrayImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+/// Thresholded image: `true` is a dark pixel.
+struct Bitmap {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    fn get(&self, x: i64, y: i64) -> Option<bool> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(self.dark[y as usize * self.width + x as usize])
+    }
+
+    fn inverted(&self) -> Bitmap {
+        Bitmap {
+            width: self.width,
+            height: self.height,
+            dark: self.dark.iter().map(|d| !d).collect(),
+        }
+    }
+}
+
+/// Decode the first QR code found in `image` to its text.
+pub fn decode(image: &GrayImage) -> Result<String, String> {
+    if image.width == 0 || image.height == 0 || image.pixels.len() < image.width * image.height {
+        return Err("The image is empty".to_string());
+    }
+    let bitmap = binarize(image);
+    // Light-on-dark codes (common in dark-themed apps) are tried inverted
+    decode_bitmap(&bitmap)
+        .or_else(|_| decode_bitmap(&bitmap.inverted()))
+        .map_err(|_| "No readable QR code found in the image".to_string())
+}
+
+fn decode_bitmap(bitmap: &Bitmap) -> Result<String, String> {
+    let finders = find_finders(bitmap);
+    let (top_left, top_right, bottom_left) =
+        pick_finder_triple(&finders).ok_or("No QR code found")?;
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let across = (top_left.distance(&top_right) + top_left.distance(&bottom_left)) / 2.0;
+    let estimate = ((across / module + 7.0 - 17.0) / 4.0).round() as i64;
+
+    // The estimate can be a version off for small or unevenly scaled codes
+    let mut last_error = "No QR code found".to_string();
+    for version in [estimate, estimate - 1, estimate + 1] {
+        if !(1..=40).contains(&version) {
+            continue;
+        }
+        let grid = sample_grid(
+            bitmap,
+            &top_left,
+            &top_right,
+            &bottom_left,
+            version as usize,
+        );
+        match decode_grid(&grid) {
+            Ok(text) => return Ok(text),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Global threshold chosen with Otsu's method; screenshots are evenly lit.
+fn binarize(image: &GrayImage) -> Bitmap {
+    let pixels = &image.pixels[..image.width * image.height];
+    let mut histogram = [0u64; 256];
+    for &p in pixels {
+        histogram[p as usize] += 1;
+    }
+    let total = pixels.len() as f64;
+    let sum_all: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| i as f64 * n as f64)
+        .sum();
+    let (mut weight_dark, mut sum_dark) = (0.0, 0.0);
+    let (mut best, mut threshold) = (-1.0, 128usize);
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_dark += count as f64;
+        sum_dark += level as f64 * count as f64;
+        let weight_light = total - weight_dark;
+        if weight_dark == 0.0 || weight_light == 0.0 {
+            continue;
+        }
+        let mean_dark = sum_dark / weight_dark;
+        let mean_light = (sum_all - sum_dark) / weight_light;
+        let between = weight_dark * weight_light * (mean_dark - mean_light).powi(2);
+        if between > best {
+            best = between;
+            threshold = level;
+        }
+    }
+    Bitmap {
+        width: image.width,
+        height: image.height,
+        dark: pixels.iter().map(|&p| (p as usize) <= threshold).collect(),
+    }
+}
+
+// ─── Finder patterns ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    x: f64,
+    y: f64,
+    /// Estimated module size in pixels.
+    module: f64,
+    hits: usize,
+}
+
+impl Finder {
+    fn distance(&self, other: &Finder) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+/// Whether five run lengths look like the 1:1:3:1:1 finder cross-section.
+fn finder_ratio(counts: &[usize; 5]) -> Option<f64> {
+    if counts.contains(&0) {
+        return None;
+    }
+    let total: usize = counts.iter().sum();
+    if total < 7 {
+        return None;
+    }
+    let module = total as f64 / 7.0;
+    let tolerance = module / 2.0;
+    let near =
+        |count: usize, modules: f64| (count as f64 - module * modules).abs() < tolerance * modules;
+    (near(counts[0], 1.0)
+        && near(counts[1], 1.0)
+        && near(counts[2], 3.0)
+        && near(counts[3], 1.0)
+        && near(counts[4], 1.0))
+    .then_some(module)
+}
+
+/// Walk out from (`x`, `y`) along `step` and back, measuring the
+/// dark-light-dark-light-dark runs through it. Returns the center of the
+/// middle run along the walk (as an offset from the start point) and the
+/// module size.
+fn cross_check(
+    bitmap: &Bitmap,
+    x: i64,
+    y: i64,
+    step: (i64, i64),
+    limit: usize,
+) -> Option<(f64, f64)> {
+    let is = |offset: i64, dark: bool| {
+        bitmap.get(x + step.0 * offset, y + step.1 * offset) == Some(dark)
+    };
+    if !is(0, true) {
+        return None;
+    }
+    let mut counts = [0usize; 5];
+
+    let mut back = 0i64;
+    for (slot, dark) in [(2, true), (1, false), (0, true)] {
+        while is(-back, dark) && counts[slot] <= limit {
+            counts[slot] += 1;
+            back += 1;
+        }
+    }
+    let center_start = -(counts[2] as i64) + 1;
+
+    let mut forward = 1i64;
+    for (slot, dark) in [(2, true), (3, false), (4, true)] {
+        while is(forward, dark) && counts[slot] <= limit {
+            counts[slot] += 1;
+            forward += 1;
+        }
+    }
+    let module = finder_ratio(&counts)?;
+    let center = center_start as f64 + counts[2] as f64 / 2.0 - 0.5;
+    Some((center, module))
+}
+
+fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut found: Vec<Finder> = Vec::new();
+    let limit = bitmap.width.max(bitmap.height);
+    for y in 0..bitmap.height {
+        // Run lengths along the row, starting with the first dark run
+        let row = &bitmap.dark[y * bitmap.width..(y + 1) * bitmap.width];
+        let mut runs: Vec<(usize, usize)> = Vec::new(); // (start, length)
+        let mut x = 0;
+        while x < row.len() {
+            let start = x;
+            while x < row.len() && row[x] == row[start] {
+                x += 1;
+            }
+            runs.push((start, x - start));
+        }
+        let first_dark = usize::from(!row.first().copied().unwrap_or(false));
+
+        for window in runs[first_dark.min(runs.len())..].windows(5).step_by(2) {
+            let counts = [
+                window[0].1,
+                window[1].1,
+                window[2].1,
+                window[3].1,
+                window[4].1,
+            ];
+            if finder_ratio(&counts).is_none() {
+                continue;
+            }
+            let cx = window[2].0 as f64 + window[2].1 as f64 / 2.0 - 0.5;
+            let Some((dy, module_v)) =
+                cross_check(bitmap, cx.round() as i64, y as i64, (0, 1), limit)
+            else {
+                continue;
+            };
+            let cy = y as f64 + dy;
+            let Some((dx, module_h)) =
+                cross_check(bitmap, cx.round() as i64, cy.round() as i64, (1, 0), limit)
+            else {
+                continue;
+            };
+            let candidate = Finder {
+                x: cx.round() + dx,
+                y: cy,
+                module: (module_v + module_h) / 2.0,
+                hits: 1,
+            };
+            match found.iter_mut().find(|f| {
+                f.distance(&candidate) < f.module.max(candidate.module) * 2.0
+                    && (f.module / candidate.module - 1.0).abs() < 0.5
+            }) {
+                Some(existing) => {
+                    let n = existing.hits as f64;
+                    existing.x = (existing.x * n + candidate.x) / (n + 1.0);
+                    existing.y = (existing.y * n + candidate.y) / (n + 1.0);
+                    existing.module = (existing.module * n + candidate.module) / (n + 1.0);
+                    existing.hits += 1;
+                }
+                None => found.push(candidate),
+            }
+        }
+    }
+    found.sort_by_key(|f| std::cmp::Reverse(f.hits));
+    found
+}
+
+/// The three finders that best form the corner of a square, ordered
+/// top-left, top-right, bottom-left (in the code's own orientation).
+fn pick_finder_triple(finders: &[Finder]) -> Option<(Finder, Finder, Finder)> {
+    let candidates = &finders[..finders.len().min(10)];
+    let mut best: Option<(f64, (Finder, Finder, Finder))> = None;
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                let trio = [candidates[i], candidates[j], candidates[k]];
+                let modules: Vec<f64> = trio.iter().map(|f| f.module).collect();
+                let largest = modules.iter().cloned().fold(f64::MIN, f64::max);
+                let smallest = modules.iter().cloned().fold(f64::MAX, f64::min);
+                if largest / smallest > 1.5 {
+                    continue;
+                }
+                // The corner is opposite the longest side
+                let sides = [
+                    trio[1].distance(&trio[2]),
+                    trio[0].distance(&trio[2]),
+                    trio[0].distance(&trio[1]),
+                ];
+                let corner = (0..3)
+                    .max_by(|&a, &b| sides[a].total_cmp(&sides[b]))
+                    .unwrap_or(0);
+                let (a, b) = ((corner + 1) % 3, (corner + 2) % 3);
+                let (leg_a, leg_b, hypotenuse) = (sides[b], sides[a], sides[corner]);
+                let leg = leg_a.max(leg_b);
+                if leg < 14.0 * smallest {
+                    continue;
+                }
+                let score = (leg_a - leg_b).abs() / leg
+                    + (hypotenuse - (leg_a.powi(2) + leg_b.powi(2)).sqrt()).abs() / hypotenuse
+                    + (largest / smallest - 1.0);
+                if score > 0.4 {
+                    continue;
+                }
+                // Top-right is clockwise from bottom-left around the corner
+                let (c, p, q) = (trio[corner], trio[a], trio[b]);
+                let cross = (p.x - c.x) * (q.y - c.y) - (p.y - c.y) * (q.x - c.x);
+                let ordered = if cross > 0.0 { (c, p, q) } else { (c, q, p) };
+                if best.as_ref().is_none_or(|(s, _)| score < *s) {
+                    best = Some((score, ordered));
+                }
+            }
+        }
+    }
+    best.map(|(_, trio)| trio)
+}
+
+// ─── Grid ───────────────────────────────────────────────────────────────────
+
+/// Modules of a symbol, row-major: `true` is dark.
+struct Grid {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl Grid {
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+}
+
+fn sample_grid(
+    bitmap: &Bitmap,
+    top_left: &Finder,
+    top_right: &Finder,
+    bottom_left: &Finder,
+    version: usize,
+) -> Grid {
+    let size = version * 4 + 17;
+    // Finder centers sit at module 3.5 from their corners
+    let span = (size - 7) as f64;
+    let (ux, uy) = (
+        (top_right.x - top_left.x) / span,
+        (top_right.y - top_left.y) / span,
+    );
+    let (vx, vy) = (
+        (bottom_left.x - top_left.x) / span,
+        (bottom_left.y - top_left.y) / span,
+    );
+    let mut modules = Vec::with_capacity(size * size);
+    for row in 0..size {
+        for col in 0..size {
+            let (u, v) = (col as f64 - 3.0, row as f64 - 3.0);
+            let px = top_left.x + u * ux + v * vx;
+            let py = top_left.y + u * uy + v * vy;
+            modules.push(
+                bitmap
+                    .get(px.round() as i64, py.round() as i64)
+                    .unwrap_or(false),
+            );
+        }
+    }
+    Grid { size, modules }
+}
+
+/// Error-correction levels in format-information order (`L` is 0b01).
+const EC_LEVELS: [usize; 4] = [1, 0, 3, 2];
+
+/// Error correction codewords per block, by version and level (L, M, Q, H).
+#[rustfmt::skip]
+const ECC_PER_BLOCK: [[usize; 4]; 40] = [
+    [7, 10, 13, 17], [10, 16, 22, 28], [15, 26, 18, 22], [20, 18, 26, 16], [26, 24, 18, 22],
+    [18, 16, 24, 28], [20, 18, 18, 26], [24, 22, 22, 26], [30, 22, 20, 24], [18, 26, 24, 28],
+    [20, 30, 28, 24], [24, 22, 26, 28], [26, 22, 24, 22], [30, 24, 20, 24], [22, 24, 30, 24],
+    [24, 28, 24, 30], [28, 28, 28, 28], [30, 26, 28, 28], [28, 26, 26, 26], [28, 26, 30, 28],
+    [28, 26, 28, 30], [28, 28, 30, 24], [30, 28, 30, 30], [30, 28, 30, 30], [26, 28, 30, 30],
+    [28, 28, 28, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30],
+    [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30],
+    [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30], [30, 28, 30, 30],
+];
+
+/// Error-correction blocks, by version and level (L, M, Q, H).
+#[rustfmt::skip]
+const BLOCKS: [[usize; 4]; 40] = [
+    [1, 1, 1, 1], [1, 1, 1, 1], [1, 1, 2, 2], [1, 2, 2, 4], [1, 2, 4, 4],
+    [2, 4, 4, 4], [2, 4, 6, 5], [2, 4, 6, 6], [2, 5, 8, 8], [4, 5, 8, 8],
+    [4, 5, 8, 11], [4, 8, 10, 11], [4, 9, 12, 16], [4, 9, 16, 16], [6, 10, 12, 18],
+    [6, 10, 17, 16], [6, 11, 16, 19], [6, 13, 18, 21], [7, 14, 21, 25], [8, 16, 20, 25],
+    [8, 17, 23, 25], [9, 17, 23, 34], [9, 18, 25, 30], [10, 20, 27, 32], [12, 21, 29, 35],
+    [12, 23, 34, 37], [12, 25, 34, 40], [13, 26, 35, 42], [14, 28, 38, 45], [15, 29, 40, 48],
+    [16, 31, 43, 51], [17, 33, 45, 54], [18, 35, 48, 57], [19, 37, 51, 60], [19, 38, 53, 63],
+    [20, 40, 56, 66], [21, 43, 59, 70], [22, 45, 62, 74], [24, 47, 65, 77], [25, 49, 68, 81],
+];
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let mut positions = vec![6];
+    let mut position = version * 4 + 17 - 7;
+    for _ in 0..count - 1 {
+        positions.insert(1, position);
+        position -= step;
+    }
+    positions
+}
+
+/// Number of data and error-correction modules in a symbol.
+fn raw_data_modules(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        modules -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+/// Modules taken by finder, timing and alignment patterns and by format and
+/// version information.
+fn function_modules(version: usize) -> Vec<bool> {
+    let size = version * 4 + 17;
+    let mut function = vec![false; size * size];
+    let mut mark = |x: usize, y: usize| function[y * size + x] = true;
+    for i in 0..size {
+        mark(6, i);
+        mark(i, 6);
+    }
+    // Finders with separators and the format areas next to them
+    for i in 0..9 {
+        for j in 0..9 {
+            mark(i, j);
+        }
+    }
+    for i in 0..8 {
+        for j in 0..9 {
+            mark(size - 1 - i, j);
+            mark(j, size - 1 - i);
+        }
+    }
+    let positions = alignment_positions(version);
+    let last = positions.len().saturating_sub(1);
+    for (i, &cx) in positions.iter().enumerate() {
+        for (j, &cy) in positions.iter().enumerate() {
+            // Not where the finders are
+            let at_edge = |k: usize| k == 0 || k == last;
+            if at_edge(i) && at_edge(j) && (i, j) != (last, last) {
+                continue;
+            }
+            for dy in 0..5 {
+                for dx in 0..5 {
+                    mark(cx + dx - 2, cy + dy - 2);
+                }
+            }
+        }
+    }
+    if version >= 7 {
+        for i in 0..6 {
+            for j in 0..3 {
+                mark(size - 11 + j, i);
+                mark(i, size - 11 + j);
+            }
+        }
+    }
+    function
+}
+
+fn format_bits(data: u32) -> u32 {
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    ((data << 10) | rem) ^ 0x5412
+}
+
+fn version_bits(version: u32) -> u32 {
+    let mut rem = version;
+    for _ in 0..12 {
+        rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+    }
+    (version << 12) | rem
+}
+
+/// Error-correction level (index into the tables) and mask from the two
+/// copies of the format information, tolerating up to three bit errors.
+fn read_format(grid: &Grid) -> Option<(usize, usize)> {
+    let size = grid.size;
+    let (mut first, mut second) = (0u32, 0u32);
+    let set = |bits: &mut u32, i: usize, dark: bool| *bits |= u32::from(dark) << i;
+    for i in 0..6 {
+        set(&mut first, i, grid.get(8, i));
+    }
+    set(&mut first, 6, grid.get(8, 7));
+    set(&mut first, 7, grid.get(8, 8));
+    set(&mut first, 8, grid.get(7, 8));
+    for i in 9..15 {
+        set(&mut first, i, grid.get(14 - i, 8));
+    }
+    for i in 0..8 {
+        set(&mut second, i, grid.get(size - 1 - i, 8));
+    }
+    for i in 8..15 {
+        set(&mut second, i, grid.get(8, size - 15 + i));
+    }
+
+    let (distance, data) = (0..32u32)
+        .map(|data| {
+            let expected = format_bits(data);
+            let distance = (expected ^ first)
+                .count_ones()
+                .min((expected ^ second).count_ones());
+            (distance, data)
+        })
+        .min()?;
+    if distance > 3 {
+        return None;
+    }
+    let level = EC_LEVELS
+        .iter()
+        .position(|&bits| bits == (data >> 3) as usize)?;
+    Some((level, (data & 7) as usize))
+}
+
+/// The version encoded in the version information blocks, if readable.
+fn read_version(grid: &Grid) -> Option<usize> {
+    let size = grid.size;
+    let (mut first, mut second) = (0u32, 0u32);
+    for i in 0..18 {
+        let (a, b) = (size - 11 + i % 3, i / 3);
+        first |= u32::from(grid.get(a, b)) << i;
+        second |= u32::from(grid.get(b, a)) << i;
+    }
+    let (distance, version) = (7..=40u32)
+        .map(|version| {
+            let expected = version_bits(version);
+            let distance = (expected ^ first)
+                .count_ones()
+                .min((expected ^ second).count_ones());
+            (distance, version)
+        })
+        .min()?;
+    (distance <= 3).then_some(version as usize)
+}
+
+fn masked(mask: usize, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+fn decode_grid(grid: &Grid) -> Result<String, String> {
+    let version = (grid.size - 17) / 4;
+    if version >= 7 && read_version(grid) != Some(version) {
+        return Err("Version information doesn't match the symbol size".to_string());
+    }
+    let (level, mask) = read_format(grid).ok_or("Unreadable format information")?;
+
+    // Codewords in the zigzag placement order, unmasked
+    let function = function_modules(version);
+    let size = grid.size;
+    let total = raw_data_modules(version) / 8;
+    let mut codewords = vec![0u8; total];
+    let mut bit = 0;
+    let mut right = size as i64 - 1;
+    while right >= 1 {
+        if right == 6 {
+            right = 5;
+        }
+        for vert in 0..size {
+            for j in 0..2 {
+                let x = (right - j) as usize;
+                let upward = (right + 1) & 2 == 0;
+                let y = if upward { size - 1 - vert } else { vert };
+                if function[y * size + x] || bit >= total * 8 {
+                    continue;
+                }
+                if grid.get(x, y) != masked(mask, x, y) {
+                    codewords[bit / 8] |= 0x80 >> (bit % 8);
+                }
+                bit += 1;
+            }
+        }
+        right -= 2;
+    }
+
+    // De-interleave; short blocks come first and lack the last data codeword
+    let blocks = BLOCKS[version - 1][level];
+    let ecc = ECC_PER_BLOCK[version - 1][level];
+    let short_len = total / blocks;
+    let short_count = blocks - total % blocks;
+    let mut rows = vec![Vec::with_capacity(short_len + 1); blocks];
+    let mut next = codewords.into_iter();
+    for i in 0..=short_len {
+        for (j, row) in rows.iter_mut().enumerate() {
+            if i == short_len - ecc && j < short_count {
+                continue;
+            }
+            if let Some(codeword) = next.next() {
+                row.push(codeword);
+            }
+        }
+    }
+    let mut data = Vec::new();
+    for mut row in rows {
+        correct_errors(&mut row, ecc)?;
+        data.extend_from_slice(&row[..row.len() - ecc]);
+    }
+    decode_segments(&data, version)
+}
+
+// ─── Reed–Solomon ───────────────────────────────────────────────────────────
+
+/// GF(256) with the QR polynomial x⁸ + x⁴ + x³ + x² + 1.
+struct Field {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+impl Field {
+    fn new() -> Self {
+        let mut field = Field {
+            exp: [0; 512],
+            log: [0; 256],
+        };
+        let mut x: u16 = 1;
+        for i in 0..255 {
+            field.exp[i] = x as u8;
+            field.log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11D;
+            }
+        }
+        for i in 255..512 {
+            field.exp[i] = field.exp[i - 255];
+        }
+        field
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            0
+        } else {
+            self.exp[(self.log[a as usize] as usize + 255 - self.log[b as usize] as usize) % 255]
+        }
+    }
+
+    fn pow(&self, exponent: usize) -> u8 {
+        self.exp[exponent % 255]
+    }
+
+    /// Evaluate a polynomial whose coefficients are in ascending order.
+    fn eval_ascending(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+}
+
+/// Correct `block` (data then `ecc` check codewords) in place, or fail when
+/// it has more errors than the code can fix.
+fn correct_errors(block: &mut [u8], ecc: usize) -> Result<(), String> {
+    let field = Field::new();
+    let syndromes = |block: &[u8]| -> Vec<u8> {
+        (0..ecc)
+            .map(|j| {
+                let x = field.pow(j);
+                block.iter().fold(0, |acc, &c| field.mul(acc, x) ^ c)
+            })
+            .collect()
+    };
+    let s = syndromes(block);
+    if s.iter().all(|&v| v == 0) {
+        return Ok(());
+    }
+
+    // Berlekamp–Massey: error locator Λ, ascending coefficients
+    let mut locator = vec![1u8];
+    let mut previous = vec![1u8];
+    let (mut length, mut shift, mut last_discrepancy) = (0usize, 1usize, 1u8);
+    for n in 0..ecc {
+        let mut discrepancy = s[n];
+        for i in 1..=length.min(locator.len() - 1) {
+            discrepancy ^= field.mul(locator[i], s[n - i]);
+        }
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let scale = field.div(discrepancy, last_discrepancy);
+        let mut updated = locator.clone();
+        if updated.len() < previous.len() + shift {
+            updated.resize(previous.len() + shift, 0);
+        }
+        for (i, &c) in previous.iter().enumerate() {
+            updated[i + shift] ^= field.mul(scale, c);
+        }
+        if 2 * length <= n {
+            previous = std::mem::replace(&mut locator, updated);
+            length = n + 1 - length;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
+        }
+    }
+    if length * 2 > ecc {
+        return Err("Too many errors in the QR code".to_string());
+    }
+
+    // Chien search: codeword i has degree n-1-i, locator X = α^(n-1-i)
+    let n = block.len();
+    let positions: Vec<usize> = (0..n)
+        .filter(|&i| {
+            let inverse = field.div(1, field.pow(n - 1 - i));
+            field.eval_ascending(&locator, inverse) == 0
+        })
+        .collect();
+    if positions.len() != length {
+        return Err("Too many errors in the QR code".to_string());
+    }
+
+    // Forney (first consecutive root α⁰): e = X·Ω(X⁻¹) / Λ'(X⁻¹)
+    let mut evaluator = vec![0u8; ecc];
+    for (i, &si) in s.iter().enumerate() {
+        for (j, &lj) in locator.iter().enumerate() {
+            if i + j < ecc {
+                evaluator[i + j] ^= field.mul(si, lj);
+            }
+        }
+    }
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+        .collect();
+    for &i in &positions {
+        let x = field.pow(n - 1 - i);
+        let inverse = field.div(1, x);
+        let denominator = field.eval_ascending(&derivative, inverse);
+        if denominator == 0 {
+            return Err("Too many errors in the QR code".to_string());
+        }
+        let magnitude = field.mul(
+            x,
+            field.div(field.eval_ascending(&evaluator, inverse), denominator),
+        );
+        block[i] ^= magnitude;
+    }
+    if syndromes(block).iter().any(|&v| v != 0) {
+        return Err("Too many errors in the QR code".to_string());
+    }
+    Ok(())
+}
+
+// ─── Segments ───────────────────────────────────────────────────────────────
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn read(&mut self, count: usize) -> Option<u32> {
+        if count > self.remaining() {
+            return None;
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = self.data[self.position / 8];
+            value = (value << 1) | u32::from((byte >> (7 - self.position % 8)) & 1);
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+fn decode_segments(data: &[u8], version: usize) -> Result<String, String> {
+    let band = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let truncated = || "The QR code's data is truncated".to_string();
+    let mut reader = BitReader { data, position: 0 };
+    let mut bytes = Vec::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4).ok_or_else(truncated)? {
+            0 => break,
+            // Numeric
+            1 => {
+                let mut count = reader.read([10, 12, 14][band]).ok_or_else(truncated)? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([0, 4, 7, 10][digits]).ok_or_else(truncated)?;
+                    bytes.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            // Alphanumeric
+            2 => {
+                let mut count = reader.read([9, 11, 13][band]).ok_or_else(truncated)? as usize;
+                while count >= 2 {
+                    let value = reader.read(11).ok_or_else(truncated)? as usize;
+                    let (hi, lo) = (value / 45, value % 45);
+                    if hi >= 45 {
+                        return Err("Invalid alphanumeric data in the QR code".to_string());
+                    }
+                    bytes.push(ALPHANUMERIC[hi]);
+                    bytes.push(ALPHANUMERIC[lo]);
+                    count -= 2;
+                }
+                if count == 1 {
+                    let value = reader.read(6).ok_or_else(truncated)? as usize;
+                    bytes.push(*ALPHANUMERIC.get(value).ok_or("Invalid alphanumeric data")?);
+                }
+            }
+            // Byte
+            4 => {
+                let count = reader.read([8, 16, 16][band]).ok_or_else(truncated)?;
+                for _ in 0..count {
+                    bytes.push(reader.read(8).ok_or_else(truncated)? as u8);
+                }
+            }
+            // ECI designator: the text is treated as UTF-8 regardless
+            7 => {
+                let first = reader.read(8).ok_or_else(truncated)?;
+                let extra = match first {
+                    f if f & 0x80 == 0 => 0,
+                    f if f & 0xC0 == 0x80 => 8,
+                    _ => 16,
+                };
+                reader.read(extra).ok_or_else(truncated)?;
+            }
+            mode => return Err(format!("Unsupported QR data mode {}", mode)),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "The QR code doesn't contain text".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::{EcLevel, QrCode, Version};
+
+    /// Render a code as a grayscale image, `scale` pixels per module with a
+    /// four-module quiet zone.
+    fn render(code: &QrCode, scale: usize) -> GrayImage {
+        let width = code.width();
+        let colors = code.to_colors();
+        let side = (width + 8) * scale;
+        let mut pixels = vec![255u8; side * side];
+        for y in 0..side {
+            for x in 0..side {
+                let (mx, my) = ((x / scale) as i64 - 4, (y / scale) as i64 - 4);
+                if mx >= 0
+                    && my >= 0
+                    && (mx as usize) < width
+                    && (my as usize) < width
+                    && colors[my as usize * width + mx as usize] == qrcode::Color::Dark
+                {
+                    pixels[y * side + x] = 0;
+                }
+            }
+        }
+        GrayImage {
+            width: side,
+            height: side,
+            pixels,
+        }
+    }
+
+    const LINK: &str = "https://meshtastic.org/e/#CgcSAQE6AggNEg4IARAEOAFAA0gBUB5oAQ";
+
+    #[test]
+    fn test_decodes_rendered_codes() {
+        for (level, scale) in [
+            (EcLevel::L, 3),
+            (EcLevel::M, 4),
+            (EcLevel::Q, 5),
+            (EcLevel::H, 2),
+        ] {
+            let code = QrCode::with_error_correction_level(LINK, level).unwrap();
+            assert_eq!(decode(&render(&code, scale)).unwrap(), LINK);
+        }
+        // A version with version information blocks and several EC blocks
+        let code = QrCode::with_version(LINK, Version::Normal(9), EcLevel::M).unwrap();
+        assert_eq!(decode(&render(&code, 3)).unwrap(), LINK);
+        // Alphanumeric and numeric segments
+        let code = QrCode::new("HTTPS://MESHTASTIC.ORG/E/12345").unwrap();
+        assert_eq!(
+            decode(&render(&code, 3)).unwrap(),
+            "HTTPS://MESHTASTIC.ORG/E/12345"
+        );
+    }
+
+    #[test]
+    fn test_corrects_damage_and_inverted_codes() {
+        let code = QrCode::with_error_correction_level(LINK, EcLevel::M).unwrap();
+        let mut image = render(&code, 4);
+        // Wipe a block of data modules in the lower right
+        let side = image.width;
+        for y in side * 3 / 5..side * 3 / 5 + 12 {
+            for x in side * 3 / 5..side * 3 / 5 + 12 {
+                image.pixels[y * side + x] = 255 - image.pixels[y * side + x];
+            }
+        }
+        assert_eq!(decode(&image).unwrap(), LINK);
+
+        for p in image.pixels.iter_mut() {
+            *p = 255 - *p;
+        }
+        assert_eq!(decode(&image).unwrap(), LINK);
+    }
+
+    #[test]
+    fn test_no_code() {
+        let image = GrayImage {
+            width: 50,
+            height: 50,
+            pixels: vec![255; 2500],
+        };
+        assert!(decode(&image).is_err());
+    }
+}
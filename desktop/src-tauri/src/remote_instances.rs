@@ -0,0 +1,209 @@
+//! Other MeshMonitor servers the user looks after, such as a club's or a
+//! repeater site's (`Config::remote_instances`). Each is checked every
+//! `CHECK_INTERVAL` through its `/api/health`, and the tray's Remote
+//! Instances submenu lists them with their state; clicking one opens it in
+//! the browser. A notification is shown when an instance goes down (fails
+//! `FAILURES_FOR_DOWN` checks in a row) and when it's back. The checks go
+//! through `outbound::Client`, so they stop in privacy mode.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+use crate::api::ApiHealth;
+use crate::config::{Config, RemoteInstance};
+use crate::{log, notifications, outbound};
+
+/// How often every instance is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long one check may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Failed checks in a row before an instance counts as down, so one
+/// dropped request doesn't raise an alert.
+const FAILURES_FOR_DOWN: u32 = 2;
+
+/// Guards against spawning a second checker thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// The configured instances with their latest state, in config order.
+static STATUSES: Mutex<Vec<(RemoteInstance, Status)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// Not checked yet, or not checked at all, with why.
+    Unknown(String),
+    Up {
+        version: Option<String>,
+    },
+    Down(String),
+}
+
+impl Status {
+    /// State as shown in the tray: `up, v3.2.1`.
+    pub fn label(&self) -> String {
+        match self {
+            Status::Unknown(reason) => reason.clone(),
+            Status::Up {
+                version: Some(version),
+            } => format!("up, v{}", version),
+            Status::Up { version: None } => "up".to_string(),
+            Status::Down(reason) => format!("down ({})", reason),
+        }
+    }
+}
+
+/// Checks of one instance, carried between rounds.
+#[derive(Default)]
+struct Tracked {
+    failures: u32,
+    status: Option<Status>,
+    /// Whether its going down was alerted, so its return is too.
+    alerted: bool,
+}
+
+/// The configured instances and their latest state.
+pub fn statuses() -> Vec<(RemoteInstance, Status)> {
+    STATUSES.lock().unwrap().clone()
+}
+
+/// Spawn the checker. It idles while no remote instances are configured.
+/// Safe to call more than once.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let client = outbound::Client::new(ureq::AgentBuilder::new().timeout(CHECK_TIMEOUT));
+        let mut tracked: HashMap<String, Tracked> = HashMap::new();
+        loop {
+            let instances = Config::load()
+                .map(|config| config.remote_instances)
+                .unwrap_or_default();
+            tracked.retain(|url, _| instances.iter().any(|instance| &instance.url == url));
+            let private = outbound::private();
+            let mut statuses = Vec::with_capacity(instances.len());
+            for instance in instances {
+                let entry = tracked.entry(instance.url.clone()).or_default();
+                let status = if private {
+                    Status::Unknown("not checked in privacy mode".to_string())
+                } else {
+                    check(&app, &client, &instance, entry)
+                };
+                statuses.push((instance, status));
+            }
+            *STATUSES.lock().unwrap() = statuses;
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+/// Check `instance` and alert when it went down or came back.
+fn check<R: Runtime>(
+    app: &AppHandle<R>,
+    client: &outbound::Client,
+    instance: &RemoteInstance,
+    tracked: &mut Tracked,
+) -> Status {
+    let status = match health(client, &instance.url) {
+        Ok(health) => {
+            tracked.failures = 0;
+            Status::Up {
+                version: health.version,
+            }
+        }
+        Err(e) => {
+            tracked.failures += 1;
+            if tracked.failures < FAILURES_FOR_DOWN {
+                // Keep what was known until the failure repeats
+                return tracked
+                    .status
+                    .clone()
+                    .unwrap_or_else(|| Status::Unknown("checking".to_string()));
+            }
+            Status::Down(e)
+        }
+    };
+    let was_up = matches!(tracked.status, Some(Status::Up { .. }));
+    let was_down = matches!(tracked.status, Some(Status::Down(_)));
+    match &status {
+        Status::Down(reason) if !was_down => {
+            log(&format!(
+                "Remote instance {} is down: {}",
+                instance.name, reason
+            ));
+            // An instance that was never reached is listed, not alerted
+            if was_up {
+                tracked.alerted = true;
+                notifications::notify(
+                    app,
+                    &format!("{} is down", instance.name),
+                    reason,
+                    Some(instance.url.clone()),
+                );
+            }
+        }
+        Status::Up { .. } if was_down => {
+            log(&format!("Remote instance {} is back up", instance.name));
+            if std::mem::take(&mut tracked.alerted) {
+                notifications::notify(
+                    app,
+                    &format!("{} is back up", instance.name),
+                    &instance.url,
+                    Some(instance.url.clone()),
+                );
+            }
+        }
+        _ => {}
+    }
+    tracked.status = Some(status.clone());
+    status
+}
+
+fn health(client: &outbound::Client, url: &str) -> Result<ApiHealth, String> {
+    let health: ApiHealth = client
+        .get(&health_url(url))?
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|_| "not a MeshMonitor server".to_string())?;
+    if health.status != "ok" {
+        return Err(format!("reports status {:?}", health.status));
+    }
+    Ok(health)
+}
+
+/// Health endpoint of the instance at `url`, which may include its
+/// `BASE_URL` path.
+fn health_url(url: &str) -> String {
+    format!("{}/api/health", url.trim().trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_url_and_labels() {
+        assert_eq!(
+            health_url("https://club.example.org/meshmonitor/"),
+            "https://club.example.org/meshmonitor/api/health"
+        );
+        assert_eq!(
+            health_url("http://192.168.1.20:8080"),
+            "http://192.168.1.20:8080/api/health"
+        );
+        assert_eq!(
+            Status::Up {
+                version: Some("3.2.1".into())
+            }
+            .label(),
+            "up, v3.2.1"
+        );
+        assert_eq!(Status::Down("timed out".into()).label(), "down (timed out)");
+    }
+}
@@ -0,0 +1,224 @@
+//! Repairing an installation that lost some of its files, typically to an
+//! antivirus program quarantining part of `node_modules`. When the bundle
+//! checks in `start_backend` fail, the user is offered a repair instead of
+//! only an error: on Windows the installer is run again, from the copy the
+//! installer leaves in the cache folder (see `windows/hooks.nsh`) or freshly
+//! downloaded and checked against the release's checksums. Elsewhere the
+//! release page for this version is opened.
+
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::crash_report::hex;
+use crate::progress::Progress;
+use crate::{audit, log, outbound};
+
+const TITLE: &str = "Installation Damaged";
+
+const RELEASES_URL: &str = "https://github.com/Yeraze/meshmonitor/releases";
+
+/// How long a stalled installer download may sit without receiving data.
+const DOWNLOAD_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether the repair was offered this run; the backend is restarted often
+/// enough (watchdog, crash recovery) that asking each time would nag.
+static OFFERED: AtomicBool = AtomicBool::new(false);
+
+/// A repair is running.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Offer to repair the installation, once per run. `problem` says what's
+/// missing.
+pub fn offer<R: Runtime>(app: &AppHandle<R>, problem: &str) {
+    if OFFERED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let message = format!(
+        "MeshMonitor can't start because part of its installation is missing:\n{}\n\n\
+         This usually means an antivirus program removed it. {} Your data and \
+         settings are kept.",
+        problem,
+        if cfg!(windows) {
+            "Repair runs the MeshMonitor installer again to put the files back."
+        } else {
+            "Reinstall this version of MeshMonitor Desktop to put the files back."
+        }
+    );
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title(TITLE)
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            if cfg!(windows) { "Repair" } else { "Download" }.to_string(),
+            "Not Now".to_string(),
+        ))
+        .show(move |repair| {
+            if repair {
+                start(&handle);
+            }
+        });
+}
+
+/// Repair the installation in the background; failures are reported with
+/// a dialog.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    let version = app.package_info().version.to_string();
+    if !cfg!(windows) {
+        let _ = app.opener().open_url(release_url(&version), None::<&str>);
+        return;
+    }
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    audit::record(audit::Source::Dialog, "installation_repaired", &version);
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        let progress = Progress::start(&handle, "Installation repair");
+        match installer(&version, &progress).and_then(|path| launch(&path)) {
+            // The installer can't replace files this app holds open
+            Ok(()) => handle.exit(0),
+            Err(e) => {
+                progress.fail(&e);
+                let handle_for_dialog = handle.clone();
+                handle
+                    .dialog()
+                    .message(format!(
+                        "MeshMonitor couldn't be repaired: {}\n\n\
+                         Download and run the installer for version {} yourself; your \
+                         data and settings are kept.",
+                        e, version
+                    ))
+                    .title(TITLE)
+                    .kind(MessageDialogKind::Error)
+                    .buttons(MessageDialogButtons::OkCancelCustom(
+                        "Open Download Page".to_string(),
+                        "Close".to_string(),
+                    ))
+                    .show(move |open| {
+                        if open {
+                            let _ = handle_for_dialog
+                                .opener()
+                                .open_url(release_url(&version), None::<&str>);
+                        }
+                    });
+            }
+        }
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// The release page for `version`.
+pub(crate) fn release_url(version: &str) -> String {
+    format!("{}/tag/v{}", RELEASES_URL, version)
+}
+
+/// File name of the Windows installer for `version`, as published.
+fn installer_name(version: &str) -> String {
+    format!("MeshMonitor-Desktop-{}-x64-setup.exe", version)
+}
+
+/// The installer for `version`: the cached copy if there is one, otherwise
+/// downloaded into the cache and verified.
+fn installer<R: Runtime>(version: &str, progress: &Progress<R>) -> Result<PathBuf, String> {
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| "Could not find the cache folder".to_string())?
+        .join("MeshMonitor")
+        .join("installer");
+    let name = installer_name(version);
+    let path = dir.join(&name);
+    if path.exists() {
+        log(&format!("Repairing with the cached installer {:?}", path));
+        return Ok(path);
+    }
+
+    let base = format!("{}/download/v{}", RELEASES_URL, version);
+    log(&format!("Downloading {} to repair the installation", name));
+    let client =
+        outbound::Client::new(ureq::AgentBuilder::new().timeout_read(DOWNLOAD_READ_TIMEOUT));
+    let checksums = client
+        .get(&format!("{}/desktop-checksums-{}.txt", base, version))?
+        .call()
+        .map_err(|e| e.to_string())
+        .and_then(|response| response.into_string().map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to download the release checksums: {}", e))?;
+    let expected = expected_hash(&checksums, &name)
+        .ok_or_else(|| format!("The release checksums don't list {}", name))?;
+
+    let response = client
+        .get(&format!("{}/{}", base, name))?
+        .call()
+        .map_err(|e| format!("Failed to download the installer: {}", e))?;
+    let size: u64 = response
+        .header("Content-Length")
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(0);
+    let mut reader = response.into_reader();
+    let mut bytes = Vec::with_capacity(size as usize);
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let read = reader
+            .read(&mut chunk)
+            .map_err(|e| format!("Failed to download the installer: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..read]);
+        progress.set(bytes.len() as u64, size);
+    }
+    if hex(&Sha256::digest(&bytes)) != expected {
+        return Err("The downloaded installer doesn't match the release checksum".to_string());
+    }
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create the installer folder: {}", e))?;
+    std::fs::write(&path, bytes).map_err(|e| format!("Failed to save the installer: {}", e))?;
+    Ok(path)
+}
+
+/// The lowercase SHA-256 listed for `name` in a `desktop-checksums` file
+/// (`HASH  name` lines, as written by the release workflow).
+fn expected_hash(checksums: &str, name: &str) -> Option<String> {
+    checksums
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .filter_map(|line| line.trim().split_once(char::is_whitespace))
+        .find(|(_, file)| file.trim() == name)
+        .map(|(hash, _)| hash.to_ascii_lowercase())
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn launch(path: &std::path::Path) -> Result<(), String> {
+    std::process::Command::new(path)
+        .spawn()
+        .map_err(|e| format!("Failed to start the installer: {}", e))?;
+    log(&format!("Started {:?} to repair the installation", path));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_hash_from_release_checksums() {
+        let hash = "A".repeat(64);
+        let checksums = format!(
+            "\u{feff}{}  MeshMonitor-Desktop-4.13.2-x64-setup.exe\r\n{}  other.exe\r\n",
+            hash,
+            "b".repeat(64)
+        );
+        assert_eq!(
+            expected_hash(&checksums, &installer_name("4.13.2")),
+            Some("a".repeat(64))
+        );
+        assert_eq!(expected_hash(&checksums, &installer_name("4.13.1")), None);
+        assert_eq!(expected_hash("not-a-hash  setup.exe", "setup.exe"), None);
+    }
+}
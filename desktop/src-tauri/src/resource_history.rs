@@ -0,0 +1,209 @@
+//! Backend CPU and memory use over the last day, to back up reports of a
+//! memory leak or a runaway backend. Once a minute the status the backend
+//! last pushed over IPC is sampled into `resource_history.json` in the data
+//! directory, which keeps the newest `KEPT_SAMPLES`. `history()` returns
+//! them along with the backend starts, stops, crashes and hangs of the same
+//! day from the uptime history, for Settings to chart.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::ipc::{self, BackendStatus};
+use crate::uptime::{self, EventKind};
+use crate::{config, BackendState};
+
+/// How often the backend is sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A day of samples.
+const KEPT_SAMPLES: usize = 24 * 60;
+
+/// How far back `history()` looks, in milliseconds.
+const HISTORY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Guards against spawning a second sampler thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Serializes access to resource_history.json.
+static HISTORY_FILE: Mutex<()> = Mutex::new(());
+
+/// The backend's resource use at one point.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Sample {
+    /// Unix milliseconds.
+    pub at: i64,
+    pub pid: u32,
+    /// Resident memory, bytes.
+    pub rss: u64,
+    /// CPU used since the previous sample, as a percentage of one core.
+    /// `None` for a backend's first sample.
+    #[serde(default)]
+    pub cpu_percent: Option<f64>,
+}
+
+/// What `get_resource_history` returns.
+#[derive(Debug, Clone, Serialize)]
+pub struct History {
+    /// Oldest first.
+    pub samples: Vec<Sample>,
+    /// Backend starts, stops, crashes and hangs, oldest first.
+    pub events: Vec<uptime::Event>,
+}
+
+/// The uptime and CPU time of the previous sample, by backend PID.
+struct Previous {
+    pid: u32,
+    uptime: i64,
+    cpu: u64,
+}
+
+/// Spawn the sampler. Safe to call more than once.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut previous: Option<Previous> = None;
+        loop {
+            std::thread::sleep(SAMPLE_INTERVAL);
+            let state = app.state::<BackendState>();
+            let pid = state
+                .process
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|child| child.id())
+                .or(*state.adopted_pid.lock().unwrap());
+            // A hung backend's last status would repeat itself
+            let Some((pid, status)) = pid
+                .filter(|&pid| ipc::heard_from_within(pid, SAMPLE_INTERVAL))
+                .and_then(|pid| Some((pid, ipc::last_status(pid)?)))
+            else {
+                previous = None;
+                continue;
+            };
+            let sample = Sample {
+                at: chrono::Utc::now().timestamp_millis(),
+                pid,
+                rss: status.rss,
+                cpu_percent: cpu_percent(previous.as_ref(), pid, &status),
+            };
+            previous = Some(Previous {
+                pid,
+                uptime: status.uptime,
+                cpu: status.cpu,
+            });
+            record(sample);
+        }
+    });
+}
+
+/// CPU used between `previous` and `status` of backend `pid`, as a
+/// percentage of one core.
+fn cpu_percent(previous: Option<&Previous>, pid: u32, status: &BackendStatus) -> Option<f64> {
+    let previous = previous.filter(|previous| previous.pid == pid)?;
+    let elapsed = status.uptime - previous.uptime;
+    let used = status.cpu.checked_sub(previous.cpu)?;
+    (elapsed > 0).then(|| used as f64 * 100.0 / elapsed as f64)
+}
+
+fn record(sample: Sample) {
+    let _file = HISTORY_FILE.lock().unwrap();
+    let mut samples = load();
+    push(&mut samples, sample);
+    let saved = history_path().and_then(|path| {
+        let content = serde_json::to_string(&samples).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        eprintln!("Failed to save resource history: {}", e);
+    }
+}
+
+/// Add `sample`, dropping the oldest beyond `KEPT_SAMPLES`.
+fn push(samples: &mut Vec<Sample>, sample: Sample) {
+    samples.push(sample);
+    let excess = samples.len().saturating_sub(KEPT_SAMPLES);
+    samples.drain(..excess);
+}
+
+fn history_path() -> Result<PathBuf, String> {
+    Ok(config::get_data_path()?.join("resource_history.json"))
+}
+
+fn load() -> Vec<Sample> {
+    history_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// The last 24 hours of samples and backend events.
+pub fn history() -> History {
+    let since = chrono::Utc::now().timestamp_millis() - HISTORY_MS;
+    let samples = {
+        let _file = HISTORY_FILE.lock().unwrap();
+        load()
+    };
+    History {
+        samples: samples
+            .into_iter()
+            .filter(|sample| sample.at >= since)
+            .collect(),
+        events: uptime::load()
+            .into_iter()
+            .filter(|event| {
+                event.at >= since
+                    && matches!(
+                        event.kind,
+                        EventKind::BackendStarted
+                            | EventKind::BackendAdopted
+                            | EventKind::BackendStopped
+                            | EventKind::BackendCrashed
+                            | EventKind::BackendHung
+                    )
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_percent_and_push() {
+        let status = BackendStatus {
+            uptime: 180_000,
+            rss: 150_000_000,
+            cpu: 15_000,
+        };
+        let previous = Previous {
+            pid: 42,
+            uptime: 120_000,
+            cpu: 12_000,
+        };
+        assert_eq!(cpu_percent(Some(&previous), 42, &status), Some(5.0));
+        // Another backend, or none sampled before
+        assert_eq!(cpu_percent(Some(&previous), 43, &status), None);
+        assert_eq!(cpu_percent(None, 42, &status), None);
+
+        let sample = |at| Sample {
+            at,
+            pid: 42,
+            rss: 0,
+            cpu_percent: None,
+        };
+        let mut samples: Vec<Sample> = (0..KEPT_SAMPLES as i64).map(sample).collect();
+        push(&mut samples, sample(KEPT_SAMPLES as i64));
+        assert_eq!(samples.len(), KEPT_SAMPLES);
+        assert_eq!(samples[0].at, 1);
+    }
+}
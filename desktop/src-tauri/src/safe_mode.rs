@@ -0,0 +1,215 @@
+//! Safe mode for a backend stuck in a crash loop. When it has exited
+//! `CRASHES_FOR_SAFE_MODE` times in a row within `EARLY_CRASH` of starting
+//! (counted from the uptime history, so across launches too), the next
+//! start is made in safe mode: the backend gets `SAFE_MODE=true` and
+//! connects to no source, node or MQTT, so the web UI comes up. The Logs
+//! window opens to show what went wrong, and the configuration the backend
+//! last ran healthily with (`config.good.json`) is offered for restore.
+//!
+//! Safe mode ends with that restore or a restart from Settings. On the next
+//! launch it's only entered again if the crashes continue, as a safe-mode
+//! backend that stays up breaks the run.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::config::{self, Config};
+use crate::uptime::{self, Event, EventKind};
+use crate::{audit, config_watcher, desktop_events, log, webview};
+
+/// A backend that exits sooner than this after starting crashed early.
+const EARLY_CRASH: Duration = Duration::from_secs(30);
+
+/// Early crashes in a row that start safe mode.
+const CRASHES_FOR_SAFE_MODE: usize = 3;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Crashes before this (Unix milliseconds) no longer count, once safe mode
+/// was left.
+static COUNT_FROM: AtomicI64 = AtomicI64::new(0);
+
+/// Whether backends are started in safe mode.
+pub fn active() -> bool {
+    ACTIVE.load(Ordering::SeqCst)
+}
+
+/// Decide, before a start, whether it's made in safe mode. On entering it,
+/// the Logs window is opened and the last good configuration offered.
+pub fn check<R: Runtime>(app: &AppHandle<R>) -> bool {
+    if active() {
+        return true;
+    }
+    let crashes = early_crashes(&uptime::load(), COUNT_FROM.load(Ordering::SeqCst));
+    if crashes < CRASHES_FOR_SAFE_MODE {
+        return false;
+    }
+    ACTIVE.store(true, Ordering::SeqCst);
+    let message = format!(
+        "Backend exited within {} s of starting {} times in a row; starting it in safe mode",
+        EARLY_CRASH.as_secs(),
+        crashes
+    );
+    log(&message);
+    audit::record(audit::Source::Scheduler, "safe_mode_entered", &message);
+    desktop_events::publish(
+        app,
+        desktop_events::Level::Warning,
+        "MeshMonitor kept crashing right after starting, so it's running in safe mode, \
+         without connecting to your node or MQTT.",
+    );
+    webview::show_log_search_window(app);
+    offer_restore(app, crashes);
+    true
+}
+
+/// Leave safe mode; the next start is a normal one.
+pub fn leave() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        COUNT_FROM.store(chrono::Utc::now().timestamp_millis(), Ordering::SeqCst);
+        log("Left safe mode");
+    }
+}
+
+/// Keep the configuration a backend answered its first heartbeat with as
+/// the last good one. Safe-mode runs prove nothing about it.
+pub fn backend_healthy() {
+    if active() {
+        return;
+    }
+    let (Ok(config_path), Some(good_path)) = (config::get_config_path(), good_path()) else {
+        return;
+    };
+    if let Err(e) = std::fs::copy(&config_path, &good_path) {
+        log(&format!(
+            "Failed to keep the last good configuration: {}",
+            e
+        ));
+    }
+}
+
+fn good_path() -> Option<PathBuf> {
+    let config_path = config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("config.good.json"))
+}
+
+fn load_good() -> Option<Config> {
+    let content = std::fs::read_to_string(good_path()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Offer the last good configuration when it differs from the current one.
+fn offer_restore<R: Runtime>(app: &AppHandle<R>, crashes: usize) {
+    let intro = format!(
+        "The MeshMonitor backend stopped {} times within seconds of starting, so it was \
+         started in safe mode, without connecting to your node or MQTT. The Logs window \
+         shows what went wrong.",
+        crashes
+    );
+    let current = Config::load().unwrap_or_default();
+    let good = load_good()
+        .filter(|good| serde_json::to_value(good).ok() != serde_json::to_value(&current).ok());
+    let Some(good) = good else {
+        app.dialog()
+            .message(format!(
+                "{}\n\nFix the problem, then restart the backend from Settings to leave safe mode.",
+                intro
+            ))
+            .title("MeshMonitor Safe Mode")
+            .kind(MessageDialogKind::Warning)
+            .show(|_| {});
+        return;
+    };
+    let handle = app.clone();
+    app.dialog()
+        .message(format!(
+            "{}\n\nYour settings have changed since MeshMonitor last ran without problems. \
+             Restore those settings and restart?",
+            intro
+        ))
+        .title("MeshMonitor Safe Mode")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Restore and Restart".to_string(),
+            "Stay in Safe Mode".to_string(),
+        ))
+        .show(move |restore| {
+            if !restore {
+                return;
+            }
+            if let Err(e) = good.save() {
+                log(&format!(
+                    "Failed to restore the last good configuration: {}",
+                    e
+                ));
+                return;
+            }
+            audit::record_config_change(audit::Source::Dialog, &current, &good);
+            log("Restored the last good configuration");
+            leave();
+            config_watcher::reload(&handle, true);
+        });
+}
+
+/// Early crashes at the end of `events`, counting from `since` (Unix
+/// milliseconds). A backend that was stopped, hung or adopted instead
+/// breaks the run, and so does one that outlived `EARLY_CRASH`.
+fn early_crashes(events: &[Event], since: i64) -> usize {
+    let early = EARLY_CRASH.as_millis() as i64;
+    let mut started = None;
+    let mut crashes = 0;
+    for event in events.iter().filter(|event| event.at >= since) {
+        match event.kind {
+            EventKind::BackendStarted => started = Some(event.at),
+            EventKind::BackendCrashed => match started.take() {
+                Some(at) if event.at - at < early => crashes += 1,
+                _ => crashes = 0,
+            },
+            EventKind::BackendStopped | EventKind::BackendHung | EventKind::BackendAdopted => {
+                started = None;
+                crashes = 0;
+            }
+            EventKind::DesktopStarted | EventKind::LinkUp | EventKind::LinkDown => {}
+        }
+    }
+    crashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(at: i64, kind: EventKind) -> Event {
+        Event {
+            at,
+            kind,
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_counts_early_crashes_in_a_row() {
+        let crash_at = |start: i64, after: i64| {
+            [
+                event(start, EventKind::BackendStarted),
+                event(start + after, EventKind::BackendCrashed),
+            ]
+        };
+        let mut events: Vec<Event> = Vec::new();
+        events.extend(crash_at(0, 2_000));
+        events.push(event(5_000, EventKind::DesktopStarted));
+        events.extend(crash_at(10_000, 1_000));
+        events.extend(crash_at(20_000, 3_000));
+        assert_eq!(early_crashes(&events, 0), 3);
+        assert_eq!(early_crashes(&events, 10_000), 2);
+
+        // A backend that ran for a while breaks the run
+        events.extend(crash_at(30_000, 600_000));
+        assert_eq!(early_crashes(&events, 0), 0);
+        events.extend(crash_at(700_000, 1_000));
+        assert_eq!(early_crashes(&events, 0), 1);
+    }
+}
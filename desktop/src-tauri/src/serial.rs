@@ -0,0 +1,307 @@
+//! USB serial link to a Meshtastic node, served to the backend through a
+//! [`bridge`](crate::bridge). The node speaks the same framed protocol on its
+//! serial port as over TCP, mixed with its debug log, so packets are framed
+//! and unframed here and everything else is dropped.
+//!
+//! The port is reopened by the bridge whenever it fails, so unplugging the
+//! node only shows it as disconnected until it is plugged back in.
+
+use std::fs::File;
+use std::io::{ErrorKind, Read, Write};
+use std::time::Duration;
+
+use crate::bridge::{encode_frame, FrameDecoder, Link};
+
+/// Baud rates a node's serial module can be set to.
+pub const BAUD_RATES: [u32; 8] = [
+    9_600, 19_200, 38_400, 57_600, 115_200, 230_400, 460_800, 921_600,
+];
+
+/// Start bytes sent on open so a node that was printing its debug log
+/// switches to the framed protocol.
+const WAKE: [u8; 32] = [0xC3; 32];
+
+struct SerialLink {
+    port: File,
+    path: String,
+    decoder: FrameDecoder,
+}
+
+impl Link for SerialLink {
+    fn send(&mut self, packet: &[u8]) -> Result<(), String> {
+        self.port
+            .write_all(&encode_frame(packet))
+            .map_err(|e| format!("Writing to {} failed: {}", self.path, e))
+    }
+
+    fn recv(&mut self, _timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        if let Some(packet) = self.decoder.next_packet() {
+            return Ok(Some(packet));
+        }
+        // Reads return after about 100 ms without data (see `open_port`)
+        let mut buf = [0u8; 1024];
+        match self.port.read(&mut buf) {
+            Ok(0) => {
+                // A vanished device node reads as end of file on Unix
+                if cfg!(unix) && !std::path::Path::new(&self.path).exists() {
+                    return Err(format!("{} was unplugged", self.path));
+                }
+                Ok(None)
+            }
+            Ok(read) => {
+                self.decoder.push(&buf[..read]);
+                Ok(self.decoder.next_packet())
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::Interrupted => Ok(None),
+            Err(e) => Err(format!("{} was disconnected: {}", self.path, e)),
+        }
+    }
+}
+
+/// Open the node on `port` at `baud`, or on the first USB serial device
+/// found when `port` is empty. Returns the link and the port it opened.
+pub fn open(port: &str, baud: u32) -> Result<(Box<dyn Link>, String), String> {
+    if !BAUD_RATES.contains(&baud) {
+        return Err(format!(
+            "Unsupported baud rate {}. Use one of {:?}.",
+            baud, BAUD_RATES
+        ));
+    }
+    let path = match port.trim() {
+        "" => available_ports()
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No USB serial device found. Plug in the node.".to_string())?,
+        port => port.to_string(),
+    };
+    let mut port = open_port(&path, baud)?;
+    port.write_all(&WAKE)
+        .map_err(|e| format!("Writing to {} failed: {}", path, e))?;
+    let link = SerialLink {
+        port,
+        path: path.clone(),
+        decoder: FrameDecoder::default(),
+    };
+    Ok((Box::new(link), path))
+}
+
+/// Open `path` raw at `baud`, 8N1, with reads that return after about
+/// 100 ms without data.
+#[cfg(unix)]
+fn open_port(path: &str, baud: u32) -> Result<File, String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::os::unix::io::AsRawFd;
+
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(path)
+        .map_err(|e| format!("Couldn't open {}: {}", path, e))?;
+    let fd = file.as_raw_fd();
+    // SAFETY: `fd` stays open for the lifetime of `file`, and `tty` is a
+    // plain struct filled in by tcgetattr before use.
+    unsafe {
+        let mut tty: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut tty) != 0 {
+            return Err(format!("{} isn't a serial port", path));
+        }
+        libc::cfmakeraw(&mut tty);
+        tty.c_cflag |= libc::CLOCAL | libc::CREAD;
+        tty.c_cc[libc::VMIN] = 0;
+        tty.c_cc[libc::VTIME] = 1;
+        libc::cfsetispeed(&mut tty, speed(baud));
+        libc::cfsetospeed(&mut tty, speed(baud));
+        if libc::tcsetattr(fd, libc::TCSANOW, &tty) != 0 {
+            return Err(format!(
+                "Couldn't configure {}: {}",
+                path,
+                std::io::Error::last_os_error()
+            ));
+        }
+        // Keep other programs from reading the node at the same time
+        libc::ioctl(fd, libc::TIOCEXCL);
+        libc::tcflush(fd, libc::TCIFLUSH);
+    }
+    Ok(file)
+}
+
+/// Termios speed for one of `BAUD_RATES`. Linux numbers them; the BSDs
+/// and macOS take the rate itself.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn speed(baud: u32) -> libc::speed_t {
+    match baud {
+        9_600 => libc::B9600,
+        19_200 => libc::B19200,
+        38_400 => libc::B38400,
+        57_600 => libc::B57600,
+        230_400 => libc::B230400,
+        460_800 => libc::B460800,
+        921_600 => libc::B921600,
+        _ => libc::B115200,
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+fn speed(baud: u32) -> libc::speed_t {
+    libc::speed_t::from(baud)
+}
+
+/// Open `path` (`COM3` or `\\.\COM12`) at `baud`, 8N1, with reads that
+/// return after about 100 ms without data.
+#[cfg(windows)]
+fn open_port(path: &str, baud: u32) -> Result<File, String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Devices::Communication::{
+        GetCommState, SetCommState, SetCommTimeouts, COMMTIMEOUTS, DCB, NOPARITY, ONESTOPBIT,
+    };
+
+    let device = if path.starts_with(r"\\.\") {
+        path.to_string()
+    } else {
+        format!(r"\\.\{}", path)
+    };
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&device)
+        .map_err(|e| format!("Couldn't open {}: {}", path, e))?;
+    let handle = file.as_raw_handle();
+    // SAFETY: `handle` stays open for the lifetime of `file`, and `dcb` is
+    // filled in by GetCommState before use.
+    unsafe {
+        let mut dcb: DCB = std::mem::zeroed();
+        dcb.DCBlength = std::mem::size_of::<DCB>() as u32;
+        if GetCommState(handle, &mut dcb) == 0 {
+            return Err(format!("{} isn't a serial port", path));
+        }
+        dcb.BaudRate = baud;
+        dcb.ByteSize = 8;
+        dcb.Parity = NOPARITY;
+        dcb.StopBits = ONESTOPBIT;
+        // fBinary, with DTR and RTS held on as they are on Unix
+        dcb._bitfield = 0x0001 | (1 << 4) | (1 << 12);
+        let timeouts = COMMTIMEOUTS {
+            ReadIntervalTimeout: u32::MAX,
+            ReadTotalTimeoutMultiplier: u32::MAX,
+            ReadTotalTimeoutConstant: 100,
+            WriteTotalTimeoutMultiplier: 0,
+            WriteTotalTimeoutConstant: 1000,
+        };
+        if SetCommState(handle, &dcb) == 0 || SetCommTimeouts(handle, &timeouts) == 0 {
+            return Err(format!(
+                "Couldn't configure {}: {}",
+                path,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(file)
+}
+
+/// USB serial devices that could be a node, most likely first.
+pub fn available_ports() -> Vec<String> {
+    imp::available_ports()
+}
+
+/// Whether a `/dev` entry is a USB serial adapter or a board's native USB
+/// port.
+#[cfg_attr(windows, allow(dead_code))]
+fn is_usb_serial(name: &str) -> bool {
+    const PREFIXES: [&str; 6] = [
+        "ttyACM",
+        "ttyUSB",
+        "cu.usbmodem",
+        "cu.usbserial",
+        "cu.wchusbserial",
+        "cu.SLAB_USBtoUART",
+    ];
+    PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// COM ports from `reg query HKLM\HARDWARE\DEVICEMAP\SERIALCOMM` output,
+/// leaving out the built-in `\Device\Serial*` ports.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_serialcomm(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?;
+            (fields.next()? == "REG_SZ").then_some(())?;
+            let port = fields.next()?;
+            (!device.starts_with(r"\Device\Serial")).then(|| port.to_string())
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::is_usb_serial;
+
+    pub fn available_ports() -> Vec<String> {
+        // Stable names that survive replugging, where udev provides them
+        let mut ports = list("/dev/serial/by-id", |_| true);
+        if ports.is_empty() {
+            ports = list("/dev", is_usb_serial);
+        }
+        ports
+    }
+
+    fn list(dir: &str, keep: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut ports: Vec<String> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| entry.file_name().to_str().is_some_and(&keep))
+                    .map(|entry| entry.path().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        ports.sort();
+        ports
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    use super::parse_serialcomm;
+
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    pub fn available_ports() -> Vec<String> {
+        Command::new("reg")
+            .args(["query", r"HKLM\HARDWARE\DEVICEMAP\SERIALCOMM"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map(|output| parse_serialcomm(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognizes_usb_serial_devices() {
+        assert!(is_usb_serial("ttyUSB0"));
+        assert!(is_usb_serial("ttyACM1"));
+        assert!(is_usb_serial("cu.usbmodem14101"));
+        assert!(!is_usb_serial("ttyS0"));
+        assert!(!is_usb_serial("tty.usbserial-0001"));
+    }
+
+    #[test]
+    fn test_parse_serialcomm_skips_builtin_ports() {
+        let output = "\r\nHKEY_LOCAL_MACHINE\\HARDWARE\\DEVICEMAP\\SERIALCOMM\r\n    \
+                      \\Device\\Serial0    REG_SZ    COM1\r\n    \
+                      \\Device\\Silabser0    REG_SZ    COM4\r\n    \
+                      \\Device\\USBSER000    REG_SZ    COM7\r\n\r\n";
+        assert_eq!(parse_serialcomm(output), vec!["COM4", "COM7"]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Read-only links for visitors: the backend issues a time-limited share
+//! token, which is put on this machine's preferred LAN or VPN address, with
+//! a QR code for the visitor's phone. Opening the link shows the web UI as
+//! an anonymous user would see it, without a login, until it expires or the
+//! backend restarts.
+
+use serde::Serialize;
+
+use crate::addresses::{self, AddressKind};
+use crate::backend_record::BackendRecord;
+use crate::config::Config;
+use crate::{api, audit};
+
+/// A link ready to hand to a visitor.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLink {
+    pub url: String,
+    pub qr_svg: String,
+    /// Unix milliseconds.
+    pub expires_at: i64,
+    /// Why the visitor's browser may be turned away, if it might.
+    pub warning: Option<String>,
+}
+
+/// Create a link valid for `minutes`.
+pub fn create(config: &Config, minutes: u32) -> Result<ShareLink, String> {
+    let control_token = BackendRecord::load()
+        .and_then(|record| record.control_token)
+        .ok_or("Start MeshMonitor's backend before creating a share link")?;
+    let base = addresses::web_addresses(config.web_port)
+        .into_iter()
+        .find(|address| address.preferred && address.kind != AddressKind::Localhost)
+        .ok_or("This computer has no LAN or VPN address a visitor could open")?;
+    let share = api::create_share_link(config.web_port, &control_token, minutes)?;
+    let url = share_url(&base.url, &share.token);
+    audit::record(
+        audit::Source::Command,
+        "share_link_created",
+        &format!("{} for {} min", base.url, minutes),
+    );
+    Ok(ShareLink {
+        qr_svg: addresses::qr_svg(&url),
+        url,
+        expires_at: share.expires_at,
+        warning: (!origin_allowed(config, &base.url)).then(|| {
+            format!(
+                "Add {} to Allowed Origins if the visitor's browser shows errors.",
+                base.url
+            )
+        }),
+    })
+}
+
+fn share_url(base: &str, token: &str) -> String {
+    format!("{}/?share={}", base.trim_end_matches('/'), token)
+}
+
+/// Whether `origin` is in `Config::allowed_origins`.
+fn origin_allowed(config: &Config, origin: &str) -> bool {
+    config.allowed_origins.as_deref().is_some_and(|origins| {
+        origins
+            .split(',')
+            .any(|allowed| allowed.trim().trim_end_matches('/') == origin)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_url_and_allowed_origin() {
+        assert_eq!(
+            share_url("http://192.168.1.50:8080", "abc"),
+            "http://192.168.1.50:8080/?share=abc"
+        );
+        let mut config = Config {
+            allowed_origins: Some("http://10.0.0.2:8080, http://192.168.1.50:8080/".to_string()),
+            ..Config::default()
+        };
+        assert!(origin_allowed(&config, "http://192.168.1.50:8080"));
+        config.allowed_origins = None;
+        assert!(!origin_allowed(&config, "http://192.168.1.50:8080"));
+    }
+}
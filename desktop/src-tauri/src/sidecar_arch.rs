@@ -0,0 +1,361 @@
+//! Chooses the bundled Node.js binary that matches the machine it runs on.
+//!
+//! Bundles may carry per-architecture binaries (`node-arm64`, `node-x64`)
+//! next to, or instead of, a plain `node`. The host is detected at runtime —
+//! including an x64 build of this app running under Rosetta on Apple Silicon
+//! or under emulation on Windows on ARM — and each candidate's executable
+//! header is checked before it is spawned, so a mismatch becomes a clear
+//! error instead of a cryptic "bad CPU type" or silent exit.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// CPU architectures that matter for the bundled sidecar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X64,
+    Arm64,
+    X86,
+}
+
+impl Arch {
+    /// Suffix used for per-architecture binaries, matching Node's own naming.
+    fn node_name(self) -> &'static str {
+        match self {
+            Arch::X64 => "x64",
+            Arch::Arm64 => "arm64",
+            Arch::X86 => "x86",
+        }
+    }
+
+    fn from_rust_arch(arch: &str) -> Option<Self> {
+        match arch {
+            "x86_64" => Some(Arch::X64),
+            "aarch64" => Some(Arch::Arm64),
+            "x86" => Some(Arch::X86),
+            _ => None,
+        }
+    }
+}
+
+/// The architecture of the machine, which may differ from the one this app
+/// was compiled for when running under translation.
+pub fn host_arch() -> Option<Arch> {
+    let compiled = Arch::from_rust_arch(std::env::consts::ARCH)?;
+
+    #[cfg(target_os = "macos")]
+    if compiled == Arch::X64 {
+        // 1 when this process is translated by Rosetta 2
+        let translated = std::process::Command::new("sysctl")
+            .args(["-n", "sysctl.proc_translated"])
+            .output()
+            .is_ok_and(|out| String::from_utf8_lossy(&out.stdout).trim() == "1");
+        if translated {
+            return Some(Arch::Arm64);
+        }
+    }
+
+    #[cfg(windows)]
+    if compiled != Arch::Arm64 {
+        // Emulated processes see their own architecture in
+        // PROCESSOR_ARCHITECTURE, but the CPU identifier gives ARM away.
+        let arm = ["PROCESSOR_ARCHITEW6432", "PROCESSOR_IDENTIFIER"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .any(|value| value.to_ascii_uppercase().contains("ARM"));
+        if arm {
+            return Some(Arch::Arm64);
+        }
+    }
+
+    Some(compiled)
+}
+
+/// The first Windows 11 build. Windows 10 on ARM only emulates x86, not x64.
+pub const WINDOWS_11_BUILD: u32 = 22000;
+
+/// The Windows build number, such as 22631, from the registry.
+#[cfg(windows)]
+pub fn windows_build() -> Option<u32> {
+    use std::os::windows::process::CommandExt;
+    use std::sync::OnceLock;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    static BUILD: OnceLock<Option<u32>> = OnceLock::new();
+    *BUILD.get_or_init(|| {
+        let output = std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion",
+                "/v",
+                "CurrentBuildNumber",
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()?;
+        parse_windows_build(&String::from_utf8_lossy(&output.stdout))
+    })
+}
+
+#[cfg(not(windows))]
+pub fn windows_build() -> Option<u32> {
+    None
+}
+
+/// The build number in `reg query` output such as
+/// `CurrentBuildNumber    REG_SZ    22631`.
+#[cfg(any(windows, test))]
+fn parse_windows_build(output: &str) -> Option<u32> {
+    output
+        .lines()
+        .find(|line| line.contains("CurrentBuildNumber"))?
+        .split_whitespace()
+        .last()?
+        .parse()
+        .ok()
+}
+
+/// Architectures `host` can run besides its own, through the OS's
+/// translation layer (Rosetta 2, Windows on ARM x64 emulation, WoW64).
+fn emulated_on(host: Arch) -> &'static [Arch] {
+    match host {
+        Arch::Arm64 if cfg!(target_os = "macos") => &[Arch::X64],
+        Arch::Arm64 if cfg!(windows) => {
+            if windows_build().is_some_and(|build| build < WINDOWS_11_BUILD) {
+                &[Arch::X86]
+            } else {
+                &[Arch::X64, Arch::X86]
+            }
+        }
+        Arch::X64 if cfg!(windows) => &[Arch::X86],
+        _ => &[],
+    }
+}
+
+/// Architectures an executable was built for, from its Mach-O (thin or
+/// universal), PE or ELF header. Empty when the format isn't recognised.
+pub fn binary_arches(header: &[u8]) -> Vec<Arch> {
+    let u16_le = |at: usize| {
+        header
+            .get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let u32_le = |at: usize| {
+        header
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let u32_be = |at: usize| {
+        header
+            .get(at..at + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let mach_cpu = |cpu: u32| match cpu {
+        0x0100_0007 => Some(Arch::X64),
+        0x0100_000c => Some(Arch::Arm64),
+        7 => Some(Arch::X86),
+        _ => None,
+    };
+
+    match header {
+        // Thin 64-bit Mach-O, little-endian
+        [0xcf, 0xfa, 0xed, 0xfe, ..] => u32_le(4).and_then(mach_cpu).into_iter().collect(),
+        // Universal binary: big-endian list of (cputype, ...) entries
+        [0xca, 0xfe, 0xba, 0xbe, ..] | [0xca, 0xfe, 0xba, 0xbf, ..] => {
+            let entry_size = if header[3] == 0xbf { 32 } else { 20 };
+            let count = u32_be(4).unwrap_or(0) as usize;
+            (0..count.min(16))
+                .filter_map(|i| u32_be(8 + i * entry_size).and_then(mach_cpu))
+                .collect()
+        }
+        [b'M', b'Z', ..] => {
+            let pe = u32_le(0x3c).unwrap_or(0) as usize;
+            if header.get(pe..pe + 4) != Some(b"PE\0\0") {
+                return Vec::new();
+            }
+            match u16_le(pe + 4) {
+                Some(0x8664) => vec![Arch::X64],
+                Some(0xaa64) => vec![Arch::Arm64],
+                Some(0x014c) => vec![Arch::X86],
+                _ => Vec::new(),
+            }
+        }
+        [0x7f, b'E', b'L', b'F', ..] => match u16_le(18) {
+            Some(0x3e) => vec![Arch::X64],
+            Some(0xb7) => vec![Arch::Arm64],
+            Some(0x03) => vec![Arch::X86],
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn read_header(path: &Path) -> Vec<u8> {
+    let mut header = Vec::with_capacity(4096);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(4096).read_to_end(&mut header);
+    }
+    header
+}
+
+fn executable_name(stem: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", stem)
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Whether Node.js is bundled in `binaries_dir` but no binary of it can run
+/// on `host`, as opposed to missing altogether.
+pub fn none_runnable(binaries_dir: &Path, host: Option<Arch>) -> bool {
+    let bundled = ["node", "node-x64", "node-arm64", "node-x86"]
+        .iter()
+        .any(|stem| binaries_dir.join(executable_name(stem)).exists());
+    bundled && select_node(binaries_dir, host).is_err()
+}
+
+/// Pick the Node.js binary in `binaries_dir` to run on `host`: a native one
+/// if bundled (`node-<arch>` first, then plain `node`), otherwise one the OS
+/// can emulate. Returns the path and a warning when falling back to
+/// emulation, or an error explaining what was bundled.
+pub fn select_node(
+    binaries_dir: &Path,
+    host: Option<Arch>,
+) -> Result<(PathBuf, Option<String>), String> {
+    let mut stems = vec!["node".to_string()];
+    if let Some(host) = host {
+        stems.insert(0, format!("node-{}", host.node_name()));
+        for arch in emulated_on(host) {
+            stems.push(format!("node-{}", arch.node_name()));
+        }
+    }
+
+    let candidates: Vec<(PathBuf, Vec<Arch>)> = stems
+        .iter()
+        .map(|stem| binaries_dir.join(executable_name(stem)))
+        .filter(|path| path.exists())
+        .map(|path| {
+            let arches = binary_arches(&read_header(&path));
+            (path, arches)
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        return Err(format!(
+            "ERROR: Node.js binary not found at {:?}",
+            binaries_dir.join(executable_name("node"))
+        ));
+    }
+
+    let Some(host) = host else {
+        // Unknown host architecture: nothing to check against
+        return Ok((candidates[0].0.clone(), None));
+    };
+
+    if let Some((path, _)) = candidates.iter().find(|(_, arches)| arches.contains(&host)) {
+        return Ok((path.clone(), None));
+    }
+    // Unrecognised headers get the benefit of the doubt
+    if let Some((path, _)) = candidates.iter().find(|(_, arches)| arches.is_empty()) {
+        return Ok((path.clone(), None));
+    }
+    if let Some((path, arches)) = candidates
+        .iter()
+        .find(|(_, arches)| arches.iter().any(|a| emulated_on(host).contains(a)))
+    {
+        let warning = format!(
+            "Bundled Node.js at {:?} is built for {:?}, running it under emulation on this {:?} machine; \
+             install the native build of MeshMonitor for better performance",
+            path, arches, host
+        );
+        return Ok((path.clone(), Some(warning)));
+    }
+
+    let found: Vec<String> = candidates
+        .iter()
+        .map(|(path, arches)| format!("{:?} ({:?})", path, arches))
+        .collect();
+    Err(format!(
+        "ERROR: No bundled Node.js binary can run on this {:?} machine (found {}). \
+         Download the MeshMonitor installer for your processor architecture.",
+        host,
+        found.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn elf(machine: u16) -> Vec<u8> {
+        let mut header = vec![0u8; 64];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[18..20].copy_from_slice(&machine.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn test_binary_arches() {
+        assert_eq!(binary_arches(&elf(0x3e)), vec![Arch::X64]);
+        assert_eq!(binary_arches(&elf(0xb7)), vec![Arch::Arm64]);
+
+        let mut thin = vec![0xcf, 0xfa, 0xed, 0xfe];
+        thin.extend_from_slice(&0x0100_000cu32.to_le_bytes());
+        assert_eq!(binary_arches(&thin), vec![Arch::Arm64]);
+
+        let mut fat = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 2];
+        for cpu in [0x0100_0007u32, 0x0100_000c] {
+            fat.extend_from_slice(&cpu.to_be_bytes());
+            fat.extend_from_slice(&[0; 16]);
+        }
+        assert_eq!(binary_arches(&fat), vec![Arch::X64, Arch::Arm64]);
+
+        let mut pe = vec![0u8; 0x90];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x84..0x86].copy_from_slice(&0xaa64u16.to_le_bytes());
+        assert_eq!(binary_arches(&pe), vec![Arch::Arm64]);
+
+        assert_eq!(binary_arches(b"#!/bin/sh\n"), vec![]);
+
+        assert_eq!(
+            parse_windows_build(
+                "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\r\n    \
+                 CurrentBuildNumber    REG_SZ    19045\r\n"
+            ),
+            Some(19045)
+        );
+    }
+
+    #[test]
+    fn test_select_prefers_native_binary() {
+        let dir =
+            std::env::temp_dir().join(format!("meshmonitor-arch-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        assert!(select_node(&dir, Some(Arch::X64)).is_err());
+
+        fs::write(dir.join(executable_name("node")), elf(0x3e)).unwrap();
+        let (path, warning) = select_node(&dir, Some(Arch::X64)).unwrap();
+        assert_eq!(path, dir.join(executable_name("node")));
+        assert!(warning.is_none());
+
+        // An x64-only bundle can't run on a host with no x64 translation
+        if emulated_on(Arch::Arm64).is_empty() {
+            assert!(select_node(&dir, Some(Arch::Arm64)).is_err());
+            assert!(none_runnable(&dir, Some(Arch::Arm64)));
+        }
+        assert!(!none_runnable(&dir, Some(Arch::X64)));
+
+        fs::write(dir.join(executable_name("node-arm64")), elf(0xb7)).unwrap();
+        let (path, _) = select_node(&dir, Some(Arch::Arm64)).unwrap();
+        assert_eq!(path, dir.join(executable_name("node-arm64")));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
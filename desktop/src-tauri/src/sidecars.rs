@@ -0,0 +1,296 @@
+//! Helper programs from `Config::sidecars`, supervised with the backend's
+//! lifecycle: started before each backend start (or adoption), stopped when
+//! the backend is stopped and when the app quits. A thread per sidecar
+//! watches it and starts it again per its `RestartPolicy`, up to
+//! `MAX_RESTARTS` times in a row. Each one's output is appended to
+//! `logs/sidecar-<name>.log`.
+//!
+//! Starting is idempotent: a sidecar already running with the same settings
+//! is left alone, so a backend restarted after a crash keeps its broker.
+
+use std::fs::OpenOptions;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{self, RestartPolicy, Sidecar};
+use crate::{backend_record, log};
+
+/// How often a sidecar is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Wait before a sidecar that exited is started again.
+const RESTART_DELAY: Duration = Duration::from_secs(3);
+
+/// Restarts in a row before a sidecar is given up on.
+const MAX_RESTARTS: u32 = 5;
+
+/// A sidecar up this long counts as having started fine; its restarts
+/// start counting afresh.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// How long a sidecar gets to exit after SIGTERM before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Supervised {
+    sidecar: Sidecar,
+    child: Mutex<Option<Child>>,
+    /// Set once it's stopped, or has exited and won't be restarted
+    stopped: AtomicBool,
+}
+
+static RUNNING: Mutex<Vec<Arc<Supervised>>> = Mutex::new(Vec::new());
+
+/// Run exactly the sidecars in `sidecars`: stop those no longer listed (or
+/// changed), start those not running, including any given up on.
+pub fn start_all(sidecars: &[Sidecar]) {
+    let mut running = RUNNING.lock().unwrap();
+    let (keep, remove): (Vec<_>, Vec<_>) = running.drain(..).partition(|supervised| {
+        sidecars.contains(&supervised.sidecar) && !supervised.stopped.load(Ordering::SeqCst)
+    });
+    *running = keep;
+    for supervised in remove {
+        stop(&supervised);
+    }
+    for sidecar in sidecars {
+        if running
+            .iter()
+            .any(|supervised| &supervised.sidecar == sidecar)
+        {
+            continue;
+        }
+        if let Err(e) = validate(sidecar) {
+            log(&e);
+            continue;
+        }
+        match spawn(sidecar) {
+            Ok(child) => {
+                let supervised = Arc::new(Supervised {
+                    sidecar: sidecar.clone(),
+                    child: Mutex::new(Some(child)),
+                    stopped: AtomicBool::new(false),
+                });
+                watch(supervised.clone());
+                running.push(supervised);
+            }
+            Err(e) => log(&e),
+        }
+    }
+}
+
+/// Stop every sidecar.
+pub fn stop_all() {
+    let running: Vec<_> = RUNNING.lock().unwrap().drain(..).collect();
+    for supervised in running {
+        stop(&supervised);
+    }
+}
+
+/// Each sidecar's name and state, for the status report.
+pub fn statuses() -> Vec<(String, String)> {
+    RUNNING
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|supervised| {
+            let pid = supervised.child.lock().unwrap().as_ref().map(Child::id);
+            let status = match pid {
+                Some(pid) => format!("running, PID {}", pid),
+                None if supervised.stopped.load(Ordering::SeqCst) => "exited".to_string(),
+                None => "restarting".to_string(),
+            };
+            (supervised.sidecar.name.clone(), status)
+        })
+        .collect()
+}
+
+fn validate(sidecar: &Sidecar) -> Result<(), String> {
+    if sidecar.name.trim().is_empty() || sidecar.command.trim().is_empty() {
+        return Err(format!(
+            "Sidecar {:?} needs both a name and a command; not started",
+            sidecar.name
+        ));
+    }
+    Ok(())
+}
+
+fn spawn(sidecar: &Sidecar) -> Result<Child, String> {
+    let logs_path = config::get_logs_path()?;
+    let log_path = logs_path.join(format!("sidecar-{}.log", file_name(&sidecar.name)));
+    let mut output = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .map_err(|e| format!("Failed to open the log for sidecar {}: {}", sidecar.name, e))?;
+    let _ = std::io::Write::write_all(
+        &mut output,
+        format!(
+            "=== {} Starting {} ===\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            sidecar.command
+        )
+        .as_bytes(),
+    );
+    let errors = output
+        .try_clone()
+        .map_err(|e| format!("Failed to open the log for sidecar {}: {}", sidecar.name, e))?;
+
+    let mut cmd = Command::new(&sidecar.command);
+    cmd.args(&sidecar.args)
+        .envs(&sidecar.env)
+        .current_dir(config::get_data_path()?)
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(output))
+        .stderr(Stdio::from(errors));
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start sidecar {}: {}", sidecar.name, e))?;
+    log(&format!(
+        "Started sidecar {} (PID {}): {}",
+        sidecar.name,
+        child.id(),
+        sidecar.command
+    ));
+    Ok(child)
+}
+
+/// Watch `supervised` until it's stopped or given up on.
+fn watch(supervised: Arc<Supervised>) {
+    std::thread::spawn(move || {
+        let name = &supervised.sidecar.name;
+        let mut started = Instant::now();
+        let mut restarts = 0;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let status = {
+                let mut child = supervised.child.lock().unwrap();
+                let Some(process) = child.as_mut() else {
+                    return;
+                };
+                match process.try_wait() {
+                    Ok(Some(status)) => {
+                        *child = None;
+                        status
+                    }
+                    _ => continue,
+                }
+            };
+            if supervised.stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            log(&format!("Sidecar {} exited ({})", name, status));
+            let restart = match supervised.sidecar.restart {
+                RestartPolicy::Never => false,
+                RestartPolicy::OnFailure => !status.success(),
+                RestartPolicy::Always => true,
+            };
+            if !restart {
+                supervised.stopped.store(true, Ordering::SeqCst);
+                return;
+            }
+            if started.elapsed() >= STABLE_AFTER {
+                restarts = 0;
+            }
+            restarts += 1;
+            if restarts > MAX_RESTARTS {
+                log(&format!(
+                    "Sidecar {} exited {} times in a row; giving up",
+                    name, MAX_RESTARTS
+                ));
+                supervised.stopped.store(true, Ordering::SeqCst);
+                return;
+            }
+            std::thread::sleep(RESTART_DELAY);
+            let mut child = supervised.child.lock().unwrap();
+            // Checked under the lock, so a stop can't miss the new process
+            if supervised.stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            match spawn(&supervised.sidecar) {
+                Ok(process) => *child = Some(process),
+                Err(e) => {
+                    log(&e);
+                    supervised.stopped.store(true, Ordering::SeqCst);
+                    return;
+                }
+            }
+            started = Instant::now();
+        }
+    });
+}
+
+/// Ask the sidecar to exit (SIGTERM), then kill it after `STOP_TIMEOUT`.
+fn stop(supervised: &Supervised) {
+    let mut child = supervised.child.lock().unwrap();
+    supervised.stopped.store(true, Ordering::SeqCst);
+    let Some(mut process) = child.take() else {
+        return;
+    };
+    let name = &supervised.sidecar.name;
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    let exited = backend_record::terminate(process.id())
+        && loop {
+            if matches!(process.try_wait(), Ok(Some(_))) {
+                break true;
+            }
+            if Instant::now() >= deadline {
+                break false;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+    if !exited {
+        let _ = process.kill();
+    }
+    let _ = process.wait();
+    log(&format!(
+        "Stopped sidecar {}{}",
+        name,
+        if exited { "" } else { " (killed)" }
+    ));
+}
+
+/// `name` with anything but letters, digits, `-` and `_` replaced, for the
+/// log file's name.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_and_log_name() {
+        let sidecar: Sidecar =
+            serde_json::from_str(r#"{"name": "Mosquitto broker", "command": "mosquitto"}"#)
+                .unwrap();
+        assert_eq!(sidecar.restart, RestartPolicy::OnFailure);
+        assert!(sidecar.args.is_empty());
+        assert!(validate(&sidecar).is_ok());
+        assert_eq!(file_name(&sidecar.name), "Mosquitto_broker");
+        assert_eq!(file_name("../tiles"), "___tiles");
+
+        let sidecar = Sidecar {
+            command: " ".to_string(),
+            ..sidecar
+        };
+        assert!(validate(&sidecar).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Unix signals, for running MeshMonitor on a machine nobody sits at, the
+//! way other daemons are managed:
+//!
+//! - SIGHUP reloads config.json and restarts the backend if the file
+//!   changed since the backend started.
+//! - SIGUSR1 writes the state of the backend and the desktop's own services
+//!   to the log.
+//!
+//! The handler only sets a flag; a thread picks it up within
+//! `CHECK_INTERVAL`. Windows has no equivalent for a windowed app, so
+//! nothing is installed there.
+
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+
+#[cfg(unix)]
+use {
+    crate::config::{self, Config},
+    crate::{
+        api, audit, config_watcher, ipc, log, outbound, polling, remote_instances, safe_mode,
+        sidecars, startup_profile, BackendState,
+    },
+    tauri::Manager,
+};
+
+/// How often the flags set by the handler are checked.
+#[cfg(unix)]
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+#[cfg(unix)]
+static RELOAD: AtomicBool = AtomicBool::new(false);
+#[cfg(unix)]
+static DUMP_STATUS: AtomicBool = AtomicBool::new(false);
+
+/// Guards against installing the handler twice.
+#[cfg(unix)]
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    // Only async-signal-safe work here
+    match signal {
+        libc::SIGHUP => RELOAD.store(true, Ordering::SeqCst),
+        libc::SIGUSR1 => DUMP_STATUS.store(true, Ordering::SeqCst),
+        _ => {}
+    }
+}
+
+/// Install the handlers and start the thread acting on them. Safe to call
+/// more than once.
+#[cfg(unix)]
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    for signal in [libc::SIGHUP, libc::SIGUSR1] {
+        // SAFETY: the handler only stores to atomics
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(signal, &action, std::ptr::null_mut()) != 0 {
+                log(&format!(
+                    "Failed to handle signal {}: {}",
+                    signal,
+                    std::io::Error::last_os_error()
+                ));
+            }
+        }
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        if RELOAD.swap(false, Ordering::SeqCst) {
+            reload(&app);
+        }
+        if DUMP_STATUS.swap(false, Ordering::SeqCst) {
+            for line in status_lines(&app) {
+                log(&line);
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn start<R: Runtime>(_app: &AppHandle<R>) {}
+
+#[cfg(unix)]
+fn reload<R: Runtime>(app: &AppHandle<R>) {
+    if let Some(error) = config::config_corruption() {
+        log(&format!(
+            "SIGHUP: config.json is not valid JSON ({}); keeping the current configuration",
+            error
+        ));
+        return;
+    }
+    config_watcher::acknowledge_edit();
+    let running = app.state::<BackendState>().is_running();
+    let restart = running && config_watcher::changed_since_backend_start();
+    log(&format!(
+        "SIGHUP: reloaded config.json{}",
+        if restart {
+            "; restarting the backend"
+        } else if running {
+            "; unchanged since the backend started"
+        } else {
+            ""
+        }
+    ));
+    audit::record(
+        audit::Source::Signal,
+        "config_reloaded",
+        if restart { "backend restarted" } else { "" },
+    );
+    config_watcher::reload(app, restart);
+}
+
+/// The SIGUSR1 report.
+#[cfg(unix)]
+fn status_lines<R: Runtime>(app: &AppHandle<R>) -> Vec<String> {
+    let config = Config::load().unwrap_or_default();
+    let state = app.state::<BackendState>();
+    let mut lines = vec![format!(
+        "SIGUSR1: MeshMonitor Desktop {} status",
+        env!("CARGO_PKG_VERSION")
+    )];
+
+    let pid = state
+        .process
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|child| child.id())
+        .or(*state.adopted_pid.lock().unwrap());
+    let backend = match pid {
+        _ if config.external_backend => "external".to_string(),
+        Some(pid) if state.adopted_pid.lock().unwrap().is_some() => {
+            format!("running, PID {} (adopted)", pid)
+        }
+        Some(pid) => format!("running, PID {}", pid),
+        None => "stopped".to_string(),
+    };
+    lines.push(format!(
+        "  Backend: {}{}",
+        backend,
+        if safe_mode::active() {
+            " (safe mode)"
+        } else {
+            ""
+        }
+    ));
+    if let Some(status) = pid.and_then(ipc::last_status) {
+        lines.push(format!(
+            "  Backend uptime: {} s, memory {} MB",
+            status.uptime / 1000,
+            status.rss / 1_000_000
+        ));
+    }
+    lines.push(format!(
+        "  Web port {}: {}",
+        config.web_port,
+        match api::health(config.web_port) {
+            Ok(health) => format!("healthy ({})", health.status),
+            Err(e) => e,
+        }
+    ));
+    if let Some(bridge) = state.bridge.lock().unwrap().as_ref() {
+        lines.push(format!("  Bridge: {}", bridge.status().summary()));
+    }
+    if let Some(proxy) = state.proxy.lock().unwrap().as_ref() {
+        lines.push(format!("  Reverse proxy: port {}", proxy.port));
+    }
+    if let Some(tile_cache) = state.tile_cache.lock().unwrap().as_ref() {
+        lines.push(format!("  Tile cache: port {}", tile_cache.port));
+    }
+    for (name, status) in sidecars::statuses() {
+        lines.push(format!("  Sidecar {}: {}", name, status));
+    }
+    for (instance, status) in remote_instances::statuses() {
+        lines.push(format!(
+            "  Remote instance {}: {}",
+            instance.name,
+            status.label()
+        ));
+    }
+    lines.push(format!(
+        "  Startup: {}",
+        startup_profile::report().summary()
+    ));
+    lines.push(format!(
+        "  Polling: {:?}, privacy mode {}",
+        polling::current(),
+        if outbound::private() { "on" } else { "off" }
+    ));
+    lines
+}
@@ -0,0 +1,280 @@
+//! Just enough SMTP to send a plain-text alert: implicit TLS on port 465,
+//! STARTTLS wherever the server offers it, and `AUTH PLAIN`. A password is
+//! never sent over a connection that isn't encrypted. Certificates are
+//! checked against the Mozilla root store built into the app. Nothing is
+//! sent in privacy mode.
+
+use base64::Engine;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::outbound;
+
+/// How long connecting, and each reply, may take.
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The port where the connection is encrypted from the start.
+const SMTPS_PORT: u16 = 465;
+
+/// The server to send through.
+pub struct Server<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    /// Username and password, when it wants a login.
+    pub login: Option<(&'a str, &'a str)>,
+}
+
+/// One message.
+pub struct Mail<'a> {
+    pub from: &'a str,
+    pub to: &'a [String],
+    pub subject: &'a str,
+    pub body: &'a str,
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Send `mail` through `server`.
+pub fn send(server: &Server, mail: &Mail) -> Result<(), String> {
+    for address in std::iter::once(mail.from).chain(mail.to.iter().map(String::as_str)) {
+        check_address(address)?;
+    }
+    if mail.to.is_empty() {
+        return Err("No recipients".to_string());
+    }
+    outbound::ensure_allowed(server.host)?;
+
+    let address = (server.host, server.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Can't resolve {}: {}", server.host, e))?
+        .next()
+        .ok_or_else(|| format!("Can't resolve {}", server.host))?;
+    let tcp = TcpStream::connect_timeout(&address, TIMEOUT)
+        .map_err(|e| format!("Can't connect to {}: {}", server.host, e))?;
+    let _ = tcp.set_read_timeout(Some(TIMEOUT));
+    let _ = tcp.set_write_timeout(Some(TIMEOUT));
+    let mut stream = if server.port == SMTPS_PORT {
+        Stream::Tls(Box::new(tls(server.host, tcp)?))
+    } else {
+        Stream::Plain(tcp)
+    };
+
+    expect(&mut stream, 220)?;
+    let features = command(&mut stream, "EHLO meshmonitor.local", 250)?;
+    if let Stream::Plain(tcp) = &stream {
+        if features
+            .iter()
+            .any(|feature| feature.eq_ignore_ascii_case("STARTTLS"))
+        {
+            let tcp = tcp.try_clone().map_err(|e| e.to_string())?;
+            command(&mut stream, "STARTTLS", 220)?;
+            stream = Stream::Tls(Box::new(tls(server.host, tcp)?));
+            command(&mut stream, "EHLO meshmonitor.local", 250)?;
+        } else if server.login.is_some() {
+            return Err(format!(
+                "{} doesn't offer encryption, so MeshMonitor won't send it the password",
+                server.host
+            ));
+        }
+    }
+    if let Some((username, password)) = server.login {
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("\0{}\0{}", username, password));
+        command(&mut stream, &format!("AUTH PLAIN {}", credentials), 235)
+            .map_err(|_| format!("{} didn't accept the username and password", server.host))?;
+    }
+    command(&mut stream, &format!("MAIL FROM:<{}>", mail.from), 250)?;
+    for to in mail.to {
+        command(&mut stream, &format!("RCPT TO:<{}>", to), 250)?;
+    }
+    command(&mut stream, "DATA", 354)?;
+    let message = message(mail, &chrono::Local::now().to_rfc2822());
+    command(&mut stream, &format!("{}\r\n.", message), 250)?;
+    let _ = command(&mut stream, "QUIT", 221);
+    Ok(())
+}
+
+fn tls(host: &str, tcp: TcpStream) -> Result<StreamOwned<ClientConnection, TcpStream>, String> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    let name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+    let connection = ClientConnection::new(Arc::new(config), name).map_err(|e| e.to_string())?;
+    Ok(StreamOwned::new(connection, tcp))
+}
+
+/// Send `line` and expect reply `code`; the reply's lines without it.
+fn command(stream: &mut Stream, line: &str, code: u16) -> Result<Vec<String>, String> {
+    stream
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("Connection to the mail server failed: {}", e))?;
+    expect(stream, code)
+}
+
+fn expect(stream: &mut Stream, code: u16) -> Result<Vec<String>, String> {
+    let (got, lines) = reply(stream)?;
+    if got != code {
+        return Err(format!(
+            "The mail server answered {} {}",
+            got,
+            lines.join(" ")
+        ));
+    }
+    Ok(lines)
+}
+
+/// Read one reply, which may span several `250-` lines.
+fn reply(stream: &mut Stream) -> Result<(u16, Vec<String>), String> {
+    let mut lines = Vec::new();
+    loop {
+        let line = read_line(stream)?;
+        let code = line
+            .get(..3)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| format!("Unexpected answer from the mail server: {}", line))?;
+        lines.push(line.get(4..).unwrap_or_default().to_string());
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, lines));
+        }
+    }
+}
+
+fn read_line(stream: &mut Stream) -> Result<String, String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while !line.ends_with(b"\r\n") {
+        match stream.read(&mut byte) {
+            Ok(0) => return Err("The mail server closed the connection".to_string()),
+            Ok(_) => line.push(byte[0]),
+            Err(e) => return Err(format!("Connection to the mail server failed: {}", e)),
+        }
+    }
+    line.truncate(line.len() - 2);
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// An address that can't break out of `<...>` or a header.
+fn check_address(address: &str) -> Result<(), String> {
+    let valid = address.contains('@')
+        && !address
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ','));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Not an email address: {:?}", address))
+    }
+}
+
+/// The message as sent after `DATA`, without the final `.`. The body is
+/// base64, so no line of it can end the message early.
+fn message(mail: &Mail, date: &str) -> String {
+    let body = base64::engine::general_purpose::STANDARD.encode(mail.body);
+    let body: Vec<&str> = body
+        .as_bytes()
+        .chunks(76)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    format!(
+        "From: MeshMonitor <{from}>\r\n\
+         To: {to}\r\n\
+         Subject: {subject}\r\n\
+         Date: {date}\r\n\
+         Message-ID: <{id}@meshmonitor.local>\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\
+         Content-Transfer-Encoding: base64\r\n\
+         \r\n\
+         {body}",
+        from = mail.from,
+        to = mail.to.join(", "),
+        subject = encode_header(mail.subject),
+        date = date,
+        id = uuid::Uuid::new_v4(),
+        body = body.join("\r\n"),
+    )
+}
+
+/// `text` as a header value: as it is when it's plain ASCII, otherwise as
+/// an RFC 2047 encoded word. Line breaks become spaces either way.
+fn encode_header(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if text.is_ascii() {
+        text
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(text)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        let to = [
+            "ops@example.org".to_string(),
+            "chair@example.org".to_string(),
+        ];
+        let mail = Mail {
+            from: "alerts@example.org",
+            to: &to,
+            subject: "Emergency from Base\r\nBcc: evil@example.com",
+            body: "SOS\r\n.\r\nnot the end",
+        };
+        let message = message(&mail, "Wed, 14 Oct 2026 09:12:33 +0000");
+        assert!(message.starts_with("From: MeshMonitor <alerts@example.org>\r\n"));
+        assert!(message.contains("\r\nTo: ops@example.org, chair@example.org\r\n"));
+        assert!(message.contains("\r\nSubject: Emergency from Base  Bcc: evil@example.com\r\n"));
+        assert!(message.ends_with("\r\n\r\nU09TDQouDQpub3QgdGhlIGVuZA=="));
+        assert_eq!(encode_header("Café"), "=?UTF-8?B?Q2Fmw6k=?=");
+
+        assert!(check_address("ops@example.org").is_ok());
+        assert!(check_address("ops@example.org>\r\nRCPT TO:<x@y").is_err());
+    }
+}
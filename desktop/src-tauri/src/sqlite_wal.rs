@@ -0,0 +1,163 @@
+//! A look at the database's write-ahead log before the backend opens it.
+//! After a power cut or a yanked SD card the `-wal` and `-shm` files next to
+//! `meshmonitor.db` can be left in a state SQLite quietly works around by
+//! dropping the newest writes, or trips over with "database is locked". The
+//! WAL header and length are checked against the database's page size, and
+//! a `-shm` index left without a WAL is removed; what was found goes to
+//! `desktop.log`.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::port_release;
+
+/// Bytes in the WAL file header.
+const WAL_HEADER_LEN: u64 = 32;
+
+/// Bytes before each page in a WAL frame.
+const FRAME_HEADER_LEN: u64 = 24;
+
+/// The two WAL magic numbers, for big- and little-endian checksums.
+const WAL_MAGIC: [u32; 2] = [0x377f_0682, 0x377f_0683];
+
+/// Check the WAL files of `db_path`, tidying what's safe to, and describe
+/// anything worth knowing.
+pub fn check(db_path: &Path) -> Vec<String> {
+    let wal_path = sibling(db_path, "-wal");
+    let shm_path = sibling(db_path, "-shm");
+    let mut findings = Vec::new();
+
+    match std::fs::metadata(&wal_path) {
+        Ok(meta) if meta.len() > 0 => {
+            if !db_path.exists() {
+                findings.push(format!(
+                    "{} exists without the database; its changes can't be applied",
+                    wal_path.display()
+                ));
+            } else if let Some(problem) = inspect(
+                &read_start(&wal_path, WAL_HEADER_LEN as usize),
+                meta.len(),
+                db_page_size(db_path),
+            ) {
+                findings.push(format!("{}: {}", wal_path.display(), problem));
+            }
+        }
+        Ok(_) => {}
+        Err(_) if shm_path.exists() && !port_release::file_locked(&shm_path) => {
+            // The index only describes a WAL; without one it's stale
+            match std::fs::remove_file(&shm_path) {
+                Ok(()) => findings.push(format!(
+                    "Removed {}, left behind without a WAL",
+                    shm_path.display()
+                )),
+                Err(e) => findings.push(format!(
+                    "{} is left behind without a WAL and couldn't be removed: {}",
+                    shm_path.display(),
+                    e
+                )),
+            }
+        }
+        Err(_) => {}
+    }
+    findings
+}
+
+/// What's wrong with a WAL of `len` bytes starting with `header`, given the
+/// database's page size.
+fn inspect(header: &[u8], len: u64, db_page_size: Option<u32>) -> Option<String> {
+    if len < WAL_HEADER_LEN || header.len() < WAL_HEADER_LEN as usize {
+        return Some(format!(
+            "only {} bytes, too short for a WAL header; SQLite will ignore it",
+            len
+        ));
+    }
+    let word = |at: usize| {
+        u32::from_be_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    if !WAL_MAGIC.contains(&word(0)) {
+        return Some(
+            "the header is damaged; changes since the last checkpoint will be lost".to_string(),
+        );
+    }
+    let page_size = word(8);
+    if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+        return Some(format!(
+            "the header gives an invalid page size of {}",
+            page_size
+        ));
+    }
+    if let Some(db_page_size) = db_page_size.filter(|&size| size != page_size) {
+        return Some(format!(
+            "its page size of {} doesn't match the database's {}; SQLite will ignore it",
+            page_size, db_page_size
+        ));
+    }
+    let partial = (len - WAL_HEADER_LEN) % (page_size as u64 + FRAME_HEADER_LEN);
+    (partial != 0).then(|| {
+        format!(
+            "ends in a partial frame ({} bytes); the last interrupted write will be discarded",
+            partial
+        )
+    })
+}
+
+/// Page size from the database header, if it can be read.
+fn db_page_size(db_path: &Path) -> Option<u32> {
+    let header = read_start(db_path, 18);
+    if header.len() < 18 || !header.starts_with(b"SQLite format 3\0") {
+        return None;
+    }
+    // 1 stands for 65536, which doesn't fit the two bytes
+    match u16::from_be_bytes([header[16], header[17]]) {
+        1 => Some(65536),
+        size => Some(size as u32),
+    }
+}
+
+fn read_start(path: &Path, len: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(len);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(len as u64).read_to_end(&mut buf);
+    }
+    buf
+}
+
+fn sibling(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(db_path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(magic: u32, page_size: u32) -> Vec<u8> {
+        let mut header = vec![0; WAL_HEADER_LEN as usize];
+        header[..4].copy_from_slice(&magic.to_be_bytes());
+        header[4..8].copy_from_slice(&3_007_000u32.to_be_bytes());
+        header[8..12].copy_from_slice(&page_size.to_be_bytes());
+        header
+    }
+
+    #[test]
+    fn test_inspect_wal_header_and_length() {
+        let frame = 4096 + FRAME_HEADER_LEN;
+        let good = header(0x377f_0682, 4096);
+        assert_eq!(inspect(&good, WAL_HEADER_LEN + 3 * frame, Some(4096)), None);
+        assert!(inspect(&good, WAL_HEADER_LEN + frame + 100, Some(4096))
+            .unwrap()
+            .contains("partial frame (100 bytes)"));
+        assert!(inspect(&good, WAL_HEADER_LEN, Some(1024))
+            .unwrap()
+            .contains("doesn't match"));
+        assert!(inspect(&header(0xdead_beef, 4096), 1000, None)
+            .unwrap()
+            .contains("damaged"));
+        assert!(inspect(&good[..10], 10, None)
+            .unwrap()
+            .contains("too short"));
+    }
+}
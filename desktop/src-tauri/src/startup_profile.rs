@@ -0,0 +1,180 @@
+//! Timing of the cold start, to see where a slow launch (typically on a hard
+//! disk) spends its time. The startup path is marked with spans: loading the
+//! config, checking the bundled files and database, spawning Node.js, and
+//! the wait until the backend's health check first answers. Only the first
+//! backend start after launch is profiled. The report is written to the log
+//! once the backend is healthy and included in the SIGUSR1 status report.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::{api, log};
+
+/// How often the health check is tried while waiting for the backend.
+const HEALTH_POLL: Duration = Duration::from_millis(250);
+
+/// Longest wait for the backend to become healthy before the report is
+/// written without it.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(300);
+
+static LAUNCHED: OnceLock<Instant> = OnceLock::new();
+
+static REPORT: Mutex<Report> = Mutex::new(Report {
+    stages: Vec::new(),
+    healthy_after: None,
+    finished: false,
+});
+
+/// One timed step of the startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub name: &'static str,
+    /// When the step began, counted from launch.
+    pub began: Duration,
+    pub took: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub stages: Vec<Stage>,
+    /// Time from launch until the backend first answered its health check.
+    pub healthy_after: Option<Duration>,
+    finished: bool,
+}
+
+/// Times the step it was created for until it's dropped.
+pub struct Span {
+    name: &'static str,
+    began: Instant,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let mut report = REPORT.lock().unwrap();
+        if report.finished {
+            return;
+        }
+        report.stages.push(Stage {
+            name: self.name,
+            began: self.began.saturating_duration_since(launched()),
+            took: self.began.elapsed(),
+        });
+    }
+}
+
+/// Mark the launch; call first thing in `main`.
+pub fn begin() {
+    launched();
+}
+
+fn launched() -> Instant {
+    *LAUNCHED.get_or_init(Instant::now)
+}
+
+/// Start timing the step `name`.
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        began: Instant::now(),
+    }
+}
+
+/// After the first spawn, wait for the backend on `web_port` to answer and
+/// complete the report. Later spawns are ignored.
+pub fn await_healthy(web_port: u16) {
+    if REPORT.lock().unwrap().finished {
+        return;
+    }
+    std::thread::spawn(move || {
+        let span = span("time to healthy");
+        let waiting = Instant::now();
+        let healthy = loop {
+            if api::health(web_port).is_ok() {
+                break true;
+            }
+            if waiting.elapsed() >= HEALTH_TIMEOUT {
+                break false;
+            }
+            std::thread::sleep(HEALTH_POLL);
+        };
+        drop(span);
+        let mut report = REPORT.lock().unwrap();
+        if report.finished {
+            return;
+        }
+        if healthy {
+            report.healthy_after = Some(launched().elapsed());
+        }
+        report.finished = true;
+        log(&format!("Startup profile: {}", report.summary()));
+    });
+}
+
+/// The report so far.
+pub fn report() -> Report {
+    REPORT.lock().unwrap().clone()
+}
+
+impl Report {
+    /// One line: `config load 12 ms at 0.4 s, ..., healthy 9.1 s after launch`.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .stages
+            .iter()
+            .map(|stage| {
+                format!(
+                    "{} {} at {:.1} s",
+                    stage.name,
+                    duration(stage.took),
+                    stage.began.as_secs_f64()
+                )
+            })
+            .collect();
+        match (self.healthy_after, self.finished) {
+            (Some(after), _) => {
+                parts.push(format!("healthy {:.1} s after launch", after.as_secs_f64()))
+            }
+            (None, true) => parts.push("not healthy within 5 minutes".to_string()),
+            (None, false) => parts.push("not healthy yet".to_string()),
+        }
+        parts.join(", ")
+    }
+}
+
+/// `340 ms`, or `8.2 s` from a second.
+fn duration(took: Duration) -> String {
+    if took < Duration::from_secs(1) {
+        format!("{} ms", took.as_millis())
+    } else {
+        format!("{:.1} s", took.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let report = Report {
+            stages: vec![
+                Stage {
+                    name: "config load",
+                    began: Duration::from_millis(420),
+                    took: Duration::from_millis(12),
+                },
+                Stage {
+                    name: "time to healthy",
+                    began: Duration::from_millis(900),
+                    took: Duration::from_millis(8_230),
+                },
+            ],
+            healthy_after: Some(Duration::from_millis(9_130)),
+            finished: true,
+        };
+        assert_eq!(
+            report.summary(),
+            "config load 12 ms at 0.4 s, time to healthy 8.2 s at 0.9 s, healthy 9.1 s after launch"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Status strip overlaid on the web UI in native-window mode, showing
+//! backend state, mesh link state and the age of the last packet. It is
+//! drawn by the desktop, not the web UI, so it keeps updating when the page
+//! itself has frozen or lost its connection — telling a stuck UI apart from
+//! a dead backend.
+//!
+//! The strip is injected with the window's initialization script and
+//! updated from Rust with `eval`, which needs no IPC access for the web UI's
+//! (remote) origin.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::api::{self, BackendApi};
+use crate::config::Config;
+use crate::webview::WEB_UI_WINDOW;
+use crate::BackendState;
+
+/// How often the strip is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Creates the strip and `window.__meshmonitorStatusBar.update(status)`.
+pub const INIT_SCRIPT: &str = r#"
+(function () {
+  if (window.__meshmonitorStatusBar) return;
+  var colors = { ok: '#22c55e', warn: '#f59e0b', bad: '#ef4444', unknown: '#71717a' };
+  var bar, items = {};
+  function build() {
+    bar = document.createElement('div');
+    bar.setAttribute('role', 'status');
+    bar.setAttribute('aria-label', 'MeshMonitor Desktop status');
+    bar.style.cssText = 'position:fixed;right:8px;bottom:8px;z-index:2147483647;' +
+      'display:flex;gap:12px;padding:3px 10px;border-radius:6px;pointer-events:none;' +
+      'background:rgba(24,24,27,0.85);color:#e4e4e7;font:11px -apple-system,' +
+      'BlinkMacSystemFont,"Segoe UI",Roboto,sans-serif;';
+    ['backend', 'link', 'packet'].forEach(function (key) {
+      var item = document.createElement('span');
+      var dot = document.createElement('span');
+      dot.style.cssText = 'display:inline-block;width:7px;height:7px;border-radius:50%;margin-right:5px;';
+      var text = document.createElement('span');
+      item.appendChild(dot);
+      item.appendChild(text);
+      bar.appendChild(item);
+      items[key] = { dot: dot, text: text };
+    });
+    document.documentElement.appendChild(bar);
+  }
+  window.__meshmonitorStatusBar = {
+    update: function (status) {
+      if (!document.documentElement) return;
+      if (!bar || !bar.isConnected) build();
+      ['backend', 'link', 'packet'].forEach(function (key) {
+        items[key].dot.style.background = colors[status[key].level] || colors.unknown;
+        items[key].text.textContent = status[key].label;
+      });
+    }
+  };
+})();
+"#;
+
+/// Severity shown as the dot color.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Ok,
+    Warn,
+    Bad,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Item {
+    level: Level,
+    label: String,
+}
+
+impl Item {
+    fn new(level: Level, label: impl Into<String>) -> Self {
+        Item {
+            level,
+            label: label.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Status {
+    backend: Item,
+    link: Item,
+    packet: Item,
+}
+
+/// Guards against spawning a second refresh thread.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Spawn the refresh thread. It only does work while the web UI window
+/// exists. Safe to call more than once.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if let Some(window) = app.get_webview_window(WEB_UI_WINDOW) {
+            let status = current_status(&app);
+            if let Ok(json) = serde_json::to_string(&status) {
+                let _ = window.eval(format!(
+                    "window.__meshmonitorStatusBar && window.__meshmonitorStatusBar.update({})",
+                    json
+                ));
+            }
+        }
+        std::thread::sleep(REFRESH_INTERVAL);
+    });
+}
+
+fn current_status<R: Runtime>(app: &AppHandle<R>) -> Status {
+    let config = Config::load().unwrap_or_default();
+    let unknown = || Item::new(Level::Unknown, "");
+
+    let running = app.state::<BackendState>().is_running() || config.external_backend;
+    let healthy = running && api::health(config.web_port).is_ok();
+    let backend = match (running, healthy) {
+        (true, true) => Item::new(Level::Ok, "Backend running"),
+        (true, false) => Item::new(Level::Bad, "Backend not responding"),
+        (false, _) => Item::new(Level::Bad, "Backend stopped"),
+    };
+
+    // Link and packet state need the API token
+    let api = BackendApi::from_config(&config).filter(|_| healthy);
+    let Some(api) = api else {
+        let hint = if healthy { "No API token" } else { "" };
+        return Status {
+            backend,
+            link: Item::new(Level::Unknown, hint),
+            packet: unknown(),
+        };
+    };
+    let link = match api.status() {
+        Ok(status) if status.connected => Item::new(Level::Ok, "Node connected"),
+        Ok(_) => Item::new(Level::Bad, "Node disconnected"),
+        Err(_) => unknown(),
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let packet = match api.last_packet_ms() {
+        Ok(Some(at)) => {
+            let age = ((now_ms - at) / 1000).max(0);
+            let level = if age < 15 * 60 {
+                Level::Ok
+            } else {
+                Level::Warn
+            };
+            Item::new(level, format!("Last packet {}", format_age(age)))
+        }
+        Ok(None) => Item::new(Level::Warn, "No packets yet"),
+        Err(_) => unknown(),
+    };
+    Status {
+        backend,
+        link,
+        packet,
+    }
+}
+
+/// Short human-readable age, e.g. "12s ago", "5m ago", "3h ago".
+fn format_age(seconds: i64) -> String {
+    match seconds {
+        s if s < 60 => format!("{}s ago", s),
+        s if s < 3600 => format!("{}m ago", s / 60),
+        s if s < 86_400 => format!("{}h ago", s / 3600),
+        s => format!("{}d ago", s / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(0), "0s ago");
+        assert_eq!(format_age(59), "59s ago");
+        assert_eq!(format_age(60), "1m ago");
+        assert_eq!(format_age(7200), "2h ago");
+        assert_eq!(format_age(200_000), "2d ago");
+    }
+}
@@ -0,0 +1,214 @@
+//! A stand-in for the Node.js backend, for testing the supervisor against a
+//! real process. Built with the `test-support` feature. `FakeBackend` is a
+//! process that listens on the web port and answers the health check like
+//! the backend, scripted to start slowly, exit after a while, stop
+//! answering while it keeps the port, or find the port taken, which covers
+//! most of the lifecycle bugs users report.
+//!
+//! The process is the test binary itself, run again with the script in
+//! `FAKE_BACKEND_ENV`. It runs just the test named `fake_backend`, so each
+//! test file that spawns one needs that test, which only calls
+//! `fake_backend_main()`:
+//!
+//! ```ignore
+//! #[test]
+//! fn fake_backend() {
+//!     meshmonitor_desktop_lib::test_support::fake_backend_main();
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::api;
+use crate::config::Config;
+
+/// Holds the script in a fake backend's environment.
+pub const FAKE_BACKEND_ENV: &str = "MESHMONITOR_FAKE_BACKEND";
+
+/// How a fake backend behaves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Script {
+    pub port: u16,
+    /// Time before it listens, like migrations on a large database.
+    pub start_delay: Duration,
+    /// Exit with `exit_code` this long after listening.
+    pub crash_after: Option<Duration>,
+    pub exit_code: i32,
+    /// Stop answering this long after listening, keeping the port, like a
+    /// frozen event loop.
+    pub hang_after: Option<Duration>,
+}
+
+impl Script {
+    /// A backend on `port` that starts right away and keeps answering.
+    pub fn new(port: u16) -> Self {
+        Script {
+            port,
+            start_delay: Duration::ZERO,
+            crash_after: None,
+            exit_code: 1,
+            hang_after: None,
+        }
+    }
+}
+
+/// A running fake backend, killed when dropped.
+pub struct FakeBackend {
+    child: Child,
+    pub port: u16,
+}
+
+impl FakeBackend {
+    /// Spawn a fake backend following `script`.
+    pub fn spawn(script: &Script) -> Result<Self, String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let script_json = serde_json::to_string(script).map_err(|e| e.to_string())?;
+        let child = Command::new(exe)
+            .args(["fake_backend", "--exact", "--nocapture", "--test-threads=1"])
+            .env(FAKE_BACKEND_ENV, script_json)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to spawn fake backend: {}", e))?;
+        Ok(FakeBackend {
+            child,
+            port: script.port,
+        })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// The process, as `BackendState::process` would hold it.
+    pub fn child(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Wait up to `timeout` for the watchdog's health check to pass;
+    /// how long it took.
+    pub fn wait_healthy(&self, config: &Config, timeout: Duration) -> Result<Duration, String> {
+        let started = Instant::now();
+        loop {
+            match api::check_health(self.port, config) {
+                Ok(()) => return Ok(started.elapsed()),
+                Err(e) if started.elapsed() >= timeout => return Err(e),
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the process to exit.
+    pub fn wait_exit(&mut self, timeout: Duration) -> Option<ExitStatus> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return Some(status);
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        None
+    }
+
+    /// What it wrote to stderr, once it has exited.
+    pub fn stderr(&mut self) -> String {
+        let mut text = String::new();
+        if let Some(mut stderr) = self.child.stderr.take() {
+            let _ = stderr.read_to_string(&mut text);
+        }
+        text
+    }
+}
+
+impl Drop for FakeBackend {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Ask the OS for a free port for a fake backend.
+pub fn free_port() -> u16 {
+    TcpListener::bind(("0.0.0.0", 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|address| address.port())
+        .expect("no free port")
+}
+
+/// Run as a fake backend when spawned as one, never returning; otherwise
+/// return right away.
+pub fn fake_backend_main() {
+    let Ok(script_json) = std::env::var(FAKE_BACKEND_ENV) else {
+        return;
+    };
+    let script: Script = serde_json::from_str(&script_json).expect("invalid fake backend script");
+    std::thread::sleep(script.start_delay);
+    // Bound like Node's server, on every interface
+    let listener = match TcpListener::bind(("0.0.0.0", script.port)) {
+        Ok(listener) => listener,
+        Err(_) => {
+            eprintln!(
+                "Error: listen EADDRINUSE: address already in use :::{}",
+                script.port
+            );
+            std::process::exit(1);
+        }
+    };
+    let listening = Instant::now();
+    if let Some(after) = script.crash_after {
+        let exit_code = script.exit_code;
+        std::thread::spawn(move || {
+            std::thread::sleep(after);
+            eprintln!("Fake backend exiting with code {}", exit_code);
+            std::process::exit(exit_code);
+        });
+    }
+    for stream in listener.incoming() {
+        if script
+            .hang_after
+            .is_some_and(|after| listening.elapsed() >= after)
+        {
+            // Connections still queue on the port, but none is answered
+            loop {
+                std::thread::sleep(Duration::from_secs(60));
+            }
+        }
+        if let Ok(stream) = stream {
+            answer(stream);
+        }
+    }
+    std::process::exit(0);
+}
+
+/// Answer one request: the health path with an `ok` report, anything else
+/// with a 404.
+fn answer(mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = if path == "/api/health" {
+        ("200 OK", r#"{"status":"ok"}"#)
+    } else {
+        ("404 Not Found", r#"{"error":"Not found"}"#)
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
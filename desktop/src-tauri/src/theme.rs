@@ -0,0 +1,198 @@
+//! Following the OS light/dark appearance. The theme is read from the OS
+//! (the `AppsUseLightTheme` registry value on Windows, `AppleInterfaceStyle`
+//! on macOS, GNOME's `color-scheme` or the GTK theme name on Linux) and
+//! given to every window this app creates, so titlebars and the background
+//! shown before a page paints match it. While any window is open the OS is
+//! checked again every few seconds; on a change every window is updated and
+//! [`THEME_EVENT`] tells the pages, which restyle themselves.
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::window::Color;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Theme, WebviewWindow};
+
+use crate::log;
+
+/// Event carrying the new theme, `"light"` or `"dark"`.
+pub const THEME_EVENT: &str = "theme-changed";
+
+/// How often the OS theme is checked while a window is open.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The theme last read from the OS.
+static DARK: AtomicBool = AtomicBool::new(true);
+
+/// Guards against spawning a second watcher.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// The current theme.
+pub fn current() -> Theme {
+    if DARK.load(Ordering::SeqCst) {
+        Theme::Dark
+    } else {
+        Theme::Light
+    }
+}
+
+/// Name of `theme` as the pages know it.
+pub fn name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Light => "light",
+        _ => "dark",
+    }
+}
+
+/// Window background for `theme`, matching the pages' own.
+pub fn background(theme: Theme) -> Color {
+    match theme {
+        Theme::Light => Color(0xf4, 0xf4, 0xf5, 0xff),
+        _ => Color(0x1a, 0x1a, 0x2e, 0xff),
+    }
+}
+
+/// Give `window` the chrome for `theme`.
+pub fn apply<R: Runtime>(window: &WebviewWindow<R>, theme: Theme) {
+    let _ = window.set_theme(Some(theme));
+    let _ = window.set_background_color(Some(background(theme)));
+}
+
+/// Read the OS theme, then keep following it.
+pub fn start<R: Runtime>(app: &AppHandle<R>) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    DARK.store(platform::detect() == Theme::Dark, Ordering::SeqCst);
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        // Nothing to restyle
+        if app.webview_windows().is_empty() {
+            continue;
+        }
+        let theme = platform::detect();
+        if theme == current() {
+            continue;
+        }
+        DARK.store(theme == Theme::Dark, Ordering::SeqCst);
+        log(&format!("OS theme changed to {}", name(theme)));
+        for window in app.webview_windows().values() {
+            apply(window, theme);
+        }
+        let _ = app.emit(THEME_EVENT, name(theme));
+    });
+}
+
+/// Standard output of `program args`, if it ran and succeeded.
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let out = command.output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{output, Theme};
+
+    pub fn detect() -> Theme {
+        let color_scheme = output(
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "color-scheme"],
+        );
+        let gtk_theme = output(
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "gtk-theme"],
+        )
+        .or_else(|| std::env::var("GTK_THEME").ok());
+        theme_for(color_scheme.as_deref(), gtk_theme.as_deref())
+    }
+
+    /// GNOME 42+ has `color-scheme`; older desktops only a dark GTK theme,
+    /// named like `Adwaita-dark` (or `Adwaita:dark` in `GTK_THEME`).
+    pub(super) fn theme_for(color_scheme: Option<&str>, gtk_theme: Option<&str>) -> Theme {
+        if color_scheme.is_some_and(|scheme| scheme.contains("prefer-dark")) {
+            return Theme::Dark;
+        }
+        let dark_gtk_theme = gtk_theme.is_some_and(|name| {
+            let name = name.trim().trim_matches('\'').to_lowercase();
+            name.ends_with("-dark") || name.ends_with(":dark")
+        });
+        if dark_gtk_theme {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{output, Theme};
+
+    /// `AppleInterfaceStyle` only exists, as `Dark`, in dark mode.
+    pub fn detect() -> Theme {
+        match output("defaults", &["read", "-g", "AppleInterfaceStyle"]) {
+            Some(style) if style.trim() == "Dark" => Theme::Dark,
+            _ => Theme::Light,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{output, Theme};
+
+    const PERSONALIZE_KEY: &str =
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+
+    /// `AppsUseLightTheme` is `0x0` in dark mode; without it, apps are light.
+    pub fn detect() -> Theme {
+        let value = output(
+            "reg",
+            &["query", PERSONALIZE_KEY, "/v", "AppsUseLightTheme"],
+        );
+        match value {
+            Some(value) if value.split_whitespace().last() == Some("0x0") => Theme::Dark,
+            _ => Theme::Light,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::Theme;
+
+    pub fn detect() -> Theme {
+        Theme::Dark
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::platform::theme_for;
+    use super::*;
+
+    #[test]
+    fn test_reads_gnome_color_scheme_and_gtk_theme() {
+        assert_eq!(theme_for(Some("'prefer-dark'\n"), None), Theme::Dark);
+        assert_eq!(
+            theme_for(Some("'default'\n"), Some("'Adwaita'\n")),
+            Theme::Light
+        );
+        assert_eq!(
+            theme_for(Some("'default'\n"), Some("'Yaru-dark'\n")),
+            Theme::Dark
+        );
+        assert_eq!(theme_for(None, Some("Adwaita:dark")), Theme::Dark);
+        assert_eq!(theme_for(None, None), Theme::Light);
+    }
+}
@@ -0,0 +1,586 @@
+//! Caching proxy for map tiles on a loopback port, so the map keeps working
+//! without internet at a field event and a metered connection fetches each
+//! tile only once. A tile is requested as `/<tileset>/<z>/<x>/<y>.png`, where
+//! the tileset is one of the web UI's built-in ones (`osm`, `cartoDark`...);
+//! it is served from the cache folder if it's there, and otherwise fetched
+//! upstream and kept. When the folder outgrows `tile_cache_max_mb`, the
+//! tiles stored longest ago are removed first.
+//!
+//! `tile_servers` in config.json adds a club's own server or a paid provider
+//! alongside the built-ins. Its API key comes from the OS keyring and is
+//! filled in here, so it never reaches the web UI or the backend's database;
+//! the backend is only told the tile cache URL to show it under.
+
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::config::{self, Config, TileServer};
+use crate::proxy;
+use crate::{log, outbound};
+
+/// How long an upstream tile server gets to answer.
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How often the listener checks whether the cache was stopped.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Share of the size limit the cache is pruned down to.
+const PRUNE_TARGET: f64 = 0.9;
+
+/// A tileset the cache can fetch from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tileset {
+    pub id: &'static str,
+    /// Upstream URL with `{z}`, `{x}` and `{y}` placeholders.
+    url: &'static str,
+    pub max_zoom: u8,
+    content_type: &'static str,
+}
+
+/// The web UI's built-in tilesets.
+pub const TILESETS: &[Tileset] = &[
+    Tileset {
+        id: "osm",
+        url: "https://tile.openstreetmap.org/{z}/{x}/{y}.png",
+        max_zoom: 19,
+        content_type: "image/png",
+    },
+    Tileset {
+        id: "osmHot",
+        url: "https://a.tile.openstreetmap.fr/hot/{z}/{x}/{y}.png",
+        max_zoom: 19,
+        content_type: "image/png",
+    },
+    Tileset {
+        id: "cartoDark",
+        url: "https://a.basemaps.cartocdn.com/dark_all/{z}/{x}/{y}.png",
+        max_zoom: 19,
+        content_type: "image/png",
+    },
+    Tileset {
+        id: "cartoLight",
+        url: "https://a.basemaps.cartocdn.com/light_all/{z}/{x}/{y}.png",
+        max_zoom: 19,
+        content_type: "image/png",
+    },
+    Tileset {
+        id: "openTopo",
+        url: "https://a.tile.opentopomap.org/{z}/{x}/{y}.png",
+        max_zoom: 17,
+        content_type: "image/png",
+    },
+    Tileset {
+        id: "esriSatellite",
+        url: "https://server.arcgisonline.com/ArcGIS/rest/services/World_Imagery/MapServer/tile/{z}/{y}/{x}",
+        max_zoom: 19,
+        content_type: "image/jpeg",
+    },
+];
+
+/// One tile of a tileset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub tileset: &'static Tileset,
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Tile {
+    /// The tile, if `z`, `x` and `y` are within `tileset`.
+    pub fn new(tileset: &'static Tileset, z: u8, x: u32, y: u32) -> Option<Self> {
+        let side = 1u64 << z;
+        (z <= tileset.max_zoom && (x as u64) < side && (y as u64) < side).then_some(Self {
+            tileset,
+            z,
+            x,
+            y,
+        })
+    }
+
+    fn upstream_url(&self) -> String {
+        self.tileset
+            .url
+            .replace("{z}", &self.z.to_string())
+            .replace("{x}", &self.x.to_string())
+            .replace("{y}", &self.y.to_string())
+    }
+
+    pub(crate) fn cache_path(&self, cache_dir: &Path) -> PathBuf {
+        cache_dir
+            .join(self.tileset.id)
+            .join(self.z.to_string())
+            .join(self.x.to_string())
+            .join(self.y.to_string())
+    }
+}
+
+/// Highest zoom level the web UI accepts for a custom tileset.
+const MAX_CUSTOM_ZOOM: u8 = 22;
+
+/// Prefix of the backend's custom tileset ids for `tile_servers`; the web UI
+/// requires custom ids to start with `custom-`.
+const BACKEND_ID_PREFIX: &str = "custom-desktop-";
+
+/// Tilesets for `tile_servers`, with their API keys filled in. Tiles borrow
+/// their tileset for as long as the built-ins, so each is leaked; one is only
+/// leaked again when its server's settings change.
+static CUSTOM: Mutex<Vec<&'static Tileset>> = Mutex::new(Vec::new());
+
+/// The tileset called `id`.
+pub fn tileset(id: &str) -> Option<&'static Tileset> {
+    TILESETS
+        .iter()
+        .find(|tileset| tileset.id == id)
+        .or_else(|| {
+            CUSTOM
+                .lock()
+                .unwrap()
+                .iter()
+                .copied()
+                .find(|tileset| tileset.id == id)
+        })
+}
+
+/// Keyring account holding the API key of tile server `id`.
+pub fn key_account(id: &str) -> String {
+    format!("tile-server:{}", id)
+}
+
+/// Serve `servers` besides the built-in tilesets, with `{apikey}` replaced
+/// by `api_key(id)`. Servers that can't be used are left out and logged.
+pub fn set_servers(
+    servers: &[TileServer],
+    api_key: impl Fn(&str) -> Result<Option<String>, String>,
+) {
+    // Keys are looked up before taking the lock; the keyring can be slow
+    let mut resolved: Vec<(&TileServer, String)> = Vec::new();
+    for server in servers {
+        let url = if resolved.iter().any(|(other, _)| other.id == server.id) {
+            Err("another tile server has the same id".to_string())
+        } else {
+            server_url(server, &api_key)
+        };
+        match url {
+            Ok(url) => resolved.push((server, url)),
+            Err(e) => log(&format!("Tile server \"{}\" left out: {}", server.id, e)),
+        }
+    }
+
+    let mut custom = CUSTOM.lock().unwrap();
+    let previous = std::mem::take(&mut *custom);
+    *custom = resolved
+        .into_iter()
+        .map(|(server, url)| {
+            let unchanged = previous.iter().copied().find(|tileset| {
+                tileset.id == server.id && tileset.url == url && tileset.max_zoom == server.max_zoom
+            });
+            unchanged.unwrap_or_else(|| {
+                Box::leak(Box::new(Tileset {
+                    id: Box::leak(server.id.clone().into_boxed_str()),
+                    content_type: content_type(&url),
+                    url: Box::leak(url.into_boxed_str()),
+                    max_zoom: server.max_zoom,
+                }))
+            })
+        })
+        .collect();
+}
+
+/// Upstream URL of `server` with its API key filled in.
+fn server_url(
+    server: &TileServer,
+    api_key: impl Fn(&str) -> Result<Option<String>, String>,
+) -> Result<String, String> {
+    check_server(server)?;
+    let url = server.url.trim();
+    if !url.contains("{apikey}") {
+        return Ok(url.to_string());
+    }
+    let key = api_key(&server.id)?
+        .ok_or_else(|| "its URL has {apikey} but no API key is saved".to_string())?;
+    Ok(url.replace("{apikey}", key.trim()))
+}
+
+/// Why `server` can't be served, if it can't.
+fn check_server(server: &TileServer) -> Result<(), String> {
+    let id_ok = !server.id.is_empty()
+        && server
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !id_ok {
+        return Err("ids may only use letters, digits, `-` and `_`".to_string());
+    }
+    if TILESETS.iter().any(|tileset| tileset.id == server.id) {
+        return Err("that id belongs to a built-in tileset".to_string());
+    }
+    let url = server.url.trim();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("the URL must start with http:// or https://".to_string());
+    }
+    if !["{z}", "{x}", "{y}"].iter().all(|part| url.contains(part)) {
+        return Err("the URL needs {z}, {x} and {y}".to_string());
+    }
+    if server.max_zoom > MAX_CUSTOM_ZOOM {
+        return Err(format!("max_zoom can be at most {}", MAX_CUSTOM_ZOOM));
+    }
+    Ok(())
+}
+
+/// Content type of tiles from `url`, going by its extension.
+fn content_type(url: &str) -> &'static str {
+    let path = url.split('?').next().unwrap_or(url).to_ascii_lowercase();
+    if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+        "image/jpeg"
+    } else if path.ends_with(".webp") {
+        "image/webp"
+    } else if path.ends_with(".pbf") || path.ends_with(".mvt") {
+        "application/x-protobuf"
+    } else {
+        "image/png"
+    }
+}
+
+/// The web UI custom tilesets for `tile_servers`, pointing at the tile cache,
+/// for the backend's `DESKTOP_TILESETS`. Empty while the tile cache is off.
+pub fn backend_tilesets(config: &Config) -> serde_json::Value {
+    let Some(port) = config.tile_cache_port else {
+        return serde_json::json!([]);
+    };
+    config
+        .tile_servers
+        .iter()
+        .filter(|server| check_server(server).is_ok())
+        .map(|server| {
+            serde_json::json!({
+                "id": format!("{}{}", BACKEND_ID_PREFIX, server.id),
+                "name": server.name,
+                "url": format!("http://127.0.0.1:{}/{}/{{z}}/{{x}}/{{y}}.png", port, server.id),
+                "attribution": server.attribution,
+                "maxZoom": server.max_zoom.max(1),
+            })
+        })
+        .collect()
+}
+
+/// Bytes in the cache folder, as last counted plus what was stored since.
+static CACHED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Size limit of the cache folder in bytes.
+static MAX_BYTES: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Guards against pruning twice at once.
+static PRUNING: AtomicBool = AtomicBool::new(false);
+
+/// A running tile cache; dropping it stops listening.
+pub struct TileCache {
+    pub port: u16,
+    pub max_mb: u32,
+    stop: Arc<AtomicBool>,
+}
+
+impl TileCache {
+    /// Listen on `port` on loopback, keeping at most `max_mb` of tiles.
+    pub fn start(port: u16, max_mb: u32) -> Result<Self, String> {
+        let cache_dir = config::get_tile_cache_path()?;
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .map_err(|e| format!("Failed to open the tile cache port {}: {}", port, e))?;
+        log(&format!(
+            "Map tile cache listening on port {} ({} MB in {})",
+            port,
+            max_mb,
+            cache_dir.display()
+        ));
+        MAX_BYTES.store(max_mb as u64 * 1024 * 1024, Ordering::SeqCst);
+        // The folder is counted once, in the background, then kept up to date
+        prune_in_background(cache_dir.clone());
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            let agent = agent();
+            while !stopped.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((client, _)) => {
+                        let (agent, cache_dir) = (agent.clone(), cache_dir.clone());
+                        std::thread::spawn(move || handle(client, &agent, &cache_dir));
+                    }
+                    Err(_) => std::thread::sleep(POLL_INTERVAL),
+                }
+            }
+            log("Map tile cache stopped");
+        });
+        Ok(Self { port, max_mb, stop })
+    }
+}
+
+impl Drop for TileCache {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// HTTP client for upstream tile servers, which ask for an identifying
+/// User-Agent.
+pub(crate) fn agent() -> outbound::Client {
+    outbound::Client::new(
+        ureq::AgentBuilder::new()
+            .timeout(UPSTREAM_TIMEOUT)
+            .user_agent(&format!(
+                "MeshMonitor-Desktop/{} (tile cache)",
+                env!("CARGO_PKG_VERSION")
+            )),
+    )
+}
+
+/// `tile` from the cache, or fetched upstream and stored.
+pub(crate) fn get(
+    agent: &outbound::Client,
+    tile: &Tile,
+    cache_dir: &Path,
+) -> Result<Vec<u8>, String> {
+    let path = tile.cache_path(cache_dir);
+    if let Ok(bytes) = std::fs::read(&path) {
+        return Ok(bytes);
+    }
+    let response = agent
+        .get(&tile.upstream_url())?
+        .call()
+        .map_err(|e| format!("Failed to fetch tile {}: {}", tile.upstream_url(), e))?;
+    let mut bytes = Vec::new();
+    std::io::Read::read_to_end(&mut response.into_reader(), &mut bytes)
+        .map_err(|e| format!("Failed to fetch tile {}: {}", tile.upstream_url(), e))?;
+    store(&path, &bytes, cache_dir);
+    Ok(bytes)
+}
+
+/// Write a tile atomically, so a crash never leaves half a tile to serve.
+fn store(path: &Path, bytes: &[u8], cache_dir: &Path) {
+    let Some(dir) = path.parent() else {
+        return;
+    };
+    let partial = path.with_extension("partial");
+    let stored = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&partial, bytes))
+        .and_then(|()| std::fs::rename(&partial, path));
+    if stored.is_err() {
+        let _ = std::fs::remove_file(&partial);
+        return;
+    }
+    let total = CACHED_BYTES.fetch_add(bytes.len() as u64, Ordering::SeqCst) + bytes.len() as u64;
+    if total > MAX_BYTES.load(Ordering::SeqCst) {
+        prune_in_background(cache_dir.to_path_buf());
+    }
+}
+
+/// Count the cache and remove the oldest tiles while it's over the limit.
+fn prune_in_background(cache_dir: PathBuf) {
+    if PRUNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(move || {
+        let removed = prune(&cache_dir, MAX_BYTES.load(Ordering::SeqCst));
+        if removed > 0 {
+            log(&format!(
+                "Map tile cache full; removed {} old tiles",
+                removed
+            ));
+        }
+        PRUNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Remove the tiles stored longest ago until the folder is back under
+/// `PRUNE_TARGET` of `max_bytes`, if it's over `max_bytes`. Returns how many
+/// were removed.
+fn prune(cache_dir: &Path, max_bytes: u64) -> usize {
+    let mut files = Vec::new();
+    collect_files(cache_dir, &mut files);
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    let mut removed = 0;
+    if total > max_bytes {
+        files.sort_by_key(|(_, _, modified)| *modified);
+        let target = (max_bytes as f64 * PRUNE_TARGET) as u64;
+        for (path, size, _) in &files {
+            if total <= target {
+                break;
+            }
+            if std::fs::remove_file(path).is_ok() {
+                total -= size;
+                removed += 1;
+            }
+        }
+    }
+    CACHED_BYTES.store(total, Ordering::SeqCst);
+    removed
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            collect_files(&entry.path(), files);
+        } else {
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), meta.len(), modified));
+        }
+    }
+}
+
+/// The tile a request path such as `/osm/12/1205/1539.png` asks for.
+fn parse_path(path: &str) -> Option<Tile> {
+    let path = path.split('?').next()?;
+    let mut parts = path.trim_start_matches('/').split('/');
+    let tileset = tileset(parts.next()?)?;
+    let z = parts.next()?.parse().ok()?;
+    let x = parts.next()?.parse().ok()?;
+    let last = parts.next()?;
+    let y = last.split_once('.').map_or(last, |(y, _)| y).parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Tile::new(tileset, z, x, y)
+}
+
+/// Serve one client connection.
+fn handle(mut client: TcpStream, agent: &outbound::Client, cache_dir: &Path) {
+    let _ = client.set_nonblocking(false);
+    let _ = client.set_read_timeout(Some(UPSTREAM_TIMEOUT));
+    let Some((head, _)) = proxy::read_head(&mut client) else {
+        return proxy::respond(client, "400 Bad Request", "Bad request");
+    };
+    let mut request_line = head.split_whitespace();
+    if request_line.next() != Some("GET") {
+        return proxy::respond(client, "405 Method Not Allowed", "Only GET is supported");
+    }
+    let Some(tile) = request_line.next().and_then(parse_path) else {
+        return proxy::respond(client, "404 Not Found", "Unknown tileset or tile");
+    };
+    match get(agent, &tile, cache_dir) {
+        Ok(bytes) => {
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+                 Cache-Control: max-age=86400\r\nAccess-Control-Allow-Origin: *\r\n\
+                 Connection: close\r\n\r\n",
+                tile.tileset.content_type,
+                bytes.len()
+            );
+            let _ = client
+                .write_all(head.as_bytes())
+                .and_then(|()| client.write_all(&bytes));
+        }
+        Err(e) => proxy::respond(client, "502 Bad Gateway", &e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path_and_upstream_url() {
+        let tile = parse_path("/osm/12/1205/1539.png").unwrap();
+        assert_eq!(
+            (tile.tileset.id, tile.z, tile.x, tile.y),
+            ("osm", 12, 1205, 1539)
+        );
+        assert_eq!(
+            tile.upstream_url(),
+            "https://tile.openstreetmap.org/12/1205/1539.png"
+        );
+        let satellite = parse_path("/esriSatellite/3/1/2?v=1").unwrap();
+        assert!(satellite.upstream_url().ends_with("/tile/3/2/1"));
+
+        // Outside the tileset's zoom range or the zoom level's grid
+        assert!(parse_path("/openTopo/18/0/0.png").is_none());
+        assert!(parse_path("/osm/2/4/0.png").is_none());
+        assert!(parse_path("/unknown/1/0/0.png").is_none());
+        assert!(parse_path("/osm/1/0/0/extra").is_none());
+    }
+
+    #[test]
+    fn test_custom_servers_get_their_api_key() {
+        let server = |id: &str, url: &str| TileServer {
+            id: id.to_string(),
+            name: "Club tiles".to_string(),
+            url: url.to_string(),
+            max_zoom: 18,
+            attribution: String::new(),
+        };
+        let servers = [
+            server(
+                "club",
+                "https://tiles.example.org/{z}/{x}/{y}.jpg?key={apikey}",
+            ),
+            server(
+                "nokey",
+                "https://other.example.org/{z}/{x}/{y}.png?key={apikey}",
+            ),
+            server("osm", "https://tiles.example.org/{z}/{x}/{y}.png"),
+        ];
+        set_servers(&servers, |id| {
+            Ok((id == "club").then(|| "s3cret".to_string()))
+        });
+
+        let tile = parse_path("/club/3/1/2.png").unwrap();
+        assert_eq!(
+            tile.upstream_url(),
+            "https://tiles.example.org/3/1/2.jpg?key=s3cret"
+        );
+        assert_eq!(tile.tileset.content_type, "image/jpeg");
+        assert!(parse_path("/nokey/3/1/2.png").is_none());
+        assert_eq!(tileset("osm").unwrap().url, TILESETS[0].url);
+
+        let config = Config {
+            tile_cache_port: Some(8090),
+            tile_servers: servers.to_vec(),
+            ..Config::default()
+        };
+        let tilesets = backend_tilesets(&config);
+        let ids: Vec<&str> = tilesets
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tileset| tileset["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, ["custom-desktop-club", "custom-desktop-nokey"]);
+        assert_eq!(
+            tilesets[0]["url"],
+            "http://127.0.0.1:8090/club/{z}/{x}/{y}.png"
+        );
+    }
+
+    #[test]
+    fn test_prune_removes_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("meshmonitor-tiles-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("osm/1/0")).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        for (name, age) in [("0", old), ("1", SystemTime::now())] {
+            let path = dir.join("osm/1/0").join(name);
+            std::fs::write(&path, [0u8; 100]).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(age)
+                .unwrap();
+        }
+
+        assert_eq!(prune(&dir, 150), 1);
+        assert!(!dir.join("osm/1/0/0").exists());
+        assert!(dir.join("osm/1/0/1").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+//! Downloading every map tile of a region into the tile cache ahead of time,
+//! so the map works at a site with no internet. A region is a bounding box
+//! and a zoom range of one tileset; tiles already cached are skipped, and the
+//! rest are fetched one at a time to stay within the tile servers' usage
+//! policies. Progress shows on the taskbar or dock and in the tray tooltip.
+
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::progress::Progress;
+use crate::tile_cache::{self, Tile, Tileset};
+use crate::tray::{TRAY_ID, TRAY_TOOLTIP};
+use crate::{attention, config, locale, log, notifications, outbound};
+
+/// Event sent to the webviews after each tile.
+pub const PROGRESS_EVENT: &str = "map-download-progress";
+
+/// Most tiles one region may have. Each zoom level has four times the tiles
+/// of the one before, so regions grow quickly.
+const MAX_TILES: u64 = 20_000;
+
+/// Latitude the web map's square projection stops at.
+const MAX_LATITUDE: f64 = 85.051_128_78;
+
+/// Whether a download is running.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Set to stop the running download after its current tile.
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+/// A bounding box and zoom range of one tileset, in degrees.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Region {
+    pub tileset: String,
+    pub north: f64,
+    pub south: f64,
+    pub east: f64,
+    pub west: f64,
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+}
+
+/// Where a download has got to.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub done: u64,
+    pub total: u64,
+    /// Tiles that couldn't be fetched.
+    pub failed: u64,
+    pub finished: bool,
+}
+
+/// Tile columns and rows of one zoom level covering a region.
+type ZoomTiles = (u8, RangeInclusive<u32>, RangeInclusive<u32>);
+
+impl Region {
+    /// The tiles to fetch at each zoom level, checking the region is valid.
+    fn tiles(&self) -> Result<(&'static Tileset, Vec<ZoomTiles>), String> {
+        let tileset = tile_cache::tileset(&self.tileset)
+            .ok_or_else(|| format!("Unknown tileset \"{}\"", self.tileset))?;
+        if !(-90.0..=90.0).contains(&self.south) || !(-90.0..=90.0).contains(&self.north) {
+            return Err("Latitudes must be between -90 and 90".to_string());
+        }
+        if !(-180.0..=180.0).contains(&self.west) || !(-180.0..=180.0).contains(&self.east) {
+            return Err("Longitudes must be between -180 and 180".to_string());
+        }
+        if self.south >= self.north || self.west >= self.east {
+            return Err("North must be above south, and east right of west".to_string());
+        }
+        if self.min_zoom > self.max_zoom || self.max_zoom > tileset.max_zoom {
+            return Err(format!(
+                "Zoom levels for {} go from 0 to {}, lowest first",
+                tileset.id, tileset.max_zoom
+            ));
+        }
+        let zooms = (self.min_zoom..=self.max_zoom)
+            .map(|z| {
+                let (west, north) = tile_at(self.west, self.north, z);
+                let (east, south) = tile_at(self.east, self.south, z);
+                (z, west..=east, north..=south)
+            })
+            .collect();
+        Ok((tileset, zooms))
+    }
+}
+
+/// Where `lon`, `lat` is on zoom level `z`, in tiles: the whole part is the
+/// tile, the fraction the spot within it.
+pub(crate) fn tile_position(lon: f64, lat: f64, z: u8) -> (f64, f64) {
+    let side = (1u64 << z) as f64;
+    let lat = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+    let x = (lon + 180.0) / 360.0 * side;
+    let y = (1.0 - lat.tan().asinh() / std::f64::consts::PI) / 2.0 * side;
+    (x, y)
+}
+
+/// The tile at `lon`, `lat` on zoom level `z`.
+fn tile_at(lon: f64, lat: f64, z: u8) -> (u32, u32) {
+    let (x, y) = tile_position(lon, lat, z);
+    let last = (1u64 << z) as f64 - 1.0;
+    (x.clamp(0.0, last) as u32, y.clamp(0.0, last) as u32)
+}
+
+fn count(zooms: &[ZoomTiles]) -> u64 {
+    zooms
+        .iter()
+        .map(|(_, xs, ys)| xs.clone().count() as u64 * ys.clone().count() as u64)
+        .sum()
+}
+
+/// Start downloading `region` in the background, returning how many tiles
+/// it has.
+pub fn start<R: Runtime>(app: &AppHandle<R>, region: Region) -> Result<u64, String> {
+    let (tileset, zooms) = region.tiles()?;
+    let total = count(&zooms);
+    if total > MAX_TILES {
+        return Err(format!(
+            "That region has {} tiles, more than the {} one download may have. \
+             Choose a smaller area or fewer zoom levels.",
+            total, MAX_TILES
+        ));
+    }
+    if outbound::private() {
+        return Err(
+            "Privacy mode is on; turn it off in Settings to download map tiles".to_string(),
+        );
+    }
+    let cache_dir = config::get_tile_cache_path()?;
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A map download is already running".to_string());
+    }
+    CANCEL.store(false, Ordering::SeqCst);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let progress = Progress::start(&app, "Map download");
+        let agent = tile_cache::agent();
+        let mut status = DownloadProgress {
+            done: 0,
+            total,
+            failed: 0,
+            finished: false,
+        };
+        let tiles = zooms.into_iter().flat_map(|(z, xs, ys)| {
+            xs.flat_map(move |x| ys.clone().filter_map(move |y| Tile::new(tileset, z, x, y)))
+        });
+        for tile in tiles {
+            if CANCEL.load(Ordering::SeqCst) {
+                break;
+            }
+            let cached = tile.cache_path(&cache_dir).exists();
+            if !cached && tile_cache::get(&agent, &tile, &cache_dir).is_err() {
+                status.failed += 1;
+            }
+            status.done += 1;
+            progress.set(status.done, total);
+            show_in_tray(&app, status.done * 100 / total.max(1));
+            let _ = app.emit(PROGRESS_EVENT, &status);
+        }
+        status.finished = true;
+        let _ = app.emit(PROGRESS_EVENT, &status);
+        attention::refresh(&app);
+        finish(&app, tileset, &status);
+        RUNNING.store(false, Ordering::SeqCst);
+    });
+    Ok(total)
+}
+
+/// Stop the running download, if there is one.
+pub fn cancel() {
+    CANCEL.store(true, Ordering::SeqCst);
+}
+
+fn show_in_tray<R: Runtime>(app: &AppHandle<R>, percent: u64) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = format!("{} — Downloading map tiles ({}%)", TRAY_TOOLTIP, percent);
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+fn finish<R: Runtime>(app: &AppHandle<R>, tileset: &Tileset, status: &DownloadProgress) {
+    let count = |n| locale::current().count(n);
+    let summary = if status.done < status.total {
+        format!(
+            "Map download stopped after {} of {} {} tiles",
+            count(status.done),
+            count(status.total),
+            tileset.id
+        )
+    } else if status.failed > 0 {
+        format!(
+            "Map download finished; {} of {} {} tiles couldn't be fetched",
+            count(status.failed),
+            count(status.total),
+            tileset.id
+        )
+    } else {
+        format!(
+            "Map download finished; {} {} tiles are available offline",
+            count(status.total),
+            tileset.id
+        )
+    };
+    log(&summary);
+    notifications::notify(app, "MeshMonitor", &summary, None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(min_zoom: u8, max_zoom: u8) -> Region {
+        Region {
+            tileset: "osm".to_string(),
+            north: 51.6,
+            south: 51.4,
+            east: 0.1,
+            west: -0.3,
+            min_zoom,
+            max_zoom,
+        }
+    }
+
+    #[test]
+    fn test_tile_at() {
+        assert_eq!(tile_at(0.0, 0.0, 0), (0, 0));
+        assert_eq!(tile_at(0.0, 0.0, 1), (1, 1));
+        assert_eq!(tile_at(-180.0, 90.0, 2), (0, 0));
+        assert_eq!(tile_at(180.0, -90.0, 2), (3, 3));
+        // Central London at zoom 12
+        assert_eq!(tile_at(-0.1276, 51.5072, 12), (2046, 1362));
+    }
+
+    #[test]
+    fn test_region_tiles() {
+        let (tileset, zooms) = region(0, 2).tiles().unwrap();
+        assert_eq!(tileset.id, "osm");
+        // The region straddles the prime meridian, so two columns from zoom 1
+        assert_eq!(count(&zooms), 5);
+
+        let (_, zooms) = region(12, 12).tiles().unwrap();
+        assert_eq!(zooms, [(12, 2044..=2049, 1360..=1363)]);
+        assert_eq!(count(&zooms), 24);
+
+        assert!(region(3, 2).tiles().is_err());
+        assert!(region(0, 20).tiles().is_err());
+        let mut flipped = region(0, 2);
+        flipped.north = 51.0;
+        assert!(flipped.tiles().is_err());
+    }
+}
@@ -1,20 +1,167 @@
+use std::sync::Mutex;
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Manager, Runtime,
 };
 use tauri_plugin_opener::OpenerExt;
 
 use crate::config::Config;
+use crate::polling::{self, PollingPreset};
+use crate::{
+    attention, audio, audit, history, kiosk, locale, login_handoff, notifications, os_auth, poller,
+    remote_instances, watchdog, webview, BackendState,
+};
+
+/// Id of the app's single tray icon, for finding it again after setup.
+pub(crate) const TRAY_ID: &str = "main";
+
+/// Accessible name of the tray icon, announced by screen readers.
+pub(crate) const TRAY_TOOLTIP: &str = "MeshMonitor";
+
+/// Tray menu entries as (id, label), in menu order. Each label carries an `&`
+/// mnemonic so every entry can be reached from the keyboard once the menu is
+/// open; the menu backend renders it natively per platform and screen
+/// readers announce the label without it.
+pub(crate) const MENU_ENTRIES: &[(&str, &str)] = &[
+    ("open", "&Open MeshMonitor"),
+    ("settings", "&Settings..."),
+    ("logs", "Open &Logs Folder"),
+    ("search_logs", "Search Lo&gs..."),
+    ("activity_report", "Ac&tivity Report..."),
+    ("reveal_db", "Reveal &Database File"),
+    ("packet_audio", "Packet &Activity Sound"),
+    ("mute_channels", "&Mute Channels"),
+    ("firmware", "Update Node &Firmware..."),
+    ("wake_node", "&Wake Node's Computer"),
+    ("polling", "&Polling"),
+    ("remote_instances", "&Remote Instances"),
+    ("history", "&History"),
+    ("quit", "&Quit and Stop Backend"),
+    ("quit_keep_backend", "Quit, &Keep Backend Running"),
+];
+
+/// How often the node status entry is refreshed.
+const NODE_STATUS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How often the Remote Instances submenu is refreshed.
+const REMOTE_INSTANCES_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Menu ids of the Remote Instances entries start with this, followed by
+/// the instance's URL.
+const REMOTE_INSTANCE_PREFIX: &str = "remote_instance:";
+
+/// How often the Mute Channels submenu is refreshed.
+const CHANNELS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Menu ids of the Mute Channels entries start with this, followed by the
+/// channel index.
+const MUTE_CHANNEL_PREFIX: &str = "mute_channel:";
+
+/// How often the tray icon's status dot is brought up to date.
+const HEALTH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Status dot diameter as a fraction of the icon size.
+const HEALTH_DOT_FRACTION: f32 = 0.4;
+
+/// What the dot in the tray icon's bottom-right corner shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Health {
+    /// The backend answers its health check and is connected to the node.
+    Good,
+    /// The backend is starting, not answering, or has lost the node.
+    Degraded,
+    /// No backend is running.
+    Down,
+}
+
+impl Health {
+    /// RGB.
+    fn color(self) -> [u8; 3] {
+        match self {
+            Health::Good => [0x22, 0xc5, 0x5e],
+            Health::Degraded => [0xea, 0xb3, 0x08],
+            Health::Down => [0xef, 0x44, 0x44],
+        }
+    }
+}
+
+/// The dot shown; `None` for no dot, with an external backend, whose state
+/// is reported by `external` instead.
+static HEALTH: Mutex<Option<Health>> = Mutex::new(None);
+
+/// How often the History submenu is refreshed.
+const HISTORY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Events listed in the History submenu.
+const HISTORY_LENGTH: usize = 5;
+
+/// The Polling submenu's entries, one checked, kept so a pick can uncheck
+/// the others.
+struct PollingItems<R: Runtime>(Vec<(PollingPreset, CheckMenuItem<R>)>);
+
+/// Label for a menu entry from `MENU_ENTRIES`.
+fn label(id: &str) -> &'static str {
+    MENU_ENTRIES
+        .iter()
+        .find(|(entry_id, _)| *entry_id == id)
+        .map(|(_, label)| *label)
+        .unwrap_or_default()
+}
 
 /// Build and configure the system tray
 pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
     // Create menu items
-    let open_item = MenuItem::with_id(app, "open", "Open MeshMonitor", true, None::<&str>)?;
-    let settings_item = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
-    let logs_item = MenuItem::with_id(app, "logs", "View Logs", true, None::<&str>)?;
-    let data_item = MenuItem::with_id(app, "data", "Open Data Folder", true, None::<&str>)?;
-    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let open_item = MenuItem::with_id(app, "open", label("open"), true, None::<&str>)?;
+    let settings_item = MenuItem::with_id(app, "settings", label("settings"), true, None::<&str>)?;
+    let logs_item = MenuItem::with_id(app, "logs", label("logs"), true, None::<&str>)?;
+    let search_logs_item =
+        MenuItem::with_id(app, "search_logs", label("search_logs"), true, None::<&str>)?;
+    let activity_report_item = MenuItem::with_id(
+        app,
+        "activity_report",
+        label("activity_report"),
+        true,
+        None::<&str>,
+    )?;
+    let reveal_db_item =
+        MenuItem::with_id(app, "reveal_db", label("reveal_db"), true, None::<&str>)?;
+    let packet_audio_item = CheckMenuItem::with_id(
+        app,
+        "packet_audio",
+        label("packet_audio"),
+        true,
+        audio::is_enabled(),
+        None::<&str>,
+    )?;
+    let mute_menu = Submenu::with_id(app, "mute_channels", label("mute_channels"), true)?;
+    let firmware_item = MenuItem::with_id(app, "firmware", label("firmware"), true, None::<&str>)?;
+    let wake_item = MenuItem::with_id(app, "wake_node", label("wake_node"), true, None::<&str>)?;
+    let polling_menu = Submenu::with_id(app, "polling", label("polling"), true)?;
+    let current_preset = polling::current();
+    let mut polling_items = Vec::new();
+    for preset in PollingPreset::ALL {
+        let item = CheckMenuItem::with_id(
+            app,
+            preset.menu_id(),
+            preset.label(),
+            true,
+            preset == current_preset,
+            None::<&str>,
+        )?;
+        polling_menu.append(&item)?;
+        polling_items.push((preset, item));
+    }
+    app.manage(PollingItems(polling_items));
+    let history_menu = Submenu::with_id(app, "history", label("history"), true)?;
+    let quit_item = MenuItem::with_id(app, "quit", label("quit"), true, None::<&str>)?;
+    let quit_keep_backend_item = MenuItem::with_id(
+        app,
+        "quit_keep_backend",
+        label("quit_keep_backend"),
+        true,
+        None::<&str>,
+    )?;
 
     // Build menu
     let menu = Menu::with_items(
@@ -23,17 +170,26 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> Result<(), Box<dyn std::err
             &open_item,
             &settings_item,
             &logs_item,
-            &data_item,
+            &search_logs_item,
+            &activity_report_item,
+            &reveal_db_item,
+            &packet_audio_item,
+            &mute_menu,
+            &firmware_item,
+            &wake_item,
+            &polling_menu,
+            &history_menu,
             &quit_item,
+            &quit_keep_backend_item,
         ],
     )?;
 
     // Build tray icon
-    let _tray = TrayIconBuilder::new()
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(app.default_window_icon().unwrap().clone())
         .menu(&menu)
         .show_menu_on_left_click(false)
-        .tooltip("MeshMonitor")
+        .tooltip(TRAY_TOOLTIP)
         .on_menu_event(move |app, event| {
             handle_menu_event(app, event.id.as_ref());
         })
@@ -50,6 +206,309 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> Result<(), Box<dyn std::err
         })
         .build(app)?;
 
+    watch_health(app);
+    watch_node_status(app, menu.clone())?;
+    watch_remote_instances(app, menu)?;
+    watch_channels(app, mute_menu);
+    watch_history(app, history_menu);
+
+    Ok(())
+}
+
+/// Keep the tray icon's status dot in step with the backend's health and
+/// its link to the node.
+fn watch_health<R: Runtime>(app: &AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        let health = (!Config::load().unwrap_or_default().external_backend).then(|| {
+            let status = watchdog::status(&app.state::<BackendState>());
+            // The link a previous backend reported says nothing about this one
+            let link = poller::link()
+                .filter(|(pid, _)| Some(*pid) == status.pid)
+                .map(|(_, up)| up);
+            health_of(&status, link)
+        });
+        let previous = std::mem::replace(&mut *HEALTH.lock().unwrap(), health);
+        if previous != health {
+            attention::refresh_icon(&app);
+        }
+        std::thread::sleep(HEALTH_INTERVAL);
+    });
+}
+
+/// The dot for backend `status`, and whether it's connected to the node
+/// when that's known; it only is with an API token, which the poller needs.
+fn health_of(status: &watchdog::Status, link: Option<bool>) -> Health {
+    match (status.running, status.healthy, link) {
+        (false, _, _) => Health::Down,
+        (true, false, _) | (true, true, Some(false)) => Health::Degraded,
+        (true, true, _) => Health::Good,
+    }
+}
+
+/// The status dot currently shown, if any.
+pub(crate) fn health() -> Option<Health> {
+    *HEALTH.lock().unwrap()
+}
+
+/// Copy of an RGBA image with a filled `health` dot in the bottom-right
+/// corner.
+pub(crate) fn with_health_dot(rgba: &[u8], width: u32, height: u32, health: Health) -> Vec<u8> {
+    let mut out = rgba.to_vec();
+    let size = width.min(height) as f32;
+    let radius = size * HEALTH_DOT_FRACTION / 2.0;
+    let (cx, cy) = (width as f32 - radius, height as f32 - radius);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                out[i..i + 3].copy_from_slice(&health.color());
+                out[i + 3] = 0xff;
+            }
+        }
+    }
+    out
+}
+
+/// Keep a disabled entry at the top of the menu with the state of the
+/// Bluetooth or serial bridge, while one is configured.
+fn watch_node_status<R: Runtime>(
+    app: &AppHandle<R>,
+    menu: Menu<R>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let item = MenuItem::with_id(app, "node_status", "", false, None::<&str>)?;
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut shown: Option<String> = None;
+        loop {
+            let summary = app
+                .state::<BackendState>()
+                .bridge
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|bridge| bridge.status().summary());
+            if summary != shown {
+                match &summary {
+                    Some(text) => {
+                        let _ = item.set_text(text);
+                        if shown.is_none() {
+                            let _ = menu.insert(&item, 0);
+                        }
+                    }
+                    None => {
+                        let _ = menu.remove(&item);
+                    }
+                }
+                shown = summary;
+            }
+            std::thread::sleep(NODE_STATUS_INTERVAL);
+        }
+    });
+    Ok(())
+}
+
+/// Keep a Remote Instances submenu above History listing each configured
+/// instance with its state, while any are configured.
+fn watch_remote_instances<R: Runtime>(
+    app: &AppHandle<R>,
+    menu: Menu<R>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let submenu = Submenu::with_id(app, "remote_instances", label("remote_instances"), true)?;
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut shown: Vec<(String, String)> = Vec::new();
+        let mut items: Vec<MenuItem<R>> = Vec::new();
+        loop {
+            let entries: Vec<(String, String)> = remote_instances::statuses()
+                .into_iter()
+                .map(|(instance, status)| {
+                    (
+                        format!("{}{}", REMOTE_INSTANCE_PREFIX, instance.url),
+                        format!("{}: {}", instance.name, status.label()),
+                    )
+                })
+                .collect();
+            if entries != shown {
+                for item in items.drain(..) {
+                    let _ = submenu.remove(&item);
+                }
+                for (id, text) in &entries {
+                    if let Ok(item) = MenuItem::with_id(&app, id, text, true, None::<&str>) {
+                        let _ = submenu.append(&item);
+                        items.push(item);
+                    }
+                }
+                if shown.is_empty() && !entries.is_empty() {
+                    let position = menu
+                        .items()
+                        .ok()
+                        .and_then(|items| items.iter().position(|item| item.id() == "history"))
+                        .unwrap_or(0);
+                    let _ = menu.insert(&submenu, position);
+                } else if entries.is_empty() {
+                    let _ = menu.remove(&submenu);
+                }
+                shown = entries;
+            }
+            std::thread::sleep(REMOTE_INSTANCES_INTERVAL);
+        }
+    });
+    Ok(())
+}
+
+/// Keep the Mute Channels submenu listing the node's channels, each checked
+/// while muted in `Config::muted_channels`.
+fn watch_channels<R: Runtime>(app: &AppHandle<R>, submenu: Submenu<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut shown: Option<Vec<(i64, String, bool)>> = None;
+        let mut items: Vec<MenuItem<R>> = Vec::new();
+        let mut checks: Vec<CheckMenuItem<R>> = Vec::new();
+        loop {
+            let muted = Config::load().unwrap_or_default().muted_channels;
+            let entries: Vec<(i64, String, bool)> = crate::poller::channels()
+                .into_iter()
+                .map(|(index, name)| (index, name, muted.contains(&index)))
+                .collect();
+            if shown.as_ref() != Some(&entries) {
+                for item in items.drain(..) {
+                    let _ = submenu.remove(&item);
+                }
+                for item in checks.drain(..) {
+                    let _ = submenu.remove(&item);
+                }
+                if entries.is_empty() {
+                    if let Ok(item) =
+                        MenuItem::new(&app, "Channels appear once connected", false, None::<&str>)
+                    {
+                        let _ = submenu.append(&item);
+                        items.push(item);
+                    }
+                }
+                for (index, name, muted) in &entries {
+                    let id = format!("{}{}", MUTE_CHANNEL_PREFIX, index);
+                    if let Ok(item) =
+                        CheckMenuItem::with_id(&app, id, name, true, *muted, None::<&str>)
+                    {
+                        let _ = submenu.append(&item);
+                        checks.push(item);
+                    }
+                }
+                shown = Some(entries);
+            }
+            std::thread::sleep(CHANNELS_INTERVAL);
+        }
+    });
+}
+
+/// Keep the History submenu listing the latest crashes, hangs, restarts and
+/// firmware updates, so a bounce overnight shows without opening the logs.
+fn watch_history<R: Runtime>(app: &AppHandle<R>, submenu: Submenu<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut shown: Vec<String> = Vec::new();
+        let mut items: Vec<MenuItem<R>> = Vec::new();
+        loop {
+            let today = chrono::Local::now().date_naive();
+            let mut labels: Vec<String> = history::recent(HISTORY_LENGTH)
+                .iter()
+                .map(|entry| entry.label(locale::current(), today))
+                .collect();
+            if labels.is_empty() {
+                labels.push("No restarts recorded".to_string());
+            }
+            if labels != shown {
+                for item in items.drain(..) {
+                    let _ = submenu.remove(&item);
+                }
+                for text in &labels {
+                    if let Ok(item) = MenuItem::new(&app, text, false, None::<&str>) {
+                        let _ = submenu.append(&item);
+                        items.push(item);
+                    }
+                }
+                shown = labels;
+            }
+            std::thread::sleep(HISTORY_INTERVAL);
+        }
+    });
+}
+
+/// Whether the desktop can show a tray icon. On Linux that needs the
+/// AppIndicator library (loading the tray without it aborts) and a
+/// StatusNotifier host on the session bus, which stock GNOME lacks. Other
+/// platforms always have a tray.
+pub fn tray_host_available() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let library = [
+            "libayatana-appindicator3.so.1",
+            "libappindicator3.so.1",
+            "libayatana-appindicator3.so",
+            "libappindicator3.so",
+        ]
+        .iter()
+        // SAFETY: these are the libraries the tray backend itself loads;
+        // opening them early runs the same initializers it would.
+        .any(|name| unsafe { libloading::Library::new(name) }.is_ok());
+        if !library {
+            eprintln!("No AppIndicator library found; tray unavailable");
+            return false;
+        }
+        if status_notifier_host() == Some(false) {
+            eprintln!("No StatusNotifier host on the session bus; tray unavailable");
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether a StatusNotifierWatcher owns its name on the session bus, via
+/// whichever D-Bus CLI is installed. `None` when neither could answer.
+#[cfg(target_os = "linux")]
+fn status_notifier_host() -> Option<bool> {
+    const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+    let gdbus = std::process::Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.DBus",
+            "--object-path",
+            "/org/freedesktop/DBus",
+            "--method",
+            "org.freedesktop.DBus.NameHasOwner",
+            WATCHER,
+        ])
+        .output();
+    if let Some(out) = gdbus.ok().filter(|out| out.status.success()) {
+        return Some(String::from_utf8_lossy(&out.stdout).contains("true"));
+    }
+    let dbus_send = std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus.NameHasOwner",
+            &format!("string:{}", WATCHER),
+        ])
+        .output();
+    if let Some(out) = dbus_send.ok().filter(|out| out.status.success()) {
+        return Some(String::from_utf8_lossy(&out.stdout).contains("boolean true"));
+    }
+    None
+}
+
+/// Run the tray menu action `id` from another surface (the control window).
+pub fn run_action<R: Runtime>(app: &AppHandle<R>, id: &str) -> Result<(), String> {
+    if !MENU_ENTRIES.iter().any(|(entry_id, _)| *entry_id == id) {
+        return Err(format!("Unknown action: {}", id));
+    }
+    handle_menu_event(app, id);
     Ok(())
 }
 
@@ -63,13 +522,55 @@ fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, item_id: &str) {
             show_settings_window(app);
         }
         "logs" => {
-            open_logs_folder();
+            open_logs_folder(app);
+        }
+        "search_logs" => {
+            // Without native windows, the folder is the next best thing
+            if !webview::show_log_search_window(app) {
+                open_logs_folder(app);
+            }
+        }
+        "activity_report" => {
+            if !webview::show_activity_report_window(app) {
+                open_reports_folder(app);
+            }
+        }
+        "reveal_db" => {
+            reveal_database_file(app);
+        }
+        "packet_audio" => {
+            toggle_packet_audio();
+        }
+        "firmware" => {
+            crate::firmware::check(app);
         }
-        "data" => {
-            open_data_folder();
+        "wake_node" => {
+            crate::wake_on_lan::wake(app);
+        }
+        // Submenus; History's entries are informational
+        "history" | "polling" | "remote_instances" | "mute_channels" => {}
+        id if id.starts_with(REMOTE_INSTANCE_PREFIX) => {
+            let url = &id[REMOTE_INSTANCE_PREFIX.len()..];
+            if let Err(e) = app.opener().open_url(url, None::<&str>) {
+                eprintln!("Failed to open browser: {}", e);
+            }
+        }
+        id if id.starts_with(MUTE_CHANNEL_PREFIX) => {
+            if let Ok(channel) = id[MUTE_CHANNEL_PREFIX.len()..].parse() {
+                toggle_channel_mute(channel);
+            }
+        }
+        id if PollingPreset::from_menu_id(id).is_some() => {
+            if let Some(preset) = PollingPreset::from_menu_id(id) {
+                select_polling_preset(app, preset);
+            }
         }
         "quit" => {
-            app.exit(0);
+            quit_stopping_backend(app);
+        }
+        "quit_keep_backend" => {
+            audit::record(audit::Source::Tray, "quit", "backend left running");
+            crate::quit(app, true);
         }
         _ => {
             println!("Unknown menu item: {}", item_id);
@@ -77,88 +578,213 @@ fn handle_menu_event<R: Runtime>(app: &AppHandle<R>, item_id: &str) {
     }
 }
 
-/// Open the web UI in the default browser
+/// Open the web UI in the default browser, which also clears the unread-DM
+/// badge
 fn open_web_ui<R: Runtime>(app: &AppHandle<R>) {
+    attention::clear(app);
+    notifications::clear_groups();
+
     let config = Config::load().unwrap_or_default();
-    let url = format!("http://localhost:{}", config.web_port);
+    let url = login_handoff::url(&config, &format!("http://localhost:{}", config.web_port));
 
+    if config.open_in_window
+        && webview::show_web_ui(app, &url, &webview::web_ui_init_script(&config))
+    {
+        return;
+    }
     if let Err(e) = app.opener().open_url(&url, None::<&str>) {
         eprintln!("Failed to open browser: {}", e);
     }
 }
 
-/// Show the settings window
-fn show_settings_window<R: Runtime>(app: &AppHandle<R>) {
-    // Check if settings window already exists
-    if let Some(window) = app.get_webview_window("settings") {
-        let _ = window.show();
-        let _ = window.set_focus();
+/// Apply and persist `preset`. A check item toggles itself when clicked, so
+/// every entry's mark is set again, leaving only `preset` checked.
+fn select_polling_preset<R: Runtime>(app: &AppHandle<R>, preset: PollingPreset) {
+    if let Some(items) = app.try_state::<PollingItems<R>>() {
+        for (item_preset, item) in &items.0 {
+            let _ = item.set_checked(*item_preset == preset);
+        }
+    }
+    polling::set(app, preset);
+}
+
+/// Flip the packet-activity ticker and persist the choice. The check mark
+/// itself is toggled natively by the menu.
+fn toggle_packet_audio() {
+    let enabled = !audio::is_enabled();
+    audio::set_enabled(enabled);
+
+    let previous = Config::load().unwrap_or_default();
+    let mut config = previous.clone();
+    config.packet_audio_enabled = enabled;
+    match config.save() {
+        Ok(()) => audit::record_config_change(audit::Source::Tray, &previous, &config),
+        Err(e) => eprintln!("Failed to save packet audio setting: {}", e),
+    }
+}
+
+/// Mute or unmute notifications for `channel` and persist it. The check mark
+/// itself is toggled natively by the menu.
+fn toggle_channel_mute(channel: i64) {
+    let previous = Config::load().unwrap_or_default();
+    let mut config = previous.clone();
+    if config.muted_channels.contains(&channel) {
+        config.muted_channels.retain(|&muted| muted != channel);
     } else {
-        // Create new settings window
-        match tauri::WebviewWindowBuilder::new(
-            app,
-            "settings",
-            tauri::WebviewUrl::App("index.html".into()),
+        config.muted_channels.push(channel);
+        config.muted_channels.sort();
+    }
+    match config.save() {
+        Ok(()) => audit::record_config_change(audit::Source::Tray, &previous, &config),
+        Err(e) => eprintln!("Failed to save muted channels: {}", e),
+    }
+}
+
+/// Quit and stop the backend, first asking for confirmation if the user
+/// opted into it
+fn quit_stopping_backend<R: Runtime>(app: &AppHandle<R>) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    if !Config::load().unwrap_or_default().confirm_quit {
+        audit::record(audit::Source::Tray, "quit", "backend stopped");
+        crate::quit(app, false);
+        return;
+    }
+    let handle = app.clone();
+    app.dialog()
+        .message(
+            "Quitting stops the MeshMonitor server. Monitoring and logging will stop \
+             until MeshMonitor is started again.",
         )
-        .title("MeshMonitor Settings")
-        .inner_size(450.0, 400.0)
-        .resizable(false)
-        .center()
-        .build()
-        {
-            Ok(window) => {
-                let _ = window.show();
-            }
-            Err(e) => {
-                eprintln!("Failed to create settings window: {}", e);
+        .title("Quit MeshMonitor?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Quit".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show(move |confirmed| {
+            if confirmed {
+                audit::record(audit::Source::Tray, "quit", "backend stopped");
+                crate::quit(&handle, false);
             }
+        });
+}
+
+/// Show the settings window, or reveal config.json for editing when native
+/// windows are unavailable
+fn show_settings_window<R: Runtime>(app: &AppHandle<R>) {
+    if kiosk::refuse_settings(app) {
+        return;
+    }
+    let handle = app.clone();
+    os_auth::gate(app, "open MeshMonitor settings", move || {
+        if !webview::show_window(&handle, "settings", "MeshMonitor Settings") {
+            webview::reveal_config_file(&handle);
         }
+    });
+}
+
+/// Open Settings at the form field `field`, for pointing at the setting
+/// that fixes a problem.
+pub(crate) fn show_setting<R: Runtime>(app: &AppHandle<R>, field: &'static str) {
+    if kiosk::refuse_settings(app) {
+        return;
     }
+    let handle = app.clone();
+    os_auth::gate(app, "open MeshMonitor settings", move || {
+        if !webview::show_window_at(&handle, "settings", "MeshMonitor Settings", field) {
+            webview::reveal_config_file(&handle);
+        }
+    });
 }
 
-/// Open the logs folder in file explorer
-fn open_logs_folder() {
+/// Open the logs folder in the file manager with desktop.log selected
+fn open_logs_folder<R: Runtime>(app: &AppHandle<R>) {
     if let Ok(logs_path) = crate::config::get_logs_path() {
         // Ensure the logs directory exists
         let _ = std::fs::create_dir_all(&logs_path);
-
-        #[cfg(target_os = "windows")]
-        {
-            let _ = std::process::Command::new("explorer")
-                .arg(&logs_path)
-                .spawn();
-        }
-        #[cfg(target_os = "macos")]
-        {
-            let _ = std::process::Command::new("open").arg(&logs_path).spawn();
-        }
-        #[cfg(target_os = "linux")]
-        {
-            let _ = std::process::Command::new("xdg-open")
-                .arg(&logs_path)
-                .spawn();
-        }
+        reveal_in_folder(app, &logs_path.join("desktop.log"), &logs_path);
     }
 }
 
-/// Open the data folder in file explorer
-fn open_data_folder() {
+/// Open the folder of saved activity reports, with the newest selected
+fn open_reports_folder<R: Runtime>(app: &AppHandle<R>) {
     if let Ok(data_path) = crate::config::get_data_path() {
-        #[cfg(target_os = "windows")]
-        {
-            let _ = std::process::Command::new("explorer")
-                .arg(data_path)
-                .spawn();
-        }
-        #[cfg(target_os = "macos")]
-        {
-            let _ = std::process::Command::new("open").arg(data_path).spawn();
-        }
-        #[cfg(target_os = "linux")]
-        {
-            let _ = std::process::Command::new("xdg-open")
-                .arg(data_path)
-                .spawn();
-        }
+        let reports = data_path.join("reports");
+        let _ = std::fs::create_dir_all(&reports);
+        let newest = std::fs::read_dir(&reports)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .max()
+            .unwrap_or_default();
+        reveal_in_folder(app, &newest, &reports);
+    }
+}
+
+/// Open the data folder in the file manager with meshmonitor.db selected,
+/// which is what support usually needs users to find
+fn reveal_database_file<R: Runtime>(app: &AppHandle<R>) {
+    if let (Ok(db_path), Ok(data_path)) = (
+        crate::config::get_database_path(),
+        crate::config::get_data_path(),
+    ) {
+        reveal_in_folder(app, &db_path, &data_path);
+    }
+}
+
+/// Select `file` in the platform file manager (Explorer, Finder, or the
+/// FileManager1 D-Bus interface on Linux). Falls back to opening `folder`
+/// when the file doesn't exist yet or the file manager can't select items.
+fn reveal_in_folder<R: Runtime>(
+    app: &AppHandle<R>,
+    file: &std::path::Path,
+    folder: &std::path::Path,
+) {
+    if file.exists() && app.opener().reveal_item_in_dir(file).is_ok() {
+        return;
+    }
+    if let Err(e) = app
+        .opener()
+        .open_path(folder.to_string_lossy(), None::<&str>)
+    {
+        eprintln!("Failed to open {}: {}", folder.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health() {
+        let status = |running, healthy| watchdog::Status {
+            running,
+            healthy,
+            pid: running.then_some(42),
+            uptime: None,
+            last_error: None,
+        };
+        assert_eq!(health_of(&status(true, true), Some(true)), Health::Good);
+        assert_eq!(health_of(&status(true, true), None), Health::Good);
+        assert_eq!(
+            health_of(&status(true, true), Some(false)),
+            Health::Degraded
+        );
+        assert_eq!(
+            health_of(&status(true, false), Some(true)),
+            Health::Degraded
+        );
+        assert_eq!(health_of(&status(false, false), None), Health::Down);
+
+        let (w, h) = (16, 16);
+        let dotted = with_health_dot(&vec![0; (w * h * 4) as usize], w, h, Health::Down);
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * w + x) * 4) as usize;
+            &dotted[i..i + 4]
+        };
+        assert_eq!(pixel(w - 2, h - 2), &[0xef, 0x44, 0x44, 0xff]);
+        assert_eq!(pixel(w - 2, 1), &[0, 0, 0, 0]);
     }
 }
@@ -0,0 +1,190 @@
+//! Spoken announcements of incoming messages via the OS text-to-speech
+//! engine, for users who can't watch the screen.
+//!
+//! Rather than linking a speech library, this drives the engine every
+//! platform already ships: `say` on macOS, System.Speech through PowerShell on
+//! Windows, and speech-dispatcher (`spd-say`) or eSpeak on Linux.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+
+use crate::api::ApiMessage;
+use crate::config::Config;
+use crate::log;
+
+/// Longest message text read aloud; anything beyond is cut with "and more".
+const MAX_SPOKEN_CHARS: usize = 280;
+
+/// Queue feeding the single speaker thread, so announcements never overlap.
+static SPEAKER: OnceLock<Mutex<Sender<String>>> = OnceLock::new();
+
+/// Whether `message` should be announced under `config` at local time `now`.
+/// Direct messages to the local node are announced; channel messages only
+/// for channels listed in `tts_channels`. Nothing is spoken during quiet
+/// hours or for messages the local node sent itself.
+pub fn should_announce(
+    config: &Config,
+    message: &ApiMessage,
+    local_node_id: Option<&str>,
+    now: chrono::NaiveTime,
+) -> bool {
+    if !config.tts_enabled || config.in_quiet_hours(now) || message.text.trim().is_empty() {
+        return false;
+    }
+    if local_node_id == Some(message.from_node_id.as_str()) {
+        return false;
+    }
+    if message.is_direct() {
+        return message.is_incoming_dm(local_node_id);
+    }
+    config.tts_channels.contains(&message.channel)
+}
+
+/// Sentence spoken for a message from `sender`.
+pub fn announcement(sender: &str, message: &ApiMessage) -> String {
+    let mut text: String = message.text.trim().chars().take(MAX_SPOKEN_CHARS).collect();
+    if message.text.trim().chars().count() > MAX_SPOKEN_CHARS {
+        text.push_str(", and more");
+    }
+    if message.is_direct() {
+        format!("Direct message from {}: {}", sender, text)
+    } else {
+        format!(
+            "Message on channel {} from {}: {}",
+            message.channel, sender, text
+        )
+    }
+}
+
+/// Queue `text` to be spoken. Returns immediately.
+pub fn speak(text: String) {
+    let sender = SPEAKER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<String>();
+        std::thread::spawn(move || {
+            for text in rx {
+                if let Err(e) = speak_blocking(&text) {
+                    log(&format!("Text-to-speech failed: {}", e));
+                }
+            }
+        });
+        Mutex::new(tx)
+    });
+    let _ = sender.lock().unwrap().send(text);
+}
+
+/// Speak `text` and wait for the engine to finish. The text is always passed
+/// on stdin or after `--` so message content can never be read as options.
+fn speak_blocking(text: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        run_with_stdin(Command::new("say").arg("-f").arg("-"), text)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let mut cmd = Command::new("powershell");
+        cmd.args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Add-Type -AssemblyName System.Speech; \
+             (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak([Console]::In.ReadToEnd())",
+        ])
+        .creation_flags(CREATE_NO_WINDOW);
+        run_with_stdin(&mut cmd, text)
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let spd = Command::new("spd-say")
+            .args(["--wait", "--", text])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match spd {
+            Ok(status) if status.success() => Ok(()),
+            _ => run_with_stdin(Command::new("espeak-ng").arg("--stdin"), text)
+                .or_else(|_| run_with_stdin(Command::new("espeak").arg("--stdin"), text)),
+        }
+    }
+}
+
+fn run_with_stdin(cmd: &mut Command, text: &str) -> Result<(), String> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start speech engine: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Speech engine failed: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Speech engine exited with {}", status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(from: &str, to: &str, channel: i64, text: &str) -> ApiMessage {
+        ApiMessage {
+            id: "1".to_string(),
+            from_node_id: from.to_string(),
+            to_node_id: to.to_string(),
+            text: text.to_string(),
+            channel,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_should_announce_dms_and_opted_in_channels() {
+        let noon = chrono::NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+        let local = Some("!local");
+        let mut config = Config::default();
+        let dm = message("!peer", "!local", -1, "hello");
+        assert!(!should_announce(&config, &dm, local, noon));
+
+        config.tts_enabled = true;
+        assert!(should_announce(&config, &dm, local, noon));
+        // Our own outgoing DM and DMs between other nodes stay silent
+        assert!(!should_announce(
+            &config,
+            &message("!local", "!peer", -1, "hi"),
+            local,
+            noon
+        ));
+        assert!(!should_announce(
+            &config,
+            &message("!a", "!b", -1, "hi"),
+            local,
+            noon
+        ));
+
+        let chan = message("!peer", "!ffffffff", 2, "net check-in");
+        assert!(!should_announce(&config, &chan, local, noon));
+        config.tts_channels = vec![2];
+        assert!(should_announce(&config, &chan, local, noon));
+
+        config.quiet_hours_start = Some("11:00".to_string());
+        config.quiet_hours_end = Some("13:00".to_string());
+        assert!(!should_announce(&config, &dm, local, noon));
+    }
+
+    #[test]
+    fn test_announcement_truncates_long_text() {
+        let long = "x".repeat(MAX_SPOKEN_CHARS + 20);
+        let spoken = announcement("Base Camp", &message("!a", "!b", -1, &long));
+        assert!(spoken.starts_with("Direct message from Base Camp: "));
+        assert!(spoken.ends_with(", and more"));
+    }
+}
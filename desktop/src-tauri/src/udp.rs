@@ -0,0 +1,158 @@
+//! Receive-only link to the mesh traffic nodes multicast on the LAN when
+//! their UDP option is on, served to the backend through a
+//! [`bridge`](crate::bridge). Each datagram is one `MeshPacket`, still
+//! encrypted with its channel key, so the backend decrypts it with the keys
+//! in its channel database as it does for MQTT.
+//!
+//! There is no node on the other end, so the configuration handshake is
+//! answered here and nothing the backend sends is transmitted.
+
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::Duration;
+
+use crate::bridge::Link;
+
+/// Multicast group and port Meshtastic firmware sends to.
+const GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 69);
+const PORT: u16 = 4403;
+
+/// `ToRadio.want_config_id`
+const TO_RADIO_WANT_CONFIG_ID: u64 = 3;
+/// `FromRadio.packet`
+const FROM_RADIO_PACKET: u64 = 2;
+/// `FromRadio.config_complete_id`
+const FROM_RADIO_CONFIG_COMPLETE_ID: u64 = 7;
+
+struct UdpLink {
+    socket: UdpSocket,
+    /// Replies made up here, handed out before any mesh traffic.
+    replies: VecDeque<Vec<u8>>,
+}
+
+impl Link for UdpLink {
+    fn send(&mut self, packet: &[u8]) -> Result<(), String> {
+        if let Some(id) = want_config_id(packet) {
+            self.replies.push_back(config_complete(id));
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, String> {
+        if let Some(reply) = self.replies.pop_front() {
+            return Ok(Some(reply));
+        }
+        let _ = self.socket.set_read_timeout(Some(timeout));
+        let mut buf = [0u8; 1024];
+        match self.socket.recv_from(&mut buf) {
+            Ok((0, _)) => Ok(None),
+            Ok((read, _)) => Ok(Some(field(FROM_RADIO_PACKET, &buf[..read]))),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(format!("Receiving LAN mesh traffic failed: {}", e)),
+        }
+    }
+}
+
+/// Join the Meshtastic multicast group. Returns the link and the group for
+/// status messages.
+pub fn open() -> Result<(Box<dyn Link>, String), String> {
+    let group = format!("{}:{}", GROUP, PORT);
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, PORT))
+        .map_err(|e| format!("Couldn't listen on UDP port {}: {}", PORT, e))?;
+    socket
+        .join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)
+        .map_err(|e| format!("Couldn't join {}: {}", group, e))?;
+    let link = UdpLink {
+        socket,
+        replies: VecDeque::new(),
+    };
+    Ok((Box::new(link), group))
+}
+
+/// The `want_config_id` of a `ToRadio` packet, if that's what it is.
+fn want_config_id(packet: &[u8]) -> Option<u32> {
+    let mut rest = packet;
+    while !rest.is_empty() {
+        let key = read_varint(&mut rest)?;
+        let value_len = match key & 7 {
+            0 => {
+                let value = read_varint(&mut rest)?;
+                if key >> 3 == TO_RADIO_WANT_CONFIG_ID {
+                    return Some(value as u32);
+                }
+                0
+            }
+            1 => 8,
+            2 => read_varint(&mut rest)? as usize,
+            5 => 4,
+            _ => return None,
+        };
+        rest = rest.get(value_len..)?;
+    }
+    None
+}
+
+/// `FromRadio` telling the backend its configuration request is done.
+fn config_complete(id: u32) -> Vec<u8> {
+    let mut packet = Vec::new();
+    write_varint(&mut packet, FROM_RADIO_CONFIG_COMPLETE_ID << 3);
+    write_varint(&mut packet, u64::from(id));
+    packet
+}
+
+/// A length-delimited protobuf field.
+fn field(number: u64, value: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(value.len() + 4);
+    write_varint(&mut encoded, (number << 3) | 2);
+    write_varint(&mut encoded, value.len() as u64);
+    encoded.extend_from_slice(value);
+    encoded
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answers_want_config_only() {
+        // ToRadio { want_config_id: 300 }
+        let want_config = [0x18, 0xAC, 0x02];
+        assert_eq!(want_config_id(&want_config), Some(300));
+        assert_eq!(config_complete(300), vec![0x38, 0xAC, 0x02]);
+
+        // ToRadio { packet: { .. } } and ToRadio { disconnect: true }
+        assert_eq!(want_config_id(&field(1, &[0x0D, 1, 2, 3, 4])), None);
+        assert_eq!(want_config_id(&[0x20, 0x01]), None);
+        assert_eq!(want_config_id(&[0x18]), None);
+    }
+
+    #[test]
+    fn test_wraps_datagrams_as_from_radio_packets() {
+        let datagram = vec![7u8; 200];
+        let wrapped = field(FROM_RADIO_PACKET, &datagram);
+        assert_eq!(&wrapped[..3], &[0x12, 0xC8, 0x01]);
+        assert_eq!(&wrapped[3..], &datagram[..]);
+    }
+}
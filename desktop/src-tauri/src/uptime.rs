@@ -0,0 +1,264 @@
+//! Reliability history: supervisor events (backend start/stop/crash/hang,
+//! mesh link up/down) appended to `uptime.jsonl` in the data directory, and
+//! a per-day availability report built from them for export.
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config;
+
+/// Something the supervisor observed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// The desktop app launched. Nothing is known to be up until the next
+    /// event says so, which closes any interval left open by a crash of the
+    /// app itself.
+    DesktopStarted,
+    BackendStarted,
+    /// A backend left running by an earlier run was adopted.
+    BackendAdopted,
+    BackendStopped,
+    BackendCrashed,
+    BackendHung,
+    LinkUp,
+    LinkDown,
+}
+
+impl EventKind {
+    /// Whether the backend is up after this event (`None`: unchanged).
+    fn backend_up(self) -> Option<bool> {
+        match self {
+            EventKind::BackendStarted | EventKind::BackendAdopted => Some(true),
+            EventKind::DesktopStarted
+            | EventKind::BackendStopped
+            | EventKind::BackendCrashed
+            | EventKind::BackendHung => Some(false),
+            EventKind::LinkUp | EventKind::LinkDown => None,
+        }
+    }
+
+    /// Whether the mesh link is up after this event (`None`: unchanged). The
+    /// link can't outlive the backend.
+    fn link_up(self) -> Option<bool> {
+        match self {
+            EventKind::LinkUp => Some(true),
+            EventKind::LinkDown => Some(false),
+            kind => kind.backend_up().filter(|up| !up),
+        }
+    }
+}
+
+/// One recorded event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Unix milliseconds.
+    pub at: i64,
+    pub kind: EventKind,
+    #[serde(default)]
+    pub detail: String,
+}
+
+/// Availability for one local calendar day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayReport {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    /// Share of the day the backend was running, 0–100.
+    pub backend_percent: f64,
+    /// Share of the day the mesh link was connected, 0–100.
+    pub link_percent: f64,
+    pub crashes: usize,
+    pub hangs: usize,
+}
+
+fn history_path() -> Result<PathBuf, String> {
+    Ok(config::get_data_path()?.join("uptime.jsonl"))
+}
+
+/// Append an event, stamped now. Errors only go to stderr.
+pub fn record(kind: EventKind, detail: &str) {
+    let event = Event {
+        at: chrono::Utc::now().timestamp_millis(),
+        kind,
+        detail: detail.to_string(),
+    };
+    let result = history_path().and_then(|path| {
+        let line = serde_json::to_string(&event)
+            .map_err(|e| format!("Failed to serialize uptime event: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open uptime history: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write uptime history: {}", e))
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+/// All recorded events, oldest first. Unparseable lines are skipped.
+pub fn load() -> Vec<Event> {
+    let Some(content) = history_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+    else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Intervals `[start, end)` during which `state` was true, closing an
+/// interval still open at `now`.
+fn up_intervals(
+    events: &[Event],
+    state: fn(EventKind) -> Option<bool>,
+    now: i64,
+) -> Vec<(i64, i64)> {
+    let mut intervals = Vec::new();
+    let mut up_since: Option<i64> = None;
+    for event in events {
+        match (state(event.kind), up_since) {
+            (Some(true), None) => up_since = Some(event.at),
+            (Some(false), Some(since)) => {
+                intervals.push((since, event.at));
+                up_since = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(since) = up_since {
+        intervals.push((since, now));
+    }
+    intervals
+}
+
+/// Percentage of `[start, end)` covered by `intervals`.
+fn coverage(intervals: &[(i64, i64)], start: i64, end: i64) -> f64 {
+    let covered: i64 = intervals
+        .iter()
+        .map(|&(from, to)| (to.min(end) - from.max(start)).max(0))
+        .sum();
+    if end <= start {
+        0.0
+    } else {
+        covered as f64 * 100.0 / (end - start) as f64
+    }
+}
+
+/// Per-day availability over the last `days` local days, today included
+/// (up to `now`).
+pub fn daily_report(events: &[Event], days: u32, now: chrono::DateTime<Local>) -> Vec<DayReport> {
+    let now_ms = now.timestamp_millis();
+    let backend = up_intervals(events, EventKind::backend_up, now_ms);
+    let link = up_intervals(events, EventKind::link_up, now_ms);
+
+    let today = now.date_naive();
+    (0..days)
+        .rev()
+        .filter_map(|back| {
+            let date = today - ChronoDuration::days(back as i64);
+            let day_start = |date: NaiveDate| {
+                Local
+                    .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+                    .earliest()
+                    .map(|t| t.timestamp_millis())
+            };
+            let start = day_start(date)?;
+            let end = day_start(date + ChronoDuration::days(1))?.min(now_ms);
+            let count = |kind| {
+                events
+                    .iter()
+                    .filter(|e| e.kind == kind && e.at >= start && e.at < end)
+                    .count()
+            };
+            Some(DayReport {
+                date: date.to_string(),
+                backend_percent: coverage(&backend, start, end),
+                link_percent: coverage(&link, start, end),
+                crashes: count(EventKind::BackendCrashed),
+                hangs: count(EventKind::BackendHung),
+            })
+        })
+        .collect()
+}
+
+/// Render the report as CSV (one row per day) or JSON (days plus the raw
+/// events in the period).
+pub fn export_report(format: &str, days: u32) -> Result<String, String> {
+    let events = load();
+    let now = Local::now();
+    let report = daily_report(&events, days, now);
+    match format {
+        "csv" => {
+            let mut csv =
+                String::from("date,backend_uptime_percent,link_uptime_percent,crashes,hangs\n");
+            for day in &report {
+                csv.push_str(&format!(
+                    "{},{:.2},{:.2},{},{}\n",
+                    day.date, day.backend_percent, day.link_percent, day.crashes, day.hangs
+                ));
+            }
+            Ok(csv)
+        }
+        "json" => {
+            let since = (now - ChronoDuration::days(days as i64)).timestamp_millis();
+            let events: Vec<&Event> = events.iter().filter(|e| e.at >= since).collect();
+            serde_json::to_string_pretty(&serde_json::json!({
+                "generated_at": now.to_rfc3339(),
+                "days": report,
+                "events": events,
+            }))
+            .map_err(|e| format!("Failed to serialize uptime report: {}", e))
+        }
+        other => Err(format!("Unknown report format: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(at: i64, kind: EventKind) -> Event {
+        Event {
+            at,
+            kind,
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_intervals_and_coverage() {
+        let events = [
+            event(0, EventKind::DesktopStarted),
+            event(100, EventKind::BackendStarted),
+            event(150, EventKind::LinkUp),
+            event(300, EventKind::BackendCrashed),
+            event(400, EventKind::BackendStarted),
+            // App crashed; the next launch closes the open interval
+            event(600, EventKind::DesktopStarted),
+        ];
+        let backend = up_intervals(&events, EventKind::backend_up, 1000);
+        assert_eq!(backend, vec![(100, 300), (400, 600)]);
+        let link = up_intervals(&events, EventKind::link_up, 1000);
+        assert_eq!(link, vec![(150, 300)]);
+
+        assert_eq!(coverage(&backend, 0, 1000), 40.0);
+        assert_eq!(coverage(&backend, 200, 300), 100.0);
+        assert_eq!(coverage(&link, 300, 1000), 0.0);
+    }
+
+    #[test]
+    fn test_open_interval_runs_until_now() {
+        let events = [event(100, EventKind::BackendAdopted)];
+        let backend = up_intervals(&events, EventKind::backend_up, 500);
+        assert_eq!(backend, vec![(100, 500)]);
+    }
+}
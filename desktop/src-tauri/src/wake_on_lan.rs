@@ -0,0 +1,198 @@
+//! Wake-on-LAN for the computer a network node hangs off
+//! (`Config::wake_on_lan_mac`), for a node on a small PC that sleeps. The
+//! magic packet is broadcast from every IPv4 interface, since the limited
+//! broadcast only leaves through one of them. The tray's **Wake Node's
+//! Computer** sends it on demand; with `Config::wake_on_lan_auto` it is
+//! also sent when the node can't be reached at login or the backend loses
+//! its link, before the connection is tried again.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+
+use crate::config::Config;
+use crate::{log, network_wait, notifications, tray};
+
+/// Port magic packets are sent to ("discard"), which sleeping network cards
+/// listen on no matter what.
+const WOL_PORT: u16 = 9;
+
+/// How long a woken computer has to bring the node back.
+const WAKE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often the node is tried while it wakes.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The MAC address in `text`, as `AA:BB:CC:DD:EE:FF`, `AA-BB-...` or
+/// twelve hex digits.
+pub fn parse_mac(text: &str) -> Result<[u8; 6], String> {
+    let digits: String = text
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    let invalid = || format!("\"{}\" is not a MAC address", text.trim());
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let mut mac = [0; 6];
+    for (i, byte) in mac.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(mac)
+}
+
+/// Six 0xFF bytes followed by the MAC sixteen times.
+fn magic_packet(mac: [u8; 6]) -> [u8; 102] {
+    let mut packet = [0xff; 102];
+    for chunk in packet[6..].chunks_mut(6) {
+        chunk.copy_from_slice(&mac);
+    }
+    packet
+}
+
+/// Broadcast the magic packet for the configured MAC address.
+pub fn send(config: &Config) -> Result<(), String> {
+    let mac = config
+        .wake_on_lan_mac
+        .as_deref()
+        .filter(|mac| !mac.trim().is_empty())
+        .ok_or_else(|| "No Wake-on-LAN MAC address is set".to_string())?;
+    let packet = magic_packet(parse_mac(mac)?);
+    let target = SocketAddr::from((Ipv4Addr::BROADCAST, WOL_PORT));
+
+    let mut sources: Vec<Ipv4Addr> = if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|interface| match interface.ip() {
+            IpAddr::V4(ip) if !ip.is_loopback() => Some(ip),
+            _ => None,
+        })
+        .collect();
+    if sources.is_empty() {
+        sources.push(Ipv4Addr::UNSPECIFIED);
+    }
+    let mut sent = 0;
+    let mut last_error = String::new();
+    for source in sources {
+        let result = UdpSocket::bind((source, 0))
+            .and_then(|socket| socket.set_broadcast(true).map(|()| socket))
+            .and_then(|socket| socket.send_to(&packet, target));
+        match result {
+            Ok(_) => sent += 1,
+            Err(e) => last_error = format!("{}: {}", source, e),
+        }
+    }
+    if sent == 0 {
+        return Err(format!(
+            "Failed to send the wake-up packet ({})",
+            last_error
+        ));
+    }
+    log(&format!("Sent a Wake-on-LAN packet to {}", mac.trim()));
+    Ok(())
+}
+
+/// Wake the node's computer from the tray, and say when the node answers.
+/// Without a MAC address, Settings opens at that field instead.
+pub fn wake<R: Runtime>(app: &AppHandle<R>) {
+    let config = Config::load().unwrap_or_default();
+    if config
+        .wake_on_lan_mac
+        .as_deref()
+        .is_none_or(|mac| mac.trim().is_empty())
+    {
+        tray::show_setting(app, "wakeOnLanMac");
+        return;
+    }
+    if let Err(e) = send(&config) {
+        log(&e);
+        notifications::notify(app, "Wake-on-LAN failed", &e, None);
+        return;
+    }
+    let Some(node) = network_wait::node_address(&config) else {
+        notifications::notify(
+            app,
+            "Wake-up sent",
+            "The node's computer should wake shortly.",
+            None,
+        );
+        return;
+    };
+    notifications::notify(
+        app,
+        "Wake-up sent",
+        &format!("Waiting for the node at {} to answer.", node),
+        None,
+    );
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        while started.elapsed() < WAKE_TIMEOUT {
+            if network_wait::reachable(&node) {
+                log(&format!(
+                    "Node at {} answered {} s after the wake-up",
+                    node,
+                    started.elapsed().as_secs()
+                ));
+                notifications::notify(
+                    &app,
+                    "Node is awake",
+                    &format!(
+                        "The node at {} answers again; MeshMonitor reconnects to it.",
+                        node
+                    ),
+                    None,
+                );
+                return;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        log(&format!("Node at {} didn't answer after the wake-up", node));
+        notifications::notify(
+            &app,
+            "Node still unreachable",
+            &format!(
+                "The node at {} didn't answer within {} seconds. Check that its computer \
+                 has Wake-on-LAN turned on.",
+                node,
+                WAKE_TIMEOUT.as_secs()
+            ),
+            None,
+        );
+    });
+}
+
+/// Send the magic packet if automatic wake-ups are on; `reason` is logged.
+pub fn wake_automatically(config: &Config, reason: &str) {
+    if !config.wake_on_lan_auto {
+        return;
+    }
+    log(&format!("{}; sending a Wake-on-LAN packet", reason));
+    if let Err(e) = send(config) {
+        log(&e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac() {
+        let mac = [0xaa, 0xbb, 0xcc, 0x01, 0x02, 0x03];
+        assert_eq!(parse_mac("AA:BB:CC:01:02:03"), Ok(mac));
+        assert_eq!(parse_mac(" aa-bb-cc-01-02-03 "), Ok(mac));
+        assert_eq!(parse_mac("aabb.cc01.0203"), Ok(mac));
+        assert!(parse_mac("AA:BB:CC:01:02").is_err());
+        assert!(parse_mac("GG:BB:CC:01:02:03").is_err());
+    }
+
+    #[test]
+    fn test_magic_packet() {
+        let mac = [1, 2, 3, 4, 5, 6];
+        let packet = magic_packet(mac);
+        assert_eq!(&packet[..6], &[0xff; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == mac));
+    }
+}
@@ -0,0 +1,454 @@
+//! Heartbeat watchdog for the backend. A crashed backend is easy to spot,
+//! but one whose event loop is frozen keeps its PID and port while answering
+//! nothing; the watchdog watches for the status updates the backend pushes
+//! over the control channel (polling the health check when it has none) and
+//! restarts the backend when heartbeats stop while the process is alive.
+//! A backend that exits on its own is restarted too, up to
+//! `MAX_CRASH_RESTARTS` times until one answers a heartbeat, or for as long
+//! as it takes with `Config::crash_recovery`, waiting twice as long before
+//! each attempt. The web port shows a status page in between, and the tray
+//! tooltip and Settings (`SUPERVISOR_EVENT`) show the restart under way.
+//!
+//! Each heartbeat also probes the health check over HTTP, so `status()` can
+//! tell whether the backend is serving requests and not merely running.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::backend_record::{self, BackendRecord};
+use crate::tray::{TRAY_ID, TRAY_TOOLTIP};
+use crate::{
+    api, attention, audit, config, crash_report, desktop_events, diagnose, fallback, ipc, log,
+    platform_support, port_release, safe_mode, show_start_failure, start_backend, stop_backend,
+    uptime, BackendState,
+};
+
+/// Event sent to the webviews as the backend is restarted after exiting.
+pub const SUPERVISOR_EVENT: &str = "backend-supervisor";
+
+/// How often the backend is asked for a heartbeat.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Consecutive missed heartbeats before the backend is declared hung (30s).
+const MISSED_HEARTBEAT_LIMIT: u32 = 3;
+
+/// How long a freshly spawned backend may take to answer its first
+/// heartbeat. Startup runs database migrations, which can be slow.
+const STARTUP_GRACE: Duration = Duration::from_secs(120);
+
+/// Restarts in a row of a backend that keeps exiting before giving up.
+const MAX_CRASH_RESTARTS: u32 = 5;
+
+/// Pause before the first restart of a backend that exited; it doubles with
+/// each further attempt.
+const CRASH_RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest pause between restarts, and the pause between crash recovery
+/// restarts once `MAX_CRASH_RESTARTS` is used up.
+const CRASH_RECOVERY_DELAY: Duration = Duration::from_secs(60);
+
+/// Heartbeat bookkeeping for one backend process.
+#[derive(Debug)]
+pub struct Heartbeat {
+    pid: u32,
+    spawned: Instant,
+    answered: bool,
+    missed: u32,
+}
+
+impl Heartbeat {
+    pub fn new(pid: u32, now: Instant) -> Self {
+        Heartbeat {
+            pid,
+            spawned: now,
+            answered: false,
+            missed: 0,
+        }
+    }
+
+    /// Record whether the latest heartbeat was answered. Returns `true` once
+    /// the backend counts as hung: it answered before (or is past its startup
+    /// grace) and has missed `MISSED_HEARTBEAT_LIMIT` heartbeats in a row.
+    pub fn observe(&mut self, answered: bool, now: Instant) -> bool {
+        if answered {
+            self.answered = true;
+            self.missed = 0;
+            return false;
+        }
+        if !self.answered && now.duration_since(self.spawned) < STARTUP_GRACE {
+            return false;
+        }
+        self.missed += 1;
+        self.missed >= MISSED_HEARTBEAT_LIMIT
+    }
+}
+
+/// What `SUPERVISOR_EVENT` tells the webviews.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Supervision {
+    /// Restart `attempt` (of `max_attempts`, unless crash recovery keeps
+    /// going) starts in `retry_in_secs`.
+    Restarting {
+        attempt: u32,
+        max_attempts: Option<u32>,
+        retry_in_secs: u64,
+        reason: String,
+    },
+    /// A backend is answering heartbeats.
+    Running,
+    /// No more restarts; the backend stays stopped.
+    GaveUp { reason: String },
+}
+
+/// What `get_backend_status` returns.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Status {
+    pub running: bool,
+    /// The latest health check of the running backend passed.
+    pub healthy: bool,
+    pub pid: Option<u32>,
+    /// Seconds since the running backend was spawned.
+    pub uptime: Option<u64>,
+    /// The latest failed health check, exit or failed restart.
+    pub last_error: Option<String>,
+}
+
+/// PID of the backend last probed, and whether its health check passed.
+static PROBE: Mutex<Option<(u32, bool)>> = Mutex::new(None);
+
+/// See `Status::last_error`.
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Guards against spawning a second watchdog thread.
+static WATCHDOG_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Spawn the watchdog thread. Safe to call more than once.
+pub fn start_watchdog<R: Runtime>(app: &AppHandle<R>) {
+    if WATCHDOG_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let mut heartbeat: Option<Heartbeat> = None;
+        let mut crash_restarts = 0;
+        loop {
+            std::thread::sleep(HEARTBEAT_INTERVAL);
+            diagnose::scan(&app);
+
+            let state = app.state::<BackendState>();
+            let pid = match running_backend(&app, &state) {
+                Backend::Running(pid) => pid,
+                Backend::Exited(reason) => {
+                    heartbeat = None;
+                    failed(&reason);
+                    restart_exited_backend(&app, &mut crash_restarts, reason);
+                    continue;
+                }
+                Backend::Stopped => {
+                    heartbeat = None;
+                    continue;
+                }
+            };
+            let Some(record) = BackendRecord::load().filter(|record| record.pid == pid) else {
+                continue;
+            };
+
+            let now = Instant::now();
+            let monitor = match &mut heartbeat {
+                Some(monitor) if monitor.pid == pid => monitor,
+                _ => heartbeat.insert(Heartbeat::new(pid, now)),
+            };
+            // The status the backend pushes over the control channel is its
+            // heartbeat; without one, fall back to the configured health check
+            let config = config::Config::load().unwrap_or_default();
+            let served = api::check_health(record.web_port, &config);
+            *PROBE.lock().unwrap() = Some((pid, served.is_ok()));
+            if let Err(e) = &served {
+                failed(e);
+            }
+            let answered = ipc::heard_from_within(pid, HEARTBEAT_INTERVAL) || served.is_ok();
+            if answered {
+                crash_restarts = 0;
+                if !monitor.answered {
+                    safe_mode::backend_healthy();
+                    supervision(&app, &Supervision::Running);
+                }
+            }
+            if monitor.observe(answered, now) {
+                heartbeat = None;
+                restart_hung_backend(&app, pid);
+            }
+        }
+    });
+}
+
+enum Backend {
+    /// PID of the live backend, spawned or adopted.
+    Running(u32),
+    /// The spawned backend exited on its own, for this reason.
+    Exited(String),
+    Stopped,
+}
+
+/// What became of the backend. A spawned backend found to have exited on
+/// its own is recorded as a crash and dropped from the state.
+fn running_backend<R: Runtime>(app: &AppHandle<R>, state: &BackendState) -> Backend {
+    let mut process = state.process.lock().unwrap();
+    if let Some(child) = process.as_mut() {
+        let pid = child.id();
+        return match child.try_wait() {
+            Ok(None) | Err(_) => Backend::Running(pid),
+            Ok(Some(status)) => {
+                let message = format!("Backend (PID {}) exited unexpectedly: {}", pid, status);
+                log(&message);
+                uptime::record(uptime::EventKind::BackendCrashed, &message);
+                // Before the restart truncates the backend's logs
+                if let Err(e) = crash_report::capture(pid, &status.to_string()) {
+                    log(&format!("Crash diagnostics failed: {}", e));
+                }
+                *process = None;
+                platform_support::backend_exited(app, &status);
+                Backend::Exited(message)
+            }
+        };
+    }
+    drop(process);
+    let adopted = *state.adopted_pid.lock().unwrap();
+    match adopted.filter(|&pid| backend_record::is_alive(pid)) {
+        Some(pid) => Backend::Running(pid),
+        None => Backend::Stopped,
+    }
+}
+
+/// Start a backend that exited again, retrying failed starts, unless it has
+/// already been restarted `MAX_CRASH_RESTARTS` times without coming up and
+/// crash recovery is off.
+fn restart_exited_backend<R: Runtime>(app: &AppHandle<R>, restarts: &mut u32, reason: String) {
+    let state = app.state::<BackendState>();
+    let config = config::Config::load().unwrap_or_default();
+    let web_port = config.web_port;
+    desktop_events::publish(
+        app,
+        desktop_events::Level::Warning,
+        &format!("{}. Restarting it.", reason),
+    );
+    let mut last_error = reason;
+    while *restarts < MAX_CRASH_RESTARTS || config.crash_recovery {
+        *restarts += 1;
+        let max_attempts = Some(MAX_CRASH_RESTARTS).filter(|&max| *restarts <= max);
+        let headline = match max_attempts {
+            Some(max) => format!(
+                "MeshMonitor backend is restarting (attempt {}/{})",
+                restarts, max
+            ),
+            None => format!("MeshMonitor backend is restarting (attempt {})", restarts),
+        };
+        let delay = restart_delay(*restarts);
+        supervision(
+            app,
+            &Supervision::Restarting {
+                attempt: *restarts,
+                max_attempts,
+                retry_in_secs: delay.as_secs(),
+                reason: last_error.clone(),
+            },
+        );
+        fallback::show(
+            &state,
+            web_port,
+            &headline,
+            Some(&format!("Last error: {}", last_error)),
+        );
+        std::thread::sleep(delay);
+        // Stopped from the tray or settings in the meantime
+        if state.is_running() {
+            return;
+        }
+        match start_backend(app) {
+            Ok(child) => {
+                *state.process.lock().unwrap() = Some(child);
+                audit::record(audit::Source::Scheduler, "backend_restarted", &last_error);
+                return;
+            }
+            Err(e) => {
+                failed(&e);
+                last_error = e;
+            }
+        }
+    }
+    log(&format!(
+        "Backend kept exiting; gave up after {} restarts",
+        MAX_CRASH_RESTARTS
+    ));
+    desktop_events::publish(
+        app,
+        desktop_events::Level::Error,
+        &format!(
+            "The MeshMonitor backend kept stopping and wasn't restarted again after {} \
+             attempts. Restart it from Settings once the problem is fixed.",
+            MAX_CRASH_RESTARTS
+        ),
+    );
+    supervision(
+        app,
+        &Supervision::GaveUp {
+            reason: last_error.clone(),
+        },
+    );
+    fallback::show(
+        &state,
+        web_port,
+        &format!(
+            "MeshMonitor backend stopped after {} failed restarts",
+            MAX_CRASH_RESTARTS
+        ),
+        Some(&format!(
+            "Last error: {}. Restart it from Settings once the problem is fixed.",
+            last_error
+        )),
+    );
+}
+
+/// Whether the backend is running and serving requests.
+pub fn status(state: &BackendState) -> Status {
+    let pid = state
+        .process
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|child| child.id())
+        .or(*state.adopted_pid.lock().unwrap());
+    let uptime = pid
+        .and_then(|pid| BackendRecord::load().filter(|record| record.pid == pid))
+        .map(|record| {
+            let elapsed = chrono::Utc::now().timestamp_millis() - record.started_at;
+            u64::try_from(elapsed / 1000).unwrap_or_default()
+        });
+    let probe = *PROBE.lock().unwrap();
+    Status {
+        running: pid.is_some(),
+        healthy: pid.is_some_and(|pid| probe == Some((pid, true))),
+        pid,
+        uptime,
+        last_error: LAST_ERROR.lock().unwrap().clone(),
+    }
+}
+
+/// Keep `error` for `status()`.
+pub fn failed(error: &str) {
+    *LAST_ERROR.lock().unwrap() = Some(error.to_string());
+}
+
+/// Pause before restart `attempt` (from 1) of a backend that keeps exiting.
+fn restart_delay(attempt: u32) -> Duration {
+    CRASH_RESTART_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(CRASH_RECOVERY_DELAY)
+}
+
+/// Tell the webviews and the tray tooltip how the restart is going.
+fn supervision<R: Runtime>(app: &AppHandle<R>, event: &Supervision) {
+    let _ = app.emit(SUPERVISOR_EVENT, event);
+    let status = match event {
+        Supervision::Restarting {
+            attempt,
+            max_attempts: Some(max),
+            ..
+        } => format!("Backend restarting (attempt {}/{})", attempt, max),
+        Supervision::Restarting { attempt, .. } => {
+            format!("Backend restarting (attempt {})", attempt)
+        }
+        Supervision::GaveUp { .. } => "Backend stopped".to_string(),
+        Supervision::Running => {
+            attention::refresh(app);
+            return;
+        }
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("{} — {}", TRAY_TOOLTIP, status)));
+    }
+}
+
+fn restart_hung_backend<R: Runtime>(app: &AppHandle<R>, pid: u32) {
+    let state = app.state::<BackendState>();
+    let message = format!(
+        "Backend (PID {}) stopped answering heartbeats; restarting it",
+        pid
+    );
+    eprintln!("{}", message);
+    log(&message);
+
+    uptime::record(uptime::EventKind::BackendHung, &format!("PID {}", pid));
+    desktop_events::publish(
+        app,
+        desktop_events::Level::Warning,
+        "The MeshMonitor backend stopped responding. Restarting it.",
+    );
+    audit::record(
+        audit::Source::Scheduler,
+        "backend_restarted",
+        &format!("PID {} stopped answering heartbeats", pid),
+    );
+
+    stop_backend(&state);
+    let config = config::Config::load().unwrap_or_default();
+    // The status page would hold the port itself, so wait for it first
+    let released = port_release::wait(config.web_port);
+    fallback::show(
+        &state,
+        config.web_port,
+        "MeshMonitor backend is restarting",
+        None,
+    );
+    match released.and_then(|()| start_backend(app)) {
+        Ok(child) => *state.process.lock().unwrap() = Some(child),
+        Err(e) => {
+            show_start_failure(app, &config, &e);
+            log(&format!("Failed to restart hung backend: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hung_after_missed_heartbeats() {
+        let start = Instant::now();
+        let mut heartbeat = Heartbeat::new(1234, start);
+        assert!(!heartbeat.observe(true, start));
+        assert!(!heartbeat.observe(false, start));
+        assert!(!heartbeat.observe(false, start));
+        // An answer in between resets the count
+        assert!(!heartbeat.observe(true, start));
+        assert!(!heartbeat.observe(false, start));
+        assert!(!heartbeat.observe(false, start));
+        assert!(heartbeat.observe(false, start));
+    }
+
+    #[test]
+    fn test_startup_grace_before_first_heartbeat() {
+        let start = Instant::now();
+        let mut heartbeat = Heartbeat::new(1234, start);
+        for _ in 0..10 {
+            assert!(!heartbeat.observe(false, start + Duration::from_secs(60)));
+        }
+        let late = start + STARTUP_GRACE;
+        assert!(!heartbeat.observe(false, late));
+        assert!(!heartbeat.observe(false, late));
+        assert!(heartbeat.observe(false, late));
+    }
+
+    #[test]
+    fn test_restart_delay_backs_off() {
+        let delays: Vec<u64> = (1..=7).map(|n| restart_delay(n).as_secs()).collect();
+        assert_eq!(delays, [5, 10, 20, 40, 60, 60, 60]);
+        assert_eq!(restart_delay(u32::MAX), CRASH_RECOVERY_DELAY);
+    }
+}
@@ -0,0 +1,99 @@
+//! Moving the web UI to a free port when another program holds
+//! `Config::web_port`. Only ports in `Config::web_port_range_start` to
+//! `web_port_range_end` are considered, and there is no fallback while the
+//! range isn't set. The port picked is saved as the new `web_port`, so the
+//! web UI stays there on later launches and bookmarks and firewall rules
+//! need updating once rather than after every clash.
+
+use std::net::TcpListener;
+use std::ops::RangeInclusive;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::config::Config;
+use crate::{audit, fallback, log, notifications, BackendState};
+
+/// `config`, with `web_port` moved into the fallback range when it's taken.
+pub fn settle<R: Runtime>(app: &AppHandle<R>, config: Config) -> Config {
+    let Some(range) = range(&config) else {
+        return config;
+    };
+    // The status page holds the port until the backend is spawned
+    let own = fallback::serving_on(&app.state::<BackendState>());
+    let reserved = [config.proxy_port, config.tile_cache_port];
+    let is_free = |port: u16| {
+        own == Some(port)
+            || (!reserved.contains(&Some(port)) && TcpListener::bind(("0.0.0.0", port)).is_ok())
+    };
+    if is_free(config.web_port) {
+        return config;
+    }
+    let Some(port) = pick(range.clone(), config.web_port, is_free) else {
+        log(&format!(
+            "Port {} is in use and no port from {} to {} is free",
+            config.web_port,
+            range.start(),
+            range.end()
+        ));
+        return config;
+    };
+    let mut moved = config.clone();
+    moved.web_port = port;
+    if let Err(e) = moved.save() {
+        log(&format!("Failed to save the new web port {}: {}", port, e));
+        return config;
+    }
+    audit::record_config_change(audit::Source::Scheduler, &config, &moved);
+    log(&format!(
+        "Port {} is in use; the web UI moved to port {}",
+        config.web_port, port
+    ));
+    notifications::notify(
+        app,
+        "Web UI Moved",
+        &format!(
+            "Port {} is used by another program, so MeshMonitor now uses port {}.",
+            config.web_port, port
+        ),
+        None,
+    );
+    moved
+}
+
+/// The configured fallback range, when both ends are set and in order.
+fn range(config: &Config) -> Option<RangeInclusive<u16>> {
+    let (start, end) = (config.web_port_range_start?, config.web_port_range_end?);
+    (start > 0 && start <= end).then_some(start..=end)
+}
+
+/// The first port of `range` other than `current` that `is_free`.
+fn pick(
+    mut range: RangeInclusive<u16>,
+    current: u16,
+    is_free: impl Fn(u16) -> bool,
+) -> Option<u16> {
+    range.find(|&port| port != current && is_free(port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_stays_in_range() {
+        let taken = [8080, 8081, 8082];
+        let is_free = |port| !taken.contains(&port);
+        assert_eq!(pick(8080..=8090, 8080, is_free), Some(8083));
+        assert_eq!(pick(8080..=8082, 8080, is_free), None);
+        assert_eq!(pick(9000..=9000, 8080, is_free), Some(9000));
+
+        let mut config = Config {
+            web_port_range_start: Some(8090),
+            web_port_range_end: Some(8080),
+            ..Config::default()
+        };
+        assert_eq!(range(&config), None);
+        config.web_port_range_start = Some(8080);
+        config.web_port_range_end = Some(8090);
+        assert_eq!(range(&config), Some(8080..=8090));
+    }
+}
@@ -0,0 +1,212 @@
+//! Forwarding mesh events to webhooks (`Config::webhooks`), such as a
+//! Discord channel or a home automation endpoint. Each webhook can give its
+//! own JSON payload per event type, with placeholders filled in from the
+//! event, so the same alert reaches services that expect different shapes.
+//! Placeholders are replaced inside the template's strings, so names and
+//! message text never break the JSON around them.
+
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::config::{Config, Webhook};
+use crate::{log, outbound};
+
+/// How long a webhook gets to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What happened on the mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    NewNode,
+    DirectMessage,
+    ChannelMessage,
+}
+
+impl EventKind {
+    /// Name of the event in `templates` and in the default payload.
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::NewNode => "new_node",
+            EventKind::DirectMessage => "direct_message",
+            EventKind::ChannelMessage => "channel_message",
+        }
+    }
+}
+
+/// One event to forward.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    pub node_id: String,
+    pub node_name: String,
+    /// Message text; empty for node events.
+    pub text: String,
+    /// Channel index, for channel messages.
+    pub channel: Option<i64>,
+    /// Unix milliseconds.
+    pub timestamp: i64,
+}
+
+impl Event {
+    /// Placeholder values, as `({name}, value)`.
+    fn fields(&self) -> [(&'static str, String); 6] {
+        let time = chrono::DateTime::from_timestamp_millis(self.timestamp)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default();
+        [
+            ("{event}", self.kind.name().to_string()),
+            ("{node_id}", self.node_id.clone()),
+            ("{node_name}", self.node_name.clone()),
+            ("{text}", self.text.clone()),
+            (
+                "{channel}",
+                self.channel.map(|c| c.to_string()).unwrap_or_default(),
+            ),
+            ("{time}", time),
+        ]
+    }
+}
+
+/// Post `event` to every webhook that wants it, in the background.
+pub fn send(config: &Config, event: Event) {
+    let deliveries: Vec<(String, Value)> = config
+        .webhooks
+        .iter()
+        .filter_map(|webhook| Some((webhook.url.clone(), payload(webhook, &event)?)))
+        .collect();
+    // Privacy mode would refuse each one; don't log that for every message
+    if deliveries.is_empty() || config.privacy_mode {
+        return;
+    }
+    std::thread::spawn(move || {
+        let client = outbound::Client::new(ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT));
+        for (url, body) in deliveries {
+            let sent = client
+                .post(&url)
+                .and_then(|request| request.send_json(body).map_err(|e| e.to_string()));
+            if let Err(e) = sent {
+                log(&format!("Webhook {} failed: {}", redact(&url), e));
+            }
+        }
+    });
+}
+
+/// What `webhook` is sent for `event`, or `None` if it has templates but
+/// none for this event.
+fn payload(webhook: &Webhook, event: &Event) -> Option<Value> {
+    let fields = event.fields();
+    if webhook.templates.is_empty() {
+        let mut body = serde_json::Map::new();
+        for (placeholder, value) in &fields {
+            let key = placeholder.trim_matches(['{', '}']).to_string();
+            body.insert(key, Value::String(value.clone()));
+        }
+        return Some(Value::Object(body));
+    }
+    let template = webhook.templates.get(event.kind.name())?;
+    Some(fill(template, &fields))
+}
+
+/// `template` with the placeholders in its strings (keys included) filled in.
+fn fill(template: &Value, fields: &[(&str, String)]) -> Value {
+    match template {
+        Value::String(text) => Value::String(fill_str(text, fields)),
+        Value::Array(items) => Value::Array(items.iter().map(|item| fill(item, fields)).collect()),
+        Value::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (fill_str(key, fields), fill(value, fields)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// `text` with its placeholders filled in, in one pass so a message that
+/// itself says `{node_id}` is left as it is.
+fn fill_str(text: &str, fields: &[(&str, String)]) -> String {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match fields
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                filled.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// `url` without its path, which for Discord and others holds the token.
+fn redact(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let host = rest.split('/').next().unwrap_or_default();
+    format!("{}://{}/…", scheme, host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> Event {
+        Event {
+            kind: EventKind::DirectMessage,
+            node_id: "!a1b2c3d4".to_string(),
+            node_name: "Base \"Camp\"".to_string(),
+            text: "Need water at {node_id}".to_string(),
+            channel: None,
+            timestamp: 1_760_000_000_000,
+        }
+    }
+
+    #[test]
+    fn test_templates_per_event() {
+        let webhook = Webhook {
+            url: "https://discord.com/api/webhooks/1/token".to_string(),
+            templates: [(
+                "direct_message".to_string(),
+                serde_json::json!({ "content": "**{node_name}**: {text}", "tts": false }),
+            )]
+            .into(),
+        };
+        assert_eq!(
+            payload(&webhook, &message()).unwrap(),
+            serde_json::json!({ "content": "**Base \"Camp\"**: Need water at {node_id}", "tts": false })
+        );
+
+        let new_node = Event {
+            kind: EventKind::NewNode,
+            ..message()
+        };
+        assert_eq!(payload(&webhook, &new_node), None);
+    }
+
+    #[test]
+    fn test_default_payload_and_redacted_url() {
+        let webhook = Webhook {
+            url: "http://automation.local/hook".to_string(),
+            templates: Default::default(),
+        };
+        let body = payload(&webhook, &message()).unwrap();
+        assert_eq!(body["event"], "direct_message");
+        assert_eq!(body["node_id"], "!a1b2c3d4");
+        assert_eq!(body["channel"], "");
+        assert!(body["time"].as_str().unwrap().starts_with("2025-10-09"));
+
+        assert_eq!(
+            redact("https://discord.com/api/webhooks/1/token"),
+            "https://discord.com/…"
+        );
+    }
+}
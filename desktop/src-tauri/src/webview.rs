@@ -0,0 +1,312 @@
+//! Native window creation with a browser fallback for systems whose WebView
+//! runtime is missing or broken.
+//!
+//! A system without libwebkit2gtk at all can't load the binary, which the
+//! package dependencies cover; what bites in practice is WebKitGTK being
+//! present but unusable — no display to attach to, or the web process
+//! failing to start. Then native windows are skipped for the rest of the run
+//! and the app keeps working as tray + browser: the web UI opens in the
+//! default browser and settings are edited in config.json.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::config::{self, Config};
+use crate::window_size::{self, Sizing};
+use crate::{desktop_events, log, login_handoff, notifications, polling, status_bar, theme};
+
+/// Set once native windows are known not to work.
+static UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the browser-fallback warning was already shown this run.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Probe the WebView runtime before the first window is created. Only Linux
+/// can be checked up front: WebView2 and WKWebView ship with the OS.
+pub fn check() {
+    #[cfg(target_os = "linux")]
+    {
+        let has_display = ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()));
+        if !has_display {
+            mark_unavailable("no X11 or Wayland display is available");
+        }
+    }
+}
+
+/// Whether native windows can be created.
+pub fn native_windows_available() -> bool {
+    !UNAVAILABLE.load(Ordering::SeqCst)
+}
+
+/// Switch to browser + tray mode for the rest of the run.
+fn mark_unavailable(reason: &str) {
+    UNAVAILABLE.store(true, Ordering::SeqCst);
+    log(&format!(
+        "Native windows disabled ({}); using the browser instead",
+        reason
+    ));
+}
+
+/// Warn the user, once per run, that native windows are unavailable.
+fn warn_once<R: Runtime>(app: &AppHandle<R>) {
+    if WARNED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    notifications::notify(
+        app,
+        "MeshMonitor is running without its own windows",
+        "The system WebView (WebKitGTK) isn't working. The web UI opens in your browser \
+         and settings can be changed in config.json.",
+        None,
+    );
+}
+
+/// Show the window `label`, creating it from the bundled settings page if
+/// needed. Returns `false` if native windows are unavailable, after warning
+/// the user, in which case the caller falls back to the browser.
+pub fn show_window<R: Runtime>(app: &AppHandle<R>, label: &str, title: &str) -> bool {
+    show_page(app, label, title, "index.html", window_size::SETTINGS)
+}
+
+/// Show the first-run wizard. Returns `false` like `show_window`.
+pub fn show_setup_window<R: Runtime>(app: &AppHandle<R>) -> bool {
+    show_page(
+        app,
+        SETUP_WINDOW,
+        "MeshMonitor Setup",
+        "setup.html",
+        window_size::SETUP,
+    )
+}
+
+/// Label of the setup window.
+pub const SETUP_WINDOW: &str = "setup";
+
+/// Like `show_window`, with the page scrolled to and focused on the form
+/// field `field`.
+pub fn show_window_at<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+    title: &str,
+    field: &str,
+) -> bool {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.emit(FOCUS_FIELD_EVENT, field);
+    }
+    show_page(
+        app,
+        label,
+        title,
+        &format!("index.html#{}", field),
+        window_size::SETTINGS,
+    )
+}
+
+/// Event asking an open settings window to focus a form field.
+const FOCUS_FIELD_EVENT: &str = "focus-field";
+
+/// Show the small control window that stands in for the tray when the
+/// desktop has none. It offers the tray's main actions and is minimized
+/// rather than hidden on close, so the app is never running invisibly.
+pub fn show_control_window<R: Runtime>(app: &AppHandle<R>) -> bool {
+    show_page(
+        app,
+        CONTROL_WINDOW,
+        "MeshMonitor",
+        "control.html",
+        window_size::CONTROL,
+    )
+}
+
+/// Label of the control window.
+pub const CONTROL_WINDOW: &str = "control";
+
+/// Show the window for searching the logs.
+pub fn show_log_search_window<R: Runtime>(app: &AppHandle<R>) -> bool {
+    show_page(
+        app,
+        LOG_SEARCH_WINDOW,
+        "MeshMonitor Logs",
+        "logs.html",
+        window_size::LOG_SEARCH,
+    )
+}
+
+/// Label of the log search window.
+pub const LOG_SEARCH_WINDOW: &str = "logs";
+
+/// Show the window with the printable activity report.
+pub fn show_activity_report_window<R: Runtime>(app: &AppHandle<R>) -> bool {
+    show_page(
+        app,
+        ACTIVITY_REPORT_WINDOW,
+        "MeshMonitor Activity Report",
+        "report.html",
+        window_size::ACTIVITY_REPORT,
+    )
+}
+
+/// Label of the activity report window.
+pub const ACTIVITY_REPORT_WINDOW: &str = "activity_report";
+
+/// Label of the window showing the web UI in native-window mode.
+pub const WEB_UI_WINDOW: &str = "main";
+
+/// What every page of the web UI window starts with: the status strip, the
+/// bridge for supervisor events and the polling preset's socket options.
+pub fn web_ui_init_script(config: &Config) -> String {
+    format!(
+        "{}\n{}\n{}",
+        status_bar::INIT_SCRIPT,
+        desktop_events::INIT_SCRIPT,
+        polling::init_script(config.polling_preset)
+    )
+}
+
+/// Show the web UI at `url` in a native window (native-window mode), with
+/// `init_script` injected into every page it loads. Returns `false` when
+/// native windows are unavailable, in which case the caller opens the
+/// browser instead.
+pub fn show_web_ui<R: Runtime>(app: &AppHandle<R>, url: &str, init_script: &str) -> bool {
+    open_web_ui_window(app, url, init_script, false)
+}
+
+/// Like `show_web_ui`, full screen and without window decorations, for
+/// kiosk mode.
+pub fn show_web_ui_fullscreen<R: Runtime>(
+    app: &AppHandle<R>,
+    url: &str,
+    init_script: &str,
+) -> bool {
+    open_web_ui_window(app, url, init_script, true)
+}
+
+fn open_web_ui_window<R: Runtime>(
+    app: &AppHandle<R>,
+    url: &str,
+    init_script: &str,
+    fullscreen: bool,
+) -> bool {
+    if let Some(window) = app.get_webview_window(WEB_UI_WINDOW) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        if fullscreen {
+            let _ = window.set_fullscreen(true);
+        }
+        let _ = window.set_focus();
+        return true;
+    }
+    if !native_windows_available() {
+        warn_once(app);
+        return false;
+    }
+    let Ok(url) = url.parse::<tauri::Url>() else {
+        return false;
+    };
+
+    let built =
+        tauri::WebviewWindowBuilder::new(app, WEB_UI_WINDOW, tauri::WebviewUrl::External(url))
+            .title("MeshMonitor")
+            .inner_size(1280.0, 800.0)
+            .min_inner_size(640.0, 480.0)
+            .initialization_script(init_script)
+            .theme(Some(theme::current()))
+            .background_color(theme::background(theme::current()))
+            .fullscreen(fullscreen)
+            .decorations(!fullscreen)
+            .center()
+            .focused(true)
+            .build();
+
+    match built {
+        Ok(window) => {
+            let _ = window.show();
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to create web UI window: {}", e);
+            mark_unavailable(&format!("creating the web UI window failed: {}", e));
+            warn_once(app);
+            false
+        }
+    }
+}
+
+fn show_page<R: Runtime>(
+    app: &AppHandle<R>,
+    label: &str,
+    title: &str,
+    page: &str,
+    sizing: Sizing,
+) -> bool {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return true;
+    }
+    if !native_windows_available() {
+        warn_once(app);
+        return false;
+    }
+
+    let built = tauri::WebviewWindowBuilder::new(app, label, tauri::WebviewUrl::App(page.into()))
+        .title(title)
+        .inner_size(sizing.default.0, sizing.default.1)
+        .min_inner_size(sizing.min.0, sizing.min.1)
+        .theme(Some(theme::current()))
+        .background_color(theme::background(theme::current()))
+        .visible(false)
+        .focused(true)
+        .build();
+
+    match built {
+        Ok(window) => {
+            // Sized once it's on a monitor, whose scaling is then known
+            window_size::apply(&window.as_ref().window(), sizing);
+            let _ = window.center();
+            let _ = window.show();
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to create {} window: {}", label, e);
+            mark_unavailable(&format!("creating the {} window failed: {}", label, e));
+            warn_once(app);
+            false
+        }
+    }
+}
+
+/// Open the web UI in the default browser once the backend answers health
+/// checks (giving up after a minute), for when there is no setup window to
+/// do it.
+pub fn open_browser_when_ready<R: Runtime>(app: &AppHandle<R>, web_port: u16) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for _ in 0..60 {
+            if crate::api::health(web_port).is_ok() {
+                let url = login_handoff::url(
+                    &Config::load().unwrap_or_default(),
+                    &format!("http://localhost:{}", web_port),
+                );
+                if let Err(e) = app.opener().open_url(&url, None::<&str>) {
+                    eprintln!("Failed to open browser: {}", e);
+                }
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+    });
+}
+
+/// Browser-mode stand-in for the settings window: reveal config.json so it
+/// can be edited by hand (the config watcher picks up the change).
+pub fn reveal_config_file<R: Runtime>(app: &AppHandle<R>) {
+    if let Ok(path) = config::get_config_path() {
+        if let Err(e) = app.opener().reveal_item_in_dir(&path) {
+            eprintln!("Failed to reveal config file: {}", e);
+        }
+    }
+}
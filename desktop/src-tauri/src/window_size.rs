@@ -0,0 +1,184 @@
+//! Sizes of the setup, settings, control, log search and activity report
+//! windows. Each opens at its
+//! default logical size, shrunk to fit the work area of the monitor it's
+//! on, so display scaling of 150% and more on a small screen doesn't cut
+//! the page off, and can be resized down to a minimum that still shows the
+//! form. The size the user leaves a window at is remembered in
+//! `window_sizes.json` for each monitor and scale factor, and reapplied
+//! when the window opens there again or is dragged to that monitor.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::{LogicalSize, Runtime, Window};
+
+use crate::config;
+use crate::webview::{
+    ACTIVITY_REPORT_WINDOW, CONTROL_WINDOW, LOG_SEARCH_WINDOW, SETUP_WINDOW, WEB_UI_WINDOW,
+};
+
+/// Share of the work area a window may take at most.
+const MAX_WORK_AREA_SHARE: f64 = 0.9;
+
+/// Default and minimum logical size of a window.
+#[derive(Debug, Clone, Copy)]
+pub struct Sizing {
+    pub default: (f64, f64),
+    pub min: (f64, f64),
+}
+
+/// The first-run wizard, wide enough for each step without scrolling.
+pub const SETUP: Sizing = Sizing {
+    default: (560.0, 600.0),
+    min: (440.0, 480.0),
+};
+
+/// The settings window.
+pub const SETTINGS: Sizing = Sizing {
+    default: (480.0, 640.0),
+    min: (400.0, 360.0),
+};
+
+/// The control window.
+pub const CONTROL: Sizing = Sizing {
+    default: (280.0, 350.0),
+    min: (240.0, 310.0),
+};
+
+/// The log search window.
+pub const LOG_SEARCH: Sizing = Sizing {
+    default: (760.0, 560.0),
+    min: (420.0, 320.0),
+};
+
+/// The activity report window, about a page wide.
+pub const ACTIVITY_REPORT: Sizing = Sizing {
+    default: (780.0, 720.0),
+    min: (480.0, 360.0),
+};
+
+/// Sizing of the window `label`, if it's one sized here.
+pub fn sizing_for(label: &str) -> Option<Sizing> {
+    match label {
+        SETUP_WINDOW => Some(SETUP),
+        CONTROL_WINDOW => Some(CONTROL),
+        LOG_SEARCH_WINDOW => Some(LOG_SEARCH),
+        ACTIVITY_REPORT_WINDOW => Some(ACTIVITY_REPORT),
+        WEB_UI_WINDOW => None,
+        _ => Some(SETTINGS),
+    }
+}
+
+/// Remembered sizes, keyed by [`key`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Sizes(BTreeMap<String, (f64, f64)>);
+
+fn sizes_path() -> Option<PathBuf> {
+    let config_path = config::get_config_path().ok()?;
+    Some(config_path.parent()?.join("window_sizes.json"))
+}
+
+fn load() -> Sizes {
+    sizes_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn key(label: &str, monitor: &str, scale_factor: f64) -> String {
+    format!("{}@{}@{}%", label, monitor, (scale_factor * 100.0).round())
+}
+
+/// Key of `window` on the monitor it's on now.
+fn window_key<R: Runtime>(window: &Window<R>) -> Option<String> {
+    let monitor = window.current_monitor().ok()??;
+    Some(key(
+        window.label(),
+        monitor.name().map(String::as_str).unwrap_or("display"),
+        monitor.scale_factor(),
+    ))
+}
+
+/// Size `window` for the monitor it's on: the size remembered there, or
+/// the default, fitted to the work area. Run after creating the window and
+/// when it moves to a monitor with another scale factor.
+pub fn apply<R: Runtime>(window: &Window<R>, sizing: Sizing) {
+    let Ok(Some(monitor)) = window.current_monitor() else {
+        return;
+    };
+    let scale = monitor.scale_factor();
+    let work_area = monitor.work_area().size.to_logical::<f64>(scale);
+    let remembered = window_key(window).and_then(|key| load().0.get(&key).copied());
+    let (size, min) = fit(
+        remembered.unwrap_or(sizing.default),
+        sizing.min,
+        (work_area.width, work_area.height),
+    );
+    let _ = window.set_min_size(Some(LogicalSize::new(min.0, min.1)));
+    let _ = window.set_size(LogicalSize::new(size.0, size.1));
+}
+
+/// Remember the size `window` was left at on its current monitor. Run when
+/// it's closed (hidden).
+pub fn remember<R: Runtime>(window: &Window<R>) {
+    let (Some(key), Ok(size), Ok(scale)) = (
+        window_key(window),
+        window.inner_size(),
+        window.scale_factor(),
+    ) else {
+        return;
+    };
+    let size = size.to_logical::<f64>(scale);
+    let mut sizes = load();
+    sizes
+        .0
+        .insert(key, (size.width.round(), size.height.round()));
+    if let (Some(path), Ok(json)) = (sizes_path(), serde_json::to_string_pretty(&sizes)) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// Fit `desired` and `min` into the share of `work_area` a window may take.
+/// Returns the size and minimum size to use.
+fn fit(desired: (f64, f64), min: (f64, f64), work_area: (f64, f64)) -> ((f64, f64), (f64, f64)) {
+    let max = (
+        work_area.0 * MAX_WORK_AREA_SHARE,
+        work_area.1 * MAX_WORK_AREA_SHARE,
+    );
+    let min = (min.0.min(max.0), min.1.min(max.1));
+    let size = (desired.0.clamp(min.0, max.0), desired.1.clamp(min.1, max.1));
+    (size, min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fits_the_work_area() {
+        // 1366x728 work area at 150%
+        let work_area = (1366.0 / 1.5, 728.0 / 1.5);
+        let (size, min) = fit(SETTINGS.default, SETTINGS.min, work_area);
+        assert_eq!(size.0, 480.0);
+        assert!((size.1 - 436.8).abs() < 0.01);
+        assert_eq!(min, SETTINGS.min);
+
+        // Too small for even the minimum
+        let (size, min) = fit(SETTINGS.default, SETTINGS.min, (400.0, 300.0));
+        assert_eq!(size, (360.0, 270.0));
+        assert_eq!(min, (360.0, 270.0));
+
+        // A remembered size below the minimum is raised to it
+        let (size, _) = fit((100.0, 100.0), SETTINGS.min, (1920.0, 1040.0));
+        assert_eq!(size, SETTINGS.min);
+    }
+
+    #[test]
+    fn test_key_tells_monitors_and_scales_apart() {
+        assert_eq!(
+            key("settings", "DELL U2720Q", 1.5),
+            "settings@DELL U2720Q@150%"
+        );
+        assert_ne!(key("settings", "A", 1.25), key("settings", "A", 1.5));
+    }
+}
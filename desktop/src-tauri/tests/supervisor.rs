@@ -0,0 +1,126 @@
+//! The supervisor against a fake backend process. Run with
+//! `cargo test --features test-support`.
+#![cfg(feature = "test-support")]
+
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use meshmonitor_desktop_lib::config::Config;
+use meshmonitor_desktop_lib::diagnose::{self, Diagnosis};
+use meshmonitor_desktop_lib::test_support::{self, FakeBackend, Script};
+use meshmonitor_desktop_lib::watchdog::Heartbeat;
+use meshmonitor_desktop_lib::{api, backend_record, port_release};
+
+/// How long any one step may take on a slow CI runner.
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Runs the fake backends the tests spawn.
+#[test]
+fn fake_backend() {
+    test_support::fake_backend_main();
+}
+
+fn config() -> Config {
+    Config {
+        health_check_timeout_secs: 1,
+        ..Config::default()
+    }
+}
+
+fn db_path(test: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "meshmonitor-supervisor-{}-{}.db",
+        test,
+        std::process::id()
+    ))
+}
+
+#[test]
+fn slow_start_is_waited_for() {
+    let config = config();
+    let backend = FakeBackend::spawn(&Script {
+        start_delay: Duration::from_secs(2),
+        ..Script::new(test_support::free_port())
+    })
+    .unwrap();
+    let spawned = Instant::now();
+    assert!(api::check_health(backend.port, &config).is_err());
+
+    // Not answering yet isn't a hang while the backend is starting
+    let mut heartbeat = Heartbeat::new(backend.pid(), spawned);
+    for _ in 0..5 {
+        assert!(!heartbeat.observe(false, Instant::now()));
+    }
+    let took = backend.wait_healthy(&config, TIMEOUT).unwrap();
+    assert!(
+        took >= Duration::from_millis(1500),
+        "healthy after {:?}",
+        took
+    );
+    assert!(!heartbeat.observe(true, Instant::now()));
+}
+
+#[test]
+fn crashed_backend_is_restarted_on_its_port() {
+    let config = config();
+    let port = test_support::free_port();
+    let script = Script {
+        crash_after: Some(Duration::from_secs(1)),
+        exit_code: 3,
+        ..Script::new(port)
+    };
+    let mut backend = FakeBackend::spawn(&script).unwrap();
+    backend.wait_healthy(&config, TIMEOUT).unwrap();
+    let pid = backend.pid();
+    let status = backend.wait_exit(TIMEOUT).expect("backend didn't exit");
+    assert_eq!(status.code(), Some(3));
+    assert!(!backend_record::is_alive(pid));
+
+    // The restart waits for the port, then the new backend gets it
+    port_release::wait_with(port, &db_path("crash"), TIMEOUT).unwrap();
+    let restarted = FakeBackend::spawn(&Script::new(port)).unwrap();
+    restarted.wait_healthy(&config, TIMEOUT).unwrap();
+    assert_ne!(restarted.pid(), pid);
+}
+
+#[test]
+fn port_conflict_is_diagnosed() {
+    let port = test_support::free_port();
+    let _other_program = TcpListener::bind(("0.0.0.0", port)).unwrap();
+    let mut backend = FakeBackend::spawn(&Script::new(port)).unwrap();
+    let status = backend.wait_exit(TIMEOUT).expect("backend didn't exit");
+    assert!(!status.success());
+    assert_eq!(
+        diagnose::classify(&backend.stderr()),
+        Some(Diagnosis::PortInUse(Some(port)))
+    );
+
+    let wait = port_release::wait_with(port, &db_path("conflict"), Duration::from_millis(500));
+    assert!(wait.unwrap_err().contains("in use by another program"));
+}
+
+#[test]
+fn hung_backend_is_detected() {
+    let config = config();
+    let mut backend = FakeBackend::spawn(&Script {
+        hang_after: Some(Duration::from_secs(1)),
+        ..Script::new(test_support::free_port())
+    })
+    .unwrap();
+    backend.wait_healthy(&config, TIMEOUT).unwrap();
+    let mut heartbeat = Heartbeat::new(backend.pid(), Instant::now());
+    assert!(!heartbeat.observe(true, Instant::now()));
+    std::thread::sleep(Duration::from_millis(1500));
+
+    let deadline = Instant::now() + TIMEOUT;
+    let mut hung = false;
+    while !hung && Instant::now() < deadline {
+        let answered = api::check_health(backend.port, &config).is_ok();
+        hung = heartbeat.observe(answered, Instant::now());
+    }
+    assert!(hung);
+    // Hung, not gone: the process still holds its PID
+    assert!(backend.child().try_wait().unwrap().is_none());
+    assert!(backend_record::is_alive(backend.pid()));
+}
@@ -25,6 +25,7 @@ import MqttPacketMonitorView from './components/MQTT/MqttPacketMonitorView';
 import AutomationTab from './components/AutomationTab';
 import { ToastProvider, useToast } from './components/ToastContainer';
 import DeviceNotificationToaster from './components/DeviceNotificationToaster';
+import DesktopEventToaster from './components/DesktopEventToaster';
 import { RebootModal } from './components/RebootModal';
 import { AppBanners } from './components/AppBanners';
 import { AppHeader } from './components/AppHeader';
@@ -1394,6 +1395,8 @@ function App() {
       setActiveTab,
       setSelectedChannel,
       setSelectedDMNode,
+      setMapCenterTarget,
+      setSelectedNodeId,
       selectedChannelRef,
     },
     {
@@ -3918,6 +3921,7 @@ const AppWithToast = () => {
               <AutomationProvider baseUrl={appBasename}>
               <ToastProvider>
                 <DeviceNotificationToaster />
+                <DesktopEventToaster />
                 <SaveBarProvider>
                   <App />
                 </SaveBarProvider>
@@ -0,0 +1,81 @@
+/**
+ * DesktopEventToaster
+ *
+ * Shows supervisor events from MeshMonitor Desktop (the backend stopping
+ * unexpectedly, the watchdog restarting it, the backend running again) as
+ * toasts when the web UI runs in the desktop app's own window. The desktop
+ * injects `window.__meshmonitorDesktop`, which keeps recent events in session
+ * storage and announces new ones with a `meshmonitor:desktop-event` event.
+ * Events raised while the page was away (the status page stands in during a
+ * backend restart) are shown on the next load. Renders nothing in a browser.
+ */
+
+import { useEffect } from 'react';
+import { useToast } from './ToastContainer';
+
+interface DesktopEvent {
+  id: number;
+  /** Milliseconds since the epoch. */
+  at: number;
+  level: 'info' | 'warning' | 'error';
+  message: string;
+}
+
+declare global {
+  interface Window {
+    __meshmonitorDesktop?: {
+      events: () => DesktopEvent[];
+    };
+  }
+}
+
+/** Highest event id shown in this window, kept across reloads. */
+const SHOWN_KEY = 'meshmonitor.desktopEventsShown';
+
+/** Events older than this when the page loads are no longer news. */
+const STALE_AFTER_MS = 10 * 60 * 1000;
+
+function lastShown(): number {
+  try {
+    return Number(sessionStorage.getItem(SHOWN_KEY)) || 0;
+  } catch {
+    return 0;
+  }
+}
+
+function markShown(id: number): void {
+  try {
+    sessionStorage.setItem(SHOWN_KEY, String(id));
+  } catch {
+    // Storage unavailable; events may be shown again after a reload
+  }
+}
+
+export default function DesktopEventToaster(): null {
+  const { showToast } = useToast();
+
+  useEffect(() => {
+    const bridge = window.__meshmonitorDesktop;
+    if (!bridge) return;
+
+    const show = (event: DesktopEvent) => {
+      if (event.id <= lastShown()) return;
+      markShown(event.id);
+      showToast(event.message, event.level, 8000);
+    };
+
+    const now = Date.now();
+    bridge
+      .events()
+      .filter((event) => now - event.at < STALE_AFTER_MS)
+      .forEach(show);
+
+    const handler = (e: Event) => show((e as CustomEvent<DesktopEvent>).detail);
+    window.addEventListener('meshmonitor:desktop-event', handler);
+    return () => {
+      window.removeEventListener('meshmonitor:desktop-event', handler);
+    };
+  }, [showToast]);
+
+  return null;
+}
@@ -16,6 +16,8 @@ export interface SQLiteDriverOptions {
   enableWAL?: boolean;
   enableForeignKeys?: boolean;
   busyTimeout?: number;
+  /** WAL pages written before an automatic checkpoint */
+  walAutocheckpoint?: number;
 }
 
 /**
@@ -31,6 +33,7 @@ export function createSQLiteDriver(options: SQLiteDriverOptions): {
     enableWAL = true,
     enableForeignKeys = true,
     busyTimeout = 5000,
+    walAutocheckpoint = 1000,
   } = options;
 
   logger.debug(`[SQLite Driver] Initializing database at: ${databasePath}`);
@@ -60,7 +63,8 @@ export function createSQLiteDriver(options: SQLiteDriverOptions): {
   // Configure database pragmas
   if (enableWAL) {
     rawDb.pragma('journal_mode = WAL');
-    logger.debug('[SQLite Driver] WAL mode enabled');
+    rawDb.pragma(`wal_autocheckpoint = ${walAutocheckpoint}`);
+    logger.debug(`[SQLite Driver] WAL mode enabled, checkpoint every ${walAutocheckpoint} pages`);
   }
 
   if (enableForeignKeys) {
@@ -153,8 +153,11 @@ export async function createDatabase(config?: Partial<DatabaseConfig>): Promise<
     throw new Error('SQLite path is required when type is "sqlite"');
   }
 
+  const env = getEnvironmentConfig();
   const { db, close } = createSQLiteDriver({
     databasePath: finalConfig.sqlitePath,
+    busyTimeout: env.sqliteBusyTimeoutMs,
+    walAutocheckpoint: env.sqliteWalAutocheckpoint,
   });
 
   return {
@@ -5,7 +5,7 @@
  * It handles:
  * 1. Capturing navigation data from service worker messages or URL hash
  * 2. Waiting for app to be ready (connected)
- * 3. Navigating to the correct channel/DM
+ * 3. Navigating to the correct channel/DM or node, or centering the map
  * 4. Scrolling to and highlighting the target message
  */
 
@@ -14,12 +14,16 @@ import { logger } from '../utils/logger';
 import { usePushNotificationNavigation } from './usePushNotificationNavigation';
 
 interface NavigationCallbacks {
-  /** Set the active tab ('channels' | 'messages') */
-  setActiveTab: (tab: 'channels' | 'messages') => void;
+  /** Set the active tab ('channels' | 'messages' | 'nodes') */
+  setActiveTab: (tab: 'channels' | 'messages' | 'nodes') => void;
   /** Set the selected channel index */
   setSelectedChannel: (channelId: number) => void;
   /** Set the selected DM node ID */
   setSelectedDMNode: (nodeId: string) => void;
+  /** Center the map on [latitude, longitude] */
+  setMapCenterTarget: (target: [number, number]) => void;
+  /** Select a node on the Nodes page, showing its details */
+  setSelectedNodeId: (nodeId: string) => void;
   /** Ref to keep selectedChannel in sync */
   selectedChannelRef?: MutableRefObject<number>;
 }
@@ -53,7 +57,14 @@ export function useNotificationNavigationHandler(
   // Use ref to persist scroll target across re-renders
   const scrollToMessageIdRef = useRef<string | null>(null);
 
-  const { setActiveTab, setSelectedChannel, setSelectedDMNode, selectedChannelRef } = callbacks;
+  const {
+    setActiveTab,
+    setSelectedChannel,
+    setSelectedDMNode,
+    setMapCenterTarget,
+    setSelectedNodeId,
+    selectedChannelRef,
+  } = callbacks;
   const { connectionStatus, channels, activeTab, selectedChannel, selectedDMNode } = state;
 
   // Sync ref with state
@@ -105,6 +116,18 @@ export function useNotificationNavigationHandler(
       }
 
       logger.info(`📬 Navigated to DM with node ${pendingNavigation.senderNodeId}`);
+    } else if (
+      pendingNavigation.type === 'map' &&
+      typeof pendingNavigation.latitude === 'number' &&
+      typeof pendingNavigation.longitude === 'number'
+    ) {
+      setActiveTab('nodes');
+      setMapCenterTarget([pendingNavigation.latitude, pendingNavigation.longitude]);
+      logger.info(`📬 Centered the map on ${pendingNavigation.latitude}, ${pendingNavigation.longitude}`);
+    } else if (pendingNavigation.type === 'node' && pendingNavigation.nodeId) {
+      setActiveTab('nodes');
+      setSelectedNodeId(pendingNavigation.nodeId);
+      logger.info(`📬 Opened node ${pendingNavigation.nodeId}`);
     }
 
     // Clear the pending navigation after handling
@@ -115,6 +138,8 @@ export function useNotificationNavigationHandler(
     setActiveTab,
     setSelectedChannel,
     setSelectedDMNode,
+    setMapCenterTarget,
+    setSelectedNodeId,
     selectedChannelRef,
     connectionStatus,
     channels,
@@ -227,6 +227,29 @@ describe('usePushNotificationNavigation', () => {
       });
     });
 
+    it('should parse node detail navigation data from URL hash via hashchange event', async () => {
+      const navigationData: NotificationNavigationData = {
+        type: 'node',
+        nodeId: '!a1b2c3d4',
+      };
+
+      const { result } = renderHook(() => usePushNotificationNavigation());
+
+      // Percent-encoded the way MeshMonitor Desktop's notifications send it
+      act(() => {
+        window.history.replaceState(
+          null,
+          '',
+          `${window.location.pathname}#notificationNav=${encodeURIComponent(JSON.stringify(navigationData))}`
+        );
+        window.dispatchEvent(new HashChangeEvent('hashchange'));
+      });
+
+      await waitFor(() => {
+        expect(result.current.pendingNavigation).toEqual(navigationData);
+      });
+    });
+
     it('should handle invalid JSON in URL hash gracefully', async () => {
       const { result } = renderHook(() => usePushNotificationNavigation());
       
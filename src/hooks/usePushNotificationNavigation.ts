@@ -12,10 +12,15 @@ import { useState, useEffect, useCallback } from 'react';
 import { logger } from '../utils/logger';
 
 export interface NotificationNavigationData {
-  type: 'channel' | 'dm';
+  type: 'channel' | 'dm' | 'map' | 'node';
   channelId?: number;
   messageId?: string;
   senderNodeId?: string;
+  /** Node whose details to show, for `type: 'node'` (sent by MeshMonitor Desktop) */
+  nodeId?: string;
+  /** Map position to center on, for `type: 'map'` (sent by MeshMonitor Desktop) */
+  latitude?: number;
+  longitude?: number;
 }
 
 interface UsePushNotificationNavigationReturn {
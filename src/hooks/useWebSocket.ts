@@ -32,6 +32,21 @@ export interface WebSocketState {
   socket: Socket | null;
 }
 
+/**
+ * Reconnection timing the desktop app sets in its web UI window from its
+ * polling preset; a `meshmonitor:socket-options` event announces changes.
+ */
+interface DesktopSocketOptions {
+  reconnectionDelay: number;
+  reconnectionDelayMax: number;
+}
+
+declare global {
+  interface Window {
+    __meshmonitorSocketOptions?: DesktopSocketOptions;
+  }
+}
+
 // --- MeshCore push-event payloads ---------------------------------------------
 //
 // Server-side these are emitted by `dataEventEmitter` and forwarded by
@@ -286,6 +301,7 @@ export function useWebSocket(enabled: boolean = true): WebSocketState {
     // Explicit URL is required — Socket.io's auto-detection fails when a <base> tag is present
     const socketPath = `${appBasename}/socket.io`;
     const socketUrl = `${window.location.protocol}//${window.location.host}`;
+    const desktopOptions = window.__meshmonitorSocketOptions;
 
     const socket = io(socketUrl, {
       path: socketPath,
@@ -293,10 +309,19 @@ export function useWebSocket(enabled: boolean = true): WebSocketState {
       transports: ['websocket', 'polling'],
       reconnection: true,
       reconnectionAttempts: 10,
-      reconnectionDelay: 1000,
-      reconnectionDelayMax: 5000,
+      reconnectionDelay: desktopOptions?.reconnectionDelay ?? 1000,
+      reconnectionDelayMax: desktopOptions?.reconnectionDelayMax ?? 5000,
     });
 
+    const applyDesktopOptions = () => {
+      const options = window.__meshmonitorSocketOptions;
+      if (options) {
+        socket.io.reconnectionDelay(options.reconnectionDelay);
+        socket.io.reconnectionDelayMax(options.reconnectionDelayMax);
+      }
+    };
+    window.addEventListener('meshmonitor:socket-options', applyDesktopOptions);
+
     socketRef.current = socket;
     // Expose the socket immediately so consumers can attach listeners before
     // the first `connect` fires.
@@ -388,6 +413,7 @@ export function useWebSocket(enabled: boolean = true): WebSocketState {
 
     // Cleanup on unmount
     return () => {
+      window.removeEventListener('meshmonitor:socket-options', applyDesktopOptions);
       socket.disconnect();
       socketRef.current = null;
     };
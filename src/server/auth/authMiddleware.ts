@@ -11,6 +11,7 @@ import databaseService from '../../services/database.js';
 import { logger } from '../../utils/logger.js';
 import { extractProxyUser, isAdminUser, isNormalProxyUserAllowed } from './proxyAuth.js';
 import { getEnvironmentConfig } from '../config/environment.js';
+import { isSharedSession } from './shareLinks.js';
 
 /**
  * Attach user to request if authenticated (optional auth)
@@ -414,6 +415,13 @@ export function requirePermission(
         if (anonymousUser && anonymousUser.isActive) {
           user = anonymousUser;
         }
+        // Share links are read-only, whatever anonymous users may do
+        if (user && action === 'write' && isSharedSession(req.session)) {
+          return res.status(403).json({
+            error: 'Shared links are read-only',
+            code: 'FORBIDDEN'
+          });
+        }
       }
 
       // If still no user, deny access
@@ -0,0 +1,65 @@
+/**
+ * Login Handoff Tests
+ *
+ * Tests token expiry and single use, and redeeming ?login=<token> into a
+ * logged-in session.
+ */
+
+import { describe, it, expect, vi, beforeEach } from 'vitest';
+import request from 'supertest';
+import express from 'express';
+import session from 'express-session';
+import {
+  clearLoginTokens,
+  createLoginToken,
+  loginHandoffMiddleware,
+  redeemLoginToken,
+} from './loginHandoff.js';
+
+vi.mock('../../services/database.js', () => ({
+  default: { auditLogAsync: vi.fn().mockResolvedValue(undefined) },
+}));
+
+const admin = { id: 2, username: 'admin', isAdmin: true };
+
+function app() {
+  const app = express();
+  app.use(session({ secret: 'test', resave: false, saveUninitialized: false }));
+  app.use(loginHandoffMiddleware);
+  app.get('/me', (req, res) => {
+    res.json({ username: req.session.username ?? null });
+  });
+  return app;
+}
+
+beforeEach(() => {
+  clearLoginTokens();
+});
+
+describe('login handoff', () => {
+  it('expires after a minute and can be redeemed once', () => {
+    const now = Date.UTC(2026, 9, 14, 12, 0, 0);
+    const expired = createLoginToken(admin, now);
+    expect(expired.expiresAt).toBe(now + 60 * 1000);
+    expect(redeemLoginToken(expired.token, expired.expiresAt)).toBeNull();
+
+    const { token } = createLoginToken(admin, now);
+    expect(redeemLoginToken(token, now + 1000)).toEqual(admin);
+    expect(redeemLoginToken(token, now + 2000)).toBeNull();
+  });
+
+  it('logs the session in and strips the token from the URL', async () => {
+    const { token } = createLoginToken(admin);
+    const agent = request.agent(app());
+
+    const redeemed = await agent.get(`/me?login=${token}&tab=map`);
+    expect(redeemed.status).toBe(302);
+    expect(redeemed.headers.location).toBe('/me?tab=map');
+    expect((await agent.get('/me')).body.username).toBe('admin');
+
+    // A second use leaves a new browser logged out
+    const other = request.agent(app());
+    await other.get(`/me?login=${token}`);
+    expect((await other.get('/me')).body.username).toBeNull();
+  });
+});
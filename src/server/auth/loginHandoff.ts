@@ -0,0 +1,113 @@
+/**
+ * Login Handoff
+ *
+ * One-time login tokens for MeshMonitor Desktop, created through POST
+ * /desktop/login-tokens just before it opens the web UI. Opening any page
+ * with `?login=<token>` logs the session in as the token's user, so someone
+ * who just launched the app isn't asked for a password. A token can be
+ * redeemed once, within `LOGIN_TOKEN_TTL_MS` of being created.
+ *
+ * Tokens are kept in memory: restarting the backend revokes them all.
+ */
+
+import { randomBytes } from 'crypto';
+import { Request, Response, NextFunction } from 'express';
+import databaseService from '../../services/database.js';
+import { logger } from '../../utils/logger.js';
+
+/** How long a token stays redeemable: one minute. */
+export const LOGIN_TOKEN_TTL_MS = 60 * 1000;
+
+export interface HandoffUser {
+  id: number;
+  username: string;
+  isAdmin: boolean;
+}
+
+export interface LoginToken {
+  token: string;
+  expiresAt: number;
+}
+
+/** Token -> user and expiry (Unix ms) */
+const tokens = new Map<string, { user: HandoffUser; expiresAt: number }>();
+
+/** Create a token logging in as `user`. */
+export function createLoginToken(user: HandoffUser, now: number = Date.now()): LoginToken {
+  pruneExpired(now);
+  const token = randomBytes(24).toString('base64url');
+  const expiresAt = now + LOGIN_TOKEN_TTL_MS;
+  tokens.set(token, { user, expiresAt });
+  return { token, expiresAt };
+}
+
+/** The user of a valid `token`, which is used up, or null if it is unknown or expired. */
+export function redeemLoginToken(token: string, now: number = Date.now()): HandoffUser | null {
+  const entry = tokens.get(token);
+  tokens.delete(token);
+  if (!entry || entry.expiresAt <= now) {
+    return null;
+  }
+  return entry.user;
+}
+
+/** Forget every token (tests). */
+export function clearLoginTokens(): void {
+  tokens.clear();
+}
+
+function pruneExpired(now: number): void {
+  for (const [token, { expiresAt }] of tokens) {
+    if (expiresAt <= now) {
+      tokens.delete(token);
+    }
+  }
+}
+
+/**
+ * Redeem `?login=<token>` on a page load: log the session in and redirect to
+ * the same URL without the token, so it doesn't linger in the browser's
+ * history. Unknown, used or expired tokens are dropped the same way, leaving
+ * the session as it was.
+ */
+export function loginHandoffMiddleware(req: Request, res: Response, next: NextFunction): void {
+  const token = req.query.login;
+  if (req.method !== 'GET' || typeof token !== 'string') {
+    next();
+    return;
+  }
+  const url = new URL(req.originalUrl, 'http://localhost');
+  url.searchParams.delete('login');
+  const location = url.pathname + url.search;
+
+  const user = redeemLoginToken(token);
+  if (!user) {
+    res.redirect(302, location);
+    return;
+  }
+  // A new session ID on login, keeping the CSRF token the page may have cached
+  const csrfToken = req.session.csrfToken;
+  req.session.regenerate((err) => {
+    if (err) {
+      logger.error('Session regeneration failed:', err);
+      res.redirect(302, location);
+      return;
+    }
+    if (csrfToken) {
+      req.session.csrfToken = csrfToken;
+    }
+    req.session.userId = user.id;
+    req.session.username = user.username;
+    req.session.authProvider = 'local';
+    req.session.isAdmin = user.isAdmin;
+    logger.info(`🔑 ${user.username} logged in by MeshMonitor Desktop`);
+    void databaseService.auditLogAsync(
+      user.id,
+      'login_success',
+      'auth',
+      JSON.stringify({ username: user.username, authProvider: 'desktop' }),
+      req.ip || null
+    );
+    req.session.save(() => res.redirect(302, location));
+  });
+}
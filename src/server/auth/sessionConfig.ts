@@ -30,6 +30,8 @@ declare module 'express-session' {
     csrfToken?: string;
     // MFA pending verification
     pendingMfaUserId?: number;
+    // Expiry (Unix ms) of the share link this session was opened with
+    shareExpiresAt?: number;
   }
 }
 
@@ -0,0 +1,62 @@
+/**
+ * Share Links Tests
+ *
+ * Tests link expiry and redeeming ?share=<token> into a shared session.
+ */
+
+import { describe, it, expect, beforeEach } from 'vitest';
+import request from 'supertest';
+import express from 'express';
+import session from 'express-session';
+import {
+  clearShareLinks,
+  createShareLink,
+  isSharedSession,
+  shareLinkExpiry,
+  shareLinkMiddleware,
+} from './shareLinks.js';
+
+function app() {
+  const app = express();
+  app.use(session({ secret: 'test', resave: false, saveUninitialized: false }));
+  app.use(shareLinkMiddleware);
+  app.get('/shared', (req, res) => {
+    res.json({ shared: isSharedSession(req.session) });
+  });
+  return app;
+}
+
+beforeEach(() => {
+  clearShareLinks();
+});
+
+describe('share links', () => {
+  it('expire after the requested minutes', () => {
+    const now = Date.UTC(2026, 9, 14, 12, 0, 0);
+    const link = createShareLink(60, now);
+    expect(link.expiresAt).toBe(now + 60 * 60 * 1000);
+    expect(shareLinkExpiry(link.token, now + 59 * 60 * 1000)).toBe(link.expiresAt);
+    expect(shareLinkExpiry(link.token, link.expiresAt)).toBeNull();
+    expect(shareLinkExpiry('unknown', now)).toBeNull();
+  });
+
+  it('redeems a valid token into the session and strips it from the URL', async () => {
+    const { token } = createShareLink(60);
+    const agent = request.agent(app());
+
+    const redeemed = await agent.get(`/shared?share=${token}&tab=map`);
+    expect(redeemed.status).toBe(302);
+    expect(redeemed.headers.location).toBe('/shared?tab=map');
+
+    const res = await agent.get('/shared');
+    expect(res.body.shared).toBe(true);
+  });
+
+  it('strips an unknown token without sharing the session', async () => {
+    const agent = request.agent(app());
+    const redeemed = await agent.get('/shared?share=forged');
+    expect(redeemed.status).toBe(302);
+    expect(redeemed.headers.location).toBe('/shared');
+    expect((await agent.get('/shared')).body.shared).toBe(false);
+  });
+});
@@ -0,0 +1,88 @@
+/**
+ * Share Links
+ *
+ * Time-limited, read-only access for a visitor, created by MeshMonitor
+ * Desktop through POST /desktop/share-links. Opening any page with
+ * `?share=<token>` marks the visitor's session as shared until the link
+ * expires. A shared session is treated as the anonymous user — so it sees
+ * what anonymous users may see — even when DISABLE_ANONYMOUS would otherwise
+ * send it to the login page, and it may never write.
+ *
+ * Links are kept in memory: restarting the backend revokes them all.
+ */
+
+import { randomBytes } from 'crypto';
+import { Request, Response, NextFunction } from 'express';
+import { Session, SessionData } from 'express-session';
+import { logger } from '../../utils/logger.js';
+
+/** Longest a link may stay valid: one week. */
+export const MAX_SHARE_LINK_MINUTES = 7 * 24 * 60;
+
+/** Token -> expiry (Unix ms) */
+const links = new Map<string, number>();
+
+export interface ShareLink {
+  token: string;
+  expiresAt: number;
+}
+
+/** Create a link valid for `minutes`. */
+export function createShareLink(minutes: number, now: number = Date.now()): ShareLink {
+  pruneExpired(now);
+  const token = randomBytes(24).toString('base64url');
+  const expiresAt = now + minutes * 60 * 1000;
+  links.set(token, expiresAt);
+  return { token, expiresAt };
+}
+
+/** Expiry of a valid `token`, or null if it is unknown or expired. */
+export function shareLinkExpiry(token: string, now: number = Date.now()): number | null {
+  const expiresAt = links.get(token);
+  if (expiresAt === undefined || expiresAt <= now) {
+    return null;
+  }
+  return expiresAt;
+}
+
+/** Whether `session` was opened through a share link that hasn't expired. */
+export function isSharedSession(
+  session: (Session & Partial<SessionData>) | undefined,
+  now: number = Date.now()
+): boolean {
+  return (session?.shareExpiresAt ?? 0) > now;
+}
+
+/** Forget every link (tests). */
+export function clearShareLinks(): void {
+  links.clear();
+}
+
+function pruneExpired(now: number): void {
+  for (const [token, expiresAt] of links) {
+    if (expiresAt <= now) {
+      links.delete(token);
+    }
+  }
+}
+
+/**
+ * Redeem `?share=<token>` on a page load: mark the session as shared and
+ * redirect to the same URL without the token, so it doesn't linger in the
+ * visitor's history. Unknown or expired tokens are dropped the same way.
+ */
+export function shareLinkMiddleware(req: Request, res: Response, next: NextFunction): void {
+  const token = req.query.share;
+  if (req.method !== 'GET' || typeof token !== 'string') {
+    next();
+    return;
+  }
+  const expiresAt = shareLinkExpiry(token);
+  if (expiresAt !== null) {
+    req.session.shareExpiresAt = expiresAt;
+    logger.info(`🔗 Share link opened from ${req.ip ?? 'unknown'}`);
+  }
+  const url = new URL(req.originalUrl, 'http://localhost');
+  url.searchParams.delete('share');
+  res.redirect(302, url.pathname + url.search);
+}
@@ -0,0 +1,63 @@
+/**
+ * SQLite Tuning Environment Configuration Tests
+ *
+ * Tests SQLITE_BUSY_TIMEOUT_MS and SQLITE_WAL_AUTOCHECKPOINT parsing.
+ */
+
+import { describe, it, expect, afterEach } from 'vitest';
+import { resetEnvironmentConfig, getEnvironmentConfig } from './environment.js';
+
+describe('SQLite tuning configuration', () => {
+  const originalBusyTimeout = process.env.SQLITE_BUSY_TIMEOUT_MS;
+  const originalAutocheckpoint = process.env.SQLITE_WAL_AUTOCHECKPOINT;
+
+  afterEach(() => {
+    for (const [name, value] of [
+      ['SQLITE_BUSY_TIMEOUT_MS', originalBusyTimeout],
+      ['SQLITE_WAL_AUTOCHECKPOINT', originalAutocheckpoint],
+    ] as const) {
+      if (value !== undefined) {
+        process.env[name] = value;
+      } else {
+        delete process.env[name];
+      }
+    }
+    resetEnvironmentConfig();
+  });
+
+  it('defaults to a 5 second busy timeout and 1000-page checkpoints', () => {
+    delete process.env.SQLITE_BUSY_TIMEOUT_MS;
+    delete process.env.SQLITE_WAL_AUTOCHECKPOINT;
+    resetEnvironmentConfig();
+
+    const config = getEnvironmentConfig();
+
+    expect(config.sqliteBusyTimeoutMs).toBe(5000);
+    expect(config.sqliteBusyTimeoutMsProvided).toBe(false);
+    expect(config.sqliteWalAutocheckpoint).toBe(1000);
+    expect(config.sqliteWalAutocheckpointProvided).toBe(false);
+  });
+
+  it('accepts positive values', () => {
+    process.env.SQLITE_BUSY_TIMEOUT_MS = '30000';
+    process.env.SQLITE_WAL_AUTOCHECKPOINT = '4000';
+    resetEnvironmentConfig();
+
+    const config = getEnvironmentConfig();
+
+    expect(config.sqliteBusyTimeoutMs).toBe(30000);
+    expect(config.sqliteBusyTimeoutMsProvided).toBe(true);
+    expect(config.sqliteWalAutocheckpoint).toBe(4000);
+  });
+
+  it('falls back to the defaults for zero or invalid values', () => {
+    process.env.SQLITE_BUSY_TIMEOUT_MS = '0';
+    process.env.SQLITE_WAL_AUTOCHECKPOINT = 'often';
+    resetEnvironmentConfig();
+
+    const config = getEnvironmentConfig();
+
+    expect(config.sqliteBusyTimeoutMs).toBe(5000);
+    expect(config.sqliteWalAutocheckpoint).toBe(1000);
+  });
+});
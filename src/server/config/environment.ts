@@ -199,6 +199,8 @@ export interface EnvironmentConfig {
   trustProxy: boolean | number | string;
   trustProxyProvided: boolean;
   versionCheckDisabled: boolean;
+  /** Start without connecting to any source (set by the desktop app after a crash loop). */
+  safeMode: boolean;
 
   // Session/Security
   sessionSecret: string;
@@ -217,6 +219,10 @@ export interface EnvironmentConfig {
   // Database
   databasePath: string;
   databasePathProvided: boolean;
+  sqliteBusyTimeoutMs: number;
+  sqliteBusyTimeoutMsProvided: boolean;
+  sqliteWalAutocheckpoint: number;
+  sqliteWalAutocheckpointProvided: boolean;
   databaseUrl: string | undefined;
   databaseUrlProvided: boolean;
   databaseType: 'sqlite' | 'postgres' | 'mysql';
@@ -466,6 +472,18 @@ export function loadEnvironmentConfig(): EnvironmentConfig {
     value: process.env.DATABASE_PATH || '/data/meshmonitor.db',
     wasProvided: process.env.DATABASE_PATH !== undefined
   };
+  // How long SQLite retries a locked database, and how many WAL pages
+  // accumulate before an automatic checkpoint; raise both on slow storage
+  const sqliteBusyTimeoutMs = parsePositiveInt(
+    'SQLITE_BUSY_TIMEOUT_MS',
+    process.env.SQLITE_BUSY_TIMEOUT_MS,
+    5000
+  );
+  const sqliteWalAutocheckpoint = parsePositiveInt(
+    'SQLITE_WAL_AUTOCHECKPOINT',
+    process.env.SQLITE_WAL_AUTOCHECKPOINT,
+    1000 // pages, SQLite's default
+  );
 
   // DATABASE_URL for PostgreSQL support
   const databaseUrl = {
@@ -503,6 +521,12 @@ export function loadEnvironmentConfig(): EnvironmentConfig {
     false
   );
 
+  const safeMode = parseBoolean(
+    'SAFE_MODE',
+    process.env.SAFE_MODE,
+    false
+  );
+
   // Meshtastic
   const meshtasticNodeIp = {
     value: process.env.MESHTASTIC_NODE_IP || '192.168.1.100',
@@ -748,6 +772,7 @@ export function loadEnvironmentConfig(): EnvironmentConfig {
   logger.debug(`   IFRAME_ALLOWED_ORIGINS: ${iframeAllowedOrigins.value.length > 0 ? iframeAllowedOrigins.value.join(',') : '(not set - iframe embedding blocked)'} (${src(iframeAllowedOrigins.wasProvided)})`);
   logger.debug(`   TRUST_PROXY: ${trustProxy.value} (${src(trustProxy.wasProvided)})`);
   logger.info(`   VERSION_CHECK_DISABLED: ${versionCheckDisabled.value} (${src(versionCheckDisabled.wasProvided)})`);
+  logger.info(`   SAFE_MODE: ${safeMode.value} (${src(safeMode.wasProvided)})`);
   logger.debug('   --- Session/Security ---');
   logger.debug(`   SESSION_SECRET: ${sessionSecretProvided ? '***provided***' : '(auto-generated)'}`);
   logger.debug(`   SESSION_COOKIE_NAME: ${sessionCookieName.value} (${src(sessionCookieName.wasProvided)})`);
@@ -761,6 +786,8 @@ export function loadEnvironmentConfig(): EnvironmentConfig {
     logger.info(`   DATABASE_URL: ***provided*** (${databaseType})`);
   } else {
     logger.info(`   DATABASE_PATH: ${databasePath.value} (${src(databasePath.wasProvided)})`);
+    logger.debug(`   SQLITE_BUSY_TIMEOUT_MS: ${sqliteBusyTimeoutMs.value}ms (${src(sqliteBusyTimeoutMs.wasProvided)})`);
+    logger.debug(`   SQLITE_WAL_AUTOCHECKPOINT: ${sqliteWalAutocheckpoint.value} pages (${src(sqliteWalAutocheckpoint.wasProvided)})`);
   }
   logger.debug(`   TRACEROUTE_HISTORY_LIMIT: ${tracerouteHistoryLimit.value} (${src(tracerouteHistoryLimit.wasProvided)})`);
   logger.debug('   --- Meshtastic ---');
@@ -838,6 +865,7 @@ export function loadEnvironmentConfig(): EnvironmentConfig {
     trustProxy: trustProxy.value,
     trustProxyProvided: trustProxy.wasProvided,
     versionCheckDisabled: versionCheckDisabled.value,
+    safeMode: safeMode.value,
 
     // Session/Security
     sessionSecret,
@@ -856,6 +884,10 @@ export function loadEnvironmentConfig(): EnvironmentConfig {
     // Database
     databasePath: databasePath.value,
     databasePathProvided: databasePath.wasProvided,
+    sqliteBusyTimeoutMs: sqliteBusyTimeoutMs.value,
+    sqliteBusyTimeoutMsProvided: sqliteBusyTimeoutMs.wasProvided,
+    sqliteWalAutocheckpoint: sqliteWalAutocheckpoint.value,
+    sqliteWalAutocheckpointProvided: sqliteWalAutocheckpoint.wasProvided,
     databaseUrl: databaseUrl.value,
     databaseUrlProvided: databaseUrl.wasProvided,
     databaseType,
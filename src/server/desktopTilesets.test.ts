@@ -0,0 +1,91 @@
+/**
+ * Desktop Tilesets Tests
+ *
+ * Tests that DESKTOP_TILESETS replaces only the desktop's own entries in the
+ * customTilesets setting, and leaves the setting alone when nothing changed.
+ */
+
+import { describe, it, expect, vi } from 'vitest';
+import { applyDesktopTilesets, mergeDesktopTilesets, parseDesktopTilesets } from './desktopTilesets.js';
+
+const clubTiles = {
+  id: 'custom-desktop-club',
+  name: 'Club tiles',
+  url: 'http://127.0.0.1:8090/club/{z}/{x}/{y}.png',
+  attribution: '© Club',
+  maxZoom: 18,
+};
+
+const userTileset = {
+  id: 'custom-1700000000000',
+  name: 'My tiles',
+  url: 'https://tiles.example.org/{z}/{x}/{y}.png',
+  attribution: '',
+  maxZoom: 19,
+  description: '',
+  createdAt: 1,
+  updatedAt: 1,
+  isVector: false,
+};
+
+function dbWith(stored: string | null) {
+  return {
+    settings: {
+      getSetting: vi.fn().mockResolvedValue(stored),
+      setSetting: vi.fn().mockResolvedValue(undefined),
+    },
+  };
+}
+
+describe('parseDesktopTilesets', () => {
+  it('drops malformed entries and ids outside the desktop prefix', () => {
+    const json = JSON.stringify([clubTiles, { id: 'custom-other', name: 'x', url: 'x', attribution: '', maxZoom: 1 }, {}]);
+    expect(parseDesktopTilesets(json)).toEqual([clubTiles]);
+  });
+
+  it('rejects anything but an array', () => {
+    expect(() => parseDesktopTilesets('{}')).toThrow('JSON array');
+  });
+});
+
+describe('mergeDesktopTilesets', () => {
+  it('keeps user tilesets and replaces stale desktop ones', () => {
+    const stale = { ...clubTiles, id: 'custom-desktop-gone', description: '', createdAt: 5, updatedAt: 5 };
+    const merged = mergeDesktopTilesets([userTileset, stale], [clubTiles], 100);
+    expect(merged.map(tileset => tileset.id)).toEqual([userTileset.id, clubTiles.id]);
+    expect(merged[0]).toEqual(userTileset);
+    expect(merged[1]).toMatchObject({ createdAt: 100, updatedAt: 100 });
+  });
+
+  it('only bumps updatedAt when an entry changed', () => {
+    const [first] = mergeDesktopTilesets([], [clubTiles], 100);
+    expect(mergeDesktopTilesets([first], [clubTiles], 200)[0]).toMatchObject({ createdAt: 100, updatedAt: 100 });
+    const renamed = { ...clubTiles, name: 'Club tiles (new)' };
+    expect(mergeDesktopTilesets([first], [renamed], 200)[0]).toMatchObject({ createdAt: 100, updatedAt: 200 });
+  });
+});
+
+describe('applyDesktopTilesets', () => {
+  it('does nothing outside the desktop app', async () => {
+    const db = dbWith(null);
+    expect(await applyDesktopTilesets(db, undefined)).toBe(false);
+    expect(db.settings.getSetting).not.toHaveBeenCalled();
+  });
+
+  it('stores the desktop tilesets next to the user ones', async () => {
+    const db = dbWith(JSON.stringify([userTileset]));
+    expect(await applyDesktopTilesets(db, JSON.stringify([clubTiles]))).toBe(true);
+    const stored = JSON.parse(db.settings.setSetting.mock.calls[0][1]);
+    expect(stored.map((tileset: { id: string }) => tileset.id)).toEqual([userTileset.id, clubTiles.id]);
+  });
+
+  it('leaves the setting alone when nothing changed', async () => {
+    const db = dbWith(JSON.stringify([userTileset]));
+    expect(await applyDesktopTilesets(db, '[]')).toBe(false);
+    expect(db.settings.setSetting).not.toHaveBeenCalled();
+
+    const empty = dbWith(null);
+    expect(await applyDesktopTilesets(empty, '[]')).toBe(false);
+    expect(empty.settings.setSetting).not.toHaveBeenCalled();
+  });
+});
@@ -0,0 +1,122 @@
+/**
+ * Desktop tilesets — map entries for the tile servers configured in
+ * MeshMonitor Desktop.
+ *
+ * The desktop app serves a club's own tile server or a paid provider through
+ * its loopback tile cache, which adds the provider's API key, and passes the
+ * resulting custom tilesets as DESKTOP_TILESETS (a JSON array of
+ * `{ id, name, url, attribution, maxZoom }`). On startup they replace the
+ * `custom-desktop-` entries in the `customTilesets` setting, leaving the
+ * tilesets users added themselves alone, so the maps show up in the tileset
+ * picker without any setup in the web UI.
+ */
+
+import { logger } from '../utils/logger.js';
+
+export const DESKTOP_TILESET_PREFIX = 'custom-desktop-';
+
+export interface DesktopTileset {
+  id: string;
+  name: string;
+  url: string;
+  attribution: string;
+  maxZoom: number;
+}
+
+interface StoredTileset extends DesktopTileset {
+  description: string;
+  createdAt: number;
+  updatedAt: number;
+  [key: string]: unknown;
+}
+
+/** Structural subset of DatabaseService that applyDesktopTilesets needs. */
+export interface DesktopTilesetsDb {
+  settings: {
+    getSetting(key: string): Promise<string | null>;
+    setSetting(key: string, value: string): Promise<void>;
+  };
+}
+
+/** Parse DESKTOP_TILESETS, dropping malformed entries. */
+export function parseDesktopTilesets(json: string): DesktopTileset[] {
+  const parsed: unknown = JSON.parse(json);
+  if (!Array.isArray(parsed)) {
+    throw new Error('DESKTOP_TILESETS must be a JSON array');
+  }
+  return parsed.filter(
+    (tileset): tileset is DesktopTileset =>
+      typeof tileset?.id === 'string' &&
+      tileset.id.startsWith(DESKTOP_TILESET_PREFIX) &&
+      typeof tileset.name === 'string' &&
+      typeof tileset.url === 'string' &&
+      typeof tileset.attribution === 'string' &&
+      typeof tileset.maxZoom === 'number',
+  );
+}
+
+/**
+ * Replace the desktop's entries in `existing` with `desktop`, keeping each
+ * entry's creation time and bumping its update time only when it changed.
+ */
+export function mergeDesktopTilesets(
+  existing: StoredTileset[],
+  desktop: DesktopTileset[],
+  now: number,
+): StoredTileset[] {
+  const previous = new Map(
+    existing.filter(tileset => tileset.id.startsWith(DESKTOP_TILESET_PREFIX)).map(tileset => [tileset.id, tileset]),
+  );
+  const own = existing.filter(tileset => !tileset.id.startsWith(DESKTOP_TILESET_PREFIX));
+  const fromDesktop = desktop.map(tileset => {
+    const before = previous.get(tileset.id);
+    const unchanged =
+      before !== undefined &&
+      before.name === tileset.name &&
+      before.url === tileset.url &&
+      before.attribution === tileset.attribution &&
+      before.maxZoom === tileset.maxZoom;
+    return {
+      id: tileset.id,
+      name: tileset.name,
+      url: tileset.url,
+      attribution: tileset.attribution,
+      maxZoom: tileset.maxZoom,
+      description: 'Served by MeshMonitor Desktop',
+      createdAt: before?.createdAt ?? now,
+      updatedAt: unchanged ? before.updatedAt : now,
+    };
+  });
+  return [...own, ...fromDesktop];
+}
+
+/**
+ * Store the tilesets from DESKTOP_TILESETS in `customTilesets`. Returns
+ * whether the setting changed, so the caller can refresh the CSP host cache.
+ */
+export async function applyDesktopTilesets(db: DesktopTilesetsDb, json: string | undefined): Promise<boolean> {
+  if (json === undefined) {
+    return false;
+  }
+  const desktop = parseDesktopTilesets(json);
+  const stored = await db.settings.getSetting('customTilesets');
+  if (!stored && desktop.length === 0) {
+    return false;
+  }
+  let existing: StoredTileset[] = [];
+  try {
+    const parsed: unknown = stored ? JSON.parse(stored) : [];
+    existing = Array.isArray(parsed) ? parsed.filter(tileset => typeof tileset?.id === 'string') : [];
+  } catch {
+    logger.warn('🗺️ customTilesets setting is not valid JSON; replacing it with the desktop tilesets');
+  }
+
+  const merged = mergeDesktopTilesets(existing, desktop, Date.now());
+  const value = JSON.stringify(merged);
+  if (value === stored) {
+    return false;
+  }
+  await db.settings.setSetting('customTilesets', value);
+  logger.info(`🗺️ Updated ${desktop.length} tileset(s) from MeshMonitor Desktop`);
+  return true;
+}
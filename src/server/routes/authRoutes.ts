@@ -14,6 +14,7 @@ import {
   generateRandomString
 } from '../auth/oidcAuth.js';
 import { requireAuth } from '../auth/authMiddleware.js';
+import { isSharedSession } from '../auth/shareLinks.js';
 import { authLimiter } from '../middleware/rateLimiters.js';
 import { mfaService } from '../services/mfa.js';
 import databaseService from '../../services/database.js';
@@ -45,7 +46,8 @@ router.get('/status', async (req: Request, res: Response) => {
   try {
     const envConfig = getEnvironmentConfig();
     const localAuthDisabled = envConfig.disableLocalAuth;
-    const anonymousDisabled = envConfig.disableAnonymous;
+    // A visitor opened through a share link sees what anonymous users may see
+    const anonymousDisabled = envConfig.disableAnonymous && !isSharedSession(req.session);
 
     // Public branding config for the login page (safe to expose pre-auth).
     const branding = {
@@ -0,0 +1,184 @@
+/**
+ * Desktop Control Routes Tests
+ *
+ * Tests POST /desktop/shutdown token and loopback checks, that the router
+ * is empty without a DESKTOP_CONTROL_TOKEN, POST /desktop/share-links,
+ * POST /desktop/login-tokens and POST /desktop/device-restore.
+ */
+
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import request from 'supertest';
+import express from 'express';
+import { createDesktopControlRouter, setDesktopControlCallbacks } from './desktopControlRoutes.js';
+import { redeemLoginToken } from '../auth/loginHandoff.js';
+
+const previewRestore = vi.fn();
+const applyRestore = vi.fn();
+const getAllUsers = vi.fn();
+
+vi.mock('../utils/resolveSourceManager.js', () => ({
+  resolveSourceManager: () => ({ getLocalNodeInfo: () => ({ nodeNum: 1, nodeId: '!00000001' }) }),
+}));
+
+vi.mock('../../services/database.js', () => ({
+  default: { auth: { getAllUsers: () => getAllUsers() } },
+}));
+
+vi.mock('../services/deviceRestoreService.js', () => ({
+  previewRestore: (...args: unknown[]) => previewRestore(...args),
+  applyRestore: (...args: unknown[]) => applyRestore(...args),
+}));
+
+function appWith(token: string | undefined) {
+  const app = express();
+  app.use(express.json());
+  app.use('/', createDesktopControlRouter(token));
+  return app;
+}
+
+afterEach(() => {
+  vi.useRealTimers();
+  previewRestore.mockReset();
+  applyRestore.mockReset();
+  getAllUsers.mockReset();
+});
+
+describe('POST /desktop/shutdown', () => {
+  it('is not mounted without a control token', async () => {
+    const res = await request(appWith(undefined)).post('/desktop/shutdown').send({});
+    expect(res.status).toBe(404);
+  });
+
+  it('rejects a missing or wrong token', async () => {
+    const app = appWith('secret-token');
+    expect((await request(app).post('/desktop/shutdown').send({})).status).toBe(401);
+    const res = await request(app)
+      .post('/desktop/shutdown')
+      .set('Authorization', 'Bearer wrong-token')
+      .send({});
+    expect(res.status).toBe(401);
+  });
+
+  it('invokes gracefulShutdown with the right token', async () => {
+    vi.useFakeTimers({ toFake: ['setTimeout'] });
+    const gracefulShutdown = vi.fn();
+    setDesktopControlCallbacks({ gracefulShutdown });
+
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/shutdown')
+      .set('Authorization', 'Bearer secret-token')
+      .send({});
+    expect(res.status).toBe(200);
+    expect(res.body.action).toBe('shutdown');
+    vi.advanceTimersByTime(100);
+    expect(gracefulShutdown).toHaveBeenCalledWith('Desktop app requested shutdown');
+  });
+});
+
+describe('POST /desktop/share-links', () => {
+  it('requires the control token', async () => {
+    const res = await request(appWith('secret-token')).post('/desktop/share-links').send({ minutes: 60 });
+    expect(res.status).toBe(401);
+  });
+
+  it('rejects a missing or out-of-range duration', async () => {
+    const app = appWith('secret-token');
+    for (const body of [{}, { minutes: 0 }, { minutes: 1.5 }, { minutes: 7 * 24 * 60 + 1 }]) {
+      const res = await request(app)
+        .post('/desktop/share-links')
+        .set('Authorization', 'Bearer secret-token')
+        .send(body);
+      expect(res.status).toBe(400);
+    }
+  });
+
+  it('returns a token valid for the requested minutes', async () => {
+    const before = Date.now();
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/share-links')
+      .set('Authorization', 'Bearer secret-token')
+      .send({ minutes: 60 });
+    expect(res.status).toBe(201);
+    expect(res.body.token).toMatch(/^[A-Za-z0-9_-]{32}$/);
+    expect(res.body.expiresAt).toBeGreaterThanOrEqual(before + 60 * 60 * 1000);
+  });
+});
+
+describe('POST /desktop/login-tokens', () => {
+  it('requires the control token', async () => {
+    const res = await request(appWith('secret-token')).post('/desktop/login-tokens').send({});
+    expect(res.status).toBe(401);
+  });
+
+  it('logs in as the first active admin', async () => {
+    getAllUsers.mockResolvedValue([
+      { id: 1, username: 'anonymous', isActive: true, isAdmin: false },
+      { id: 3, username: 'later', isActive: true, isAdmin: true },
+      { id: 2, username: 'admin', isActive: true, isAdmin: true },
+    ]);
+    const before = Date.now();
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/login-tokens')
+      .set('Authorization', 'Bearer secret-token')
+      .send({});
+    expect(res.status).toBe(201);
+    expect(res.body.token).toMatch(/^[A-Za-z0-9_-]{32}$/);
+    expect(res.body.expiresAt).toBeGreaterThanOrEqual(before + 60 * 1000);
+    expect(redeemLoginToken(res.body.token)?.username).toBe('admin');
+  });
+
+  it('has no one to log in as without an active admin', async () => {
+    getAllUsers.mockResolvedValue([{ id: 2, username: 'admin', isActive: false, isAdmin: true }]);
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/login-tokens')
+      .set('Authorization', 'Bearer secret-token')
+      .send({});
+    expect(res.status).toBe(404);
+  });
+});
+
+describe('POST /desktop/device-restore', () => {
+  const change = { path: 'config.lora.txPower', from: '27', to: '20' };
+
+  it('requires the backup text', async () => {
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/device-restore')
+      .set('Authorization', 'Bearer secret-token')
+      .send({});
+    expect(res.status).toBe(400);
+    expect(previewRestore).not.toHaveBeenCalled();
+  });
+
+  it('only previews unless asked to apply', async () => {
+    previewRestore.mockResolvedValue([change]);
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/device-restore')
+      .set('Authorization', 'Bearer secret-token')
+      .send({ yaml: 'config: {}' });
+    expect(res.status).toBe(200);
+    expect(res.body).toEqual({ nodeId: '!00000001', changes: [change], applied: false });
+    expect(applyRestore).not.toHaveBeenCalled();
+  });
+
+  it('applies the backup when asked', async () => {
+    previewRestore.mockResolvedValue([change]);
+    applyRestore.mockResolvedValue(undefined);
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/device-restore')
+      .set('Authorization', 'Bearer secret-token')
+      .send({ yaml: 'config: {}', apply: true });
+    expect(res.body.applied).toBe(true);
+    expect(applyRestore).toHaveBeenCalledWith(expect.anything(), 'config: {}');
+  });
+
+  it('reports a backup that cannot be restored', async () => {
+    previewRestore.mockRejectedValue(new Error('Not a device configuration backup'));
+    const res = await request(appWith('secret-token'))
+      .post('/desktop/device-restore')
+      .set('Authorization', 'Bearer secret-token')
+      .send({ yaml: 'owner: Nobody', apply: true });
+    expect(res.status).toBe(400);
+    expect(res.body.error).toBe('Not a device configuration backup');
+    expect(applyRestore).not.toHaveBeenCalled();
+  });
+});
@@ -0,0 +1,139 @@
+/**
+ * Desktop Control Routes
+ *
+ * POST /desktop/shutdown  — graceful shutdown requested by MeshMonitor Desktop
+ *                           before it stops the backend process
+ * POST /desktop/share-links — create a time-limited, read-only share link
+ *                           (see auth/shareLinks.ts); body `{ minutes }`
+ * POST /desktop/login-tokens — create a one-time login token (see
+ *                           auth/loginHandoff.ts) for the first active admin
+ * POST /desktop/device-restore — preview, and with `apply: true` write, a
+ *                           device backup to the local node (see
+ *                           services/deviceRestoreService.ts); body `{ yaml, apply }`
+ *
+ * The desktop app spawns the backend with a random per-launch
+ * DESKTOP_CONTROL_TOKEN and authenticates with it as a bearer token, since it
+ * has no user session. Without that token the router is empty, so regular
+ * (Docker / bare-metal) installs never expose these routes. It is mounted
+ * ahead of the CSRF-protected apiRouter, and requests must come from loopback.
+ *
+ * Shutdown goes through the server-lifecycle `gracefulShutdown` (closes the
+ * Meshtastic socket and the database), injected from server.ts via
+ * setDesktopControlCallbacks() like systemRoutes.
+ */
+
+import { timingSafeEqual } from 'crypto';
+import { Router, Request, Response, NextFunction } from 'express';
+import { logger } from '../../utils/logger.js';
+import { createShareLink, MAX_SHARE_LINK_MINUTES } from '../auth/shareLinks.js';
+import { createLoginToken } from '../auth/loginHandoff.js';
+import databaseService from '../../services/database.js';
+import { resolveSourceManager } from '../utils/resolveSourceManager.js';
+import { previewRestore, applyRestore } from '../services/deviceRestoreService.js';
+
+export interface DesktopControlCallbacks {
+  gracefulShutdown: (reason: string) => void;
+}
+
+let callbacks: DesktopControlCallbacks = {
+  gracefulShutdown: () => {
+    logger.warn('gracefulShutdown called before desktop control callbacks were registered');
+  },
+};
+
+export function setDesktopControlCallbacks(cb: DesktopControlCallbacks): void {
+  callbacks = cb;
+}
+
+const LOOPBACK_ADDRESSES = new Set(['127.0.0.1', '::1', '::ffff:127.0.0.1']);
+
+function tokenMatches(header: string | undefined, token: string): boolean {
+  const presented = Buffer.from(header?.replace(/^Bearer\s+/i, '') ?? '');
+  const expected = Buffer.from(token);
+  return presented.length === expected.length && timingSafeEqual(presented, expected);
+}
+
+/**
+ * Build the desktop control router. Returns an empty router when `token` is
+ * unset, i.e. when not running under MeshMonitor Desktop.
+ */
+export function createDesktopControlRouter(token: string | undefined): Router {
+  const router: Router = Router();
+  if (!token) {
+    return router;
+  }
+
+  const requireDesktop = (req: Request, res: Response, next: NextFunction): void => {
+    if (!LOOPBACK_ADDRESSES.has(req.socket.remoteAddress ?? '')) {
+      res.status(403).json({ error: 'Forbidden' });
+      return;
+    }
+    if (!tokenMatches(req.headers.authorization, token)) {
+      res.status(401).json({ error: 'Unauthorized' });
+      return;
+    }
+    next();
+  };
+
+  router.post('/desktop/shutdown', requireDesktop, (_req: Request, res: Response) => {
+    logger.info('🛑 Shutdown requested by MeshMonitor Desktop');
+    res.json({ success: true, action: 'shutdown' });
+
+    // Let the response flush before the HTTP server starts closing
+    setTimeout(() => {
+      callbacks.gracefulShutdown('Desktop app requested shutdown');
+    }, 100);
+  });
+
+  router.post('/desktop/share-links', requireDesktop, (req: Request, res: Response) => {
+    const minutes = req.body?.minutes;
+    if (!Number.isInteger(minutes) || minutes < 1 || minutes > MAX_SHARE_LINK_MINUTES) {
+      res.status(400).json({ error: `minutes must be a whole number from 1 to ${MAX_SHARE_LINK_MINUTES}` });
+      return;
+    }
+    const link = createShareLink(minutes);
+    logger.info(`🔗 Share link created by MeshMonitor Desktop, valid for ${minutes} min`);
+    res.status(201).json(link);
+  });
+
+  router.post('/desktop/login-tokens', requireDesktop, async (_req: Request, res: Response) => {
+    try {
+      const admin = (await databaseService.auth.getAllUsers())
+        .filter((user) => user.isActive && user.isAdmin)
+        .sort((a, b) => a.id - b.id)[0];
+      if (!admin) {
+        res.status(404).json({ error: 'No active admin account to log in as' });
+        return;
+      }
+      const token = createLoginToken({ id: admin.id, username: admin.username, isAdmin: true });
+      res.status(201).json(token);
+    } catch (error) {
+      logger.error('❌ Failed to create a login token:', error);
+      res.status(500).json({ error: 'Internal server error' });
+    }
+  });
+
+  router.post('/desktop/device-restore', requireDesktop, async (req: Request, res: Response) => {
+    const yaml = req.body?.yaml;
+    if (typeof yaml !== 'string' || yaml.length === 0) {
+      res.status(400).json({ error: 'yaml must be the text of a device backup' });
+      return;
+    }
+    const manager = resolveSourceManager(undefined);
+    const nodeId = manager.getLocalNodeInfo()?.nodeId ?? null;
+    try {
+      const changes = await previewRestore(manager, yaml);
+      const apply = req.body.apply === true && changes.length > 0;
+      if (apply) {
+        logger.info(`♻️ Device backup restore requested by MeshMonitor Desktop (${changes.length} changes)`);
+        await applyRestore(manager, yaml);
+      }
+      res.json({ nodeId, changes, applied: apply });
+    } catch (error) {
+      logger.error('❌ Device backup restore failed:', error);
+      res.status(400).json({ error: error instanceof Error ? error.message : String(error) });
+    }
+  });
+
+  return router;
+}
@@ -2,7 +2,7 @@
  * v1 API — actions endpoint tests
  *
  * Covers POST /api/v1/sources/:sourceId/actions/{traceroute,request-position,
- * request-nodeinfo,request-neighbors} including:
+ * request-nodeinfo,request-neighbors,add-channel} including:
  *   - 200 happy paths
  *   - 400 missing/invalid destination
  *   - 401 unauthenticated
@@ -16,7 +16,7 @@
 import { describe, it, expect, beforeEach, afterEach, vi } from 'vitest';
 import express, { Express } from 'express';
 import request from 'supertest';
-import { neighborInfoRateLimitMap } from './actions.js';
+import { freeChannelSlots, neighborInfoRateLimitMap } from './actions.js';
 import { TxDisabledError } from '../../errors/txDisabledError.js';
 
 // ──────────────────────────────────────────────────────────────────────────────
@@ -53,6 +53,16 @@ vi.mock('../../../services/database.js', () => ({
     messages: {
       insertMessage: vi.fn(),
     },
+    channels: {
+      getAllChannels: vi.fn(),
+      upsertChannel: vi.fn(),
+    },
+  },
+}));
+
+vi.mock('../../services/channelUrlService.js', () => ({
+  default: {
+    decodeUrl: vi.fn(),
   },
 }));
 
@@ -66,6 +76,7 @@ const mockManager = {
   sendNodeInfoRequest:   vi.fn(),
   sendNeighborInfoRequest: vi.fn(),
   getLocalNodeInfo:      vi.fn(),
+  setChannelConfig:      vi.fn(),
 };
 
 vi.mock('../../utils/resolveSourceManager.js', () => ({
@@ -78,10 +89,12 @@ vi.mock('../../utils/resolveSourceManager.js', () => ({
 
 import databaseService from '../../../services/database.js';
 import { resolveSourceManager } from '../../utils/resolveSourceManager.js';
+import channelUrlService from '../../services/channelUrlService.js';
 import actionsRouter from './actions.js';
 
 const mockDb = databaseService as any;
 const mockResolveSourceManager = resolveSourceManager as ReturnType<typeof vi.fn>;
+const mockDecodeUrl = channelUrlService.decodeUrl as ReturnType<typeof vi.fn>;
 
 // ──────────────────────────────────────────────────────────────────────────────
 // App factory
@@ -147,6 +160,10 @@ beforeEach(() => {
   mockManager.sendPositionRequest.mockResolvedValue({ packetId: 1001, requestId: 2001 });
   mockManager.sendNodeInfoRequest.mockResolvedValue({ packetId: 1002, requestId: 2002 });
   mockManager.sendNeighborInfoRequest.mockResolvedValue({ packetId: 1003, requestId: 2003 });
+  mockManager.setChannelConfig.mockResolvedValue(undefined);
+  // Default: only the primary channel is configured
+  mockDb.channels.getAllChannels.mockResolvedValue([{ id: 0, name: 'LongFast', psk: 'AQ==', role: 1 }]);
+  mockDb.channels.upsertChannel.mockResolvedValue(undefined);
 });
 
 afterEach(() => {
@@ -622,3 +639,78 @@ describe('default source alias', () => {
     expect(res.status).toBe(404);
   });
 });
+
+// ══════════════════════════════════════════════════════════════════════════════
+// POST /add-channel
+// ══════════════════════════════════════════════════════════════════════════════
+
+describe('freeChannelSlots', () => {
+  it('skips the primary slot and enabled secondaries', () => {
+    expect(freeChannelSlots([
+      { id: 0, role: 1 },
+      { id: 1, role: 2 },
+      { id: 2, role: 0 },
+      { id: 3 },
+    ])).toEqual([2, 3, 4, 5, 6, 7]);
+  });
+});
+
+describe('POST /add-channel', () => {
+  const URL = 'https://meshtastic.org/e/#CgcSAQE6AggNEg4IARAEOAFAA0gBUB5oAQ';
+
+  it('writes decoded channels to the first free secondary slot', async () => {
+    mockDecodeUrl.mockReturnValue({ channels: [{ name: 'Friends', psk: 'c2VjcmV0' }] });
+    const res = await post(buildApp(adminUser), SOURCE_A, 'add-channel', { url: URL });
+    expect(res.status).toBe(200);
+    expect(res.body.data).toEqual({ added: [{ index: 1, name: 'Friends' }], skipped: 0 });
+    expect(mockManager.setChannelConfig).toHaveBeenCalledWith(1, expect.objectContaining({
+      name: 'Friends',
+      psk: 'c2VjcmV0',
+      role: 2,
+    }));
+    expect(mockDb.channels.upsertChannel).toHaveBeenCalledWith(
+      expect.objectContaining({ id: 1, name: 'Friends' }),
+      SOURCE_A
+    );
+  });
+
+  it('only reports what it would add on a dry run', async () => {
+    mockDecodeUrl.mockReturnValue({ channels: [{ name: 'Friends', psk: 'c2VjcmV0' }] });
+    const res = await post(buildApp(adminUser), SOURCE_A, 'add-channel', { url: URL, dryRun: true });
+    expect(res.status).toBe(200);
+    expect(res.body.data).toEqual({ added: [{ index: 1, name: 'Friends' }], skipped: 0, dryRun: true });
+    expect(mockManager.setChannelConfig).not.toHaveBeenCalled();
+    expect(mockDb.channels.upsertChannel).not.toHaveBeenCalled();
+  });
+
+  it('skips channels the node already has', async () => {
+    mockDecodeUrl.mockReturnValue({ channels: [{ name: 'LongFast', psk: 'AQ==' }] });
+    const res = await post(buildApp(adminUser), SOURCE_A, 'add-channel', { url: URL });
+    expect(res.status).toBe(200);
+    expect(res.body.data).toEqual({ added: [], skipped: 1 });
+    expect(mockManager.setChannelConfig).not.toHaveBeenCalled();
+  });
+
+  it('returns 409 when there are not enough free slots', async () => {
+    mockDb.channels.getAllChannels.mockResolvedValue(
+      Array.from({ length: 8 }, (_, id) => ({ id, name: `ch${id}`, role: id === 0 ? 1 : 2 }))
+    );
+    mockDecodeUrl.mockReturnValue({ channels: [{ name: 'Friends', psk: 'c2VjcmV0' }] });
+    const res = await post(buildApp(adminUser), SOURCE_A, 'add-channel', { url: URL });
+    expect(res.status).toBe(409);
+    expect(res.body.code).toBe('NO_FREE_SLOTS');
+  });
+
+  it('returns 400 for a URL that does not decode', async () => {
+    mockDecodeUrl.mockReturnValue(null);
+    const res = await post(buildApp(adminUser), SOURCE_A, 'add-channel', { url: 'https://example.com' });
+    expect(res.status).toBe(400);
+  });
+
+  it('returns 403 when user lacks configuration:write on the source', async () => {
+    mockDb.checkPermissionAsync.mockResolvedValue(false);
+    const res = await post(buildApp(normalUser), SOURCE_A, 'add-channel', { url: URL });
+    expect(res.status).toBe(403);
+    expect(mockManager.setChannelConfig).not.toHaveBeenCalled();
+  });
+});
@@ -2,7 +2,8 @@
  * v1 API - Node Actions Endpoint
  *
  * POST actions for interacting with mesh nodes: traceroute, position request,
- * nodeinfo exchange, and neighbor info request. Requires Bearer token auth.
+ * nodeinfo exchange, neighbor info request, and adding channels from a
+ * Meshtastic channel URL. Requires Bearer token auth.
  * All operations are scoped to the source identified by :sourceId.
  *
  * Permissions (enforced by per-route `attachSource` middleware so the
@@ -10,6 +11,7 @@
  * match every other v1 resource):
  *   traceroute, request-neighbors      → traceroute:write
  *   request-position, request-nodeinfo → messages:write
+ *   add-channel                        → configuration:write
  */
 
 import express, { Request, Response } from 'express';
@@ -290,5 +292,88 @@ router.post('/request-neighbors', attachSource('traceroute', 'write'), async (re
   }
 });
 
-export { neighborInfoRateLimitMap };
+// POST /add-channel ───────────────────────────────────────────────────────────
+
+/**
+ * Secondary slots (1–7) that are free — no row, or DISABLED — in ascending
+ * order. Slot 0 is the primary channel and is never handed out.
+ */
+function freeChannelSlots(existing: Array<{ id: number; role?: number }>): number[] {
+  const used = new Set(existing.filter((ch) => ch.role !== undefined && ch.role !== 0).map((ch) => ch.id));
+  const slots: number[] = [];
+  for (let slot = 1; slot <= 7; slot++) {
+    if (!used.has(slot)) slots.push(slot);
+  }
+  return slots;
+}
+
+/**
+ * Add the channels from a Meshtastic channel URL (https://meshtastic.org/e/#…)
+ * to free secondary slots, leaving existing channels and the LoRa settings in
+ * the URL alone. Channels already on the node (same name and PSK) are skipped.
+ * With `dryRun: true` nothing is written; the response shows what would be.
+ */
+router.post('/add-channel', attachSource('configuration', 'write'), async (req: Request, res: Response) => {
+  try {
+    const sourceId = resolvedSourceIdFromPath(req) as string;
+    const { url, dryRun } = req.body ?? {};
+    if (!url || typeof url !== 'string') {
+      return res.status(400).json({ success: false, error: 'Channel URL is required' });
+    }
+
+    const channelUrlService = (await import('../../services/channelUrlService.js')).default;
+    const decoded = channelUrlService.decodeUrl(url);
+    if (!decoded || decoded.channels.length === 0) {
+      return res.status(400).json({ success: false, error: 'Invalid or empty Meshtastic channel URL' });
+    }
+
+    const existing = await databaseService.channels.getAllChannels(sourceId);
+    const pskOf = (psk?: string) => (psk === 'none' ? undefined : psk || undefined);
+    const present = (ch: { name?: string; psk?: string }) =>
+      existing.some(
+        (row) => row.role !== 0 && row.name === (ch.name || '') && pskOf(row.psk) === pskOf(ch.psk)
+      );
+    const toAdd = decoded.channels.filter((ch) => !present(ch));
+    const skipped = decoded.channels.length - toAdd.length;
+
+    const slots = freeChannelSlots(existing);
+    if (toAdd.length > slots.length) {
+      return res.status(409).json({
+        success: false,
+        error: `Not enough free channel slots (${toAdd.length} needed, ${slots.length} free)`,
+        code: 'NO_FREE_SLOTS',
+      });
+    }
+
+    if (dryRun === true) {
+      const added = toAdd.map((channel, i) => ({ index: slots[i], name: channel.name || '' }));
+      return res.json({ success: true, data: { added, skipped, dryRun: true } });
+    }
+
+    const manager = resolveSourceManager(sourceId);
+    const added = [];
+    for (const [i, channel] of toAdd.entries()) {
+      const slot = slots[i];
+      const settings = {
+        name: channel.name || '',
+        psk: pskOf(channel.psk),
+        role: 2,
+        uplinkEnabled: channel.uplinkEnabled ?? true,
+        downlinkEnabled: channel.downlinkEnabled ?? true,
+        positionPrecision: channel.positionPrecision,
+      };
+      await manager.setChannelConfig(slot, settings);
+      await databaseService.channels.upsertChannel({ id: slot, ...settings }, sourceId);
+      added.push({ index: slot, name: settings.name });
+      logger.info(`📥 [v1/actions] Added channel "${settings.name}" to slot ${slot} from URL`);
+    }
+
+    res.json({ success: true, data: { added, skipped } });
+  } catch (error) {
+    logger.error('[v1/actions] Error adding channel from URL:', error);
+    res.status(500).json({ success: false, error: 'Failed to add channel' });
+  }
+});
+
+export { freeChannelSlots, neighborInfoRateLimitMap };
 export default router;
@@ -0,0 +1,123 @@
+/**
+ * v1 API — firmware endpoint tests
+ *
+ * Covers GET /api/v1/sources/:sourceId/firmware and the pure
+ * buildFirmwareInfo helper:
+ *   - newest release with an artifact for the node's platform
+ *   - updateAvailable only when the release is newer
+ *   - unknown hardware / missing firmware version
+ *   - 503 before the local node is known
+ */
+
+import { describe, it, expect, beforeEach, vi } from 'vitest';
+import express, { Express } from 'express';
+import request from 'supertest';
+
+vi.mock('../../services/firmwareUpdateService.js', async () => {
+  const map = await import('../../services/firmwareHardwareMap.js');
+  return {
+    getBoardName: map.getBoardName,
+    getPlatformForBoard: map.getPlatformForBoard,
+    getHardwareDisplayName: map.getHardwareDisplayName,
+    firmwareUpdateService: {
+      findFirmwareZipAsset: (release: any, platform: string) =>
+        release.assets.find((a: any) => new RegExp(`^firmware-${platform}-.*\\.zip$`).test(a.name)) ?? null,
+      getChannel: vi.fn(),
+      fetchReleases: vi.fn(),
+      filterByChannel: vi.fn((releases: any[]) => releases),
+    },
+  };
+});
+
+const mockManager = {
+  getLocalNodeInfo: vi.fn(),
+};
+
+vi.mock('../../utils/resolveSourceManager.js', () => ({
+  resolveSourceManager: vi.fn(() => mockManager),
+}));
+
+import { firmwareUpdateService } from '../../services/firmwareUpdateService.js';
+import firmwareRouter, { buildFirmwareInfo } from './firmware.js';
+
+const mockService = firmwareUpdateService as any;
+
+const HELTEC_V3 = 43;
+
+function release(version: string, assetNames: string[]) {
+  return {
+    tagName: `v${version}`,
+    version,
+    prerelease: false,
+    publishedAt: '2026-01-01T00:00:00Z',
+    htmlUrl: `https://github.com/meshtastic/firmware/releases/tag/v${version}`,
+    assets: assetNames.map((name) => ({ name, size: 1000, downloadUrl: `https://example.invalid/${name}` })),
+  };
+}
+
+const RELEASES = [
+  release('2.7.2.aaaaaaa', ['firmware-nrf52840-2.7.2.aaaaaaa.zip']),
+  release('2.7.1.bbbbbbb', ['firmware-esp32s3-2.7.1.bbbbbbb.zip', 'firmware-esp32-2.7.1.bbbbbbb.zip']),
+  release('2.6.0.ccccccc', ['firmware-esp32s3-2.6.0.ccccccc.zip']),
+];
+
+function buildApp(): Express {
+  const app = express();
+  app.use('/api/v1/sources/:sourceId/firmware', firmwareRouter);
+  return app;
+}
+
+beforeEach(() => {
+  vi.clearAllMocks();
+  mockService.getChannel.mockResolvedValue('stable');
+  mockService.fetchReleases.mockResolvedValue(RELEASES);
+  mockManager.getLocalNodeInfo.mockReturnValue({ hwModel: HELTEC_V3, firmwareVersion: '2.6.0.ccccccc' });
+});
+
+describe('buildFirmwareInfo', () => {
+  it('picks the newest release with an artifact for the board platform', () => {
+    const info = buildFirmwareInfo({ hwModel: HELTEC_V3, firmwareVersion: '2.6.0.ccccccc' }, RELEASES);
+
+    expect(info.board).toBe('heltec-v3');
+    expect(info.platform).toBe('esp32s3');
+    expect(info.latest?.version).toBe('2.7.1.bbbbbbb');
+    expect(info.latest?.asset.name).toBe('firmware-esp32s3-2.7.1.bbbbbbb.zip');
+    expect(info.updateAvailable).toBe(true);
+  });
+
+  it('reports no update when the node is current', () => {
+    const info = buildFirmwareInfo({ hwModel: HELTEC_V3, firmwareVersion: '2.7.1.bbbbbbb' }, RELEASES);
+
+    expect(info.updateAvailable).toBe(false);
+  });
+
+  it('handles unknown hardware and a missing version', () => {
+    const info = buildFirmwareInfo({}, RELEASES);
+
+    expect(info.board).toBeNull();
+    expect(info.platform).toBeNull();
+    expect(info.latest).toBeNull();
+    expect(info.updateAvailable).toBe(false);
+  });
+});
+
+describe('GET /firmware', () => {
+  it('returns firmware info for the local node', async () => {
+    const res = await request(buildApp()).get('/api/v1/sources/default/firmware');
+
+    expect(res.status).toBe(200);
+    expect(res.body.success).toBe(true);
+    expect(res.body.data.channel).toBe('stable');
+    expect(res.body.data.currentVersion).toBe('2.6.0.ccccccc');
+    expect(res.body.data.latest.version).toBe('2.7.1.bbbbbbb');
+    expect(res.body.data.updateAvailable).toBe(true);
+  });
+
+  it('returns 503 before the local node is known', async () => {
+    mockManager.getLocalNodeInfo.mockReturnValue(null);
+    const res = await request(buildApp()).get('/api/v1/sources/default/firmware');
+
+    expect(res.status).toBe(503);
+    expect(res.body.success).toBe(false);
+  });
+});
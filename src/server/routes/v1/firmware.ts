@@ -0,0 +1,109 @@
+/**
+ * Firmware endpoint
+ *
+ * Compares the local node's firmware against Meshtastic releases on the
+ * configured channel and names the release artifact for its board. Used by
+ * API clients that flash firmware themselves (e.g. MeshMonitor Desktop over
+ * USB serial); the web UI's OTA flow lives under /api/firmware.
+ */
+
+import express, { Request, Response } from 'express';
+import { resolveSourceManager } from '../../utils/resolveSourceManager.js';
+import { logger } from '../../../utils/logger.js';
+import { compareVersions } from '../../utils/systemInfo.js';
+import {
+  firmwareUpdateService,
+  getBoardName,
+  getPlatformForBoard,
+  getHardwareDisplayName,
+  type FirmwareAsset,
+  type FirmwareRelease,
+} from '../../services/firmwareUpdateService.js';
+import { resolvedSourceIdFromPath } from './sourceParam.js';
+
+const router = express.Router({ mergeParams: true });
+
+interface LocalFirmware {
+  hwModel?: number;
+  firmwareVersion?: string;
+}
+
+interface LatestFirmware {
+  version: string;
+  tagName: string;
+  publishedAt: string;
+  prerelease: boolean;
+  htmlUrl: string;
+  asset: FirmwareAsset;
+}
+
+/**
+ * Pick the newest release with an artifact for the node's board and decide
+ * whether it is newer than what the node runs. `releases` must already be
+ * filtered by channel and ordered newest first, as GitHub returns them.
+ */
+export function buildFirmwareInfo(node: LocalFirmware, releases: FirmwareRelease[]) {
+  const hwModel = node.hwModel ?? null;
+  const board = hwModel !== null ? getBoardName(hwModel) : null;
+  const platform = board ? getPlatformForBoard(board) : null;
+  const currentVersion = node.firmwareVersion || null;
+
+  let latest: LatestFirmware | null = null;
+  if (platform) {
+    for (const release of releases) {
+      const asset = firmwareUpdateService.findFirmwareZipAsset(release, platform);
+      if (asset) {
+        latest = {
+          version: release.version,
+          tagName: release.tagName,
+          publishedAt: release.publishedAt,
+          prerelease: release.prerelease,
+          htmlUrl: release.htmlUrl,
+          asset,
+        };
+        break;
+      }
+    }
+  }
+
+  return {
+    currentVersion,
+    hwModel,
+    hardware: hwModel !== null ? getHardwareDisplayName(hwModel) : null,
+    board,
+    platform,
+    latest,
+    updateAvailable:
+      latest !== null && currentVersion !== null && compareVersions(latest.version, currentVersion) > 0,
+  };
+}
+
+router.get('/', async (req: Request, res: Response) => {
+  try {
+    const manager = resolveSourceManager(resolvedSourceIdFromPath(req));
+    const localNode = manager.getLocalNodeInfo();
+    if (!localNode) {
+      return res.status(503).json({
+        success: false,
+        error: 'Service Unavailable',
+        message: 'Local node information is not available yet',
+      });
+    }
+
+    const channel = await firmwareUpdateService.getChannel();
+    const releases = firmwareUpdateService.filterByChannel(
+      await firmwareUpdateService.fetchReleases(),
+      channel
+    );
+
+    res.json({
+      success: true,
+      data: { channel, ...buildFirmwareInfo(localNode, releases) },
+    });
+  } catch (err) {
+    logger.error('[v1/firmware] Error:', err);
+    res.status(500).json({ success: false, error: 'Internal server error' });
+  }
+});
+
+export default router;
@@ -10,7 +10,7 @@
  *   /api/v1/docs/*        — OpenAPI + Swagger UI (public)
  *   /api/v1/sources       — list sources the token can read (NEW)
  *   /api/v1/sources/:sourceId/{nodes,messages,channels,telemetry,traceroutes,
- *                              packets,network,status}  — per-source, canonical
+ *                              packets,network,status,firmware}  — per-source, canonical
  *   /api/v1/sources/:sourceId/nodes/:nodeId/position-history
  *   /api/v1/solar, /api/v1/channel-database — deployment-global (unchanged)
  *
@@ -41,6 +41,7 @@ import sourcesRouter from './sources.js';
 import { attachSource } from './sourceParam.js';
 import { deprecationShim } from './deprecatedShim.js';
 import actionsRouter from './actions.js';
+import firmwareRouter from './firmware.js';
 
 const router = express.Router();
 
@@ -68,6 +69,7 @@ router.get('/', (_req, res) => {
       packets: '/api/v1/sources/{sourceId}/packets',
       positionHistory: '/api/v1/sources/{sourceId}/nodes/{nodeId}/position-history',
       status: '/api/v1/sources/{sourceId}/status',
+      firmware: '/api/v1/sources/{sourceId}/firmware',
       channelDatabase: '/api/v1/channel-database',
       solar: '/api/v1/solar',
       actions: '/api/v1/sources/{sourceId}/actions',
@@ -128,6 +130,11 @@ router.use(
   attachSource('info', 'read'),
   statusRouter
 );
+router.use(
+  '/sources/:sourceId/firmware',
+  attachSource('configuration', 'read'),
+  firmwareRouter
+);
 // Actions endpoints (traceroute / position / nodeinfo / neighbors) mix two
 // permission resources — `traceroute:write` and `messages:write` — so they
 // apply `attachSource` per-route inside `actionsRouter` rather than at the
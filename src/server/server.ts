@@ -6,6 +6,9 @@ import fs from 'fs';
 // Side-effect only: patches JSON.stringify to handle BigInt. Must run before
 // anything else in the app can serialize a value that might contain one.
 import './utils/jsonBigIntReplacer.js';
+// Side-effect only: reads secrets the desktop app sends over stdin into
+// process.env. Must run before the environment config is loaded.
+import './utils/desktopSecrets.js';
 import databaseService from '../services/database.js';
 import { fallbackManager } from './meshtasticManager.js';
 import { MeshtasticManager } from './meshtasticManager.js';
@@ -219,6 +222,15 @@ app.use(express.urlencoded({ limit: '10mb', extended: true, parameterLimit: 1000
 const sessionMiddleware = getSessionMiddleware();
 app.use(sessionMiddleware);
 
+// Share links and login tokens come from MeshMonitor Desktop, so only its
+// backend redeems them
+import { shareLinkMiddleware } from './auth/shareLinks.js';
+import { loginHandoffMiddleware } from './auth/loginHandoff.js';
+if (process.env.DESKTOP_CONTROL_TOKEN) {
+  app.use(shareLinkMiddleware);
+  app.use(loginHandoffMiddleware);
+}
+
 // Security: CSRF protection middleware
 import { csrfTokenMiddleware, csrfProtection, csrfTokenEndpoint } from './middleware/csrf.js';
 app.use(csrfTokenMiddleware); // Generate and attach tokens to all requests
@@ -318,15 +330,21 @@ setTimeout(async () => {
     // NOTE: Per-source scheduler settings are applied inside bootstrapSources
     // via applyManagerSettings(). Globally-scoped schedulers self-bootstrap
     // inside their own start*Scheduler methods.
-    await bootstrapSources({
-      db: databaseService,
-      env: { meshtasticNodeIp: env.meshtasticNodeIp, meshtasticTcpPort: env.meshtasticTcpPort },
-      registry: sourceManagerRegistry,
-      makeMeshtastic: (id, cfg) => new MeshtasticManager(id, cfg),
-      // fallbackManager.connect() is called only when no tcp source auto-connects
-      // (S4: all-MeshCore / all-disabled-tcp / autoConnect:false installs).
-      fallbackManager: fallbackManager,
-    });
+    // SAFE_MODE (set by the desktop app after repeated early crashes) skips
+    // every source, node and MQTT alike, so the web UI comes up to fix things.
+    if (env.safeMode) {
+      logger.warn('⚠️  SAFE_MODE: not connecting to any source');
+    } else {
+      await bootstrapSources({
+        db: databaseService,
+        env: { meshtasticNodeIp: env.meshtasticNodeIp, meshtasticTcpPort: env.meshtasticTcpPort },
+        registry: sourceManagerRegistry,
+        makeMeshtastic: (id, cfg) => new MeshtasticManager(id, cfg),
+        // fallbackManager.connect() is called only when no tcp source auto-connects
+        // (S4: all-MeshCore / all-disabled-tcp / autoConnect:false installs).
+        fallbackManager: fallbackManager,
+      });
+    }
 
     // Initialize backup scheduler. Pass a resolver (not a captured instance)
     // so the scheduler always targets the registry's current primary
@@ -667,6 +685,9 @@ import serverInfoRoutes from './routes/serverInfoRoutes.js';
 import scriptRoutes, { scriptsEndpoint, getScriptsDirectory } from './routes/scriptRoutes.js';
 import deviceRoutes from './routes/deviceRoutes.js';
 import systemRoutes, { setSystemCallbacks } from './routes/systemRoutes.js';
+import { createDesktopControlRouter, setDesktopControlCallbacks } from './routes/desktopControlRoutes.js';
+import { applyDesktopTilesets } from './desktopTilesets.js';
+import { startDesktopIpc } from './services/desktopIpcService.js';
 import channelRoutes from './routes/channelRoutes.js';
 import pollRoutes from './routes/pollRoutes.js';
 import configRoutes from './routes/configRoutes.js';
@@ -948,6 +969,25 @@ setSettingsCallbacks({
 setSystemCallbacks({
   gracefulShutdown,
 });
+setDesktopControlCallbacks({
+  gracefulShutdown,
+});
+
+// Connect back to MeshMonitor Desktop's control channel when it spawned us
+if (process.env.DESKTOP_IPC_ADDRESS && process.env.DESKTOP_CONTROL_TOKEN) {
+  startDesktopIpc(process.env.DESKTOP_IPC_ADDRESS, process.env.DESKTOP_CONTROL_TOKEN, {
+    gracefulShutdown,
+  });
+}
+
+// Show the tile servers configured in MeshMonitor Desktop in the map picker
+if (process.env.DESKTOP_CONTROL_TOKEN) {
+  databaseService
+    .waitForReady()
+    .then(() => applyDesktopTilesets(databaseService, process.env.DESKTOP_TILESETS))
+    .then(changed => (changed ? refreshTileHostnameCache() : undefined))
+    .catch(error => logger.error('Failed to apply tilesets from MeshMonitor Desktop:', error));
+}
 
 // Note: GET/POST/DELETE/etc /nodes routes (incl. /auto-favorite/status and
 // /auto-ping/stop/:nodeNum) are in routes/nodesRoutes.ts
@@ -963,6 +1003,15 @@ if (BASE_URL) {
 app.get('/api/scripts', apiLimiter, scriptsEndpoint);
 
 
+// MeshMonitor Desktop supervisor control (token-authenticated, loopback only).
+// Empty unless the desktop app passed DESKTOP_CONTROL_TOKEN; mounted before
+// apiRouter because the desktop has no session or CSRF token.
+const desktopControlRouter = createDesktopControlRouter(process.env.DESKTOP_CONTROL_TOKEN);
+if (BASE_URL) {
+  app.use(`${BASE_URL}/api`, desktopControlRouter);
+}
+app.use('/api', desktopControlRouter);
+
 // Public embed config API (must come BEFORE apiRouter to avoid rate limiter and CSRF)
 // CSP middleware is applied per-route inside the router (needs req.params.profileId)
 if (BASE_URL) {
@@ -0,0 +1,88 @@
+/**
+ * Desktop IPC Service Tests
+ *
+ * Tests command handling and the hello/status/reply exchange over a real
+ * local socket.
+ */
+
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import net from 'net';
+import os from 'os';
+import path from 'path';
+import { handleDesktopCommand, startDesktopIpc } from './desktopIpcService.js';
+
+afterEach(() => {
+  vi.useRealTimers();
+  delete process.env.DESKTOP_IPC_TEST_VALUE;
+});
+
+describe('handleDesktopCommand', () => {
+  const callbacks = { gracefulShutdown: vi.fn() };
+
+  it('validates the log level', () => {
+    expect(handleDesktopCommand({ command: 'setLogLevel', level: 'debug' }, callbacks)).toBeNull();
+    expect(handleDesktopCommand({ command: 'setLogLevel', level: 'verbose' }, callbacks)).toMatch(/Invalid log level/);
+    handleDesktopCommand({ command: 'setLogLevel', level: 'info' }, callbacks);
+  });
+
+  it('applies reloaded environment values', () => {
+    const error = handleDesktopCommand(
+      { command: 'reloadConfig', env: { DESKTOP_IPC_TEST_VALUE: 'yes' } },
+      callbacks
+    );
+    expect(error).toBeNull();
+    expect(process.env.DESKTOP_IPC_TEST_VALUE).toBe('yes');
+  });
+
+  it('shuts down gracefully on quiesce', () => {
+    vi.useFakeTimers({ toFake: ['setTimeout'] });
+    expect(handleDesktopCommand({ command: 'quiesce' }, callbacks)).toBeNull();
+    vi.advanceTimersByTime(100);
+    expect(callbacks.gracefulShutdown).toHaveBeenCalledWith('Desktop app requested shutdown');
+  });
+
+  it('rejects unknown commands', () => {
+    const error = handleDesktopCommand({ command: 'bogus' } as never, callbacks);
+    expect(error).toMatch(/Unknown command/);
+  });
+});
+
+describe('startDesktopIpc', () => {
+  it('says hello, pushes status, and replies to commands', async () => {
+    const address =
+      process.platform === 'win32'
+        ? '\\\\.\\pipe\\meshmonitor-ipc-test'
+        : path.join(os.tmpdir(), `meshmonitor-ipc-${process.pid}.sock`);
+    const received: Array<Record<string, unknown>> = [];
+
+    const server = net.createServer((conn) => {
+      let buffered = '';
+      conn.setEncoding('utf8');
+      conn.on('data', (chunk: string) => {
+        buffered += chunk;
+        const lines = buffered.split('\n');
+        buffered = lines.pop() ?? '';
+        for (const line of lines) {
+          const message = JSON.parse(line);
+          received.push(message);
+          if (message.type === 'hello') {
+            conn.write(JSON.stringify({ command: 'setLogLevel', level: 'info' }) + '\n');
+          }
+        }
+      });
+    });
+    await new Promise<void>((resolve) => server.listen(address, resolve));
+
+    const socket = startDesktopIpc(address, 'secret-token', { gracefulShutdown: vi.fn() });
+    await vi.waitFor(() => {
+      expect(received.some((m) => m.type === 'reply')).toBe(true);
+    });
+
+    expect(received[0]).toMatchObject({ type: 'hello', token: 'secret-token', pid: process.pid });
+    expect(received.some((m) => m.type === 'status')).toBe(true);
+    expect(received.find((m) => m.type === 'reply')).toMatchObject({ command: 'setLogLevel', ok: true });
+
+    socket.destroy();
+    await new Promise<void>((resolve) => server.close(() => resolve()));
+  });
+});
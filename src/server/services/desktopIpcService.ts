@@ -0,0 +1,135 @@
+/**
+ * Desktop IPC Service
+ *
+ * Control channel back to MeshMonitor Desktop. When the desktop app spawns
+ * the backend it listens on a local socket (a unix socket, or a loopback TCP
+ * port on Windows) and passes its address as DESKTOP_IPC_ADDRESS. The backend
+ * connects, identifies itself with DESKTOP_CONTROL_TOKEN, pushes a status
+ * update every few seconds (the supervisor's heartbeat), and runs commands
+ * sent back over the same connection without needing a restart.
+ *
+ * Wire format: newline-delimited JSON.
+ *   backend → desktop: { type: 'hello', token, pid }
+ *                      { type: 'status', uptime, rss, cpu }
+ *                      { type: 'reply', command, ok, error? }
+ *   desktop → backend: { command: 'setLogLevel', level }
+ *                      { command: 'reloadConfig', env: { NAME: value } }
+ *                      { command: 'quiesce' }
+ */
+
+import net from 'net';
+import { logger, setLogLevel } from '../../utils/logger.js';
+import { resetEnvironmentConfig } from '../config/environment.js';
+
+export interface DesktopIpcCallbacks {
+  gracefulShutdown: (reason: string) => void;
+}
+
+export type DesktopIpcCommand =
+  | { command: 'setLogLevel'; level: string }
+  | { command: 'reloadConfig'; env: Record<string, string> }
+  | { command: 'quiesce' };
+
+/** How often status is pushed; the desktop treats a silence as a hang. */
+export const STATUS_INTERVAL_MS = 5000;
+
+const startedAt = Date.now();
+
+/**
+ * Run one command from the desktop. Returns an error message, or null on
+ * success.
+ */
+export function handleDesktopCommand(cmd: DesktopIpcCommand, callbacks: DesktopIpcCallbacks): string | null {
+  switch (cmd.command) {
+    case 'setLogLevel':
+      return setLogLevel(cmd.level) ? null : `Invalid log level: ${cmd.level}`;
+    case 'reloadConfig':
+      // Only settings read through getEnvironmentConfig() at use pick this
+      // up; anything captured at startup (port, database) still needs a restart
+      for (const [name, value] of Object.entries(cmd.env ?? {})) {
+        process.env[name] = value;
+      }
+      resetEnvironmentConfig();
+      if (cmd.env?.LOG_LEVEL) {
+        setLogLevel(cmd.env.LOG_LEVEL);
+      }
+      return null;
+    case 'quiesce':
+      // Reply first; shutdown closes the socket
+      setTimeout(() => callbacks.gracefulShutdown('Desktop app requested shutdown'), 100);
+      return null;
+    default:
+      return `Unknown command: ${(cmd as { command?: string }).command}`;
+  }
+}
+
+function connectOptions(address: string): net.NetConnectOpts {
+  const tcp = address.match(/^tcp:\/\/([^:]+):(\d+)$/);
+  return tcp ? { host: tcp[1], port: Number(tcp[2]) } : { path: address };
+}
+
+/**
+ * Connect to the desktop app's IPC socket. The connection is best-effort:
+ * if it fails or drops, the backend carries on and the desktop falls back to
+ * polling /api/health and HTTP shutdown.
+ */
+export function startDesktopIpc(address: string, token: string, callbacks: DesktopIpcCallbacks): net.Socket {
+  const socket = net.createConnection(connectOptions(address));
+  const send = (message: object): void => {
+    if (!socket.destroyed) {
+      socket.write(JSON.stringify(message) + '\n');
+    }
+  };
+  const pushStatus = (): void => {
+    // CPU time (user and system) since the process started, in ms
+    const { user, system } = process.cpuUsage();
+    send({
+      type: 'status',
+      uptime: Date.now() - startedAt,
+      rss: process.memoryUsage().rss,
+      cpu: Math.round((user + system) / 1000),
+    });
+  };
+
+  let statusTimer: NodeJS.Timeout | undefined;
+  let buffered = '';
+
+  socket.setEncoding('utf8');
+  socket.on('connect', () => {
+    logger.info('🖥️ Connected to MeshMonitor Desktop control channel');
+    send({ type: 'hello', token, pid: process.pid });
+    pushStatus();
+    statusTimer = setInterval(pushStatus, STATUS_INTERVAL_MS);
+    statusTimer.unref();
+  });
+  socket.on('data', (chunk: string) => {
+    buffered += chunk;
+    let newline: number;
+    while ((newline = buffered.indexOf('\n')) >= 0) {
+      const line = buffered.slice(0, newline).trim();
+      buffered = buffered.slice(newline + 1);
+      if (!line) continue;
+
+      let cmd: DesktopIpcCommand;
+      try {
+        cmd = JSON.parse(line);
+      } catch {
+        logger.warn('Ignoring malformed desktop IPC message');
+        continue;
+      }
+      logger.info(`🖥️ Desktop command: ${cmd.command}`);
+      const error = handleDesktopCommand(cmd, callbacks);
+      send({ type: 'reply', command: cmd.command, ok: error === null, ...(error ? { error } : {}) });
+    }
+  });
+  socket.on('error', (error) => {
+    logger.warn('Desktop control channel error:', error.message);
+  });
+  socket.on('close', () => {
+    if (statusTimer) clearInterval(statusTimer);
+    logger.debug('Desktop control channel closed');
+  });
+  socket.unref();
+
+  return socket;
+}
@@ -0,0 +1,77 @@
+/**
+ * Device Restore Service Tests
+ *
+ * Tests parsing a device backup into the sections a restore writes and the
+ * preview diff between two backups.
+ */
+
+import { describe, it, expect } from 'vitest';
+import { parseBackup, diffBackups } from './deviceRestoreService.js';
+
+const fields = {
+  config: new Set(['device', 'lora', 'security']),
+  module: new Set(['telemetry', 'mqtt']),
+};
+
+const backupYaml = `# start of Meshtastic configure yaml
+config:
+  device:
+    role: ROUTER
+  lora:
+    region: US
+    txPower: 20
+  mqtt:
+    encryptionEnabled: false
+  security:
+    privateKey: base64:c2VjcmV0
+module_config:
+  mqtt:
+    password: hunter2
+owner: Base Station
+owner_short: BASE`;
+
+describe('parseBackup', () => {
+  it('keeps known sections and never the security keys', () => {
+    const backup = parseBackup(backupYaml, fields);
+    expect(Object.keys(backup.config)).toEqual(['device', 'lora']);
+    expect(backup.config.lora.txPower).toBe(20);
+    expect(backup.moduleConfig).toEqual({ mqtt: { password: 'hunter2' } });
+    expect(backup.channels).toBeNull();
+    expect(backup.owner).toEqual({ longName: 'Base Station', shortName: 'BASE' });
+  });
+
+  it('rejects files that are no device backup', () => {
+    expect(() => parseBackup('owner: Nobody', fields)).toThrow('Not a device configuration backup');
+  });
+});
+
+describe('diffBackups', () => {
+  it('lists changed settings of the restored sections with secrets masked', () => {
+    const snapshot = parseBackup(backupYaml, fields);
+    const current = parseBackup(
+      `config:
+  device:
+    role: CLIENT
+  lora:
+    region: US
+    txPower: 27
+    hopLimit: 5
+  position:
+    gpsEnabled: true
+module_config:
+  mqtt:
+    password: other
+owner: Base Station
+owner_short: BASE`,
+      { ...fields, config: new Set([...fields.config, 'position']) }
+    );
+
+    expect(diffBackups(current, snapshot)).toEqual([
+      { path: 'config.device.role', from: 'CLIENT', to: 'ROUTER' },
+      { path: 'config.lora.hopLimit', from: '5', to: null },
+      { path: 'config.lora.txPower', from: '27', to: '20' },
+      { path: 'module_config.mqtt.password', from: '(hidden)', to: '(hidden)' },
+    ]);
+    expect(diffBackups(snapshot, snapshot)).toEqual([]);
+  });
+});
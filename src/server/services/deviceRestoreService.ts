@@ -0,0 +1,227 @@
+/**
+ * Device Restore Service
+ *
+ * Writes a device backup (the `meshtastic --export-config` YAML produced by
+ * deviceBackupService) back to the local node, and previews what that would
+ * change by comparing it with a backup of the node as it is now.
+ *
+ * What is restored:
+ *   - every `config` and `module_config` section the protobufs know; a section
+ *     replaces the node's, so fields missing from it return to their defaults
+ *   - the channels in `channel_url`, by slot; slots beyond them are disabled
+ *   - the owner's long and short name
+ *
+ * Security keys stay with the radio they were generated on and are never
+ * written, nor are the location and canned messages.
+ */
+
+import YAML from 'yamljs';
+import { getProtobufRoot } from '../protobufLoader.js';
+import protobufService from '../protobufService.js';
+import channelUrlService, { type DecodedChannelSettings } from './channelUrlService.js';
+import { deviceBackupService } from './deviceBackupService.js';
+import { logger } from '../../utils/logger.js';
+
+/** One setting a restore changes; `null` means unset (the firmware default). */
+export interface ConfigChange {
+  path: string;
+  from: string | null;
+  to: string | null;
+}
+
+/** The parts of a backup a restore writes. */
+export interface RestorableBackup {
+  config: Record<string, any>;
+  moduleConfig: Record<string, any>;
+  /** `null` when the backup has no channel URL, leaving channels alone. */
+  channels: DecodedChannelSettings[] | null;
+  owner: { longName: string; shortName: string } | null;
+}
+
+const SKIPPED_CONFIG_SECTIONS = new Set(['security', 'sessionkey']);
+
+const SECRET_FIELD = /psk|key|password/i;
+
+/** Strip the `base64:` prefix the export format puts on bytes fields. */
+function normalizeValues(value: any): any {
+  if (typeof value === 'string' && value.startsWith('base64:')) {
+    return value.slice('base64:'.length);
+  }
+  if (Array.isArray(value)) {
+    return value.map(normalizeValues);
+  }
+  if (value && typeof value === 'object') {
+    return Object.fromEntries(Object.entries(value).map(([k, v]) => [k, normalizeValues(v)]));
+  }
+  return value;
+}
+
+function protobufFields(): { config: Set<string>; module: Set<string> } {
+  const root = getProtobufRoot();
+  const Config = root?.lookupType('meshtastic.Config');
+  const ModuleConfig = root?.lookupType('meshtastic.ModuleConfig');
+  if (!Config || !ModuleConfig) {
+    throw new Error('Protobuf definitions not loaded');
+  }
+  return {
+    config: new Set(Object.keys(Config.fields)),
+    module: new Set(Object.keys(ModuleConfig.fields)),
+  };
+}
+
+/**
+ * Parse backup YAML into the parts a restore writes, keeping only sections
+ * named in `fields` (the protobuf Config / ModuleConfig fields).
+ */
+export function parseBackup(yaml: string, fields: { config: Set<string>; module: Set<string> }): RestorableBackup {
+  let backup: any;
+  try {
+    backup = YAML.parse(yaml);
+  } catch (error) {
+    throw new Error(`Not a readable backup: ${error instanceof Error ? error.message : String(error)}`);
+  }
+  if (!backup || typeof backup !== 'object' || (!backup.config && !backup.module_config && !backup.channel_url)) {
+    throw new Error('Not a device configuration backup');
+  }
+  backup = normalizeValues(backup);
+
+  const pick = (sections: any, known: Set<string>) =>
+    Object.fromEntries(
+      Object.entries(sections ?? {}).filter(
+        ([name, value]) => known.has(name) && !SKIPPED_CONFIG_SECTIONS.has(name) && value && typeof value === 'object'
+      )
+    );
+
+  let channels: DecodedChannelSettings[] | null = null;
+  if (typeof backup.channel_url === 'string') {
+    const decoded = channelUrlService.decodeUrl(backup.channel_url);
+    if (!decoded) {
+      throw new Error('The backup\'s channel URL could not be decoded');
+    }
+    channels = decoded.channels;
+  }
+
+  return {
+    config: pick(backup.config, fields.config),
+    moduleConfig: pick(backup.module_config, fields.module),
+    channels,
+    owner: backup.owner
+      ? { longName: String(backup.owner), shortName: String(backup.owner_short ?? '') }
+      : null,
+  };
+}
+
+function flattenInto(flat: Map<string, string>, path: string, value: any): void {
+  if (value && typeof value === 'object' && !Array.isArray(value)) {
+    for (const key of Object.keys(value)) {
+      flattenInto(flat, `${path}.${key}`, value[key]);
+    }
+  } else if (value !== null && value !== undefined) {
+    flat.set(path, Array.isArray(value) ? value.join(', ') : String(value));
+  }
+}
+
+/**
+ * Settings of `backup` as dotted paths. With `within`, only the sections a
+ * restore of `within` would write are included.
+ */
+function flatten(backup: RestorableBackup, within: RestorableBackup = backup): Map<string, string> {
+  const flat = new Map<string, string>();
+  for (const name of Object.keys(within.config)) {
+    flattenInto(flat, `config.${name}`, backup.config[name]);
+  }
+  for (const name of Object.keys(within.moduleConfig)) {
+    flattenInto(flat, `module_config.${name}`, backup.moduleConfig[name]);
+  }
+  if (within.channels) {
+    (backup.channels ?? []).forEach((channel, index) => {
+      flattenInto(flat, `channels.${index}`, {
+        name: channel.name,
+        psk: channel.psk,
+        uplinkEnabled: channel.uplinkEnabled,
+        downlinkEnabled: channel.downlinkEnabled,
+        positionPrecision: channel.positionPrecision,
+      });
+    });
+  }
+  if (within.owner && backup.owner) {
+    flat.set('owner', backup.owner.longName);
+    flat.set('owner_short', backup.owner.shortName);
+  }
+  return flat;
+}
+
+/** What restoring `snapshot` onto a node configured like `current` changes. Keys are masked. */
+export function diffBackups(current: RestorableBackup, snapshot: RestorableBackup): ConfigChange[] {
+  const before = flatten(current, snapshot);
+  const after = flatten(snapshot);
+  const paths = [...new Set([...after.keys(), ...before.keys()])].sort();
+  const mask = (path: string, value: string | undefined) =>
+    value === undefined ? null : SECRET_FIELD.test(path.split('.').pop() ?? '') ? '(hidden)' : value;
+  return paths
+    .filter((path) => before.get(path) !== after.get(path))
+    .map((path) => ({ path, from: mask(path, before.get(path)), to: mask(path, after.get(path)) }));
+}
+
+/** Compare `yaml` with the node `manager` is connected to. */
+export async function previewRestore(manager: any, yaml: string): Promise<ConfigChange[]> {
+  if (!manager.getLocalNodeInfo()?.nodeNum) {
+    throw new Error('Not connected to a node');
+  }
+  const fields = protobufFields();
+  const snapshot = parseBackup(yaml, fields);
+  const current = parseBackup(await deviceBackupService.generateBackup(manager), fields);
+  return diffBackups(current, snapshot);
+}
+
+/** Write `yaml` to the node `manager` is connected to, in one edit transaction. */
+export async function applyRestore(manager: any, yaml: string): Promise<void> {
+  const nodeNum = manager.getLocalNodeInfo()?.nodeNum;
+  if (!nodeNum) {
+    throw new Error('Not connected to a node');
+  }
+  const root = getProtobufRoot();
+  const AdminMessage = root?.lookupType('meshtastic.AdminMessage');
+  if (!AdminMessage) {
+    throw new Error('Protobuf definitions not loaded');
+  }
+  const fields = protobufFields();
+  const snapshot = parseBackup(yaml, fields);
+  const current = parseBackup(await deviceBackupService.generateBackup(manager), fields);
+
+  // fromObject takes the export's enum names and base64 bytes as they are
+  const send = async (adminMsgData: any) => {
+    const encoded = AdminMessage.encode(AdminMessage.fromObject(adminMsgData)).finish();
+    await manager.sendLocalAdminPacket(protobufService.createAdminPacket(encoded, nodeNum, nodeNum));
+  };
+
+  await manager.beginEditSettings();
+  for (const [name, section] of Object.entries(snapshot.config)) {
+    logger.debug(`♻️ Restoring ${name} config`);
+    await send({ setConfig: { [name]: section } });
+  }
+  for (const [name, section] of Object.entries(snapshot.moduleConfig)) {
+    logger.debug(`♻️ Restoring ${name} module config`);
+    await send({ setModuleConfig: { [name]: section } });
+  }
+  if (snapshot.channels) {
+    for (const [index, channel] of snapshot.channels.entries()) {
+      await manager.setChannelConfig(index, {
+        name: channel.name ?? '',
+        psk: channel.psk,
+        role: index === 0 ? 1 : 2,
+        uplinkEnabled: channel.uplinkEnabled,
+        downlinkEnabled: channel.downlinkEnabled,
+        positionPrecision: channel.positionPrecision,
+      });
+    }
+    for (let index = snapshot.channels.length; index < (current.channels?.length ?? 0); index++) {
+      await manager.setChannelConfig(index, { role: 0 });
+    }
+  }
+  if (snapshot.owner) {
+    await manager.setNodeOwner(snapshot.owner.longName, snapshot.owner.shortName);
+  }
+  await manager.commitEditSettings();
+  logger.info('♻️ Device configuration restored from backup');
+}
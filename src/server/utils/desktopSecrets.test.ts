@@ -0,0 +1,30 @@
+import { describe, it, expect } from 'vitest';
+import { applyDesktopSecrets } from './desktopSecrets.js';
+
+describe('applyDesktopSecrets', () => {
+  it('applies known secrets to the environment', () => {
+    const env: NodeJS.ProcessEnv = {};
+    const applied = applyDesktopSecrets(
+      JSON.stringify({ SESSION_SECRET: 'abc', DESKTOP_CONTROL_TOKEN: 'def' }),
+      env
+    );
+    expect(applied).toEqual(['SESSION_SECRET', 'DESKTOP_CONTROL_TOKEN']);
+    expect(env.SESSION_SECRET).toBe('abc');
+    expect(env.DESKTOP_CONTROL_TOKEN).toBe('def');
+  });
+
+  it('ignores unknown names and non-string values', () => {
+    const env: NodeJS.ProcessEnv = {};
+    const applied = applyDesktopSecrets(JSON.stringify({ PATH: '/tmp', SESSION_SECRET: 42 }), env);
+    expect(applied).toEqual([]);
+    expect(env).toEqual({});
+  });
+
+  it('treats empty input as no secrets', () => {
+    expect(applyDesktopSecrets('  \n', {})).toEqual([]);
+  });
+
+  it('throws on malformed input', () => {
+    expect(() => applyDesktopSecrets('{not json', {})).toThrow();
+  });
+});
@@ -0,0 +1,47 @@
+/**
+ * Desktop secret delivery.
+ *
+ * MeshMonitor Desktop doesn't put secrets (SESSION_SECRET,
+ * DESKTOP_CONTROL_TOKEN) in the backend's environment, where any process
+ * that can read /proc or Process Explorer could see them. It sets
+ * DESKTOP_SECRETS_STDIN=true and writes them to the backend's stdin as a
+ * single JSON object, then closes the pipe. This module reads that object
+ * as a **module-load side effect** and copies the values into process.env,
+ * so server.ts must import it before anything reads the environment config.
+ */
+
+import fs from 'fs';
+
+/** Names the desktop app is allowed to set this way. */
+const DESKTOP_SECRET_NAMES = ['SESSION_SECRET', 'DESKTOP_CONTROL_TOKEN'];
+
+/**
+ * Apply the secrets in `input` (JSON) to `env`. Unknown names and
+ * non-string values are ignored. Returns the names applied.
+ */
+export function applyDesktopSecrets(input: string, env: NodeJS.ProcessEnv): string[] {
+  const trimmed = input.trim();
+  if (!trimmed) {
+    return [];
+  }
+  const secrets: Record<string, unknown> = JSON.parse(trimmed);
+  const applied: string[] = [];
+  for (const name of DESKTOP_SECRET_NAMES) {
+    const value = secrets[name];
+    if (typeof value === 'string' && value) {
+      env[name] = value;
+      applied.push(name);
+    }
+  }
+  return applied;
+}
+
+if (process.env.DESKTOP_SECRETS_STDIN === 'true') {
+  try {
+    // Blocks until the desktop app closes the pipe, which it does right
+    // after spawning us
+    applyDesktopSecrets(fs.readFileSync(0, 'utf8'), process.env);
+  } catch (error) {
+    console.error('[ERROR] Failed to read secrets from the desktop app:', (error as Error).message);
+  }
+}
@@ -16,6 +16,8 @@ const mockGetEnvironmentConfig = vi.hoisted(() =>
   vi.fn().mockReturnValue({
     databasePath: ':memory:',
     databasePathProvided: true,
+    sqliteBusyTimeoutMs: 5000,
+    sqliteWalAutocheckpoint: 1000,
     baseUrl: '/',
     port: 8080,
     debug: false,
@@ -14,6 +14,8 @@ const mockGetEnvironmentConfig = vi.hoisted(() =>
   vi.fn().mockReturnValue({
     databasePath: ':memory:',
     databasePathProvided: true,
+    sqliteBusyTimeoutMs: 5000,
+    sqliteWalAutocheckpoint: 1000,
     baseUrl: '/meshmonitor',
     port: 8080,
     debug: false,
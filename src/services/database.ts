@@ -1730,10 +1730,12 @@ class DatabaseService {
    */
   private openSqliteDatabase(dbPath: string, dbDir: string): BetterSqlite3Database.Database {
     const attemptOpen = (): BetterSqlite3Database.Database => {
+      const { sqliteBusyTimeoutMs, sqliteWalAutocheckpoint } = getEnvironmentConfig();
       const db = new BetterSqlite3Database(dbPath);
       db.pragma('journal_mode = WAL');
+      db.pragma(`wal_autocheckpoint = ${sqliteWalAutocheckpoint}`);
       db.pragma('foreign_keys = ON');
-      db.pragma('busy_timeout = 5000');
+      db.pragma(`busy_timeout = ${sqliteBusyTimeoutMs}`);
       return db;
     };
 
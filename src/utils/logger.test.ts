@@ -149,6 +149,21 @@ describe('logger LOG_LEVEL support', () => {
     expect(consoleMocks.log).not.toHaveBeenCalledWith('[DEBUG]', 'd');
     expect(consoleMocks.log).toHaveBeenCalledWith('[INFO]', 'i');
   });
+
+  it('should change the level at runtime with setLogLevel', async () => {
+    process.env.LOG_LEVEL = 'info';
+    const mod = await import('./logger.js');
+
+    mod.logger.debug('before');
+    expect(mod.setLogLevel('DEBUG')).toBe(true);
+    mod.logger.debug('after');
+    expect(mod.setLogLevel('verbose')).toBe(false);
+    mod.logger.debug('still');
+
+    expect(consoleMocks.log).not.toHaveBeenCalledWith('[DEBUG]', 'before');
+    expect(consoleMocks.log).toHaveBeenCalledWith('[DEBUG]', 'after');
+    expect(consoleMocks.log).toHaveBeenCalledWith('[DEBUG]', 'still');
+  });
 });
 
 // Regression coverage for CodeQL js/log-injection (alerts #128–#131). The
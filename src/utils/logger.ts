@@ -36,9 +36,22 @@ function getLogLevel(): LogLevel {
 }
 
 // Evaluated once at module import. Changing process.env.LOG_LEVEL after import
-// has no effect on the live logger; tests that vary the level must call
-// vi.resetModules() (see logger.test.ts) to force a re-import.
-const currentLevel = getLogLevel();
+// has no effect on the live logger (use setLogLevel); tests that vary the env
+// var must call vi.resetModules() (see logger.test.ts) to force a re-import.
+let currentLevel = getLogLevel();
+
+/**
+ * Change the log level at runtime (e.g. from the desktop app's IPC channel).
+ * Returns false and leaves the level unchanged if `level` is not valid.
+ */
+export function setLogLevel(level: string): boolean {
+  const normalized = level.toLowerCase() as LogLevel;
+  if (!LOG_LEVEL_ORDER.includes(normalized)) {
+    return false;
+  }
+  currentLevel = normalized;
+  return true;
+}
 
 function shouldLog(level: LogLevel): boolean {
   return LOG_LEVEL_ORDER.indexOf(level) >= LOG_LEVEL_ORDER.indexOf(currentLevel);